@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use rusqlite::Connection;
 use tauri::State;
 use crate::DbState;
+use crate::migration::add_column_if_missing;
+use crate::service::academic_year as service;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AcademicYear {
@@ -14,6 +16,35 @@ pub struct AcademicYear {
     pub created_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<String>,
+    /// First day of the year, YYYY-MM-DD. Years with dates may not overlap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    /// Set once the year is closed; its classes, subjects, timetable and calendar are then
+    /// read-only unless an override is in force.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_by: Option<String>,
+}
+
+/// Permission, recorded by an admin, to change a closed year until `valid_until`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub academic_year_id: i64,
+    /// Name of the active staff member granting it; stored as it is on the staff record.
+    pub granted_by: String,
+    pub reason: String,
+    /// Last day the override applies, inclusive; defaults to the day it is granted.
+    pub valid_until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granted_at: Option<String>,
+    /// Set when the override is ended before it runs out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
 }
 
 pub fn init_academic_year_table(conn: &Connection) -> rusqlite::Result<()> {
@@ -32,130 +63,54 @@ pub fn init_academic_year_table(conn: &Connection) -> rusqlite::Result<()> {
         "INSERT OR IGNORE INTO academic_years (academic_year, status) VALUES (?1, ?2)",
         [&"2024 - 2025", &"active"],
     )?;
+    add_column_if_missing(conn, "academic_years", "start_date", "TEXT")?;
+    add_column_if_missing(conn, "academic_years", "end_date", "TEXT")?;
+    add_column_if_missing(conn, "academic_years", "closed_at", "DATETIME")?;
+    add_column_if_missing(conn, "academic_years", "closed_by", "TEXT")?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS academic_year_overrides (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            granted_by TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            valid_until TEXT NOT NULL,
+            granted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            ended_at DATETIME,
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
     Ok(())
 }
 
+/// Creates the year, or updates the dates of an existing one with the same label.
 #[tauri::command]
 pub async fn upsert_academic_year(
     state: State<'_, DbState>, 
     year: String,
-    set_as_current: bool
+    set_as_current: bool,
+    start_date: Option<String>,
+    end_date: Option<String>,
 ) -> Result<i64, String> {
-    let conn = state.0.lock().unwrap();
-    
-    // First try to update existing year
-    let existing_id: Option<i64> = conn.query_row(
-        "SELECT id FROM academic_years WHERE academic_year = ?1",
-        [&year],
-        |row| row.get(0)
-    ).ok();
-    
-    let id = match existing_id {
-        Some(id) => {
-            // Year exists, update it
-            conn.execute(
-                "UPDATE academic_years SET 
-                    updated_at = CURRENT_TIMESTAMP
-                 WHERE id = ?1",
-                [id],
-            )
-            .map_err(|e| e.to_string())?;
-            id
-        },
-        None => {
-            // Year doesn't exist, insert new
-            conn.execute(
-                "INSERT INTO academic_years (academic_year, status)
-                 VALUES (?1, ?2)",
-                [&year, &if set_as_current { "active".to_string() } else { "inactive".to_string() }],
-            )
-            .map_err(|e| e.to_string())?;
-            conn.last_insert_rowid()
-        }
-    };
-    
-    // If we need to set this as current, update status
-    if set_as_current {
-        // First set all to inactive
-        conn.execute(
-            "UPDATE academic_years SET status = 'inactive'",
-            [],
-        )
-        .map_err(|e| e.to_string())?;
-        
-        // Then set the selected one to active
-        conn.execute(
-            "UPDATE academic_years SET 
-                status = 'active',
-                updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            [id],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-    
-    Ok(id)
+    state
+        .write(move |conn| {
+            service::upsert_academic_year(conn, &year, set_as_current, start_date, end_date)
+        })
+        .await
 }
 
 #[tauri::command]
 pub async fn get_current_academic_year(
     state: State<'_, DbState>
 ) -> Result<Option<AcademicYear>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = match conn.prepare(
-        "SELECT id, academic_year, status, created_at, updated_at 
-         FROM academic_years 
-         WHERE status = 'active'
-         ORDER BY created_at DESC
-         LIMIT 1",
-    ) {
-        Ok(stmt) => stmt,
-        Err(e) => return Err(e.to_string()),
-    };
-
-    match stmt.query_row([], |row| {
-        Ok(AcademicYear {
-            id: Some(row.get(0)?),
-            academic_year: row.get(1)?,
-            status: row.get(2)?,
-            created_at: row.get(3)?,
-            updated_at: row.get(4)?,
-        })
-    }) {
-        Ok(year) => Ok(Some(year)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    state.read(service::get_current_academic_year).await
 }
 
 #[tauri::command]
 pub async fn get_all_academic_years(
     state: State<'_, DbState>
 ) -> Result<Vec<AcademicYear>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, academic_year, status, created_at, updated_at 
-             FROM academic_years 
-             ORDER BY created_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
-
-    let academic_years = stmt
-        .query_map([], |row| {
-            Ok(AcademicYear {
-                id: Some(row.get(0)?),
-                academic_year: row.get(1)?,
-                status: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
-
-    Ok(academic_years)
+    state.read(service::get_all_academic_years).await
 }
 
 #[tauri::command]
@@ -163,35 +118,58 @@ pub async fn set_current_academic_year(
     state: State<'_, DbState>, 
     id: i64
 ) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    
-    // First set all to inactive
-    conn.execute(
-        "UPDATE academic_years SET status = 'inactive'",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-    
-    // Then set the selected one to active
-    conn.execute(
-        "UPDATE academic_years SET 
-            status = 'active',
-            updated_at = CURRENT_TIMESTAMP
-         WHERE id = ?1",
-        [id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+    state
+        .write(move |conn| service::set_current_academic_year(conn, id))
+        .await
 }
 
+/// Deletes a year that nothing refers to yet.
 #[tauri::command]
 pub async fn delete_academic_year(
     state: State<'_, DbState>, 
     id: i64
 ) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute("DELETE FROM academic_years WHERE id = ?1", [id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
-}
\ No newline at end of file
+    state
+        .write(move |conn| service::delete_academic_year(conn, id))
+        .await
+}
+
+/// Closes the year for edits once its results and fees are final.
+#[tauri::command]
+pub async fn close_academic_year(
+    state: State<'_, DbState>,
+    id: i64,
+    closed_by: String,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::close_academic_year(conn, id, &closed_by))
+        .await
+}
+
+/// Records an admin override that lets a closed year be changed for a while.
+#[tauri::command]
+pub async fn grant_academic_year_override(
+    state: State<'_, DbState>,
+    year_override: YearOverride,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::grant_override(conn, year_override))
+        .await
+}
+
+#[tauri::command]
+pub async fn end_academic_year_override(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::end_override(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_academic_year_overrides(
+    state: State<'_, DbState>,
+    academic_year_id: i64,
+) -> Result<Vec<YearOverride>, String> {
+    state
+        .read(move |conn| service::get_overrides(conn, academic_year_id))
+        .await
+}
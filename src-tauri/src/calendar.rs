@@ -0,0 +1,213 @@
+// Academic calendar: terms, holidays and events of each academic year
+use crate::service::calendar as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_calendar_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Calendar");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create calendar dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Term {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub academic_year_id: i64,
+    /// e.g. "Term 1" or "Semester 2".
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+/// A holiday or event on the academic calendar.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub academic_year_id: i64,
+    /// holiday or event. Only holidays take days out of the working-day count.
+    pub kind: String,
+    pub title: String,
+    pub start_date: String,
+    /// Last day of a multi-day entry; defaults to the start date.
+    pub end_date: Option<String>,
+    /// full or half; half-day entries cover a single date.
+    pub day_part: Option<String>,
+    pub description: Option<String>,
+}
+
+/// School-wide calendar settings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarSettings {
+    /// Day of the week the school is closed, e.g. `sunday`. Staff leave, the muster roll and
+    /// working-day counts all skip it.
+    pub weekly_off: String,
+}
+
+/// Working days in a stretch of the academic year. The weekly off is not a working day, a
+/// half-day holiday counts as half of one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkingDays {
+    pub academic_year_id: i64,
+    pub from: String,
+    pub to: String,
+    pub calendar_days: u32,
+    pub weekly_offs: u32,
+    pub holidays: u32,
+    pub half_days: u32,
+    pub working_days: f64,
+}
+
+pub fn init_calendar_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendar_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            weekly_off TEXT NOT NULL DEFAULT 'sunday'
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO calendar_settings (id) VALUES (1)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS academic_terms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            UNIQUE (academic_year_id, name),
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendar_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            day_part TEXT NOT NULL DEFAULT 'full',
+            description TEXT,
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_calendar_entries_dates
+         ON calendar_entries(start_date, end_date)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_calendar_settings(state: State<'_, DbState>) -> Result<CalendarSettings, String> {
+    state.read(service::get_settings).await
+}
+
+#[tauri::command]
+pub async fn save_calendar_settings(
+    state: State<'_, DbState>,
+    settings: CalendarSettings,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_settings(conn, settings))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_academic_terms(
+    state: State<'_, DbState>,
+    academic_year_id: i64,
+) -> Result<Vec<Term>, String> {
+    state
+        .read(move |conn| service::get_terms(conn, academic_year_id))
+        .await
+}
+
+/// Creates a term, or updates it when `term.id` is set.
+#[tauri::command]
+pub async fn save_academic_term(state: State<'_, DbState>, term: Term) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_term(conn, term))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_academic_term(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_term(conn, id))
+        .await
+}
+
+/// Holidays and events of the year, optionally limited to those touching `from..=to`.
+#[tauri::command]
+pub async fn get_calendar_entries(
+    state: State<'_, DbState>,
+    academic_year_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<CalendarEntry>, String> {
+    state
+        .read(move |conn| service::get_entries(conn, academic_year_id, from, to))
+        .await
+}
+
+/// Creates a holiday or event, or updates it when `entry.id` is set.
+#[tauri::command]
+pub async fn save_calendar_entry(
+    state: State<'_, DbState>,
+    entry: CalendarEntry,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_entry(conn, entry))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_calendar_entry(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_entry(conn, id))
+        .await
+}
+
+/// Working days of the year, or of `from..=to` within it.
+#[tauri::command]
+pub async fn get_working_days(
+    state: State<'_, DbState>,
+    academic_year_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<WorkingDays, String> {
+    state
+        .read(move |conn| service::get_working_days(conn, academic_year_id, from, to))
+        .await
+}
+
+/// Writes the year's terms, holidays and events as an iCalendar file and returns its path.
+#[tauri::command]
+pub async fn export_academic_calendar_ics(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    academic_year_id: i64,
+) -> Result<String, String> {
+    let (file_name, ics) = state
+        .read(move |conn| service::render_ics(conn, academic_year_id))
+        .await?;
+    let path = ensure_calendar_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, ics).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
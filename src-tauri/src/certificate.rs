@@ -0,0 +1,270 @@
+// Certificate related structs and implementations
+use crate::service::certificate as service;
+use crate::DbState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_certificates_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Certificates");
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create certificates dir: {}", e))?;
+
+    Ok(dir)
+}
+
+/// Editable wording of one certificate type. `{placeholders}` are filled in at issue time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateTemplate {
+    pub certificate_type: String,
+    pub title: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueCertificateRequest {
+    pub student_id: i64,
+    /// One of `transfer`, `leaving`, `bonafide` or `character`.
+    pub certificate_type: String,
+    /// Defaults to today.
+    pub issue_date: Option<String>,
+    /// Transfer and leaving certificates only; defaults to the issue date.
+    pub leaving_date: Option<String>,
+    /// Required for transfer and leaving certificates.
+    pub leaving_reason: Option<String>,
+    /// Defaults to "Good".
+    pub conduct: Option<String>,
+    /// What a bonafide certificate is needed for, e.g. "bank account opening".
+    pub purpose: Option<String>,
+    pub remarks: Option<String>,
+    pub issued_by: Option<String>,
+}
+
+/// One row of the issued-certificates register. Duplicates share the original's number.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedCertificate {
+    pub id: i64,
+    pub certificate_type: String,
+    pub certificate_number: String,
+    pub academic_year_id: i64,
+    pub academic_year: Option<String>,
+    pub serial_no: i64,
+    /// 0 for the original, 1 for the first duplicate and so on.
+    pub copy_number: i64,
+    pub duplicate_of: Option<i64>,
+    /// Cleared if the student record is later deleted; name and GR number are kept.
+    pub student_id: Option<i64>,
+    pub student_name: String,
+    pub gr_number: String,
+    pub issue_date: String,
+    pub title: String,
+    pub body: String,
+    pub remarks: Option<String>,
+    pub issued_by: Option<String>,
+    pub file_name: String,
+    pub created_at: Option<String>,
+}
+
+const DEFAULT_TEMPLATES: [(&str, &str, &str); 4] = [
+    (
+        "transfer",
+        "Transfer Certificate",
+        "1. Name of the pupil: {student_name}\n\
+         2. General Register No.: {gr_number}\n\
+         3. Father's name: {father_name}\n\
+         4. Mother's name: {mother_name}\n\
+         5. Nationality: {nationality}\n\
+         6. Date of birth: {dob}\n\
+         7. Date of admission: {admission_date}\n\
+         8. Class in which the pupil last studied: {class_name}\n\
+         9. Date of leaving the school: {leaving_date}\n\
+         10. Reason for leaving: {leaving_reason}\n\
+         11. Conduct: {conduct}\n\
+         12. Remarks: {remarks}",
+    ),
+    (
+        "leaving",
+        "School Leaving Certificate",
+        "Certified that {student_name}, {child_of} {father_name} and {mother_name}, \
+         General Register No. {gr_number}, born on {dob}, was admitted to this school on \
+         {admission_date} and left on {leaving_date} while studying in {class_name}.\n\n\
+         Reason for leaving: {leaving_reason}\n\
+         Conduct: {conduct}\n\
+         Remarks: {remarks}",
+    ),
+    (
+        "bonafide",
+        "Bonafide Certificate",
+        "This is to certify that {student_name}, {child_of} {father_name}, is a bonafide \
+         student of {school_name}, studying in {class_name} during the academic year \
+         {academic_year}. As per our General Register (No. {gr_number}) the date of birth \
+         is {dob}.\n\n\
+         This certificate is issued on request for the purpose of {purpose}.",
+    ),
+    (
+        "character",
+        "Character Certificate",
+        "This is to certify that {student_name}, {child_of} {father_name}, General Register \
+         No. {gr_number}, was a student of this school from {admission_date} to {leaving_date}, \
+         last studying in {class_name}.\n\n\
+         To the best of our knowledge the pupil's conduct and character during this period \
+         were {conduct}.\n\n\
+         We wish the pupil every success in life.",
+    ),
+];
+
+pub fn init_certificate_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS certificate_templates (
+            certificate_type TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            body TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    for (certificate_type, title, body) in DEFAULT_TEMPLATES {
+        conn.execute(
+            "INSERT OR IGNORE INTO certificate_templates (certificate_type, title, body)
+             VALUES (?1, ?2, ?3)",
+            params![certificate_type, title, body],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS issued_certificates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            certificate_type TEXT NOT NULL,
+            certificate_number TEXT NOT NULL,
+            academic_year_id INTEGER NOT NULL,
+            serial_no INTEGER NOT NULL,
+            copy_number INTEGER NOT NULL DEFAULT 0,
+            duplicate_of INTEGER,
+            student_id INTEGER,
+            student_name TEXT NOT NULL,
+            gr_number TEXT NOT NULL,
+            issue_date TEXT NOT NULL,
+            title TEXT NOT NULL,
+            body TEXT NOT NULL,
+            remarks TEXT,
+            issued_by TEXT,
+            file_name TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (certificate_type, academic_year_id, serial_no, copy_number),
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id),
+            FOREIGN KEY (duplicate_of) REFERENCES issued_certificates(id),
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_issued_certificates_student
+         ON issued_certificates(student_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_certificate_templates(
+    state: State<'_, DbState>,
+) -> Result<Vec<CertificateTemplate>, String> {
+    state.read(service::get_certificate_templates).await
+}
+
+#[tauri::command]
+pub async fn update_certificate_template(
+    state: State<'_, DbState>,
+    template: CertificateTemplate,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_certificate_template(conn, template))
+        .await
+}
+
+/// Records the certificate in the register, writes its PDF and returns the register entry.
+#[tauri::command]
+pub async fn issue_certificate(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    request: IssueCertificateRequest,
+) -> Result<IssuedCertificate, String> {
+    let (issued, pdf) = state
+        .write(move |conn| {
+            let issued = service::issue_certificate(conn, request)?;
+            let pdf = service::render_certificate_pdf(conn, &issued)?;
+            Ok((issued, pdf))
+        })
+        .await?;
+    write_certificate_file(&app_handle, &issued, &pdf)?;
+    Ok(issued)
+}
+
+#[tauri::command]
+pub async fn issue_duplicate_certificate(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+    remarks: Option<String>,
+    issued_by: Option<String>,
+) -> Result<IssuedCertificate, String> {
+    let (issued, pdf) = state
+        .write(move |conn| {
+            let issued = service::issue_duplicate(conn, id, remarks, issued_by)?;
+            let pdf = service::render_certificate_pdf(conn, &issued)?;
+            Ok((issued, pdf))
+        })
+        .await?;
+    write_certificate_file(&app_handle, &issued, &pdf)?;
+    Ok(issued)
+}
+
+#[tauri::command]
+pub async fn get_issued_certificates(
+    state: State<'_, DbState>,
+    student_id: Option<i64>,
+    certificate_type: Option<String>,
+) -> Result<Vec<IssuedCertificate>, String> {
+    state
+        .read(move |conn| {
+            service::get_issued_certificates(conn, student_id, certificate_type.as_deref())
+        })
+        .await
+}
+
+/// Path of the certificate's PDF, re-creating the file from the register if it went missing.
+#[tauri::command]
+pub async fn get_certificate_file_path(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<String, String> {
+    let issued = state
+        .read(move |conn| service::get_issued_certificate(conn, id))
+        .await?;
+    let path = ensure_certificates_dir(&app_handle)?.join(&issued.file_name);
+    if !path.exists() {
+        let pdf = state
+            .read(move |conn| service::render_certificate_pdf(conn, &issued))
+            .await?;
+        fs::write(&path, pdf).map_err(|e| format!("Failed to write certificate: {}", e))?;
+    }
+    Ok(path.to_string_lossy().into_owned())
+}
+
+fn write_certificate_file(
+    app_handle: &AppHandle,
+    issued: &IssuedCertificate,
+    pdf: &[u8],
+) -> Result<(), String> {
+    let path = ensure_certificates_dir(app_handle)?.join(&issued.file_name);
+    fs::write(path, pdf).map_err(|e| format!("Failed to write certificate: {}", e))
+}
@@ -0,0 +1,356 @@
+// Circulars and notices: templates, audiences, delivery over email/SMS and WhatsApp exports
+use crate::migration::add_column_if_missing;
+use crate::service::communication as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_communication_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Communication");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create communication dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Outgoing mail server and SMS gateway details. The password and API key are never sent
+/// back; `has_password` and `has_api_key` say whether one is stored, and saving without a
+/// new value keeps it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommunicationSettings {
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    /// starttls, tls or none. With none, logging in is only allowed to a relay on this
+    /// machine.
+    pub smtp_security: String,
+    pub smtp_username: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub smtp_password: Option<String>,
+    #[serde(default)]
+    pub has_password: bool,
+    pub smtp_from: Option<String>,
+    pub smtp_from_name: Option<String>,
+    /// `https://` URL (or `http://` to a relay on this machine) with `{to}`, `{message}` and
+    /// optionally `{sender}` and `{api_key}`.
+    pub sms_gateway_url: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub sms_api_key: Option<String>,
+    #[serde(default)]
+    pub has_api_key: bool,
+    pub sms_sender_id: Option<String>,
+    /// Tries per recipient before a failed delivery is left alone.
+    pub max_attempts: u32,
+}
+
+/// Reusable message text. `{student_name}`, `{class_name}` and `{due_amount}` are filled in
+/// per recipient.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+/// What to send and to whom.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageDraft {
+    /// circular or notice.
+    pub kind: Option<String>,
+    pub title: String,
+    /// Email subject; defaults to the title.
+    pub subject: Option<String>,
+    /// Falls back to the template's body when blank.
+    pub body: Option<String>,
+    pub template_id: Option<i64>,
+    /// email, sms, whatsapp, or board for a notice-board post with no delivery.
+    pub channel: String,
+    /// school, class, staff or guardian.
+    pub audience: String,
+    /// Class ids for a class audience, guardian ids for guardians, optional staff ids.
+    #[serde(default)]
+    pub target_ids: Vec<i64>,
+    /// Amount due per student id, for `{due_amount}`; defaults to each student's open dues.
+    /// Students without a positive amount are skipped when the text mentions it.
+    pub due_amounts: Option<HashMap<i64, f64>>,
+    /// Last day the message stays on the notice board.
+    pub expires_on: Option<String>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Message {
+    pub id: i64,
+    pub kind: String,
+    pub title: String,
+    pub subject: String,
+    pub body: String,
+    pub channel: String,
+    pub audience: String,
+    pub target_ids: Vec<i64>,
+    pub expires_on: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub recipients: u32,
+    pub sent: u32,
+    pub failed: u32,
+    pub pending: u32,
+    /// Claimed by a send still in progress.
+    pub sending: u32,
+    pub skipped: u32,
+    pub exported: u32,
+}
+
+/// One rendered copy of a message and how its delivery went.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageRecipient {
+    pub id: i64,
+    pub message_id: i64,
+    /// guardian or staff; student when no guardian is linked and the copy was skipped.
+    pub recipient_type: String,
+    pub recipient_id: i64,
+    pub student_id: Option<i64>,
+    pub name: String,
+    /// Email address or phone number; empty when none is on record.
+    pub address: String,
+    pub subject: String,
+    pub body: String,
+    /// pending, sending, sent, failed, skipped or exported.
+    pub status: String,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Acknowledgement from the mail server or gateway.
+    pub provider_ref: Option<String>,
+    pub sent_at: Option<String>,
+}
+
+pub fn init_communication_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS communication_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            smtp_host TEXT,
+            smtp_port INTEGER NOT NULL DEFAULT 25,
+            smtp_username TEXT,
+            smtp_password TEXT,
+            smtp_from TEXT,
+            smtp_from_name TEXT,
+            sms_gateway_url TEXT,
+            sms_api_key TEXT,
+            sms_sender_id TEXT,
+            max_attempts INTEGER NOT NULL DEFAULT 3
+        )",
+        [],
+    )?;
+    add_column_if_missing(
+        conn,
+        "communication_settings",
+        "smtp_security",
+        "TEXT NOT NULL DEFAULT 'starttls'",
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO communication_settings (id) VALUES (1)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            subject TEXT,
+            body TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            audience TEXT NOT NULL,
+            target_ids TEXT NOT NULL DEFAULT '',
+            expires_on TEXT,
+            on_board BOOLEAN NOT NULL DEFAULT 1,
+            created_by TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS message_recipients (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            message_id INTEGER NOT NULL,
+            recipient_type TEXT NOT NULL,
+            recipient_id INTEGER NOT NULL,
+            student_id INTEGER,
+            name TEXT NOT NULL,
+            address TEXT NOT NULL DEFAULT '',
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            provider_ref TEXT,
+            sent_at DATETIME,
+            FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_message_recipients_message
+         ON message_recipients(message_id, status)",
+        [],
+    )?;
+    // Nothing is sending at startup; copies claimed by a send the app closed during may or
+    // may not have gone out, so they count as a failed try.
+    conn.execute(
+        "UPDATE message_recipients SET status = 'failed', attempts = attempts + 1,
+            last_error = 'Interrupted while sending; it may have gone out'
+         WHERE status = 'sending'",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_communication_settings(
+    state: State<'_, DbState>,
+) -> Result<CommunicationSettings, String> {
+    state.read(service::get_settings).await
+}
+
+#[tauri::command]
+pub async fn save_communication_settings(
+    state: State<'_, DbState>,
+    settings: CommunicationSettings,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_settings(conn, settings))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_message_templates(
+    state: State<'_, DbState>,
+) -> Result<Vec<MessageTemplate>, String> {
+    state.read(service::get_templates).await
+}
+
+/// Creates a template, or updates it when `template.id` is set.
+#[tauri::command]
+pub async fn save_message_template(
+    state: State<'_, DbState>,
+    template: MessageTemplate,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_template(conn, template))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_message_template(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_template(conn, id))
+        .await
+}
+
+/// Resolves the audience and renders one copy per recipient, ready to send.
+#[tauri::command]
+pub async fn compose_message(
+    state: State<'_, DbState>,
+    draft: MessageDraft,
+) -> Result<Message, String> {
+    state
+        .write(move |conn| service::compose_message(conn, draft))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_messages(state: State<'_, DbState>) -> Result<Vec<Message>, String> {
+    state.read(service::get_messages).await
+}
+
+#[tauri::command]
+pub async fn get_message_recipients(
+    state: State<'_, DbState>,
+    message_id: i64,
+    status: Option<String>,
+) -> Result<Vec<MessageRecipient>, String> {
+    state
+        .read(move |conn| service::get_recipients(conn, message_id, status))
+        .await
+}
+
+/// Circulars and notices still on the board, optionally only those a class should see.
+/// Personalised messages and those for individual guardians never appear there.
+#[tauri::command]
+pub async fn get_notice_board(
+    state: State<'_, DbState>,
+    class_id: Option<i64>,
+) -> Result<Vec<Message>, String> {
+    state
+        .read(move |conn| service::get_notice_board(conn, class_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_message(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_message(conn, id))
+        .await
+}
+
+/// Sends every pending copy, and retries failed ones that have tries left. The copies are
+/// claimed as `sending` first so a second send started meanwhile cannot pick them up; the
+/// network work happens between two short writes so the writer is not held meanwhile.
+#[tauri::command]
+pub async fn send_message(state: State<'_, DbState>, message_id: i64) -> Result<Message, String> {
+    let (mut channel, recipients) = state
+        .write(move |conn| service::claim_deliveries(conn, message_id))
+        .await?;
+    let claimed: Vec<i64> = recipients.iter().map(|r| r.id).collect();
+    let attempts =
+        tauri::async_runtime::spawn_blocking(move || service::deliver(&mut *channel, recipients))
+            .await
+            .unwrap_or_else(|e| {
+                // Record the claimed copies as failed rather than leave them stuck in sending.
+                claimed
+                    .into_iter()
+                    .map(|recipient_id| service::DeliveryAttempt {
+                        recipient_id,
+                        outcome: Err(format!("Delivery task failed: {}", e)),
+                    })
+                    .collect()
+            });
+    state
+        .write(move |conn| service::record_deliveries(conn, message_id, attempts))
+        .await
+}
+
+/// Writes a CSV for bulk WhatsApp tools, then marks the copies in it as exported. When the
+/// file cannot be written nothing is marked.
+#[tauri::command]
+pub async fn export_whatsapp_message(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    message_id: i64,
+) -> Result<String, String> {
+    let (file_name, csv, ids) = state
+        .read(move |conn| service::export_whatsapp(conn, message_id))
+        .await?;
+    let path = ensure_communication_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, csv).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    state
+        .write(move |conn| service::mark_whatsapp_exported(conn, ids))
+        .await?;
+    Ok(path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,97 @@
+// Dashboard: school-wide figures for the overview page
+use crate::service::dashboard as service;
+use crate::DbState;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// Longest a cached result is served without any write, so figures tied to today's date
+/// (admissions this month, today's attendance) roll over soon after midnight.
+const MAX_CACHE_AGE: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountBy {
+    pub label: String,
+    pub count: u32,
+}
+
+/// Student dues raised so far, split by where they stand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeCollection {
+    pub raised: f64,
+    pub collected: f64,
+    pub waived: f64,
+    pub outstanding: f64,
+    /// Collected as a percentage of what was raised less waivers.
+    pub collection_rate: Option<f64>,
+}
+
+/// Today's staff attendance register.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttendanceToday {
+    /// Active staff who have joined and are not on approved leave.
+    pub expected: u32,
+    pub on_leave: u32,
+    pub marked: u32,
+    /// Half days count as half present.
+    pub present: f64,
+    /// Present as a percentage of those expected; `None` until someone is marked.
+    pub rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardStats {
+    pub as_of: String,
+    /// Students on the roll.
+    pub active_students: u32,
+    pub students_by_gender: Vec<CountBy>,
+    /// In grade order.
+    pub students_by_class: Vec<CountBy>,
+    pub new_admissions_this_month: u32,
+    pub enquiries_by_status: Vec<CountBy>,
+    pub staff_headcount: u32,
+    pub staff_by_department: Vec<CountBy>,
+    pub fee_collection: FeeCollection,
+    pub attendance_today: AttendanceToday,
+}
+
+/// Last computed statistics with the database generation they were computed at.
+#[derive(Default)]
+pub struct DashboardCache(Mutex<Option<(u64, Instant, DashboardStats)>>);
+
+impl DashboardCache {
+    fn get(&self, generation: u64) -> Option<DashboardStats> {
+        let cached = self.0.lock().ok()?;
+        match cached.as_ref() {
+            Some((at, computed, stats))
+                if *at == generation && computed.elapsed() < MAX_CACHE_AGE =>
+            {
+                Some(stats.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn put(&self, generation: u64, stats: &DashboardStats) {
+        if let Ok(mut cached) = self.0.lock() {
+            *cached = Some((generation, Instant::now(), stats.clone()));
+        }
+    }
+}
+
+/// Overview figures, served from the cache until the next write to the database.
+#[tauri::command]
+pub async fn get_dashboard_stats(
+    state: State<'_, DbState>,
+    cache: State<'_, DashboardCache>,
+) -> Result<DashboardStats, String> {
+    // Taken before reading, so a write that lands mid-computation leaves the result stale.
+    let generation = state.generation();
+    if let Some(stats) = cache.get(generation) {
+        return Ok(stats);
+    }
+    let stats = state.read(service::get_stats).await?;
+    cache.put(generation, &stats);
+    Ok(stats)
+}
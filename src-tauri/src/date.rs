@@ -0,0 +1,226 @@
+// Calendar helpers for the `YYYY-MM-DD` dates and `HH:MM` times stored in the database.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// English name of a month numbered from 1.
+pub fn month_name(month: u32) -> &'static str {
+    MONTH_NAMES
+        .get(month.wrapping_sub(1) as usize)
+        .copied()
+        .unwrap_or("")
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Date, String> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(format!("Invalid date {:04}-{:02}-{:02}", year, month, day));
+        }
+        Ok(Date { year, month, day })
+    }
+
+    /// Parses `YYYY-MM-DD`; anything after the day (such as a time) is ignored.
+    pub fn parse(value: &str) -> Result<Date, String> {
+        let invalid = || format!("Invalid date '{}', expected YYYY-MM-DD", value);
+        let value = value.trim();
+        let date = value.get(..10).ok_or_else(invalid)?;
+        let mut parts = date.split('-');
+        let (Some(y), Some(m), Some(d), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if y.len() != 4 || m.len() != 2 || d.len() != 2 {
+            return Err(invalid());
+        }
+        let year = y.parse().map_err(|_| invalid())?;
+        let month = m.parse().map_err(|_| invalid())?;
+        let day = d.parse().map_err(|_| invalid())?;
+        Date::new(year, month, day).map_err(|_| invalid())
+    }
+
+    /// Days since 1970-01-01 (proleptic Gregorian calendar).
+    fn to_days(self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    fn from_days(days: i64) -> Date {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Date { year, month, day }
+    }
+
+    pub fn add_days(self, days: i64) -> Date {
+        Date::from_days(self.to_days() + days)
+    }
+
+    /// The same day `months` later, or the month's last day when it is shorter.
+    pub fn add_months(self, months: u32) -> Date {
+        let index = self.year as i64 * 12 + self.month as i64 - 1 + months as i64;
+        let year = index.div_euclid(12) as i32;
+        let month = index.rem_euclid(12) as u32 + 1;
+        let day = self.day.min(days_in_month(year, month));
+        Date { year, month, day }
+    }
+
+    /// Whole months from `self` to `other`; negative when `other` is earlier.
+    pub fn months_until(self, other: Date) -> i64 {
+        let months =
+            (other.year as i64 - self.year as i64) * 12 + other.month as i64 - self.month as i64;
+        if months > 0 && other.day < self.day {
+            months - 1
+        } else if months < 0 && other.day > self.day {
+            months + 1
+        } else {
+            months
+        }
+    }
+
+    /// Number of days from `self` to `other`; negative when `other` is earlier.
+    pub fn days_until(self, other: Date) -> i64 {
+        other.to_days() - self.to_days()
+    }
+
+    /// 0 for Monday through 6 for Sunday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.to_days() + 3).rem_euclid(7) as u32
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Parses `HH:MM` (seconds, if present, are ignored) into minutes after midnight.
+pub fn parse_time(value: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid time '{}', expected HH:MM", value);
+    let mut parts = value.trim().split(':');
+    let hours: u32 = parts
+        .next()
+        .filter(|h| h.len() == 2)
+        .and_then(|h| h.parse().ok())
+        .ok_or_else(invalid)?;
+    let minutes: u32 = parts
+        .next()
+        .filter(|m| m.len() == 2)
+        .and_then(|m| m.parse().ok())
+        .ok_or_else(invalid)?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_dates() {
+        assert_eq!(
+            Date::parse("2024-02-29").unwrap(),
+            Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            }
+        );
+        assert_eq!(
+            Date::parse("2024-06-10 09:30:00").unwrap().to_string(),
+            "2024-06-10"
+        );
+        for bad in ["2023-02-29", "2024-13-01", "24-01-01", "2024/01/01", ""] {
+            assert!(Date::parse(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn day_arithmetic_crosses_months_and_years() {
+        let date = Date::parse("2024-12-30").unwrap();
+        assert_eq!(date.add_days(3).to_string(), "2025-01-02");
+        assert_eq!(
+            Date::parse("2024-03-01").unwrap().add_days(-1).to_string(),
+            "2024-02-29"
+        );
+        assert_eq!(date.days_until(Date::parse("2025-01-02").unwrap()), 3);
+        assert_eq!(
+            Date::parse("1970-01-01").unwrap().add_days(0).to_string(),
+            "1970-01-01"
+        );
+    }
+
+    #[test]
+    fn month_arithmetic_clamps_to_the_month_end() {
+        let date = Date::parse("2015-01-31").unwrap();
+        assert_eq!(date.add_months(1).to_string(), "2015-02-28");
+        assert_eq!(date.add_months(109).to_string(), "2024-02-29");
+        assert_eq!(date.months_until(Date::parse("2025-01-30").unwrap()), 119);
+        assert_eq!(date.months_until(Date::parse("2025-01-31").unwrap()), 120);
+    }
+
+    #[test]
+    fn weekdays() {
+        // 2025-06-01 was a Sunday, 2024-01-01 a Monday.
+        assert_eq!(Date::parse("2025-06-01").unwrap().weekday(), 6);
+        assert_eq!(Date::parse("2024-01-01").unwrap().weekday(), 0);
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("09:05").unwrap(), 545);
+        assert_eq!(parse_time("17:30:59").unwrap(), 1050);
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("9:5").is_err());
+    }
+}
@@ -1,14 +1,268 @@
-use rusqlite::Connection;
-use std::path::PathBuf;
+use log::warn;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
+/// Number of read-only connections kept open next to the single writer.
+const READER_COUNT: usize = 4;
+
+/// How long a connection waits on a locked database before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Locks `mutex`, recovering the connection if a previous holder panicked.
+fn lock_recovering(mutex: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            warn!("Recovering database connection after a panic");
+            mutex.clear_poison();
+            reset_after_panic(poisoned.into_inner())
+        }
+    }
+}
+
+/// Rolls back whatever transaction the panicking holder left open.
+fn reset_after_panic(conn: MutexGuard<'_, Connection>) -> MutexGuard<'_, Connection> {
+    if !conn.is_autocommit() {
+        if let Err(e) = conn.execute_batch("ROLLBACK") {
+            warn!("Failed to roll back abandoned transaction: {}", e);
+        }
+    }
+    conn
+}
+
+/// SQLite connections in WAL mode: one writer plus a few read-only connections,
+/// so long reads never wait on a write and vice versa.
+pub struct DbPool {
+    writer: Mutex<Connection>,
+    readers: Vec<Mutex<Connection>>,
+    next_reader: AtomicUsize,
+    /// Bumped after every `DbState::write`, so caches can tell when their data went stale.
+    generation: AtomicU64,
+}
+
+impl DbPool {
+    pub fn open(path: &Path, reader_count: usize) -> rusqlite::Result<Self> {
+        let writer = Connection::open(path)?;
+        writer.busy_timeout(BUSY_TIMEOUT)?;
+        writer.pragma_update(None, "journal_mode", "WAL")?;
+        writer.pragma_update(None, "synchronous", "NORMAL")?;
+        writer.pragma_update(None, "foreign_keys", "ON")?;
+
+        let mut readers = Vec::with_capacity(reader_count);
+        for _ in 0..reader_count.max(1) {
+            let reader = Connection::open_with_flags(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            readers.push(Mutex::new(reader));
+        }
+
+        Ok(DbPool {
+            writer: Mutex::new(writer),
+            readers,
+            next_reader: AtomicUsize::new(0),
+            generation: AtomicU64::new(0),
+        })
+    }
+
+    /// The single connection allowed to modify the database.
+    pub fn writer(&self) -> MutexGuard<'_, Connection> {
+        lock_recovering(&self.writer)
+    }
+
+    /// Any idle read-only connection, or the next one in turn if all are busy.
+    pub fn reader(&self) -> MutexGuard<'_, Connection> {
+        for reader in &self.readers {
+            match reader.try_lock() {
+                Ok(guard) => return guard,
+                Err(TryLockError::Poisoned(poisoned)) => {
+                    warn!("Recovering database connection after a panic");
+                    reader.clear_poison();
+                    return reset_after_panic(poisoned.into_inner());
+                }
+                Err(TryLockError::WouldBlock) => continue,
+            }
+        }
+
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        lock_recovering(&self.readers[index])
+    }
+}
+
+// Database state that will be shared across the application
+pub struct DbState(pub Arc<DbPool>);
+
+impl DbState {
+    /// Runs `f` on a reader connection on the blocking thread pool.
+    pub async fn read<T, F>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = Arc::clone(&self.0);
+        tauri::async_runtime::spawn_blocking(move || f(&pool.reader()))
+            .await
+            .map_err(|e| format!("Database task failed: {}", e))?
+    }
+
+    /// Runs `f` on the writer connection on the blocking thread pool.
+    pub async fn write<T, F>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = Arc::clone(&self.0);
+        tauri::async_runtime::spawn_blocking(move || {
+            let result = f(&mut pool.writer());
+            pool.generation.fetch_add(1, Ordering::Release);
+            result
+        })
+        .await
+        .map_err(|e| format!("Database task failed: {}", e))?
+    }
+
+    /// Number of writes so far. Anything computed at an earlier generation may be stale.
+    pub fn generation(&self) -> u64 {
+        self.0.generation.load(Ordering::Acquire)
+    }
+}
+
 pub fn establish_connection(
     app_handle: &AppHandle,
-) -> Result<Connection, Box<dyn std::error::Error>> {
+) -> Result<DbPool, Box<dyn std::error::Error>> {
     let app_dir: PathBuf = app_handle.path().app_data_dir().unwrap();
     std::fs::create_dir_all(&app_dir)?;
     let db_path = app_dir.join("campussync.db");
 
-    let conn = Connection::open(&db_path)?;
-    Ok(conn)
+    let pool = DbPool::open(&db_path, READER_COUNT)?;
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "campussync-{}-{}.db",
+                name,
+                std::process::id()
+            ));
+            let db = TempDb(path);
+            db.cleanup();
+            db
+        }
+
+        fn cleanup(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.cleanup();
+        }
+    }
+
+    fn pool(db: &TempDb) -> DbPool {
+        let pool = DbPool::open(&db.0, 2).unwrap();
+        pool.writer()
+            .execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .unwrap();
+        pool
+    }
+
+    fn count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn opens_in_wal_mode() {
+        let db = TempDb::new("wal");
+        let pool = pool(&db);
+        let mode: String = pool
+            .writer()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+    }
+
+    #[test]
+    fn readers_are_not_blocked_by_an_open_write() {
+        let db = TempDb::new("concurrent");
+        let pool = pool(&db);
+        pool.writer().execute("INSERT INTO items (name) VALUES ('a')", []).unwrap();
+
+        let writer = pool.writer();
+        writer.execute_batch("BEGIN IMMEDIATE; INSERT INTO items (name) VALUES ('b');").unwrap();
+
+        // The uncommitted row is invisible, but the read does not wait for it.
+        assert_eq!(count(&pool.reader()), 1);
+        writer.execute_batch("COMMIT").unwrap();
+        assert_eq!(count(&pool.reader()), 2);
+    }
+
+    #[test]
+    fn readers_reject_writes() {
+        let db = TempDb::new("readonly");
+        let pool = pool(&db);
+        let result = pool.reader().execute("INSERT INTO items (name) VALUES ('x')", []);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn busy_readers_hand_out_another_connection() {
+        let db = TempDb::new("busy");
+        let pool = pool(&db);
+        let first = pool.reader();
+        let second = pool.reader();
+        assert_eq!(count(&first), count(&second));
+    }
+
+    #[test]
+    fn writes_advance_the_generation() {
+        let db = TempDb::new("generation");
+        let state = DbState(Arc::new(pool(&db)));
+        assert_eq!(state.generation(), 0);
+        tauri::async_runtime::block_on(state.read(|conn| Ok(count(conn)))).unwrap();
+        assert_eq!(state.generation(), 0);
+        tauri::async_runtime::block_on(state.write(|conn| {
+            conn.execute("INSERT INTO items (name) VALUES ('a')", [])
+                .map_err(|e| e.to_string())
+        }))
+        .unwrap();
+        assert_eq!(state.generation(), 1);
+    }
+
+    #[test]
+    fn poisoned_writer_is_recovered_and_rolled_back() {
+        let db = TempDb::new("poison");
+        let pool = pool(&db);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let conn = pool.writer();
+            conn.execute_batch("BEGIN; INSERT INTO items (name) VALUES ('lost');").unwrap();
+            panic!("command panicked mid-transaction");
+        }));
+        assert!(result.is_err());
+
+        let conn = pool.writer();
+        assert!(conn.is_autocommit());
+        assert_eq!(count(&conn), 0);
+        conn.execute("INSERT INTO items (name) VALUES ('ok')", []).unwrap();
+        assert_eq!(count(&conn), 1);
+    }
 }
@@ -0,0 +1,224 @@
+// Discipline: behavioural incidents, parent meetings and acknowledgements
+use crate::service::discipline as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// An incident. Who may read it depends on `visibility` and the viewer's role: `staff`
+/// incidents are open to every teacher, `counsellor` ones to counsellors and the principal,
+/// and `principal` ones to the principal alone.
+///
+/// The viewer's role is passed in by the frontend: the app has no login, so nothing on this
+/// side knows who is at the keyboard. These rules keep incidents out of the wrong screens; they
+/// are a UI-level filter, not access control, and anyone who can call the commands directly can
+/// claim any role.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisciplineIncident {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    pub incident_date: String,
+    /// disruption, bullying, fighting, damage, cheating, uniform, late or other.
+    pub category: String,
+    /// minor, moderate or major.
+    pub severity: String,
+    pub description: String,
+    pub action_taken: Option<String>,
+    /// Staff id of the person reporting the incident.
+    pub reported_by: i64,
+    /// staff, counsellor or principal; defaults to staff.
+    pub visibility: Option<String>,
+    /// Whether the incident is mentioned on the student's character certificate.
+    #[serde(default)]
+    pub include_on_certificate: bool,
+    /// Guardian who acknowledged the incident, set through `acknowledge_incident`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledged_by: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledged_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_remarks: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reporter_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledged_by_name: Option<String>,
+}
+
+/// A meeting with the student's parents, about one incident or the student's conduct in
+/// general. Meetings about an incident share its visibility.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParentMeeting {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    pub incident_id: Option<i64>,
+    pub meeting_date: String,
+    /// A guardian linked to the student.
+    pub guardian_id: Option<i64>,
+    /// Staff present at the meeting.
+    pub held_by: Option<String>,
+    pub summary: String,
+    pub follow_up: Option<String>,
+    /// For meetings not tied to an incident; defaults to staff.
+    pub visibility: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardian_name: Option<String>,
+}
+
+/// One dated entry of a student's discipline timeline.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisciplineTimelineEntry {
+    pub date: String,
+    /// incident, acknowledgement or meeting.
+    pub entry_type: String,
+    pub incident_id: Option<i64>,
+    pub meeting_id: Option<i64>,
+    pub title: String,
+    pub details: Option<String>,
+    pub severity: Option<String>,
+}
+
+pub fn init_discipline_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS discipline_incidents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            incident_date TEXT NOT NULL,
+            category TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            description TEXT NOT NULL,
+            action_taken TEXT,
+            reported_by INTEGER NOT NULL,
+            visibility TEXT NOT NULL DEFAULT 'staff',
+            include_on_certificate INTEGER NOT NULL DEFAULT 0,
+            acknowledged_by INTEGER,
+            acknowledged_on TEXT,
+            parent_remarks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            FOREIGN KEY (reported_by) REFERENCES staff(id),
+            FOREIGN KEY (acknowledged_by) REFERENCES guardians(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS parent_meetings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            incident_id INTEGER,
+            meeting_date TEXT NOT NULL,
+            guardian_id INTEGER,
+            held_by TEXT,
+            summary TEXT NOT NULL,
+            follow_up TEXT,
+            visibility TEXT NOT NULL DEFAULT 'staff',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            FOREIGN KEY (incident_id) REFERENCES discipline_incidents(id) ON DELETE CASCADE,
+            FOREIGN KEY (guardian_id) REFERENCES guardians(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Records an incident, or updates it when `incident.id` is set and the viewer may see it.
+/// `viewer_role` is taken on trust from the frontend; see [`DisciplineIncident`].
+#[tauri::command]
+pub async fn save_discipline_incident(
+    state: State<'_, DbState>,
+    incident: DisciplineIncident,
+    viewer_role: String,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_incident(conn, incident, &viewer_role))
+        .await
+}
+
+/// Only the principal deletes incidents, going by the role the frontend passes in, which keeps
+/// the delete button off other screens but does not stop a caller naming itself principal.
+#[tauri::command]
+pub async fn delete_discipline_incident(
+    state: State<'_, DbState>,
+    id: i64,
+    viewer_role: String,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_incident(conn, id, &viewer_role))
+        .await
+}
+
+/// Incidents the viewer may see, latest first. Filtered by the role the frontend reports, not
+/// by any check of who is asking.
+#[tauri::command]
+pub async fn get_discipline_incidents(
+    state: State<'_, DbState>,
+    viewer_role: String,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+    unacknowledged_only: Option<bool>,
+) -> Result<Vec<DisciplineIncident>, String> {
+    state
+        .read(move |conn| {
+            service::get_incidents(
+                conn,
+                &viewer_role,
+                student_id,
+                from,
+                to,
+                unacknowledged_only.unwrap_or(false),
+            )
+        })
+        .await
+}
+
+/// Records that a guardian on the student's record has seen the incident.
+#[tauri::command]
+pub async fn acknowledge_discipline_incident(
+    state: State<'_, DbState>,
+    incident_id: i64,
+    guardian_id: i64,
+    acknowledged_on: Option<String>,
+    parent_remarks: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| {
+            service::acknowledge_incident(
+                conn,
+                incident_id,
+                guardian_id,
+                acknowledged_on,
+                parent_remarks,
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn record_parent_meeting(
+    state: State<'_, DbState>,
+    meeting: ParentMeeting,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_meeting(conn, meeting))
+        .await
+}
+
+/// Incidents, acknowledgements and meetings of one student the viewer may see, latest first.
+/// As with the incident list, `viewer_role` is the frontend's word.
+#[tauri::command]
+pub async fn get_discipline_timeline(
+    state: State<'_, DbState>,
+    student_id: i64,
+    viewer_role: String,
+) -> Result<Vec<DisciplineTimelineEntry>, String> {
+    state
+        .read(move |conn| service::get_timeline(conn, student_id, &viewer_role))
+        .await
+}
@@ -0,0 +1,115 @@
+// Student dues: charges raised against a student by other modules (library fines and the
+// like) or by hand, and their settlement
+use crate::service::dues as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StudentDue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    /// Module that raised the charge, e.g. library; manual for charges entered by hand.
+    pub source: Option<String>,
+    /// Identifies the charge within its source so it is only raised once.
+    pub reference: Option<String>,
+    pub description: String,
+    pub amount: f64,
+    /// Defaults to today.
+    pub raised_on: Option<String>,
+    /// open, paid or waived.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settled_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settled_note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+}
+
+/// Closing an open due as paid or waived. A waiver needs a note.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DueSettlement {
+    pub status: String,
+    /// Defaults to today.
+    pub settled_on: Option<String>,
+    pub note: Option<String>,
+}
+
+pub fn init_dues_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS student_dues (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            reference TEXT,
+            description TEXT NOT NULL,
+            amount REAL NOT NULL,
+            raised_on TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            settled_on TEXT,
+            settled_note TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_student_dues_reference
+         ON student_dues(source, reference) WHERE reference IS NOT NULL",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_student_dues_student ON student_dues(student_id, status)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_student_dues(
+    state: State<'_, DbState>,
+    student_id: i64,
+    include_settled: Option<bool>,
+) -> Result<Vec<StudentDue>, String> {
+    state
+        .read(move |conn| {
+            service::get_student_dues(conn, student_id, include_settled.unwrap_or(false))
+        })
+        .await
+}
+
+/// Open dues of every student on the roll, or of one class.
+#[tauri::command]
+pub async fn get_open_dues(
+    state: State<'_, DbState>,
+    class_id: Option<i64>,
+) -> Result<Vec<StudentDue>, String> {
+    state
+        .read(move |conn| service::get_open_dues(conn, class_id))
+        .await
+}
+
+/// Raises a charge by hand.
+#[tauri::command]
+pub async fn add_student_due(state: State<'_, DbState>, due: StudentDue) -> Result<i64, String> {
+    state
+        .write(move |conn| service::add_manual_due(conn, due))
+        .await
+}
+
+#[tauri::command]
+pub async fn settle_student_due(
+    state: State<'_, DbState>,
+    id: i64,
+    settlement: DueSettlement,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::settle_due(conn, id, settlement))
+        .await
+}
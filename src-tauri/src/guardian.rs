@@ -0,0 +1,194 @@
+// Guardian related structs and implementations
+use crate::service::guardian as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Guardian {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub full_name: String,
+    pub relation_type: String,
+    pub phone: Option<String>,
+    pub alternate_phone: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub occupation: Option<String>,
+    pub annual_income: Option<f64>,
+    pub aadhaar_number: Option<String>,
+    pub pan_number: Option<String>,
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// A guardian as seen from one student, with that link's primary-contact flag.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StudentGuardian {
+    #[serde(flatten)]
+    pub guardian: Guardian,
+    pub is_primary_contact: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sibling {
+    pub student_id: i64,
+    pub gr_number: String,
+    pub full_name: String,
+    pub dob: Option<String>,
+    pub class_id: String,
+    pub class_name: Option<String>,
+}
+
+/// Everything shared between a student and their siblings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Family {
+    pub student_id: i64,
+    pub guardians: Vec<StudentGuardian>,
+    /// Other students sharing at least one guardian, eldest first.
+    pub siblings: Vec<Sibling>,
+}
+
+pub fn init_guardian_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS guardians (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            full_name TEXT NOT NULL,
+            relation_type TEXT NOT NULL,
+            phone TEXT,
+            alternate_phone TEXT,
+            email TEXT,
+            address TEXT,
+            occupation TEXT,
+            annual_income REAL,
+            aadhaar_number TEXT,
+            pan_number TEXT,
+            notes TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS student_guardians (
+            student_id INTEGER NOT NULL,
+            guardian_id INTEGER NOT NULL,
+            is_primary_contact BOOLEAN NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (student_id, guardian_id),
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            FOREIGN KEY (guardian_id) REFERENCES guardians(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // At most one primary contact per student
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_student_guardians_primary
+         ON student_guardians(student_id) WHERE is_primary_contact = 1",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_student_guardians_guardian
+         ON student_guardians(guardian_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_guardian(state: State<'_, DbState>, guardian: Guardian) -> Result<i64, String> {
+    state
+        .write(move |conn| service::create_guardian(conn, guardian))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_guardian(state: State<'_, DbState>, id: i64) -> Result<Guardian, String> {
+    state.read(move |conn| service::get_guardian(conn, id)).await
+}
+
+#[tauri::command]
+pub async fn search_guardians(
+    state: State<'_, DbState>,
+    query: String,
+) -> Result<Vec<Guardian>, String> {
+    state
+        .read(move |conn| service::search_guardians(conn, &query))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_guardian(
+    state: State<'_, DbState>,
+    id: i64,
+    guardian: Guardian,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_guardian(conn, id, guardian))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_guardian(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state.write(move |conn| service::delete_guardian(conn, id)).await
+}
+
+#[tauri::command]
+pub async fn link_guardian(
+    state: State<'_, DbState>,
+    student_id: i64,
+    guardian_id: i64,
+    is_primary_contact: bool,
+) -> Result<(), String> {
+    state
+        .write(move |conn| {
+            service::link_guardian(conn, student_id, guardian_id, is_primary_contact)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn unlink_guardian(
+    state: State<'_, DbState>,
+    student_id: i64,
+    guardian_id: i64,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::unlink_guardian(conn, student_id, guardian_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn set_primary_guardian(
+    state: State<'_, DbState>,
+    student_id: i64,
+    guardian_id: i64,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::set_primary_guardian(conn, student_id, guardian_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_student_guardians(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Vec<StudentGuardian>, String> {
+    state
+        .read(move |conn| service::get_student_guardians(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_student_family(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Family, String> {
+    state
+        .read(move |conn| service::get_student_family(conn, student_id))
+        .await
+}
@@ -0,0 +1,367 @@
+// Health: dated checkups and growth, vaccinations, infirmary visits and medical alerts
+use crate::service::health as service;
+use crate::DbState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_register_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Attendance Registers");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create registers dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Doses expected of every student, as (vaccine, dose number, due age in months). Based on
+/// the school-age part of the IAP schedule; schools can change due ages or switch doses off.
+const DEFAULT_VACCINE_SCHEDULE: [(&str, u32, u32); 7] = [
+    ("DTaP booster", 2, 60),
+    ("MMR", 3, 60),
+    ("Typhoid conjugate", 2, 60),
+    ("HPV", 1, 108),
+    ("HPV", 2, 114),
+    ("Tdap", 1, 120),
+    ("Td", 1, 192),
+];
+
+/// One dated checkup. `age_months`, `bmi` and the percentile fields are worked out from the
+/// student's date of birth and gender when checkups are read.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthCheckup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    /// Defaults to today.
+    pub checkup_date: Option<String>,
+    pub weight_kg: Option<f64>,
+    pub height_cm: Option<f64>,
+    pub hb_range: Option<String>,
+    pub vision: Option<String>,
+    pub dental: Option<String>,
+    pub remarks: Option<String>,
+    /// Doctor or nurse who did the checkup.
+    pub checked_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_months: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bmi: Option<f64>,
+    /// Reference percentiles the BMI falls between, such as "50th-85th".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bmi_percentile: Option<String>,
+    /// underweight, healthy, overweight or obese.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bmi_category: Option<String>,
+}
+
+/// A dose on the school's vaccination schedule.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaccineDose {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub vaccine: String,
+    pub dose_number: u32,
+    /// Age at which the dose falls due.
+    pub due_age_months: u32,
+    /// Inactive doses are kept but no longer reminded about.
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A dose a student has had, or, on a vaccination card, one still to come.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StudentVaccination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    pub vaccine: String,
+    pub dose_number: u32,
+    pub given_on: Option<String>,
+    pub batch_number: Option<String>,
+    /// Hospital or clinic where the dose was given.
+    pub given_at: Option<String>,
+    pub remarks: Option<String>,
+    /// Date of birth plus the scheduled due age; `None` for doses off the schedule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    /// given, overdue, due or upcoming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// A scheduled dose a student on the roll has not had yet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaccinationReminder {
+    pub student_id: i64,
+    pub student_name: String,
+    pub class_name: Option<String>,
+    pub mobile_number: Option<String>,
+    pub vaccine: String,
+    pub dose_number: u32,
+    pub due_date: String,
+    /// Days past the due date; negative when the dose is not yet due.
+    pub days_overdue: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InfirmaryVisit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    /// Defaults to today.
+    pub visit_date: Option<String>,
+    /// HH:MM; defaults to now.
+    pub visit_time: Option<String>,
+    pub complaint: String,
+    pub temperature_c: Option<f64>,
+    pub treatment: Option<String>,
+    /// returned_to_class, rested, sent_home or referred; defaults to returned_to_class.
+    pub outcome: Option<String>,
+    #[serde(default)]
+    pub guardian_informed: bool,
+    pub attended_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    /// The student's allergies and chronic conditions, shown to whoever attends.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medical_alert: Option<String>,
+}
+
+/// A student on the roll whose record lists allergies or chronic conditions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MedicalAlert {
+    pub student_id: i64,
+    pub student_name: String,
+    pub class_name: Option<String>,
+    pub roll_number: Option<String>,
+    pub blood_group: Option<String>,
+    pub medical_conditions: String,
+    pub emergency_contact_person: Option<String>,
+    pub emergency_contact: Option<String>,
+}
+
+pub fn init_health_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS health_checkups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            checkup_date TEXT NOT NULL,
+            weight_kg REAL,
+            height_cm REAL,
+            hb_range TEXT,
+            vision TEXT,
+            dental TEXT,
+            remarks TEXT,
+            checked_by TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vaccine_schedule (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vaccine TEXT NOT NULL,
+            dose_number INTEGER NOT NULL,
+            due_age_months INTEGER NOT NULL,
+            active INTEGER NOT NULL DEFAULT 1,
+            UNIQUE (vaccine, dose_number)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS student_vaccinations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            vaccine TEXT NOT NULL,
+            dose_number INTEGER NOT NULL,
+            given_on TEXT NOT NULL,
+            batch_number TEXT,
+            given_at TEXT,
+            remarks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (student_id, vaccine, dose_number),
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS infirmary_visits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            visit_date TEXT NOT NULL,
+            visit_time TEXT NOT NULL,
+            complaint TEXT NOT NULL,
+            temperature_c REAL,
+            treatment TEXT,
+            outcome TEXT NOT NULL DEFAULT 'returned_to_class',
+            guardian_informed INTEGER NOT NULL DEFAULT 0,
+            attended_by TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_health_checkups_student
+         ON health_checkups(student_id, checkup_date)",
+        [],
+    )?;
+
+    for (vaccine, dose_number, due_age_months) in DEFAULT_VACCINE_SCHEDULE {
+        conn.execute(
+            "INSERT OR IGNORE INTO vaccine_schedule (vaccine, dose_number, due_age_months)
+             VALUES (?1, ?2, ?3)",
+            params![vaccine, dose_number, due_age_months],
+        )?;
+    }
+    Ok(())
+}
+
+/// Records a checkup, or corrects it when `checkup.id` is set. The latest checkup also
+/// updates the weight, height and Hb range on the student's record.
+#[tauri::command]
+pub async fn save_health_checkup(
+    state: State<'_, DbState>,
+    checkup: HealthCheckup,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_checkup(conn, checkup))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_health_checkup(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_checkup(conn, id))
+        .await
+}
+
+/// Checkups of one student, oldest first, with BMI against the reference percentiles.
+#[tauri::command]
+pub async fn get_growth_history(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Vec<HealthCheckup>, String> {
+    state
+        .read(move |conn| service::get_growth_history(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_vaccine_schedule(state: State<'_, DbState>) -> Result<Vec<VaccineDose>, String> {
+    state.read(service::get_vaccine_schedule).await
+}
+
+#[tauri::command]
+pub async fn save_vaccine_schedule_dose(
+    state: State<'_, DbState>,
+    dose: VaccineDose,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_schedule_dose(conn, dose))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_vaccination(
+    state: State<'_, DbState>,
+    vaccination: StudentVaccination,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_vaccination(conn, vaccination))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_vaccination(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_vaccination(conn, id))
+        .await
+}
+
+/// The student's vaccination card: scheduled doses with their status, then any other
+/// doses on record.
+#[tauri::command]
+pub async fn get_student_vaccinations(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Vec<StudentVaccination>, String> {
+    state
+        .read(move |conn| service::get_student_vaccinations(conn, student_id))
+        .await
+}
+
+/// Doses overdue or falling due within `within_days` (30 by default) of `as_of`.
+#[tauri::command]
+pub async fn get_vaccination_reminders(
+    state: State<'_, DbState>,
+    as_of: Option<String>,
+    within_days: Option<u32>,
+    class_id: Option<i64>,
+) -> Result<Vec<VaccinationReminder>, String> {
+    state
+        .read(move |conn| service::get_reminders(conn, as_of, within_days, class_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_infirmary_visit(
+    state: State<'_, DbState>,
+    visit: InfirmaryVisit,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_infirmary_visit(conn, visit))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_infirmary_visits(
+    state: State<'_, DbState>,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<InfirmaryVisit>, String> {
+    state
+        .read(move |conn| service::get_infirmary_visits(conn, student_id, from, to))
+        .await
+}
+
+/// Allergy and chronic-condition alerts for a class register, or for the whole school.
+#[tauri::command]
+pub async fn get_medical_alerts(
+    state: State<'_, DbState>,
+    class_id: Option<i64>,
+) -> Result<Vec<MedicalAlert>, String> {
+    state
+        .read(move |conn| service::get_medical_alerts(conn, class_id))
+        .await
+}
+
+/// Writes a class's printable attendance register for a month, with medical alerts under
+/// the students who have them, and returns its path.
+#[tauri::command]
+pub async fn export_attendance_register(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    class_id: i64,
+    year: i32,
+    month: u32,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_attendance_register_pdf(conn, class_id, year, month))
+        .await?;
+    let path = ensure_register_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
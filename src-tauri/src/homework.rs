@@ -0,0 +1,291 @@
+// Homework posted against a class and subject, its attachments and per-student submissions
+use crate::service::homework as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_homework_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Homework");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create homework dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Homework {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub class_id: i64,
+    /// Must be taught in the class.
+    pub subject_id: i64,
+    /// Staff id of the teacher setting it; defaults to the class's subject teacher.
+    pub teacher_id: Option<i64>,
+    pub title: String,
+    pub description: Option<String>,
+    /// Defaults to today.
+    pub assigned_on: Option<String>,
+    pub due_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teacher_name: Option<String>,
+    /// Uploaded separately; ignored when saving.
+    #[serde(default)]
+    pub attachments: Vec<HomeworkAttachment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HomeworkAttachment {
+    pub id: i64,
+    pub homework_id: i64,
+    /// Name of the stored file in the Homework folder.
+    pub file_name: String,
+    /// Name of the file as uploaded.
+    pub original_name: String,
+    pub uploaded_at: Option<String>,
+}
+
+/// A teacher's entry for one student. `status` is submitted, late or missing, or pending to
+/// clear the entry; when left out it follows from `submitted_on` and the due date.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionEntry {
+    pub student_id: i64,
+    pub status: Option<String>,
+    pub submitted_on: Option<String>,
+    pub grade: Option<String>,
+    pub remarks: Option<String>,
+}
+
+/// Where one student stands on a piece of homework. Students with nothing recorded are
+/// pending until the due date has passed and missing after it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HomeworkSubmission {
+    pub homework_id: i64,
+    pub student_id: i64,
+    pub student_name: String,
+    pub roll_number: Option<String>,
+    pub status: String,
+    pub submitted_on: Option<String>,
+    pub grade: Option<String>,
+    pub remarks: Option<String>,
+}
+
+/// Homework of a class falling due in one Monday-to-Sunday week.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HomeworkDigest {
+    pub class_id: i64,
+    pub class_name: String,
+    pub week_start: String,
+    pub week_end: String,
+    pub items: Vec<DigestItem>,
+    /// Students with missing work that week, most missed first.
+    pub students_missing: Vec<MissingWork>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestItem {
+    pub homework_id: i64,
+    pub subject_name: String,
+    pub title: String,
+    pub assigned_on: String,
+    pub due_date: String,
+    pub submitted: u32,
+    pub late: u32,
+    pub missing: u32,
+    pub pending: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MissingWork {
+    pub student_id: i64,
+    pub student_name: String,
+    pub titles: Vec<String>,
+}
+
+pub fn init_homework_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS homework (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            class_id INTEGER NOT NULL,
+            subject_id INTEGER NOT NULL,
+            teacher_id INTEGER,
+            title TEXT NOT NULL,
+            description TEXT,
+            assigned_on TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (class_id) REFERENCES classes(id) ON DELETE CASCADE,
+            FOREIGN KEY (subject_id) REFERENCES subjects(id),
+            FOREIGN KEY (teacher_id) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_homework_class_due ON homework(class_id, due_date)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS homework_attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            homework_id INTEGER NOT NULL,
+            file_name TEXT NOT NULL DEFAULT '',
+            original_name TEXT NOT NULL,
+            uploaded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (homework_id) REFERENCES homework(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS homework_submissions (
+            homework_id INTEGER NOT NULL,
+            student_id INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            submitted_on TEXT,
+            grade TEXT,
+            remarks TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (homework_id, student_id),
+            FOREIGN KEY (homework_id) REFERENCES homework(id) ON DELETE CASCADE,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Homework of the class, optionally only that due within `from..=to`, latest due first.
+#[tauri::command]
+pub async fn get_class_homework(
+    state: State<'_, DbState>,
+    class_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Homework>, String> {
+    state
+        .read(move |conn| service::get_class_homework(conn, class_id, from, to))
+        .await
+}
+
+/// Posts homework, or updates it when `homework.id` is set.
+#[tauri::command]
+pub async fn save_homework(state: State<'_, DbState>, homework: Homework) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_homework(conn, homework))
+        .await
+}
+
+/// Deletes the homework with its submissions and attachment files.
+#[tauri::command]
+pub async fn delete_homework(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<(), String> {
+    let files = state
+        .write(move |conn| service::delete_homework(conn, id))
+        .await?;
+    let dir = ensure_homework_dir(&app_handle)?;
+    for file in files {
+        let path = dir.join(&file);
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", file, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn upload_homework_attachment(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    homework_id: i64,
+    file_name: String,
+    file_bytes: Vec<u8>,
+) -> Result<HomeworkAttachment, String> {
+    let attachment = state
+        .write(move |conn| service::add_attachment(conn, homework_id, &file_name))
+        .await?;
+    let path = ensure_homework_dir(&app_handle)?.join(&attachment.file_name);
+    if let Err(e) = fs::write(&path, file_bytes) {
+        let id = attachment.id;
+        state
+            .write(move |conn| service::delete_attachment(conn, id))
+            .await?;
+        return Err(format!(
+            "Failed to write {}: {}",
+            attachment.original_name, e
+        ));
+    }
+    Ok(attachment)
+}
+
+#[tauri::command]
+pub async fn delete_homework_attachment(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<(), String> {
+    let file = state
+        .write(move |conn| service::delete_attachment(conn, id))
+        .await?;
+    let path = ensure_homework_dir(&app_handle)?.join(&file);
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", file, e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_homework_attachment_path(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<String, String> {
+    let attachment = state
+        .read(move |conn| service::get_attachment(conn, id))
+        .await?;
+    let path = ensure_homework_dir(&app_handle)?.join(attachment.file_name);
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Every student of the class with where they stand on the homework.
+#[tauri::command]
+pub async fn get_homework_submissions(
+    state: State<'_, DbState>,
+    homework_id: i64,
+) -> Result<Vec<HomeworkSubmission>, String> {
+    state
+        .read(move |conn| service::get_submissions(conn, homework_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_homework_submissions(
+    state: State<'_, DbState>,
+    homework_id: i64,
+    entries: Vec<SubmissionEntry>,
+) -> Result<Vec<HomeworkSubmission>, String> {
+    state
+        .write(move |conn| service::record_submissions(conn, homework_id, entries))
+        .await
+}
+
+/// Homework of the class due in the week containing `week_of` (default: this week).
+#[tauri::command]
+pub async fn get_homework_digest(
+    state: State<'_, DbState>,
+    class_id: i64,
+    week_of: Option<String>,
+) -> Result<HomeworkDigest, String> {
+    state
+        .read(move |conn| service::get_weekly_digest(conn, class_id, week_of))
+        .await
+}
@@ -0,0 +1,411 @@
+// Hostel: boarding houses, rooms and beds, allotment to students, movement and visitor registers
+use crate::service::hostel as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hostel {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    /// boys, girls or mixed; defaults to mixed.
+    pub hostel_type: Option<String>,
+    /// Staff id of the warden.
+    pub warden_id: Option<i64>,
+    /// Charged to each boarder whenever boarding fees are raised.
+    pub boarding_fee: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warden_name: Option<String>,
+}
+
+/// A room on a floor of a hostel. Beds are saved with the room.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostelRoom {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub hostel_id: i64,
+    /// 0 for the ground floor.
+    pub floor: i64,
+    pub room_number: String,
+    #[serde(default)]
+    pub beds: Vec<HostelBed>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostelBed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// e.g. A, B or Upper.
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BedAllotment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub bed_id: i64,
+    pub student_id: i64,
+    /// Defaults to today.
+    pub allotted_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacated_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacate_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostel_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostel_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub floor: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bed_label: Option<String>,
+}
+
+/// A boarder going out of the hostel and coming back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostelMovement {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    /// outing for the day, or leave for home.
+    pub movement_type: String,
+    /// YYYY-MM-DD HH:MM; defaults to now.
+    pub out_at: Option<String>,
+    /// YYYY-MM-DD HH:MM the boarder is due back.
+    pub expected_back: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub returned_at: Option<String>,
+    pub purpose: Option<String>,
+    /// Guardian or staff member the boarder went with.
+    pub escorted_by: Option<String>,
+    pub approved_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostelVisitor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    pub visitor_name: String,
+    pub relation: Option<String>,
+    pub phone: Option<String>,
+    /// Defaults to today.
+    pub visited_on: Option<String>,
+    /// HH:MM; defaults to now.
+    pub in_time: Option<String>,
+    pub out_time: Option<String>,
+    pub purpose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostelOccupancy {
+    pub hostel_id: i64,
+    pub name: String,
+    pub rooms: u32,
+    pub beds: u32,
+    pub occupied: u32,
+    pub vacant: u32,
+    /// Boarders out on an outing or leave right now.
+    pub out: u32,
+}
+
+/// Outcome of raising a period's boarding fees as student dues.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardingFeeRun {
+    pub raised: u32,
+    pub total: f64,
+    /// Boarders already charged for the period.
+    pub already_raised: u32,
+}
+
+pub fn init_hostel_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostels (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            hostel_type TEXT NOT NULL DEFAULT 'mixed',
+            warden_id INTEGER,
+            boarding_fee REAL NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (warden_id) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostel_rooms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            hostel_id INTEGER NOT NULL,
+            floor INTEGER NOT NULL,
+            room_number TEXT NOT NULL COLLATE NOCASE,
+            UNIQUE (hostel_id, room_number),
+            FOREIGN KEY (hostel_id) REFERENCES hostels(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostel_beds (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            room_id INTEGER NOT NULL,
+            label TEXT NOT NULL COLLATE NOCASE,
+            UNIQUE (room_id, label),
+            FOREIGN KEY (room_id) REFERENCES hostel_rooms(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostel_allotments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            bed_id INTEGER NOT NULL,
+            student_id INTEGER NOT NULL,
+            allotted_on TEXT NOT NULL,
+            vacated_on TEXT,
+            vacate_reason TEXT,
+            FOREIGN KEY (bed_id) REFERENCES hostel_beds(id),
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // One boarder per bed and one bed per boarder at a time.
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_hostel_allotments_bed
+         ON hostel_allotments(bed_id) WHERE vacated_on IS NULL",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_hostel_allotments_student
+         ON hostel_allotments(student_id) WHERE vacated_on IS NULL",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostel_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            movement_type TEXT NOT NULL,
+            out_at TEXT NOT NULL,
+            expected_back TEXT,
+            returned_at TEXT,
+            purpose TEXT,
+            escorted_by TEXT,
+            approved_by TEXT,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hostel_visitors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            visitor_name TEXT NOT NULL,
+            relation TEXT,
+            phone TEXT,
+            visited_on TEXT NOT NULL,
+            in_time TEXT NOT NULL,
+            out_time TEXT,
+            purpose TEXT,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_hostels(state: State<'_, DbState>) -> Result<Vec<Hostel>, String> {
+    state.read(service::get_hostels).await
+}
+
+/// Adds a hostel, or updates it when `hostel.id` is set.
+#[tauri::command]
+pub async fn save_hostel(state: State<'_, DbState>, hostel: Hostel) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_hostel(conn, hostel))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_hostel(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_hostel(conn, id))
+        .await
+}
+
+/// Rooms of the hostel by floor, with each bed's current boarder.
+#[tauri::command]
+pub async fn get_hostel_rooms(
+    state: State<'_, DbState>,
+    hostel_id: i64,
+) -> Result<Vec<HostelRoom>, String> {
+    state
+        .read(move |conn| service::get_rooms(conn, hostel_id))
+        .await
+}
+
+/// Saves a room and its beds. Beds left out are removed unless occupied.
+#[tauri::command]
+pub async fn save_hostel_room(
+    state: State<'_, DbState>,
+    room: HostelRoom,
+) -> Result<HostelRoom, String> {
+    state
+        .write(move |conn| service::save_room(conn, room))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_hostel_room(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_room(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn allot_hostel_bed(
+    state: State<'_, DbState>,
+    allotment: BedAllotment,
+) -> Result<BedAllotment, String> {
+    state
+        .write(move |conn| service::allot_bed(conn, allotment))
+        .await
+}
+
+#[tauri::command]
+pub async fn vacate_hostel_bed(
+    state: State<'_, DbState>,
+    student_id: i64,
+    vacated_on: Option<String>,
+    reason: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::vacate_bed(conn, student_id, vacated_on, reason))
+        .await
+}
+
+/// Current boarders of one hostel or all.
+#[tauri::command]
+pub async fn get_boarders(
+    state: State<'_, DbState>,
+    hostel_id: Option<i64>,
+) -> Result<Vec<BedAllotment>, String> {
+    state
+        .read(move |conn| service::get_boarders(conn, hostel_id))
+        .await
+}
+
+/// Beds the student has been allotted, latest first.
+#[tauri::command]
+pub async fn get_student_allotments(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Vec<BedAllotment>, String> {
+    state
+        .read(move |conn| service::get_student_allotments(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_hostel_occupancy(
+    state: State<'_, DbState>,
+) -> Result<Vec<HostelOccupancy>, String> {
+    state.read(service::get_occupancy).await
+}
+
+/// Signs a boarder out on an outing or leave.
+#[tauri::command]
+pub async fn record_hostel_checkout(
+    state: State<'_, DbState>,
+    movement: HostelMovement,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::check_out(conn, movement))
+        .await
+}
+
+/// Signs a boarder back in.
+#[tauri::command]
+pub async fn record_hostel_return(
+    state: State<'_, DbState>,
+    id: i64,
+    returned_at: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::check_in(conn, id, returned_at))
+        .await
+}
+
+/// Movements that began within `from..=to`, or of boarders still out when `out_only` is set.
+#[tauri::command]
+pub async fn get_hostel_movements(
+    state: State<'_, DbState>,
+    from: Option<String>,
+    to: Option<String>,
+    movement_type: Option<String>,
+    out_only: Option<bool>,
+) -> Result<Vec<HostelMovement>, String> {
+    state
+        .read(move |conn| {
+            service::get_movements(conn, from, to, movement_type, out_only.unwrap_or(false))
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn record_hostel_visitor(
+    state: State<'_, DbState>,
+    visitor: HostelVisitor,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_visitor(conn, visitor))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_hostel_visitor_exit(
+    state: State<'_, DbState>,
+    id: i64,
+    out_time: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::record_visitor_exit(conn, id, out_time))
+        .await
+}
+
+/// Visitors of one boarder, or everyone's within `from..=to`.
+#[tauri::command]
+pub async fn get_hostel_visitors(
+    state: State<'_, DbState>,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<HostelVisitor>, String> {
+    state
+        .read(move |conn| service::get_visitors(conn, student_id, from, to))
+        .await
+}
+
+/// Raises each boarder's hostel fee for `period` (e.g. "2024-25 Term 1") as a due.
+#[tauri::command]
+pub async fn raise_boarding_fees(
+    state: State<'_, DbState>,
+    period: String,
+    raised_on: Option<String>,
+) -> Result<BoardingFeeRun, String> {
+    state
+        .write(move |conn| service::raise_fees(conn, &period, raised_on))
+        .await
+}
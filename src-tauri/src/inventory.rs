@@ -0,0 +1,349 @@
+// Inventory: asset register, consumable stock, depreciation and yearly stock verification
+use crate::service::inventory as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_inventory_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Inventory");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create inventory dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Asset tag or stock code, unique.
+    pub code: String,
+    pub name: String,
+    /// furniture, lab, it, sports, stationery or other.
+    pub category: String,
+    /// asset or consumable. Only consumables are issued and reordered.
+    pub item_kind: String,
+    /// pieces, boxes, litres and so on.
+    pub unit: Option<String>,
+    pub purchase_date: Option<String>,
+    pub vendor: Option<String>,
+    pub invoice_number: Option<String>,
+    /// Total cost of the purchase, the basis for depreciation.
+    #[serde(default)]
+    pub purchase_cost: f64,
+    /// Room or place where the item is kept.
+    pub location: Option<String>,
+    /// Staff id of the person responsible for the item.
+    pub assigned_to: Option<i64>,
+    /// new, good, fair, poor, damaged, lost or disposed; defaults to good.
+    pub condition: Option<String>,
+    pub reorder_level: Option<u32>,
+    /// straight_line or written_down; none for items that are not depreciated.
+    pub depreciation_method: Option<String>,
+    pub useful_life_years: Option<u32>,
+    /// Yearly rate in percent, for written_down.
+    pub depreciation_rate: Option<f64>,
+    pub salvage_value: Option<f64>,
+    /// Units on hand. Taken as the opening receipt when the item is added; after that
+    /// it changes only through stock movements.
+    #[serde(default)]
+    pub quantity: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assigned_name: Option<String>,
+    #[serde(default)]
+    pub below_reorder: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockMovement {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub item_id: i64,
+    /// receipt, issue or adjustment.
+    pub movement_type: String,
+    /// Positive for receipts and issues; adjustments carry their sign.
+    pub quantity: i64,
+    pub moved_on: Option<String>,
+    /// Invoice, requisition or verification the movement came from.
+    pub reference: Option<String>,
+    /// Who the stock was issued to.
+    pub issued_to: Option<String>,
+    pub remarks: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_name: Option<String>,
+}
+
+/// One financial year (April to March) of an item's depreciation schedule.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepreciationRow {
+    /// 2024-25, for instance.
+    pub financial_year: String,
+    pub opening_value: f64,
+    pub depreciation: f64,
+    pub closing_value: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockVerification {
+    pub id: i64,
+    /// 2024-25, for instance.
+    pub label: String,
+    pub started_on: String,
+    pub completed_on: Option<String>,
+    pub verified_by: Option<String>,
+    /// open or closed.
+    pub status: String,
+    pub items: u32,
+    pub counted: u32,
+    pub discrepancies: u32,
+}
+
+/// An item as expected at the start of a verification and as found.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationLine {
+    pub item_id: i64,
+    pub code: String,
+    pub name: String,
+    pub category: String,
+    pub location: Option<String>,
+    pub expected_quantity: i64,
+    pub found_quantity: Option<i64>,
+    /// Condition noted during the count.
+    pub condition: Option<String>,
+    pub remarks: Option<String>,
+    /// Book value when the verification started.
+    #[serde(default)]
+    pub book_value: f64,
+    /// Book value of the units not found.
+    #[serde(default)]
+    pub shortfall_value: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub verification: StockVerification,
+    pub lines: Vec<VerificationLine>,
+    pub book_value: f64,
+    pub shortfall_value: f64,
+}
+
+pub fn init_inventory_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inventory_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            code TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            name TEXT NOT NULL,
+            category TEXT NOT NULL,
+            item_kind TEXT NOT NULL,
+            unit TEXT,
+            purchase_date TEXT,
+            vendor TEXT,
+            invoice_number TEXT,
+            purchase_cost REAL NOT NULL DEFAULT 0,
+            location TEXT,
+            assigned_to INTEGER,
+            condition TEXT NOT NULL DEFAULT 'good',
+            reorder_level INTEGER,
+            depreciation_method TEXT,
+            useful_life_years INTEGER,
+            depreciation_rate REAL,
+            salvage_value REAL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (assigned_to) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inventory_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            movement_type TEXT NOT NULL,
+            quantity INTEGER NOT NULL,
+            moved_on TEXT NOT NULL,
+            reference TEXT,
+            issued_to TEXT,
+            remarks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (item_id) REFERENCES inventory_items(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inventory_verifications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            label TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            started_on TEXT NOT NULL,
+            completed_on TEXT,
+            verified_by TEXT,
+            status TEXT NOT NULL DEFAULT 'open'
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inventory_verification_lines (
+            verification_id INTEGER NOT NULL,
+            item_id INTEGER NOT NULL,
+            expected_quantity INTEGER NOT NULL,
+            book_value REAL NOT NULL DEFAULT 0,
+            found_quantity INTEGER,
+            condition TEXT,
+            remarks TEXT,
+            PRIMARY KEY (verification_id, item_id),
+            FOREIGN KEY (verification_id) REFERENCES inventory_verifications(id) ON DELETE CASCADE,
+            FOREIGN KEY (item_id) REFERENCES inventory_items(id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Items, optionally of one category or kind, with the units on hand.
+#[tauri::command]
+pub async fn get_inventory_items(
+    state: State<'_, DbState>,
+    category: Option<String>,
+    item_kind: Option<String>,
+) -> Result<Vec<InventoryItem>, String> {
+    state
+        .read(move |conn| service::get_items(conn, category, item_kind))
+        .await
+}
+
+/// Adds an item, or updates it when `item.id` is set.
+#[tauri::command]
+pub async fn save_inventory_item(
+    state: State<'_, DbState>,
+    item: InventoryItem,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_item(conn, item))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_inventory_item(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_item(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_stock_movement(
+    state: State<'_, DbState>,
+    movement: StockMovement,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_movement(conn, movement))
+        .await
+}
+
+/// Movements of one item, latest first.
+#[tauri::command]
+pub async fn get_stock_movements(
+    state: State<'_, DbState>,
+    item_id: i64,
+) -> Result<Vec<StockMovement>, String> {
+    state
+        .read(move |conn| service::get_movements(conn, item_id))
+        .await
+}
+
+/// Consumables at or below their reorder level.
+#[tauri::command]
+pub async fn get_reorder_list(state: State<'_, DbState>) -> Result<Vec<InventoryItem>, String> {
+    state.read(service::get_reorder_list).await
+}
+
+#[tauri::command]
+pub async fn get_depreciation_schedule(
+    state: State<'_, DbState>,
+    item_id: i64,
+) -> Result<Vec<DepreciationRow>, String> {
+    state
+        .read(move |conn| service::get_depreciation_schedule(conn, item_id))
+        .await
+}
+
+/// Opens a verification, taking the units on hand of every item still in use as expected.
+#[tauri::command]
+pub async fn start_stock_verification(
+    state: State<'_, DbState>,
+    label: String,
+    verified_by: Option<String>,
+) -> Result<StockVerification, String> {
+    state
+        .write(move |conn| service::start_verification(conn, &label, verified_by))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_stock_verifications(
+    state: State<'_, DbState>,
+) -> Result<Vec<StockVerification>, String> {
+    state.read(service::get_verifications).await
+}
+
+/// Records what was found for one item of an open verification.
+#[tauri::command]
+pub async fn record_verification_count(
+    state: State<'_, DbState>,
+    verification_id: i64,
+    item_id: i64,
+    found_quantity: i64,
+    condition: Option<String>,
+    remarks: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| {
+            service::record_count(
+                conn,
+                verification_id,
+                item_id,
+                found_quantity,
+                condition,
+                remarks,
+            )
+        })
+        .await
+}
+
+/// Closes a fully counted verification and adjusts stock to what was found.
+#[tauri::command]
+pub async fn close_stock_verification(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<StockVerification, String> {
+    state
+        .write(move |conn| service::close_verification(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_stock_audit_report(
+    state: State<'_, DbState>,
+    verification_id: i64,
+) -> Result<AuditReport, String> {
+    state
+        .read(move |conn| service::get_audit_report(conn, verification_id))
+        .await
+}
+
+/// Writes the audit report of a verification and returns its path.
+#[tauri::command]
+pub async fn export_stock_audit_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    verification_id: i64,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_audit_pdf(conn, verification_id))
+        .await?;
+    let path = ensure_inventory_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,313 @@
+// Staff leave: leave types, yearly quotas, applications and balances
+use crate::service::leave as service;
+use crate::DbState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeaveType {
+    /// Key used everywhere else, e.g. `casual`.
+    pub leave_type: String,
+    /// Abbreviation shown on the muster roll, e.g. `CL`.
+    pub short_code: String,
+    pub name: String,
+    /// Unpaid leave is deducted from salary and is not limited by a quota.
+    pub paid: bool,
+    /// Whether weekly offs and holidays inside the leave period count as leave days (e.g.
+    /// maternity leave).
+    pub include_weekly_offs: bool,
+    /// Whether an unused balance moves to the next year.
+    pub carry_forward: bool,
+    /// Cap on the carried balance; `None` carries everything.
+    pub max_carry_forward: Option<f64>,
+}
+
+/// Days of one leave type granted each calendar year to staff of an employment type.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaveQuota {
+    pub employment_type: String,
+    pub leave_type: String,
+    pub days_per_year: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaveApplication {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub staff_id: i64,
+    pub leave_type: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// Only for single-day applications.
+    #[serde(default)]
+    pub half_day: bool,
+    /// Worked out from the dates when applying.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<f64>,
+    pub reason: Option<String>,
+    /// pending, approved, rejected or cancelled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision_note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staff_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaveBalance {
+    pub leave_type: String,
+    pub name: String,
+    pub paid: bool,
+    /// Carried forward from the previous year.
+    pub opening: f64,
+    pub quota: f64,
+    pub used: f64,
+    pub pending: f64,
+    /// `opening + quota - used`; `None` for unpaid leave, which has no limit.
+    pub available: Option<f64>,
+}
+
+/// (leave_type, short_code, name, paid, include_weekly_offs, carry_forward, max_carry_forward)
+type LeaveTypeSeed = (
+    &'static str,
+    &'static str,
+    &'static str,
+    bool,
+    bool,
+    bool,
+    Option<f64>,
+);
+
+const DEFAULT_LEAVE_TYPES: [LeaveTypeSeed; 5] = [
+    ("casual", "CL", "Casual Leave", true, false, false, None),
+    ("sick", "SL", "Sick Leave", true, false, true, Some(30.0)),
+    (
+        "earned",
+        "EL",
+        "Earned Leave",
+        true,
+        false,
+        true,
+        Some(300.0),
+    ),
+    (
+        "maternity",
+        "ML",
+        "Maternity Leave",
+        true,
+        true,
+        false,
+        None,
+    ),
+    (
+        "unpaid",
+        "LWP",
+        "Leave Without Pay",
+        false,
+        false,
+        false,
+        None,
+    ),
+];
+
+const DEFAULT_QUOTAS: [(&str, &str, f64); 10] = [
+    ("Full-time", "casual", 12.0),
+    ("Full-time", "sick", 10.0),
+    ("Full-time", "earned", 15.0),
+    ("Full-time", "maternity", 182.0),
+    ("Part-time", "casual", 6.0),
+    ("Part-time", "sick", 5.0),
+    ("Contract", "casual", 8.0),
+    ("Contract", "sick", 6.0),
+    ("Temporary", "casual", 4.0),
+    ("Temporary", "sick", 3.0),
+];
+
+pub fn init_leave_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leave_types (
+            leave_type TEXT PRIMARY KEY,
+            short_code TEXT NOT NULL,
+            name TEXT NOT NULL,
+            paid BOOLEAN NOT NULL DEFAULT 1,
+            include_weekly_offs BOOLEAN NOT NULL DEFAULT 0,
+            carry_forward BOOLEAN NOT NULL DEFAULT 0,
+            max_carry_forward REAL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leave_quotas (
+            employment_type TEXT NOT NULL COLLATE NOCASE,
+            leave_type TEXT NOT NULL,
+            days_per_year REAL NOT NULL,
+            PRIMARY KEY (employment_type, leave_type),
+            FOREIGN KEY (leave_type) REFERENCES leave_types(leave_type) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leave_applications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            staff_id INTEGER NOT NULL,
+            leave_type TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            half_day BOOLEAN NOT NULL DEFAULT 0,
+            days REAL NOT NULL,
+            reason TEXT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            decided_by TEXT,
+            decided_on TEXT,
+            decision_note TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE,
+            FOREIGN KEY (leave_type) REFERENCES leave_types(leave_type)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_leave_applications_staff
+         ON leave_applications(staff_id, start_date)",
+        [],
+    )?;
+    // Opening balances carried forward from the previous year
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS leave_balances (
+            staff_id INTEGER NOT NULL,
+            leave_type TEXT NOT NULL,
+            year INTEGER NOT NULL,
+            opening REAL NOT NULL DEFAULT 0,
+            PRIMARY KEY (staff_id, leave_type, year),
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE,
+            FOREIGN KEY (leave_type) REFERENCES leave_types(leave_type) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    for (leave_type, short_code, name, paid, weekly_offs, carry, max_carry) in DEFAULT_LEAVE_TYPES {
+        conn.execute(
+            "INSERT OR IGNORE INTO leave_types (
+                leave_type, short_code, name, paid, include_weekly_offs, carry_forward, max_carry_forward
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![leave_type, short_code, name, paid, weekly_offs, carry, max_carry],
+        )?;
+    }
+    for (employment_type, leave_type, days) in DEFAULT_QUOTAS {
+        conn.execute(
+            "INSERT OR IGNORE INTO leave_quotas (employment_type, leave_type, days_per_year)
+             VALUES (?1, ?2, ?3)",
+            params![employment_type, leave_type, days],
+        )?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_leave_types(state: State<'_, DbState>) -> Result<Vec<LeaveType>, String> {
+    state.read(service::get_leave_types).await
+}
+
+#[tauri::command]
+pub async fn update_leave_type(
+    state: State<'_, DbState>,
+    leave_type: LeaveType,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_leave_type(conn, leave_type))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_leave_quotas(state: State<'_, DbState>) -> Result<Vec<LeaveQuota>, String> {
+    state.read(service::get_leave_quotas).await
+}
+
+#[tauri::command]
+pub async fn set_leave_quota(state: State<'_, DbState>, quota: LeaveQuota) -> Result<(), String> {
+    state
+        .write(move |conn| service::set_leave_quota(conn, quota))
+        .await
+}
+
+#[tauri::command]
+pub async fn apply_leave(
+    state: State<'_, DbState>,
+    application: LeaveApplication,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::apply_leave(conn, application))
+        .await
+}
+
+#[tauri::command]
+pub async fn approve_leave(
+    state: State<'_, DbState>,
+    id: i64,
+    decided_by: Option<String>,
+    note: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::approve_leave(conn, id, decided_by, note))
+        .await
+}
+
+#[tauri::command]
+pub async fn reject_leave(
+    state: State<'_, DbState>,
+    id: i64,
+    decided_by: Option<String>,
+    note: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::reject_leave(conn, id, decided_by, note))
+        .await
+}
+
+#[tauri::command]
+pub async fn cancel_leave(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::cancel_leave(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_leave_applications(
+    state: State<'_, DbState>,
+    staff_id: Option<i64>,
+    status: Option<String>,
+) -> Result<Vec<LeaveApplication>, String> {
+    state
+        .read(move |conn| service::get_leave_applications(conn, staff_id, status.as_deref()))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_leave_balances(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    year: i32,
+) -> Result<Vec<LeaveBalance>, String> {
+    state
+        .read(move |conn| service::get_leave_balances(conn, staff_id, year))
+        .await
+}
+
+/// Closes `from_year`: unused balances of carry-forward leave types become the next year's opening.
+#[tauri::command]
+pub async fn carry_forward_leave(
+    state: State<'_, DbState>,
+    from_year: i32,
+) -> Result<usize, String> {
+    state
+        .write(move |conn| service::carry_forward_leave(conn, from_year))
+        .await
+}
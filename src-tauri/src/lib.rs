@@ -3,23 +3,51 @@ mod db;
 mod enquiry;
 mod image;
 mod migration;
+mod repository;
 mod school;
+mod service;
 mod staff;
 mod students; 
 mod idcard; 
 mod academic_year; 
+mod guardian;
+mod certificate;
+mod lifecycle;
+mod pdf;
+mod xlsx;
+mod date;
+mod leave;
+mod staff_attendance;
+mod payroll;
+mod subject;
+mod timetable;
+mod calendar;
+mod messaging;
+mod communication;
+mod homework;
+mod dues;
+mod library;
+mod transport;
+mod hostel;
+mod inventory;
+mod visitor;
+mod discipline;
+mod health;
+mod dashboard;
+mod report;
+mod statutory;
+#[cfg(test)]
+mod test_support;
 
 use db::establish_connection;
 use log::error;
 use migration::run_migrations;
-use rusqlite::Connection;
 use std::fs;
-use std::sync::Mutex;
+use std::sync::Arc;
 use tauri::Manager;
 use tauri::Runtime;
 
-// Database state that will be shared across the application
-pub struct DbState(pub Mutex<Connection>);
+pub use db::DbState;
 
 #[tauri::command]
 async fn read_file_content(path: String) -> Result<Vec<u8>, String> {
@@ -42,23 +70,24 @@ pub fn run() {
                 )?;
             }
 
-            // Initialize database and store connection in state
-            let conn = match establish_connection(app.handle()) {
-                Ok(conn) => {
+            // Initialize database and store the connection pool in state
+            let pool = match establish_connection(app.handle()) {
+                Ok(pool) => {
                     // Run migrations
-                    if let Err(e) = run_migrations(&conn) {
+                    if let Err(e) = run_migrations(&pool.writer()) {
                         error!("Failed to run migrations: {}", e);
-                        return Err(e.into());
+                        return Err(e);
                     }
-                    conn
+                    pool
                 }
                 Err(e) => {
                     error!("Failed to establish database connection: {}", e);
-                    return Err(e.into());
+                    return Err(e);
                 }
             };
 
-            app.manage(DbState(std::sync::Mutex::new(conn)));
+            app.manage(DbState(Arc::new(pool)));
+            app.manage(dashboard::DashboardCache::default());
 
             Ok(())
         })
@@ -90,6 +119,14 @@ pub fn run() {
             class::toggle_class_status,
             class::initialize_default_classes,
             class::check_and_initialize_default_classes_once,
+            class::get_grades,
+            class::set_class_capacity,
+            class::get_class_waitlist,
+            class::admit_from_waitlist,
+            class::remove_from_waitlist,
+            class::get_class_templates,
+            class::save_class_template,
+            class::delete_class_template,
             // Staff commands
             staff::create_staff,
             staff::get_staff,
@@ -119,6 +156,252 @@ pub fn run() {
              academic_year::get_all_academic_years,
              academic_year::set_current_academic_year,
              academic_year::delete_academic_year,
+            academic_year::close_academic_year,
+            academic_year::grant_academic_year_override,
+            academic_year::end_academic_year_override,
+            academic_year::get_academic_year_overrides,
+            // Guardian commands
+            guardian::create_guardian,
+            guardian::get_guardian,
+            guardian::search_guardians,
+            guardian::update_guardian,
+            guardian::delete_guardian,
+            guardian::link_guardian,
+            guardian::unlink_guardian,
+            guardian::set_primary_guardian,
+            guardian::get_student_guardians,
+            guardian::get_student_family,
+            // Certificate commands
+            certificate::get_certificate_templates,
+            certificate::update_certificate_template,
+            certificate::issue_certificate,
+            certificate::issue_duplicate_certificate,
+            certificate::get_issued_certificates,
+            certificate::get_certificate_file_path,
+            // Student lifecycle commands
+            lifecycle::change_student_status,
+            lifecycle::get_student_status_history,
+            lifecycle::get_alumni,
+            lifecycle::update_alumni,
+            // Staff attendance commands
+            staff_attendance::staff_check_in,
+            staff_attendance::staff_check_out,
+            staff_attendance::mark_staff_attendance,
+            staff_attendance::get_staff_attendance_register,
+            staff_attendance::get_staff_muster_roll,
+            // Leave commands
+            leave::get_leave_types,
+            leave::update_leave_type,
+            leave::get_leave_quotas,
+            leave::set_leave_quota,
+            leave::apply_leave,
+            leave::approve_leave,
+            leave::reject_leave,
+            leave::cancel_leave,
+            leave::get_leave_applications,
+            leave::get_leave_balances,
+            leave::carry_forward_leave,
+            // Payroll commands
+            payroll::save_salary_structure,
+            payroll::get_salary_structures,
+            payroll::delete_salary_structure,
+            payroll::get_staff_pay_details,
+            payroll::save_staff_pay_details,
+            payroll::run_payroll,
+            payroll::finalize_payroll_run,
+            payroll::get_payroll_runs,
+            payroll::get_payslips,
+            payroll::export_payslip_pdf,
+            payroll::export_run_payslips_pdf,
+            payroll::export_bank_transfer_csv,
+            payroll::get_statutory_summary,
+            // Subject commands
+            subject::create_subject,
+            subject::get_subjects,
+            subject::update_subject,
+            subject::delete_subject,
+            subject::assign_class_subject,
+            subject::remove_class_subject,
+            subject::get_class_subjects,
+            subject::set_subject_teacher,
+            subject::set_class_teacher,
+            subject::get_class_teachers,
+            subject::set_teacher_max_periods,
+            subject::get_teacher_workload,
+            subject::copy_class_subjects,
+            // Timetable commands
+            timetable::get_timetable_settings,
+            timetable::save_timetable_settings,
+            timetable::get_bell_schedule,
+            timetable::save_bell_schedule,
+            timetable::get_rooms,
+            timetable::create_room,
+            timetable::update_room,
+            timetable::delete_room,
+            timetable::set_timetable_entry,
+            timetable::clear_timetable_entry,
+            timetable::get_class_timetable,
+            timetable::get_teacher_timetable,
+            timetable::get_timetable_clashes,
+            timetable::generate_timetable,
+            timetable::export_class_timetable_pdf,
+            timetable::export_teacher_timetable_pdf,
+            timetable::get_substitution_suggestions,
+            timetable::assign_substitute,
+            // Calendar commands
+            calendar::get_calendar_settings,
+            calendar::save_calendar_settings,
+            calendar::get_academic_terms,
+            calendar::save_academic_term,
+            calendar::delete_academic_term,
+            calendar::get_calendar_entries,
+            calendar::save_calendar_entry,
+            calendar::delete_calendar_entry,
+            calendar::get_working_days,
+            calendar::export_academic_calendar_ics,
+            // Communication commands
+            communication::get_communication_settings,
+            communication::save_communication_settings,
+            communication::get_message_templates,
+            communication::save_message_template,
+            communication::delete_message_template,
+            communication::compose_message,
+            communication::get_messages,
+            communication::get_message_recipients,
+            communication::get_notice_board,
+            communication::delete_message,
+            communication::send_message,
+            communication::export_whatsapp_message,
+            // Homework commands
+            homework::get_class_homework,
+            homework::save_homework,
+            homework::delete_homework,
+            homework::upload_homework_attachment,
+            homework::delete_homework_attachment,
+            homework::get_homework_attachment_path,
+            homework::get_homework_submissions,
+            homework::record_homework_submissions,
+            homework::get_homework_digest,
+            // Dues commands
+            dues::get_student_dues,
+            dues::get_open_dues,
+            dues::add_student_due,
+            dues::settle_student_due,
+            // Library commands
+            library::get_library_settings,
+            library::save_library_settings,
+            library::get_library_books,
+            library::save_library_book,
+            library::delete_library_book,
+            library::get_book_copies,
+            library::add_book_copy,
+            library::withdraw_book_copy,
+            library::issue_library_book,
+            library::return_library_book,
+            library::renew_library_book,
+            library::get_member_loans,
+            library::get_overdue_loans,
+            library::get_most_borrowed_books,
+            // Transport commands
+            transport::get_vehicles,
+            transport::save_vehicle,
+            transport::delete_vehicle,
+            transport::get_vehicle_alerts,
+            transport::get_transport_routes,
+            transport::save_transport_route,
+            transport::delete_transport_route,
+            transport::get_fare_slabs,
+            transport::save_fare_slabs,
+            transport::allocate_student_transport,
+            transport::remove_student_transport,
+            transport::get_student_transport,
+            transport::get_route_students,
+            transport::export_route_list_pdf,
+            transport::raise_transport_fees,
+            // Hostel commands
+            hostel::get_hostels,
+            hostel::save_hostel,
+            hostel::delete_hostel,
+            hostel::get_hostel_rooms,
+            hostel::save_hostel_room,
+            hostel::delete_hostel_room,
+            hostel::allot_hostel_bed,
+            hostel::vacate_hostel_bed,
+            hostel::get_boarders,
+            hostel::get_student_allotments,
+            hostel::get_hostel_occupancy,
+            hostel::record_hostel_checkout,
+            hostel::record_hostel_return,
+            hostel::get_hostel_movements,
+            hostel::record_hostel_visitor,
+            hostel::record_hostel_visitor_exit,
+            hostel::get_hostel_visitors,
+            hostel::raise_boarding_fees,
+            // Inventory commands
+            inventory::get_inventory_items,
+            inventory::save_inventory_item,
+            inventory::delete_inventory_item,
+            inventory::record_stock_movement,
+            inventory::get_stock_movements,
+            inventory::get_reorder_list,
+            inventory::get_depreciation_schedule,
+            inventory::start_stock_verification,
+            inventory::get_stock_verifications,
+            inventory::record_verification_count,
+            inventory::close_stock_verification,
+            inventory::get_stock_audit_report,
+            inventory::export_stock_audit_pdf,
+            // Visitor and gate pass commands
+            visitor::record_visitor,
+            visitor::record_visitor_exit,
+            visitor::search_visitors,
+            visitor::issue_gate_pass,
+            visitor::get_gate_passes,
+            visitor::get_daily_gate_register,
+            visitor::export_daily_gate_register_pdf,
+            // Discipline commands
+            discipline::save_discipline_incident,
+            discipline::delete_discipline_incident,
+            discipline::get_discipline_incidents,
+            discipline::acknowledge_discipline_incident,
+            discipline::record_parent_meeting,
+            discipline::get_discipline_timeline,
+            // Health commands
+            health::save_health_checkup,
+            health::delete_health_checkup,
+            health::get_growth_history,
+            health::get_vaccine_schedule,
+            health::save_vaccine_schedule_dose,
+            health::record_vaccination,
+            health::delete_vaccination,
+            health::get_student_vaccinations,
+            health::get_vaccination_reminders,
+            health::record_infirmary_visit,
+            health::get_infirmary_visits,
+            health::get_medical_alerts,
+            health::export_attendance_register,
+            // Dashboard commands
+            dashboard::get_dashboard_stats,
+            // Report builder commands
+            report::get_report_schema,
+            report::run_report,
+            report::get_saved_reports,
+            report::save_report,
+            report::delete_saved_report,
+            report::run_saved_report,
+            report::export_report,
+            report::export_saved_report,
+            // Statutory return commands
+            statutory::get_student_statutory_details,
+            statutory::save_student_statutory_details,
+            statutory::get_staff_statutory_details,
+            statutory::save_staff_statutory_details,
+            statutory::get_udise_enrolment,
+            statutory::get_udise_teacher_profiles,
+            statutory::get_board_candidates,
+            statutory::validate_statutory_export,
+            statutory::export_statutory_validation,
+            statutory::export_statutory_return,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,299 @@
+// Library: catalogue of books and their copies, circulation to students and staff, fines
+use crate::service::library as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Loan rules. Fines accrue per day a book is kept past its due date.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibrarySettings {
+    pub student_loan_days: u32,
+    pub staff_loan_days: u32,
+    pub student_max_books: u32,
+    pub staff_max_books: u32,
+    pub max_renewals: u32,
+    pub fine_per_day: f64,
+    /// Raise students' fines as dues when the book comes back.
+    pub post_fines_to_dues: bool,
+}
+
+/// A title in the catalogue. Each physical copy has its own accession number.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryBook {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// ISBN-10 or ISBN-13; hyphens and spaces are dropped.
+    pub isbn: Option<String>,
+    pub title: String,
+    pub author: String,
+    pub publisher: Option<String>,
+    pub category: Option<String>,
+    pub shelf_location: Option<String>,
+    /// Copies in circulation, i.e. not withdrawn.
+    #[serde(default)]
+    pub copies: u32,
+    #[serde(default)]
+    pub available: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookCopy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub book_id: i64,
+    pub accession_number: String,
+    /// Printed barcode, when it differs from the accession number.
+    pub barcode: Option<String>,
+    /// available, issued or withdrawn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryLoan {
+    pub id: i64,
+    pub copy_id: i64,
+    pub book_id: i64,
+    pub title: String,
+    pub accession_number: String,
+    /// student or staff.
+    pub member_type: String,
+    pub member_id: i64,
+    pub member_name: String,
+    pub issued_on: String,
+    pub due_date: String,
+    pub renewals: u32,
+    pub returned_on: Option<String>,
+    /// Charged on return; in the overdue report, the fine accrued so far.
+    pub fine_amount: f64,
+    /// The student due the fine was raised as.
+    pub fine_due_id: Option<i64>,
+    /// Days past the due date on return, or as of the report date for open loans.
+    pub days_overdue: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BorrowCount {
+    pub book_id: i64,
+    pub title: String,
+    pub author: String,
+    pub loans: u32,
+}
+
+pub fn init_library_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS library_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            student_loan_days INTEGER NOT NULL DEFAULT 14,
+            staff_loan_days INTEGER NOT NULL DEFAULT 30,
+            student_max_books INTEGER NOT NULL DEFAULT 2,
+            staff_max_books INTEGER NOT NULL DEFAULT 5,
+            max_renewals INTEGER NOT NULL DEFAULT 2,
+            fine_per_day REAL NOT NULL DEFAULT 1,
+            post_fines_to_dues BOOLEAN NOT NULL DEFAULT 1
+        )",
+        [],
+    )?;
+    conn.execute("INSERT OR IGNORE INTO library_settings (id) VALUES (1)", [])?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS library_books (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            isbn TEXT,
+            title TEXT NOT NULL,
+            author TEXT NOT NULL,
+            publisher TEXT,
+            category TEXT,
+            shelf_location TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS library_copies (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            book_id INTEGER NOT NULL,
+            accession_number TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            barcode TEXT UNIQUE,
+            status TEXT NOT NULL DEFAULT 'available',
+            added_on DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (book_id) REFERENCES library_books(id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS library_loans (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            copy_id INTEGER NOT NULL,
+            member_type TEXT NOT NULL,
+            member_id INTEGER NOT NULL,
+            issued_on TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            renewals INTEGER NOT NULL DEFAULT 0,
+            returned_on TEXT,
+            fine_amount REAL NOT NULL DEFAULT 0,
+            fine_due_id INTEGER,
+            FOREIGN KEY (copy_id) REFERENCES library_copies(id),
+            FOREIGN KEY (fine_due_id) REFERENCES student_dues(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_library_loans_member
+         ON library_loans(member_type, member_id, returned_on)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_library_loans_open
+         ON library_loans(copy_id) WHERE returned_on IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_library_settings(state: State<'_, DbState>) -> Result<LibrarySettings, String> {
+    state.read(service::get_settings).await
+}
+
+#[tauri::command]
+pub async fn save_library_settings(
+    state: State<'_, DbState>,
+    settings: LibrarySettings,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_settings(conn, settings))
+        .await
+}
+
+/// Catalogue, optionally filtered by title, author, ISBN or accession number.
+#[tauri::command]
+pub async fn get_library_books(
+    state: State<'_, DbState>,
+    query: Option<String>,
+) -> Result<Vec<LibraryBook>, String> {
+    state
+        .read(move |conn| service::get_books(conn, query))
+        .await
+}
+
+/// Adds a title to the catalogue, or updates it when `book.id` is set.
+#[tauri::command]
+pub async fn save_library_book(
+    state: State<'_, DbState>,
+    book: LibraryBook,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_book(conn, book))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_library_book(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_book(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_book_copies(
+    state: State<'_, DbState>,
+    book_id: i64,
+) -> Result<Vec<BookCopy>, String> {
+    state
+        .read(move |conn| service::get_copies(conn, book_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn add_book_copy(state: State<'_, DbState>, copy: BookCopy) -> Result<i64, String> {
+    state.write(move |conn| service::add_copy(conn, copy)).await
+}
+
+/// Takes a copy out of circulation (lost, damaged, discarded).
+#[tauri::command]
+pub async fn withdraw_book_copy(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::withdraw_copy(conn, id))
+        .await
+}
+
+/// Issues the copy with this barcode or accession number.
+#[tauri::command]
+pub async fn issue_library_book(
+    state: State<'_, DbState>,
+    code: String,
+    member_type: String,
+    member_id: i64,
+    issued_on: Option<String>,
+) -> Result<LibraryLoan, String> {
+    state
+        .write(move |conn| service::issue_book(conn, &code, &member_type, member_id, issued_on))
+        .await
+}
+
+#[tauri::command]
+pub async fn return_library_book(
+    state: State<'_, DbState>,
+    code: String,
+    returned_on: Option<String>,
+) -> Result<LibraryLoan, String> {
+    state
+        .write(move |conn| service::return_book(conn, &code, returned_on))
+        .await
+}
+
+#[tauri::command]
+pub async fn renew_library_book(
+    state: State<'_, DbState>,
+    code: String,
+) -> Result<LibraryLoan, String> {
+    state
+        .write(move |conn| service::renew_book(conn, &code))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_member_loans(
+    state: State<'_, DbState>,
+    member_type: String,
+    member_id: i64,
+    include_returned: Option<bool>,
+) -> Result<Vec<LibraryLoan>, String> {
+    state
+        .read(move |conn| {
+            service::get_member_loans(
+                conn,
+                &member_type,
+                member_id,
+                include_returned.unwrap_or(false),
+            )
+        })
+        .await
+}
+
+/// Books out past their due date as of `as_of` (default: today).
+#[tauri::command]
+pub async fn get_overdue_loans(
+    state: State<'_, DbState>,
+    as_of: Option<String>,
+) -> Result<Vec<LibraryLoan>, String> {
+    state
+        .read(move |conn| service::get_overdue(conn, as_of))
+        .await
+}
+
+/// Titles issued most often between `from` and `to`.
+#[tauri::command]
+pub async fn get_most_borrowed_books(
+    state: State<'_, DbState>,
+    from: String,
+    to: String,
+    limit: Option<u32>,
+) -> Result<Vec<BorrowCount>, String> {
+    state
+        .read(move |conn| service::get_most_borrowed(conn, &from, &to, limit))
+        .await
+}
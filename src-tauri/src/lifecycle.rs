@@ -0,0 +1,135 @@
+// Student lifecycle: status transitions, their history and the alumni directory
+use crate::service::lifecycle as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// A requested move of a student to another lifecycle status.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusChange {
+    /// One of applicant, active, suspended, transferred, left, graduated or alumni.
+    pub status: String,
+    /// Defaults to today.
+    pub effective_date: Option<String>,
+    /// Required when suspending, transferring or marking a student as left.
+    pub reason: Option<String>,
+    /// Used when graduating; defaults to the year of the effective date.
+    pub passing_year: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusHistoryEntry {
+    pub id: i64,
+    pub student_id: i64,
+    /// `None` for the first status a student was given.
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub effective_date: String,
+    pub reason: Option<String>,
+    pub recorded_at: Option<String>,
+}
+
+/// Alumni directory entry, created when a student graduates.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlumniRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Cleared if the student record is later deleted; the entry itself is kept.
+    pub student_id: Option<i64>,
+    pub full_name: String,
+    pub gr_number: String,
+    pub final_class: Option<String>,
+    pub passing_year: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub current_occupation: Option<String>,
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+pub fn init_lifecycle_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS student_status_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            from_status TEXT,
+            to_status TEXT NOT NULL,
+            effective_date TEXT NOT NULL,
+            reason TEXT,
+            recorded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_student_status_history_student
+         ON student_status_history(student_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alumni (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER UNIQUE,
+            full_name TEXT NOT NULL,
+            gr_number TEXT NOT NULL,
+            final_class TEXT,
+            passing_year TEXT NOT NULL,
+            email TEXT,
+            phone TEXT,
+            address TEXT,
+            current_occupation TEXT,
+            notes TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn change_student_status(
+    state: State<'_, DbState>,
+    student_id: i64,
+    change: StatusChange,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::change_status(conn, student_id, change))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_student_status_history(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Vec<StatusHistoryEntry>, String> {
+    state
+        .read(move |conn| service::get_status_history(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_alumni(
+    state: State<'_, DbState>,
+    passing_year: Option<String>,
+    query: Option<String>,
+) -> Result<Vec<AlumniRecord>, String> {
+    state
+        .read(move |conn| service::get_alumni(conn, passing_year.as_deref(), query.as_deref()))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_alumni(
+    state: State<'_, DbState>,
+    id: i64,
+    alumni: AlumniRecord,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_alumni(conn, id, alumni))
+        .await
+}
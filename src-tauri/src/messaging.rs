@@ -0,0 +1,230 @@
+// Outbound message transports: SMTP for email through lettre, and an HTTPS gateway for SMS
+// through ureq. Both verify the server's certificate with rustls' bundled roots.
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::PoolConfig;
+use lettre::{Address, Message, SmtpTransport, Transport};
+use std::net::IpAddr;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the SMTP connection is secured: STARTTLS after connecting, TLS from the first byte,
+/// or not at all.
+pub const SMTP_SECURITY: [&str; 3] = ["starttls", "tls", "none"];
+
+/// One rendered message for one recipient.
+pub struct Outgoing<'a> {
+    /// Email address or phone number, depending on the channel.
+    pub to: &'a str,
+    pub name: &'a str,
+    pub subject: &'a str,
+    pub body: &'a str,
+}
+
+/// A way of getting a message to a recipient. `send` returns whatever acknowledgement the
+/// far end gave (queue id, gateway response) so it can be kept with the delivery.
+pub trait Channel {
+    fn send(&mut self, message: &Outgoing) -> Result<String, String>;
+}
+
+/// Whether `host` names this machine, so nothing sent to it crosses the network.
+pub fn is_loopback(host: &str) -> bool {
+    let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Refuses an SMTP login over an unencrypted connection to another machine, where the
+/// password would cross the network readable by anyone on the way.
+pub fn check_smtp_login(host: &str, security: &str, username: Option<&str>) -> Result<(), String> {
+    if security == "none" && username.is_some_and(|u| !u.is_empty()) && !is_loopback(host) {
+        return Err(format!(
+            "Cannot log in to {} without TLS. Choose STARTTLS or TLS, or use a relay on this \
+             machine",
+            host
+        ));
+    }
+    Ok(())
+}
+
+/// Drops CR and LF so user text cannot start a new header.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ").trim().to_string()
+}
+
+pub fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !value.contains(|c: char| c.is_whitespace() || "<>(),;:\"[]\\".contains(c))
+}
+
+fn mailbox(name: &str, address: &str) -> Result<Mailbox, String> {
+    let address: Address = address
+        .parse()
+        .map_err(|_| format!("'{}' is not an email address", address))?;
+    let name = single_line(name);
+    Ok(Mailbox::new((!name.is_empty()).then_some(name), address))
+}
+
+/// Email over SMTP, logging in when a username is set. One transport serves a whole batch,
+/// so every copy goes through the same session unless the server drops it.
+pub struct SmtpChannel {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpChannel {
+    /// `security` is one of [`SMTP_SECURITY`]. Nothing is sent until the first message.
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: &str,
+        username: Option<String>,
+        password: Option<String>,
+        from: &str,
+        from_name: Option<&str>,
+    ) -> Result<Self, String> {
+        check_smtp_login(host, security, username.as_deref())?;
+        let builder = match security {
+            "tls" => SmtpTransport::relay(host),
+            "starttls" => SmtpTransport::starttls_relay(host),
+            _ => Ok(SmtpTransport::builder_dangerous(host)),
+        }
+        .map_err(|e| format!("Cannot use {} as the SMTP server: {}", host, e))?;
+        let mut builder = builder
+            .port(port)
+            .timeout(Some(IO_TIMEOUT))
+            .pool_config(PoolConfig::new().max_size(1));
+        if let Some(username) = username.filter(|u| !u.is_empty()) {
+            builder = builder.credentials(Credentials::new(username, password.unwrap_or_default()));
+        }
+        Ok(SmtpChannel {
+            transport: builder.build(),
+            from: mailbox(from_name.unwrap_or(""), from)?,
+        })
+    }
+}
+
+impl Channel for SmtpChannel {
+    fn send(&mut self, message: &Outgoing) -> Result<String, String> {
+        if !is_email(message.to) {
+            return Err(format!("'{}' is not an email address", message.to));
+        }
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(mailbox(message.name, message.to)?)
+            .subject(single_line(message.subject))
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.replace("\r\n", "\n"))
+            .map_err(|e| format!("Cannot build the email: {}", e))?;
+        let response = self
+            .transport
+            .send(&email)
+            .map_err(|e| format!("SMTP delivery failed: {}", e))?;
+        Ok(response.message().collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, for query strings.
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Accepts an `https://` gateway URL, or `http://` to a relay on this machine. The request
+/// carries the API key, numbers and text, so it must not cross the network in the clear.
+pub fn check_gateway_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    let (secure, rest) = match url.get(..8) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("https://") => (true, &url[8..]),
+        _ => match url.get(..7) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("http://") => (false, &url[7..]),
+            _ => return Err("The SMS gateway URL must start with https://".to_string()),
+        },
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(""),
+        None => host_port.split(':').next().unwrap_or(""),
+    };
+    if host.is_empty() {
+        return Err("The gateway URL has no host".to_string());
+    }
+    if !secure && !is_loopback(host) {
+        return Err(format!(
+            "Cannot send the API key and messages to {} unencrypted. Use the gateway's \
+             https:// URL",
+            host
+        ));
+    }
+    Ok(())
+}
+
+/// SMS through an HTTP gateway called with GET. The URL is a template whose `{to}`,
+/// `{message}`, `{sender}` and `{api_key}` placeholders are filled in per message, which
+/// covers the query-string APIs most bulk SMS providers offer.
+pub struct SmsGatewayChannel {
+    agent: ureq::Agent,
+    url_template: String,
+    api_key: String,
+    sender_id: String,
+}
+
+impl SmsGatewayChannel {
+    pub fn new(url_template: String, api_key: String, sender_id: String) -> Self {
+        SmsGatewayChannel {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(CONNECT_TIMEOUT)
+                .timeout(IO_TIMEOUT)
+                .user_agent("CampusSync")
+                .build(),
+            url_template,
+            api_key,
+            sender_id,
+        }
+    }
+}
+
+/// The start of a gateway reply, enough to show what it said.
+fn reply_text(response: ureq::Response) -> String {
+    let body = response.into_string().unwrap_or_default();
+    body.trim().chars().take(200).collect()
+}
+
+impl Channel for SmsGatewayChannel {
+    fn send(&mut self, message: &Outgoing) -> Result<String, String> {
+        let url = self
+            .url_template
+            .replace("{to}", &url_encode(message.to))
+            .replace("{message}", &url_encode(message.body))
+            .replace("{sender}", &url_encode(&self.sender_id))
+            .replace("{api_key}", &url_encode(&self.api_key));
+        check_gateway_url(&url)?;
+        match self.agent.get(&url).call() {
+            Ok(response) => Ok(reply_text(response)),
+            Err(ureq::Error::Status(status, response)) => Err(format!(
+                "SMS gateway answered {}: {}",
+                status,
+                reply_text(response)
+            )),
+            Err(ureq::Error::Transport(e)) => Err(format!("SMS gateway request failed: {}", e)),
+        }
+    }
+}
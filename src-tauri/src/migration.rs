@@ -2,14 +2,59 @@ use rusqlite::Connection;
 
 pub fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     // Enable foreign key support
-    conn.pragma_update(None, "foreign_keys", &"ON")?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
     
     // Run all table initialization functions
     crate::enquiry::init_enquiry_tables(conn)?;
     crate::school::init_school_table(conn)?;
     crate::class::init_class_table(conn)?;
+    crate::service::class::seed_class_templates(conn)?;
     crate::staff::init_staff_table(conn)?;
     crate::students::init_student_table(conn)?;
+    crate::service::class::migrate_class_layout(conn)?;
     crate::academic_year::init_academic_year_table(conn)?;
+    crate::guardian::init_guardian_tables(conn)?;
+    crate::service::guardian::migrate_inline_guardians(conn)?;
+    crate::certificate::init_certificate_tables(conn)?;
+    crate::lifecycle::init_lifecycle_tables(conn)?;
+    crate::service::lifecycle::migrate_legacy_statuses(conn)?;
+    crate::staff_attendance::init_staff_attendance_table(conn)?;
+    crate::leave::init_leave_tables(conn)?;
+    crate::payroll::init_payroll_tables(conn)?;
+    crate::subject::init_subject_tables(conn)?;
+    crate::timetable::init_timetable_tables(conn)?;
+    crate::calendar::init_calendar_tables(conn)?;
+    crate::communication::init_communication_tables(conn)?;
+    crate::homework::init_homework_tables(conn)?;
+    crate::dues::init_dues_table(conn)?;
+    crate::library::init_library_tables(conn)?;
+    crate::transport::init_transport_tables(conn)?;
+    crate::hostel::init_hostel_tables(conn)?;
+    crate::inventory::init_inventory_tables(conn)?;
+    crate::visitor::init_visitor_tables(conn)?;
+    crate::discipline::init_discipline_tables(conn)?;
+    crate::health::init_health_tables(conn)?;
+    crate::report::init_report_tables(conn)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Adds `column` to an existing table unless an earlier run already did.
+pub fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,377 @@
+// Staff payroll: salary structures, monthly runs, payslips and bank/statutory exports
+use crate::service::payroll as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_payroll_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Payroll");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create payroll dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Monthly salary of one staff member from `effective_from` until the next revision.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SalaryStructure {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub staff_id: i64,
+    pub effective_from: String,
+    pub basic: f64,
+    /// Dearness allowance.
+    pub da: f64,
+    /// House rent allowance.
+    pub hra: f64,
+    pub other_allowances: f64,
+    /// Provident fund: 12% of basic + DA, on wages up to the statutory ceiling.
+    pub pf_applicable: bool,
+    /// Employees' State Insurance, deducted only while gross pay is within the ESI limit.
+    pub esi_applicable: bool,
+    /// Monthly professional tax, which differs by state.
+    pub professional_tax: f64,
+    pub other_deductions: f64,
+    pub remarks: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// Bank and statutory identifiers used on payslips and in exports.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StaffPayDetails {
+    pub staff_id: i64,
+    pub bank_name: Option<String>,
+    pub account_number: Option<String>,
+    pub ifsc_code: Option<String>,
+    pub pan_number: Option<String>,
+    /// PF universal account number.
+    pub uan_number: Option<String>,
+    pub esi_number: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayrollRun {
+    pub id: i64,
+    pub year: i32,
+    pub month: u32,
+    /// draft or finalized. Draft runs can be recalculated; finalized ones are locked.
+    pub status: String,
+    pub staff_count: i64,
+    pub total_gross: f64,
+    pub total_deductions: f64,
+    pub total_net: f64,
+    pub created_at: Option<String>,
+    pub finalized_at: Option<String>,
+}
+
+/// Result of calculating a run: staff without a salary structure for the month are skipped.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayrollRunOutcome {
+    pub run: PayrollRun,
+    pub skipped_staff: Vec<String>,
+}
+
+/// One staff member's pay for a run. Staff and bank details are copied in so the payslip
+/// stays as issued.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Payslip {
+    pub id: i64,
+    pub run_id: i64,
+    pub staff_id: i64,
+    pub staff_name: String,
+    pub designation: String,
+    pub department: String,
+    pub days_in_month: f64,
+    /// Absent days and leave without pay.
+    pub unpaid_days: f64,
+    pub paid_days: f64,
+    pub basic: f64,
+    pub da: f64,
+    pub hra: f64,
+    pub other_allowances: f64,
+    pub gross: f64,
+    pub pf_wages: f64,
+    pub employee_pf: f64,
+    pub employer_pf: f64,
+    pub esi_wages: f64,
+    pub employee_esi: f64,
+    pub employer_esi: f64,
+    pub professional_tax: f64,
+    pub other_deductions: f64,
+    pub total_deductions: f64,
+    pub net_pay: f64,
+    pub bank_name: Option<String>,
+    pub account_number: Option<String>,
+    pub ifsc_code: Option<String>,
+    pub pan_number: Option<String>,
+    pub uan_number: Option<String>,
+    pub esi_number: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BankTransferExport {
+    pub path: String,
+    pub count: usize,
+    pub total: f64,
+    /// Staff left out of the file because their account number or IFSC is missing.
+    pub missing_bank_details: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatutoryRow {
+    pub staff_id: i64,
+    pub staff_name: String,
+    pub uan_number: Option<String>,
+    pub esi_number: Option<String>,
+    pub pf_wages: f64,
+    pub employee_pf: f64,
+    pub employer_pf: f64,
+    pub esi_wages: f64,
+    pub employee_esi: f64,
+    pub employer_esi: f64,
+    pub professional_tax: f64,
+}
+
+/// PF, ESI and professional tax due for a month's run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatutorySummary {
+    pub run_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub rows: Vec<StatutoryRow>,
+    pub total_pf: f64,
+    pub total_esi: f64,
+    pub total_professional_tax: f64,
+}
+
+pub fn init_payroll_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS salary_structures (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            staff_id INTEGER NOT NULL,
+            effective_from TEXT NOT NULL,
+            basic REAL NOT NULL DEFAULT 0,
+            da REAL NOT NULL DEFAULT 0,
+            hra REAL NOT NULL DEFAULT 0,
+            other_allowances REAL NOT NULL DEFAULT 0,
+            pf_applicable BOOLEAN NOT NULL DEFAULT 1,
+            esi_applicable BOOLEAN NOT NULL DEFAULT 1,
+            professional_tax REAL NOT NULL DEFAULT 0,
+            other_deductions REAL NOT NULL DEFAULT 0,
+            remarks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (staff_id, effective_from),
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS staff_pay_details (
+            staff_id INTEGER PRIMARY KEY,
+            bank_name TEXT,
+            account_number TEXT,
+            ifsc_code TEXT,
+            pan_number TEXT,
+            uan_number TEXT,
+            esi_number TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS payroll_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            year INTEGER NOT NULL,
+            month INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'draft',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            finalized_at DATETIME,
+            UNIQUE (year, month)
+        )",
+        [],
+    )?;
+    // Staff id is kept without a foreign key so payslips outlive deleted staff records.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS payslips (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL,
+            staff_id INTEGER NOT NULL,
+            staff_name TEXT NOT NULL,
+            designation TEXT NOT NULL,
+            department TEXT NOT NULL,
+            days_in_month REAL NOT NULL,
+            unpaid_days REAL NOT NULL,
+            paid_days REAL NOT NULL,
+            basic REAL NOT NULL,
+            da REAL NOT NULL,
+            hra REAL NOT NULL,
+            other_allowances REAL NOT NULL,
+            gross REAL NOT NULL,
+            pf_wages REAL NOT NULL,
+            employee_pf REAL NOT NULL,
+            employer_pf REAL NOT NULL,
+            esi_wages REAL NOT NULL,
+            employee_esi REAL NOT NULL,
+            employer_esi REAL NOT NULL,
+            professional_tax REAL NOT NULL,
+            other_deductions REAL NOT NULL,
+            total_deductions REAL NOT NULL,
+            net_pay REAL NOT NULL,
+            bank_name TEXT,
+            account_number TEXT,
+            ifsc_code TEXT,
+            pan_number TEXT,
+            uan_number TEXT,
+            esi_number TEXT,
+            UNIQUE (run_id, staff_id),
+            FOREIGN KEY (run_id) REFERENCES payroll_runs(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_salary_structure(
+    state: State<'_, DbState>,
+    structure: SalaryStructure,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_salary_structure(conn, structure))
+        .await
+}
+
+/// Every revision for the staff member, latest first.
+#[tauri::command]
+pub async fn get_salary_structures(
+    state: State<'_, DbState>,
+    staff_id: i64,
+) -> Result<Vec<SalaryStructure>, String> {
+    state
+        .read(move |conn| service::get_salary_structures(conn, staff_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_salary_structure(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_salary_structure(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_staff_pay_details(
+    state: State<'_, DbState>,
+    staff_id: i64,
+) -> Result<Option<StaffPayDetails>, String> {
+    state
+        .read(move |conn| service::get_pay_details(conn, staff_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn save_staff_pay_details(
+    state: State<'_, DbState>,
+    details: StaffPayDetails,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_pay_details(conn, details))
+        .await
+}
+
+/// Calculates (or recalculates, while still a draft) the payroll for a month.
+#[tauri::command]
+pub async fn run_payroll(
+    state: State<'_, DbState>,
+    year: i32,
+    month: u32,
+) -> Result<PayrollRunOutcome, String> {
+    state
+        .write(move |conn| service::run_payroll(conn, year, month))
+        .await
+}
+
+#[tauri::command]
+pub async fn finalize_payroll_run(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::finalize_run(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_payroll_runs(state: State<'_, DbState>) -> Result<Vec<PayrollRun>, String> {
+    state.read(service::get_runs).await
+}
+
+#[tauri::command]
+pub async fn get_payslips(state: State<'_, DbState>, run_id: i64) -> Result<Vec<Payslip>, String> {
+    state
+        .read(move |conn| service::get_payslips(conn, run_id))
+        .await
+}
+
+/// Writes one staff member's payslip PDF and returns its path.
+#[tauri::command]
+pub async fn export_payslip_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    payslip_id: i64,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_payslip_pdf(conn, payslip_id))
+        .await?;
+    write_payroll_file(&app_handle, &file_name, &pdf)
+}
+
+/// Writes every payslip of a run into one PDF, a page each, for printing.
+#[tauri::command]
+pub async fn export_run_payslips_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    run_id: i64,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_run_payslips_pdf(conn, run_id))
+        .await?;
+    write_payroll_file(&app_handle, &file_name, &pdf)
+}
+
+#[tauri::command]
+pub async fn export_bank_transfer_csv(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    run_id: i64,
+) -> Result<BankTransferExport, String> {
+    let (file_name, csv, mut export) = state
+        .read(move |conn| service::bank_transfer_csv(conn, run_id))
+        .await?;
+    export.path = write_payroll_file(&app_handle, &file_name, csv.as_bytes())?;
+    Ok(export)
+}
+
+#[tauri::command]
+pub async fn get_statutory_summary(
+    state: State<'_, DbState>,
+    run_id: i64,
+) -> Result<StatutorySummary, String> {
+    state
+        .read(move |conn| service::get_statutory_summary(conn, run_id))
+        .await
+}
+
+fn write_payroll_file(
+    app_handle: &AppHandle,
+    file_name: &str,
+    contents: &[u8],
+) -> Result<String, String> {
+    let path = ensure_payroll_dir(app_handle)?.join(file_name);
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,350 @@
+// Minimal PDF writer for printable documents (certificates, payslips, reports).
+// Uses the built-in Helvetica fonts, so nothing has to be embedded.
+use std::fmt::Write as _;
+
+pub const A4_WIDTH: f32 = 595.0;
+pub const A4_HEIGHT: f32 = 842.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// Glyph widths (1/1000 em) of Helvetica and Helvetica-Bold for ASCII 32..=126.
+const REGULAR_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Width of `text` in points when set in `font` at `size`.
+pub fn text_width(text: &str, size: f32, font: Font) -> f32 {
+    let widths = match font {
+        Font::Regular => &REGULAR_WIDTHS,
+        Font::Bold => &BOLD_WIDTHS,
+    };
+    let units: u32 = text
+        .chars()
+        .map(|c| match c as u32 {
+            code @ 32..=126 => widths[(code - 32) as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+/// Breaks `text` into lines no wider than `max_width`, keeping explicit line breaks.
+pub fn wrap(text: &str, size: f32, font: Font, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && text_width(&candidate, size, font) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Encodes text as a PDF string literal in WinAnsi, replacing what it cannot represent.
+fn encode_text(text: &str, out: &mut Vec<u8>) {
+    out.push(b'(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(c as u8);
+            }
+            ' '..='~' => out.push(c as u8),
+            '\u{a0}'..='\u{ff}' => out.push(c as u32 as u8),
+            '\u{20b9}' => out.extend_from_slice(b"Rs."),
+            '\t' => out.push(b' '),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+}
+
+pub struct Page {
+    width: f32,
+    height: f32,
+    content: Vec<u8>,
+}
+
+impl Page {
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Draws a single line of text with its baseline at `y`, measured from the bottom of the page.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, font: Font, text: &str) {
+        let _ = write!(
+            BytesWriter(&mut self.content),
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ",
+            font.resource(),
+            size,
+            x,
+            y
+        );
+        encode_text(text, &mut self.content);
+        self.content.extend_from_slice(b" Tj ET\n");
+    }
+
+    /// Draws text anchored at `x` on its left edge, centre or right edge.
+    pub fn text_aligned(
+        &mut self,
+        x: f32,
+        y: f32,
+        size: f32,
+        font: Font,
+        align: Align,
+        text: &str,
+    ) {
+        let width = text_width(text, size, font);
+        let left = match align {
+            Align::Left => x,
+            Align::Center => x - width / 2.0,
+            Align::Right => x - width,
+        };
+        self.text(left, y, size, font, text);
+    }
+
+    /// Draws wrapped text starting at `y` and returns the baseline below the last line.
+    #[allow(clippy::too_many_arguments)]
+    pub fn paragraph(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        size: f32,
+        font: Font,
+        leading: f32,
+        text: &str,
+    ) -> f32 {
+        let mut y = y;
+        for line in wrap(text, size, font, width) {
+            self.text(x, y, size, font, &line);
+            y -= leading;
+        }
+        y
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32) {
+        let _ = writeln!(
+            BytesWriter(&mut self.content),
+            "{:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            thickness,
+            x1,
+            y1,
+            x2,
+            y2
+        );
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, thickness: f32) {
+        let _ = writeln!(
+            BytesWriter(&mut self.content),
+            "{:.2} w {:.2} {:.2} {:.2} {:.2} re S",
+            thickness,
+            x,
+            y,
+            width,
+            height
+        );
+    }
+}
+
+/// Lets `write!` append formatted text straight into a byte buffer.
+struct BytesWriter<'a>(&'a mut Vec<u8>);
+
+impl std::fmt::Write for BytesWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+pub struct Document {
+    title: String,
+    pages: Vec<Page>,
+}
+
+impl Document {
+    pub fn new(title: &str) -> Self {
+        Document {
+            title: title.to_string(),
+            pages: Vec::new(),
+        }
+    }
+
+    /// Starts a new A4 portrait page and returns it for drawing.
+    pub fn add_page(&mut self) -> &mut Page {
+        self.add_page_sized(A4_WIDTH, A4_HEIGHT)
+    }
+
+    pub fn add_page_sized(&mut self, width: f32, height: f32) -> &mut Page {
+        self.pages.push(Page {
+            width,
+            height,
+            content: Vec::new(),
+        });
+        self.pages.last_mut().expect("page was just added")
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Object numbers: 1 catalog, 2 page tree, 3-4 fonts, 5 info, then a page and its content per page.
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 6 + i * 2).collect();
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .into_bytes(),
+        );
+        for base_font in ["Helvetica", "Helvetica-Bold"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    base_font
+                )
+                .into_bytes(),
+            );
+        }
+        let mut info = b"<< /Producer (CampusSync) /Title ".to_vec();
+        encode_text(&self.title, &mut info);
+        info.extend_from_slice(b" >>");
+        objects.push(info);
+
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.0} {:.0}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    page.width,
+                    page.height,
+                    id + 1
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            stream.extend_from_slice(&page.content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        );
+        out.extend_from_slice(xref.as_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_respects_width_and_line_breaks() {
+        let lines = wrap("one two three four\nfive", 10.0, Font::Regular, 40.0);
+        assert!(lines.len() > 2);
+        assert_eq!(lines.last().map(String::as_str), Some("five"));
+        for line in &lines {
+            assert!(text_width(line, 10.0, Font::Regular) <= 40.0 || !line.contains(' '));
+        }
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).rposition(|w| w == needle)
+    }
+
+    #[test]
+    fn xref_offsets_point_at_objects() {
+        let mut doc = Document::new("Test (1)");
+        let page = doc.add_page();
+        page.text_aligned(
+            A4_WIDTH / 2.0,
+            800.0,
+            14.0,
+            Font::Bold,
+            Align::Center,
+            "Hello (world)",
+        );
+        page.line(40.0, 790.0, 555.0, 790.0, 1.0);
+        let bytes = doc.to_bytes();
+
+        assert!(bytes.starts_with(b"%PDF-1.4"));
+        assert!(find(&bytes, b"(Hello \\(world\\)) Tj").is_some());
+
+        let startxref = find(&bytes, b"startxref\n").unwrap() + b"startxref\n".len();
+        let tail = String::from_utf8(bytes[startxref..].to_vec()).unwrap();
+        let xref_at: usize = tail.lines().next().unwrap().parse().unwrap();
+        assert!(bytes[xref_at..].starts_with(b"xref"));
+
+        let table = String::from_utf8(bytes[xref_at..].to_vec()).unwrap();
+        let entry = table.lines().nth(4).unwrap();
+        let offset: usize = entry[..10].parse().unwrap();
+        assert!(bytes[offset..].starts_with(b"2 0 obj"));
+    }
+}
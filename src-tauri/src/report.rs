@@ -0,0 +1,204 @@
+// Reports: ad-hoc lists built from a whitelisted schema, saved and re-run on demand
+use crate::service::report as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_reports_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Reports");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create reports dir: {}", e))?;
+    Ok(dir)
+}
+
+/// What to list: fields of one entity from `get_report_schema`, narrowed by filters and
+/// optionally grouped with aggregates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    /// students, staff, enquiries or dues.
+    pub entity: String,
+    /// Field keys to show. When grouping, every column must also be grouped; leave empty
+    /// to show the grouped fields.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// All must hold.
+    #[serde(default)]
+    pub filters: Vec<ReportFilter>,
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    pub aggregates: Vec<ReportAggregate>,
+    #[serde(default)]
+    pub order_by: Vec<ReportOrder>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportFilter {
+    pub field: String,
+    /// One of the operators the schema lists for the field.
+    pub op: String,
+    /// A single value, or a list for `in`, `not_in` and `between`; omitted for `is_blank`
+    /// and `is_not_blank`.
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportAggregate {
+    /// count, sum, avg, min or max.
+    pub function: String,
+    /// Not needed for count.
+    pub field: Option<String>,
+    /// Column heading; defaults to e.g. "Sum of Amount".
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportOrder {
+    /// Key of an output column: a field, or an aggregate such as `count` or `sum_amount`.
+    pub key: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: ReportDefinition,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportColumn {
+    pub key: String,
+    pub label: String,
+    /// text, number or date.
+    pub kind: String,
+}
+
+/// A report ready for the grid: one value per column in each row.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportResult {
+    pub columns: Vec<ReportColumn>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportSchemaField {
+    pub key: String,
+    pub label: String,
+    pub kind: String,
+    pub operators: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportSchemaEntity {
+    pub key: String,
+    pub label: String,
+    pub fields: Vec<ReportSchemaField>,
+}
+
+pub fn init_report_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS saved_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            description TEXT,
+            definition TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Entities, fields and filter operators a report definition may use.
+#[tauri::command]
+pub async fn get_report_schema() -> Result<Vec<ReportSchemaEntity>, String> {
+    Ok(service::get_schema())
+}
+
+#[tauri::command]
+pub async fn run_report(
+    state: State<'_, DbState>,
+    definition: ReportDefinition,
+) -> Result<ReportResult, String> {
+    state
+        .read(move |conn| service::run_report(conn, &definition))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_saved_reports(state: State<'_, DbState>) -> Result<Vec<SavedReport>, String> {
+    state.read(service::get_saved_reports).await
+}
+
+/// Saves a definition under a unique name, or updates it when `report.id` is set.
+#[tauri::command]
+pub async fn save_report(state: State<'_, DbState>, report: SavedReport) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_report(conn, report))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_saved_report(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_report(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn run_saved_report(state: State<'_, DbState>, id: i64) -> Result<ReportResult, String> {
+    state
+        .read(move |conn| service::run_saved_report(conn, id))
+        .await
+}
+
+/// Writes a report as csv, xlsx or pdf and returns its path.
+#[tauri::command]
+pub async fn export_report(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    definition: ReportDefinition,
+    title: String,
+    format: String,
+) -> Result<String, String> {
+    let (file_name, bytes) = state
+        .read(move |conn| service::render_report(conn, &definition, &title, &format))
+        .await?;
+    let path = ensure_reports_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Writes a saved report as csv, xlsx or pdf, titled with its name, and returns its path.
+#[tauri::command]
+pub async fn export_saved_report(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    id: i64,
+    format: String,
+) -> Result<String, String> {
+    let (file_name, bytes) = state
+        .read(move |conn| service::render_saved_report(conn, id, &format))
+        .await?;
+    let path = ensure_reports_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
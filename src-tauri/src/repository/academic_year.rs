@@ -0,0 +1,214 @@
+use crate::academic_year::{AcademicYear, YearOverride};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+fn map_academic_year(row: &Row) -> rusqlite::Result<AcademicYear> {
+    Ok(AcademicYear {
+        id: Some(row.get(0)?),
+        academic_year: row.get(1)?,
+        status: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        start_date: row.get(5)?,
+        end_date: row.get(6)?,
+        closed_at: row.get(7)?,
+        closed_by: row.get(8)?,
+    })
+}
+
+pub fn find_id_by_label(conn: &Connection, label: &str) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM academic_years WHERE academic_year = ?1",
+        params![label],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn exists(conn: &Connection, id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM academic_years WHERE id = ?1)",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<AcademicYear>> {
+    conn.query_row(
+        "SELECT id, academic_year, status, created_at, updated_at, start_date, end_date,
+                closed_at, closed_by
+         FROM academic_years WHERE id = ?1",
+        params![id],
+        map_academic_year,
+    )
+    .optional()
+}
+
+/// Another dated year sharing at least one day with `start..=end`, by label.
+pub fn find_overlapping(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT academic_year FROM academic_years
+         WHERE start_date <= ?2 AND end_date >= ?1 AND id != COALESCE(?3, -1)
+         LIMIT 1",
+        params![start, end, exclude_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn set_dates(conn: &Connection, id: i64, start: &str, end: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE academic_years SET start_date = ?1, end_date = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?3",
+        params![start, end, id],
+    )?;
+    Ok(())
+}
+
+pub fn insert(conn: &Connection, label: &str, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO academic_years (academic_year, status) VALUES (?1, ?2)",
+        params![label, status],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn touch(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE academic_years SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+pub fn find_current(conn: &Connection) -> rusqlite::Result<Option<AcademicYear>> {
+    conn.query_row(
+        "SELECT id, academic_year, status, created_at, updated_at, start_date, end_date,
+                closed_at, closed_by
+         FROM academic_years
+         WHERE status = 'active'
+         ORDER BY created_at DESC
+         LIMIT 1",
+        [],
+        map_academic_year,
+    )
+    .optional()
+}
+
+pub fn find_all(conn: &Connection) -> rusqlite::Result<Vec<AcademicYear>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, academic_year, status, created_at, updated_at, start_date, end_date,
+                closed_at, closed_by
+         FROM academic_years
+         ORDER BY created_at DESC",
+    )?;
+    let years = stmt
+        .query_map([], map_academic_year)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(years)
+}
+
+/// Marks `id` as the only active year.
+pub fn set_current(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+    conn.execute("UPDATE academic_years SET status = 'inactive'", [])?;
+    conn.execute(
+        "UPDATE academic_years SET
+            status = 'active',
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM academic_years WHERE id = ?1", params![id])
+}
+
+pub fn close(conn: &Connection, id: i64, closed_by: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE academic_years SET closed_at = CURRENT_TIMESTAMP, closed_by = ?1,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?2 AND closed_at IS NULL",
+        params![closed_by, id],
+    )
+}
+
+/// Whether an override lets the closed year be changed on `date`.
+pub fn has_open_override(conn: &Connection, id: i64, date: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM academic_year_overrides
+         WHERE academic_year_id = ?1 AND ended_at IS NULL AND valid_until >= ?2)",
+        params![id, date],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_override(conn: &Connection, year_override: &YearOverride) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO academic_year_overrides (academic_year_id, granted_by, reason, valid_until)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            year_override.academic_year_id,
+            year_override.granted_by,
+            year_override.reason,
+            year_override.valid_until
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn end_override(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE academic_year_overrides SET ended_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND ended_at IS NULL",
+        params![id],
+    )
+}
+
+pub fn find_overrides(conn: &Connection, id: i64) -> rusqlite::Result<Vec<YearOverride>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, academic_year_id, granted_by, reason, valid_until, granted_at, ended_at
+         FROM academic_year_overrides
+         WHERE academic_year_id = ?1
+         ORDER BY granted_at DESC, id DESC",
+    )?;
+    let overrides = stmt
+        .query_map(params![id], |row| {
+            Ok(YearOverride {
+                id: Some(row.get(0)?),
+                academic_year_id: row.get(1)?,
+                granted_by: row.get(2)?,
+                reason: row.get(3)?,
+                valid_until: row.get(4)?,
+                granted_at: row.get(5)?,
+                ended_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(overrides)
+}
+
+/// Records that refer to the year, as `(what, count)`, for those with any.
+pub fn count_dependents(conn: &Connection, id: i64) -> rusqlite::Result<Vec<(&'static str, i64)>> {
+    let tables = [
+        ("classes", "SELECT COUNT(*) FROM classes WHERE academic_years = ?1"),
+        ("class subjects", "SELECT COUNT(*) FROM class_subjects WHERE academic_year_id = ?1"),
+        ("timetable periods", "SELECT COUNT(*) FROM timetable_entries WHERE academic_year_id = ?1"),
+        ("certificates", "SELECT COUNT(*) FROM issued_certificates WHERE academic_year_id = ?1"),
+        ("terms", "SELECT COUNT(*) FROM academic_terms WHERE academic_year_id = ?1"),
+        ("calendar entries", "SELECT COUNT(*) FROM calendar_entries WHERE academic_year_id = ?1"),
+    ];
+    let mut dependents = Vec::new();
+    for (what, query) in tables {
+        let count: i64 = conn.query_row(query, params![id], |row| row.get(0))?;
+        if count > 0 {
+            dependents.push((what, count));
+        }
+    }
+    Ok(dependents)
+}
@@ -0,0 +1,239 @@
+use crate::calendar::{CalendarEntry, CalendarSettings, Term};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub fn find_settings(conn: &Connection) -> rusqlite::Result<CalendarSettings> {
+    conn.query_row(
+        "SELECT weekly_off FROM calendar_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(CalendarSettings {
+                weekly_off: row.get(0)?,
+            })
+        },
+    )
+}
+
+pub fn save_settings(conn: &Connection, settings: &CalendarSettings) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE calendar_settings SET weekly_off = ?1 WHERE id = 1",
+        params![settings.weekly_off],
+    )?;
+    Ok(())
+}
+
+fn map_term(row: &Row) -> rusqlite::Result<Term> {
+    Ok(Term {
+        id: Some(row.get(0)?),
+        academic_year_id: row.get(1)?,
+        name: row.get(2)?,
+        start_date: row.get(3)?,
+        end_date: row.get(4)?,
+    })
+}
+
+pub fn find_terms(conn: &Connection, academic_year_id: i64) -> rusqlite::Result<Vec<Term>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, academic_year_id, name, start_date, end_date FROM academic_terms
+         WHERE academic_year_id = ?1
+         ORDER BY start_date",
+    )?;
+    let terms = stmt
+        .query_map(params![academic_year_id], map_term)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(terms)
+}
+
+/// Academic year of the term.
+pub fn find_term_year(conn: &Connection, id: i64) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT academic_year_id FROM academic_terms WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Another term of the year sharing a day with `start..=end`, by name.
+pub fn find_overlapping_term(
+    conn: &Connection,
+    academic_year_id: i64,
+    start: &str,
+    end: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT name FROM academic_terms
+         WHERE academic_year_id = ?1 AND start_date <= ?3 AND end_date >= ?2
+           AND id != COALESCE(?4, -1)
+         LIMIT 1",
+        params![academic_year_id, start, end, exclude_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn term_name_taken(
+    conn: &Connection,
+    academic_year_id: i64,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM academic_terms
+         WHERE academic_year_id = ?1 AND name = ?2 COLLATE NOCASE AND id != COALESCE(?3, -1))",
+        params![academic_year_id, name, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_term(conn: &Connection, term: &Term) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO academic_terms (academic_year_id, name, start_date, end_date)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            term.academic_year_id,
+            term.name,
+            term.start_date,
+            term.end_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_term(conn: &Connection, id: i64, term: &Term) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE academic_terms SET academic_year_id = ?1, name = ?2, start_date = ?3, end_date = ?4
+         WHERE id = ?5",
+        params![
+            term.academic_year_id,
+            term.name,
+            term.start_date,
+            term.end_date,
+            id
+        ],
+    )
+}
+
+pub fn delete_term(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM academic_terms WHERE id = ?1", params![id])
+}
+
+fn map_entry(row: &Row) -> rusqlite::Result<CalendarEntry> {
+    Ok(CalendarEntry {
+        id: Some(row.get(0)?),
+        academic_year_id: row.get(1)?,
+        kind: row.get(2)?,
+        title: row.get(3)?,
+        start_date: row.get(4)?,
+        end_date: Some(row.get(5)?),
+        day_part: Some(row.get(6)?),
+        description: row.get(7)?,
+    })
+}
+
+const SELECT_ENTRIES: &str = "SELECT id, academic_year_id, kind, title, start_date, end_date,
+        day_part, description
+     FROM calendar_entries";
+
+/// Entries of the year touching `from..=to`, in date order.
+pub fn find_entries(
+    conn: &Connection,
+    academic_year_id: i64,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<CalendarEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE academic_year_id = ?1 AND start_date <= ?3 AND end_date >= ?2
+         ORDER BY start_date, id",
+        SELECT_ENTRIES
+    ))?;
+    let entries = stmt
+        .query_map(params![academic_year_id, from, to], map_entry)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+/// Holidays of any year touching `from..=to`.
+pub fn find_holidays_between(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<CalendarEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE kind = 'holiday' AND start_date <= ?2 AND end_date >= ?1 ORDER BY start_date",
+        SELECT_ENTRIES
+    ))?;
+    let entries = stmt
+        .query_map(params![from, to], map_entry)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+/// Academic year of the calendar entry.
+pub fn find_entry_year(conn: &Connection, id: i64) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT academic_year_id FROM calendar_entries WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn insert_entry(conn: &Connection, entry: &CalendarEntry) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO calendar_entries
+            (academic_year_id, kind, title, start_date, end_date, day_part, description)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            entry.academic_year_id,
+            entry.kind,
+            entry.title,
+            entry.start_date,
+            entry.end_date,
+            entry.day_part,
+            entry.description
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_entry(conn: &Connection, id: i64, entry: &CalendarEntry) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE calendar_entries SET
+            academic_year_id = ?1, kind = ?2, title = ?3, start_date = ?4, end_date = ?5,
+            day_part = ?6, description = ?7
+         WHERE id = ?8",
+        params![
+            entry.academic_year_id,
+            entry.kind,
+            entry.title,
+            entry.start_date,
+            entry.end_date,
+            entry.day_part,
+            entry.description,
+            id
+        ],
+    )
+}
+
+pub fn delete_entry(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM calendar_entries WHERE id = ?1", params![id])
+}
+
+/// Terms and entries of the year falling outside `start..=end`.
+pub fn count_outside(
+    conn: &Connection,
+    academic_year_id: i64,
+    start: &str,
+    end: &str,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT
+            (SELECT COUNT(*) FROM academic_terms
+             WHERE academic_year_id = ?1 AND (start_date < ?2 OR end_date > ?3))
+          + (SELECT COUNT(*) FROM calendar_entries
+             WHERE academic_year_id = ?1 AND (start_date < ?2 OR end_date > ?3))",
+        params![academic_year_id, start, end],
+        |row| row.get(0),
+    )
+}
@@ -0,0 +1,169 @@
+use crate::certificate::{CertificateTemplate, IssuedCertificate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_ISSUED: &str = "SELECT
+        c.id, c.certificate_type, c.certificate_number, c.academic_year_id, ay.academic_year,
+        c.serial_no, c.copy_number, c.duplicate_of, c.student_id, c.student_name, c.gr_number,
+        c.issue_date, c.title, c.body, c.remarks, c.issued_by, c.file_name, c.created_at
+     FROM issued_certificates c
+     LEFT JOIN academic_years ay ON ay.id = c.academic_year_id";
+
+fn map_issued(row: &Row) -> rusqlite::Result<IssuedCertificate> {
+    Ok(IssuedCertificate {
+        id: row.get(0)?,
+        certificate_type: row.get(1)?,
+        certificate_number: row.get(2)?,
+        academic_year_id: row.get(3)?,
+        academic_year: row.get(4)?,
+        serial_no: row.get(5)?,
+        copy_number: row.get(6)?,
+        duplicate_of: row.get(7)?,
+        student_id: row.get(8)?,
+        student_name: row.get(9)?,
+        gr_number: row.get(10)?,
+        issue_date: row.get(11)?,
+        title: row.get(12)?,
+        body: row.get(13)?,
+        remarks: row.get(14)?,
+        issued_by: row.get(15)?,
+        file_name: row.get(16)?,
+        created_at: row.get(17)?,
+    })
+}
+
+fn map_template(row: &Row) -> rusqlite::Result<CertificateTemplate> {
+    Ok(CertificateTemplate {
+        certificate_type: row.get(0)?,
+        title: row.get(1)?,
+        body: row.get(2)?,
+        updated_at: row.get(3)?,
+    })
+}
+
+pub fn find_templates(conn: &Connection) -> rusqlite::Result<Vec<CertificateTemplate>> {
+    let mut stmt = conn.prepare(
+        "SELECT certificate_type, title, body, updated_at
+         FROM certificate_templates
+         ORDER BY certificate_type",
+    )?;
+    let templates = stmt
+        .query_map([], map_template)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(templates)
+}
+
+pub fn find_template(
+    conn: &Connection,
+    certificate_type: &str,
+) -> rusqlite::Result<Option<CertificateTemplate>> {
+    conn.query_row(
+        "SELECT certificate_type, title, body, updated_at
+         FROM certificate_templates
+         WHERE certificate_type = ?1",
+        params![certificate_type],
+        map_template,
+    )
+    .optional()
+}
+
+pub fn update_template(
+    conn: &Connection,
+    template: &CertificateTemplate,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE certificate_templates SET
+            title = ?1,
+            body = ?2,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE certificate_type = ?3",
+        params![template.title, template.body, template.certificate_type],
+    )
+}
+
+pub fn next_serial(
+    conn: &Connection,
+    certificate_type: &str,
+    academic_year_id: i64,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(serial_no), 0) + 1 FROM issued_certificates
+         WHERE certificate_type = ?1 AND academic_year_id = ?2",
+        params![certificate_type, academic_year_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn next_copy_number(conn: &Connection, original_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(copy_number), 0) + 1 FROM issued_certificates
+         WHERE id = ?1 OR duplicate_of = ?1",
+        params![original_id],
+        |row| row.get(0),
+    )
+}
+
+/// Whether an original transfer or leaving certificate was already issued to the student.
+pub fn has_leaving_certificate(conn: &Connection, student_id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM issued_certificates
+            WHERE student_id = ?1
+              AND certificate_type IN ('transfer', 'leaving')
+              AND copy_number = 0
+         )",
+        params![student_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert(conn: &Connection, certificate: &IssuedCertificate) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO issued_certificates (
+            certificate_type, certificate_number, academic_year_id, serial_no, copy_number,
+            duplicate_of, student_id, student_name, gr_number, issue_date, title, body,
+            remarks, issued_by, file_name
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            certificate.certificate_type,
+            certificate.certificate_number,
+            certificate.academic_year_id,
+            certificate.serial_no,
+            certificate.copy_number,
+            certificate.duplicate_of,
+            certificate.student_id,
+            certificate.student_name,
+            certificate.gr_number,
+            certificate.issue_date,
+            certificate.title,
+            certificate.body,
+            certificate.remarks,
+            certificate.issued_by,
+            certificate.file_name,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<IssuedCertificate>> {
+    let query = format!("{} WHERE c.id = ?1", SELECT_ISSUED);
+    conn.query_row(&query, params![id], map_issued).optional()
+}
+
+/// Register entries, newest first, optionally narrowed to one student or certificate type.
+pub fn find_all(
+    conn: &Connection,
+    student_id: Option<i64>,
+    certificate_type: Option<&str>,
+) -> rusqlite::Result<Vec<IssuedCertificate>> {
+    let query = format!(
+        "{} WHERE (?1 IS NULL OR c.student_id = ?1)
+              AND (?2 IS NULL OR c.certificate_type = ?2)
+         ORDER BY c.issue_date DESC, c.id DESC",
+        SELECT_ISSUED
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let certificates = stmt
+        .query_map(params![student_id, certificate_type], map_issued)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(certificates)
+}
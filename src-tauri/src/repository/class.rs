@@ -0,0 +1,430 @@
+use crate::academic_year::AcademicYear;
+use crate::class::{Class, ClassTemplate, WaitlistEntry};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_WITH_YEAR: &str = "SELECT
+        c.id,
+        c.class_name,
+        c.academic_years,
+        c.status,
+        c.created_at,
+        c.updated_at,
+        a.id as ay_id,
+        a.academic_year as ay_name,
+        a.status as ay_status,
+        a.created_at as ay_created_at,
+        a.updated_at as ay_updated_at,
+        c.grade,
+        c.section,
+        c.grade_order,
+        c.capacity,
+        c.when_full,
+        (SELECT COUNT(*) FROM students s
+         WHERE s.class_id = c.id
+           AND (s.status IS NULL OR s.status IN ('active', 'suspended'))) AS enrolled,
+        a.start_date as ay_start_date,
+        a.end_date as ay_end_date,
+        a.closed_at as ay_closed_at,
+        a.closed_by as ay_closed_by
+     FROM classes c
+     LEFT JOIN academic_years a ON c.academic_years = a.id";
+
+fn map_class_with_year(row: &Row) -> rusqlite::Result<Class> {
+    let ay_id: Option<i64> = row.get(6)?;
+    let academic_year_details = match ay_id {
+        Some(ay_id) => Some(AcademicYear {
+            id: Some(ay_id),
+            academic_year: row.get(7)?,
+            status: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get(10)?,
+            start_date: row.get(17)?,
+            end_date: row.get(18)?,
+            closed_at: row.get(19)?,
+            closed_by: row.get(20)?,
+        }),
+        None => None,
+    };
+
+    Ok(Class {
+        id: Some(row.get(0)?),
+        class_name: row.get(1)?,
+        academic_years: row.get(2)?,
+        status: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        academic_year_details,
+        grade: row.get(11)?,
+        section: row.get(12)?,
+        grade_order: row.get(13)?,
+        capacity: row.get(14)?,
+        when_full: row.get(15)?,
+        enrolled: row.get(16)?,
+    })
+}
+
+pub fn count(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("SELECT COUNT(*) FROM classes", [], |row| row.get(0))
+}
+
+pub fn exists(conn: &Connection, id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM classes WHERE id = ?1)",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+/// Case- and whitespace-insensitive name lookup within one academic year.
+pub fn name_taken(
+    conn: &Connection,
+    class_name: &str,
+    academic_year_id: i64,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM classes
+            WHERE LOWER(TRIM(class_name)) = LOWER(TRIM(?1))
+              AND academic_years = ?2
+              AND id != COALESCE(?3, -1)
+         )",
+        params![class_name, academic_year_id, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert(
+    conn: &Connection,
+    class_name: &str,
+    academic_year_id: i64,
+    status: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO classes (class_name, academic_years, status)
+         VALUES (?1, ?2, ?3)",
+        params![class_name, academic_year_id, status],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update(
+    conn: &Connection,
+    id: i64,
+    class_name: &str,
+    academic_year_id: i64,
+    status: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE classes SET
+            class_name = ?1,
+            academic_years = ?2,
+            status = ?3,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?4",
+        params![class_name, academic_year_id, status, id],
+    )
+}
+
+pub fn find_status(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT status FROM classes WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn set_status(conn: &Connection, id: i64, status: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE classes SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![status, id],
+    )?;
+    Ok(())
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Class>> {
+    conn.query_row(
+        "SELECT id, class_name, academic_years, status, created_at, updated_at,
+                grade, section, grade_order, capacity, when_full
+         FROM classes WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Class {
+                id: Some(row.get(0)?),
+                class_name: row.get(1)?,
+                academic_years: row.get(2)?,
+                status: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                academic_year_details: None,
+                grade: row.get(6)?,
+                section: row.get(7)?,
+                grade_order: row.get(8)?,
+                capacity: row.get(9)?,
+                when_full: row.get(10)?,
+                enrolled: None,
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn find_all(conn: &Connection, only_active: bool) -> rusqlite::Result<Vec<Class>> {
+    let mut query = String::from(SELECT_WITH_YEAR);
+    if only_active {
+        query.push_str(" WHERE c.status = 'active'");
+    }
+    // Grade order first, so Class-2 comes before Class-10; unparsed names go last. The grade
+    // keeps the sections of grades sharing an order (all unranked ones do) together.
+    query.push_str(
+        " ORDER BY c.grade_order IS NULL, c.grade_order, c.grade, c.section, c.class_name",
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let classes = stmt
+        .query_map([], map_class_with_year)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(classes)
+}
+
+/// Whether the first-run class setup has happened.
+pub fn defaults_initialized(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM classes_initialized)",
+        [],
+        |row| row.get(0),
+    )
+}
+
+pub fn mark_defaults_initialized(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("INSERT OR IGNORE INTO classes_initialized (id) VALUES (1)", [])?;
+    Ok(())
+}
+
+pub fn set_layout(
+    conn: &Connection,
+    id: i64,
+    grade: &str,
+    section: Option<&str>,
+    grade_order: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE classes SET grade = ?1, section = ?2, grade_order = ?3 WHERE id = ?4",
+        params![grade, section, grade_order, id],
+    )?;
+    Ok(())
+}
+
+/// Classes saved before grade and section were stored, as `(id, class_name)`.
+pub fn find_without_layout(conn: &Connection) -> rusqlite::Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare("SELECT id, class_name FROM classes WHERE grade IS NULL")?;
+    let classes = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(classes)
+}
+
+/// Copies the section of each class onto its students, for one class or all of them.
+pub fn sync_student_sections(conn: &Connection, class_id: Option<i64>) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET section = (SELECT section FROM classes WHERE classes.id = students.class_id)
+         WHERE class_id IN (
+            SELECT id FROM classes WHERE section IS NOT NULL AND (?1 IS NULL OR id = ?1)
+         )",
+        params![class_id],
+    )
+}
+
+pub fn set_capacity(
+    conn: &Connection,
+    id: i64,
+    capacity: Option<u32>,
+    when_full: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE classes SET capacity = ?1, when_full = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?3",
+        params![capacity, when_full, id],
+    )
+}
+
+/// Students of the class who hold a seat: active, suspended or still being admitted.
+pub fn count_on_roll(conn: &Connection, class_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM students
+         WHERE class_id = ?1 AND (status IS NULL OR status IN ('active', 'suspended'))",
+        params![class_id.to_string()],
+        |row| row.get(0),
+    )
+}
+
+pub fn add_to_waitlist(
+    conn: &Connection,
+    class_id: i64,
+    student_id: i64,
+    waitlisted_on: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO class_waitlist (class_id, student_id, waitlisted_on) VALUES (?1, ?2, ?3)",
+        params![class_id, student_id, waitlisted_on],
+    )?;
+    Ok(())
+}
+
+pub fn remove_from_waitlist(conn: &Connection, student_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM class_waitlist WHERE student_id = ?1",
+        params![student_id],
+    )
+}
+
+/// The class the student is waitlisted for, if any.
+pub fn find_waitlisted_class(conn: &Connection, student_id: i64) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT class_id FROM class_waitlist WHERE student_id = ?1",
+        params![student_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Waitlist of a class, first come first served.
+pub fn find_waitlist(conn: &Connection, class_id: i64) -> rusqlite::Result<Vec<WaitlistEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT w.student_id, w.class_id, s.full_name, s.gr_number, w.waitlisted_on
+         FROM class_waitlist w
+         JOIN students s ON s.id = w.student_id
+         WHERE w.class_id = ?1
+         ORDER BY w.waitlisted_on, w.id",
+    )?;
+    let entries = stmt
+        .query_map(params![class_id], |row| {
+            Ok(WaitlistEntry {
+                student_id: row.get(0)?,
+                class_id: row.get(1)?,
+                full_name: row.get(2)?,
+                gr_number: row.get(3)?,
+                waitlisted_on: row.get(4)?,
+                position: 0,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+fn find_template_classes(conn: &Connection, template_id: i64) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT class_name FROM class_template_classes WHERE template_id = ?1 ORDER BY position",
+    )?;
+    let names = stmt
+        .query_map(params![template_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+fn query_templates(
+    conn: &Connection,
+    filter: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Vec<ClassTemplate>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, description, code IS NOT NULL FROM class_templates WHERE {}
+         ORDER BY code IS NULL, id",
+        filter
+    ))?;
+    let mut templates = stmt
+        .query_map(params, |row| {
+            Ok(ClassTemplate {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                description: row.get(2)?,
+                builtin: row.get(3)?,
+                class_names: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for template in &mut templates {
+        if let Some(id) = template.id {
+            template.class_names = find_template_classes(conn, id)?;
+        }
+    }
+    Ok(templates)
+}
+
+/// Built-in templates first, then custom ones in the order they were added.
+pub fn find_templates(conn: &Connection) -> rusqlite::Result<Vec<ClassTemplate>> {
+    query_templates(conn, "1", [])
+}
+
+pub fn find_template(conn: &Connection, id: i64) -> rusqlite::Result<Option<ClassTemplate>> {
+    Ok(query_templates(conn, "id = ?1", params![id])?.pop())
+}
+
+pub fn find_template_id_by_code(conn: &Connection, code: &str) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM class_templates WHERE code = ?1",
+        params![code],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn template_name_taken(
+    conn: &Connection,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM class_templates WHERE name = ?1 AND id != COALESCE(?2, -1))",
+        params![name, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_template(
+    conn: &Connection,
+    code: Option<&str>,
+    name: &str,
+    description: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO class_templates (code, name, description) VALUES (?1, ?2, ?3)",
+        params![code, name, description],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_template(
+    conn: &Connection,
+    id: i64,
+    name: &str,
+    description: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE class_templates SET name = ?1, description = ?2 WHERE id = ?3",
+        params![name, description, id],
+    )
+}
+
+pub fn replace_template_classes(
+    conn: &Connection,
+    template_id: i64,
+    class_names: &[String],
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM class_template_classes WHERE template_id = ?1",
+        params![template_id],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO class_template_classes (template_id, position, class_name) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, class_name) in class_names.iter().enumerate() {
+        stmt.execute(params![template_id, position as i64 + 1, class_name])?;
+    }
+    Ok(())
+}
+
+pub fn delete_template(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM class_templates WHERE id = ?1", params![id])
+}
@@ -0,0 +1,429 @@
+use crate::communication::{CommunicationSettings, Message, MessageRecipient, MessageTemplate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub fn find_settings(conn: &Connection) -> rusqlite::Result<CommunicationSettings> {
+    conn.query_row(
+        "SELECT smtp_host, smtp_port, smtp_username, smtp_password, smtp_from, smtp_from_name,
+                sms_gateway_url, sms_api_key, sms_sender_id, max_attempts, smtp_security
+         FROM communication_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(CommunicationSettings {
+                smtp_host: row.get(0)?,
+                smtp_port: row.get(1)?,
+                smtp_security: row.get(10)?,
+                smtp_username: row.get(2)?,
+                smtp_password: row.get(3)?,
+                has_password: false,
+                smtp_from: row.get(4)?,
+                smtp_from_name: row.get(5)?,
+                sms_gateway_url: row.get(6)?,
+                sms_api_key: row.get(7)?,
+                has_api_key: false,
+                sms_sender_id: row.get(8)?,
+                max_attempts: row.get(9)?,
+            })
+        },
+    )
+}
+
+pub fn save_settings(conn: &Connection, settings: &CommunicationSettings) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE communication_settings SET
+            smtp_host = ?1, smtp_port = ?2, smtp_username = ?3, smtp_password = ?4,
+            smtp_from = ?5, smtp_from_name = ?6, sms_gateway_url = ?7, sms_api_key = ?8,
+            sms_sender_id = ?9, max_attempts = ?10, smtp_security = ?11
+         WHERE id = 1",
+        params![
+            settings.smtp_host,
+            settings.smtp_port,
+            settings.smtp_username,
+            settings.smtp_password,
+            settings.smtp_from,
+            settings.smtp_from_name,
+            settings.sms_gateway_url,
+            settings.sms_api_key,
+            settings.sms_sender_id,
+            settings.max_attempts,
+            settings.smtp_security
+        ],
+    )?;
+    Ok(())
+}
+
+fn map_template(row: &Row) -> rusqlite::Result<MessageTemplate> {
+    Ok(MessageTemplate {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        subject: row.get(2)?,
+        body: row.get(3)?,
+    })
+}
+
+pub fn find_templates(conn: &Connection) -> rusqlite::Result<Vec<MessageTemplate>> {
+    let mut stmt =
+        conn.prepare("SELECT id, name, subject, body FROM message_templates ORDER BY name")?;
+    let templates = stmt
+        .query_map([], map_template)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(templates)
+}
+
+pub fn find_template(conn: &Connection, id: i64) -> rusqlite::Result<Option<MessageTemplate>> {
+    conn.query_row(
+        "SELECT id, name, subject, body FROM message_templates WHERE id = ?1",
+        params![id],
+        map_template,
+    )
+    .optional()
+}
+
+pub fn template_name_taken(
+    conn: &Connection,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM message_templates
+         WHERE name = ?1 COLLATE NOCASE AND id != COALESCE(?2, -1))",
+        params![name, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_template(conn: &Connection, template: &MessageTemplate) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO message_templates (name, subject, body) VALUES (?1, ?2, ?3)",
+        params![template.name, template.subject, template.body],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_template(
+    conn: &Connection,
+    id: i64,
+    template: &MessageTemplate,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE message_templates SET name = ?1, subject = ?2, body = ?3 WHERE id = ?4",
+        params![template.name, template.subject, template.body, id],
+    )
+}
+
+pub fn delete_template(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM message_templates WHERE id = ?1", params![id])
+}
+
+/// A student on the roll with one guardian to reach them through. The guardian columns
+/// are `None` when nobody is linked.
+pub struct FamilyContact {
+    pub student_id: i64,
+    pub student_name: String,
+    pub class_name: String,
+    pub guardian_id: Option<i64>,
+    pub guardian_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+const SELECT_FAMILIES: &str = "SELECT s.id, s.full_name, COALESCE(c.class_name, ''),
+        g.id, g.full_name, g.phone, g.email
+     FROM students s
+     LEFT JOIN classes c ON c.id = s.class_id";
+
+fn map_family(row: &Row) -> rusqlite::Result<FamilyContact> {
+    Ok(FamilyContact {
+        student_id: row.get(0)?,
+        student_name: row.get(1)?,
+        class_name: row.get(2)?,
+        guardian_id: row.get(3)?,
+        guardian_name: row.get(4)?,
+        phone: row.get(5)?,
+        email: row.get(6)?,
+    })
+}
+
+/// Students on the roll, of one class or the whole school, each with their primary contact
+/// or, when none is marked, every linked guardian.
+pub fn find_families(
+    conn: &Connection,
+    class_id: Option<i64>,
+) -> rusqlite::Result<Vec<FamilyContact>> {
+    let mut stmt = conn.prepare(&format!(
+        "{}
+         LEFT JOIN student_guardians sg ON sg.student_id = s.id
+           AND (sg.is_primary_contact = 1 OR NOT EXISTS (
+                SELECT 1 FROM student_guardians p
+                WHERE p.student_id = s.id AND p.is_primary_contact = 1))
+         LEFT JOIN guardians g ON g.id = sg.guardian_id
+         WHERE (s.status IS NULL OR s.status IN ('active', 'suspended'))
+           AND (?1 IS NULL OR s.class_id = ?1)
+         ORDER BY c.grade_order, c.section, s.full_name, s.id, g.id",
+        SELECT_FAMILIES
+    ))?;
+    let families = stmt
+        .query_map(params![class_id], map_family)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(families)
+}
+
+/// The guardian's children on the roll.
+pub fn find_guardian_families(
+    conn: &Connection,
+    guardian_id: i64,
+) -> rusqlite::Result<Vec<FamilyContact>> {
+    let mut stmt = conn.prepare(&format!(
+        "{}
+         JOIN student_guardians sg ON sg.student_id = s.id
+         JOIN guardians g ON g.id = sg.guardian_id
+         WHERE sg.guardian_id = ?1
+           AND (s.status IS NULL OR s.status IN ('active', 'suspended'))
+         ORDER BY s.full_name, s.id",
+        SELECT_FAMILIES
+    ))?;
+    let families = stmt
+        .query_map(params![guardian_id], map_family)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(families)
+}
+
+/// Copy of a message ready to be stored for one recipient.
+pub struct NewRecipient {
+    pub recipient_type: &'static str,
+    pub recipient_id: i64,
+    pub student_id: Option<i64>,
+    pub name: String,
+    pub address: String,
+    pub subject: String,
+    pub body: String,
+    pub status: &'static str,
+    pub last_error: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn insert_message(
+    conn: &Connection,
+    kind: &str,
+    title: &str,
+    subject: &str,
+    body: &str,
+    channel: &str,
+    audience: &str,
+    target_ids: &str,
+    expires_on: Option<&str>,
+    on_board: bool,
+    created_by: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO messages
+            (kind, title, subject, body, channel, audience, target_ids, expires_on, on_board,
+             created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            kind, title, subject, body, channel, audience, target_ids, expires_on, on_board,
+            created_by
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn insert_recipient(
+    conn: &Connection,
+    message_id: i64,
+    recipient: &NewRecipient,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO message_recipients
+            (message_id, recipient_type, recipient_id, student_id, name, address, subject, body,
+             status, last_error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            message_id,
+            recipient.recipient_type,
+            recipient.recipient_id,
+            recipient.student_id,
+            recipient.name,
+            recipient.address,
+            recipient.subject,
+            recipient.body,
+            recipient.status,
+            recipient.last_error
+        ],
+    )?;
+    Ok(())
+}
+
+const SELECT_MESSAGES: &str = "SELECT m.id, m.kind, m.title, m.subject, m.body, m.channel,
+        m.audience, m.target_ids, m.expires_on, m.created_by, m.created_at,
+        COUNT(r.id),
+        COUNT(CASE WHEN r.status = 'sent' THEN 1 END),
+        COUNT(CASE WHEN r.status = 'failed' THEN 1 END),
+        COUNT(CASE WHEN r.status = 'pending' THEN 1 END),
+        COUNT(CASE WHEN r.status = 'sending' THEN 1 END),
+        COUNT(CASE WHEN r.status = 'skipped' THEN 1 END),
+        COUNT(CASE WHEN r.status = 'exported' THEN 1 END)
+     FROM messages m
+     LEFT JOIN message_recipients r ON r.message_id = m.id";
+
+fn map_message(row: &Row) -> rusqlite::Result<Message> {
+    let target_ids: String = row.get(7)?;
+    Ok(Message {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        title: row.get(2)?,
+        subject: row.get(3)?,
+        body: row.get(4)?,
+        channel: row.get(5)?,
+        audience: row.get(6)?,
+        target_ids: target_ids
+            .split(',')
+            .filter_map(|id| id.parse().ok())
+            .collect(),
+        expires_on: row.get(8)?,
+        created_by: row.get(9)?,
+        created_at: row.get(10)?,
+        recipients: row.get(11)?,
+        sent: row.get(12)?,
+        failed: row.get(13)?,
+        pending: row.get(14)?,
+        sending: row.get(15)?,
+        skipped: row.get(16)?,
+        exported: row.get(17)?,
+    })
+}
+
+fn query_messages(
+    conn: &Connection,
+    filter: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Vec<Message>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE {} GROUP BY m.id ORDER BY m.created_at DESC, m.id DESC",
+        SELECT_MESSAGES, filter
+    ))?;
+    let messages = stmt
+        .query_map(params, map_message)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(messages)
+}
+
+pub fn find_messages(conn: &Connection) -> rusqlite::Result<Vec<Message>> {
+    query_messages(conn, "1 = 1", [])
+}
+
+pub fn find_message(conn: &Connection, id: i64) -> rusqlite::Result<Option<Message>> {
+    Ok(query_messages(conn, "m.id = ?1", params![id])?.pop())
+}
+
+/// Board messages not expired by `today`, limited to school-wide ones and those for the
+/// class when `class_id` is given.
+pub fn find_board(
+    conn: &Connection,
+    today: &str,
+    class_id: Option<i64>,
+) -> rusqlite::Result<Vec<Message>> {
+    query_messages(
+        conn,
+        "m.on_board = 1 AND (m.expires_on IS NULL OR m.expires_on >= ?1)
+         AND (?2 IS NULL OR m.audience = 'school'
+              OR (m.audience = 'class' AND ',' || m.target_ids || ',' LIKE '%,' || ?2 || ',%'))",
+        params![today, class_id],
+    )
+}
+
+pub fn delete_message(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM messages WHERE id = ?1", params![id])
+}
+
+const SELECT_RECIPIENTS: &str = "SELECT id, message_id, recipient_type, recipient_id, student_id,
+        name, address, subject, body, status, attempts, last_error, provider_ref, sent_at
+     FROM message_recipients";
+
+fn map_recipient(row: &Row) -> rusqlite::Result<MessageRecipient> {
+    Ok(MessageRecipient {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        recipient_type: row.get(2)?,
+        recipient_id: row.get(3)?,
+        student_id: row.get(4)?,
+        name: row.get(5)?,
+        address: row.get(6)?,
+        subject: row.get(7)?,
+        body: row.get(8)?,
+        status: row.get(9)?,
+        attempts: row.get(10)?,
+        last_error: row.get(11)?,
+        provider_ref: row.get(12)?,
+        sent_at: row.get(13)?,
+    })
+}
+
+pub fn find_recipients(
+    conn: &Connection,
+    message_id: i64,
+    status: Option<&str>,
+) -> rusqlite::Result<Vec<MessageRecipient>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE message_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY id",
+        SELECT_RECIPIENTS
+    ))?;
+    let recipients = stmt
+        .query_map(params![message_id, status], map_recipient)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(recipients)
+}
+
+/// Pending copies, and failed ones with fewer than `max_attempts` tries.
+pub fn find_deliverable(
+    conn: &Connection,
+    message_id: i64,
+    max_attempts: u32,
+) -> rusqlite::Result<Vec<MessageRecipient>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE message_id = ?1
+           AND (status = 'pending' OR (status = 'failed' AND attempts < ?2))
+         ORDER BY id",
+        SELECT_RECIPIENTS
+    ))?;
+    let recipients = stmt
+        .query_map(params![message_id, max_attempts], map_recipient)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(recipients)
+}
+
+/// Marks the copies as taken by a send in progress.
+pub fn mark_sending(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt =
+        conn.prepare("UPDATE message_recipients SET status = 'sending' WHERE id = ?1")?;
+    for id in ids {
+        stmt.execute(params![id])?;
+    }
+    Ok(())
+}
+
+pub fn record_sent(conn: &Connection, id: i64, provider_ref: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE message_recipients SET status = 'sent', attempts = attempts + 1,
+            last_error = NULL, provider_ref = ?1, sent_at = CURRENT_TIMESTAMP
+         WHERE id = ?2 AND status = 'sending'",
+        params![provider_ref, id],
+    )
+}
+
+pub fn record_failed(conn: &Connection, id: i64, error: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE message_recipients SET status = 'failed', attempts = attempts + 1,
+            last_error = ?1
+         WHERE id = ?2 AND status = 'sending'",
+        params![error, id],
+    )
+}
+
+pub fn mark_exported(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE message_recipients SET status = 'exported', attempts = attempts + 1,
+            last_error = NULL, sent_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND status IN ('pending', 'failed', 'exported')",
+        params![id],
+    )
+}
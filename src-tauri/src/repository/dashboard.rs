@@ -0,0 +1,99 @@
+use crate::dashboard::CountBy;
+use rusqlite::{params, Connection, Row};
+
+const ON_ROLL: &str = "(s.status IS NULL OR s.status IN ('active', 'suspended'))";
+
+fn map_count(row: &Row) -> rusqlite::Result<CountBy> {
+    Ok(CountBy {
+        label: row.get(0)?,
+        count: row.get(1)?,
+    })
+}
+
+fn counts(conn: &Connection, sql: &str) -> rusqlite::Result<Vec<CountBy>> {
+    let mut stmt = conn.prepare(sql)?;
+    let counts = stmt
+        .query_map([], map_count)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(counts)
+}
+
+/// Students on the roll by gender, capitalised so "female" and "Female" count together.
+pub fn students_by_gender(conn: &Connection) -> rusqlite::Result<Vec<CountBy>> {
+    counts(
+        conn,
+        &format!(
+            "SELECT COALESCE(
+                    UPPER(SUBSTR(NULLIF(TRIM(s.gender), ''), 1, 1)) || LOWER(SUBSTR(TRIM(s.gender), 2)),
+                    'Unspecified'),
+                COUNT(*)
+             FROM students s
+             WHERE {}
+             GROUP BY 1
+             ORDER BY COUNT(*) DESC, 1",
+            ON_ROLL
+        ),
+    )
+}
+
+pub fn students_by_class(conn: &Connection) -> rusqlite::Result<Vec<CountBy>> {
+    counts(
+        conn,
+        &format!(
+            "SELECT COALESCE(c.class_name, 'Unassigned'), COUNT(*)
+             FROM students s
+             LEFT JOIN classes c ON c.id = s.class_id
+             WHERE {}
+             GROUP BY c.id",
+            ON_ROLL
+        ),
+    )
+}
+
+/// Students on the roll admitted between two dates.
+pub fn count_admissions(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<u32> {
+    conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM students s
+             WHERE {} AND s.admission_date BETWEEN ?1 AND ?2",
+            ON_ROLL
+        ),
+        params![from, to],
+        |row| row.get(0),
+    )
+}
+
+pub fn enquiries_by_status(conn: &Connection) -> rusqlite::Result<Vec<CountBy>> {
+    counts(
+        conn,
+        "SELECT COALESCE(NULLIF(LOWER(TRIM(status)), ''), 'new'), COUNT(*)
+         FROM enquiries
+         GROUP BY 1
+         ORDER BY COUNT(*) DESC, 1",
+    )
+}
+
+/// Active staff by department.
+pub fn staff_by_department(conn: &Connection) -> rusqlite::Result<Vec<CountBy>> {
+    counts(
+        conn,
+        "SELECT TRIM(department), COUNT(*)
+         FROM staff
+         WHERE status IS NULL OR LOWER(TRIM(status)) = 'active'
+         GROUP BY TRIM(department) COLLATE NOCASE
+         ORDER BY COUNT(*) DESC, 1",
+    )
+}
+
+/// Total raised, paid, waived and still open across all student dues.
+pub fn dues_totals(conn: &Connection) -> rusqlite::Result<(f64, f64, f64, f64)> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0),
+            COALESCE(SUM(CASE WHEN status = 'paid' THEN amount END), 0),
+            COALESCE(SUM(CASE WHEN status = 'waived' THEN amount END), 0),
+            COALESCE(SUM(CASE WHEN status = 'open' THEN amount END), 0)
+         FROM student_dues",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )
+}
@@ -0,0 +1,215 @@
+use crate::discipline::{DisciplineIncident, ParentMeeting};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+/// Rank of a visibility level, compared against the viewer's rank.
+fn rank_of(column: &str) -> String {
+    format!(
+        "CASE {} WHEN 'staff' THEN 0 WHEN 'counsellor' THEN 1 ELSE 2 END",
+        column
+    )
+}
+
+const SELECT_INCIDENTS: &str = "SELECT d.id, d.student_id, d.incident_date, d.category,
+        d.severity, d.description, d.action_taken, d.reported_by, d.visibility,
+        d.include_on_certificate, d.acknowledged_by, d.acknowledged_on, d.parent_remarks,
+        s.full_name, c.class_name, st.name, g.full_name
+     FROM discipline_incidents d
+     JOIN students s ON s.id = d.student_id
+     LEFT JOIN classes c ON c.id = s.class_id
+     LEFT JOIN staff st ON st.id = d.reported_by
+     LEFT JOIN guardians g ON g.id = d.acknowledged_by";
+
+fn map_incident(row: &Row) -> rusqlite::Result<DisciplineIncident> {
+    Ok(DisciplineIncident {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        incident_date: row.get(2)?,
+        category: row.get(3)?,
+        severity: row.get(4)?,
+        description: row.get(5)?,
+        action_taken: row.get(6)?,
+        reported_by: row.get(7)?,
+        visibility: Some(row.get(8)?),
+        include_on_certificate: row.get(9)?,
+        acknowledged_by: row.get(10)?,
+        acknowledged_on: row.get(11)?,
+        parent_remarks: row.get(12)?,
+        student_name: Some(row.get(13)?),
+        class_name: row.get(14)?,
+        reporter_name: row.get(15)?,
+        acknowledged_by_name: row.get(16)?,
+    })
+}
+
+pub fn find_incident(conn: &Connection, id: i64) -> rusqlite::Result<Option<DisciplineIncident>> {
+    conn.query_row(
+        &format!("{} WHERE d.id = ?1", SELECT_INCIDENTS),
+        params![id],
+        map_incident,
+    )
+    .optional()
+}
+
+/// Incidents up to the viewer's rank, latest first.
+pub fn find_incidents(
+    conn: &Connection,
+    viewer_rank: u8,
+    student_id: Option<i64>,
+    from: &str,
+    to: &str,
+    unacknowledged_only: bool,
+) -> rusqlite::Result<Vec<DisciplineIncident>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE {} <= ?1 AND (?2 IS NULL OR d.student_id = ?2)
+           AND d.incident_date BETWEEN ?3 AND ?4
+           AND (?5 = 0 OR d.acknowledged_on IS NULL)
+         ORDER BY d.incident_date DESC, d.id DESC",
+        SELECT_INCIDENTS,
+        rank_of("d.visibility")
+    ))?;
+    let incidents = stmt
+        .query_map(
+            params![viewer_rank, student_id, from, to, unacknowledged_only],
+            map_incident,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(incidents)
+}
+
+/// Incidents marked for the student's character certificate, oldest first.
+pub fn find_certificate_incidents(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Vec<DisciplineIncident>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE d.student_id = ?1 AND d.include_on_certificate = 1
+         ORDER BY d.incident_date, d.id",
+        SELECT_INCIDENTS
+    ))?;
+    let incidents = stmt
+        .query_map(params![student_id], map_incident)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(incidents)
+}
+
+pub fn insert_incident(conn: &Connection, incident: &DisciplineIncident) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO discipline_incidents (student_id, incident_date, category, severity,
+            description, action_taken, reported_by, visibility, include_on_certificate)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            incident.student_id,
+            incident.incident_date,
+            incident.category,
+            incident.severity,
+            incident.description,
+            incident.action_taken,
+            incident.reported_by,
+            incident.visibility,
+            incident.include_on_certificate
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Updates what was recorded; the student and any acknowledgement stay as they are.
+pub fn update_incident(
+    conn: &Connection,
+    id: i64,
+    incident: &DisciplineIncident,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE discipline_incidents SET incident_date = ?1, category = ?2, severity = ?3,
+            description = ?4, action_taken = ?5, reported_by = ?6, visibility = ?7,
+            include_on_certificate = ?8
+         WHERE id = ?9",
+        params![
+            incident.incident_date,
+            incident.category,
+            incident.severity,
+            incident.description,
+            incident.action_taken,
+            incident.reported_by,
+            incident.visibility,
+            incident.include_on_certificate,
+            id
+        ],
+    )
+}
+
+pub fn delete_incident(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM discipline_incidents WHERE id = ?1",
+        params![id],
+    )
+}
+
+pub fn acknowledge(
+    conn: &Connection,
+    id: i64,
+    guardian_id: i64,
+    acknowledged_on: &str,
+    parent_remarks: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE discipline_incidents
+         SET acknowledged_by = ?1, acknowledged_on = ?2, parent_remarks = ?3
+         WHERE id = ?4",
+        params![guardian_id, acknowledged_on, parent_remarks, id],
+    )
+}
+
+pub fn insert_meeting(conn: &Connection, meeting: &ParentMeeting) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO parent_meetings (student_id, incident_id, meeting_date, guardian_id,
+            held_by, summary, follow_up, visibility)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            meeting.student_id,
+            meeting.incident_id,
+            meeting.meeting_date,
+            meeting.guardian_id,
+            meeting.held_by,
+            meeting.summary,
+            meeting.follow_up,
+            meeting.visibility
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Meetings with the student's parents up to the viewer's rank. A meeting about an
+/// incident takes the incident's visibility.
+pub fn find_meetings(
+    conn: &Connection,
+    student_id: i64,
+    viewer_rank: u8,
+) -> rusqlite::Result<Vec<ParentMeeting>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT m.id, m.student_id, m.incident_id, m.meeting_date, m.guardian_id, m.held_by,
+            m.summary, m.follow_up, COALESCE(d.visibility, m.visibility), g.full_name
+         FROM parent_meetings m
+         LEFT JOIN discipline_incidents d ON d.id = m.incident_id
+         LEFT JOIN guardians g ON g.id = m.guardian_id
+         WHERE m.student_id = ?1 AND {} <= ?2
+         ORDER BY m.meeting_date DESC, m.id DESC",
+        rank_of("COALESCE(d.visibility, m.visibility)")
+    ))?;
+    let meetings = stmt
+        .query_map(params![student_id, viewer_rank], |row| {
+            Ok(ParentMeeting {
+                id: Some(row.get(0)?),
+                student_id: row.get(1)?,
+                incident_id: row.get(2)?,
+                meeting_date: row.get(3)?,
+                guardian_id: row.get(4)?,
+                held_by: row.get(5)?,
+                summary: row.get(6)?,
+                follow_up: row.get(7)?,
+                visibility: Some(row.get(8)?),
+                guardian_name: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(meetings)
+}
@@ -0,0 +1,120 @@
+use crate::dues::StudentDue;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_DUES: &str = "SELECT d.id, d.student_id, d.source, d.reference, d.description,
+        d.amount, d.raised_on, d.status, d.settled_on, d.settled_note, s.full_name, c.class_name
+     FROM student_dues d
+     JOIN students s ON s.id = d.student_id
+     LEFT JOIN classes c ON c.id = s.class_id";
+
+fn map_due(row: &Row) -> rusqlite::Result<StudentDue> {
+    Ok(StudentDue {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        source: Some(row.get(2)?),
+        reference: row.get(3)?,
+        description: row.get(4)?,
+        amount: row.get(5)?,
+        raised_on: Some(row.get(6)?),
+        status: Some(row.get(7)?),
+        settled_on: row.get(8)?,
+        settled_note: row.get(9)?,
+        student_name: Some(row.get(10)?),
+        class_name: row.get(11)?,
+    })
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<StudentDue>> {
+    conn.query_row(
+        &format!("{} WHERE d.id = ?1", SELECT_DUES),
+        params![id],
+        map_due,
+    )
+    .optional()
+}
+
+pub fn find_for_student(
+    conn: &Connection,
+    student_id: i64,
+    include_settled: bool,
+) -> rusqlite::Result<Vec<StudentDue>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE d.student_id = ?1 AND (?2 OR d.status = 'open')
+         ORDER BY d.raised_on DESC, d.id DESC",
+        SELECT_DUES
+    ))?;
+    let dues = stmt
+        .query_map(params![student_id, include_settled], map_due)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(dues)
+}
+
+/// Open dues of students on the roll, optionally of one class, by class then student.
+pub fn find_open(conn: &Connection, class_id: Option<i64>) -> rusqlite::Result<Vec<StudentDue>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE d.status = 'open'
+           AND (s.status IS NULL OR s.status IN ('active', 'suspended'))
+           AND (?1 IS NULL OR s.class_id = ?1)
+         ORDER BY c.grade_order, c.section, s.full_name, d.raised_on, d.id",
+        SELECT_DUES
+    ))?;
+    let dues = stmt
+        .query_map(params![class_id], map_due)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(dues)
+}
+
+/// Total open dues per student.
+pub fn find_outstanding_totals(conn: &Connection) -> rusqlite::Result<Vec<(i64, f64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT student_id, SUM(amount) FROM student_dues
+         WHERE status = 'open' GROUP BY student_id",
+    )?;
+    let totals = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(totals)
+}
+
+pub fn reference_taken(conn: &Connection, source: &str, reference: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM student_dues WHERE source = ?1 AND reference = ?2)",
+        params![source, reference],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert(
+    conn: &Connection,
+    due: &StudentDue,
+    source: &str,
+    raised_on: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO student_dues (student_id, source, reference, description, amount, raised_on)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            due.student_id,
+            source,
+            due.reference,
+            due.description,
+            due.amount,
+            raised_on
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn settle(
+    conn: &Connection,
+    id: i64,
+    status: &str,
+    settled_on: &str,
+    note: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE student_dues SET status = ?1, settled_on = ?2, settled_note = ?3
+         WHERE id = ?4 AND status = 'open'",
+        params![status, settled_on, note, id],
+    )
+}
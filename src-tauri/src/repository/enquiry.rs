@@ -0,0 +1,150 @@
+use crate::enquiry::{Enquiry, FollowUp, Note};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+fn map_enquiry(row: &Row) -> rusqlite::Result<Enquiry> {
+    Ok(Enquiry {
+        id: Some(row.get(0)?),
+        student_name: row.get(1)?,
+        parent_name: row.get(2)?,
+        phone: row.get(3)?,
+        email: row.get(4)?,
+        source: row.get(5)?,
+        status: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+pub fn insert(conn: &Connection, enquiry: &Enquiry, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO enquiries (student_name, parent_name, phone, email, source, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            enquiry.student_name,
+            enquiry.parent_name,
+            enquiry.phone,
+            enquiry.email,
+            enquiry.source,
+            status,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Enquiry>> {
+    conn.query_row(
+        "SELECT id, student_name, parent_name, phone, email, source, status, created_at
+         FROM enquiries WHERE id = ?1",
+        params![id],
+        map_enquiry,
+    )
+    .optional()
+}
+
+pub fn find_all(conn: &Connection) -> rusqlite::Result<Vec<Enquiry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, student_name, parent_name, phone, email, source, status, created_at
+         FROM enquiries ORDER BY created_at DESC",
+    )?;
+    let enquiries = stmt
+        .query_map([], map_enquiry)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(enquiries)
+}
+
+pub fn update(conn: &Connection, id: i64, enquiry: &Enquiry) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE enquiries SET
+            student_name = ?1,
+            parent_name = ?2,
+            phone = ?3,
+            email = ?4,
+            source = ?5,
+            status = ?6
+         WHERE id = ?7",
+        params![
+            enquiry.student_name,
+            enquiry.parent_name,
+            enquiry.phone,
+            enquiry.email,
+            enquiry.source,
+            enquiry.status,
+            id,
+        ],
+    )
+}
+
+pub fn update_status(conn: &Connection, id: i64, status: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE enquiries SET status = ?1 WHERE id = ?2",
+        params![status, id],
+    )
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM enquiries WHERE id = ?1", params![id])
+}
+
+pub fn insert_follow_up(conn: &Connection, follow_up: &FollowUp) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO followups (enquiry_id, notes, status, follow_up_date)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            follow_up.enquiry_id,
+            follow_up.notes,
+            follow_up.status,
+            follow_up.follow_up_date,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_follow_ups(conn: &Connection, enquiry_id: i64) -> rusqlite::Result<Vec<FollowUp>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, enquiry_id, notes, status, created_at, follow_up_date
+         FROM followups
+         WHERE enquiry_id = ?1
+         ORDER BY created_at DESC",
+    )?;
+    let follow_ups = stmt
+        .query_map(params![enquiry_id], |row| {
+            Ok(FollowUp {
+                id: Some(row.get(0)?),
+                enquiry_id: row.get(1)?,
+                notes: row.get(2)?,
+                status: row.get(3)?,
+                created_at: row.get(4)?,
+                follow_up_date: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(follow_ups)
+}
+
+pub fn insert_note(conn: &Connection, note: &Note) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO notes (enquiry_id, notes)
+         VALUES (?1, ?2)",
+        params![note.enquiry_id, note.notes],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_notes(conn: &Connection, enquiry_id: i64) -> rusqlite::Result<Vec<Note>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, enquiry_id, notes, created_at
+         FROM notes
+         WHERE enquiry_id = ?1
+         ORDER BY created_at DESC",
+    )?;
+    let notes = stmt
+        .query_map(params![enquiry_id], |row| {
+            Ok(Note {
+                id: Some(row.get(0)?),
+                enquiry_id: row.get(1)?,
+                notes: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(notes)
+}
@@ -0,0 +1,345 @@
+use crate::guardian::{Guardian, Sibling, StudentGuardian};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const GUARDIAN_COLUMNS: &str = "g.id, g.full_name, g.relation_type, g.phone, g.alternate_phone, g.email,
+        g.address, g.occupation, g.annual_income, g.aadhaar_number, g.pan_number, g.notes,
+        g.created_at, g.updated_at";
+
+fn map_guardian(row: &Row) -> rusqlite::Result<Guardian> {
+    Ok(Guardian {
+        id: Some(row.get(0)?),
+        full_name: row.get(1)?,
+        relation_type: row.get(2)?,
+        phone: row.get(3)?,
+        alternate_phone: row.get(4)?,
+        email: row.get(5)?,
+        address: row.get(6)?,
+        occupation: row.get(7)?,
+        annual_income: row.get(8)?,
+        aadhaar_number: row.get(9)?,
+        pan_number: row.get(10)?,
+        notes: row.get(11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+    })
+}
+
+pub fn insert(conn: &Connection, guardian: &Guardian) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO guardians (
+            full_name, relation_type, phone, alternate_phone, email, address,
+            occupation, annual_income, aadhaar_number, pan_number, notes
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            guardian.full_name,
+            guardian.relation_type,
+            guardian.phone,
+            guardian.alternate_phone,
+            guardian.email,
+            guardian.address,
+            guardian.occupation,
+            guardian.annual_income,
+            guardian.aadhaar_number,
+            guardian.pan_number,
+            guardian.notes,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update(conn: &Connection, id: i64, guardian: &Guardian) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE guardians SET
+            full_name = ?1,
+            relation_type = ?2,
+            phone = ?3,
+            alternate_phone = ?4,
+            email = ?5,
+            address = ?6,
+            occupation = ?7,
+            annual_income = ?8,
+            aadhaar_number = ?9,
+            pan_number = ?10,
+            notes = ?11,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?12",
+        params![
+            guardian.full_name,
+            guardian.relation_type,
+            guardian.phone,
+            guardian.alternate_phone,
+            guardian.email,
+            guardian.address,
+            guardian.occupation,
+            guardian.annual_income,
+            guardian.aadhaar_number,
+            guardian.pan_number,
+            guardian.notes,
+            id,
+        ],
+    )
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM guardians WHERE id = ?1", params![id])
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Guardian>> {
+    let query = format!("SELECT {} FROM guardians g WHERE g.id = ?1", GUARDIAN_COLUMNS);
+    conn.query_row(&query, params![id], map_guardian).optional()
+}
+
+/// Matches name, phone or Aadhaar number, for picking an existing parent when admitting a sibling.
+pub fn search(conn: &Connection, term: &str) -> rusqlite::Result<Vec<Guardian>> {
+    let query = format!(
+        "SELECT {} FROM guardians g
+         WHERE g.full_name LIKE ?1 OR g.phone LIKE ?1 OR g.alternate_phone LIKE ?1 OR g.aadhaar_number LIKE ?1
+         ORDER BY g.full_name
+         LIMIT 50",
+        GUARDIAN_COLUMNS
+    );
+    let pattern = format!("%{}%", term);
+    let mut stmt = conn.prepare(&query)?;
+    let guardians = stmt
+        .query_map(params![pattern], map_guardian)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(guardians)
+}
+
+/// Guardian with the same relation, name (ignoring case) and phone, if one was already recorded.
+pub fn find_matching(
+    conn: &Connection,
+    relation_type: &str,
+    full_name: &str,
+    phone: Option<&str>,
+) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM guardians
+         WHERE relation_type = ?1
+           AND LOWER(TRIM(full_name)) = LOWER(TRIM(?2))
+           AND phone IS ?3
+         ORDER BY id
+         LIMIT 1",
+        params![relation_type, full_name, phone],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn is_linked(conn: &Connection, student_id: i64, guardian_id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM student_guardians WHERE student_id = ?1 AND guardian_id = ?2)",
+        params![student_id, guardian_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_student_ids(conn: &Connection, guardian_id: i64) -> rusqlite::Result<Vec<i64>> {
+    let mut stmt = conn.prepare("SELECT student_id FROM student_guardians WHERE guardian_id = ?1")?;
+    let ids = stmt
+        .query_map(params![guardian_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ids)
+}
+
+pub fn count_links(conn: &Connection, student_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM student_guardians WHERE student_id = ?1",
+        params![student_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn link(
+    conn: &Connection,
+    student_id: i64,
+    guardian_id: i64,
+    is_primary_contact: bool,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO student_guardians (student_id, guardian_id, is_primary_contact)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(student_id, guardian_id) DO UPDATE SET is_primary_contact = excluded.is_primary_contact",
+        params![student_id, guardian_id, is_primary_contact],
+    )?;
+    Ok(())
+}
+
+pub fn unlink(conn: &Connection, student_id: i64, guardian_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM student_guardians WHERE student_id = ?1 AND guardian_id = ?2",
+        params![student_id, guardian_id],
+    )
+}
+
+pub fn clear_primary(conn: &Connection, student_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE student_guardians SET is_primary_contact = 0 WHERE student_id = ?1",
+        params![student_id],
+    )?;
+    Ok(())
+}
+
+/// Promotes the longest-linked remaining guardian when a student has no primary contact.
+pub fn ensure_primary(conn: &Connection, student_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE student_guardians SET is_primary_contact = 1
+         WHERE student_id = ?1
+           AND NOT EXISTS (
+               SELECT 1 FROM student_guardians WHERE student_id = ?1 AND is_primary_contact = 1
+           )
+           AND guardian_id = (
+               SELECT guardian_id FROM student_guardians WHERE student_id = ?1
+               ORDER BY created_at, guardian_id LIMIT 1
+           )",
+        params![student_id],
+    )?;
+    Ok(())
+}
+
+pub fn find_for_student(conn: &Connection, student_id: i64) -> rusqlite::Result<Vec<StudentGuardian>> {
+    let query = format!(
+        "SELECT {}, sg.is_primary_contact
+         FROM student_guardians sg
+         JOIN guardians g ON g.id = sg.guardian_id
+         WHERE sg.student_id = ?1
+         ORDER BY sg.is_primary_contact DESC, g.id",
+        GUARDIAN_COLUMNS
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let guardians = stmt
+        .query_map(params![student_id], |row| {
+            Ok(StudentGuardian {
+                guardian: map_guardian(row)?,
+                is_primary_contact: row.get(14)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(guardians)
+}
+
+/// Other students sharing at least one guardian with `student_id`, eldest first.
+pub fn find_siblings(conn: &Connection, student_id: i64) -> rusqlite::Result<Vec<Sibling>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT s.id, s.gr_number, s.full_name, s.dob, s.class_id, c.class_name
+         FROM student_guardians mine
+         JOIN student_guardians theirs ON theirs.guardian_id = mine.guardian_id
+         JOIN students s ON s.id = theirs.student_id
+         LEFT JOIN classes c ON c.id = s.class_id
+         WHERE mine.student_id = ?1 AND theirs.student_id != ?1
+         ORDER BY s.dob IS NULL, s.dob, s.id",
+    )?;
+    let siblings = stmt
+        .query_map(params![student_id], |row| {
+            Ok(Sibling {
+                student_id: row.get(0)?,
+                gr_number: row.get(1)?,
+                full_name: row.get(2)?,
+                dob: row.get(3)?,
+                class_id: row.get(4)?,
+                class_name: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(siblings)
+}
+
+/// Inline parent details still kept on `students`, used to seed guardian records.
+pub struct InlineParents {
+    pub student_id: i64,
+    pub father_name: Option<String>,
+    pub father_occupation: Option<String>,
+    pub mother_name: Option<String>,
+    pub mother_occupation: Option<String>,
+    pub annual_income: Option<f64>,
+    pub mobile_number: Option<String>,
+    pub address: Option<String>,
+    pub guardian_contact_info: Option<String>,
+}
+
+fn map_inline_parents(row: &Row) -> rusqlite::Result<InlineParents> {
+    Ok(InlineParents {
+        student_id: row.get(0)?,
+        father_name: row.get(1)?,
+        father_occupation: row.get(2)?,
+        mother_name: row.get(3)?,
+        mother_occupation: row.get(4)?,
+        annual_income: row.get(5)?,
+        mobile_number: row.get(6)?,
+        address: row.get(7)?,
+        guardian_contact_info: row.get(8)?,
+    })
+}
+
+const INLINE_PARENT_COLUMNS: &str = "s.id, s.father_name, s.father_occupation, s.mother_name,
+        s.mother_occupation, s.annual_income, s.mobile_number, s.address, s.guardian_contact_info";
+
+pub fn find_inline_parents(conn: &Connection, student_id: i64) -> rusqlite::Result<Option<InlineParents>> {
+    let query = format!("SELECT {} FROM students s WHERE s.id = ?1", INLINE_PARENT_COLUMNS);
+    conn.query_row(&query, params![student_id], map_inline_parents)
+        .optional()
+}
+
+/// Students that have no guardian linked yet.
+pub fn find_unlinked_inline_parents(conn: &Connection) -> rusqlite::Result<Vec<InlineParents>> {
+    let query = format!(
+        "SELECT {} FROM students s
+         WHERE NOT EXISTS (SELECT 1 FROM student_guardians sg WHERE sg.student_id = s.id)
+         ORDER BY s.id",
+        INLINE_PARENT_COLUMNS
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt
+        .query_map([], map_inline_parents)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Copies a father's or mother's details back onto the legacy columns of every linked student.
+pub fn sync_inline_parent_columns(
+    conn: &Connection,
+    guardian_id: i64,
+    guardian: &Guardian,
+) -> rusqlite::Result<()> {
+    match guardian.relation_type.as_str() {
+        "father" => {
+            conn.execute(
+                "UPDATE students SET
+                    father_name = ?1,
+                    father_occupation = ?2,
+                    annual_income = COALESCE(?3, annual_income),
+                    updated_at = CURRENT_TIMESTAMP
+                 WHERE id IN (SELECT student_id FROM student_guardians WHERE guardian_id = ?4)",
+                params![guardian.full_name, guardian.occupation, guardian.annual_income, guardian_id],
+            )?;
+        }
+        "mother" => {
+            conn.execute(
+                "UPDATE students SET
+                    mother_name = ?1,
+                    mother_occupation = ?2,
+                    updated_at = CURRENT_TIMESTAMP
+                 WHERE id IN (SELECT student_id FROM student_guardians WHERE guardian_id = ?3)",
+                params![guardian.full_name, guardian.occupation, guardian_id],
+            )?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+pub fn inline_parents_migrated(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'guardians_migrated')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+pub fn mark_inline_parents_migrated(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS guardians_migrated (id INTEGER PRIMARY KEY)",
+        [],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,429 @@
+use crate::health::{HealthCheckup, InfirmaryVisit, MedicalAlert, StudentVaccination, VaccineDose};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const ON_ROLL: &str = "(s.status IS NULL OR s.status IN ('active', 'suspended'))";
+
+const SELECT_CHECKUPS: &str = "SELECT id, student_id, checkup_date, weight_kg, height_cm,
+        hb_range, vision, dental, remarks, checked_by
+     FROM health_checkups";
+
+const SELECT_VISITS: &str = "SELECT v.id, v.student_id, v.visit_date, v.visit_time,
+        v.complaint, v.temperature_c, v.treatment, v.outcome, v.guardian_informed,
+        v.attended_by, s.full_name, c.class_name, NULLIF(TRIM(s.medical_conditions), '')
+     FROM infirmary_visits v
+     JOIN students s ON s.id = v.student_id
+     LEFT JOIN classes c ON c.id = s.class_id";
+
+fn map_checkup(row: &Row) -> rusqlite::Result<HealthCheckup> {
+    Ok(HealthCheckup {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        checkup_date: Some(row.get(2)?),
+        weight_kg: row.get(3)?,
+        height_cm: row.get(4)?,
+        hb_range: row.get(5)?,
+        vision: row.get(6)?,
+        dental: row.get(7)?,
+        remarks: row.get(8)?,
+        checked_by: row.get(9)?,
+        age_months: None,
+        bmi: None,
+        bmi_percentile: None,
+        bmi_category: None,
+    })
+}
+
+fn map_dose(row: &Row) -> rusqlite::Result<VaccineDose> {
+    Ok(VaccineDose {
+        id: Some(row.get(0)?),
+        vaccine: row.get(1)?,
+        dose_number: row.get(2)?,
+        due_age_months: row.get(3)?,
+        active: row.get(4)?,
+    })
+}
+
+fn map_vaccination(row: &Row) -> rusqlite::Result<StudentVaccination> {
+    Ok(StudentVaccination {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        vaccine: row.get(2)?,
+        dose_number: row.get(3)?,
+        given_on: Some(row.get(4)?),
+        batch_number: row.get(5)?,
+        given_at: row.get(6)?,
+        remarks: row.get(7)?,
+        due_date: None,
+        status: None,
+    })
+}
+
+fn map_visit(row: &Row) -> rusqlite::Result<InfirmaryVisit> {
+    Ok(InfirmaryVisit {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        visit_date: Some(row.get(2)?),
+        visit_time: Some(row.get(3)?),
+        complaint: row.get(4)?,
+        temperature_c: row.get(5)?,
+        treatment: row.get(6)?,
+        outcome: Some(row.get(7)?),
+        guardian_informed: row.get(8)?,
+        attended_by: row.get(9)?,
+        student_name: Some(row.get(10)?),
+        class_name: row.get(11)?,
+        medical_alert: row.get(12)?,
+    })
+}
+
+pub fn find_checkup(conn: &Connection, id: i64) -> rusqlite::Result<Option<HealthCheckup>> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_CHECKUPS),
+        params![id],
+        map_checkup,
+    )
+    .optional()
+}
+
+/// Checkups of one student, oldest first.
+pub fn find_checkups(conn: &Connection, student_id: i64) -> rusqlite::Result<Vec<HealthCheckup>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE student_id = ?1 ORDER BY checkup_date, id",
+        SELECT_CHECKUPS
+    ))?;
+    let checkups = stmt
+        .query_map(params![student_id], map_checkup)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(checkups)
+}
+
+pub fn insert_checkup(conn: &Connection, checkup: &HealthCheckup) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO health_checkups (student_id, checkup_date, weight_kg, height_cm, hb_range,
+            vision, dental, remarks, checked_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            checkup.student_id,
+            checkup.checkup_date,
+            checkup.weight_kg,
+            checkup.height_cm,
+            checkup.hb_range,
+            checkup.vision,
+            checkup.dental,
+            checkup.remarks,
+            checkup.checked_by
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_checkup(
+    conn: &Connection,
+    id: i64,
+    checkup: &HealthCheckup,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE health_checkups SET checkup_date = ?1, weight_kg = ?2, height_cm = ?3,
+            hb_range = ?4, vision = ?5, dental = ?6, remarks = ?7, checked_by = ?8
+         WHERE id = ?9",
+        params![
+            checkup.checkup_date,
+            checkup.weight_kg,
+            checkup.height_cm,
+            checkup.hb_range,
+            checkup.vision,
+            checkup.dental,
+            checkup.remarks,
+            checkup.checked_by,
+            id
+        ],
+    )
+}
+
+pub fn delete_checkup(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM health_checkups WHERE id = ?1", params![id])
+}
+
+/// Copies the measurements of the student's latest checkup onto the student record,
+/// keeping what is there when the checkup left a measurement out.
+pub fn sync_latest_measurements(conn: &Connection, student_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            weight_kg = COALESCE(h.weight_kg, students.weight_kg),
+            height_cm = COALESCE(h.height_cm, students.height_cm),
+            hb_range = COALESCE(h.hb_range, students.hb_range)
+         FROM (SELECT weight_kg, height_cm, hb_range FROM health_checkups
+               WHERE student_id = ?1 ORDER BY checkup_date DESC, id DESC LIMIT 1) AS h
+         WHERE students.id = ?1",
+        params![student_id],
+    )
+}
+
+pub fn find_schedule(conn: &Connection, active_only: bool) -> rusqlite::Result<Vec<VaccineDose>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, vaccine, dose_number, due_age_months, active FROM vaccine_schedule
+         WHERE ?1 = 0 OR active = 1
+         ORDER BY due_age_months, vaccine COLLATE NOCASE, dose_number",
+    )?;
+    let doses = stmt
+        .query_map(params![active_only], map_dose)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(doses)
+}
+
+/// Id of the scheduled dose with this vaccine and number, whatever the case of the name.
+pub fn find_schedule_dose_id(
+    conn: &Connection,
+    vaccine: &str,
+    dose_number: u32,
+) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM vaccine_schedule
+         WHERE vaccine = ?1 COLLATE NOCASE AND dose_number = ?2",
+        params![vaccine, dose_number],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn insert_schedule_dose(conn: &Connection, dose: &VaccineDose) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO vaccine_schedule (vaccine, dose_number, due_age_months, active)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            dose.vaccine,
+            dose.dose_number,
+            dose.due_age_months,
+            dose.active
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_schedule_dose(
+    conn: &Connection,
+    id: i64,
+    dose: &VaccineDose,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE vaccine_schedule SET vaccine = ?1, dose_number = ?2, due_age_months = ?3,
+            active = ?4
+         WHERE id = ?5",
+        params![
+            dose.vaccine,
+            dose.dose_number,
+            dose.due_age_months,
+            dose.active,
+            id
+        ],
+    )
+}
+
+/// Doses one student has had, in the order given.
+pub fn find_vaccinations(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Vec<StudentVaccination>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, student_id, vaccine, dose_number, given_on, batch_number, given_at, remarks
+         FROM student_vaccinations
+         WHERE student_id = ?1
+         ORDER BY given_on, vaccine COLLATE NOCASE, dose_number",
+    )?;
+    let vaccinations = stmt
+        .query_map(params![student_id], map_vaccination)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(vaccinations)
+}
+
+pub fn vaccination_recorded(
+    conn: &Connection,
+    student_id: i64,
+    vaccine: &str,
+    dose_number: u32,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM student_vaccinations
+         WHERE student_id = ?1 AND vaccine = ?2 COLLATE NOCASE AND dose_number = ?3)",
+        params![student_id, vaccine, dose_number],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_vaccination(
+    conn: &Connection,
+    vaccination: &StudentVaccination,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO student_vaccinations (student_id, vaccine, dose_number, given_on,
+            batch_number, given_at, remarks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            vaccination.student_id,
+            vaccination.vaccine,
+            vaccination.dose_number,
+            vaccination.given_on,
+            vaccination.batch_number,
+            vaccination.given_at,
+            vaccination.remarks
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_vaccination(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM student_vaccinations WHERE id = ?1",
+        params![id],
+    )
+}
+
+/// A scheduled dose a student has not had, with what is needed to work out its due date.
+pub struct PendingDose {
+    pub student_id: i64,
+    pub student_name: String,
+    pub class_name: Option<String>,
+    pub mobile_number: Option<String>,
+    pub dob: String,
+    pub vaccine: String,
+    pub dose_number: u32,
+    pub due_age_months: u32,
+}
+
+/// Active scheduled doses missing from the records of students on the roll with a date of
+/// birth, optionally in one class.
+pub fn find_pending_doses(
+    conn: &Connection,
+    class_id: Option<i64>,
+) -> rusqlite::Result<Vec<PendingDose>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.full_name, c.class_name, s.mobile_number, s.dob, d.vaccine,
+            d.dose_number, d.due_age_months
+         FROM students s
+         JOIN vaccine_schedule d ON d.active = 1
+         LEFT JOIN classes c ON c.id = s.class_id
+         WHERE {} AND NULLIF(TRIM(s.dob), '') IS NOT NULL
+           AND (?1 IS NULL OR s.class_id = ?1)
+           AND NOT EXISTS (SELECT 1 FROM student_vaccinations v
+                           WHERE v.student_id = s.id AND v.vaccine = d.vaccine COLLATE NOCASE
+                             AND v.dose_number = d.dose_number)
+         ORDER BY c.class_name, s.full_name, d.due_age_months, d.vaccine COLLATE NOCASE,
+            d.dose_number",
+        ON_ROLL
+    ))?;
+    let doses = stmt
+        .query_map(params![class_id], |row| {
+            Ok(PendingDose {
+                student_id: row.get(0)?,
+                student_name: row.get(1)?,
+                class_name: row.get(2)?,
+                mobile_number: row.get(3)?,
+                dob: row.get(4)?,
+                vaccine: row.get(5)?,
+                dose_number: row.get(6)?,
+                due_age_months: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(doses)
+}
+
+pub fn insert_visit(conn: &Connection, visit: &InfirmaryVisit) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO infirmary_visits (student_id, visit_date, visit_time, complaint,
+            temperature_c, treatment, outcome, guardian_informed, attended_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            visit.student_id,
+            visit.visit_date,
+            visit.visit_time,
+            visit.complaint,
+            visit.temperature_c,
+            visit.treatment,
+            visit.outcome,
+            visit.guardian_informed,
+            visit.attended_by
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Infirmary visits between two dates, latest first.
+pub fn find_visits(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<InfirmaryVisit>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR v.student_id = ?1) AND v.visit_date BETWEEN ?2 AND ?3
+         ORDER BY v.visit_date DESC, v.visit_time DESC, v.id DESC",
+        SELECT_VISITS
+    ))?;
+    let visits = stmt
+        .query_map(params![student_id, from, to], map_visit)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(visits)
+}
+
+/// Students on the roll with allergies or chronic conditions recorded, by class and roll.
+pub fn find_medical_alerts(
+    conn: &Connection,
+    class_id: Option<i64>,
+) -> rusqlite::Result<Vec<MedicalAlert>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.full_name, c.class_name, s.roll_number, s.blood_group,
+            TRIM(s.medical_conditions), s.emergency_contact_person, s.emergency_contact
+         FROM students s
+         LEFT JOIN classes c ON c.id = s.class_id
+         WHERE {} AND NULLIF(TRIM(s.medical_conditions), '') IS NOT NULL
+           AND (?1 IS NULL OR s.class_id = ?1)
+         ORDER BY c.class_name, CAST(s.roll_number AS INTEGER), s.full_name",
+        ON_ROLL
+    ))?;
+    let alerts = stmt
+        .query_map(params![class_id], |row| {
+            Ok(MedicalAlert {
+                student_id: row.get(0)?,
+                student_name: row.get(1)?,
+                class_name: row.get(2)?,
+                roll_number: row.get(3)?,
+                blood_group: row.get(4)?,
+                medical_conditions: row.get(5)?,
+                emergency_contact_person: row.get(6)?,
+                emergency_contact: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(alerts)
+}
+
+/// A student on the roll of a class, in roll order, with their medical alert if any.
+pub struct RegisterStudent {
+    pub roll_number: Option<String>,
+    pub full_name: String,
+    pub medical_alert: Option<String>,
+}
+
+pub fn find_register_students(
+    conn: &Connection,
+    class_id: i64,
+) -> rusqlite::Result<Vec<RegisterStudent>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.roll_number, s.full_name, NULLIF(TRIM(s.medical_conditions), '')
+         FROM students s
+         WHERE {} AND s.class_id = ?1
+         ORDER BY CAST(s.roll_number AS INTEGER), s.full_name",
+        ON_ROLL
+    ))?;
+    let students = stmt
+        .query_map(params![class_id], |row| {
+            Ok(RegisterStudent {
+                roll_number: row.get(0)?,
+                full_name: row.get(1)?,
+                medical_alert: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(students)
+}
@@ -0,0 +1,237 @@
+use crate::homework::{Homework, HomeworkAttachment, HomeworkSubmission};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_HOMEWORK: &str = "SELECT h.id, h.class_id, h.subject_id, h.teacher_id, h.title,
+        h.description, h.assigned_on, h.due_date, c.class_name, s.name, st.name
+     FROM homework h
+     JOIN classes c ON c.id = h.class_id
+     JOIN subjects s ON s.id = h.subject_id
+     LEFT JOIN staff st ON st.id = h.teacher_id";
+
+fn map_homework(row: &Row) -> rusqlite::Result<Homework> {
+    Ok(Homework {
+        id: Some(row.get(0)?),
+        class_id: row.get(1)?,
+        subject_id: row.get(2)?,
+        teacher_id: row.get(3)?,
+        title: row.get(4)?,
+        description: row.get(5)?,
+        assigned_on: Some(row.get(6)?),
+        due_date: row.get(7)?,
+        class_name: Some(row.get(8)?),
+        subject_name: Some(row.get(9)?),
+        teacher_name: row.get(10)?,
+        attachments: Vec::new(),
+    })
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Homework>> {
+    conn.query_row(
+        &format!("{} WHERE h.id = ?1", SELECT_HOMEWORK),
+        params![id],
+        map_homework,
+    )
+    .optional()
+}
+
+/// Homework of the class due within `from..=to`, latest due first.
+pub fn find_for_class(
+    conn: &Connection,
+    class_id: i64,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<Homework>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE h.class_id = ?1 AND h.due_date BETWEEN ?2 AND ?3
+         ORDER BY h.due_date DESC, s.name, h.id DESC",
+        SELECT_HOMEWORK
+    ))?;
+    let homework = stmt
+        .query_map(params![class_id, from, to], map_homework)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(homework)
+}
+
+pub fn insert(conn: &Connection, homework: &Homework) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO homework
+            (class_id, subject_id, teacher_id, title, description, assigned_on, due_date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            homework.class_id,
+            homework.subject_id,
+            homework.teacher_id,
+            homework.title,
+            homework.description,
+            homework.assigned_on,
+            homework.due_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update(conn: &Connection, id: i64, homework: &Homework) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE homework SET class_id = ?1, subject_id = ?2, teacher_id = ?3, title = ?4,
+            description = ?5, assigned_on = ?6, due_date = ?7
+         WHERE id = ?8",
+        params![
+            homework.class_id,
+            homework.subject_id,
+            homework.teacher_id,
+            homework.title,
+            homework.description,
+            homework.assigned_on,
+            homework.due_date,
+            id
+        ],
+    )
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM homework WHERE id = ?1", params![id])
+}
+
+pub fn count_for_subject(conn: &Connection, subject_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM homework WHERE subject_id = ?1",
+        params![subject_id],
+        |row| row.get(0),
+    )
+}
+
+fn map_attachment(row: &Row) -> rusqlite::Result<HomeworkAttachment> {
+    Ok(HomeworkAttachment {
+        id: row.get(0)?,
+        homework_id: row.get(1)?,
+        file_name: row.get(2)?,
+        original_name: row.get(3)?,
+        uploaded_at: row.get(4)?,
+    })
+}
+
+pub fn find_attachments(
+    conn: &Connection,
+    homework_id: i64,
+) -> rusqlite::Result<Vec<HomeworkAttachment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, homework_id, file_name, original_name, uploaded_at
+         FROM homework_attachments WHERE homework_id = ?1 ORDER BY id",
+    )?;
+    let attachments = stmt
+        .query_map(params![homework_id], map_attachment)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(attachments)
+}
+
+pub fn find_attachment(conn: &Connection, id: i64) -> rusqlite::Result<Option<HomeworkAttachment>> {
+    conn.query_row(
+        "SELECT id, homework_id, file_name, original_name, uploaded_at
+         FROM homework_attachments WHERE id = ?1",
+        params![id],
+        map_attachment,
+    )
+    .optional()
+}
+
+pub fn insert_attachment(
+    conn: &Connection,
+    homework_id: i64,
+    original_name: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO homework_attachments (homework_id, original_name) VALUES (?1, ?2)",
+        params![homework_id, original_name],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_attachment_file(conn: &Connection, id: i64, file_name: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE homework_attachments SET file_name = ?1 WHERE id = ?2",
+        params![file_name, id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_attachment(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM homework_attachments WHERE id = ?1",
+        params![id],
+    )
+}
+
+/// Students on the roll of the class, plus anyone else with an entry for the homework, in
+/// roll-number order. Students without an entry have an empty status.
+pub fn find_submissions(
+    conn: &Connection,
+    homework_id: i64,
+    class_id: i64,
+) -> rusqlite::Result<Vec<HomeworkSubmission>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.full_name, s.roll_number, COALESCE(hs.status, ''), hs.submitted_on,
+                hs.grade, hs.remarks
+         FROM students s
+         LEFT JOIN homework_submissions hs ON hs.student_id = s.id AND hs.homework_id = ?1
+         WHERE hs.student_id IS NOT NULL
+            OR (s.class_id = ?2 AND (s.status IS NULL OR s.status IN ('active', 'suspended')))
+         ORDER BY CAST(s.roll_number AS INTEGER), s.full_name, s.id",
+    )?;
+    let submissions = stmt
+        .query_map(params![homework_id, class_id], |row| {
+            Ok(HomeworkSubmission {
+                homework_id,
+                student_id: row.get(0)?,
+                student_name: row.get(1)?,
+                roll_number: row.get(2)?,
+                status: row.get(3)?,
+                submitted_on: row.get(4)?,
+                grade: row.get(5)?,
+                remarks: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(submissions)
+}
+
+pub fn upsert_submission(
+    conn: &Connection,
+    homework_id: i64,
+    student_id: i64,
+    status: &str,
+    submitted_on: Option<&str>,
+    grade: Option<&str>,
+    remarks: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO homework_submissions
+            (homework_id, student_id, status, submitted_on, grade, remarks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(homework_id, student_id) DO UPDATE SET
+            status = excluded.status,
+            submitted_on = excluded.submitted_on,
+            grade = excluded.grade,
+            remarks = excluded.remarks,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            homework_id,
+            student_id,
+            status,
+            submitted_on,
+            grade,
+            remarks
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_submission(
+    conn: &Connection,
+    homework_id: i64,
+    student_id: i64,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM homework_submissions WHERE homework_id = ?1 AND student_id = ?2",
+        params![homework_id, student_id],
+    )
+}
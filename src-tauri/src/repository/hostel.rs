@@ -0,0 +1,547 @@
+use crate::hostel::{
+    BedAllotment, Hostel, HostelBed, HostelMovement, HostelOccupancy, HostelRoom, HostelVisitor,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_HOSTELS: &str = "SELECT h.id, h.name, h.hostel_type, h.warden_id, h.boarding_fee,
+        st.name
+     FROM hostels h
+     LEFT JOIN staff st ON st.id = h.warden_id";
+
+const SELECT_ALLOTMENTS: &str = "SELECT a.id, a.bed_id, a.student_id, a.allotted_on,
+        a.vacated_on, a.vacate_reason, s.full_name, c.class_name, h.id, h.name, r.floor,
+        r.room_number, b.label
+     FROM hostel_allotments a
+     JOIN students s ON s.id = a.student_id
+     LEFT JOIN classes c ON c.id = s.class_id
+     JOIN hostel_beds b ON b.id = a.bed_id
+     JOIN hostel_rooms r ON r.id = b.room_id
+     JOIN hostels h ON h.id = r.hostel_id";
+
+const SELECT_MOVEMENTS: &str = "SELECT m.id, m.student_id, m.movement_type, m.out_at,
+        m.expected_back, m.returned_at, m.purpose, m.escorted_by, m.approved_by, s.full_name
+     FROM hostel_movements m
+     JOIN students s ON s.id = m.student_id";
+
+const SELECT_VISITORS: &str = "SELECT v.id, v.student_id, v.visitor_name, v.relation, v.phone,
+        v.visited_on, v.in_time, v.out_time, v.purpose, s.full_name
+     FROM hostel_visitors v
+     JOIN students s ON s.id = v.student_id";
+
+fn map_hostel(row: &Row) -> rusqlite::Result<Hostel> {
+    Ok(Hostel {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        hostel_type: Some(row.get(2)?),
+        warden_id: row.get(3)?,
+        boarding_fee: row.get(4)?,
+        warden_name: row.get(5)?,
+    })
+}
+
+fn map_allotment(row: &Row) -> rusqlite::Result<BedAllotment> {
+    Ok(BedAllotment {
+        id: Some(row.get(0)?),
+        bed_id: row.get(1)?,
+        student_id: row.get(2)?,
+        allotted_on: Some(row.get(3)?),
+        vacated_on: row.get(4)?,
+        vacate_reason: row.get(5)?,
+        student_name: Some(row.get(6)?),
+        class_name: row.get(7)?,
+        hostel_id: Some(row.get(8)?),
+        hostel_name: Some(row.get(9)?),
+        floor: Some(row.get(10)?),
+        room_number: Some(row.get(11)?),
+        bed_label: Some(row.get(12)?),
+    })
+}
+
+fn map_movement(row: &Row) -> rusqlite::Result<HostelMovement> {
+    Ok(HostelMovement {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        movement_type: row.get(2)?,
+        out_at: Some(row.get(3)?),
+        expected_back: row.get(4)?,
+        returned_at: row.get(5)?,
+        purpose: row.get(6)?,
+        escorted_by: row.get(7)?,
+        approved_by: row.get(8)?,
+        student_name: Some(row.get(9)?),
+    })
+}
+
+fn map_visitor(row: &Row) -> rusqlite::Result<HostelVisitor> {
+    Ok(HostelVisitor {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        visitor_name: row.get(2)?,
+        relation: row.get(3)?,
+        phone: row.get(4)?,
+        visited_on: Some(row.get(5)?),
+        in_time: Some(row.get(6)?),
+        out_time: row.get(7)?,
+        purpose: row.get(8)?,
+        student_name: Some(row.get(9)?),
+    })
+}
+
+pub fn find_hostels(conn: &Connection) -> rusqlite::Result<Vec<Hostel>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} ORDER BY h.name COLLATE NOCASE",
+        SELECT_HOSTELS
+    ))?;
+    let hostels = stmt
+        .query_map([], map_hostel)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(hostels)
+}
+
+pub fn find_hostel(conn: &Connection, id: i64) -> rusqlite::Result<Option<Hostel>> {
+    conn.query_row(
+        &format!("{} WHERE h.id = ?1", SELECT_HOSTELS),
+        params![id],
+        map_hostel,
+    )
+    .optional()
+}
+
+pub fn hostel_name_taken(
+    conn: &Connection,
+    name: &str,
+    except_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM hostels
+          WHERE name = ?1 COLLATE NOCASE AND (?2 IS NULL OR id != ?2))",
+        params![name, except_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_hostel(
+    conn: &Connection,
+    hostel: &Hostel,
+    hostel_type: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostels (name, hostel_type, warden_id, boarding_fee)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            hostel.name,
+            hostel_type,
+            hostel.warden_id,
+            hostel.boarding_fee
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_hostel(
+    conn: &Connection,
+    id: i64,
+    hostel: &Hostel,
+    hostel_type: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostels SET name = ?1, hostel_type = ?2, warden_id = ?3, boarding_fee = ?4
+         WHERE id = ?5",
+        params![
+            hostel.name,
+            hostel_type,
+            hostel.warden_id,
+            hostel.boarding_fee,
+            id
+        ],
+    )
+}
+
+/// Deletes the hostel with its rooms, beds and past allotments.
+pub fn delete_hostel(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM hostel_allotments WHERE bed_id IN (
+            SELECT b.id FROM hostel_beds b
+            JOIN hostel_rooms r ON r.id = b.room_id
+            WHERE r.hostel_id = ?1)",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM hostels WHERE id = ?1", params![id])
+}
+
+/// Current boarders of the hostel, or of the room when `room_id` is set.
+pub fn count_boarders(
+    conn: &Connection,
+    hostel_id: Option<i64>,
+    room_id: Option<i64>,
+) -> rusqlite::Result<u32> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM hostel_allotments a
+         JOIN hostel_beds b ON b.id = a.bed_id
+         JOIN hostel_rooms r ON r.id = b.room_id
+         WHERE a.vacated_on IS NULL
+           AND (?1 IS NULL OR r.hostel_id = ?1)
+           AND (?2 IS NULL OR r.id = ?2)",
+        params![hostel_id, room_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_rooms(conn: &Connection, hostel_id: i64) -> rusqlite::Result<Vec<HostelRoom>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, hostel_id, floor, room_number FROM hostel_rooms
+         WHERE hostel_id = ?1
+         ORDER BY floor, room_number",
+    )?;
+    let rooms = stmt
+        .query_map(params![hostel_id], |row| {
+            Ok(HostelRoom {
+                id: Some(row.get(0)?),
+                hostel_id: row.get(1)?,
+                floor: row.get(2)?,
+                room_number: row.get(3)?,
+                beds: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rooms)
+}
+
+/// Hostel id of the room.
+pub fn find_room_hostel(conn: &Connection, room_id: i64) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT hostel_id FROM hostel_rooms WHERE id = ?1",
+        params![room_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Beds of the room with their current boarder.
+pub fn find_beds(conn: &Connection, room_id: i64) -> rusqlite::Result<Vec<HostelBed>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.id, b.label, a.student_id, s.full_name
+         FROM hostel_beds b
+         LEFT JOIN hostel_allotments a ON a.bed_id = b.id AND a.vacated_on IS NULL
+         LEFT JOIN students s ON s.id = a.student_id
+         WHERE b.room_id = ?1
+         ORDER BY b.label",
+    )?;
+    let beds = stmt
+        .query_map(params![room_id], |row| {
+            Ok(HostelBed {
+                id: Some(row.get(0)?),
+                label: row.get(1)?,
+                student_id: row.get(2)?,
+                student_name: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(beds)
+}
+
+pub fn room_number_taken(
+    conn: &Connection,
+    hostel_id: i64,
+    room_number: &str,
+    except_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM hostel_rooms
+          WHERE hostel_id = ?1 AND room_number = ?2 COLLATE NOCASE
+            AND (?3 IS NULL OR id != ?3))",
+        params![hostel_id, room_number, except_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_room(conn: &Connection, room: &HostelRoom) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostel_rooms (hostel_id, floor, room_number) VALUES (?1, ?2, ?3)",
+        params![room.hostel_id, room.floor, room.room_number],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_room(conn: &Connection, id: i64, room: &HostelRoom) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostel_rooms SET floor = ?1, room_number = ?2 WHERE id = ?3",
+        params![room.floor, room.room_number, id],
+    )
+}
+
+/// Deletes the room with its beds and their past allotments.
+pub fn delete_room(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM hostel_allotments
+         WHERE bed_id IN (SELECT id FROM hostel_beds WHERE room_id = ?1)",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM hostel_rooms WHERE id = ?1", params![id])
+}
+
+pub fn insert_bed(conn: &Connection, room_id: i64, label: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostel_beds (room_id, label) VALUES (?1, ?2)",
+        params![room_id, label],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_bed(conn: &Connection, id: i64, label: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostel_beds SET label = ?1 WHERE id = ?2",
+        params![label, id],
+    )
+}
+
+/// Deletes the bed with its past allotments.
+pub fn delete_bed(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM hostel_allotments WHERE bed_id = ?1",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM hostel_beds WHERE id = ?1", params![id])
+}
+
+/// Hostel type and id of the bed, with its current boarder if any.
+pub fn find_bed_details(
+    conn: &Connection,
+    bed_id: i64,
+) -> rusqlite::Result<Option<(i64, String, Option<String>)>> {
+    conn.query_row(
+        "SELECT h.id, h.hostel_type, s.full_name
+         FROM hostel_beds b
+         JOIN hostel_rooms r ON r.id = b.room_id
+         JOIN hostels h ON h.id = r.hostel_id
+         LEFT JOIN hostel_allotments a ON a.bed_id = b.id AND a.vacated_on IS NULL
+         LEFT JOIN students s ON s.id = a.student_id
+         WHERE b.id = ?1",
+        params![bed_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .optional()
+}
+
+pub fn find_current_allotment(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Option<BedAllotment>> {
+    conn.query_row(
+        &format!(
+            "{} WHERE a.student_id = ?1 AND a.vacated_on IS NULL",
+            SELECT_ALLOTMENTS
+        ),
+        params![student_id],
+        map_allotment,
+    )
+    .optional()
+}
+
+pub fn find_student_allotments(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Vec<BedAllotment>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE a.student_id = ?1 ORDER BY a.allotted_on DESC, a.id DESC",
+        SELECT_ALLOTMENTS
+    ))?;
+    let allotments = stmt
+        .query_map(params![student_id], map_allotment)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(allotments)
+}
+
+/// Current boarders, by hostel, floor, room and bed.
+pub fn find_boarders(
+    conn: &Connection,
+    hostel_id: Option<i64>,
+) -> rusqlite::Result<Vec<BedAllotment>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE a.vacated_on IS NULL AND (?1 IS NULL OR h.id = ?1)
+         ORDER BY h.name COLLATE NOCASE, r.floor, r.room_number, b.label",
+        SELECT_ALLOTMENTS
+    ))?;
+    let allotments = stmt
+        .query_map(params![hostel_id], map_allotment)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(allotments)
+}
+
+pub fn insert_allotment(
+    conn: &Connection,
+    bed_id: i64,
+    student_id: i64,
+    allotted_on: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostel_allotments (bed_id, student_id, allotted_on) VALUES (?1, ?2, ?3)",
+        params![bed_id, student_id, allotted_on],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn vacate(
+    conn: &Connection,
+    id: i64,
+    vacated_on: &str,
+    reason: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostel_allotments SET vacated_on = ?1, vacate_reason = ?2
+         WHERE id = ?3 AND vacated_on IS NULL",
+        params![vacated_on, reason, id],
+    )
+}
+
+pub fn find_occupancy(conn: &Connection) -> rusqlite::Result<Vec<HostelOccupancy>> {
+    let mut stmt = conn.prepare(
+        "SELECT h.id, h.name,
+            (SELECT COUNT(*) FROM hostel_rooms r WHERE r.hostel_id = h.id),
+            (SELECT COUNT(*) FROM hostel_beds b
+              JOIN hostel_rooms r ON r.id = b.room_id WHERE r.hostel_id = h.id),
+            (SELECT COUNT(*) FROM hostel_allotments a
+              JOIN hostel_beds b ON b.id = a.bed_id
+              JOIN hostel_rooms r ON r.id = b.room_id
+              WHERE r.hostel_id = h.id AND a.vacated_on IS NULL),
+            (SELECT COUNT(*) FROM hostel_movements m
+              JOIN hostel_allotments a ON a.student_id = m.student_id AND a.vacated_on IS NULL
+              JOIN hostel_beds b ON b.id = a.bed_id
+              JOIN hostel_rooms r ON r.id = b.room_id
+              WHERE r.hostel_id = h.id AND m.returned_at IS NULL)
+         FROM hostels h
+         ORDER BY h.name COLLATE NOCASE",
+    )?;
+    let occupancy = stmt
+        .query_map([], |row| {
+            let beds: u32 = row.get(3)?;
+            let occupied: u32 = row.get(4)?;
+            Ok(HostelOccupancy {
+                hostel_id: row.get(0)?,
+                name: row.get(1)?,
+                rooms: row.get(2)?,
+                beds,
+                occupied,
+                vacant: beds.saturating_sub(occupied),
+                out: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(occupancy)
+}
+
+pub fn find_movement(conn: &Connection, id: i64) -> rusqlite::Result<Option<HostelMovement>> {
+    conn.query_row(
+        &format!("{} WHERE m.id = ?1", SELECT_MOVEMENTS),
+        params![id],
+        map_movement,
+    )
+    .optional()
+}
+
+pub fn is_out(conn: &Connection, student_id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM hostel_movements
+          WHERE student_id = ?1 AND returned_at IS NULL)",
+        params![student_id],
+        |row| row.get(0),
+    )
+}
+
+/// Movements that began on a day within `from..=to`, or only those of boarders still out.
+pub fn find_movements(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    movement_type: Option<&str>,
+    out_only: bool,
+) -> rusqlite::Result<Vec<HostelMovement>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE substr(m.out_at, 1, 10) BETWEEN ?1 AND ?2
+           AND (?3 IS NULL OR m.movement_type = ?3)
+           AND (NOT ?4 OR m.returned_at IS NULL)
+         ORDER BY m.out_at DESC, m.id DESC",
+        SELECT_MOVEMENTS
+    ))?;
+    let movements = stmt
+        .query_map(params![from, to, movement_type, out_only], map_movement)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(movements)
+}
+
+pub fn insert_movement(conn: &Connection, movement: &HostelMovement) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostel_movements (student_id, movement_type, out_at, expected_back,
+            purpose, escorted_by, approved_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            movement.student_id,
+            movement.movement_type,
+            movement.out_at,
+            movement.expected_back,
+            movement.purpose,
+            movement.escorted_by,
+            movement.approved_by
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn record_return(conn: &Connection, id: i64, returned_at: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostel_movements SET returned_at = ?1 WHERE id = ?2 AND returned_at IS NULL",
+        params![returned_at, id],
+    )
+}
+
+pub fn find_visitor(conn: &Connection, id: i64) -> rusqlite::Result<Option<HostelVisitor>> {
+    conn.query_row(
+        &format!("{} WHERE v.id = ?1", SELECT_VISITORS),
+        params![id],
+        map_visitor,
+    )
+    .optional()
+}
+
+pub fn find_visitors(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<HostelVisitor>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR v.student_id = ?1) AND v.visited_on BETWEEN ?2 AND ?3
+         ORDER BY v.visited_on DESC, v.in_time DESC, v.id DESC",
+        SELECT_VISITORS
+    ))?;
+    let visitors = stmt
+        .query_map(params![student_id, from, to], map_visitor)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(visitors)
+}
+
+pub fn insert_visitor(conn: &Connection, visitor: &HostelVisitor) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO hostel_visitors (student_id, visitor_name, relation, phone, visited_on,
+            in_time, out_time, purpose)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            visitor.student_id,
+            visitor.visitor_name,
+            visitor.relation,
+            visitor.phone,
+            visitor.visited_on,
+            visitor.in_time,
+            visitor.out_time,
+            visitor.purpose
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_visitor_out_time(conn: &Connection, id: i64, out_time: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE hostel_visitors SET out_time = ?1 WHERE id = ?2",
+        params![out_time, id],
+    )
+}
@@ -0,0 +1,374 @@
+use crate::inventory::{InventoryItem, StockMovement, StockVerification, VerificationLine};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+/// Units on hand: receipts and adjustments add, issues take away.
+const ON_HAND: &str = "COALESCE((SELECT SUM(CASE m.movement_type WHEN 'issue' THEN -m.quantity
+            ELSE m.quantity END)
+        FROM inventory_movements m WHERE m.item_id = i.id), 0)";
+
+fn select_items() -> String {
+    format!(
+        "SELECT i.id, i.code, i.name, i.category, i.item_kind, i.unit, i.purchase_date,
+            i.vendor, i.invoice_number, i.purchase_cost, i.location, i.assigned_to,
+            i.condition, i.reorder_level, i.depreciation_method, i.useful_life_years,
+            i.depreciation_rate, i.salvage_value, {} AS on_hand, st.name
+         FROM inventory_items i
+         LEFT JOIN staff st ON st.id = i.assigned_to",
+        ON_HAND
+    )
+}
+
+const SELECT_VERIFICATIONS: &str = "SELECT v.id, v.label, v.started_on, v.completed_on,
+        v.verified_by, v.status,
+        (SELECT COUNT(*) FROM inventory_verification_lines l WHERE l.verification_id = v.id),
+        (SELECT COUNT(*) FROM inventory_verification_lines l
+          WHERE l.verification_id = v.id AND l.found_quantity IS NOT NULL),
+        (SELECT COUNT(*) FROM inventory_verification_lines l
+          WHERE l.verification_id = v.id AND l.found_quantity != l.expected_quantity)
+     FROM inventory_verifications v";
+
+fn map_item(row: &Row) -> rusqlite::Result<InventoryItem> {
+    let quantity: i64 = row.get(18)?;
+    let reorder_level: Option<u32> = row.get(13)?;
+    let item_kind: String = row.get(4)?;
+    Ok(InventoryItem {
+        id: Some(row.get(0)?),
+        code: row.get(1)?,
+        name: row.get(2)?,
+        category: row.get(3)?,
+        below_reorder: item_kind == "consumable"
+            && reorder_level.is_some_and(|level| quantity <= i64::from(level)),
+        item_kind,
+        unit: row.get(5)?,
+        purchase_date: row.get(6)?,
+        vendor: row.get(7)?,
+        invoice_number: row.get(8)?,
+        purchase_cost: row.get(9)?,
+        location: row.get(10)?,
+        assigned_to: row.get(11)?,
+        condition: Some(row.get(12)?),
+        reorder_level,
+        depreciation_method: row.get(14)?,
+        useful_life_years: row.get(15)?,
+        depreciation_rate: row.get(16)?,
+        salvage_value: row.get(17)?,
+        quantity,
+        assigned_name: row.get(19)?,
+    })
+}
+
+fn map_verification(row: &Row) -> rusqlite::Result<StockVerification> {
+    Ok(StockVerification {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        started_on: row.get(2)?,
+        completed_on: row.get(3)?,
+        verified_by: row.get(4)?,
+        status: row.get(5)?,
+        items: row.get(6)?,
+        counted: row.get(7)?,
+        discrepancies: row.get(8)?,
+    })
+}
+
+pub fn find_items(
+    conn: &Connection,
+    category: Option<&str>,
+    item_kind: Option<&str>,
+) -> rusqlite::Result<Vec<InventoryItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR i.category = ?1) AND (?2 IS NULL OR i.item_kind = ?2)
+         ORDER BY i.name COLLATE NOCASE, i.code",
+        select_items()
+    ))?;
+    let items = stmt
+        .query_map(params![category, item_kind], map_item)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items)
+}
+
+pub fn find_item(conn: &Connection, id: i64) -> rusqlite::Result<Option<InventoryItem>> {
+    conn.query_row(
+        &format!("{} WHERE i.id = ?1", select_items()),
+        params![id],
+        map_item,
+    )
+    .optional()
+}
+
+/// Items that are neither lost nor disposed of.
+pub fn find_items_in_use(conn: &Connection) -> rusqlite::Result<Vec<InventoryItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE i.condition NOT IN ('lost', 'disposed') ORDER BY i.category, i.code",
+        select_items()
+    ))?;
+    let items = stmt
+        .query_map([], map_item)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items)
+}
+
+pub fn code_taken(conn: &Connection, code: &str, except_id: Option<i64>) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM inventory_items
+          WHERE code = ?1 COLLATE NOCASE AND (?2 IS NULL OR id != ?2))",
+        params![code, except_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_item(conn: &Connection, item: &InventoryItem) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO inventory_items (code, name, category, item_kind, unit, purchase_date,
+            vendor, invoice_number, purchase_cost, location, assigned_to, condition,
+            reorder_level, depreciation_method, useful_life_years, depreciation_rate,
+            salvage_value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            item.code,
+            item.name,
+            item.category,
+            item.item_kind,
+            item.unit,
+            item.purchase_date,
+            item.vendor,
+            item.invoice_number,
+            item.purchase_cost,
+            item.location,
+            item.assigned_to,
+            item.condition,
+            item.reorder_level,
+            item.depreciation_method,
+            item.useful_life_years,
+            item.depreciation_rate,
+            item.salvage_value
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_item(conn: &Connection, id: i64, item: &InventoryItem) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE inventory_items SET code = ?1, name = ?2, category = ?3, item_kind = ?4,
+            unit = ?5, purchase_date = ?6, vendor = ?7, invoice_number = ?8,
+            purchase_cost = ?9, location = ?10, assigned_to = ?11, condition = ?12,
+            reorder_level = ?13, depreciation_method = ?14, useful_life_years = ?15,
+            depreciation_rate = ?16, salvage_value = ?17
+         WHERE id = ?18",
+        params![
+            item.code,
+            item.name,
+            item.category,
+            item.item_kind,
+            item.unit,
+            item.purchase_date,
+            item.vendor,
+            item.invoice_number,
+            item.purchase_cost,
+            item.location,
+            item.assigned_to,
+            item.condition,
+            item.reorder_level,
+            item.depreciation_method,
+            item.useful_life_years,
+            item.depreciation_rate,
+            item.salvage_value,
+            id
+        ],
+    )
+}
+
+pub fn set_condition(conn: &Connection, id: i64, condition: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE inventory_items SET condition = ?1 WHERE id = ?2",
+        params![condition, id],
+    )
+}
+
+pub fn delete_item(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM inventory_items WHERE id = ?1", params![id])
+}
+
+/// Whether the item was part of any stock verification.
+pub fn was_verified(conn: &Connection, item_id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM inventory_verification_lines WHERE item_id = ?1)",
+        params![item_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_movement(conn: &Connection, movement: &StockMovement) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO inventory_movements (item_id, movement_type, quantity, moved_on,
+            reference, issued_to, remarks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            movement.item_id,
+            movement.movement_type,
+            movement.quantity,
+            movement.moved_on,
+            movement.reference,
+            movement.issued_to,
+            movement.remarks
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_movements(conn: &Connection, item_id: i64) -> rusqlite::Result<Vec<StockMovement>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.id, m.item_id, m.movement_type, m.quantity, m.moved_on, m.reference,
+            m.issued_to, m.remarks, i.name
+         FROM inventory_movements m
+         JOIN inventory_items i ON i.id = m.item_id
+         WHERE m.item_id = ?1
+         ORDER BY m.moved_on DESC, m.id DESC",
+    )?;
+    let movements = stmt
+        .query_map(params![item_id], |row| {
+            Ok(StockMovement {
+                id: Some(row.get(0)?),
+                item_id: row.get(1)?,
+                movement_type: row.get(2)?,
+                quantity: row.get(3)?,
+                moved_on: Some(row.get(4)?),
+                reference: row.get(5)?,
+                issued_to: row.get(6)?,
+                remarks: row.get(7)?,
+                item_name: Some(row.get(8)?),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(movements)
+}
+
+pub fn find_verifications(conn: &Connection) -> rusqlite::Result<Vec<StockVerification>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} ORDER BY v.started_on DESC, v.id DESC",
+        SELECT_VERIFICATIONS
+    ))?;
+    let verifications = stmt
+        .query_map([], map_verification)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(verifications)
+}
+
+pub fn find_verification(
+    conn: &Connection,
+    id: i64,
+) -> rusqlite::Result<Option<StockVerification>> {
+    conn.query_row(
+        &format!("{} WHERE v.id = ?1", SELECT_VERIFICATIONS),
+        params![id],
+        map_verification,
+    )
+    .optional()
+}
+
+pub fn open_verification_label(conn: &Connection) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT label FROM inventory_verifications WHERE status = 'open' LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn label_taken(conn: &Connection, label: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM inventory_verifications WHERE label = ?1 COLLATE NOCASE)",
+        params![label],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_verification(
+    conn: &Connection,
+    label: &str,
+    started_on: &str,
+    verified_by: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO inventory_verifications (label, started_on, verified_by)
+         VALUES (?1, ?2, ?3)",
+        params![label, started_on, verified_by],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn insert_line(
+    conn: &Connection,
+    verification_id: i64,
+    item_id: i64,
+    expected_quantity: i64,
+    book_value: f64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO inventory_verification_lines (verification_id, item_id,
+            expected_quantity, book_value)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![verification_id, item_id, expected_quantity, book_value],
+    )?;
+    Ok(())
+}
+
+pub fn update_count(
+    conn: &Connection,
+    verification_id: i64,
+    item_id: i64,
+    found_quantity: i64,
+    condition: Option<&str>,
+    remarks: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE inventory_verification_lines
+         SET found_quantity = ?1, condition = ?2, remarks = ?3
+         WHERE verification_id = ?4 AND item_id = ?5",
+        params![found_quantity, condition, remarks, verification_id, item_id],
+    )
+}
+
+pub fn find_lines(
+    conn: &Connection,
+    verification_id: i64,
+) -> rusqlite::Result<Vec<VerificationLine>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.item_id, i.code, i.name, i.category, i.location, l.expected_quantity,
+            l.found_quantity, l.condition, l.remarks, l.book_value
+         FROM inventory_verification_lines l
+         JOIN inventory_items i ON i.id = l.item_id
+         WHERE l.verification_id = ?1
+         ORDER BY i.category, i.code",
+    )?;
+    let lines = stmt
+        .query_map(params![verification_id], |row| {
+            Ok(VerificationLine {
+                item_id: row.get(0)?,
+                code: row.get(1)?,
+                name: row.get(2)?,
+                category: row.get(3)?,
+                location: row.get(4)?,
+                expected_quantity: row.get(5)?,
+                found_quantity: row.get(6)?,
+                condition: row.get(7)?,
+                remarks: row.get(8)?,
+                book_value: row.get(9)?,
+                shortfall_value: 0.0,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lines)
+}
+
+pub fn complete_verification(
+    conn: &Connection,
+    id: i64,
+    completed_on: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE inventory_verifications SET status = 'closed', completed_on = ?1
+         WHERE id = ?2 AND status = 'open'",
+        params![completed_on, id],
+    )
+}
@@ -0,0 +1,273 @@
+use crate::leave::{LeaveApplication, LeaveQuota, LeaveType};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const TYPE_COLUMNS: &str =
+    "leave_type, short_code, name, paid, include_weekly_offs, carry_forward, max_carry_forward";
+
+fn map_type(row: &Row) -> rusqlite::Result<LeaveType> {
+    Ok(LeaveType {
+        leave_type: row.get(0)?,
+        short_code: row.get(1)?,
+        name: row.get(2)?,
+        paid: row.get(3)?,
+        include_weekly_offs: row.get(4)?,
+        carry_forward: row.get(5)?,
+        max_carry_forward: row.get(6)?,
+    })
+}
+
+pub fn find_types(conn: &Connection) -> rusqlite::Result<Vec<LeaveType>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM leave_types ORDER BY paid DESC, name",
+        TYPE_COLUMNS
+    ))?;
+    let types = stmt
+        .query_map([], map_type)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(types)
+}
+
+pub fn find_type(conn: &Connection, leave_type: &str) -> rusqlite::Result<Option<LeaveType>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM leave_types WHERE leave_type = ?1",
+            TYPE_COLUMNS
+        ),
+        params![leave_type],
+        map_type,
+    )
+    .optional()
+}
+
+pub fn update_type(conn: &Connection, leave_type: &LeaveType) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE leave_types SET
+            short_code = ?1, name = ?2, paid = ?3, include_weekly_offs = ?4,
+            carry_forward = ?5, max_carry_forward = ?6
+         WHERE leave_type = ?7",
+        params![
+            leave_type.short_code,
+            leave_type.name,
+            leave_type.paid,
+            leave_type.include_weekly_offs,
+            leave_type.carry_forward,
+            leave_type.max_carry_forward,
+            leave_type.leave_type,
+        ],
+    )
+}
+
+pub fn find_quotas(conn: &Connection) -> rusqlite::Result<Vec<LeaveQuota>> {
+    let mut stmt = conn.prepare(
+        "SELECT employment_type, leave_type, days_per_year
+         FROM leave_quotas ORDER BY employment_type, leave_type",
+    )?;
+    let quotas = stmt
+        .query_map([], |row| {
+            Ok(LeaveQuota {
+                employment_type: row.get(0)?,
+                leave_type: row.get(1)?,
+                days_per_year: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(quotas)
+}
+
+/// Yearly quota; employment types are matched case-insensitively.
+pub fn find_quota(
+    conn: &Connection,
+    employment_type: &str,
+    leave_type: &str,
+) -> rusqlite::Result<Option<f64>> {
+    conn.query_row(
+        "SELECT days_per_year FROM leave_quotas WHERE employment_type = ?1 AND leave_type = ?2",
+        params![employment_type.trim(), leave_type],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn upsert_quota(conn: &Connection, quota: &LeaveQuota) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO leave_quotas (employment_type, leave_type, days_per_year)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(employment_type, leave_type) DO UPDATE SET
+            days_per_year = excluded.days_per_year",
+        params![quota.employment_type, quota.leave_type, quota.days_per_year],
+    )?;
+    Ok(())
+}
+
+pub fn insert_application(
+    conn: &Connection,
+    application: &LeaveApplication,
+    days: f64,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO leave_applications (
+            staff_id, leave_type, start_date, end_date, half_day, days, reason
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            application.staff_id,
+            application.leave_type,
+            application.start_date,
+            application.end_date,
+            application.half_day,
+            days,
+            application.reason,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+const APPLICATION_COLUMNS: &str = "a.id, a.staff_id, a.leave_type, a.start_date, a.end_date,
+        a.half_day, a.days, a.reason, a.status, a.decided_by, a.decided_on, a.decision_note,
+        s.name, a.created_at";
+
+fn map_application(row: &Row) -> rusqlite::Result<LeaveApplication> {
+    Ok(LeaveApplication {
+        id: Some(row.get(0)?),
+        staff_id: row.get(1)?,
+        leave_type: row.get(2)?,
+        start_date: row.get(3)?,
+        end_date: row.get(4)?,
+        half_day: row.get(5)?,
+        days: Some(row.get(6)?),
+        reason: row.get(7)?,
+        status: Some(row.get(8)?),
+        decided_by: row.get(9)?,
+        decided_on: row.get(10)?,
+        decision_note: row.get(11)?,
+        staff_name: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+pub fn find_application(conn: &Connection, id: i64) -> rusqlite::Result<Option<LeaveApplication>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM leave_applications a JOIN staff s ON s.id = a.staff_id WHERE a.id = ?1",
+            APPLICATION_COLUMNS
+        ),
+        params![id],
+        map_application,
+    )
+    .optional()
+}
+
+pub fn find_applications(
+    conn: &Connection,
+    staff_id: Option<i64>,
+    status: Option<&str>,
+) -> rusqlite::Result<Vec<LeaveApplication>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM leave_applications a JOIN staff s ON s.id = a.staff_id
+         WHERE (?1 IS NULL OR a.staff_id = ?1) AND (?2 IS NULL OR a.status = ?2)
+         ORDER BY a.start_date DESC, a.id DESC",
+        APPLICATION_COLUMNS
+    ))?;
+    let applications = stmt
+        .query_map(params![staff_id, status], map_application)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(applications)
+}
+
+/// Approved applications overlapping `start..=end`, for the muster roll.
+pub fn find_approved_between(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+) -> rusqlite::Result<Vec<LeaveApplication>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM leave_applications a JOIN staff s ON s.id = a.staff_id
+         WHERE a.status = 'approved' AND a.start_date <= ?2 AND a.end_date >= ?1
+         ORDER BY a.start_date",
+        APPLICATION_COLUMNS
+    ))?;
+    let applications = stmt
+        .query_map(params![start, end], map_application)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(applications)
+}
+
+pub fn set_application_status(
+    conn: &Connection,
+    id: i64,
+    status: &str,
+    decided_by: Option<&str>,
+    decision_note: Option<&str>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE leave_applications SET
+            status = ?1, decided_by = ?2, decided_on = date('now', 'localtime'), decision_note = ?3
+         WHERE id = ?4",
+        params![status, decided_by, decision_note, id],
+    )
+}
+
+/// Whether the staff member already has a pending or approved application touching the period.
+pub fn has_overlap(
+    conn: &Connection,
+    staff_id: i64,
+    start: &str,
+    end: &str,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM leave_applications
+            WHERE staff_id = ?1 AND status IN ('pending', 'approved')
+              AND start_date <= ?3 AND end_date >= ?2
+         )",
+        params![staff_id, start, end],
+        |row| row.get(0),
+    )
+}
+
+/// Total days of one leave type in a calendar year, for applications in `status`.
+pub fn sum_days(
+    conn: &Connection,
+    staff_id: i64,
+    leave_type: &str,
+    year: i32,
+    status: &str,
+) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(days), 0) FROM leave_applications
+         WHERE staff_id = ?1 AND leave_type = ?2 AND status = ?3
+           AND CAST(strftime('%Y', start_date) AS INTEGER) = ?4",
+        params![staff_id, leave_type, status, year],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_opening(
+    conn: &Connection,
+    staff_id: i64,
+    leave_type: &str,
+    year: i32,
+) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT opening FROM leave_balances WHERE staff_id = ?1 AND leave_type = ?2 AND year = ?3",
+        params![staff_id, leave_type, year],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(|opening| opening.unwrap_or(0.0))
+}
+
+pub fn upsert_opening(
+    conn: &Connection,
+    staff_id: i64,
+    leave_type: &str,
+    year: i32,
+    opening: f64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO leave_balances (staff_id, leave_type, year, opening)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(staff_id, leave_type, year) DO UPDATE SET opening = excluded.opening",
+        params![staff_id, leave_type, year, opening],
+    )?;
+    Ok(())
+}
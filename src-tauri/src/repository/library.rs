@@ -0,0 +1,400 @@
+use crate::library::{BookCopy, BorrowCount, LibraryBook, LibraryLoan, LibrarySettings};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+/// Loans with the member's name; `?1` is the date overdue days are counted to for open loans.
+const SELECT_LOANS: &str = "SELECT l.id, l.copy_id, c.book_id, b.title, c.accession_number,
+        l.member_type, l.member_id,
+        CASE l.member_type WHEN 'student' THEN s.full_name ELSE st.name END,
+        l.issued_on, l.due_date, l.renewals, l.returned_on, l.fine_amount, l.fine_due_id,
+        CAST(MAX(0, julianday(COALESCE(l.returned_on, ?1)) - julianday(l.due_date)) AS INTEGER)
+     FROM library_loans l
+     JOIN library_copies c ON c.id = l.copy_id
+     JOIN library_books b ON b.id = c.book_id
+     LEFT JOIN students s ON l.member_type = 'student' AND s.id = l.member_id
+     LEFT JOIN staff st ON l.member_type = 'staff' AND st.id = l.member_id";
+
+const SELECT_BOOKS: &str = "SELECT b.id, b.isbn, b.title, b.author, b.publisher, b.category,
+        b.shelf_location,
+        (SELECT COUNT(*) FROM library_copies c
+          WHERE c.book_id = b.id AND c.status != 'withdrawn'),
+        (SELECT COUNT(*) FROM library_copies c
+          WHERE c.book_id = b.id AND c.status = 'available')
+     FROM library_books b";
+
+const SELECT_COPIES: &str = "SELECT c.id, c.book_id, c.accession_number, c.barcode, c.status,
+        b.title
+     FROM library_copies c
+     JOIN library_books b ON b.id = c.book_id";
+
+fn map_loan(row: &Row) -> rusqlite::Result<LibraryLoan> {
+    Ok(LibraryLoan {
+        id: row.get(0)?,
+        copy_id: row.get(1)?,
+        book_id: row.get(2)?,
+        title: row.get(3)?,
+        accession_number: row.get(4)?,
+        member_type: row.get(5)?,
+        member_id: row.get(6)?,
+        member_name: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+        issued_on: row.get(8)?,
+        due_date: row.get(9)?,
+        renewals: row.get(10)?,
+        returned_on: row.get(11)?,
+        fine_amount: row.get(12)?,
+        fine_due_id: row.get(13)?,
+        days_overdue: row.get(14)?,
+    })
+}
+
+fn map_book(row: &Row) -> rusqlite::Result<LibraryBook> {
+    Ok(LibraryBook {
+        id: Some(row.get(0)?),
+        isbn: row.get(1)?,
+        title: row.get(2)?,
+        author: row.get(3)?,
+        publisher: row.get(4)?,
+        category: row.get(5)?,
+        shelf_location: row.get(6)?,
+        copies: row.get(7)?,
+        available: row.get(8)?,
+    })
+}
+
+fn map_copy(row: &Row) -> rusqlite::Result<BookCopy> {
+    Ok(BookCopy {
+        id: Some(row.get(0)?),
+        book_id: row.get(1)?,
+        accession_number: row.get(2)?,
+        barcode: row.get(3)?,
+        status: Some(row.get(4)?),
+        title: Some(row.get(5)?),
+    })
+}
+
+pub fn find_settings(conn: &Connection) -> rusqlite::Result<LibrarySettings> {
+    conn.query_row(
+        "SELECT student_loan_days, staff_loan_days, student_max_books, staff_max_books,
+                max_renewals, fine_per_day, post_fines_to_dues
+         FROM library_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(LibrarySettings {
+                student_loan_days: row.get(0)?,
+                staff_loan_days: row.get(1)?,
+                student_max_books: row.get(2)?,
+                staff_max_books: row.get(3)?,
+                max_renewals: row.get(4)?,
+                fine_per_day: row.get(5)?,
+                post_fines_to_dues: row.get(6)?,
+            })
+        },
+    )
+}
+
+pub fn update_settings(conn: &Connection, settings: &LibrarySettings) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE library_settings SET student_loan_days = ?1, staff_loan_days = ?2,
+            student_max_books = ?3, staff_max_books = ?4, max_renewals = ?5,
+            fine_per_day = ?6, post_fines_to_dues = ?7
+         WHERE id = 1",
+        params![
+            settings.student_loan_days,
+            settings.staff_loan_days,
+            settings.student_max_books,
+            settings.staff_max_books,
+            settings.max_renewals,
+            settings.fine_per_day,
+            settings.post_fines_to_dues
+        ],
+    )
+}
+
+pub fn find_book(conn: &Connection, id: i64) -> rusqlite::Result<Option<LibraryBook>> {
+    conn.query_row(
+        &format!("{} WHERE b.id = ?1", SELECT_BOOKS),
+        params![id],
+        map_book,
+    )
+    .optional()
+}
+
+/// Books whose title, author or ISBN contains `query`, or with a copy of that accession
+/// number or barcode.
+pub fn find_books(conn: &Connection, query: Option<&str>) -> rusqlite::Result<Vec<LibraryBook>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE ?1 IS NULL
+            OR b.title LIKE '%' || ?1 || '%'
+            OR b.author LIKE '%' || ?1 || '%'
+            OR b.isbn = ?1
+            OR EXISTS (SELECT 1 FROM library_copies c WHERE c.book_id = b.id
+                        AND (c.accession_number = ?1 OR c.barcode = ?1))
+         ORDER BY b.title COLLATE NOCASE, b.author, b.id",
+        SELECT_BOOKS
+    ))?;
+    let books = stmt
+        .query_map(params![query], map_book)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(books)
+}
+
+pub fn insert_book(conn: &Connection, book: &LibraryBook) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO library_books (isbn, title, author, publisher, category, shelf_location)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            book.isbn,
+            book.title,
+            book.author,
+            book.publisher,
+            book.category,
+            book.shelf_location
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_book(conn: &Connection, id: i64, book: &LibraryBook) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE library_books SET isbn = ?1, title = ?2, author = ?3, publisher = ?4,
+            category = ?5, shelf_location = ?6
+         WHERE id = ?7",
+        params![
+            book.isbn,
+            book.title,
+            book.author,
+            book.publisher,
+            book.category,
+            book.shelf_location,
+            id
+        ],
+    )
+}
+
+pub fn delete_book(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM library_copies WHERE book_id = ?1", params![id])?;
+    conn.execute("DELETE FROM library_books WHERE id = ?1", params![id])
+}
+
+/// Loans ever made of any copy of the book.
+pub fn count_loans_of_book(conn: &Connection, book_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM library_loans l
+         JOIN library_copies c ON c.id = l.copy_id
+         WHERE c.book_id = ?1",
+        params![book_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_copies(conn: &Connection, book_id: i64) -> rusqlite::Result<Vec<BookCopy>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE c.book_id = ?1 ORDER BY c.accession_number COLLATE NOCASE",
+        SELECT_COPIES
+    ))?;
+    let copies = stmt
+        .query_map(params![book_id], map_copy)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(copies)
+}
+
+pub fn find_copy(conn: &Connection, id: i64) -> rusqlite::Result<Option<BookCopy>> {
+    conn.query_row(
+        &format!("{} WHERE c.id = ?1", SELECT_COPIES),
+        params![id],
+        map_copy,
+    )
+    .optional()
+}
+
+/// The copy with this barcode, or else this accession number.
+pub fn find_copy_by_code(conn: &Connection, code: &str) -> rusqlite::Result<Option<BookCopy>> {
+    conn.query_row(
+        &format!(
+            "{} WHERE c.barcode = ?1 OR c.accession_number = ?1
+             ORDER BY c.barcode = ?1 DESC LIMIT 1",
+            SELECT_COPIES
+        ),
+        params![code],
+        map_copy,
+    )
+    .optional()
+}
+
+/// Whether a copy already uses `code` as its accession number or barcode.
+pub fn code_taken(conn: &Connection, code: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM library_copies
+          WHERE accession_number = ?1 COLLATE NOCASE OR barcode = ?1)",
+        params![code],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_copy(conn: &Connection, copy: &BookCopy) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO library_copies (book_id, accession_number, barcode) VALUES (?1, ?2, ?3)",
+        params![copy.book_id, copy.accession_number, copy.barcode],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_copy_status(conn: &Connection, id: i64, status: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE library_copies SET status = ?1 WHERE id = ?2",
+        params![status, id],
+    )
+}
+
+pub fn find_loan(conn: &Connection, id: i64, as_of: &str) -> rusqlite::Result<Option<LibraryLoan>> {
+    conn.query_row(
+        &format!("{} WHERE l.id = ?2", SELECT_LOANS),
+        params![as_of, id],
+        map_loan,
+    )
+    .optional()
+}
+
+pub fn find_open_loan(
+    conn: &Connection,
+    copy_id: i64,
+    as_of: &str,
+) -> rusqlite::Result<Option<LibraryLoan>> {
+    conn.query_row(
+        &format!(
+            "{} WHERE l.copy_id = ?2 AND l.returned_on IS NULL",
+            SELECT_LOANS
+        ),
+        params![as_of, copy_id],
+        map_loan,
+    )
+    .optional()
+}
+
+pub fn find_member_loans(
+    conn: &Connection,
+    member_type: &str,
+    member_id: i64,
+    include_returned: bool,
+    as_of: &str,
+) -> rusqlite::Result<Vec<LibraryLoan>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE l.member_type = ?2 AND l.member_id = ?3 AND (?4 OR l.returned_on IS NULL)
+         ORDER BY l.returned_on IS NOT NULL, l.issued_on DESC, l.id DESC",
+        SELECT_LOANS
+    ))?;
+    let loans = stmt
+        .query_map(
+            params![as_of, member_type, member_id, include_returned],
+            map_loan,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(loans)
+}
+
+/// Open loans due before `as_of`, longest overdue first.
+pub fn find_overdue(conn: &Connection, as_of: &str) -> rusqlite::Result<Vec<LibraryLoan>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE l.returned_on IS NULL AND l.due_date < ?1
+         ORDER BY l.due_date, l.id",
+        SELECT_LOANS
+    ))?;
+    let loans = stmt
+        .query_map(params![as_of], map_loan)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(loans)
+}
+
+pub fn count_open_loans(
+    conn: &Connection,
+    member_type: &str,
+    member_id: i64,
+) -> rusqlite::Result<u32> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM library_loans
+         WHERE member_type = ?1 AND member_id = ?2 AND returned_on IS NULL",
+        params![member_type, member_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn count_overdue_loans(
+    conn: &Connection,
+    member_type: &str,
+    member_id: i64,
+    as_of: &str,
+) -> rusqlite::Result<u32> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM library_loans
+         WHERE member_type = ?1 AND member_id = ?2 AND returned_on IS NULL AND due_date < ?3",
+        params![member_type, member_id, as_of],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_loan(
+    conn: &Connection,
+    copy_id: i64,
+    member_type: &str,
+    member_id: i64,
+    issued_on: &str,
+    due_date: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO library_loans (copy_id, member_type, member_id, issued_on, due_date)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![copy_id, member_type, member_id, issued_on, due_date],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn renew_loan(conn: &Connection, id: i64, due_date: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE library_loans SET due_date = ?1, renewals = renewals + 1
+         WHERE id = ?2 AND returned_on IS NULL",
+        params![due_date, id],
+    )
+}
+
+pub fn close_loan(
+    conn: &Connection,
+    id: i64,
+    returned_on: &str,
+    fine_amount: f64,
+    fine_due_id: Option<i64>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE library_loans SET returned_on = ?1, fine_amount = ?2, fine_due_id = ?3
+         WHERE id = ?4 AND returned_on IS NULL",
+        params![returned_on, fine_amount, fine_due_id, id],
+    )
+}
+
+/// Titles by number of loans issued within `from..=to`, most first.
+pub fn find_most_borrowed(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    limit: u32,
+) -> rusqlite::Result<Vec<BorrowCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT b.id, b.title, b.author, COUNT(*) AS loans
+         FROM library_loans l
+         JOIN library_copies c ON c.id = l.copy_id
+         JOIN library_books b ON b.id = c.book_id
+         WHERE l.issued_on BETWEEN ?1 AND ?2
+         GROUP BY b.id
+         ORDER BY loans DESC, b.title COLLATE NOCASE
+         LIMIT ?3",
+    )?;
+    let counts = stmt
+        .query_map(params![from, to, limit], |row| {
+            Ok(BorrowCount {
+                book_id: row.get(0)?,
+                title: row.get(1)?,
+                author: row.get(2)?,
+                loans: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(counts)
+}
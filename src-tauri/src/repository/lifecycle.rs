@@ -0,0 +1,170 @@
+use crate::lifecycle::{AlumniRecord, StatusHistoryEntry};
+use rusqlite::{params, Connection, Row};
+
+pub fn insert_history(
+    conn: &Connection,
+    student_id: i64,
+    from_status: Option<&str>,
+    to_status: &str,
+    effective_date: &str,
+    reason: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO student_status_history (student_id, from_status, to_status, effective_date, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![student_id, from_status, to_status, effective_date, reason],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_history(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Vec<StatusHistoryEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, student_id, from_status, to_status, effective_date, reason, recorded_at
+         FROM student_status_history
+         WHERE student_id = ?1
+         ORDER BY effective_date, id",
+    )?;
+    let history = stmt
+        .query_map(params![student_id], |row| {
+            Ok(StatusHistoryEntry {
+                id: row.get(0)?,
+                student_id: row.get(1)?,
+                from_status: row.get(2)?,
+                to_status: row.get(3)?,
+                effective_date: row.get(4)?,
+                reason: row.get(5)?,
+                recorded_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(history)
+}
+
+/// Maps free-text statuses saved before the lifecycle existed onto lifecycle statuses.
+/// "inactive" becomes "left"; anything else unrecognised (or blank) becomes "active".
+pub fn normalize_legacy_statuses(conn: &Connection, statuses: &[&str]) -> rusqlite::Result<usize> {
+    let known = statuses
+        .iter()
+        .map(|s| format!("'{}'", s))
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute(
+        &format!(
+            "UPDATE students SET status = CASE
+                WHEN LOWER(TRIM(status)) IN ({known}) THEN LOWER(TRIM(status))
+                WHEN LOWER(TRIM(status)) = 'inactive' THEN 'left'
+                ELSE 'active'
+             END
+             WHERE status IS NULL OR status NOT IN ({known})"
+        ),
+        [],
+    )
+}
+
+/// Gives every student without history a first entry for their current status.
+pub fn record_missing_initial_statuses(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO student_status_history (student_id, from_status, to_status, effective_date, reason)
+         SELECT s.id, NULL, s.status,
+                COALESCE(NULLIF(TRIM(s.admission_date), ''), date(s.created_at), date('now', 'localtime')),
+                'Recorded before status tracking'
+         FROM students s
+         WHERE s.status IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM student_status_history h WHERE h.student_id = s.id)",
+        [],
+    )
+}
+
+const ALUMNI_COLUMNS: &str = "id, student_id, full_name, gr_number, final_class, passing_year,
+        email, phone, address, current_occupation, notes, updated_at";
+
+fn map_alumni(row: &Row) -> rusqlite::Result<AlumniRecord> {
+    Ok(AlumniRecord {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        full_name: row.get(2)?,
+        gr_number: row.get(3)?,
+        final_class: row.get(4)?,
+        passing_year: row.get(5)?,
+        email: row.get(6)?,
+        phone: row.get(7)?,
+        address: row.get(8)?,
+        current_occupation: row.get(9)?,
+        notes: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+/// Adds the graduate to the directory. Graduating again only moves the final class and year;
+/// contact details the school has since updated are kept.
+pub fn upsert_alumni(conn: &Connection, alumni: &AlumniRecord) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO alumni (
+            student_id, full_name, gr_number, final_class, passing_year, email, phone, address
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(student_id) DO UPDATE SET
+            final_class = excluded.final_class,
+            passing_year = excluded.passing_year,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            alumni.student_id,
+            alumni.full_name,
+            alumni.gr_number,
+            alumni.final_class,
+            alumni.passing_year,
+            alumni.email,
+            alumni.phone,
+            alumni.address,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Directory entries, optionally for one passing year and matching a name, GR number or phone.
+pub fn find_alumni(
+    conn: &Connection,
+    passing_year: Option<&str>,
+    term: Option<&str>,
+) -> rusqlite::Result<Vec<AlumniRecord>> {
+    let query = format!(
+        "SELECT {} FROM alumni
+         WHERE (?1 IS NULL OR passing_year = ?1)
+           AND (?2 IS NULL OR full_name LIKE ?2 OR gr_number LIKE ?2 OR phone LIKE ?2)
+         ORDER BY passing_year DESC, full_name",
+        ALUMNI_COLUMNS
+    );
+    let pattern = term.map(|t| format!("%{}%", t));
+    let mut stmt = conn.prepare(&query)?;
+    let alumni = stmt
+        .query_map(params![passing_year, pattern], map_alumni)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(alumni)
+}
+
+pub fn update_alumni(conn: &Connection, id: i64, alumni: &AlumniRecord) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE alumni SET
+            final_class = ?1,
+            passing_year = ?2,
+            email = ?3,
+            phone = ?4,
+            address = ?5,
+            current_occupation = ?6,
+            notes = ?7,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?8",
+        params![
+            alumni.final_class,
+            alumni.passing_year,
+            alumni.email,
+            alumni.phone,
+            alumni.address,
+            alumni.current_occupation,
+            alumni.notes,
+            id,
+        ],
+    )
+}
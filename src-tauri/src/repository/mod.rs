@@ -0,0 +1,41 @@
+// Data access layer: plain SQL against a `rusqlite::Connection`, no business rules.
+pub mod academic_year;
+pub mod calendar;
+pub mod certificate;
+pub mod class;
+pub mod communication;
+pub mod dashboard;
+pub mod discipline;
+pub mod dues;
+pub mod enquiry;
+pub mod guardian;
+pub mod health;
+pub mod homework;
+pub mod hostel;
+pub mod inventory;
+pub mod leave;
+pub mod library;
+pub mod lifecycle;
+pub mod payroll;
+pub mod report;
+pub mod school;
+pub mod staff;
+pub mod staff_attendance;
+pub mod statutory;
+pub mod students;
+pub mod subject;
+pub mod timetable;
+pub mod transport;
+pub mod visitor;
+
+use rusqlite::Connection;
+
+/// Today's date in local time as `YYYY-MM-DD`.
+pub fn today(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+}
+
+/// Current local time as `HH:MM`.
+pub fn now_time(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT strftime('%H:%M', 'now', 'localtime')", [], |row| row.get(0))
+}
@@ -0,0 +1,321 @@
+use crate::payroll::{PayrollRun, Payslip, SalaryStructure, StaffPayDetails};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const STRUCTURE_COLUMNS: &str = "id, staff_id, effective_from, basic, da, hra, other_allowances,
+        pf_applicable, esi_applicable, professional_tax, other_deductions, remarks, created_at";
+
+fn map_structure(row: &Row) -> rusqlite::Result<SalaryStructure> {
+    Ok(SalaryStructure {
+        id: Some(row.get(0)?),
+        staff_id: row.get(1)?,
+        effective_from: row.get(2)?,
+        basic: row.get(3)?,
+        da: row.get(4)?,
+        hra: row.get(5)?,
+        other_allowances: row.get(6)?,
+        pf_applicable: row.get(7)?,
+        esi_applicable: row.get(8)?,
+        professional_tax: row.get(9)?,
+        other_deductions: row.get(10)?,
+        remarks: row.get(11)?,
+        created_at: row.get(12)?,
+    })
+}
+
+/// Saves a revision; saving again for the same effective date replaces it.
+pub fn upsert_structure(conn: &Connection, structure: &SalaryStructure) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO salary_structures (
+            staff_id, effective_from, basic, da, hra, other_allowances, pf_applicable,
+            esi_applicable, professional_tax, other_deductions, remarks
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT(staff_id, effective_from) DO UPDATE SET
+            basic = excluded.basic,
+            da = excluded.da,
+            hra = excluded.hra,
+            other_allowances = excluded.other_allowances,
+            pf_applicable = excluded.pf_applicable,
+            esi_applicable = excluded.esi_applicable,
+            professional_tax = excluded.professional_tax,
+            other_deductions = excluded.other_deductions,
+            remarks = excluded.remarks
+         RETURNING id",
+        params![
+            structure.staff_id,
+            structure.effective_from,
+            structure.basic,
+            structure.da,
+            structure.hra,
+            structure.other_allowances,
+            structure.pf_applicable,
+            structure.esi_applicable,
+            structure.professional_tax,
+            structure.other_deductions,
+            structure.remarks,
+        ],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_structures(conn: &Connection, staff_id: i64) -> rusqlite::Result<Vec<SalaryStructure>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM salary_structures WHERE staff_id = ?1 ORDER BY effective_from DESC",
+        STRUCTURE_COLUMNS
+    ))?;
+    let structures = stmt
+        .query_map(params![staff_id], map_structure)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(structures)
+}
+
+/// The revision in force on `date`.
+pub fn find_structure_on(
+    conn: &Connection,
+    staff_id: i64,
+    date: &str,
+) -> rusqlite::Result<Option<SalaryStructure>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM salary_structures
+             WHERE staff_id = ?1 AND effective_from <= ?2
+             ORDER BY effective_from DESC LIMIT 1",
+            STRUCTURE_COLUMNS
+        ),
+        params![staff_id, date],
+        map_structure,
+    )
+    .optional()
+}
+
+pub fn delete_structure(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM salary_structures WHERE id = ?1", params![id])
+}
+
+pub fn find_pay_details(
+    conn: &Connection,
+    staff_id: i64,
+) -> rusqlite::Result<Option<StaffPayDetails>> {
+    conn.query_row(
+        "SELECT staff_id, bank_name, account_number, ifsc_code, pan_number, uan_number, esi_number
+         FROM staff_pay_details WHERE staff_id = ?1",
+        params![staff_id],
+        |row| {
+            Ok(StaffPayDetails {
+                staff_id: row.get(0)?,
+                bank_name: row.get(1)?,
+                account_number: row.get(2)?,
+                ifsc_code: row.get(3)?,
+                pan_number: row.get(4)?,
+                uan_number: row.get(5)?,
+                esi_number: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn upsert_pay_details(conn: &Connection, details: &StaffPayDetails) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO staff_pay_details (
+            staff_id, bank_name, account_number, ifsc_code, pan_number, uan_number, esi_number
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(staff_id) DO UPDATE SET
+            bank_name = excluded.bank_name,
+            account_number = excluded.account_number,
+            ifsc_code = excluded.ifsc_code,
+            pan_number = excluded.pan_number,
+            uan_number = excluded.uan_number,
+            esi_number = excluded.esi_number,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            details.staff_id,
+            details.bank_name,
+            details.account_number,
+            details.ifsc_code,
+            details.pan_number,
+            details.uan_number,
+            details.esi_number,
+        ],
+    )?;
+    Ok(())
+}
+
+const SELECT_RUNS: &str = "SELECT r.id, r.year, r.month, r.status,
+        COUNT(p.id), COALESCE(SUM(p.gross), 0), COALESCE(SUM(p.total_deductions), 0),
+        COALESCE(SUM(p.net_pay), 0), r.created_at, r.finalized_at
+     FROM payroll_runs r
+     LEFT JOIN payslips p ON p.run_id = r.id";
+
+fn map_run(row: &Row) -> rusqlite::Result<PayrollRun> {
+    Ok(PayrollRun {
+        id: row.get(0)?,
+        year: row.get(1)?,
+        month: row.get(2)?,
+        status: row.get(3)?,
+        staff_count: row.get(4)?,
+        total_gross: row.get(5)?,
+        total_deductions: row.get(6)?,
+        total_net: row.get(7)?,
+        created_at: row.get(8)?,
+        finalized_at: row.get(9)?,
+    })
+}
+
+pub fn find_runs(conn: &Connection) -> rusqlite::Result<Vec<PayrollRun>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} GROUP BY r.id ORDER BY r.year DESC, r.month DESC",
+        SELECT_RUNS
+    ))?;
+    let runs = stmt
+        .query_map([], map_run)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(runs)
+}
+
+pub fn find_run(conn: &Connection, id: i64) -> rusqlite::Result<Option<PayrollRun>> {
+    conn.query_row(
+        &format!("{} WHERE r.id = ?1 GROUP BY r.id", SELECT_RUNS),
+        params![id],
+        map_run,
+    )
+    .optional()
+}
+
+pub fn find_run_id(conn: &Connection, year: i32, month: u32) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM payroll_runs WHERE year = ?1 AND month = ?2",
+        params![year, month],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn insert_run(conn: &Connection, year: i32, month: u32) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO payroll_runs (year, month) VALUES (?1, ?2)",
+        params![year, month],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn finalize_run(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE payroll_runs SET status = 'finalized', finalized_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND status = 'draft'",
+        params![id],
+    )
+}
+
+pub fn delete_payslips(conn: &Connection, run_id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM payslips WHERE run_id = ?1", params![run_id])
+}
+
+pub fn insert_payslip(conn: &Connection, payslip: &Payslip) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO payslips (
+            run_id, staff_id, staff_name, designation, department, days_in_month, unpaid_days,
+            paid_days, basic, da, hra, other_allowances, gross, pf_wages, employee_pf,
+            employer_pf, esi_wages, employee_esi, employer_esi, professional_tax,
+            other_deductions, total_deductions, net_pay, bank_name, account_number, ifsc_code,
+            pan_number, uan_number, esi_number
+         ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+            ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29
+         )",
+        params![
+            payslip.run_id,
+            payslip.staff_id,
+            payslip.staff_name,
+            payslip.designation,
+            payslip.department,
+            payslip.days_in_month,
+            payslip.unpaid_days,
+            payslip.paid_days,
+            payslip.basic,
+            payslip.da,
+            payslip.hra,
+            payslip.other_allowances,
+            payslip.gross,
+            payslip.pf_wages,
+            payslip.employee_pf,
+            payslip.employer_pf,
+            payslip.esi_wages,
+            payslip.employee_esi,
+            payslip.employer_esi,
+            payslip.professional_tax,
+            payslip.other_deductions,
+            payslip.total_deductions,
+            payslip.net_pay,
+            payslip.bank_name,
+            payslip.account_number,
+            payslip.ifsc_code,
+            payslip.pan_number,
+            payslip.uan_number,
+            payslip.esi_number,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+const SELECT_PAYSLIPS: &str = "SELECT
+        id, run_id, staff_id, staff_name, designation, department, days_in_month, unpaid_days,
+        paid_days, basic, da, hra, other_allowances, gross, pf_wages, employee_pf,
+        employer_pf, esi_wages, employee_esi, employer_esi, professional_tax,
+        other_deductions, total_deductions, net_pay, bank_name, account_number, ifsc_code,
+        pan_number, uan_number, esi_number
+     FROM payslips";
+
+fn map_payslip(row: &Row) -> rusqlite::Result<Payslip> {
+    Ok(Payslip {
+        id: row.get(0)?,
+        run_id: row.get(1)?,
+        staff_id: row.get(2)?,
+        staff_name: row.get(3)?,
+        designation: row.get(4)?,
+        department: row.get(5)?,
+        days_in_month: row.get(6)?,
+        unpaid_days: row.get(7)?,
+        paid_days: row.get(8)?,
+        basic: row.get(9)?,
+        da: row.get(10)?,
+        hra: row.get(11)?,
+        other_allowances: row.get(12)?,
+        gross: row.get(13)?,
+        pf_wages: row.get(14)?,
+        employee_pf: row.get(15)?,
+        employer_pf: row.get(16)?,
+        esi_wages: row.get(17)?,
+        employee_esi: row.get(18)?,
+        employer_esi: row.get(19)?,
+        professional_tax: row.get(20)?,
+        other_deductions: row.get(21)?,
+        total_deductions: row.get(22)?,
+        net_pay: row.get(23)?,
+        bank_name: row.get(24)?,
+        account_number: row.get(25)?,
+        ifsc_code: row.get(26)?,
+        pan_number: row.get(27)?,
+        uan_number: row.get(28)?,
+        esi_number: row.get(29)?,
+    })
+}
+
+pub fn find_payslips(conn: &Connection, run_id: i64) -> rusqlite::Result<Vec<Payslip>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE run_id = ?1 ORDER BY staff_name",
+        SELECT_PAYSLIPS
+    ))?;
+    let payslips = stmt
+        .query_map(params![run_id], map_payslip)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(payslips)
+}
+
+pub fn find_payslip(conn: &Connection, id: i64) -> rusqlite::Result<Option<Payslip>> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_PAYSLIPS),
+        params![id],
+        map_payslip,
+    )
+    .optional()
+}
@@ -0,0 +1,113 @@
+use crate::report::SavedReport;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde_json::Value;
+
+/// Runs a compiled report query, turning each row into JSON values for the grid.
+pub fn run_query(
+    conn: &Connection,
+    sql: &str,
+    values: &[SqlValue],
+    width: usize,
+) -> rusqlite::Result<Vec<Vec<Value>>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(params_from_iter(values.iter()), |row| {
+            (0..width)
+                .map(|index| {
+                    Ok(match row.get_ref(index)? {
+                        ValueRef::Integer(number) => Value::from(number),
+                        ValueRef::Real(number) => Value::from(number),
+                        ValueRef::Text(text) => Value::from(String::from_utf8_lossy(text)),
+                        ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                    })
+                })
+                .collect()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// A saved report with its definition still as the stored JSON text.
+pub struct StoredReport {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+const SELECT_REPORTS: &str =
+    "SELECT id, name, description, definition, created_at, updated_at FROM saved_reports";
+
+fn map_report(row: &Row) -> rusqlite::Result<StoredReport> {
+    Ok(StoredReport {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        definition: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+pub fn find_reports(conn: &Connection) -> rusqlite::Result<Vec<StoredReport>> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY name COLLATE NOCASE", SELECT_REPORTS))?;
+    let reports = stmt
+        .query_map([], map_report)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(reports)
+}
+
+pub fn find_report(conn: &Connection, id: i64) -> rusqlite::Result<Option<StoredReport>> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_REPORTS),
+        params![id],
+        map_report,
+    )
+    .optional()
+}
+
+pub fn report_name_taken(
+    conn: &Connection,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM saved_reports
+         WHERE name = ?1 COLLATE NOCASE AND id != COALESCE(?2, -1))",
+        params![name, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_report(
+    conn: &Connection,
+    report: &SavedReport,
+    definition: &str,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO saved_reports (name, description, definition) VALUES (?1, ?2, ?3)",
+        params![report.name, report.description, definition],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_report(
+    conn: &Connection,
+    id: i64,
+    report: &SavedReport,
+    definition: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE saved_reports
+         SET name = ?1, description = ?2, definition = ?3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?4",
+        params![report.name, report.description, definition, id],
+    )
+}
+
+pub fn delete_report(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM saved_reports WHERE id = ?1", params![id])
+}
@@ -0,0 +1,104 @@
+use crate::school::School;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub fn find_first(conn: &Connection) -> rusqlite::Result<Option<School>> {
+    conn.query_row(
+        "SELECT id, school_name, school_board, school_medium, principal_name, contact_number, alternate_contact_number,
+                school_email, address, city, state, pincode, website, school_image, created_at, updated_at,
+                udise_code, affiliation_number
+         FROM schools LIMIT 1",
+        [],
+        |row| {
+            Ok(School {
+                id: Some(row.get(0)?),
+                school_name: row.get(1)?,
+                school_board: row.get(2)?,
+                school_medium: row.get(3)?,
+                principal_name: row.get(4)?,
+                contact_number: row.get(5)?,
+                alternate_contact_number: row.get(6)?,
+                school_email: row.get(7)?,
+                address: row.get(8)?,
+                city: row.get(9)?,
+                state: row.get(10)?,
+                pincode: row.get(11)?,
+                website: row.get(12)?,
+                school_image: row.get(13)?,
+                created_at: row.get(14)?,
+                updated_at: row.get(15)?,
+                udise_code: row.get(16)?,
+                affiliation_number: row.get(17)?,
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn insert(conn: &Connection, school: &School) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO schools (
+            school_name, school_board, school_medium, principal_name, contact_number, alternate_contact_number,
+            school_email, address, city, state, pincode, website, school_image, udise_code,
+            affiliation_number
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            school.school_name,
+            school.school_board,
+            school.school_medium,
+            school.principal_name,
+            school.contact_number,
+            school.alternate_contact_number,
+            school.school_email,
+            school.address,
+            school.city,
+            school.state,
+            school.pincode,
+            school.website,
+            school.school_image,
+            school.udise_code,
+            school.affiliation_number,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update(conn: &Connection, id: i64, school: &School) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE schools SET
+            school_name = ?1,
+            school_board = ?2,
+            school_medium = ?3,
+            principal_name = ?4,
+            contact_number = ?5,
+            alternate_contact_number = ?6,
+            school_email = ?7,
+            address = ?8,
+            city = ?9,
+            state = ?10,
+            pincode = ?11,
+            website = ?12,
+            school_image = ?13,
+            udise_code = ?14,
+            affiliation_number = ?15,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?16",
+        params![
+            school.school_name,
+            school.school_board,
+            school.school_medium,
+            school.principal_name,
+            school.contact_number,
+            school.alternate_contact_number,
+            school.school_email,
+            school.address,
+            school.city,
+            school.state,
+            school.pincode,
+            school.website,
+            school.school_image,
+            school.udise_code,
+            school.affiliation_number,
+            id,
+        ],
+    )
+}
@@ -0,0 +1,100 @@
+use crate::staff::Staff;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+fn map_staff(row: &Row) -> rusqlite::Result<Staff> {
+    Ok(Staff {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        gender: row.get(2)?,
+        dob: row.get(3)?,
+        phone: row.get(4)?,
+        alt_phone: row.get(5)?,
+        email: row.get(6)?,
+        qualification: row.get(7)?,
+        designation: row.get(8)?,
+        department: row.get(9)?,
+        joining_date: row.get(10)?,
+        employment_type: row.get(11)?,
+        photo_url: row.get(12)?,
+        status: row.get(13)?,
+        created_at: row.get(14)?,
+    })
+}
+
+pub fn insert(conn: &Connection, staff: &Staff, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO staff (
+            name, gender, dob, phone, alt_phone, email, qualification,
+            designation, department, joining_date, employment_type, photo_url, status
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            staff.name,
+            staff.gender,
+            staff.dob,
+            staff.phone,
+            staff.alt_phone,
+            staff.email,
+            staff.qualification,
+            staff.designation,
+            staff.department,
+            staff.joining_date,
+            staff.employment_type,
+            staff.photo_url,
+            status,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Staff>> {
+    conn.query_row(
+        "SELECT id, name, gender, dob, phone, alt_phone, email, qualification,
+                designation, department, joining_date, employment_type, photo_url, status, created_at
+         FROM staff WHERE id = ?1",
+        params![id],
+        map_staff,
+    )
+    .optional()
+}
+
+pub fn find_all(conn: &Connection) -> rusqlite::Result<Vec<Staff>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, gender, dob, phone, alt_phone, email, qualification,
+                designation, department, joining_date, employment_type, photo_url, status, created_at
+         FROM staff ORDER BY created_at DESC",
+    )?;
+    let staffs = stmt
+        .query_map([], map_staff)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(staffs)
+}
+
+pub fn update(conn: &Connection, id: i64, staff: &Staff, status: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE staff SET
+            name = ?1, gender = ?2, dob = ?3, phone = ?4, alt_phone = ?5,
+            email = ?6, qualification = ?7, designation = ?8, department = ?9,
+            joining_date = ?10, employment_type = ?11, photo_url = ?12, status = ?13
+         WHERE id = ?14",
+        params![
+            staff.name,
+            staff.gender,
+            staff.dob,
+            staff.phone,
+            staff.alt_phone,
+            staff.email,
+            staff.qualification,
+            staff.designation,
+            staff.department,
+            staff.joining_date,
+            staff.employment_type,
+            staff.photo_url,
+            status,
+            id,
+        ],
+    )
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM staff WHERE id = ?1", params![id])
+}
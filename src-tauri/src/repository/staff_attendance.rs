@@ -0,0 +1,70 @@
+use crate::staff_attendance::StaffAttendance;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const COLUMNS: &str = "id, staff_id, date, check_in, check_out, status, remarks";
+
+fn map_attendance(row: &Row) -> rusqlite::Result<StaffAttendance> {
+    Ok(StaffAttendance {
+        id: Some(row.get(0)?),
+        staff_id: row.get(1)?,
+        date: row.get(2)?,
+        check_in: row.get(3)?,
+        check_out: row.get(4)?,
+        status: row.get(5)?,
+        remarks: row.get(6)?,
+    })
+}
+
+pub fn find(
+    conn: &Connection,
+    staff_id: i64,
+    date: &str,
+) -> rusqlite::Result<Option<StaffAttendance>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM staff_attendance WHERE staff_id = ?1 AND date = ?2",
+            COLUMNS
+        ),
+        params![staff_id, date],
+        map_attendance,
+    )
+    .optional()
+}
+
+pub fn find_between(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+) -> rusqlite::Result<Vec<StaffAttendance>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM staff_attendance WHERE date BETWEEN ?1 AND ?2 ORDER BY date, staff_id",
+        COLUMNS
+    ))?;
+    let records = stmt
+        .query_map(params![start, end], map_attendance)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(records)
+}
+
+/// Inserts the day's record or replaces the one already marked.
+pub fn upsert(conn: &Connection, attendance: &StaffAttendance) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO staff_attendance (staff_id, date, check_in, check_out, status, remarks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(staff_id, date) DO UPDATE SET
+            check_in = excluded.check_in,
+            check_out = excluded.check_out,
+            status = excluded.status,
+            remarks = excluded.remarks,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            attendance.staff_id,
+            attendance.date,
+            attendance.check_in,
+            attendance.check_out,
+            attendance.status,
+            attendance.remarks,
+        ],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use crate::statutory::StatutoryDetails;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const ON_ROLL: &str = "(s.status IS NULL OR s.status IN ('active', 'suspended'))";
+
+fn map_details(row: &Row) -> rusqlite::Result<StatutoryDetails> {
+    Ok(StatutoryDetails {
+        social_category: row.get(0)?,
+        aadhaar_number: row.get(1)?,
+        disability: row.get(2)?,
+    })
+}
+
+pub fn find_student_details(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Option<StatutoryDetails>> {
+    conn.query_row(
+        "SELECT social_category, aadhaar_number, disability FROM students WHERE id = ?1",
+        params![student_id],
+        map_details,
+    )
+    .optional()
+}
+
+pub fn update_student_details(
+    conn: &Connection,
+    student_id: i64,
+    details: &StatutoryDetails,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students
+         SET social_category = ?1, aadhaar_number = ?2, disability = ?3,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?4",
+        params![
+            details.social_category,
+            details.aadhaar_number,
+            details.disability,
+            student_id
+        ],
+    )
+}
+
+/// Name of another student already holding this Aadhaar number.
+pub fn student_with_aadhaar(
+    conn: &Connection,
+    aadhaar_number: &str,
+    exclude_id: i64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT full_name FROM students WHERE aadhaar_number = ?1 AND id != ?2 LIMIT 1",
+        params![aadhaar_number, exclude_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn find_staff_details(
+    conn: &Connection,
+    staff_id: i64,
+) -> rusqlite::Result<Option<StatutoryDetails>> {
+    conn.query_row(
+        "SELECT social_category, aadhaar_number, disability FROM staff WHERE id = ?1",
+        params![staff_id],
+        map_details,
+    )
+    .optional()
+}
+
+pub fn update_staff_details(
+    conn: &Connection,
+    staff_id: i64,
+    details: &StatutoryDetails,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE staff SET social_category = ?1, aadhaar_number = ?2, disability = ?3
+         WHERE id = ?4",
+        params![
+            details.social_category,
+            details.aadhaar_number,
+            details.disability,
+            staff_id
+        ],
+    )
+}
+
+/// Name of another staff member already holding this Aadhaar number.
+pub fn staff_with_aadhaar(
+    conn: &Connection,
+    aadhaar_number: &str,
+    exclude_id: i64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT name FROM staff WHERE aadhaar_number = ?1 AND id != ?2 LIMIT 1",
+        params![aadhaar_number, exclude_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// A student on the roll with everything the returns draw on.
+pub struct StudentRecord {
+    pub id: i64,
+    pub gr_number: String,
+    pub roll_number: Option<String>,
+    pub full_name: String,
+    pub father_name: String,
+    pub mother_name: String,
+    pub dob: Option<String>,
+    pub gender: String,
+    pub class_id: Option<i64>,
+    pub class_name: Option<String>,
+    pub details: StatutoryDetails,
+}
+
+pub fn find_students_on_roll(conn: &Connection) -> rusqlite::Result<Vec<StudentRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.gr_number, s.roll_number, s.full_name, s.father_name, s.mother_name,
+                s.dob, s.gender, c.id, c.class_name,
+                s.social_category, s.aadhaar_number, s.disability
+         FROM students s
+         LEFT JOIN classes c ON c.id = s.class_id
+         WHERE {}
+         ORDER BY c.class_name, CAST(s.roll_number AS INTEGER), s.full_name",
+        ON_ROLL
+    ))?;
+    let students = stmt
+        .query_map([], |row| {
+            Ok(StudentRecord {
+                id: row.get(0)?,
+                gr_number: row.get(1)?,
+                roll_number: row.get(2)?,
+                full_name: row.get(3)?,
+                father_name: row.get(4)?,
+                mother_name: row.get(5)?,
+                dob: row.get(6)?,
+                gender: row.get(7)?,
+                class_id: row.get(8)?,
+                class_name: row.get(9)?,
+                details: StatutoryDetails {
+                    social_category: row.get(10)?,
+                    aadhaar_number: row.get(11)?,
+                    disability: row.get(12)?,
+                },
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(students)
+}
+
+pub struct TeacherRecord {
+    pub id: i64,
+    pub name: String,
+    pub gender: String,
+    pub dob: String,
+    pub qualification: String,
+    pub designation: String,
+    pub employment_type: String,
+    pub joining_date: String,
+    pub details: StatutoryDetails,
+}
+
+/// Active staff who teach: a designation mentioning teacher, or a subject or class of their
+/// own in an active class.
+pub fn find_teachers(conn: &Connection) -> rusqlite::Result<Vec<TeacherRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT st.id, st.name, st.gender, st.dob, st.qualification, st.designation,
+                st.employment_type, st.joining_date,
+                st.social_category, st.aadhaar_number, st.disability
+         FROM staff st
+         WHERE (st.status IS NULL OR LOWER(TRIM(st.status)) = 'active')
+           AND (st.designation LIKE '%teacher%'
+                OR st.id IN (SELECT cs.teacher_id FROM class_subjects cs
+                             JOIN classes c ON c.id = cs.class_id
+                             WHERE c.status = 'active')
+                OR st.id IN (SELECT ct.staff_id FROM class_teachers ct
+                             JOIN classes c ON c.id = ct.class_id
+                             WHERE c.status = 'active'))
+         ORDER BY st.name COLLATE NOCASE",
+    )?;
+    let teachers = stmt
+        .query_map([], |row| {
+            Ok(TeacherRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                gender: row.get(2)?,
+                dob: row.get(3)?,
+                qualification: row.get(4)?,
+                designation: row.get(5)?,
+                employment_type: row.get(6)?,
+                joining_date: row.get(7)?,
+                details: StatutoryDetails {
+                    social_category: row.get(8)?,
+                    aadhaar_number: row.get(9)?,
+                    disability: row.get(10)?,
+                },
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(teachers)
+}
+
+/// Teacher, subject and class of every subject assignment in an active class.
+pub fn find_teaching(conn: &Connection) -> rusqlite::Result<Vec<(i64, String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT cs.teacher_id, sub.name, c.class_name
+         FROM class_subjects cs
+         JOIN subjects sub ON sub.id = cs.subject_id
+         JOIN classes c ON c.id = cs.class_id
+         WHERE cs.teacher_id IS NOT NULL AND c.status = 'active'
+         ORDER BY sub.name",
+    )?;
+    let teaching = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(teaching)
+}
+
+/// Class and subject name of every class-subject mapping.
+pub fn find_class_subjects(conn: &Connection) -> rusqlite::Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT cs.class_id, sub.name
+         FROM class_subjects cs
+         JOIN subjects sub ON sub.id = cs.subject_id
+         ORDER BY sub.name",
+    )?;
+    let subjects = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(subjects)
+}
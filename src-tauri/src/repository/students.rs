@@ -0,0 +1,436 @@
+use crate::students::{Student, StudentContact, StudentCore, StudentDocs, StudentHealth};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_STUDENT: &str = "SELECT
+        students.id,
+        students.gr_number, students.roll_number, students.full_name, students.dob, students.gender,
+        students.mother_name, students.father_name, students.father_occupation, students.mother_occupation, students.annual_income,
+        students.nationality, students.profile_image, students.class_id, students.section, students.academic_year,
+        students.email, students.mobile_number, students.alternate_contact_number,
+        students.address, students.city, students.state, students.country, students.postal_code, students.guardian_contact_info,
+        students.blood_group, students.status, students.admission_date, students.weight_kg, students.height_cm, students.hb_range,
+        students.medical_conditions, students.emergency_contact_person, students.emergency_contact,
+        students.birth_certificate, students.transfer_certificate, students.previous_academic_records,
+        students.address_proof, students.id_proof, students.passport_photo, students.medical_certificate,
+        students.vaccination_certificate, students.other_documents,
+        classes.class_name
+     FROM students
+     LEFT JOIN classes ON students.class_id = classes.id";
+
+fn map_student(row: &Row) -> rusqlite::Result<Student> {
+    Ok(Student {
+        core: StudentCore {
+            id: row.get(0)?,
+            gr_number: row.get(1)?,
+            roll_number: row.get(2)?,
+            full_name: row.get(3)?,
+            dob: row.get(4)?,
+            gender: row.get(5)?,
+            mother_name: row.get(6)?,
+            father_name: row.get(7)?,
+            father_occupation: row.get(8)?,
+            mother_occupation: row.get(9)?,
+            annual_income: row.get(10)?,
+            nationality: row.get(11)?,
+            profile_image: row.get(12)?,
+            class_id: row.get(13)?,
+            class_name: row.get(43)?,
+            section: row.get(14)?,
+            academic_year: row.get(15)?,
+        },
+        contact: StudentContact {
+            email: row.get(16)?,
+            mobile_number: row.get(17)?,
+            alternate_contact_number: row.get(18)?,
+            address: row.get(19)?,
+            city: row.get(20)?,
+            state: row.get(21)?,
+            country: row.get(22)?,
+            postal_code: row.get(23)?,
+            guardian_contact_info: row.get(24)?,
+        },
+        health: StudentHealth {
+            blood_group: row.get(25)?,
+            status: row.get(26)?,
+            admission_date: row.get(27)?,
+            weight_kg: row.get(28)?,
+            height_cm: row.get(29)?,
+            hb_range: row.get(30)?,
+            medical_conditions: row.get(31)?,
+            emergency_contact_person: row.get(32)?,
+            emergency_contact: row.get(33)?,
+        },
+        docs: StudentDocs {
+            birth_certificate: row.get(34)?,
+            transfer_certificate: row.get(35)?,
+            previous_academic_records: row.get(36)?,
+            address_proof: row.get(37)?,
+            id_proof: row.get(38)?,
+            passport_photo: row.get(39)?,
+            medical_certificate: row.get(40)?,
+            vaccination_certificate: row.get(41)?,
+            other_documents: row.get(42)?,
+        },
+    })
+}
+
+pub fn class_exists(conn: &Connection, class_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM classes WHERE id = ?1)",
+        params![class_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn exists(conn: &Connection, id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM students WHERE id = ?1)",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+pub fn gr_number_exists(conn: &Connection, gr_number: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM students WHERE gr_number = ?1)",
+        params![gr_number],
+        |row| row.get(0),
+    )
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Student>> {
+    let query = format!("{} WHERE students.id = ?1", SELECT_STUDENT);
+    conn.query_row(&query, params![id], map_student).optional()
+}
+
+/// Every student, or only those with the given lifecycle status.
+pub fn find_all(conn: &Connection, status: Option<&str>) -> rusqlite::Result<Vec<Student>> {
+    let query = format!(
+        "{} WHERE ?1 IS NULL OR students.status = ?1 ORDER BY students.id",
+        SELECT_STUDENT
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let students = stmt
+        .query_map(params![status], map_student)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(students)
+}
+
+/// Inserts a student with every section filled in, as done by the Excel import.
+pub fn insert_full(conn: &Connection, student: &Student) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO students (
+            gr_number, roll_number, full_name, dob, gender,
+            mother_name, father_name, father_occupation, mother_occupation, annual_income,
+            nationality, profile_image, class_id, section, academic_year,
+            email, mobile_number, alternate_contact_number, address, city,
+            state, country, postal_code, guardian_contact_info,
+            blood_group, status, admission_date, weight_kg, height_cm, hb_range,
+            medical_conditions, emergency_contact_person, emergency_contact,
+            birth_certificate, transfer_certificate, previous_academic_records,
+            address_proof, id_proof, passport_photo, medical_certificate,
+            vaccination_certificate, other_documents
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5,
+            ?6, ?7, ?8, ?9, ?10,
+            ?11, ?12, ?13, ?14, ?15,
+            ?16, ?17, ?18, ?19, ?20,
+            ?21, ?22, ?23, ?24,
+            ?25, ?26, ?27, ?28, ?29, ?30,
+            ?31, ?32, ?33,
+            ?34, ?35, ?36,
+            ?37, ?38, ?39, ?40,
+            ?41, ?42
+        )",
+        params![
+            // Core fields
+            student.core.gr_number,
+            student.core.roll_number,
+            student.core.full_name,
+            student.core.dob,
+            student.core.gender,
+            student.core.mother_name,
+            student.core.father_name,
+            student.core.father_occupation,
+            student.core.mother_occupation,
+            student.core.annual_income,
+            student.core.nationality,
+            student.core.profile_image,
+            student.core.class_id,
+            student.core.section,
+            student.core.academic_year,
+            // Contact fields
+            student.contact.email,
+            student.contact.mobile_number,
+            student.contact.alternate_contact_number,
+            student.contact.address,
+            student.contact.city,
+            student.contact.state,
+            student.contact.country,
+            student.contact.postal_code,
+            student.contact.guardian_contact_info,
+            // Health fields
+            student.health.blood_group,
+            student.health.status,
+            student.health.admission_date,
+            student.health.weight_kg,
+            student.health.height_cm,
+            student.health.hb_range,
+            student.health.medical_conditions,
+            student.health.emergency_contact_person,
+            student.health.emergency_contact,
+            // Document fields
+            student.docs.birth_certificate,
+            student.docs.transfer_certificate,
+            student.docs.previous_academic_records,
+            student.docs.address_proof,
+            student.docs.id_proof,
+            student.docs.passport_photo,
+            student.docs.medical_certificate,
+            student.docs.vaccination_certificate,
+            student.docs.other_documents,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn insert_core(conn: &Connection, core: &StudentCore) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO students (
+            gr_number, roll_number, full_name, dob, gender,
+            mother_name, father_name, father_occupation, mother_occupation, annual_income,
+            nationality, profile_image, class_id, section, academic_year
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5,
+            ?6, ?7, ?8, ?9, ?10,
+            ?11, ?12, ?13, ?14, ?15
+        )",
+        params![
+            core.gr_number,
+            core.roll_number,
+            core.full_name,
+            core.dob,
+            core.gender,
+            core.mother_name,
+            core.father_name,
+            core.father_occupation,
+            core.mother_occupation,
+            core.annual_income,
+            core.nationality,
+            core.profile_image,
+            core.class_id,
+            core.section,
+            core.academic_year,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_core(conn: &Connection, id: i64, core: &StudentCore) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            gr_number = ?1,
+            roll_number = ?2,
+            full_name = ?3,
+            dob = ?4,
+            gender = ?5,
+            mother_name = ?6,
+            father_name = ?7,
+            father_occupation = ?8,
+            mother_occupation = ?9,
+            annual_income = ?10,
+            nationality = ?11,
+            profile_image = ?12,
+            class_id = ?13,
+            section = ?14,
+            academic_year = ?15,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?16",
+        params![
+            core.gr_number,
+            core.roll_number,
+            core.full_name,
+            core.dob,
+            core.gender,
+            core.mother_name,
+            core.father_name,
+            core.father_occupation,
+            core.mother_occupation,
+            core.annual_income,
+            core.nationality,
+            core.profile_image,
+            core.class_id,
+            core.section,
+            core.academic_year,
+            id,
+        ],
+    )
+}
+
+pub fn update_contact(conn: &Connection, id: i64, contact: &StudentContact) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            email = ?1,
+            mobile_number = ?2,
+            alternate_contact_number = ?3,
+            address = ?4,
+            city = ?5,
+            state = ?6,
+            country = ?7,
+            postal_code = ?8,
+            guardian_contact_info = ?9,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?10",
+        params![
+            contact.email,
+            contact.mobile_number,
+            contact.alternate_contact_number,
+            contact.address,
+            contact.city,
+            contact.state,
+            contact.country,
+            contact.postal_code,
+            contact.guardian_contact_info,
+            id,
+        ],
+    )
+}
+
+/// Status is left alone here; it only changes through the lifecycle service.
+pub fn update_health(conn: &Connection, id: i64, health: &StudentHealth) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            blood_group = ?1,
+            admission_date = ?2,
+            weight_kg = ?3,
+            height_cm = ?4,
+            hb_range = ?5,
+            medical_conditions = ?6,
+            emergency_contact_person = ?7,
+            emergency_contact = ?8,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?9",
+        params![
+            health.blood_group,
+            health.admission_date,
+            health.weight_kg,
+            health.height_cm,
+            health.hb_range,
+            health.medical_conditions,
+            health.emergency_contact_person,
+            health.emergency_contact,
+            id,
+        ],
+    )
+}
+
+pub fn update_docs(conn: &Connection, id: i64, docs: &StudentDocs) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            birth_certificate = ?1,
+            transfer_certificate = ?2,
+            previous_academic_records = ?3,
+            address_proof = ?4,
+            id_proof = ?5,
+            passport_photo = ?6,
+            medical_certificate = ?7,
+            vaccination_certificate = ?8,
+            other_documents = ?9,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?10",
+        params![
+            docs.birth_certificate,
+            docs.transfer_certificate,
+            docs.previous_academic_records,
+            docs.address_proof,
+            docs.id_proof,
+            docs.passport_photo,
+            docs.medical_certificate,
+            docs.vaccination_certificate,
+            docs.other_documents,
+            id,
+        ],
+    )
+}
+
+/// `None` when the student does not exist; the inner value is the (possibly unset) status.
+pub fn find_status(conn: &Connection, id: i64) -> rusqlite::Result<Option<Option<String>>> {
+    conn.query_row(
+        "SELECT status FROM students WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn find_class_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT class_id FROM students WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn find_leaving_details(
+    conn: &Connection,
+    id: i64,
+) -> rusqlite::Result<Option<(Option<String>, Option<String>)>> {
+    conn.query_row(
+        "SELECT leaving_date, leaving_reason FROM students WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+pub fn set_status(conn: &Connection, id: i64, status: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![status, id],
+    )
+}
+
+pub fn set_leaving_details(
+    conn: &Connection,
+    id: i64,
+    leaving_date: &str,
+    leaving_reason: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE students SET
+            leaving_date = ?1,
+            leaving_reason = ?2,
+            updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?3",
+        params![leaving_date, leaving_reason, id],
+    )
+}
+
+pub fn find_docs(conn: &Connection, id: i64) -> rusqlite::Result<Option<StudentDocs>> {
+    conn.query_row(
+        "SELECT birth_certificate, transfer_certificate, previous_academic_records,
+                address_proof, id_proof, passport_photo, medical_certificate,
+                vaccination_certificate, other_documents
+         FROM students WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(StudentDocs {
+                birth_certificate: row.get(0)?,
+                transfer_certificate: row.get(1)?,
+                previous_academic_records: row.get(2)?,
+                address_proof: row.get(3)?,
+                id_proof: row.get(4)?,
+                passport_photo: row.get(5)?,
+                medical_certificate: row.get(6)?,
+                vaccination_certificate: row.get(7)?,
+                other_documents: row.get(8)?,
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM students WHERE id = ?1", params![id])
+}
@@ -0,0 +1,266 @@
+use crate::subject::{ClassSubject, ClassTeacher, Subject};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+fn map_subject(row: &Row) -> rusqlite::Result<Subject> {
+    Ok(Subject {
+        id: Some(row.get(0)?),
+        code: row.get(1)?,
+        name: row.get(2)?,
+        subject_type: row.get(3)?,
+        status: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+pub fn insert(conn: &Connection, subject: &Subject, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO subjects (code, name, subject_type, status) VALUES (?1, ?2, ?3, ?4)",
+        params![subject.code, subject.name, subject.subject_type, status],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update(
+    conn: &Connection,
+    id: i64,
+    subject: &Subject,
+    status: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE subjects SET code = ?1, name = ?2, subject_type = ?3, status = ?4 WHERE id = ?5",
+        params![subject.code, subject.name, subject.subject_type, status, id],
+    )
+}
+
+pub fn delete(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM subjects WHERE id = ?1", params![id])
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Subject>> {
+    conn.query_row(
+        "SELECT id, code, name, subject_type, status, created_at FROM subjects WHERE id = ?1",
+        params![id],
+        map_subject,
+    )
+    .optional()
+}
+
+pub fn find_all(conn: &Connection, include_inactive: bool) -> rusqlite::Result<Vec<Subject>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, code, name, subject_type, status, created_at FROM subjects
+         WHERE ?1 OR status = 'active'
+         ORDER BY name",
+    )?;
+    let subjects = stmt
+        .query_map(params![include_inactive], map_subject)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(subjects)
+}
+
+/// Case-insensitive code lookup, ignoring `exclude_id`.
+pub fn code_taken(
+    conn: &Connection,
+    code: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM subjects WHERE code = ?1 AND id != COALESCE(?2, -1))",
+        params![code, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn count_class_mappings(conn: &Connection, subject_id: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM class_subjects WHERE subject_id = ?1",
+        params![subject_id],
+        |row| row.get(0),
+    )
+}
+
+const SELECT_CLASS_SUBJECTS: &str = "SELECT
+        cs.id, cs.class_id, cs.subject_id, cs.periods_per_week, cs.teacher_id,
+        cs.academic_year_id, c.class_name, s.code, s.name, s.subject_type, st.name
+     FROM class_subjects cs
+     JOIN classes c ON c.id = cs.class_id
+     JOIN subjects s ON s.id = cs.subject_id
+     LEFT JOIN staff st ON st.id = cs.teacher_id";
+
+fn map_class_subject(row: &Row) -> rusqlite::Result<ClassSubject> {
+    Ok(ClassSubject {
+        id: Some(row.get(0)?),
+        class_id: row.get(1)?,
+        subject_id: row.get(2)?,
+        periods_per_week: row.get(3)?,
+        teacher_id: row.get(4)?,
+        academic_year_id: Some(row.get(5)?),
+        class_name: Some(row.get(6)?),
+        subject_code: Some(row.get(7)?),
+        subject_name: Some(row.get(8)?),
+        subject_type: Some(row.get(9)?),
+        teacher_name: row.get(10)?,
+    })
+}
+
+pub fn upsert_class_subject(
+    conn: &Connection,
+    assignment: &ClassSubject,
+    academic_year_id: i64,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO class_subjects (class_id, subject_id, academic_year_id, periods_per_week, teacher_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(class_id, subject_id) DO UPDATE SET
+            periods_per_week = excluded.periods_per_week,
+            teacher_id = excluded.teacher_id
+         RETURNING id",
+        params![
+            assignment.class_id,
+            assignment.subject_id,
+            academic_year_id,
+            assignment.periods_per_week,
+            assignment.teacher_id,
+        ],
+        |row| row.get(0),
+    )
+}
+
+pub fn delete_class_subject(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM class_subjects WHERE id = ?1", params![id])
+}
+
+pub fn find_class_subject(conn: &Connection, id: i64) -> rusqlite::Result<Option<ClassSubject>> {
+    conn.query_row(
+        &format!("{} WHERE cs.id = ?1", SELECT_CLASS_SUBJECTS),
+        params![id],
+        map_class_subject,
+    )
+    .optional()
+}
+
+pub fn find_class_subjects(
+    conn: &Connection,
+    class_id: i64,
+) -> rusqlite::Result<Vec<ClassSubject>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE cs.class_id = ?1 ORDER BY s.name",
+        SELECT_CLASS_SUBJECTS
+    ))?;
+    let subjects = stmt
+        .query_map(params![class_id], map_class_subject)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(subjects)
+}
+
+/// Every mapping with a teacher in the academic year, ordered by teacher then class.
+pub fn find_taught_in_year(
+    conn: &Connection,
+    academic_year_id: i64,
+) -> rusqlite::Result<Vec<ClassSubject>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE cs.academic_year_id = ?1 AND cs.teacher_id IS NOT NULL
+         ORDER BY cs.teacher_id, c.grade_order, c.section, c.class_name, s.name",
+        SELECT_CLASS_SUBJECTS
+    ))?;
+    let subjects = stmt
+        .query_map(params![academic_year_id], map_class_subject)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(subjects)
+}
+
+pub fn set_subject_teacher(
+    conn: &Connection,
+    id: i64,
+    teacher_id: Option<i64>,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE class_subjects SET teacher_id = ?1 WHERE id = ?2",
+        params![teacher_id, id],
+    )
+}
+
+/// Adds the subjects of each class in one year to the class of the same name in another,
+/// skipping subjects already mapped there. Returns the number of mappings added.
+pub fn copy_mappings(conn: &Connection, from_year: i64, to_year: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT OR IGNORE INTO class_subjects (class_id, subject_id, academic_year_id, periods_per_week)
+         SELECT target.id, cs.subject_id, ?2, cs.periods_per_week
+         FROM class_subjects cs
+         JOIN classes source ON source.id = cs.class_id
+         JOIN classes target ON LOWER(TRIM(target.class_name)) = LOWER(TRIM(source.class_name))
+                            AND target.academic_years = ?2
+         WHERE cs.academic_year_id = ?1",
+        params![from_year, to_year],
+    )
+}
+
+pub fn set_class_teacher(conn: &Connection, class_id: i64, staff_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO class_teachers (class_id, staff_id) VALUES (?1, ?2)
+         ON CONFLICT(class_id) DO UPDATE SET
+            staff_id = excluded.staff_id,
+            assigned_at = CURRENT_TIMESTAMP",
+        params![class_id, staff_id],
+    )?;
+    Ok(())
+}
+
+pub fn clear_class_teacher(conn: &Connection, class_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM class_teachers WHERE class_id = ?1",
+        params![class_id],
+    )
+}
+
+pub fn find_class_teachers(
+    conn: &Connection,
+    academic_year_id: i64,
+) -> rusqlite::Result<Vec<ClassTeacher>> {
+    let mut stmt = conn.prepare(
+        "SELECT ct.class_id, c.class_name, ct.staff_id, s.name
+         FROM class_teachers ct
+         JOIN classes c ON c.id = ct.class_id
+         JOIN staff s ON s.id = ct.staff_id
+         WHERE c.academic_years = ?1
+         ORDER BY c.grade_order, c.section, c.class_name",
+    )?;
+    let teachers = stmt
+        .query_map(params![academic_year_id], |row| {
+            Ok(ClassTeacher {
+                class_id: row.get(0)?,
+                class_name: row.get(1)?,
+                staff_id: row.get(2)?,
+                staff_name: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(teachers)
+}
+
+pub fn find_max_periods(conn: &Connection, staff_id: i64) -> rusqlite::Result<Option<u32>> {
+    conn.query_row(
+        "SELECT max_periods_per_week FROM teacher_period_limits WHERE staff_id = ?1",
+        params![staff_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn set_max_periods(
+    conn: &Connection,
+    staff_id: i64,
+    max_periods: Option<u32>,
+) -> rusqlite::Result<()> {
+    match max_periods {
+        Some(max) => conn.execute(
+            "INSERT INTO teacher_period_limits (staff_id, max_periods_per_week) VALUES (?1, ?2)
+             ON CONFLICT(staff_id) DO UPDATE SET max_periods_per_week = excluded.max_periods_per_week",
+            params![staff_id, max],
+        )?,
+        None => conn.execute(
+            "DELETE FROM teacher_period_limits WHERE staff_id = ?1",
+            params![staff_id],
+        )?,
+    };
+    Ok(())
+}
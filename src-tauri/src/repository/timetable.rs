@@ -0,0 +1,304 @@
+use crate::timetable::{BellSlot, Room, TimetableEntry, TimetableSettings};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+pub fn find_settings(conn: &Connection) -> rusqlite::Result<TimetableSettings> {
+    conn.query_row(
+        "SELECT working_days, max_periods_per_day FROM timetable_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(TimetableSettings {
+                working_days: row.get(0)?,
+                max_periods_per_day: row.get(1)?,
+            })
+        },
+    )
+}
+
+pub fn save_settings(conn: &Connection, settings: &TimetableSettings) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE timetable_settings SET working_days = ?1, max_periods_per_day = ?2 WHERE id = 1",
+        params![settings.working_days, settings.max_periods_per_day],
+    )?;
+    Ok(())
+}
+
+pub fn find_bell_schedule(conn: &Connection) -> rusqlite::Result<Vec<BellSlot>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, label, kind, start_time, end_time FROM bell_schedule ORDER BY position",
+    )?;
+    let slots = stmt
+        .query_map([], |row| {
+            Ok(BellSlot {
+                id: Some(row.get(0)?),
+                label: row.get(1)?,
+                kind: row.get(2)?,
+                start_time: row.get(3)?,
+                end_time: row.get(4)?,
+                period_no: None,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(slots)
+}
+
+pub fn replace_bell_schedule(conn: &Connection, slots: &[BellSlot]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM bell_schedule", [])?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO bell_schedule (position, label, kind, start_time, end_time)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (position, slot) in slots.iter().enumerate() {
+        stmt.execute(params![
+            position as i64 + 1,
+            slot.label,
+            slot.kind,
+            slot.start_time,
+            slot.end_time
+        ])?;
+    }
+    Ok(())
+}
+
+/// Highest period number used by any timetable entry.
+pub fn max_period_used(conn: &Connection) -> rusqlite::Result<Option<u32>> {
+    conn.query_row("SELECT MAX(period_no) FROM timetable_entries", [], |row| {
+        row.get(0)
+    })
+}
+
+/// Latest weekday used by any timetable entry.
+pub fn max_day_used(conn: &Connection) -> rusqlite::Result<Option<u32>> {
+    conn.query_row("SELECT MAX(day) FROM timetable_entries", [], |row| {
+        row.get(0)
+    })
+}
+
+fn map_room(row: &Row) -> rusqlite::Result<Room> {
+    Ok(Room {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        capacity: row.get(2)?,
+        status: row.get(3)?,
+    })
+}
+
+pub fn find_rooms(conn: &Connection, include_inactive: bool) -> rusqlite::Result<Vec<Room>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, capacity, status FROM rooms
+         WHERE ?1 OR status = 'active'
+         ORDER BY name",
+    )?;
+    let rooms = stmt
+        .query_map(params![include_inactive], map_room)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rooms)
+}
+
+pub fn find_room(conn: &Connection, id: i64) -> rusqlite::Result<Option<Room>> {
+    conn.query_row(
+        "SELECT id, name, capacity, status FROM rooms WHERE id = ?1",
+        params![id],
+        map_room,
+    )
+    .optional()
+}
+
+pub fn room_name_taken(
+    conn: &Connection,
+    name: &str,
+    exclude_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM rooms WHERE name = ?1 AND id != COALESCE(?2, -1))",
+        params![name, exclude_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_room(conn: &Connection, room: &Room, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO rooms (name, capacity, status) VALUES (?1, ?2, ?3)",
+        params![room.name, room.capacity, status],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_room(
+    conn: &Connection,
+    id: i64,
+    room: &Room,
+    status: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE rooms SET name = ?1, capacity = ?2, status = ?3 WHERE id = ?4",
+        params![room.name, room.capacity, status, id],
+    )
+}
+
+pub fn delete_room(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM rooms WHERE id = ?1", params![id])
+}
+
+const SELECT_ENTRIES: &str = "SELECT
+        te.id, te.class_id, te.day, te.period_no, te.class_subject_id, te.room_id,
+        te.academic_year_id, c.class_name, cs.subject_id, s.code, s.name,
+        cs.teacher_id, st.name, r.name
+     FROM timetable_entries te
+     JOIN classes c ON c.id = te.class_id
+     JOIN class_subjects cs ON cs.id = te.class_subject_id
+     JOIN subjects s ON s.id = cs.subject_id
+     LEFT JOIN staff st ON st.id = cs.teacher_id
+     LEFT JOIN rooms r ON r.id = te.room_id";
+
+fn map_entry(row: &Row) -> rusqlite::Result<TimetableEntry> {
+    Ok(TimetableEntry {
+        id: Some(row.get(0)?),
+        class_id: row.get(1)?,
+        day: row.get(2)?,
+        period_no: row.get(3)?,
+        class_subject_id: row.get(4)?,
+        room_id: row.get(5)?,
+        academic_year_id: Some(row.get(6)?),
+        class_name: Some(row.get(7)?),
+        subject_id: Some(row.get(8)?),
+        subject_code: Some(row.get(9)?),
+        subject_name: Some(row.get(10)?),
+        teacher_id: row.get(11)?,
+        teacher_name: row.get(12)?,
+        room_name: row.get(13)?,
+    })
+}
+
+fn query_entries(
+    conn: &Connection,
+    filter: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Vec<TimetableEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE {} ORDER BY te.day, te.period_no, c.grade_order, c.section, c.class_name",
+        SELECT_ENTRIES, filter
+    ))?;
+    let entries = stmt
+        .query_map(params, map_entry)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+pub fn find_entry(conn: &Connection, id: i64) -> rusqlite::Result<Option<TimetableEntry>> {
+    Ok(query_entries(conn, "te.id = ?1", params![id])?.pop())
+}
+
+pub fn find_class_entries(
+    conn: &Connection,
+    class_id: i64,
+) -> rusqlite::Result<Vec<TimetableEntry>> {
+    query_entries(conn, "te.class_id = ?1", params![class_id])
+}
+
+pub fn find_year_entries(
+    conn: &Connection,
+    academic_year_id: i64,
+) -> rusqlite::Result<Vec<TimetableEntry>> {
+    query_entries(conn, "te.academic_year_id = ?1", params![academic_year_id])
+}
+
+pub fn find_teacher_entries(
+    conn: &Connection,
+    staff_id: i64,
+    academic_year_id: i64,
+) -> rusqlite::Result<Vec<TimetableEntry>> {
+    query_entries(
+        conn,
+        "cs.teacher_id = ?1 AND te.academic_year_id = ?2",
+        params![staff_id, academic_year_id],
+    )
+}
+
+/// Places the entry in its class period, replacing the subject already there.
+pub fn upsert_entry(
+    conn: &Connection,
+    entry: &TimetableEntry,
+    academic_year_id: i64,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO timetable_entries
+            (academic_year_id, class_id, day, period_no, class_subject_id, room_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(class_id, day, period_no) DO UPDATE SET
+            class_subject_id = excluded.class_subject_id,
+            room_id = excluded.room_id
+         RETURNING id",
+        params![
+            academic_year_id,
+            entry.class_id,
+            entry.day,
+            entry.period_no,
+            entry.class_subject_id,
+            entry.room_id,
+        ],
+        |row| row.get(0),
+    )
+}
+
+pub fn delete_entry(
+    conn: &Connection,
+    class_id: i64,
+    day: u32,
+    period_no: u32,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM timetable_entries WHERE class_id = ?1 AND day = ?2 AND period_no = ?3",
+        params![class_id, day, period_no],
+    )
+}
+
+pub fn delete_class_entries(conn: &Connection, class_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM timetable_entries WHERE class_id = ?1",
+        params![class_id],
+    )
+}
+
+/// Substitutions recorded for `date` as `(entry_id, substitute_id, substitute_name)`.
+pub fn find_substitutions(
+    conn: &Connection,
+    date: &str,
+) -> rusqlite::Result<Vec<(i64, i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT ts.entry_id, ts.substitute_id, s.name
+         FROM timetable_substitutions ts
+         JOIN staff s ON s.id = ts.substitute_id
+         WHERE ts.date = ?1",
+    )?;
+    let substitutions = stmt
+        .query_map(params![date], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(substitutions)
+}
+
+pub fn upsert_substitution(
+    conn: &Connection,
+    date: &str,
+    entry_id: i64,
+    substitute_id: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO timetable_substitutions (date, entry_id, substitute_id) VALUES (?1, ?2, ?3)
+         ON CONFLICT(date, entry_id) DO UPDATE SET substitute_id = excluded.substitute_id",
+        params![date, entry_id, substitute_id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_substitution(
+    conn: &Connection,
+    date: &str,
+    entry_id: i64,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM timetable_substitutions WHERE date = ?1 AND entry_id = ?2",
+        params![date, entry_id],
+    )
+}
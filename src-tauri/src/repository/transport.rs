@@ -0,0 +1,383 @@
+use crate::transport::{FareSlab, RouteStop, TransportAllocation, TransportRoute, Vehicle};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const ON_ROLL: &str = "(s.status IS NULL OR s.status IN ('active', 'suspended'))";
+
+const SELECT_VEHICLES: &str = "SELECT v.id, v.registration_number, v.capacity, v.vehicle_type,
+        v.fitness_expiry, v.insurance_expiry, v.driver_id, v.status, st.name, st.phone
+     FROM transport_vehicles v
+     LEFT JOIN staff st ON st.id = v.driver_id";
+
+const SELECT_ALLOCATIONS: &str = "SELECT a.student_id, a.stop_id, a.allocated_on, s.full_name,
+        c.class_name, s.roll_number, s.mobile_number, r.id, r.name, st.name, st.pickup_time,
+        st.distance_km,
+        (SELECT f.amount FROM transport_fare_slabs f
+          WHERE st.distance_km >= f.from_km AND st.distance_km < f.to_km
+          ORDER BY f.from_km LIMIT 1),
+        NULLIF(TRIM(s.medical_conditions), '')
+     FROM student_transport a
+     JOIN students s ON s.id = a.student_id
+     LEFT JOIN classes c ON c.id = s.class_id
+     JOIN transport_stops st ON st.id = a.stop_id
+     JOIN transport_routes r ON r.id = st.route_id";
+
+fn map_vehicle(row: &Row) -> rusqlite::Result<Vehicle> {
+    Ok(Vehicle {
+        id: Some(row.get(0)?),
+        registration_number: row.get(1)?,
+        capacity: row.get(2)?,
+        vehicle_type: row.get(3)?,
+        fitness_expiry: row.get(4)?,
+        insurance_expiry: row.get(5)?,
+        driver_id: row.get(6)?,
+        status: Some(row.get(7)?),
+        driver_name: row.get(8)?,
+        driver_phone: row.get(9)?,
+    })
+}
+
+fn map_allocation(row: &Row) -> rusqlite::Result<TransportAllocation> {
+    Ok(TransportAllocation {
+        student_id: row.get(0)?,
+        stop_id: row.get(1)?,
+        allocated_on: Some(row.get(2)?),
+        student_name: Some(row.get(3)?),
+        class_name: row.get(4)?,
+        roll_number: row.get(5)?,
+        mobile_number: row.get(6)?,
+        route_id: Some(row.get(7)?),
+        route_name: Some(row.get(8)?),
+        stop_name: Some(row.get(9)?),
+        pickup_time: Some(row.get(10)?),
+        distance_km: Some(row.get(11)?),
+        fare: row.get(12)?,
+        capacity_warning: None,
+        medical_alert: row.get(13)?,
+    })
+}
+
+pub fn find_vehicles(conn: &Connection) -> rusqlite::Result<Vec<Vehicle>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} ORDER BY v.status, v.registration_number",
+        SELECT_VEHICLES
+    ))?;
+    let vehicles = stmt
+        .query_map([], map_vehicle)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(vehicles)
+}
+
+pub fn find_vehicle(conn: &Connection, id: i64) -> rusqlite::Result<Option<Vehicle>> {
+    conn.query_row(
+        &format!("{} WHERE v.id = ?1", SELECT_VEHICLES),
+        params![id],
+        map_vehicle,
+    )
+    .optional()
+}
+
+pub fn registration_taken(
+    conn: &Connection,
+    registration_number: &str,
+    except_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM transport_vehicles
+          WHERE registration_number = ?1 COLLATE NOCASE AND (?2 IS NULL OR id != ?2))",
+        params![registration_number, except_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_vehicle(conn: &Connection, vehicle: &Vehicle, status: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO transport_vehicles (registration_number, capacity, vehicle_type,
+            fitness_expiry, insurance_expiry, driver_id, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            vehicle.registration_number,
+            vehicle.capacity,
+            vehicle.vehicle_type,
+            vehicle.fitness_expiry,
+            vehicle.insurance_expiry,
+            vehicle.driver_id,
+            status
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_vehicle(
+    conn: &Connection,
+    id: i64,
+    vehicle: &Vehicle,
+    status: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE transport_vehicles SET registration_number = ?1, capacity = ?2,
+            vehicle_type = ?3, fitness_expiry = ?4, insurance_expiry = ?5, driver_id = ?6,
+            status = ?7
+         WHERE id = ?8",
+        params![
+            vehicle.registration_number,
+            vehicle.capacity,
+            vehicle.vehicle_type,
+            vehicle.fitness_expiry,
+            vehicle.insurance_expiry,
+            vehicle.driver_id,
+            status,
+            id
+        ],
+    )
+}
+
+pub fn delete_vehicle(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM transport_vehicles WHERE id = ?1", params![id])
+}
+
+/// Name of a route the vehicle runs, if any.
+pub fn find_route_of_vehicle(
+    conn: &Connection,
+    vehicle_id: i64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT name FROM transport_routes WHERE vehicle_id = ?1 ORDER BY name LIMIT 1",
+        params![vehicle_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Routes with their vehicle and students on the roll; stops are loaded separately.
+pub fn find_routes(conn: &Connection) -> rusqlite::Result<Vec<TransportRoute>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT r.id, r.name, r.vehicle_id, v.registration_number, v.capacity,
+            (SELECT COUNT(*) FROM student_transport a
+              JOIN transport_stops st ON st.id = a.stop_id
+              JOIN students s ON s.id = a.student_id
+              WHERE st.route_id = r.id AND {})
+         FROM transport_routes r
+         LEFT JOIN transport_vehicles v ON v.id = r.vehicle_id
+         ORDER BY r.name COLLATE NOCASE",
+        ON_ROLL
+    ))?;
+    let routes = stmt
+        .query_map([], |row| {
+            Ok(TransportRoute {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                vehicle_id: row.get(2)?,
+                stops: Vec::new(),
+                registration_number: row.get(3)?,
+                capacity: row.get(4)?,
+                students: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(routes)
+}
+
+pub fn find_stops(conn: &Connection, route_id: i64) -> rusqlite::Result<Vec<RouteStop>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT st.id, st.name, st.pickup_time, st.drop_time, st.distance_km,
+            (SELECT COUNT(*) FROM student_transport a
+              JOIN students s ON s.id = a.student_id
+              WHERE a.stop_id = st.id AND {})
+         FROM transport_stops st
+         WHERE st.route_id = ?1
+         ORDER BY st.stop_order",
+        ON_ROLL
+    ))?;
+    let stops = stmt
+        .query_map(params![route_id], |row| {
+            Ok(RouteStop {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                pickup_time: row.get(2)?,
+                drop_time: row.get(3)?,
+                distance_km: row.get(4)?,
+                students: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(stops)
+}
+
+pub fn route_name_taken(
+    conn: &Connection,
+    name: &str,
+    except_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM transport_routes
+          WHERE name = ?1 COLLATE NOCASE AND (?2 IS NULL OR id != ?2))",
+        params![name, except_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_route(conn: &Connection, route: &TransportRoute) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO transport_routes (name, vehicle_id) VALUES (?1, ?2)",
+        params![route.name, route.vehicle_id],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_route(conn: &Connection, id: i64, route: &TransportRoute) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE transport_routes SET name = ?1, vehicle_id = ?2 WHERE id = ?3",
+        params![route.name, route.vehicle_id, id],
+    )
+}
+
+/// Deletes the route, its stops and any allocations of students no longer on the roll.
+pub fn delete_route(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM student_transport
+         WHERE stop_id IN (SELECT id FROM transport_stops WHERE route_id = ?1)",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM transport_stops WHERE route_id = ?1",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM transport_routes WHERE id = ?1", params![id])
+}
+
+pub fn insert_stop(
+    conn: &Connection,
+    route_id: i64,
+    stop_order: usize,
+    stop: &RouteStop,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO transport_stops (route_id, stop_order, name, pickup_time, drop_time,
+            distance_km)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            route_id,
+            stop_order as i64,
+            stop.name,
+            stop.pickup_time,
+            stop.drop_time,
+            stop.distance_km
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_stop(
+    conn: &Connection,
+    id: i64,
+    stop_order: usize,
+    stop: &RouteStop,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE transport_stops SET stop_order = ?1, name = ?2, pickup_time = ?3,
+            drop_time = ?4, distance_km = ?5
+         WHERE id = ?6",
+        params![
+            stop_order as i64,
+            stop.name,
+            stop.pickup_time,
+            stop.drop_time,
+            stop.distance_km,
+            id
+        ],
+    )
+}
+
+/// Deletes a stop along with allocations of students no longer on the roll.
+pub fn delete_stop(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM student_transport WHERE stop_id = ?1",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM transport_stops WHERE id = ?1", params![id])
+}
+
+/// Route id of the stop.
+pub fn find_stop_route(conn: &Connection, stop_id: i64) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        "SELECT route_id FROM transport_stops WHERE id = ?1",
+        params![stop_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn find_fare_slabs(conn: &Connection) -> rusqlite::Result<Vec<FareSlab>> {
+    let mut stmt =
+        conn.prepare("SELECT from_km, to_km, amount FROM transport_fare_slabs ORDER BY from_km")?;
+    let slabs = stmt
+        .query_map([], |row| {
+            Ok(FareSlab {
+                from_km: row.get(0)?,
+                to_km: row.get(1)?,
+                amount: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(slabs)
+}
+
+pub fn replace_fare_slabs(conn: &Connection, slabs: &[FareSlab]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM transport_fare_slabs", [])?;
+    for slab in slabs {
+        conn.execute(
+            "INSERT INTO transport_fare_slabs (from_km, to_km, amount) VALUES (?1, ?2, ?3)",
+            params![slab.from_km, slab.to_km, slab.amount],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn find_allocation(
+    conn: &Connection,
+    student_id: i64,
+) -> rusqlite::Result<Option<TransportAllocation>> {
+    conn.query_row(
+        &format!("{} WHERE a.student_id = ?1", SELECT_ALLOCATIONS),
+        params![student_id],
+        map_allocation,
+    )
+    .optional()
+}
+
+/// Allocations of students on the roll, of one route or all, in pickup order.
+pub fn find_allocations(
+    conn: &Connection,
+    route_id: Option<i64>,
+) -> rusqlite::Result<Vec<TransportAllocation>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE {} AND (?1 IS NULL OR r.id = ?1)
+         ORDER BY r.name COLLATE NOCASE, st.stop_order, s.full_name",
+        SELECT_ALLOCATIONS, ON_ROLL
+    ))?;
+    let allocations = stmt
+        .query_map(params![route_id], map_allocation)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(allocations)
+}
+
+pub fn upsert_allocation(
+    conn: &Connection,
+    student_id: i64,
+    stop_id: i64,
+    allocated_on: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO student_transport (student_id, stop_id, allocated_on) VALUES (?1, ?2, ?3)
+         ON CONFLICT(student_id) DO UPDATE SET stop_id = excluded.stop_id,
+            allocated_on = excluded.allocated_on",
+        params![student_id, stop_id, allocated_on],
+    )
+}
+
+pub fn delete_allocation(conn: &Connection, student_id: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM student_transport WHERE student_id = ?1",
+        params![student_id],
+    )
+}
@@ -0,0 +1,176 @@
+use crate::visitor::{GatePass, Visitor};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_VISITORS: &str = "SELECT id, visitor_name, phone, purpose, person_to_meet,
+        staff_id, visited_on, in_time, out_time, photo, remarks
+     FROM visitors";
+
+const SELECT_GATE_PASSES: &str = "SELECT p.id, p.student_id, p.guardian_id, p.issued_on,
+        p.out_time, p.reason, p.collected_by, p.approved_by, s.full_name, c.class_name,
+        g.full_name, g.relation_type, g.phone
+     FROM gate_passes p
+     JOIN students s ON s.id = p.student_id
+     LEFT JOIN classes c ON c.id = s.class_id
+     JOIN guardians g ON g.id = p.guardian_id";
+
+fn map_visitor(row: &Row) -> rusqlite::Result<Visitor> {
+    Ok(Visitor {
+        id: Some(row.get(0)?),
+        visitor_name: row.get(1)?,
+        phone: row.get(2)?,
+        purpose: row.get(3)?,
+        person_to_meet: row.get(4)?,
+        staff_id: row.get(5)?,
+        visited_on: Some(row.get(6)?),
+        in_time: Some(row.get(7)?),
+        out_time: row.get(8)?,
+        photo: row.get(9)?,
+        remarks: row.get(10)?,
+    })
+}
+
+fn map_gate_pass(row: &Row) -> rusqlite::Result<GatePass> {
+    Ok(GatePass {
+        id: Some(row.get(0)?),
+        student_id: row.get(1)?,
+        guardian_id: row.get(2)?,
+        issued_on: Some(row.get(3)?),
+        out_time: Some(row.get(4)?),
+        reason: row.get(5)?,
+        collected_by: Some(row.get(6)?),
+        approved_by: row.get(7)?,
+        student_name: Some(row.get(8)?),
+        class_name: row.get(9)?,
+        guardian_name: Some(row.get(10)?),
+        guardian_relation: Some(row.get(11)?),
+        guardian_phone: row.get(12)?,
+    })
+}
+
+pub fn insert_visitor(conn: &Connection, visitor: &Visitor) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO visitors (visitor_name, phone, purpose, person_to_meet, staff_id,
+            visited_on, in_time, out_time, photo, remarks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            visitor.visitor_name,
+            visitor.phone,
+            visitor.purpose,
+            visitor.person_to_meet,
+            visitor.staff_id,
+            visitor.visited_on,
+            visitor.in_time,
+            visitor.out_time,
+            visitor.photo,
+            visitor.remarks
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_visitor(conn: &Connection, id: i64) -> rusqlite::Result<Option<Visitor>> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", SELECT_VISITORS),
+        params![id],
+        map_visitor,
+    )
+    .optional()
+}
+
+pub fn set_out_time(conn: &Connection, id: i64, out_time: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE visitors SET out_time = ?1 WHERE id = ?2",
+        params![out_time, id],
+    )
+}
+
+/// Visits between the dates whose visitor, phone, purpose or person to meet contains
+/// `term`, latest first. An empty term matches every visit.
+pub fn search_visitors(
+    conn: &Connection,
+    term: &str,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<Visitor>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE visited_on BETWEEN ?2 AND ?3
+           AND (visitor_name LIKE '%' || ?1 || '%' OR phone LIKE '%' || ?1 || '%'
+             OR purpose LIKE '%' || ?1 || '%' OR person_to_meet LIKE '%' || ?1 || '%')
+         ORDER BY visited_on DESC, in_time DESC",
+        SELECT_VISITORS
+    ))?;
+    let visitors = stmt
+        .query_map(params![term, from, to], map_visitor)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(visitors)
+}
+
+/// Visits of one day in the order they came in.
+pub fn find_visitors_on(conn: &Connection, date: &str) -> rusqlite::Result<Vec<Visitor>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE visited_on = ?1 ORDER BY in_time, id",
+        SELECT_VISITORS
+    ))?;
+    let visitors = stmt
+        .query_map(params![date], map_visitor)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(visitors)
+}
+
+pub fn insert_gate_pass(conn: &Connection, pass: &GatePass) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO gate_passes (student_id, guardian_id, issued_on, out_time, reason,
+            collected_by, approved_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            pass.student_id,
+            pass.guardian_id,
+            pass.issued_on,
+            pass.out_time,
+            pass.reason,
+            pass.collected_by,
+            pass.approved_by
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_gate_pass(conn: &Connection, id: i64) -> rusqlite::Result<Option<GatePass>> {
+    conn.query_row(
+        &format!("{} WHERE p.id = ?1", SELECT_GATE_PASSES),
+        params![id],
+        map_gate_pass,
+    )
+    .optional()
+}
+
+/// Out time of a pass already issued to the student on the date.
+pub fn find_pass_time(
+    conn: &Connection,
+    student_id: i64,
+    issued_on: &str,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT out_time FROM gate_passes WHERE student_id = ?1 AND issued_on = ?2 LIMIT 1",
+        params![student_id, issued_on],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn find_gate_passes(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: &str,
+    to: &str,
+) -> rusqlite::Result<Vec<GatePass>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR p.student_id = ?1) AND p.issued_on BETWEEN ?2 AND ?3
+         ORDER BY p.issued_on DESC, p.out_time DESC",
+        SELECT_GATE_PASSES
+    ))?;
+    let passes = stmt
+        .query_map(params![student_id, from, to], map_gate_pass)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(passes)
+}
@@ -0,0 +1,356 @@
+use crate::academic_year::{AcademicYear, YearOverride};
+use crate::date::Date;
+use crate::repository;
+use crate::repository::academic_year as repo;
+use crate::repository::class as class_repo;
+use crate::repository::staff as staff_repo;
+use crate::service::calendar;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+
+/// Validates the dates of a year: both or neither, in order, and clear of other years.
+fn check_dates(
+    conn: &Connection,
+    id: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Option<(Date, Date)>, String> {
+    let blank = |d: Option<String>| d.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    let (start, end) = match (blank(start_date), blank(end_date)) {
+        (None, None) => return Ok(None),
+        (Some(start), Some(end)) => (Date::parse(&start)?, Date::parse(&end)?),
+        _ => return Err("Both the start and end dates of the academic year are required".to_string()),
+    };
+    if end <= start {
+        return Err("The academic year must end after it starts".to_string());
+    }
+    if let Some(other) = repo::find_overlapping(conn, &start.to_string(), &end.to_string(), id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!("The dates overlap academic year {}", other));
+    }
+    if let Some(id) = id {
+        calendar::ensure_fits_year(conn, id, start, end)?;
+    }
+    Ok(Some((start, end)))
+}
+
+/// Creates the year, or updates an existing one with the same label. Dates are optional
+/// but, once given, must not overlap another year.
+pub fn upsert_academic_year(
+    conn: &Connection,
+    year: &str,
+    set_as_current: bool,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<i64, String> {
+    let year = year.trim();
+    if year.is_empty() {
+        return Err("Academic year is required".to_string());
+    }
+    let existing = repo::find_id_by_label(conn, year).map_err(|e| e.to_string())?;
+    if let (Some(id), true) = (existing, set_as_current) {
+        ensure_not_closed(&find_year(conn, id)?)?;
+    }
+    let dates = check_dates(conn, existing, start_date, end_date)?;
+    if let (Some(id), Some(_)) = (existing, dates) {
+        ensure_open(conn, id)?;
+    }
+
+    let id = match existing {
+        Some(id) => {
+            repo::touch(conn, id).map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            let status = if set_as_current { "active" } else { "inactive" };
+            repo::insert(conn, year, status).map_err(|e| e.to_string())?
+        }
+    };
+
+    if let Some((start, end)) = dates {
+        repo::set_dates(conn, id, &start.to_string(), &end.to_string())
+            .map_err(|e| e.to_string())?;
+    }
+    if set_as_current {
+        repo::set_current(conn, id).map_err(|e| e.to_string())?;
+    }
+
+    Ok(id)
+}
+
+pub fn get_current_academic_year(conn: &Connection) -> Result<Option<AcademicYear>, String> {
+    repo::find_current(conn).map_err(|e| e.to_string())
+}
+
+pub fn get_all_academic_years(conn: &Connection) -> Result<Vec<AcademicYear>, String> {
+    repo::find_all(conn).map_err(|e| e.to_string())
+}
+
+/// A closed year cannot become the current one; overrides only allow corrections to it.
+fn ensure_not_closed(year: &AcademicYear) -> Result<(), String> {
+    if year.closed_at.is_some() {
+        return Err(format!(
+            "{} is closed and cannot be made the current year",
+            year.academic_year
+        ));
+    }
+    Ok(())
+}
+
+pub fn set_current_academic_year(conn: &Connection, id: i64) -> Result<(), String> {
+    ensure_not_closed(&find_year(conn, id)?)?;
+    repo::set_current(conn, id).map_err(|e| e.to_string())
+}
+
+fn find_year(conn: &Connection, id: i64) -> Result<AcademicYear, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Academic year does not exist".to_string())
+}
+
+/// Years can only be deleted before anything refers to them.
+pub fn delete_academic_year(conn: &Connection, id: i64) -> Result<(), String> {
+    let year = find_year(conn, id)?;
+    let dependents = repo::count_dependents(conn, id).map_err(|e| e.to_string())?;
+    if !dependents.is_empty() {
+        let listed: Vec<String> = dependents
+            .iter()
+            .map(|(what, count)| format!("{} ({})", what, count))
+            .collect();
+        return Err(format!(
+            "Cannot delete {} while it has {}",
+            year.academic_year,
+            listed.join(", ")
+        ));
+    }
+    repo::delete(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Fails when the year is closed and no override is in force. Every write to a year's
+/// classes, subjects, timetable or calendar checks this first.
+pub fn ensure_open(conn: &Connection, id: i64) -> Result<(), String> {
+    let year = find_year(conn, id)?;
+    if year.closed_at.is_none() {
+        return Ok(());
+    }
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    if repo::has_open_override(conn, id, &today).map_err(|e| e.to_string())? {
+        return Ok(());
+    }
+    Err(format!(
+        "Academic year {} is closed. An admin override must be recorded to change it",
+        year.academic_year
+    ))
+}
+
+/// `ensure_open` for the year of a class.
+pub fn ensure_class_open(conn: &Connection, class_id: i64) -> Result<(), String> {
+    let class = class_repo::find_by_id(conn, class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())?;
+    ensure_open(conn, class.academic_years)
+}
+
+pub fn close_academic_year(conn: &Connection, id: i64, closed_by: &str) -> Result<(), String> {
+    let year = find_year(conn, id)?;
+    let closed_by = closed_by.trim();
+    if closed_by.is_empty() {
+        return Err("Record who is closing the year".to_string());
+    }
+    if year.status.as_deref() == Some("active") {
+        return Err(format!(
+            "{} is the current year; make another year current before closing it",
+            year.academic_year
+        ));
+    }
+    if repo::close(conn, id, closed_by).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("{} is already closed", year.academic_year));
+    }
+    Ok(())
+}
+
+/// The name on record of the active staff member called `name`, ignoring case.
+fn staff_name(conn: &Connection, name: &str) -> Result<String, String> {
+    staff_repo::find_all(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| is_active(s.status.as_deref()) && s.name.trim().eq_ignore_ascii_case(name))
+        .map(|s| s.name.trim().to_string())
+        .ok_or_else(|| format!("'{}' is not an active member of staff", name))
+}
+
+pub fn grant_override(conn: &Connection, mut year_override: YearOverride) -> Result<i64, String> {
+    let year = find_year(conn, year_override.academic_year_id)?;
+    if year.closed_at.is_none() {
+        return Err(format!("{} is not closed", year.academic_year));
+    }
+    year_override.granted_by = year_override.granted_by.trim().to_string();
+    year_override.reason = year_override.reason.trim().to_string();
+    if year_override.granted_by.is_empty() || year_override.reason.is_empty() {
+        return Err("An override needs the admin granting it and a reason".to_string());
+    }
+    year_override.granted_by = staff_name(conn, &year_override.granted_by)?;
+    let today = Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?;
+    let valid_until = match year_override.valid_until.as_deref().map(str::trim) {
+        None | Some("") => today,
+        Some(date) => Date::parse(date)?,
+    };
+    if valid_until < today {
+        return Err("An override cannot end in the past".to_string());
+    }
+    year_override.valid_until = Some(valid_until.to_string());
+    repo::insert_override(conn, &year_override).map_err(|e| e.to_string())
+}
+
+pub fn end_override(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::end_override(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err("Override not found or already ended".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_overrides(conn: &Connection, academic_year_id: i64) -> Result<Vec<YearOverride>, String> {
+    repo::find_overrides(conn, academic_year_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_class, memory_db, sample_staff, DEFAULT_ACADEMIC_YEAR_ID};
+
+    #[test]
+    fn migrations_seed_an_active_default_year() {
+        let conn = memory_db();
+        let current = get_current_academic_year(&conn).unwrap().unwrap();
+        assert_eq!(current.id, Some(DEFAULT_ACADEMIC_YEAR_ID));
+        assert_eq!(current.academic_year, "2024 - 2025");
+    }
+
+    #[test]
+    fn upsert_reuses_existing_label() {
+        let conn = memory_db();
+        let first = upsert_academic_year(&conn, "2025 - 2026", false, None, None).unwrap();
+        let second = upsert_academic_year(&conn, " 2025 - 2026 ", false, None, None).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(get_all_academic_years(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn upsert_rejects_blank_label() {
+        let conn = memory_db();
+        assert!(upsert_academic_year(&conn, "  ", false, None, None).is_err());
+    }
+
+    #[test]
+    fn setting_current_leaves_a_single_active_year() {
+        let conn = memory_db();
+        let id = upsert_academic_year(&conn, "2025 - 2026", true, None, None).unwrap();
+        let active: Vec<_> = get_all_academic_years(&conn)
+            .unwrap()
+            .into_iter()
+            .filter(|y| y.status.as_deref() == Some("active"))
+            .collect();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, Some(id));
+
+        set_current_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID).unwrap();
+        let current = get_current_academic_year(&conn).unwrap().unwrap();
+        assert_eq!(current.id, Some(DEFAULT_ACADEMIC_YEAR_ID));
+    }
+
+    #[test]
+    fn set_current_rejects_unknown_year() {
+        let conn = memory_db();
+        assert!(set_current_academic_year(&conn, 999).is_err());
+        // The existing active year must survive the failed call.
+        assert!(get_current_academic_year(&conn).unwrap().is_some());
+    }
+
+    #[test]
+    fn dated_years_may_not_overlap() {
+        let conn = memory_db();
+        let dates = |start: &str, end: &str| (Some(start.to_string()), Some(end.to_string()));
+        let (start, end) = dates("2025-06-01", "2026-04-30");
+        let id = upsert_academic_year(&conn, "2025 - 2026", false, start, end).unwrap();
+
+        let (start, end) = dates("2026-04-01", "2027-03-31");
+        let err = upsert_academic_year(&conn, "2026 - 2027", false, start, end).unwrap_err();
+        assert!(err.contains("2025 - 2026"));
+        let (start, end) = dates("2026-06-01", "2026-05-01");
+        assert!(upsert_academic_year(&conn, "2026 - 2027", false, start, end).is_err());
+        assert!(upsert_academic_year(&conn, "2026 - 2027", false, Some("2026-06-01".to_string()), None).is_err());
+
+        // A year may move its own dates.
+        let (start, end) = dates("2025-05-15", "2026-04-30");
+        assert_eq!(upsert_academic_year(&conn, "2025 - 2026", false, start, end).unwrap(), id);
+        let year = repo::find_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!(year.start_date.as_deref(), Some("2025-05-15"));
+    }
+
+    #[test]
+    fn delete_removes_year() {
+        let conn = memory_db();
+        let id = upsert_academic_year(&conn, "2030 - 2031", false, None, None).unwrap();
+        delete_academic_year(&conn, id).unwrap();
+        assert_eq!(get_all_academic_years(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn delete_is_blocked_while_classes_refer_to_the_year() {
+        let conn = memory_db();
+        create_class(&conn, "Class-1 A");
+        let err = delete_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID).unwrap_err();
+        assert!(err.contains("classes (1)"));
+        assert_eq!(get_all_academic_years(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn closed_years_are_read_only_without_an_override() {
+        use crate::service::class::{create_class as add_class, toggle_class_status};
+
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A");
+        assert!(close_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID, "Principal").is_err());
+        upsert_academic_year(&conn, "2025 - 2026", true, None, None).unwrap();
+        close_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID, "Principal").unwrap();
+        assert!(close_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID, "Principal").is_err());
+
+        let err = toggle_class_status(&conn, class_id).unwrap_err();
+        assert!(err.contains("is closed"));
+        assert!(add_class(&conn, "Class-1 B", DEFAULT_ACADEMIC_YEAR_ID, None).is_err());
+        let err = set_current_academic_year(&conn, DEFAULT_ACADEMIC_YEAR_ID).unwrap_err();
+        assert!(err.contains("cannot be made the current year"), "{}", err);
+        assert!(upsert_academic_year(&conn, "2024 - 2025", true, None, None).is_err());
+
+        crate::service::staff::create_staff(&conn, sample_staff("Meera Iyer")).unwrap();
+        let override_for = |valid_until: Option<&str>| YearOverride {
+            id: None,
+            academic_year_id: DEFAULT_ACADEMIC_YEAR_ID,
+            granted_by: " meera iyer ".to_string(),
+            reason: "Correct a section name".to_string(),
+            valid_until: valid_until.map(String::from),
+            granted_at: None,
+            ended_at: None,
+        };
+        assert!(grant_override(&conn, override_for(Some("2000-01-01"))).is_err());
+        let err = grant_override(
+            &conn,
+            YearOverride {
+                granted_by: "Admin".to_string(),
+                ..override_for(None)
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("not an active member of staff"), "{}", err);
+        let id = grant_override(&conn, override_for(None)).unwrap();
+        toggle_class_status(&conn, class_id).unwrap();
+
+        end_override(&conn, id).unwrap();
+        assert!(toggle_class_status(&conn, class_id).is_err());
+        let overrides = get_overrides(&conn, DEFAULT_ACADEMIC_YEAR_ID).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].granted_by, "Meera Iyer");
+    }
+}
@@ -0,0 +1,663 @@
+use crate::academic_year::AcademicYear;
+use crate::calendar::{CalendarEntry, CalendarSettings, Term, WorkingDays};
+use crate::date::Date;
+use crate::repository;
+use crate::repository::academic_year as academic_year_repo;
+use crate::repository::calendar as repo;
+use crate::repository::school as school_repo;
+use crate::service::academic_year::ensure_open;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub const ENTRY_KINDS: [&str; 2] = ["holiday", "event"];
+pub const DAY_PARTS: [&str; 2] = ["full", "half"];
+/// Days of the week in the order [`Date::weekday`] numbers them.
+pub const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(
+    value: Option<&str>,
+    options: &[&str],
+    default: &str,
+    what: &str,
+) -> Result<String, String> {
+    let value = value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or(default)
+        .to_lowercase();
+    if options.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            options.join(", ")
+        ))
+    }
+}
+
+fn parse_range(start: &str, end: &str) -> Result<(Date, Date), String> {
+    let (start, end) = (Date::parse(start)?, Date::parse(end)?);
+    if end < start {
+        return Err("The end date cannot be before the start date".to_string());
+    }
+    Ok((start, end))
+}
+
+/// The academic year with its dates; calendars can only be kept for dated years.
+fn dated_year(
+    conn: &Connection,
+    academic_year_id: i64,
+) -> Result<(AcademicYear, Date, Date), String> {
+    let year = academic_year_repo::find_by_id(conn, academic_year_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Academic year does not exist".to_string())?;
+    match (year.start_date.as_deref(), year.end_date.as_deref()) {
+        (Some(start), Some(end)) => {
+            let (start, end) = parse_range(start, end)?;
+            Ok((year, start, end))
+        }
+        _ => Err(format!(
+            "Set the start and end dates of {} first",
+            year.academic_year
+        )),
+    }
+}
+
+fn ensure_within(
+    year: &AcademicYear,
+    year_start: Date,
+    year_end: Date,
+    start: Date,
+    end: Date,
+) -> Result<(), String> {
+    if start < year_start || end > year_end {
+        return Err(format!(
+            "{} to {} falls outside {} ({} to {})",
+            start, end, year.academic_year, year_start, year_end
+        ));
+    }
+    Ok(())
+}
+
+fn term_year(conn: &Connection, id: i64) -> Result<i64, String> {
+    repo::find_term_year(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Term with id {} not found", id))
+}
+
+fn entry_year(conn: &Connection, id: i64) -> Result<i64, String> {
+    repo::find_entry_year(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Calendar entry with id {} not found", id))
+}
+
+pub fn get_settings(conn: &Connection) -> Result<CalendarSettings, String> {
+    repo::find_settings(conn).map_err(|e| e.to_string())
+}
+
+pub fn save_settings(conn: &Connection, settings: CalendarSettings) -> Result<(), String> {
+    let settings = CalendarSettings {
+        weekly_off: one_of(
+            Some(&settings.weekly_off),
+            &WEEKDAYS,
+            "sunday",
+            "weekly off",
+        )?,
+    };
+    repo::save_settings(conn, &settings).map_err(|e| e.to_string())
+}
+
+pub fn get_terms(conn: &Connection, academic_year_id: i64) -> Result<Vec<Term>, String> {
+    repo::find_terms(conn, academic_year_id).map_err(|e| e.to_string())
+}
+
+/// Terms lie within their academic year and do not overlap each other.
+pub fn save_term(conn: &Connection, mut term: Term) -> Result<i64, String> {
+    term.name = term.name.trim().to_string();
+    if term.name.is_empty() {
+        return Err("Term name is required".to_string());
+    }
+    let (year, year_start, year_end) = dated_year(conn, term.academic_year_id)?;
+    ensure_open(conn, term.academic_year_id)?;
+    if let Some(id) = term.id {
+        ensure_open(conn, term_year(conn, id)?)?;
+    }
+    let (start, end) = parse_range(&term.start_date, &term.end_date)?;
+    ensure_within(&year, year_start, year_end, start, end)?;
+    term.start_date = start.to_string();
+    term.end_date = end.to_string();
+
+    if repo::term_name_taken(conn, term.academic_year_id, &term.name, term.id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "{} already has a term named '{}'",
+            year.academic_year, term.name
+        ));
+    }
+    if let Some(other) = repo::find_overlapping_term(
+        conn,
+        term.academic_year_id,
+        &term.start_date,
+        &term.end_date,
+        term.id,
+    )
+    .map_err(|e| e.to_string())?
+    {
+        return Err(format!("'{}' overlaps {}", term.name, other));
+    }
+
+    match term.id {
+        Some(id) => {
+            if repo::update_term(conn, id, &term).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Term with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_term(conn, &term).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_term(conn: &Connection, id: i64) -> Result<(), String> {
+    ensure_open(conn, term_year(conn, id)?)?;
+    if repo::delete_term(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Term with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Entries of the year, limited to those touching `from..=to` when given.
+pub fn get_entries(
+    conn: &Connection,
+    academic_year_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<CalendarEntry>, String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    repo::find_entries(conn, academic_year_id, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Holidays and events lie within their academic year; a half-day entry covers one date.
+pub fn save_entry(conn: &Connection, mut entry: CalendarEntry) -> Result<i64, String> {
+    entry.title = entry.title.trim().to_string();
+    if entry.title.is_empty() {
+        return Err("Title is required".to_string());
+    }
+    entry.kind = one_of(
+        Some(&entry.kind),
+        &ENTRY_KINDS,
+        "holiday",
+        "calendar entry kind",
+    )?;
+    let day_part = one_of(entry.day_part.as_deref(), &DAY_PARTS, "full", "day part")?;
+
+    let (year, year_start, year_end) = dated_year(conn, entry.academic_year_id)?;
+    ensure_open(conn, entry.academic_year_id)?;
+    if let Some(id) = entry.id {
+        ensure_open(conn, entry_year(conn, id)?)?;
+    }
+    let end_date = non_blank(entry.end_date.take()).unwrap_or_else(|| entry.start_date.clone());
+    let (start, end) = parse_range(&entry.start_date, &end_date)?;
+    ensure_within(&year, year_start, year_end, start, end)?;
+    if day_part == "half" && start != end {
+        return Err("A half-day entry must start and end on the same date".to_string());
+    }
+    entry.start_date = start.to_string();
+    entry.end_date = Some(end.to_string());
+    entry.day_part = Some(day_part);
+    entry.description = non_blank(entry.description);
+
+    match entry.id {
+        Some(id) => {
+            if repo::update_entry(conn, id, &entry).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Calendar entry with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_entry(conn, &entry).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_entry(conn: &Connection, id: i64) -> Result<(), String> {
+    ensure_open(conn, entry_year(conn, id)?)?;
+    if repo::delete_entry(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Calendar entry with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Fails when changing the dates of a year would leave its terms or calendar outside it.
+pub fn ensure_fits_year(
+    conn: &Connection,
+    academic_year_id: i64,
+    start: Date,
+    end: Date,
+) -> Result<(), String> {
+    let outside = repo::count_outside(conn, academic_year_id, &start.to_string(), &end.to_string())
+        .map_err(|e| e.to_string())?;
+    if outside > 0 {
+        return Err(format!(
+            "{} term(s) or calendar entries fall outside {} to {}",
+            outside, start, end
+        ));
+    }
+    Ok(())
+}
+
+/// Holiday dates between `from` and `to`, mapped to whether only half the day is off.
+/// A full-day holiday wins over a half-day one on the same date.
+pub fn holidays_between(
+    conn: &Connection,
+    from: Date,
+    to: Date,
+) -> Result<HashMap<Date, bool>, String> {
+    let mut holidays = HashMap::new();
+    let entries = repo::find_holidays_between(conn, &from.to_string(), &to.to_string())
+        .map_err(|e| e.to_string())?;
+    for entry in entries {
+        let half = entry.day_part.as_deref() == Some("half");
+        let start = Date::parse(&entry.start_date)?.max(from);
+        let end = Date::parse(entry.end_date.as_deref().unwrap_or(&entry.start_date))?.min(to);
+        if end < start {
+            continue;
+        }
+        for offset in 0..=start.days_until(end) {
+            holidays
+                .entry(start.add_days(offset))
+                .and_modify(|h: &mut bool| *h = *h && half)
+                .or_insert(half);
+        }
+    }
+    Ok(holidays)
+}
+
+/// The weekly off and the holidays of a stretch of dates: what keeps a day from being a
+/// working day.
+pub struct DaysOff {
+    /// 0 for Monday through 6 for Sunday, as [`Date::weekday`] counts.
+    pub weekly_off: u32,
+    pub holidays: HashMap<Date, bool>,
+}
+
+impl DaysOff {
+    pub fn is_weekly_off(&self, date: Date) -> bool {
+        date.weekday() == self.weekly_off
+    }
+
+    /// How much of `date` is worked: none of the weekly off or a full-day holiday, half of a
+    /// half-day holiday, otherwise all of it.
+    pub fn working_share(&self, date: Date) -> f64 {
+        if self.is_weekly_off(date) {
+            return 0.0;
+        }
+        match self.holidays.get(&date) {
+            Some(false) => 0.0,
+            Some(true) => 0.5,
+            None => 1.0,
+        }
+    }
+}
+
+/// The school's weekly off and the holidays between `from` and `to`.
+pub fn days_off(conn: &Connection, from: Date, to: Date) -> Result<DaysOff, String> {
+    let weekly_off = get_settings(conn)?.weekly_off;
+    let weekly_off = WEEKDAYS
+        .iter()
+        .position(|day| *day == weekly_off)
+        .ok_or_else(|| format!("Invalid weekly off '{}'", weekly_off))?;
+    Ok(DaysOff {
+        weekly_off: weekly_off as u32,
+        holidays: holidays_between(conn, from, to)?,
+    })
+}
+
+/// Working days of the year, or of `from..=to` within it.
+pub fn get_working_days(
+    conn: &Connection,
+    academic_year_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<WorkingDays, String> {
+    let (year, year_start, year_end) = dated_year(conn, academic_year_id)?;
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?,
+        None => year_start,
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?,
+        None => year_end,
+    };
+    let (from, to) = parse_range(&from.to_string(), &to.to_string())?;
+    ensure_within(&year, year_start, year_end, from, to)?;
+
+    let days_off = days_off(conn, from, to)?;
+    let mut days = WorkingDays {
+        academic_year_id,
+        from: from.to_string(),
+        to: to.to_string(),
+        calendar_days: 0,
+        weekly_offs: 0,
+        holidays: 0,
+        half_days: 0,
+        working_days: 0.0,
+    };
+    for offset in 0..=from.days_until(to) {
+        let date = from.add_days(offset);
+        days.calendar_days += 1;
+        if days_off.is_weekly_off(date) {
+            days.weekly_offs += 1;
+        } else {
+            match days_off.holidays.get(&date) {
+                Some(false) => days.holidays += 1,
+                Some(true) => {
+                    days.half_days += 1;
+                    days.working_days += 0.5;
+                }
+                None => days.working_days += 1.0,
+            }
+        }
+    }
+    Ok(days)
+}
+
+/// Escapes text for an iCalendar property value.
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded at 75 octets as RFC 5545 requires.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn ics_date(date: Date) -> String {
+    date.to_string().replace('-', "")
+}
+
+/// All-day event; the end date in iCalendar is the day after the last one.
+#[allow(clippy::too_many_arguments)]
+fn push_event(
+    ics: &mut String,
+    uid: &str,
+    stamp: &str,
+    summary: &str,
+    start: Date,
+    end: Date,
+    category: &str,
+    description: Option<&str>,
+) {
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}@campussync", uid));
+    push_line(ics, &format!("DTSTAMP:{}", stamp));
+    push_line(ics, &format!("DTSTART;VALUE=DATE:{}", ics_date(start)));
+    push_line(
+        ics,
+        &format!("DTEND;VALUE=DATE:{}", ics_date(end.add_days(1))),
+    );
+    push_line(ics, &format!("SUMMARY:{}", ics_text(summary)));
+    push_line(ics, &format!("CATEGORIES:{}", category));
+    if let Some(description) = description {
+        push_line(ics, &format!("DESCRIPTION:{}", ics_text(description)));
+    }
+    push_line(ics, "TRANSP:TRANSPARENT");
+    push_line(ics, "END:VEVENT");
+}
+
+/// The year's terms, holidays and events as an iCalendar file, with its file name.
+pub fn render_ics(conn: &Connection, academic_year_id: i64) -> Result<(String, Vec<u8>), String> {
+    let (year, year_start, year_end) = dated_year(conn, academic_year_id)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let today = Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?;
+    let stamp = format!("{}T000000Z", ics_date(today));
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//CampusSync//Academic Calendar//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    let calendar_name = match school {
+        Some(school) => format!("{} {}", school.school_name, year.academic_year),
+        None => year.academic_year.clone(),
+    };
+    push_line(
+        &mut ics,
+        &format!("X-WR-CALNAME:{}", ics_text(&calendar_name)),
+    );
+
+    push_event(
+        &mut ics,
+        &format!("academic-year-{}", academic_year_id),
+        &stamp,
+        &format!("Academic year {}", year.academic_year),
+        year_start,
+        year_end,
+        "ACADEMIC YEAR",
+        None,
+    );
+    for term in get_terms(conn, academic_year_id)? {
+        let (start, end) = parse_range(&term.start_date, &term.end_date)?;
+        push_event(
+            &mut ics,
+            &format!("term-{}", term.id.unwrap_or_default()),
+            &stamp,
+            &term.name,
+            start,
+            end,
+            "TERM",
+            None,
+        );
+    }
+    for entry in get_entries(conn, academic_year_id, None, None)? {
+        let end = entry.end_date.as_deref().unwrap_or(&entry.start_date);
+        let (start, end) = parse_range(&entry.start_date, end)?;
+        let summary = if entry.day_part.as_deref() == Some("half") {
+            format!("{} (half day)", entry.title)
+        } else {
+            entry.title.clone()
+        };
+        push_event(
+            &mut ics,
+            &format!("{}-{}", entry.kind, entry.id.unwrap_or_default()),
+            &stamp,
+            &summary,
+            start,
+            end,
+            &entry.kind.to_uppercase(),
+            entry.description.as_deref(),
+        );
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+
+    let file_name = format!(
+        "Academic-Calendar-{}.ics",
+        year.academic_year.replace(' ', "")
+    );
+    Ok((file_name, ics.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::academic_year::upsert_academic_year;
+    use crate::test_support::memory_db;
+
+    fn dated_year_id(conn: &Connection) -> i64 {
+        upsert_academic_year(
+            conn,
+            "2025 - 2026",
+            false,
+            Some("2025-06-01".to_string()),
+            Some("2026-04-30".to_string()),
+        )
+        .unwrap()
+    }
+
+    fn holiday(
+        academic_year_id: i64,
+        title: &str,
+        start: &str,
+        end: Option<&str>,
+        day_part: &str,
+    ) -> CalendarEntry {
+        CalendarEntry {
+            id: None,
+            academic_year_id,
+            kind: "holiday".to_string(),
+            title: title.to_string(),
+            start_date: start.to_string(),
+            end_date: end.map(String::from),
+            day_part: Some(day_part.to_string()),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn terms_stay_inside_the_year_without_overlapping() {
+        let conn = memory_db();
+        let year_id = dated_year_id(&conn);
+        let term = |name: &str, start: &str, end: &str| Term {
+            id: None,
+            academic_year_id: year_id,
+            name: name.to_string(),
+            start_date: start.to_string(),
+            end_date: end.to_string(),
+        };
+
+        save_term(&conn, term("Term 1", "2025-06-01", "2025-10-15")).unwrap();
+        let err = save_term(&conn, term("Term 2", "2025-10-15", "2026-04-30")).unwrap_err();
+        assert!(err.contains("overlaps Term 1"));
+        assert!(save_term(&conn, term("Term 2", "2025-10-20", "2026-05-15")).is_err());
+        save_term(&conn, term("Term 2", "2025-10-20", "2026-04-30")).unwrap();
+        assert_eq!(get_terms(&conn, year_id).unwrap().len(), 2);
+
+        // Years without dates cannot hold terms, and the year cannot shrink past them.
+        assert!(save_term(
+            &conn,
+            Term {
+                academic_year_id: 1,
+                ..term("Term 1", "2024-06-01", "2024-10-01")
+            }
+        )
+        .is_err());
+        assert!(upsert_academic_year(
+            &conn,
+            "2025 - 2026",
+            false,
+            Some("2025-07-01".to_string()),
+            Some("2026-04-30".to_string()),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn working_days_skip_sundays_and_holidays() {
+        let conn = memory_db();
+        let year_id = dated_year_id(&conn);
+        // 2025-08-11 is a Monday; the week to Sunday 17th has six weekdays.
+        save_entry(
+            &conn,
+            holiday(year_id, "Independence Day", "2025-08-15", None, "full"),
+        )
+        .unwrap();
+        save_entry(
+            &conn,
+            holiday(year_id, "Sports day", "2025-08-13", None, "half"),
+        )
+        .unwrap();
+        let mut event = holiday(year_id, "PTM", "2025-08-12", None, "full");
+        event.kind = "event".to_string();
+        save_entry(&conn, event).unwrap();
+        assert!(save_entry(
+            &conn,
+            holiday(year_id, "Break", "2025-08-13", Some("2025-08-14"), "half")
+        )
+        .is_err());
+
+        let days = get_working_days(
+            &conn,
+            year_id,
+            Some("2025-08-11".to_string()),
+            Some("2025-08-17".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                days.calendar_days,
+                days.weekly_offs,
+                days.holidays,
+                days.half_days
+            ),
+            (7, 1, 1, 1)
+        );
+        assert_eq!(days.working_days, 4.5);
+        assert_eq!(
+            get_entries(&conn, year_id, Some("2025-08-14".to_string()), None)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ics_export_lists_terms_and_holidays_as_all_day_events() {
+        let conn = memory_db();
+        let year_id = dated_year_id(&conn);
+        save_entry(
+            &conn,
+            holiday(
+                year_id,
+                "Diwali, Bhai Dooj",
+                "2025-10-20",
+                Some("2025-10-23"),
+                "full",
+            ),
+        )
+        .unwrap();
+
+        let (file_name, ics) = render_ics(&conn, year_id).unwrap();
+        let ics = String::from_utf8(ics).unwrap();
+        assert_eq!(file_name, "Academic-Calendar-2025-2026.ics");
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("SUMMARY:Diwali\\, Bhai Dooj\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20251020\r\nDTEND;VALUE=DATE:20251024\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+        assert!(render_ics(&conn, 1).is_err());
+    }
+}
@@ -0,0 +1,677 @@
+use crate::certificate::{CertificateTemplate, IssueCertificateRequest, IssuedCertificate};
+use crate::date::Date;
+use crate::pdf::{Align, Document, Font};
+use crate::repository;
+use crate::repository::academic_year as year_repo;
+use crate::repository::certificate as repo;
+use crate::repository::school as school_repo;
+use crate::repository::students as student_repo;
+use crate::school::School;
+use crate::service::discipline::certificate_incidents;
+use crate::service::lifecycle::record_leaving;
+use crate::service::students::ensure_on_roll;
+use rusqlite::Connection;
+
+/// Certificate types and the prefix used in their numbers, e.g. `TC/2024-2025/0001`.
+const CERTIFICATE_PREFIXES: [(&str, &str); 4] = [
+    ("transfer", "TC"),
+    ("leaving", "LC"),
+    ("bonafide", "BC"),
+    ("character", "CC"),
+];
+
+fn prefix_for(certificate_type: &str) -> Result<&'static str, String> {
+    CERTIFICATE_PREFIXES
+        .iter()
+        .find(|(kind, _)| *kind == certificate_type)
+        .map(|(_, prefix)| *prefix)
+        .ok_or_else(|| format!("Unknown certificate type '{}'", certificate_type))
+}
+
+/// Transfer and leaving certificates take the student off the roll.
+fn is_leaving_certificate(certificate_type: &str) -> bool {
+    matches!(certificate_type, "transfer" | "leaving")
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn file_name_for(certificate_number: &str, copy_number: i64) -> String {
+    let base = certificate_number.replace('/', "-");
+    if copy_number > 0 {
+        format!("{}-D{}.pdf", base, copy_number)
+    } else {
+        format!("{}.pdf", base)
+    }
+}
+
+/// Shows `YYYY-MM-DD` dates the way they are written on certificates (`DD-MM-YYYY`).
+fn display_date(date: &str) -> String {
+    let parts: Vec<&str> = date.split('-').collect();
+    match parts.as_slice() {
+        [year, month, day] if year.len() == 4 => format!("{}-{}-{}", day, month, year),
+        _ => date.to_string(),
+    }
+}
+
+/// `value` as a normalised date, or today when it is blank.
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(date) => Ok(Date::parse(&date)?.to_string()),
+        None => repository::today(conn).map_err(|e| e.to_string()),
+    }
+}
+
+fn child_of(gender: &str) -> &'static str {
+    match gender.trim().to_lowercase().chars().next() {
+        Some('m') => "son of",
+        Some('f') => "daughter of",
+        _ => "child of",
+    }
+}
+
+/// Replaces `{name}` placeholders with their values. Unknown placeholders are left as written.
+pub fn fill_placeholders(text: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let key = &after[..end];
+                match values.iter().find(|(name, _)| *name == key) {
+                    Some((_, value)) => out.push_str(value),
+                    None => {
+                        out.push('{');
+                        out.push_str(key);
+                        out.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn get_certificate_templates(conn: &Connection) -> Result<Vec<CertificateTemplate>, String> {
+    repo::find_templates(conn).map_err(|e| e.to_string())
+}
+
+pub fn update_certificate_template(
+    conn: &Connection,
+    template: CertificateTemplate,
+) -> Result<(), String> {
+    prefix_for(&template.certificate_type)?;
+    if template.title.trim().is_empty() || template.body.trim().is_empty() {
+        return Err("Certificate title and body are required".to_string());
+    }
+    repo::update_template(conn, &template).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Issues an original certificate with the next serial number of the current academic year.
+/// A transfer or leaving certificate also marks the student as left.
+pub fn issue_certificate(
+    conn: &Connection,
+    request: IssueCertificateRequest,
+) -> Result<IssuedCertificate, String> {
+    let certificate_type = request.certificate_type.trim().to_lowercase();
+    let prefix = prefix_for(&certificate_type)?;
+    // Checked before anything is written, so a mistyped date never takes a serial number.
+    let issue_date = date_or_today(conn, request.issue_date)?;
+    let requested_leaving_date = non_blank(request.leaving_date)
+        .map(|date| Date::parse(&date).map(|d| d.to_string()))
+        .transpose()?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let student = student_repo::find_by_id(&tx, request.student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", request.student_id))?;
+
+    let (leaving_date, leaving_reason) = if is_leaving_certificate(&certificate_type) {
+        let already_issued =
+            repo::has_leaving_certificate(&tx, request.student_id).map_err(|e| e.to_string())?;
+        if already_issued {
+            return Err(
+                "A transfer or leaving certificate was already issued to this student; issue a duplicate instead"
+                    .to_string(),
+            );
+        }
+        let reason = non_blank(request.leaving_reason)
+            .ok_or_else(|| "Reason for leaving is required".to_string())?;
+        let date = requested_leaving_date.unwrap_or_else(|| issue_date.clone());
+        record_leaving(&tx, request.student_id, &date, &reason)?;
+        (Some(date), Some(reason))
+    } else {
+        if certificate_type == "bonafide" {
+            ensure_on_roll(&tx, request.student_id)?;
+        }
+        let (stored_date, stored_reason) =
+            student_repo::find_leaving_details(&tx, request.student_id)
+                .map_err(|e| e.to_string())?
+                .unwrap_or_default();
+        (requested_leaving_date.or(stored_date), stored_reason)
+    };
+
+    let year = year_repo::find_current(&tx)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Set a current academic year before issuing certificates".to_string())?;
+    let academic_year_id = year.id.unwrap_or_default();
+    let serial_no =
+        repo::next_serial(&tx, &certificate_type, academic_year_id).map_err(|e| e.to_string())?;
+    let certificate_number = format!(
+        "{}/{}/{:04}",
+        prefix,
+        year.academic_year.replace(' ', ""),
+        serial_no
+    );
+
+    let template = repo::find_template(&tx, &certificate_type)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No template for {} certificates", certificate_type))?;
+    let school = school_repo::find_first(&tx).map_err(|e| e.to_string())?;
+
+    let core = &student.core;
+    let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let optional_date = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(display_date)
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let incidents = certificate_incidents(&tx, request.student_id)?
+        .iter()
+        .map(|incident| {
+            let mut line = format!(
+                "{}: {}",
+                display_date(&incident.incident_date),
+                incident.description
+            );
+            if let Some(action) = &incident.action_taken {
+                line.push_str(&format!(" Action taken: {}", action));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let values = [
+        (
+            "school_name",
+            school
+                .as_ref()
+                .map(|s| s.school_name.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "school_board",
+            school
+                .as_ref()
+                .map(|s| s.school_board.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "principal_name",
+            school
+                .as_ref()
+                .map(|s| s.principal_name.clone())
+                .unwrap_or_default(),
+        ),
+        ("student_name", core.full_name.clone()),
+        ("gr_number", core.gr_number.clone()),
+        ("father_name", core.father_name.clone()),
+        ("mother_name", core.mother_name.clone()),
+        ("child_of", child_of(&core.gender).to_string()),
+        ("gender", core.gender.clone()),
+        ("nationality", optional(&core.nationality)),
+        ("dob", optional_date(&core.dob)),
+        (
+            "admission_date",
+            optional_date(&student.health.admission_date),
+        ),
+        ("class_name", optional(&core.class_name)),
+        ("section", optional(&core.section)),
+        ("academic_year", year.academic_year.clone()),
+        ("leaving_date", optional_date(&leaving_date)),
+        ("leaving_reason", optional(&leaving_reason)),
+        (
+            "conduct",
+            non_blank(request.conduct).unwrap_or_else(|| "Good".to_string()),
+        ),
+        (
+            "purpose",
+            non_blank(request.purpose).unwrap_or_else(|| "general use".to_string()),
+        ),
+        ("remarks", optional(&request.remarks)),
+        (
+            "incidents",
+            if incidents.is_empty() {
+                "None".to_string()
+            } else {
+                incidents.clone()
+            },
+        ),
+        ("issue_date", display_date(&issue_date)),
+        ("certificate_number", certificate_number.clone()),
+    ];
+
+    let mut body = fill_placeholders(&template.body, &values);
+    // Incidents the principal marked for the certificate are never left off a character
+    // certificate, even when its wording has no {incidents} placeholder.
+    if certificate_type == "character"
+        && !incidents.is_empty()
+        && !template.body.contains("{incidents}")
+    {
+        body.push_str("\n\nIncidents on record:\n");
+        body.push_str(&incidents);
+    }
+
+    let certificate = IssuedCertificate {
+        id: 0,
+        certificate_type,
+        file_name: file_name_for(&certificate_number, 0),
+        certificate_number,
+        academic_year_id,
+        academic_year: Some(year.academic_year.clone()),
+        serial_no,
+        copy_number: 0,
+        duplicate_of: None,
+        student_id: Some(request.student_id),
+        student_name: core.full_name.clone(),
+        gr_number: core.gr_number.clone(),
+        issue_date,
+        title: fill_placeholders(&template.title, &values),
+        body,
+        remarks: non_blank(request.remarks),
+        issued_by: non_blank(request.issued_by),
+        created_at: None,
+    };
+    let id = repo::insert(&tx, &certificate).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_issued_certificate(conn, id)
+}
+
+/// Issues a duplicate copy carrying the original's number and wording.
+pub fn issue_duplicate(
+    conn: &Connection,
+    id: i64,
+    remarks: Option<String>,
+    issued_by: Option<String>,
+) -> Result<IssuedCertificate, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let requested = get_issued_certificate(&tx, id)?;
+    let original_id = requested.duplicate_of.unwrap_or(requested.id);
+    let original = get_issued_certificate(&tx, original_id)?;
+    let copy_number = repo::next_copy_number(&tx, original_id).map_err(|e| e.to_string())?;
+    let issue_date = repository::today(&tx).map_err(|e| e.to_string())?;
+
+    let duplicate = IssuedCertificate {
+        id: 0,
+        copy_number,
+        duplicate_of: Some(original_id),
+        issue_date,
+        remarks: non_blank(remarks),
+        issued_by: non_blank(issued_by),
+        file_name: file_name_for(&original.certificate_number, copy_number),
+        created_at: None,
+        ..original
+    };
+    let duplicate_id = repo::insert(&tx, &duplicate).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_issued_certificate(conn, duplicate_id)
+}
+
+pub fn get_issued_certificate(conn: &Connection, id: i64) -> Result<IssuedCertificate, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Certificate with id {} not found", id))
+}
+
+pub fn get_issued_certificates(
+    conn: &Connection,
+    student_id: Option<i64>,
+    certificate_type: Option<&str>,
+) -> Result<Vec<IssuedCertificate>, String> {
+    repo::find_all(conn, student_id, certificate_type).map_err(|e| e.to_string())
+}
+
+pub fn render_certificate_pdf(
+    conn: &Connection,
+    certificate: &IssuedCertificate,
+) -> Result<Vec<u8>, String> {
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    Ok(certificate_pdf(certificate, school.as_ref()))
+}
+
+fn certificate_pdf(certificate: &IssuedCertificate, school: Option<&School>) -> Vec<u8> {
+    let mut doc = Document::new(&format!(
+        "{} {}",
+        certificate.title, certificate.certificate_number
+    ));
+    let page = doc.add_page();
+    let (width, height) = (page.width(), page.height());
+    let center = width / 2.0;
+    let margin = 60.0;
+
+    page.rect(30.0, 30.0, width - 60.0, height - 60.0, 1.5);
+    page.rect(34.0, 34.0, width - 68.0, height - 68.0, 0.5);
+
+    let mut y = height - 85.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            20.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 18.0;
+        let address = format!(
+            "{}, {}, {} - {}",
+            school.address, school.city, school.state, school.pincode
+        );
+        page.text_aligned(center, y, 10.0, Font::Regular, Align::Center, &address);
+        y -= 14.0;
+        let contact = format!(
+            "{} | Phone: {} | Email: {}",
+            school.school_board, school.contact_number, school.school_email
+        );
+        page.text_aligned(center, y, 9.0, Font::Regular, Align::Center, &contact);
+        y -= 14.0;
+    }
+    page.line(margin, y, width - margin, y, 1.0);
+
+    y -= 40.0;
+    page.text_aligned(
+        center,
+        y,
+        16.0,
+        Font::Bold,
+        Align::Center,
+        &certificate.title,
+    );
+    let title_width = crate::pdf::text_width(&certificate.title, 16.0, Font::Bold);
+    page.line(
+        center - title_width / 2.0,
+        y - 4.0,
+        center + title_width / 2.0,
+        y - 4.0,
+        0.8,
+    );
+    if certificate.copy_number > 0 {
+        y -= 20.0;
+        let label = format!("DUPLICATE COPY ({})", certificate.copy_number);
+        page.text_aligned(center, y, 11.0, Font::Bold, Align::Center, &label);
+    }
+
+    y -= 34.0;
+    let number = format!("Certificate No.: {}", certificate.certificate_number);
+    page.text(margin, y, 10.5, Font::Regular, &number);
+    let date = format!("Date: {}", display_date(&certificate.issue_date));
+    page.text_aligned(width - margin, y, 10.5, Font::Regular, Align::Right, &date);
+
+    y -= 36.0;
+    page.paragraph(
+        margin,
+        y,
+        width - 2.0 * margin,
+        11.5,
+        Font::Regular,
+        19.0,
+        &certificate.body,
+    );
+
+    let signature_y = 130.0;
+    page.line(margin, signature_y, margin + 140.0, signature_y, 0.5);
+    page.text_aligned(
+        margin,
+        signature_y - 14.0,
+        10.0,
+        Font::Regular,
+        Align::Left,
+        "Class Teacher",
+    );
+    page.line(
+        width - margin - 140.0,
+        signature_y,
+        width - margin,
+        signature_y,
+        0.5,
+    );
+    page.text_aligned(
+        width - margin,
+        signature_y - 14.0,
+        10.0,
+        Font::Regular,
+        Align::Right,
+        "Principal",
+    );
+    if let Some(school) = school {
+        page.text_aligned(
+            width - margin,
+            signature_y - 28.0,
+            9.0,
+            Font::Regular,
+            Align::Right,
+            &school.principal_name,
+        );
+    }
+    page.text_aligned(
+        center,
+        signature_y - 14.0,
+        10.0,
+        Font::Regular,
+        Align::Center,
+        "School Seal",
+    );
+
+    doc.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::StatusChange;
+    use crate::service::academic_year::upsert_academic_year;
+    use crate::service::lifecycle::change_status;
+    use crate::service::school::upsert_school_details;
+    use crate::service::students::save_core;
+    use crate::test_support::{create_class, memory_db, sample_school, sample_student};
+
+    fn request(student_id: i64, certificate_type: &str) -> IssueCertificateRequest {
+        IssueCertificateRequest {
+            student_id,
+            certificate_type: certificate_type.to_string(),
+            issue_date: Some("2025-03-31".to_string()),
+            leaving_date: None,
+            leaving_reason: None,
+            conduct: None,
+            purpose: None,
+            remarks: None,
+            issued_by: None,
+        }
+    }
+
+    fn admit(conn: &Connection, gr_number: &str) -> i64 {
+        let class_id = create_class(conn, &format!("Class-5 {}", gr_number)).to_string();
+        save_core(conn, sample_student(&class_id, gr_number).core).unwrap()
+    }
+
+    #[test]
+    fn placeholders_are_filled_and_unknown_ones_kept() {
+        let values = [("student_name", "Asha".to_string())];
+        assert_eq!(
+            fill_placeholders("{student_name} of {class_name} {", &values),
+            "Asha of {class_name} {"
+        );
+    }
+
+    #[test]
+    fn transfer_certificate_takes_student_off_the_roll() {
+        let conn = memory_db();
+        let student_id = admit(&conn, "GR-1");
+
+        assert!(issue_certificate(&conn, request(student_id, "transfer")).is_err());
+        let mut tc = request(student_id, "transfer");
+        tc.leaving_reason = Some("Family relocated".to_string());
+        let issued = issue_certificate(&conn, tc).unwrap();
+
+        assert_eq!(issued.certificate_number, "TC/2024-2025/0001");
+        assert_eq!(issued.file_name, "TC-2024-2025-0001.pdf");
+        assert!(issued.body.contains("Family relocated"));
+        assert!(issued.body.contains("31-03-2025"));
+
+        let err = ensure_on_roll(&conn, student_id).unwrap_err();
+        assert!(err.contains("not on the rolls"));
+        assert!(issue_certificate(&conn, request(student_id, "bonafide")).is_err());
+
+        let mut again = request(student_id, "leaving");
+        again.leaving_reason = Some("Again".to_string());
+        assert!(issue_certificate(&conn, again)
+            .unwrap_err()
+            .contains("duplicate"));
+
+        // Character certificates remain available and pick up the stored leaving date.
+        let character = issue_certificate(&conn, request(student_id, "character")).unwrap();
+        assert!(character.body.contains("to 31-03-2025"));
+    }
+
+    #[test]
+    fn transfer_certificate_for_a_student_already_marked_left() {
+        let conn = memory_db();
+        let student_id = admit(&conn, "GR-1");
+        change_status(
+            &conn,
+            student_id,
+            StatusChange {
+                status: "left".to_string(),
+                effective_date: Some("2025-03-15".to_string()),
+                reason: Some("Moved away".to_string()),
+                passing_year: None,
+            },
+        )
+        .unwrap();
+
+        let mut tc = request(student_id, "transfer");
+        tc.leaving_reason = Some("Family relocated".to_string());
+        let issued = issue_certificate(&conn, tc).unwrap();
+        assert_eq!(issued.certificate_number, "TC/2024-2025/0001");
+        assert!(issued.body.contains("Family relocated"));
+        assert_eq!(
+            student_repo::find_status(&conn, student_id)
+                .unwrap()
+                .flatten()
+                .as_deref(),
+            Some("left")
+        );
+    }
+
+    #[test]
+    fn serial_numbers_run_per_type_and_year() {
+        let conn = memory_db();
+        let first = admit(&conn, "GR-1");
+        let second = admit(&conn, "GR-2");
+
+        let mut misdated = request(first, "bonafide");
+        misdated.issue_date = Some("31/03/2025".to_string());
+        assert!(issue_certificate(&conn, misdated)
+            .unwrap_err()
+            .contains("Invalid date"));
+        let mut misdated = request(first, "character");
+        misdated.leaving_date = Some("2025-02-30".to_string());
+        assert!(issue_certificate(&conn, misdated).is_err());
+
+        let a = issue_certificate(&conn, request(first, "bonafide")).unwrap();
+        let b = issue_certificate(&conn, request(second, "bonafide")).unwrap();
+        let c = issue_certificate(&conn, request(first, "character")).unwrap();
+        assert_eq!((a.serial_no, b.serial_no, c.serial_no), (1, 2, 1));
+
+        upsert_academic_year(&conn, "2025 - 2026", true, None, None).unwrap();
+        let d = issue_certificate(&conn, request(first, "bonafide")).unwrap();
+        assert_eq!(d.certificate_number, "BC/2025-2026/0001");
+
+        let register = get_issued_certificates(&conn, Some(first), Some("bonafide")).unwrap();
+        assert_eq!(register.len(), 2);
+    }
+
+    #[test]
+    fn bonafide_uses_edited_template() {
+        let conn = memory_db();
+        upsert_school_details(&conn, &sample_school()).unwrap();
+        let student_id = admit(&conn, "GR-1");
+
+        update_certificate_template(
+            &conn,
+            CertificateTemplate {
+                certificate_type: "bonafide".to_string(),
+                title: "Bonafide Certificate".to_string(),
+                body: "{student_name}, {child_of} {father_name}, studies at {school_name} for {purpose}."
+                    .to_string(),
+                updated_at: None,
+            },
+        )
+        .unwrap();
+
+        let mut bonafide = request(student_id, "bonafide");
+        bonafide.purpose = Some("a passport application".to_string());
+        let issued = issue_certificate(&conn, bonafide).unwrap();
+        assert_eq!(
+            issued.body,
+            "Student GR-1, daughter of Rajesh, studies at Green Valley School for a passport application."
+        );
+
+        let bad = CertificateTemplate {
+            certificate_type: "migration".to_string(),
+            title: "Migration".to_string(),
+            body: "x".to_string(),
+            updated_at: None,
+        };
+        assert!(update_certificate_template(&conn, bad).is_err());
+    }
+
+    #[test]
+    fn duplicates_share_the_original_number() {
+        let conn = memory_db();
+        let student_id = admit(&conn, "GR-1");
+        let original = issue_certificate(&conn, request(student_id, "bonafide")).unwrap();
+
+        let first = issue_duplicate(&conn, original.id, Some("Lost".to_string()), None).unwrap();
+        let second = issue_duplicate(&conn, first.id, None, None).unwrap();
+
+        assert_eq!(first.certificate_number, original.certificate_number);
+        assert_eq!(first.body, original.body);
+        assert_eq!((first.copy_number, second.copy_number), (1, 2));
+        assert_eq!(second.duplicate_of, Some(original.id));
+        assert_eq!(second.file_name, "BC-2024-2025-0001-D2.pdf");
+        assert!(issue_duplicate(&conn, 999, None, None).is_err());
+    }
+
+    #[test]
+    fn pdf_carries_school_and_duplicate_marking() {
+        let conn = memory_db();
+        upsert_school_details(&conn, &sample_school()).unwrap();
+        let student_id = admit(&conn, "GR-1");
+        let original = issue_certificate(&conn, request(student_id, "character")).unwrap();
+        let duplicate = issue_duplicate(&conn, original.id, None, None).unwrap();
+
+        let pdf = render_certificate_pdf(&conn, &duplicate).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF"));
+        assert!(text.contains("(Green Valley School) Tj"));
+        assert!(text.contains("(DUPLICATE COPY \\(1\\)) Tj"));
+    }
+}
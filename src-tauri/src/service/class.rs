@@ -0,0 +1,725 @@
+use crate::class::{Class, ClassTemplate, Grade, GradeSection, WaitlistEntry};
+use crate::lifecycle::StatusChange;
+use crate::repository;
+use crate::repository::academic_year as academic_year_repo;
+use crate::repository::class as repo;
+use crate::service::academic_year::{ensure_class_open, ensure_open};
+use crate::service::lifecycle;
+use rusqlite::Connection;
+
+pub const WHEN_FULL_OPTIONS: [&str; 2] = ["reject", "waitlist"];
+
+/// Grade order of names without a recognisable grade, so they sort after the rest.
+const UNRANKED_GRADE: i64 = 100;
+
+const SECTIONS: [&str; 4] = ["A", "B", "C", "D"];
+const PRE_PRIMARY_GRADES: [&str; 3] = ["Nursery", "LKG", "UKG"];
+const SENIOR_STREAMS: [&str; 3] = ["Science", "Commerce", "Arts"];
+
+/// Template used when setup does not choose one.
+pub const DEFAULT_TEMPLATE_CODE: &str = "k-10";
+
+fn with_sections(grades: &[String]) -> Vec<String> {
+    grades
+        .iter()
+        .flat_map(|grade| SECTIONS.iter().map(move |section| format!("{} {}", grade, section)))
+        .collect()
+}
+
+/// Built-in templates with their codes, seeded into the database by `seed_class_templates`.
+fn builtin_templates() -> Vec<(&'static str, ClassTemplate)> {
+    let pre_primary = with_sections(&PRE_PRIMARY_GRADES.map(String::from));
+    let numbered: Vec<String> = (1..=10).map(|n| format!("Class-{}", n)).collect();
+    let k10 = [pre_primary.clone(), with_sections(&numbered)].concat();
+    let senior: Vec<String> = [11, 12]
+        .iter()
+        .flat_map(|n| SENIOR_STREAMS.iter().map(move |stream| format!("Class-{} {} A", n, stream)))
+        .collect();
+    let k12 = [k10.clone(), senior].concat();
+
+    let template = |name: &str, description: &str, class_names: Vec<String>| ClassTemplate {
+        id: None,
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        builtin: true,
+        class_names,
+    };
+    vec![
+        ("pre-primary", template("Pre-primary", "Nursery, LKG and UKG, sections A-D", pre_primary)),
+        ("k-10", template("K-10", "Nursery to Class-10, sections A-D", k10)),
+        (
+            "k-12",
+            template(
+                "K-12 with streams",
+                "Nursery to Class-10, then Class-11 and 12 in Science, Commerce and Arts",
+                k12,
+            ),
+        ),
+    ]
+}
+
+/// Adds any built-in template the database does not have yet. Runs with the migrations.
+pub fn seed_class_templates(conn: &Connection) -> Result<(), String> {
+    for (code, template) in builtin_templates() {
+        if repo::find_template_id_by_code(conn, code).map_err(|e| e.to_string())?.is_none() {
+            let id = repo::insert_template(conn, Some(code), &template.name, template.description.as_deref())
+                .map_err(|e| e.to_string())?;
+            repo::replace_template_classes(conn, id, &template.class_names).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn get_templates(conn: &Connection) -> Result<Vec<ClassTemplate>, String> {
+    repo::find_templates(conn).map_err(|e| e.to_string())
+}
+
+/// The chosen template, or the default one when none is given.
+fn find_template(conn: &Connection, template_id: Option<i64>) -> Result<ClassTemplate, String> {
+    let template_id = match template_id {
+        Some(id) => Some(id),
+        None => repo::find_template_id_by_code(conn, DEFAULT_TEMPLATE_CODE).map_err(|e| e.to_string())?,
+    };
+    template_id
+        .map(|id| repo::find_template(conn, id))
+        .transpose()
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .ok_or_else(|| "Class template not found".to_string())
+}
+
+fn ensure_custom_template(conn: &Connection, id: i64) -> Result<(), String> {
+    let template = find_template(conn, Some(id))?;
+    if template.builtin {
+        return Err(format!("'{}' is a built-in template and cannot be changed", template.name));
+    }
+    Ok(())
+}
+
+/// Creates a custom template, or updates it when `template.id` is set. Returns its id.
+pub fn save_template(conn: &Connection, template: ClassTemplate) -> Result<i64, String> {
+    let name = template.name.trim();
+    if name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    let mut class_names: Vec<String> = Vec::new();
+    for class_name in template.class_names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if class_names.iter().any(|n| n.eq_ignore_ascii_case(class_name)) {
+            return Err(format!("Class '{}' is listed twice", class_name));
+        }
+        class_names.push(class_name.to_string());
+    }
+    if class_names.is_empty() {
+        return Err("A template needs at least one class".to_string());
+    }
+    if let Some(id) = template.id {
+        ensure_custom_template(conn, id)?;
+    }
+    if repo::template_name_taken(conn, name, template.id).map_err(|e| e.to_string())? {
+        return Err(format!("A template named '{}' already exists", name));
+    }
+
+    let description = template
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = match template.id {
+        Some(id) => {
+            repo::update_template(&tx, id, name, description).map_err(|e| e.to_string())?;
+            id
+        }
+        None => repo::insert_template(&tx, None, name, description).map_err(|e| e.to_string())?,
+    };
+    repo::replace_template_classes(&tx, id, &class_names).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub fn delete_template(conn: &Connection, id: i64) -> Result<(), String> {
+    ensure_custom_template(conn, id)?;
+    repo::delete_template(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+const ROMAN_GRADES: [&str; 12] = [
+    "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII",
+];
+
+/// The grade number of a Roman numeral from I to XII.
+fn roman_grade(word: &str) -> Option<i64> {
+    let word = word.to_uppercase();
+    ROMAN_GRADES
+        .iter()
+        .position(|numeral| *numeral == word)
+        .map(|index| index as i64 + 1)
+}
+
+/// Splits a class name into grade and section: "Class-5 B" is grade "Class-5", section "B".
+/// A trailing Roman numeral is the grade when nothing before it is ("Class IV"); otherwise a
+/// trailing word of one or two letters is taken as the section ("Class XI A").
+pub fn split_class_name(class_name: &str) -> (String, Option<String>) {
+    let words: Vec<&str> = class_name.split_whitespace().collect();
+    let numbered = |words: &[&str]| {
+        words
+            .iter()
+            .any(|w| w.contains(|c: char| c.is_ascii_digit()) || roman_grade(w).is_some())
+    };
+    match words.split_last() {
+        Some((last, rest)) if roman_grade(last).is_some() && !numbered(rest) => {
+            (words.join(" "), None)
+        }
+        Some((last, rest))
+            if !rest.is_empty()
+                && last.len() <= 2
+                && last.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            (rest.join(" "), Some(last.to_uppercase()))
+        }
+        _ => (words.join(" "), None),
+    }
+}
+
+/// Position of a grade in the school: pre-primary grades come before Class-1, numbered
+/// grades follow their number, in digits or Roman numerals.
+pub fn grade_order(grade: &str) -> i64 {
+    let compact: String = grade
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    match compact.as_str() {
+        "playgroup" | "prenursery" => -3,
+        "nursery" => -2,
+        "lkg" | "jrkg" | "juniorkg" => -1,
+        "ukg" | "srkg" | "seniorkg" => 0,
+        _ => compact
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()
+            .or_else(|| {
+                grade
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .find_map(roman_grade)
+            })
+            .unwrap_or(UNRANKED_GRADE),
+    }
+}
+
+/// Stores the grade and section parsed from the class name and copies the section onto the
+/// students of the class.
+fn apply_layout(conn: &Connection, id: i64, class_name: &str) -> Result<(), String> {
+    let (grade, section) = split_class_name(class_name);
+    repo::set_layout(conn, id, &grade, section.as_deref(), grade_order(&grade))
+        .map_err(|e| e.to_string())?;
+    repo::sync_student_sections(conn, Some(id)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn insert_class(
+    conn: &Connection,
+    class_name: &str,
+    academic_year_id: i64,
+    status: &str,
+) -> Result<i64, String> {
+    let id = repo::insert(conn, class_name, academic_year_id, status).map_err(|e| e.to_string())?;
+    apply_layout(conn, id, class_name)?;
+    Ok(id)
+}
+
+/// Fills grade and section for classes saved before they were stored, and lines up the
+/// section of every student with their class.
+pub fn migrate_class_layout(conn: &Connection) -> Result<(), String> {
+    for (id, class_name) in repo::find_without_layout(conn).map_err(|e| e.to_string())? {
+        let (grade, section) = split_class_name(&class_name);
+        repo::set_layout(conn, id, &grade, section.as_deref(), grade_order(&grade))
+            .map_err(|e| e.to_string())?;
+    }
+    repo::sync_student_sections(conn, None).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Inserts the classes of the template (the default one when `template_id` is `None`) that
+/// are missing from `academic_year_id`, as inactive.
+pub fn initialize_default_classes(
+    conn: &Connection,
+    academic_year_id: i64,
+    template_id: Option<i64>,
+) -> Result<(), String> {
+    if !academic_year_repo::exists(conn, academic_year_id).map_err(|e| e.to_string())? {
+        return Err("Academic year does not exist".to_string());
+    }
+    ensure_open(conn, academic_year_id)?;
+    let template = find_template(conn, template_id)?;
+    for class_name in &template.class_names {
+        if !repo::name_taken(conn, class_name, academic_year_id, None).map_err(|e| e.to_string())? {
+            insert_class(conn, class_name, academic_year_id, "inactive")?;
+        }
+    }
+    Ok(())
+}
+
+/// First-run setup. Schools that already have classes are treated as set up.
+pub fn check_and_initialize_default_classes_once(
+    conn: &Connection,
+    academic_year_id: i64,
+    template_id: Option<i64>,
+) -> Result<(), String> {
+    if repo::defaults_initialized(conn).map_err(|e| e.to_string())? {
+        return Ok(());
+    }
+    if repo::count(conn).map_err(|e| e.to_string())? == 0 {
+        initialize_default_classes(conn, academic_year_id, template_id)?;
+    }
+    repo::mark_defaults_initialized(conn).map_err(|e| e.to_string())
+}
+
+pub fn toggle_class_status(conn: &Connection, id: i64) -> Result<(), String> {
+    ensure_class_open(conn, id)?;
+    let current_status = repo::find_status(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())?;
+
+    let new_status = if current_status == "active" {
+        "inactive"
+    } else {
+        "active"
+    };
+    repo::set_status(conn, id, new_status).map_err(|e| e.to_string())
+}
+
+pub fn get_active_classes(conn: &Connection) -> Result<Vec<Class>, String> {
+    repo::find_all(conn, true).map_err(|e| e.to_string())
+}
+
+pub fn create_class(
+    conn: &Connection,
+    class_name: &str,
+    academic_year_id: i64,
+    status: Option<String>,
+) -> Result<i64, String> {
+    ensure_open(conn, academic_year_id)?;
+    if repo::name_taken(conn, class_name, academic_year_id, None).map_err(|e| e.to_string())? {
+        return Err(format!("Class '{}' already exists in the selected academic year", class_name));
+    }
+
+    insert_class(
+        conn,
+        class_name.trim(),
+        academic_year_id,
+        &status.unwrap_or_else(|| "active".to_string()),
+    )
+}
+
+pub fn get_all_classes(conn: &Connection) -> Result<Vec<Class>, String> {
+    repo::find_all(conn, false).map_err(|e| e.to_string())
+}
+
+pub fn update_class(
+    conn: &Connection,
+    id: i64,
+    class_name: &str,
+    academic_year_id: i64,
+    status: &str,
+) -> Result<(), String> {
+    if !repo::exists(conn, id).map_err(|e| e.to_string())? {
+        return Err("Class not found".to_string());
+    }
+
+    if repo::name_taken(conn, class_name, academic_year_id, Some(id)).map_err(|e| e.to_string())? {
+        return Err(format!("Class '{}' already exists in the selected academic year", class_name));
+    }
+
+    if !academic_year_repo::exists(conn, academic_year_id).map_err(|e| e.to_string())? {
+        return Err("Academic year does not exist".to_string());
+    }
+    ensure_class_open(conn, id)?;
+    ensure_open(conn, academic_year_id)?;
+
+    repo::update(conn, id, class_name.trim(), academic_year_id, status)
+        .map_err(|e| e.to_string())?;
+    apply_layout(conn, id, class_name.trim())
+}
+
+pub fn get_class(conn: &Connection, id: i64) -> Result<Class, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())
+}
+
+/// Classes of the academic year (the current one by default) grouped by grade, in grade order.
+pub fn get_grades(conn: &Connection, academic_year_id: Option<i64>) -> Result<Vec<Grade>, String> {
+    let academic_year_id = match academic_year_id {
+        Some(id) => id,
+        None => academic_year_repo::find_current(conn)
+            .map_err(|e| e.to_string())?
+            .and_then(|year| year.id)
+            .ok_or_else(|| "No current academic year is set".to_string())?,
+    };
+
+    let mut grades: Vec<Grade> = Vec::new();
+    for class in repo::find_all(conn, false).map_err(|e| e.to_string())? {
+        let Some(class_id) = class.id.filter(|_| class.academic_years == academic_year_id) else {
+            continue;
+        };
+        let (grade, section) = match class.grade {
+            Some(grade) => (grade, class.section),
+            None => split_class_name(&class.class_name),
+        };
+        let grade_order = class.grade_order.unwrap_or_else(|| self::grade_order(&grade));
+        let section = GradeSection {
+            class_id,
+            class_name: class.class_name,
+            section,
+            capacity: class.capacity,
+            enrolled: class.enrolled.unwrap_or(0),
+        };
+        match grades.last_mut() {
+            Some(last) if last.grade == grade => last.sections.push(section),
+            _ => grades.push(Grade {
+                grade,
+                grade_order,
+                sections: vec![section],
+            }),
+        }
+    }
+    Ok(grades)
+}
+
+/// Sets the seats of a section. Lowering them below the students already enrolled only stops
+/// new admissions; nobody loses a seat.
+pub fn set_capacity(
+    conn: &Connection,
+    id: i64,
+    capacity: Option<u32>,
+    when_full: Option<String>,
+) -> Result<(), String> {
+    ensure_class_open(conn, id)?;
+    if capacity == Some(0) {
+        return Err("Capacity must be at least 1 seat; leave it empty for no limit".to_string());
+    }
+    let when_full = match when_full.as_deref().map(str::trim) {
+        None | Some("") => "reject".to_string(),
+        Some(option) => {
+            let option = option.to_lowercase();
+            if !WHEN_FULL_OPTIONS.contains(&option.as_str()) {
+                return Err(format!(
+                    "Invalid option '{}'. Expected one of: {}",
+                    option,
+                    WHEN_FULL_OPTIONS.join(", ")
+                ));
+            }
+            option
+        }
+    };
+
+    if repo::set_capacity(conn, id, capacity, &when_full).map_err(|e| e.to_string())? == 0 {
+        return Err("Class not found".to_string());
+    }
+    Ok(())
+}
+
+/// Checks the class for a free seat, returning it with the reason it is full, if it is.
+fn check_seat(conn: &Connection, class_id: i64) -> Result<(Class, Option<String>), String> {
+    let class = get_class(conn, class_id)?;
+    let full = match class.capacity {
+        Some(capacity) => {
+            let enrolled = repo::count_on_roll(conn, class_id).map_err(|e| e.to_string())?;
+            (enrolled >= i64::from(capacity)).then(|| {
+                format!(
+                    "{} is full ({} of {} seats taken)",
+                    class.class_name, enrolled, capacity
+                )
+            })
+        }
+        None => None,
+    };
+    Ok((class, full))
+}
+
+/// Fails when the class has no free seat.
+pub fn ensure_seat(conn: &Connection, class_id: i64) -> Result<(), String> {
+    match check_seat(conn, class_id)?.1 {
+        Some(full) => Err(full),
+        None => Ok(()),
+    }
+}
+
+/// Whether a new admission to the class has to wait for a seat. Fails when the class is
+/// full and rejects further admissions.
+pub fn admission_waitlisted(conn: &Connection, class_id: i64) -> Result<bool, String> {
+    match check_seat(conn, class_id)? {
+        (_, None) => Ok(false),
+        (class, Some(_)) if class.when_full.as_deref() == Some("waitlist") => Ok(true),
+        (_, Some(full)) => Err(full),
+    }
+}
+
+/// Puts a newly entered student on the waitlist of the class as an applicant.
+pub fn waitlist_student(conn: &Connection, class_id: i64, student_id: i64) -> Result<(), String> {
+    lifecycle::set_form_status(conn, student_id, Some("applicant".to_string()), None)?;
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    repo::add_to_waitlist(conn, class_id, student_id, &today).map_err(|e| e.to_string())
+}
+
+pub fn get_waitlist(conn: &Connection, class_id: i64) -> Result<Vec<WaitlistEntry>, String> {
+    let mut entries = repo::find_waitlist(conn, class_id).map_err(|e| e.to_string())?;
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.position = index as u32 + 1;
+    }
+    Ok(entries)
+}
+
+/// Admits a waitlisted applicant into the class they waited for, if a seat is free.
+pub fn admit_from_waitlist(conn: &Connection, student_id: i64) -> Result<(), String> {
+    let class_id = repo::find_waitlisted_class(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} is not on a waitlist", student_id))?;
+    ensure_class_open(conn, class_id)?;
+    // The lifecycle checks the seat and takes the student off the waitlist.
+    lifecycle::change_status(
+        conn,
+        student_id,
+        StatusChange {
+            status: "active".to_string(),
+            effective_date: None,
+            reason: None,
+            passing_year: None,
+        },
+    )
+}
+
+pub fn remove_from_waitlist(conn: &Connection, student_id: i64) -> Result<(), String> {
+    if repo::remove_from_waitlist(conn, student_id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Student with id {} is not on a waitlist", student_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{memory_db, DEFAULT_ACADEMIC_YEAR_ID};
+
+    #[test]
+    fn create_rejects_duplicate_name_in_same_year() {
+        let conn = memory_db();
+        create_class(&conn, "Class-1 A", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        let err = create_class(&conn, " class-1 a ", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap_err();
+        assert!(err.contains("already exists"));
+    }
+
+    #[test]
+    fn create_trims_name_and_defaults_to_active() {
+        let conn = memory_db();
+        let id = create_class(&conn, "  Class-2 B ", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        let class = get_class(&conn, id).unwrap();
+        assert_eq!(class.class_name, "Class-2 B");
+        assert_eq!(class.status.as_deref(), Some("active"));
+    }
+
+    #[test]
+    fn reads_never_seed_and_setup_uses_the_chosen_template() {
+        let conn = memory_db();
+        assert!(get_all_classes(&conn).unwrap().is_empty());
+
+        let k12 = get_templates(&conn)
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "K-12 with streams")
+            .unwrap();
+        check_and_initialize_default_classes_once(&conn, DEFAULT_ACADEMIC_YEAR_ID, k12.id).unwrap();
+        let classes = get_all_classes(&conn).unwrap();
+        assert_eq!(classes.len(), k12.class_names.len());
+        assert!(classes.iter().any(|c| c.class_name == "Class-11 Commerce A"));
+        assert!(classes
+            .iter()
+            .all(|c| c.academic_years == DEFAULT_ACADEMIC_YEAR_ID && c.academic_year_details.is_some()));
+        assert!(get_active_classes(&conn).unwrap().is_empty());
+
+        // Setup only runs once.
+        check_and_initialize_default_classes_once(&conn, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        assert_eq!(repo::count(&conn).unwrap(), k12.class_names.len() as i64);
+    }
+
+    #[test]
+    fn initialize_defaults_is_idempotent() {
+        let conn = memory_db();
+        initialize_default_classes(&conn, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        initialize_default_classes(&conn, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        assert_eq!(
+            repo::count(&conn).unwrap(),
+            find_template(&conn, None).unwrap().class_names.len() as i64
+        );
+    }
+
+    #[test]
+    fn custom_templates_are_validated_and_builtins_are_read_only() {
+        let conn = memory_db();
+        let template = |id: Option<i64>, names: &[&str]| ClassTemplate {
+            id,
+            name: "Junior College".to_string(),
+            description: None,
+            builtin: false,
+            class_names: names.iter().map(|n| n.to_string()).collect(),
+        };
+
+        let err = save_template(&conn, template(None, &["Class-11 Arts A", "class-11 arts a"])).unwrap_err();
+        assert!(err.contains("listed twice"));
+        let id = save_template(&conn, template(None, &["Class-11 Arts A", " ", "Class-12 Arts A"])).unwrap();
+        assert_eq!(find_template(&conn, Some(id)).unwrap().class_names.len(), 2);
+        assert!(save_template(&conn, template(None, &["Class-11 Science A"])).is_err());
+
+        let builtin = find_template(&conn, None).unwrap();
+        assert!(save_template(&conn, template(builtin.id, &["Class-1 A"])).is_err());
+        assert!(delete_template(&conn, builtin.id.unwrap()).is_err());
+
+        initialize_default_classes(&conn, DEFAULT_ACADEMIC_YEAR_ID, Some(id)).unwrap();
+        assert_eq!(repo::count(&conn).unwrap(), 2);
+        delete_template(&conn, id).unwrap();
+        assert_eq!(get_templates(&conn).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn toggle_flips_status() {
+        let conn = memory_db();
+        let id = create_class(&conn, "Class-3 A", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        toggle_class_status(&conn, id).unwrap();
+        assert_eq!(get_class(&conn, id).unwrap().status.as_deref(), Some("inactive"));
+        toggle_class_status(&conn, id).unwrap();
+        assert_eq!(get_active_classes(&conn).unwrap().len(), 1);
+        assert!(toggle_class_status(&conn, 999).is_err());
+    }
+
+    #[test]
+    fn update_validates_name_and_year() {
+        let conn = memory_db();
+        let a = create_class(&conn, "Class-4 A", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        let b = create_class(&conn, "Class-4 B", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+
+        assert!(update_class(&conn, b, "Class-4 A", DEFAULT_ACADEMIC_YEAR_ID, "active").is_err());
+        assert!(update_class(&conn, b, "Class-4 C", 999, "active").is_err());
+        assert!(update_class(&conn, 999, "Class-4 D", DEFAULT_ACADEMIC_YEAR_ID, "active").is_err());
+
+        // Renaming a class to its own name is not a duplicate.
+        update_class(&conn, a, "Class-4 A", DEFAULT_ACADEMIC_YEAR_ID, "inactive").unwrap();
+        update_class(&conn, b, "Class-4 C", DEFAULT_ACADEMIC_YEAR_ID, "active").unwrap();
+        assert_eq!(get_class(&conn, b).unwrap().class_name, "Class-4 C");
+        assert_eq!(get_class(&conn, a).unwrap().status.as_deref(), Some("inactive"));
+    }
+
+    #[test]
+    fn grades_sort_numerically_with_sections() {
+        assert_eq!(split_class_name("Class-10 b"), ("Class-10".to_string(), Some("B".to_string())));
+        assert_eq!(split_class_name("Class-11 Science"), ("Class-11 Science".to_string(), None));
+        assert_eq!(grade_order("Nursery"), -2);
+        assert_eq!(grade_order("UKG"), 0);
+        assert_eq!(grade_order("Class-12 Commerce"), 12);
+
+        let conn = memory_db();
+        for name in ["Class-10 A", "Class-2 B", "LKG A", "Class-2 A"] {
+            create_class(&conn, name, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        }
+        let names: Vec<String> = get_active_classes(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.class_name)
+            .collect();
+        assert_eq!(names, ["LKG A", "Class-2 A", "Class-2 B", "Class-10 A"]);
+
+        let grades = get_grades(&conn, None).unwrap();
+        assert_eq!(grades.len(), 3);
+        assert_eq!(grades[1].grade, "Class-2");
+        assert_eq!(grades[1].sections.len(), 2);
+    }
+
+    #[test]
+    fn roman_numeral_grades_are_not_taken_for_sections() {
+        assert_eq!(split_class_name("Class IV"), ("Class IV".to_string(), None));
+        assert_eq!(split_class_name("Class XI A"), ("Class XI".to_string(), Some("A".to_string())));
+        assert_eq!(split_class_name("Class-5 V"), ("Class-5".to_string(), Some("V".to_string())));
+        assert_eq!(grade_order("Class IV"), 4);
+        assert_eq!(grade_order("Class XI"), 11);
+        assert_eq!(grade_order("Class-XII Science"), 12);
+
+        let conn = memory_db();
+        for name in ["Class XI A", "Class IV", "Class II B", "Class II A"] {
+            create_class(&conn, name, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        }
+        let grades: Vec<(String, i64, usize)> = get_grades(&conn, None)
+            .unwrap()
+            .into_iter()
+            .map(|g| (g.grade, g.grade_order, g.sections.len()))
+            .collect();
+        assert_eq!(
+            grades,
+            [
+                ("Class II".to_string(), 2, 2),
+                ("Class IV".to_string(), 4, 1),
+                ("Class XI".to_string(), 11, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn unranked_grades_keep_their_sections_together() {
+        let conn = memory_db();
+        for name in ["Sunflower A", "Daisy B", "Sunflower B", "Daisy A"] {
+            create_class(&conn, name, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        }
+        let grades: Vec<(String, Vec<Option<String>>)> = get_grades(&conn, None)
+            .unwrap()
+            .into_iter()
+            .map(|g| (g.grade, g.sections.into_iter().map(|s| s.section).collect()))
+            .collect();
+        let sections = vec![Some("A".to_string()), Some("B".to_string())];
+        assert_eq!(
+            grades,
+            [
+                ("Daisy".to_string(), sections.clone()),
+                ("Sunflower".to_string(), sections)
+            ]
+        );
+    }
+
+    #[test]
+    fn full_sections_reject_or_waitlist_admissions() {
+        use crate::service::students::save_core;
+        use crate::test_support::sample_student;
+
+        let conn = memory_db();
+        let id = create_class(&conn, "Class-5 B", DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        assert!(set_capacity(&conn, id, Some(0), None).is_err());
+        set_capacity(&conn, id, Some(1), None).unwrap();
+
+        let mut core = sample_student(&id.to_string(), "GR-1").core;
+        core.section = Some("D".to_string());
+        let first = save_core(&conn, core).unwrap();
+        let stored = crate::repository::students::find_by_id(&conn, first).unwrap().unwrap();
+        assert_eq!(stored.core.section.as_deref(), Some("B"));
+
+        let err = save_core(&conn, sample_student(&id.to_string(), "GR-2").core).unwrap_err();
+        assert!(err.contains("is full"));
+
+        set_capacity(&conn, id, Some(1), Some("waitlist".to_string())).unwrap();
+        let second = save_core(&conn, sample_student(&id.to_string(), "GR-2").core).unwrap();
+        let waitlist = get_waitlist(&conn, id).unwrap();
+        assert_eq!(waitlist.len(), 1);
+        assert_eq!(waitlist[0].student_id, second);
+        assert_eq!(waitlist[0].position, 1);
+        assert!(admit_from_waitlist(&conn, second).unwrap_err().contains("is full"));
+
+        // A seat opens when the first student leaves.
+        lifecycle::record_leaving(&conn, first, "2024-08-01", "Relocated").unwrap();
+        admit_from_waitlist(&conn, second).unwrap();
+        assert!(get_waitlist(&conn, id).unwrap().is_empty());
+        assert_eq!(
+            crate::repository::students::find_status(&conn, second).unwrap(),
+            Some(Some("active".to_string()))
+        );
+    }
+}
@@ -0,0 +1,1125 @@
+use crate::communication::{
+    CommunicationSettings, Message, MessageDraft, MessageRecipient, MessageTemplate,
+};
+use crate::date::Date;
+use crate::messaging::{
+    check_gateway_url, check_smtp_login, is_email, Channel, Outgoing, SmsGatewayChannel,
+    SmtpChannel, SMTP_SECURITY,
+};
+use crate::repository;
+use crate::repository::class as class_repo;
+use crate::repository::communication::{self as repo, FamilyContact, NewRecipient};
+use crate::repository::guardian as guardian_repo;
+use crate::repository::staff as staff_repo;
+use crate::service::dues::outstanding_totals;
+use crate::service::payroll::money;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub const MESSAGE_KINDS: [&str; 2] = ["circular", "notice"];
+pub const CHANNELS: [&str; 4] = ["email", "sms", "whatsapp", "board"];
+pub const AUDIENCES: [&str; 4] = ["school", "class", "staff", "guardian"];
+pub const RECIPIENT_STATUSES: [&str; 6] = [
+    "pending", "sending", "sent", "failed", "skipped", "exported",
+];
+pub const PLACEHOLDERS: [&str; 3] = ["student_name", "class_name", "due_amount"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(
+    value: Option<&str>,
+    options: &[&str],
+    default: &str,
+    what: &str,
+) -> Result<String, String> {
+    let value = value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or(default)
+        .to_lowercase();
+    if options.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            options.join(", ")
+        ))
+    }
+}
+
+/// `{name}` placeholders in `text`. Anything else in braces that looks like a placeholder is
+/// rejected so a typo does not go out to every parent verbatim.
+fn placeholders(text: &str) -> Result<Vec<&'static str>, String> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..close];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+            match PLACEHOLDERS.iter().find(|p| **p == name) {
+                Some(placeholder) => found.push(*placeholder),
+                None => {
+                    return Err(format!(
+                        "Unknown placeholder {{{}}}. Available: {}",
+                        name,
+                        PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                    ))
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+    }
+    Ok(found)
+}
+
+fn render(text: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// The settings as shown in the form: the password and API key are replaced by whether one
+/// is stored.
+pub fn get_settings(conn: &Connection) -> Result<CommunicationSettings, String> {
+    let settings = repo::find_settings(conn).map_err(|e| e.to_string())?;
+    Ok(CommunicationSettings {
+        has_password: settings.smtp_password.is_some(),
+        has_api_key: settings.sms_api_key.is_some(),
+        smtp_password: None,
+        sms_api_key: None,
+        ..settings
+    })
+}
+
+/// Saves the settings. A blank password or API key keeps the one already stored.
+pub fn save_settings(conn: &Connection, settings: CommunicationSettings) -> Result<(), String> {
+    let stored = repo::find_settings(conn).map_err(|e| e.to_string())?;
+    let settings = CommunicationSettings {
+        smtp_host: non_blank(settings.smtp_host),
+        smtp_security: one_of(
+            Some(&settings.smtp_security),
+            &SMTP_SECURITY,
+            "starttls",
+            "SMTP security",
+        )?,
+        smtp_username: non_blank(settings.smtp_username),
+        smtp_password: settings
+            .smtp_password
+            .filter(|p| !p.is_empty())
+            .or(stored.smtp_password),
+        smtp_from: non_blank(settings.smtp_from),
+        smtp_from_name: non_blank(settings.smtp_from_name),
+        sms_gateway_url: non_blank(settings.sms_gateway_url),
+        sms_api_key: non_blank(settings.sms_api_key).or(stored.sms_api_key),
+        sms_sender_id: non_blank(settings.sms_sender_id),
+        ..settings
+    };
+    if settings.smtp_port == 0 {
+        return Err("The SMTP port must be between 1 and 65535".to_string());
+    }
+    if !(1..=10).contains(&settings.max_attempts) {
+        return Err("Attempts per recipient must be between 1 and 10".to_string());
+    }
+    if let Some(host) = settings.smtp_host.as_deref() {
+        check_smtp_login(
+            host,
+            &settings.smtp_security,
+            settings.smtp_username.as_deref(),
+        )?;
+    }
+    if let Some(from) = settings.smtp_from.as_deref() {
+        if !is_email(from) {
+            return Err(format!("'{}' is not a valid sender address", from));
+        }
+    }
+    if let Some(url) = settings.sms_gateway_url.as_deref() {
+        check_gateway_url(url)?;
+        if !url.contains("{to}") || !url.contains("{message}") {
+            return Err(
+                "The SMS gateway URL needs {to} and {message} where the number and text go"
+                    .to_string(),
+            );
+        }
+    }
+    repo::save_settings(conn, &settings).map_err(|e| e.to_string())
+}
+
+pub fn get_templates(conn: &Connection) -> Result<Vec<MessageTemplate>, String> {
+    repo::find_templates(conn).map_err(|e| e.to_string())
+}
+
+fn find_template(conn: &Connection, id: i64) -> Result<MessageTemplate, String> {
+    repo::find_template(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Message template with id {} not found", id))
+}
+
+pub fn save_template(conn: &Connection, template: MessageTemplate) -> Result<i64, String> {
+    let template = MessageTemplate {
+        name: template.name.trim().to_string(),
+        subject: non_blank(template.subject),
+        body: template.body.trim().to_string(),
+        ..template
+    };
+    if template.name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    if template.body.is_empty() {
+        return Err("Template text is required".to_string());
+    }
+    placeholders(template.subject.as_deref().unwrap_or(""))?;
+    placeholders(&template.body)?;
+    if repo::template_name_taken(conn, &template.name, template.id).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "A template named '{}' already exists",
+            template.name
+        ));
+    }
+    match template.id {
+        Some(id) => {
+            if repo::update_template(conn, id, &template).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Message template with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_template(conn, &template).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_template(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::delete_template(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Message template with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Text and addressing shared by every copy of a message being composed.
+struct Composition<'a> {
+    channel: &'a str,
+    subject: &'a str,
+    body: &'a str,
+    uses_student: bool,
+    uses_due: bool,
+    due_amounts: &'a HashMap<i64, f64>,
+}
+
+impl Composition<'_> {
+    /// The guardian's email or phone for the channel, or why the copy cannot go out.
+    fn address(&self, email: Option<&str>, phone: Option<&str>) -> Result<String, String> {
+        let (value, what) = match self.channel {
+            "email" => (email, "email address"),
+            _ => (phone, "phone number"),
+        };
+        let value = value.map(str::trim).unwrap_or("");
+        if value.is_empty() {
+            return Err(format!("No {} on record", what));
+        }
+        if self.channel == "email" && !is_email(value) {
+            return Err(format!("'{}' is not an email address", value));
+        }
+        Ok(value.to_string())
+    }
+
+    fn family_copy(&self, family: &FamilyContact) -> NewRecipient {
+        let due = self
+            .due_amounts
+            .get(&family.student_id)
+            .copied()
+            .filter(|amount| *amount > 0.0);
+        let due_amount = due.map(money).unwrap_or_default();
+        let values = [
+            ("student_name", family.student_name.as_str()),
+            ("class_name", family.class_name.as_str()),
+            ("due_amount", due_amount.as_str()),
+        ];
+        let mut copy = NewRecipient {
+            recipient_type: "guardian",
+            recipient_id: family.guardian_id.unwrap_or(family.student_id),
+            student_id: Some(family.student_id),
+            name: family
+                .guardian_name
+                .clone()
+                .unwrap_or_else(|| family.student_name.clone()),
+            address: String::new(),
+            subject: render(self.subject, &values),
+            body: render(self.body, &values),
+            status: "pending",
+            last_error: None,
+        };
+        let problem = if family.guardian_id.is_none() {
+            copy.recipient_type = "student";
+            Some("No guardian on record".to_string())
+        } else if self.uses_due && due.is_none() {
+            Some("No amount due".to_string())
+        } else {
+            match self.address(family.email.as_deref(), family.phone.as_deref()) {
+                Ok(address) => {
+                    copy.address = address;
+                    None
+                }
+                Err(problem) => Some(problem),
+            }
+        };
+        if let Some(problem) = problem {
+            copy.status = "skipped";
+            copy.last_error = Some(problem);
+        }
+        copy
+    }
+
+    fn direct_copy(
+        &self,
+        recipient_type: &'static str,
+        recipient_id: i64,
+        name: &str,
+        email: Option<&str>,
+        phone: Option<&str>,
+    ) -> NewRecipient {
+        let mut copy = NewRecipient {
+            recipient_type,
+            recipient_id,
+            student_id: None,
+            name: name.to_string(),
+            address: String::new(),
+            subject: self.subject.to_string(),
+            body: self.body.to_string(),
+            status: "pending",
+            last_error: None,
+        };
+        let problem = if self.uses_student {
+            Some("No student on the roll to fill the placeholders".to_string())
+        } else {
+            self.address(email, phone)
+                .map(|address| copy.address = address)
+                .err()
+        };
+        if let Some(problem) = problem {
+            copy.status = "skipped";
+            copy.last_error = Some(problem);
+        }
+        copy
+    }
+}
+
+fn resolve_recipients(
+    conn: &Connection,
+    audience: &str,
+    target_ids: &[i64],
+    composition: &Composition,
+) -> Result<Vec<NewRecipient>, String> {
+    let mut copies = Vec::new();
+    match audience {
+        "school" => {
+            for family in repo::find_families(conn, None).map_err(|e| e.to_string())? {
+                copies.push(composition.family_copy(&family));
+            }
+        }
+        "class" => {
+            for class_id in target_ids {
+                class_repo::find_by_id(conn, *class_id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Class with id {} not found", class_id))?;
+                for family in
+                    repo::find_families(conn, Some(*class_id)).map_err(|e| e.to_string())?
+                {
+                    copies.push(composition.family_copy(&family));
+                }
+            }
+        }
+        "guardian" => {
+            for guardian_id in target_ids {
+                let guardian = guardian_repo::find_by_id(conn, *guardian_id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Guardian with id {} not found", guardian_id))?;
+                let families =
+                    repo::find_guardian_families(conn, *guardian_id).map_err(|e| e.to_string())?;
+                if families.is_empty() {
+                    copies.push(composition.direct_copy(
+                        "guardian",
+                        *guardian_id,
+                        &guardian.full_name,
+                        guardian.email.as_deref(),
+                        guardian.phone.as_deref(),
+                    ));
+                }
+                for family in &families {
+                    copies.push(composition.family_copy(family));
+                }
+            }
+        }
+        _ => {
+            let staff = staff_repo::find_all(conn).map_err(|e| e.to_string())?;
+            for id in target_ids {
+                if !staff.iter().any(|s| s.id == Some(*id)) {
+                    return Err(format!("Staff member with id {} not found", id));
+                }
+            }
+            for member in staff.iter().filter(|s| {
+                is_active(s.status.as_deref())
+                    && (target_ids.is_empty() || s.id.is_some_and(|id| target_ids.contains(&id)))
+            }) {
+                copies.push(composition.direct_copy(
+                    "staff",
+                    member.id.unwrap_or_default(),
+                    &member.name,
+                    Some(&member.email),
+                    Some(&member.phone),
+                ));
+            }
+        }
+    }
+    Ok(copies)
+}
+
+/// Stores the message and one rendered copy per recipient. Copies that cannot go out (no
+/// address, no amount due for a fee reminder) are kept as skipped with the reason.
+pub fn compose_message(conn: &Connection, draft: MessageDraft) -> Result<Message, String> {
+    let kind = one_of(
+        draft.kind.as_deref(),
+        &MESSAGE_KINDS,
+        "circular",
+        "message kind",
+    )?;
+    let channel = one_of(Some(&draft.channel), &CHANNELS, "email", "channel")?;
+    let audience = one_of(Some(&draft.audience), &AUDIENCES, "school", "audience")?;
+    let title = draft.title.trim().to_string();
+    if title.is_empty() {
+        return Err("Message title is required".to_string());
+    }
+    let template = draft
+        .template_id
+        .map(|id| find_template(conn, id))
+        .transpose()?;
+    let body = non_blank(draft.body)
+        .or_else(|| template.as_ref().map(|t| t.body.clone()))
+        .ok_or_else(|| "Write the message or pick a template".to_string())?;
+    let subject = non_blank(draft.subject)
+        .or_else(|| template.as_ref().and_then(|t| non_blank(t.subject.clone())))
+        .unwrap_or_else(|| title.clone());
+    let used = [placeholders(&subject)?, placeholders(&body)?].concat();
+    if let Some(expires_on) = draft.expires_on.as_deref() {
+        Date::parse(expires_on)?;
+    }
+    let mut target_ids = draft.target_ids;
+    target_ids.sort_unstable();
+    target_ids.dedup();
+    if target_ids.is_empty() && matches!(audience.as_str(), "class" | "guardian") {
+        return Err(format!("Pick at least one {} to send to", audience));
+    }
+    if audience == "school" && !target_ids.is_empty() {
+        return Err("A school-wide message cannot be limited to targets".to_string());
+    }
+    if let Some(placeholder) = used.first() {
+        if channel == "board" {
+            return Err(format!(
+                "Notice-board posts are not personalised; remove {{{}}}",
+                placeholder
+            ));
+        }
+        if audience == "staff" {
+            return Err(format!("Staff messages cannot use {{{}}}", placeholder));
+        }
+    }
+
+    let due_amounts = match draft.due_amounts {
+        Some(amounts) => amounts,
+        None if used.contains(&"due_amount") => outstanding_totals(conn)?,
+        None => HashMap::new(),
+    };
+    let composition = Composition {
+        channel: &channel,
+        subject: &subject,
+        body: &body,
+        uses_student: !used.is_empty(),
+        uses_due: used.contains(&"due_amount"),
+        due_amounts: &due_amounts,
+    };
+    let copies = if channel == "board" {
+        Vec::new()
+    } else {
+        resolve_recipients(conn, &audience, &target_ids, &composition)?
+    };
+    if channel != "board" && copies.is_empty() {
+        return Err("Nobody in the audience to send to".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let targets = target_ids
+        .iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let id = repo::insert_message(
+        &tx,
+        &kind,
+        &title,
+        &subject,
+        &body,
+        &channel,
+        &audience,
+        &targets,
+        draft.expires_on.as_deref(),
+        used.is_empty() && audience != "guardian",
+        non_blank(draft.created_by).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    for copy in &copies {
+        repo::insert_recipient(&tx, id, copy).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_message(conn, id)
+}
+
+pub fn get_messages(conn: &Connection) -> Result<Vec<Message>, String> {
+    repo::find_messages(conn).map_err(|e| e.to_string())
+}
+
+pub fn get_message(conn: &Connection, id: i64) -> Result<Message, String> {
+    repo::find_message(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Message with id {} not found", id))
+}
+
+pub fn get_recipients(
+    conn: &Connection,
+    message_id: i64,
+    status: Option<String>,
+) -> Result<Vec<MessageRecipient>, String> {
+    let status = match non_blank(status) {
+        Some(status) => Some(one_of(
+            Some(&status),
+            &RECIPIENT_STATUSES,
+            "pending",
+            "delivery status",
+        )?),
+        None => None,
+    };
+    get_message(conn, message_id)?;
+    repo::find_recipients(conn, message_id, status.as_deref()).map_err(|e| e.to_string())
+}
+
+pub fn get_notice_board(conn: &Connection, class_id: Option<i64>) -> Result<Vec<Message>, String> {
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    repo::find_board(conn, &today, class_id).map_err(|e| e.to_string())
+}
+
+pub fn delete_message(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::delete_message(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Message with id {} not found", id));
+    }
+    Ok(())
+}
+
+fn build_channel(
+    settings: CommunicationSettings,
+    channel: &str,
+) -> Result<Box<dyn Channel + Send>, String> {
+    match channel {
+        "email" => {
+            let (Some(host), Some(from)) = (settings.smtp_host, settings.smtp_from) else {
+                return Err("Set the SMTP server and sender address first".to_string());
+            };
+            Ok(Box::new(SmtpChannel::new(
+                &host,
+                settings.smtp_port,
+                &settings.smtp_security,
+                settings.smtp_username,
+                settings.smtp_password,
+                &from,
+                settings.smtp_from_name.as_deref(),
+            )?))
+        }
+        "sms" => {
+            let Some(url_template) = settings.sms_gateway_url else {
+                return Err("Set the SMS gateway URL first".to_string());
+            };
+            Ok(Box::new(SmsGatewayChannel::new(
+                url_template,
+                settings.sms_api_key.unwrap_or_default(),
+                settings.sms_sender_id.unwrap_or_default(),
+            )))
+        }
+        "whatsapp" => Err("WhatsApp messages are exported for a bulk WhatsApp tool".to_string()),
+        _ => Err("Notice-board posts are not delivered to anyone".to_string()),
+    }
+}
+
+/// The channel to send the message through and the copies still due a try: pending ones and
+/// failed ones below the attempt limit. They are marked `sending` in the same transaction
+/// that picks them, so an overlapping send finds nothing left to take.
+pub fn claim_deliveries(
+    conn: &Connection,
+    message_id: i64,
+) -> Result<(Box<dyn Channel + Send>, Vec<MessageRecipient>), String> {
+    let message = get_message(conn, message_id)?;
+    let settings = repo::find_settings(conn).map_err(|e| e.to_string())?;
+    let max_attempts = settings.max_attempts;
+    let channel = build_channel(settings, &message.channel)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let recipients =
+        repo::find_deliverable(&tx, message_id, max_attempts).map_err(|e| e.to_string())?;
+    let ids: Vec<i64> = recipients.iter().map(|r| r.id).collect();
+    repo::mark_sending(&tx, &ids).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((channel, recipients))
+}
+
+/// Outcome of one try at one copy: the far end's acknowledgement or the error.
+pub struct DeliveryAttempt {
+    pub recipient_id: i64,
+    pub outcome: Result<String, String>,
+}
+
+/// Sends each copy in turn. One failure does not stop the rest.
+pub fn deliver(
+    channel: &mut dyn Channel,
+    recipients: Vec<MessageRecipient>,
+) -> Vec<DeliveryAttempt> {
+    recipients
+        .into_iter()
+        .map(|recipient| DeliveryAttempt {
+            recipient_id: recipient.id,
+            outcome: channel.send(&Outgoing {
+                to: &recipient.address,
+                name: &recipient.name,
+                subject: &recipient.subject,
+                body: &recipient.body,
+            }),
+        })
+        .collect()
+}
+
+pub fn record_deliveries(
+    conn: &Connection,
+    message_id: i64,
+    attempts: Vec<DeliveryAttempt>,
+) -> Result<Message, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for attempt in &attempts {
+        match &attempt.outcome {
+            Ok(reference) => repo::record_sent(&tx, attempt.recipient_id, reference),
+            Err(error) => repo::record_failed(&tx, attempt.recipient_id, error),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_message(conn, message_id)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Digits only, with the Indian country code added to bare ten-digit mobile numbers, which is
+/// the form bulk WhatsApp tools expect.
+fn whatsapp_number(phone: &str) -> String {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    let digits = digits.trim_start_matches('0');
+    if digits.len() == 10 {
+        format!("91{}", digits)
+    } else {
+        digits.to_string()
+    }
+}
+
+/// CSV of number, name and text for every copy not yet sent, for bulk WhatsApp tools, and the
+/// ids of the copies in it. Nothing is marked here: the caller marks them with
+/// [`mark_whatsapp_exported`] once the file is written. Exporting again repeats them in case
+/// the file was lost.
+pub fn export_whatsapp(
+    conn: &Connection,
+    message_id: i64,
+) -> Result<(String, String, Vec<i64>), String> {
+    let message = get_message(conn, message_id)?;
+    if message.channel != "whatsapp" {
+        return Err(format!(
+            "'{}' goes out by {}, not WhatsApp",
+            message.title, message.channel
+        ));
+    }
+    let recipients: Vec<MessageRecipient> = repo::find_recipients(conn, message_id, None)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| matches!(r.status.as_str(), "pending" | "failed" | "exported"))
+        .collect();
+    if recipients.is_empty() {
+        return Err("No copies of this message are left to export".to_string());
+    }
+
+    let mut csv = String::from("Phone,Name,Message\r\n");
+    for recipient in &recipients {
+        csv.push_str(&format!(
+            "{},{},{}\r\n",
+            whatsapp_number(&recipient.address),
+            csv_field(&recipient.name),
+            csv_field(&recipient.body)
+        ));
+    }
+    let ids = recipients.iter().map(|r| r.id).collect();
+    Ok((format!("WhatsApp-Message-{}.csv", message_id), csv, ids))
+}
+
+/// Marks the copies written to a WhatsApp export as exported.
+pub fn mark_whatsapp_exported(conn: &Connection, ids: Vec<i64>) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for id in ids {
+        repo::mark_exported(&tx, id).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::students::{save_contact, save_core};
+    use crate::test_support::{create_class, memory_db, sample_student};
+    use rusqlite::params;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn draft(channel: &str, audience: &str, target_ids: Vec<i64>, body: &str) -> MessageDraft {
+        MessageDraft {
+            kind: None,
+            title: "Fee reminder".to_string(),
+            subject: None,
+            body: Some(body.to_string()),
+            template_id: None,
+            channel: channel.to_string(),
+            audience: audience.to_string(),
+            target_ids,
+            due_amounts: None,
+            expires_on: None,
+            created_by: None,
+        }
+    }
+
+    fn settings(conn: &Connection) -> CommunicationSettings {
+        get_settings(conn).unwrap()
+    }
+
+    /// Sends whatever is due on `message_id`, as the command does.
+    fn send(conn: &Connection, message_id: i64) -> Message {
+        let (mut channel, recipients) = claim_deliveries(conn, message_id).unwrap();
+        let attempts = deliver(&mut *channel, recipients);
+        record_deliveries(conn, message_id, attempts).unwrap()
+    }
+
+    /// A stand-in SMTP server on a free local port that accepts `connections` sessions,
+    /// refuses mail for addresses starting with "bounce" and reports each transcript. It
+    /// offers no STARTTLS, so it is only reachable with security "none".
+    fn smtp_stand_in(connections: usize) -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, transcripts) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let mut writer = stream.unwrap();
+                let mut reader = BufReader::new(writer.try_clone().unwrap());
+                let mut transcript = String::new();
+                let (mut in_data, mut auth_step) = (false, 0);
+                writer.write_all(b"220 stand-in ready\r\n").unwrap();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    transcript.push_str(&line);
+                    let reply: &[u8] = if in_data {
+                        if line != ".\r\n" {
+                            continue;
+                        }
+                        in_data = false;
+                        b"250 queued as 42\r\n"
+                    } else if auth_step > 0 {
+                        auth_step -= 1;
+                        if auth_step == 1 {
+                            b"334 UGFzc3dvcmQ6\r\n"
+                        } else {
+                            b"235 authenticated\r\n"
+                        }
+                    } else if line.starts_with("EHLO") {
+                        b"250-stand-in\r\n250 AUTH LOGIN\r\n"
+                    } else if line.starts_with("AUTH LOGIN") {
+                        auth_step = 2;
+                        b"334 VXNlcm5hbWU6\r\n"
+                    } else if line.starts_with("RCPT TO:<bounce") {
+                        b"550 no such mailbox\r\n"
+                    } else if line.starts_with("DATA") {
+                        in_data = true;
+                        b"354 go ahead\r\n"
+                    } else if line.starts_with("QUIT") {
+                        writer.write_all(b"221 bye\r\n").unwrap();
+                        break;
+                    } else {
+                        b"250 ok\r\n"
+                    };
+                    writer.write_all(reply).unwrap();
+                }
+                sender.send(transcript).unwrap();
+            }
+        });
+        (port, transcripts)
+    }
+
+    /// A stand-in HTTP gateway answering one request per scripted `(status, body)`, with a
+    /// chunked body as many gateways send, and reporting each request line.
+    fn http_stand_in(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).unwrap_or(0) == 0 || header == "\r\n" {
+                        break;
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+                     {:x}\r\n{}\r\n0\r\n\r\n",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                sender.send(request_line.trim_end().to_string()).unwrap();
+            }
+        });
+        (port, requests)
+    }
+
+    /// Two students in "Class-5 A" whose fathers, Rajesh and Suresh, are the primary contacts.
+    fn class_with_students(conn: &Connection) -> (i64, i64, i64) {
+        let class_id = create_class(conn, "Class-5 A");
+        let mut ids = Vec::new();
+        for (gr_number, father, mobile) in [
+            ("G1", "Rajesh", "9876543210"),
+            ("G2", "Suresh", "9812345678"),
+        ] {
+            let mut student = sample_student(&class_id.to_string(), gr_number);
+            student.core.father_name = father.to_string();
+            student.contact.mobile_number = Some(mobile.to_string());
+            let id = save_core(conn, student.core).unwrap();
+            save_contact(conn, id, student.contact).unwrap();
+            ids.push(id);
+        }
+        (class_id, ids[0], ids[1])
+    }
+
+    #[test]
+    fn compose_renders_each_copy_and_skips_students_without_dues() {
+        let conn = memory_db();
+        let (class_id, first, second) = class_with_students(&conn);
+
+        let body = "Dear parent of {student_name} ({class_name}), Rs. {due_amount} is due.";
+        let err = compose_message(&conn, draft("sms", "class", vec![class_id], "Pay {fees}"))
+            .unwrap_err();
+        assert!(err.contains("Unknown placeholder {fees}"), "{}", err);
+        let err = compose_message(&conn, draft("sms", "staff", vec![], body)).unwrap_err();
+        assert!(err.contains("Staff messages cannot use"), "{}", err);
+
+        let mut fee_reminder = draft("sms", "class", vec![class_id], body);
+        fee_reminder.due_amounts = Some(HashMap::from([(first, 12500.0), (second, 0.0)]));
+        let message = compose_message(&conn, fee_reminder).unwrap();
+        assert_eq!(
+            (message.recipients, message.pending, message.skipped),
+            (2, 1, 1)
+        );
+        assert_eq!(message.subject, "Fee reminder");
+
+        let copies = get_recipients(&conn, message.id, None).unwrap();
+        let sent = copies.iter().find(|c| c.student_id == Some(first)).unwrap();
+        assert_eq!(
+            sent.body,
+            "Dear parent of Student G1 (Class-5 A), Rs. 12,500.00 is due."
+        );
+        assert_eq!(
+            (sent.name.as_str(), sent.address.as_str()),
+            ("Rajesh", "9876543210")
+        );
+        let skipped = copies
+            .iter()
+            .find(|c| c.student_id == Some(second))
+            .unwrap();
+        assert_eq!(skipped.status, "skipped");
+        assert_eq!(skipped.last_error.as_deref(), Some("No amount due"));
+    }
+
+    #[test]
+    fn email_goes_through_smtp_and_records_each_outcome() {
+        let conn = memory_db();
+        let (class_id, first, second) = class_with_students(&conn);
+        for (student_id, email) in [
+            (first, "rajesh@family.test"),
+            (second, "bounce@family.test"),
+        ] {
+            conn.execute(
+                "UPDATE guardians SET email = ?1 WHERE id =
+                    (SELECT guardian_id FROM student_guardians WHERE student_id = ?2)",
+                params![email, student_id],
+            )
+            .unwrap();
+        }
+        let (port, transcripts) = smtp_stand_in(1);
+        let remote = CommunicationSettings {
+            smtp_host: Some("smtp.mailhost.test".to_string()),
+            smtp_port: 587,
+            smtp_username: Some("office".to_string()),
+            smtp_password: Some("secret".to_string()),
+            ..settings(&conn)
+        };
+        assert_eq!(remote.smtp_security, "starttls");
+        let err = save_settings(
+            &conn,
+            CommunicationSettings {
+                smtp_security: "none".to_string(),
+                ..remote
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("without TLS"), "{}", err);
+        let err = save_settings(
+            &conn,
+            CommunicationSettings {
+                smtp_security: "ssl".to_string(),
+                ..settings(&conn)
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("Invalid SMTP security 'ssl'"), "{}", err);
+        save_settings(
+            &conn,
+            CommunicationSettings {
+                smtp_host: Some("127.0.0.1".to_string()),
+                smtp_port: port,
+                smtp_security: "none".to_string(),
+                smtp_username: Some("office".to_string()),
+                smtp_password: Some("secret".to_string()),
+                smtp_from: Some("office@greenvalley.test".to_string()),
+                smtp_from_name: Some("Green Valley School".to_string()),
+                ..settings(&conn)
+            },
+        )
+        .unwrap();
+
+        // The password is kept but never handed back, and saving without it keeps it.
+        let shown = settings(&conn);
+        assert!(shown.has_password && shown.smtp_password.is_none());
+        assert!(!serde_json::to_string(&shown).unwrap().contains("secret"));
+        save_settings(
+            &conn,
+            CommunicationSettings {
+                smtp_from_name: Some("Green Valley High School".to_string()),
+                ..shown
+            },
+        )
+        .unwrap();
+
+        let mut circular = draft(
+            "email",
+            "class",
+            vec![class_id],
+            "Sports day for {student_name}.\n.\nBring water.",
+        );
+        circular.subject = Some("Sports day".to_string());
+        let message = compose_message(&conn, circular).unwrap();
+        let message = send(&conn, message.id);
+        assert_eq!((message.sent, message.failed), (1, 1));
+
+        let copies = get_recipients(&conn, message.id, None).unwrap();
+        let delivered = copies.iter().find(|c| c.student_id == Some(first)).unwrap();
+        assert_eq!(delivered.provider_ref.as_deref(), Some("queued as 42"));
+        assert!(delivered.sent_at.is_some());
+        let bounced = copies
+            .iter()
+            .find(|c| c.student_id == Some(second))
+            .unwrap();
+        assert_eq!((bounced.status.as_str(), bounced.attempts), ("failed", 1));
+        let error = bounced.last_error.as_deref().unwrap();
+        assert!(
+            error.contains("550") && error.contains("no such mailbox"),
+            "{}",
+            error
+        );
+
+        // Both copies went through one session.
+        let transcript = transcripts.recv().unwrap();
+        assert_eq!(transcript.matches("EHLO").count(), 1, "{}", transcript);
+        assert!(transcript.contains("RCPT TO:<bounce@family.test>"));
+        assert!(
+            transcript.contains("AUTH LOGIN\r\nb2ZmaWNl\r\nc2VjcmV0\r\n"),
+            "{}",
+            transcript
+        );
+        assert!(transcript.contains("RCPT TO:<rajesh@family.test>"));
+        assert!(transcript.contains("Subject: Sports day\r\n"));
+        // The lone "." line is dot-stuffed so it does not end the message early.
+        assert!(transcript.contains("Sports day for Student G1.\r\n..\r\nBring water.\r\n.\r\n"));
+    }
+
+    #[test]
+    fn failed_sms_is_retried_until_the_attempt_limit() {
+        let conn = memory_db();
+        let (class_id, first, _) = class_with_students(&conn);
+        conn.execute(
+            "UPDATE students SET status = 'left' WHERE id != ?1",
+            params![first],
+        )
+        .unwrap();
+        let (port, requests) = http_stand_in(vec![
+            ("500 Internal Server Error", "gateway down"),
+            ("200 OK", "ok:1001"),
+        ]);
+        let err = save_settings(
+            &conn,
+            CommunicationSettings {
+                sms_gateway_url: Some("ftp://sms.test/send?to={to}&text={message}".to_string()),
+                ..settings(&conn)
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("must start with https://"), "{}", err);
+        save_settings(
+            &conn,
+            CommunicationSettings {
+                sms_gateway_url: Some("https://sms.test/send?to={to}&text={message}".to_string()),
+                ..settings(&conn)
+            },
+        )
+        .unwrap();
+        let err = save_settings(
+            &conn,
+            CommunicationSettings {
+                sms_gateway_url: Some("http://sms.test/send?to={to}&text={message}".to_string()),
+                ..settings(&conn)
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("unencrypted"), "{}", err);
+        save_settings(
+            &conn,
+            CommunicationSettings {
+                sms_gateway_url: Some(format!(
+                    "http://127.0.0.1:{}/send?key={{api_key}}&to={{to}}&from={{sender}}&text={{message}}",
+                    port
+                )),
+                sms_api_key: Some("k1".to_string()),
+                sms_sender_id: Some("GVSCHL".to_string()),
+                max_attempts: 2,
+                ..settings(&conn)
+            },
+        )
+        .unwrap();
+
+        let message = compose_message(
+            &conn,
+            draft("sms", "class", vec![class_id], "School closed today"),
+        )
+        .unwrap();
+        let message = send(&conn, message.id);
+        assert_eq!((message.sent, message.failed), (0, 1));
+        let failed = &get_recipients(&conn, message.id, Some("failed".to_string())).unwrap()[0];
+        assert_eq!(
+            failed.last_error.as_deref(),
+            Some("SMS gateway answered 500: gateway down")
+        );
+
+        // The retry is claimed before it goes out, so an overlapping send has nothing to take.
+        let (mut channel, recipients) = claim_deliveries(&conn, message.id).unwrap();
+        assert_eq!(recipients.len(), 1);
+        assert_eq!(get_message(&conn, message.id).unwrap().sending, 1);
+        assert!(claim_deliveries(&conn, message.id).unwrap().1.is_empty());
+        let attempts = deliver(&mut *channel, recipients);
+        let message = record_deliveries(&conn, message.id, attempts).unwrap();
+        assert_eq!((message.sent, message.failed, message.sending), (1, 0, 0));
+        let sent = &get_recipients(&conn, message.id, Some("sent".to_string())).unwrap()[0];
+        assert_eq!(
+            (sent.attempts, sent.provider_ref.as_deref()),
+            (2, Some("ok:1001"))
+        );
+        assert_eq!(
+            requests.recv().unwrap(),
+            "GET /send?key=k1&to=9876543210&from=GVSCHL&text=School%20closed%20today HTTP/1.1"
+        );
+        assert!(claim_deliveries(&conn, message.id).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn whatsapp_export_and_notice_board() {
+        let conn = memory_db();
+        let (class_id, _, _) = class_with_students(&conn);
+        let other_class = create_class(&conn, "Class-6 A");
+
+        let message = compose_message(
+            &conn,
+            draft(
+                "whatsapp",
+                "class",
+                vec![class_id],
+                "Hello, parent of {student_name}",
+            ),
+        )
+        .unwrap();
+        assert!(claim_deliveries(&conn, message.id).is_err());
+        let (file_name, csv, ids) = export_whatsapp(&conn, message.id).unwrap();
+        assert_eq!(file_name, format!("WhatsApp-Message-{}.csv", message.id));
+        assert_eq!(
+            csv,
+            "Phone,Name,Message\r\n\
+             919876543210,Rajesh,\"Hello, parent of Student G1\"\r\n\
+             919812345678,Suresh,\"Hello, parent of Student G2\"\r\n"
+        );
+        // Nothing counts as exported until the file is written and the copies are marked.
+        assert_eq!(get_message(&conn, message.id).unwrap().exported, 0);
+        mark_whatsapp_exported(&conn, ids).unwrap();
+        assert_eq!(get_message(&conn, message.id).unwrap().exported, 2);
+
+        let holiday =
+            compose_message(&conn, draft("email", "school", vec![], "Holiday on Friday")).unwrap();
+        let mut expired = draft("board", "school", vec![], "Old notice");
+        expired.expires_on = Some("2000-01-01".to_string());
+        compose_message(&conn, expired).unwrap();
+        let mut notice = draft("board", "class", vec![other_class], "Class 6 trip");
+        notice.kind = Some("notice".to_string());
+        let notice = compose_message(&conn, notice).unwrap();
+        assert_eq!(notice.recipients, 0);
+        let err = compose_message(&conn, draft("board", "school", vec![], "Hi {student_name}"))
+            .unwrap_err();
+        assert!(err.contains("not personalised"), "{}", err);
+
+        // Personalised messages stay off the board, as do expired ones and other classes'.
+        let board = |class_id| -> Vec<i64> {
+            get_notice_board(&conn, class_id)
+                .unwrap()
+                .iter()
+                .map(|m| m.id)
+                .collect()
+        };
+        assert_eq!(board(Some(class_id)), vec![holiday.id]);
+        assert_eq!(board(Some(other_class)), vec![notice.id, holiday.id]);
+        assert_eq!(board(None), vec![notice.id, holiday.id]);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::dashboard::{AttendanceToday, CountBy, DashboardStats, FeeCollection};
+use crate::date::{days_in_month, Date};
+use crate::repository;
+use crate::repository::dashboard as repo;
+use crate::service::class::{grade_order, split_class_name};
+use crate::service::staff_attendance::get_register;
+use rusqlite::Connection;
+
+fn percentage(part: f64, whole: f64) -> Option<f64> {
+    (whole > 0.0).then(|| (part / whole * 1000.0).round() / 10.0)
+}
+
+fn total(counts: &[CountBy]) -> u32 {
+    counts.iter().map(|c| c.count).sum()
+}
+
+fn fee_collection(conn: &Connection) -> Result<FeeCollection, String> {
+    let (raised, collected, waived, outstanding) =
+        repo::dues_totals(conn).map_err(|e| e.to_string())?;
+    Ok(FeeCollection {
+        raised,
+        collected,
+        waived,
+        outstanding,
+        collection_rate: percentage(collected, raised - waived),
+    })
+}
+
+fn attendance_today(conn: &Connection) -> Result<AttendanceToday, String> {
+    let register = get_register(conn, None)?;
+    let mut attendance = AttendanceToday {
+        expected: 0,
+        on_leave: 0,
+        marked: 0,
+        present: 0.0,
+        rate: None,
+    };
+    for record in &register {
+        if record.on_leave.is_some() {
+            attendance.on_leave += 1;
+            continue;
+        }
+        attendance.expected += 1;
+        if let Some(mark) = &record.attendance {
+            attendance.marked += 1;
+            attendance.present += match mark.status.as_str() {
+                "present" => 1.0,
+                "half_day" => 0.5,
+                _ => 0.0,
+            };
+        }
+    }
+    if attendance.marked > 0 {
+        attendance.rate = percentage(attendance.present, attendance.expected as f64);
+    }
+    Ok(attendance)
+}
+
+/// Every figure on the overview page, worked out afresh.
+pub fn get_stats(conn: &Connection) -> Result<DashboardStats, String> {
+    let today = Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?;
+    let month_start = Date { day: 1, ..today };
+    let month_end = Date {
+        day: days_in_month(today.year, today.month),
+        ..today
+    };
+
+    let students_by_gender = repo::students_by_gender(conn).map_err(|e| e.to_string())?;
+    let mut students_by_class = repo::students_by_class(conn).map_err(|e| e.to_string())?;
+    students_by_class.sort_by(|a, b| {
+        let (grade_a, section_a) = split_class_name(&a.label);
+        let (grade_b, section_b) = split_class_name(&b.label);
+        grade_order(&grade_a)
+            .cmp(&grade_order(&grade_b))
+            .then_with(|| grade_a.cmp(&grade_b))
+            .then_with(|| section_a.cmp(&section_b))
+    });
+    let staff_by_department = repo::staff_by_department(conn).map_err(|e| e.to_string())?;
+
+    Ok(DashboardStats {
+        as_of: today.to_string(),
+        active_students: total(&students_by_gender),
+        students_by_gender,
+        students_by_class,
+        new_admissions_this_month: repo::count_admissions(
+            conn,
+            &month_start.to_string(),
+            &month_end.to_string(),
+        )
+        .map_err(|e| e.to_string())?,
+        enquiries_by_status: repo::enquiries_by_status(conn).map_err(|e| e.to_string())?,
+        staff_headcount: total(&staff_by_department),
+        staff_by_department,
+        fee_collection: fee_collection(conn)?,
+        attendance_today: attendance_today(conn)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dues::{DueSettlement, StudentDue};
+    use crate::service::dues::{post_due, settle_due};
+    use crate::service::enquiry::{create_enquiry, update_enquiry_status};
+    use crate::service::staff::create_staff;
+    use crate::service::staff_attendance::mark_attendance;
+    use crate::service::students::{save_core, save_health};
+    use crate::staff::Staff;
+    use crate::staff_attendance::StaffAttendance;
+    use crate::test_support::{
+        create_class, memory_db, sample_enquiry, sample_staff, sample_student,
+    };
+
+    fn counts(counts: &[CountBy]) -> Vec<(&str, u32)> {
+        counts.iter().map(|c| (c.label.as_str(), c.count)).collect()
+    }
+
+    fn due(student_id: i64, amount: f64) -> StudentDue {
+        StudentDue {
+            id: None,
+            student_id,
+            source: None,
+            reference: None,
+            description: "Term fee".to_string(),
+            amount,
+            raised_on: None,
+            status: None,
+            settled_on: None,
+            settled_note: None,
+            student_name: None,
+            class_name: None,
+        }
+    }
+
+    #[test]
+    fn students_enquiries_and_staff_are_counted() {
+        let conn = memory_db();
+        let class_10 = create_class(&conn, "Class-10");
+        let class_2 = create_class(&conn, "Class-2 A");
+        let today = repository::today(&conn).unwrap();
+        let first = save_core(&conn, sample_student(&class_10.to_string(), "S1").core).unwrap();
+        save_core(&conn, sample_student(&class_2.to_string(), "S2").core).unwrap();
+        let mut boy = sample_student(&class_2.to_string(), "S3");
+        boy.core.gender = "male".to_string();
+        save_core(&conn, boy.core).unwrap();
+        let mut admitted = sample_student(&class_2.to_string(), "S4").health;
+        admitted.status = Some("active".to_string());
+        admitted.admission_date = Some(today.clone());
+        save_health(&conn, first, admitted).unwrap();
+
+        let enquiry = create_enquiry(&conn, sample_enquiry("Asha")).unwrap();
+        create_enquiry(&conn, sample_enquiry("Ravi")).unwrap();
+        update_enquiry_status(&conn, enquiry, "Converted").unwrap();
+
+        create_staff(&conn, sample_staff("Meera Iyer")).unwrap();
+        create_staff(
+            &conn,
+            Staff {
+                department: "Administration".to_string(),
+                ..sample_staff("Vijay Rao")
+            },
+        )
+        .unwrap();
+        create_staff(
+            &conn,
+            Staff {
+                status: Some("inactive".to_string()),
+                ..sample_staff("Old Hand")
+            },
+        )
+        .unwrap();
+
+        let stats = get_stats(&conn).unwrap();
+        assert_eq!(stats.as_of, today);
+        assert_eq!(stats.active_students, 3);
+        assert_eq!(
+            counts(&stats.students_by_gender),
+            vec![("Female", 2), ("Male", 1)]
+        );
+        assert_eq!(
+            counts(&stats.students_by_class),
+            vec![("Class-2 A", 2), ("Class-10", 1)]
+        );
+        assert_eq!(stats.new_admissions_this_month, 1);
+        assert_eq!(
+            counts(&stats.enquiries_by_status),
+            vec![("converted", 1), ("new", 1)]
+        );
+        assert_eq!(stats.staff_headcount, 2);
+        assert_eq!(
+            counts(&stats.staff_by_department),
+            vec![("Administration", 1), ("Science", 1)]
+        );
+    }
+
+    #[test]
+    fn fees_and_attendance_are_summarised() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-5");
+        let student = save_core(&conn, sample_student(&class_id.to_string(), "S1").core).unwrap();
+        let paid = post_due(&conn, due(student, 600.0), "fees").unwrap();
+        let waived = post_due(&conn, due(student, 200.0), "fees").unwrap();
+        post_due(&conn, due(student, 400.0), "fees").unwrap();
+        let settle = |status: &str, note: Option<&str>| DueSettlement {
+            status: status.to_string(),
+            settled_on: None,
+            note: note.map(str::to_string),
+        };
+        settle_due(&conn, paid, settle("paid", None)).unwrap();
+        settle_due(&conn, waived, settle("waived", Some("Sibling concession"))).unwrap();
+
+        let present = create_staff(&conn, sample_staff("Meera Iyer")).unwrap();
+        let half_day = create_staff(&conn, sample_staff("Vijay Rao")).unwrap();
+        create_staff(&conn, sample_staff("Anil Desai")).unwrap();
+        let before = get_stats(&conn).unwrap();
+        assert_eq!(before.attendance_today.rate, None);
+
+        for (staff_id, status) in [(present, "present"), (half_day, "half_day")] {
+            mark_attendance(
+                &conn,
+                StaffAttendance {
+                    id: None,
+                    staff_id,
+                    date: before.as_of.clone(),
+                    check_in: None,
+                    check_out: None,
+                    status: status.to_string(),
+                    remarks: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let stats = get_stats(&conn).unwrap();
+        let fees = &stats.fee_collection;
+        assert_eq!(
+            (fees.raised, fees.collected, fees.waived, fees.outstanding),
+            (1200.0, 600.0, 200.0, 400.0)
+        );
+        assert_eq!(fees.collection_rate, Some(60.0));
+        let attendance = &stats.attendance_today;
+        assert_eq!((attendance.expected, attendance.marked), (3, 2));
+        assert_eq!(attendance.rate, Some(50.0));
+    }
+}
@@ -0,0 +1,517 @@
+use crate::date::Date;
+use crate::discipline::{DisciplineIncident, DisciplineTimelineEntry, ParentMeeting};
+use crate::repository;
+use crate::repository::discipline as repo;
+use crate::repository::guardian as guardian_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::students as student_repo;
+use rusqlite::Connection;
+
+/// Roles a viewer can hold, from least to most trusted.
+pub const VIEWER_ROLES: [&str; 3] = ["teacher", "counsellor", "principal"];
+/// Who may read an incident, matched by position against `VIEWER_ROLES`.
+pub const VISIBILITY_LEVELS: [&str; 3] = ["staff", "counsellor", "principal"];
+pub const CATEGORIES: [&str; 8] = [
+    "disruption",
+    "bullying",
+    "fighting",
+    "damage",
+    "cheating",
+    "uniform",
+    "late",
+    "other",
+];
+pub const SEVERITIES: [&str; 3] = ["minor", "moderate", "major"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, allowed: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn rank(value: &str, allowed: &[&str], what: &str) -> Result<u8, String> {
+    let value = one_of(value, allowed, what)?;
+    Ok(allowed.iter().position(|v| *v == value).unwrap_or_default() as u8)
+}
+
+fn viewer_rank(viewer_role: &str) -> Result<u8, String> {
+    rank(viewer_role, &VIEWER_ROLES, "role")
+}
+
+fn visibility(value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(level) => one_of(&level, &VISIBILITY_LEVELS, "visibility"),
+        None => Ok("staff".to_string()),
+    }
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn capitalise(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn ensure_guardian_of(conn: &Connection, student_id: i64, guardian_id: i64) -> Result<(), String> {
+    let guardian = guardian_repo::find_by_id(conn, guardian_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Guardian with id {} not found", guardian_id))?;
+    if !guardian_repo::is_linked(conn, student_id, guardian_id).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "{} is not a guardian on this student's record",
+            guardian.full_name
+        ));
+    }
+    Ok(())
+}
+
+/// The incident, provided the viewer may see it. Hidden incidents read as missing.
+fn find_visible(conn: &Connection, id: i64, viewer_rank: u8) -> Result<DisciplineIncident, String> {
+    let not_found = || format!("Incident with id {} not found", id);
+    let incident = repo::find_incident(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(not_found)?;
+    let level = incident.visibility.as_deref().unwrap_or("staff");
+    if rank(level, &VISIBILITY_LEVELS, "visibility")? > viewer_rank {
+        return Err(not_found());
+    }
+    Ok(incident)
+}
+
+/// Records or corrects an incident. Viewers cannot file an incident above their own role,
+/// and only the principal decides what goes on a character certificate.
+pub fn save_incident(
+    conn: &Connection,
+    incident: DisciplineIncident,
+    viewer_role: &str,
+) -> Result<i64, String> {
+    let viewer = viewer_rank(viewer_role)?;
+    let incident = DisciplineIncident {
+        incident_date: Date::parse(&incident.incident_date)?.to_string(),
+        category: one_of(&incident.category, &CATEGORIES, "category")?,
+        severity: one_of(&incident.severity, &SEVERITIES, "severity")?,
+        description: incident.description.trim().to_string(),
+        action_taken: non_blank(incident.action_taken),
+        visibility: Some(visibility(incident.visibility)?),
+        ..incident
+    };
+    if incident.description.is_empty() {
+        return Err("Describe what happened".to_string());
+    }
+    if incident.incident_date > today(conn)?.to_string() {
+        return Err("An incident cannot be dated in the future".to_string());
+    }
+    let level = incident.visibility.as_deref().unwrap_or("staff");
+    if rank(level, &VISIBILITY_LEVELS, "visibility")? > viewer {
+        return Err(format!(
+            "A {} cannot restrict an incident to the {}",
+            viewer_role.trim().to_lowercase(),
+            level
+        ));
+    }
+    if !student_repo::exists(conn, incident.student_id).map_err(|e| e.to_string())? {
+        return Err(format!("Student with id {} not found", incident.student_id));
+    }
+    if staff_repo::find_by_id(conn, incident.reported_by)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!(
+            "Staff member with id {} not found",
+            incident.reported_by
+        ));
+    }
+    let existing = incident
+        .id
+        .map(|id| find_visible(conn, id, viewer))
+        .transpose()?;
+    let was_on_certificate = existing
+        .as_ref()
+        .is_some_and(|existing| existing.include_on_certificate);
+    if incident.include_on_certificate != was_on_certificate
+        && viewer < VIEWER_ROLES.len() as u8 - 1
+    {
+        return Err("Only the principal decides what appears on a certificate".to_string());
+    }
+
+    match existing {
+        Some(existing) => {
+            if existing.student_id != incident.student_id {
+                return Err("An incident cannot be moved to another student".to_string());
+            }
+            let id = existing.id.unwrap_or_default();
+            repo::update_incident(conn, id, &incident).map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+        None => repo::insert_incident(conn, &incident).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_incident(conn: &Connection, id: i64, viewer_role: &str) -> Result<(), String> {
+    let viewer = viewer_rank(viewer_role)?;
+    if viewer < VIEWER_ROLES.len() as u8 - 1 {
+        return Err("Only the principal can delete an incident".to_string());
+    }
+    find_visible(conn, id, viewer)?;
+    repo::delete_incident(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_incidents(
+    conn: &Connection,
+    viewer_role: &str,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+    unacknowledged_only: bool,
+) -> Result<Vec<DisciplineIncident>, String> {
+    let viewer = viewer_rank(viewer_role)?;
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    repo::find_incidents(conn, viewer, student_id, &from, &to, unacknowledged_only)
+        .map_err(|e| e.to_string())
+}
+
+pub fn acknowledge_incident(
+    conn: &Connection,
+    incident_id: i64,
+    guardian_id: i64,
+    acknowledged_on: Option<String>,
+    parent_remarks: Option<String>,
+) -> Result<(), String> {
+    let incident = repo::find_incident(conn, incident_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Incident with id {} not found", incident_id))?;
+    if let (Some(name), Some(on)) = (&incident.acknowledged_by_name, &incident.acknowledged_on) {
+        return Err(format!(
+            "{} already acknowledged this incident on {}",
+            name, on
+        ));
+    }
+    ensure_guardian_of(conn, incident.student_id, guardian_id)?;
+    let acknowledged_on = match non_blank(acknowledged_on) {
+        Some(date) => Date::parse(&date)?,
+        None => today(conn)?,
+    }
+    .to_string();
+    if acknowledged_on < incident.incident_date {
+        return Err("An incident cannot be acknowledged before it happened".to_string());
+    }
+    repo::acknowledge(
+        conn,
+        incident_id,
+        guardian_id,
+        &acknowledged_on,
+        non_blank(parent_remarks).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn record_meeting(conn: &Connection, meeting: ParentMeeting) -> Result<i64, String> {
+    let meeting = ParentMeeting {
+        meeting_date: Date::parse(&meeting.meeting_date)?.to_string(),
+        held_by: non_blank(meeting.held_by),
+        summary: meeting.summary.trim().to_string(),
+        follow_up: non_blank(meeting.follow_up),
+        visibility: Some(visibility(meeting.visibility)?),
+        ..meeting
+    };
+    if meeting.summary.is_empty() {
+        return Err("Summarise what was discussed".to_string());
+    }
+    if !student_repo::exists(conn, meeting.student_id).map_err(|e| e.to_string())? {
+        return Err(format!("Student with id {} not found", meeting.student_id));
+    }
+    if let Some(incident_id) = meeting.incident_id {
+        let incident = repo::find_incident(conn, incident_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Incident with id {} not found", incident_id))?;
+        if incident.student_id != meeting.student_id {
+            return Err("The incident is about another student".to_string());
+        }
+    }
+    if let Some(guardian_id) = meeting.guardian_id {
+        ensure_guardian_of(conn, meeting.student_id, guardian_id)?;
+    }
+    repo::insert_meeting(conn, &meeting).map_err(|e| e.to_string())
+}
+
+/// Everything on the student's record the viewer may see, latest first. An acknowledgement
+/// appears as its own entry on the day it was given.
+pub fn get_timeline(
+    conn: &Connection,
+    student_id: i64,
+    viewer_role: &str,
+) -> Result<Vec<DisciplineTimelineEntry>, String> {
+    let viewer = viewer_rank(viewer_role)?;
+    let incidents = repo::find_incidents(
+        conn,
+        viewer,
+        Some(student_id),
+        "0000-01-01",
+        "9999-12-31",
+        false,
+    )
+    .map_err(|e| e.to_string())?;
+    let meetings = repo::find_meetings(conn, student_id, viewer).map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for meeting in meetings {
+        let details = match meeting.follow_up {
+            Some(follow_up) => format!("{} Follow-up: {}", meeting.summary, follow_up),
+            None => meeting.summary,
+        };
+        entries.push(DisciplineTimelineEntry {
+            date: meeting.meeting_date,
+            entry_type: "meeting".to_string(),
+            incident_id: meeting.incident_id,
+            meeting_id: meeting.id,
+            title: format!(
+                "Parent meeting with {}",
+                meeting.guardian_name.as_deref().unwrap_or("parents")
+            ),
+            details: Some(details),
+            severity: None,
+        });
+    }
+    for incident in incidents {
+        if let Some(acknowledged_on) = incident.acknowledged_on {
+            entries.push(DisciplineTimelineEntry {
+                date: acknowledged_on,
+                entry_type: "acknowledgement".to_string(),
+                incident_id: incident.id,
+                meeting_id: None,
+                title: format!(
+                    "Acknowledged by {}",
+                    incident
+                        .acknowledged_by_name
+                        .as_deref()
+                        .unwrap_or("guardian")
+                ),
+                details: incident.parent_remarks,
+                severity: None,
+            });
+        }
+        let details = match incident.action_taken {
+            Some(action) => format!("{} Action taken: {}", incident.description, action),
+            None => incident.description,
+        };
+        entries.push(DisciplineTimelineEntry {
+            date: incident.incident_date,
+            entry_type: "incident".to_string(),
+            incident_id: incident.id,
+            meeting_id: None,
+            title: capitalise(&incident.category),
+            details: Some(details),
+            severity: Some(incident.severity),
+        });
+    }
+    entries.sort_by(|a, b| b.date.cmp(&a.date));
+    Ok(entries)
+}
+
+/// Incidents the principal marked for the student's character certificate, oldest first.
+pub fn certificate_incidents(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<DisciplineIncident>, String> {
+    repo::find_certificate_incidents(conn, student_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate::IssueCertificateRequest;
+    use crate::guardian::Guardian;
+    use crate::service::certificate::issue_certificate;
+    use crate::service::guardian::{create_guardian, link_guardian};
+    use crate::service::staff::create_staff;
+    use crate::service::students::save_core;
+    use crate::test_support::{
+        create_class, memory_db, sample_guardian, sample_staff, sample_student,
+    };
+
+    fn incident(student_id: i64, reported_by: i64, visibility: &str) -> DisciplineIncident {
+        DisciplineIncident {
+            id: None,
+            student_id,
+            incident_date: "2024-09-03".to_string(),
+            category: "Fighting".to_string(),
+            severity: "major".to_string(),
+            description: "Fight during the lunch break.".to_string(),
+            action_taken: Some("Two-day suspension".to_string()),
+            reported_by,
+            visibility: Some(visibility.to_string()),
+            include_on_certificate: false,
+            acknowledged_by: None,
+            acknowledged_on: None,
+            parent_remarks: None,
+            student_name: None,
+            class_name: None,
+            reporter_name: None,
+            acknowledged_by_name: None,
+        }
+    }
+
+    fn setup(conn: &Connection) -> (i64, i64) {
+        let class_id = create_class(conn, "Class-7 A");
+        let student = save_core(conn, sample_student(&class_id.to_string(), "D1").core).unwrap();
+        let teacher = create_staff(conn, sample_staff("Rohit Verma")).unwrap();
+        (student, teacher)
+    }
+
+    #[test]
+    fn visibility_follows_the_viewer_role() {
+        let conn = memory_db();
+        let (student, teacher) = setup(&conn);
+        save_incident(&conn, incident(student, teacher, "staff"), "teacher").unwrap();
+        let sensitive = save_incident(
+            &conn,
+            incident(student, teacher, "counsellor"),
+            "counsellor",
+        )
+        .unwrap();
+        assert!(
+            save_incident(&conn, incident(student, teacher, "principal"), "teacher")
+                .unwrap_err()
+                .contains("cannot restrict")
+        );
+
+        let seen = |role: &str| {
+            get_incidents(&conn, role, Some(student), None, None, false)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(
+            (seen("teacher"), seen("counsellor"), seen("principal")),
+            (1, 2, 2)
+        );
+        assert!(get_timeline(&conn, student, "teacher")
+            .unwrap()
+            .iter()
+            .all(|entry| entry.incident_id != Some(sensitive)));
+        assert_eq!(
+            delete_incident(&conn, sensitive, "teacher").unwrap_err(),
+            "Only the principal can delete an incident"
+        );
+        let flagged = DisciplineIncident {
+            include_on_certificate: true,
+            ..incident(student, teacher, "staff")
+        };
+        assert!(save_incident(&conn, flagged, "counsellor").is_err());
+        assert!(get_incidents(&conn, "janitor", None, None, None, false).is_err());
+    }
+
+    #[test]
+    fn acknowledgements_and_meetings_build_the_timeline() {
+        let conn = memory_db();
+        let (student, teacher) = setup(&conn);
+        let father = create_guardian(&conn, sample_guardian("Suresh Patil", "Father")).unwrap();
+        let stranger = create_guardian(
+            &conn,
+            Guardian {
+                phone: Some("9811100000".to_string()),
+                ..sample_guardian("Someone Else", "Other")
+            },
+        )
+        .unwrap();
+        link_guardian(&conn, student, father, true).unwrap();
+        let id = save_incident(&conn, incident(student, teacher, "staff"), "teacher").unwrap();
+
+        assert!(acknowledge_incident(&conn, id, stranger, None, None).is_err());
+        assert!(acknowledge_incident(&conn, id, father, Some("2024-09-01".into()), None).is_err());
+        acknowledge_incident(
+            &conn,
+            id,
+            father,
+            Some("2024-09-04".to_string()),
+            Some("We will talk to him".to_string()),
+        )
+        .unwrap();
+        assert!(acknowledge_incident(&conn, id, father, None, None)
+            .unwrap_err()
+            .contains("already acknowledged"));
+        assert!(get_incidents(&conn, "teacher", None, None, None, true)
+            .unwrap()
+            .is_empty());
+
+        record_meeting(
+            &conn,
+            ParentMeeting {
+                id: None,
+                student_id: student,
+                incident_id: Some(id),
+                meeting_date: "2024-09-06".to_string(),
+                guardian_id: Some(father),
+                held_by: Some("Class teacher".to_string()),
+                summary: "Discussed behaviour at lunch.".to_string(),
+                follow_up: Some("Review in a month".to_string()),
+                visibility: None,
+                guardian_name: None,
+            },
+        )
+        .unwrap();
+        let timeline = get_timeline(&conn, student, "teacher").unwrap();
+        let kinds: Vec<&str> = timeline.iter().map(|e| e.entry_type.as_str()).collect();
+        assert_eq!(kinds, vec!["meeting", "acknowledgement", "incident"]);
+        assert_eq!(timeline[1].title, "Acknowledged by Suresh Patil");
+        assert_eq!(timeline[2].title, "Fighting");
+    }
+
+    #[test]
+    fn flagged_incidents_appear_on_the_character_certificate() {
+        let conn = memory_db();
+        let (student, teacher) = setup(&conn);
+        let request = || IssueCertificateRequest {
+            student_id: student,
+            certificate_type: "character".to_string(),
+            issue_date: Some("2025-03-31".to_string()),
+            leaving_date: None,
+            leaving_reason: None,
+            conduct: None,
+            purpose: None,
+            remarks: None,
+            issued_by: None,
+        };
+        let id = save_incident(&conn, incident(student, teacher, "staff"), "teacher").unwrap();
+        let certificate = issue_certificate(&conn, request()).unwrap();
+        assert!(!certificate.body.contains("Incidents on record"));
+
+        let mut flagged = get_incidents(&conn, "principal", Some(student), None, None, false)
+            .unwrap()
+            .remove(0);
+        flagged.include_on_certificate = true;
+        assert_eq!(save_incident(&conn, flagged, "principal").unwrap(), id);
+        let certificate = issue_certificate(&conn, request()).unwrap();
+        assert!(certificate.body.ends_with(
+            "Incidents on record:\n03-09-2024: Fight during the lunch break. \
+             Action taken: Two-day suspension"
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::date::Date;
+use crate::dues::{DueSettlement, StudentDue};
+use crate::repository;
+use crate::repository::dues as repo;
+use crate::service::students::ensure_on_roll;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub const SETTLEMENT_STATUSES: [&str; 2] = ["paid", "waived"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(date) => Ok(Date::parse(&date)?.to_string()),
+        None => repository::today(conn).map_err(|e| e.to_string()),
+    }
+}
+
+/// Raises a charge against a student on the roll on behalf of `source`. A charge whose
+/// reference was already raised by the same source is rejected, so hooks can post without
+/// double billing. Runs in the caller's transaction, if any.
+pub fn post_due(conn: &Connection, due: StudentDue, source: &str) -> Result<i64, String> {
+    let due = StudentDue {
+        reference: non_blank(due.reference),
+        description: due.description.trim().to_string(),
+        amount: (due.amount * 100.0).round() / 100.0,
+        ..due
+    };
+    ensure_on_roll(conn, due.student_id)?;
+    if due.description.is_empty() {
+        return Err("Say what the charge is for".to_string());
+    }
+    if !due.amount.is_finite() || due.amount <= 0.0 {
+        return Err("The amount due must be more than zero".to_string());
+    }
+    if let Some(reference) = due.reference.as_deref() {
+        if repo::reference_taken(conn, source, reference).map_err(|e| e.to_string())? {
+            return Err(format!(
+                "The {} charge {} was already raised",
+                source, reference
+            ));
+        }
+    }
+    let raised_on = date_or_today(conn, due.raised_on.clone())?;
+    repo::insert(conn, &due, source, &raised_on).map_err(|e| e.to_string())
+}
+
+pub fn add_manual_due(conn: &Connection, due: StudentDue) -> Result<i64, String> {
+    post_due(conn, due, "manual")
+}
+
+pub fn get_student_dues(
+    conn: &Connection,
+    student_id: i64,
+    include_settled: bool,
+) -> Result<Vec<StudentDue>, String> {
+    repo::find_for_student(conn, student_id, include_settled).map_err(|e| e.to_string())
+}
+
+pub fn get_open_dues(conn: &Connection, class_id: Option<i64>) -> Result<Vec<StudentDue>, String> {
+    repo::find_open(conn, class_id).map_err(|e| e.to_string())
+}
+
+/// Total open dues per student id.
+pub fn outstanding_totals(conn: &Connection) -> Result<HashMap<i64, f64>, String> {
+    Ok(repo::find_outstanding_totals(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .collect())
+}
+
+pub fn settle_due(conn: &Connection, id: i64, settlement: DueSettlement) -> Result<(), String> {
+    let status = settlement.status.trim().to_lowercase();
+    if !SETTLEMENT_STATUSES.contains(&status.as_str()) {
+        return Err(format!(
+            "Invalid settlement '{}'. Expected one of: {}",
+            status,
+            SETTLEMENT_STATUSES.join(", ")
+        ));
+    }
+    let note = non_blank(settlement.note);
+    if status == "waived" && note.is_none() {
+        return Err("Record why the due is waived".to_string());
+    }
+    let due = repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Due with id {} not found", id))?;
+    let settled_on = date_or_today(conn, settlement.settled_on)?;
+    if Some(settled_on.as_str()) < due.raised_on.as_deref() {
+        return Err("A due cannot be settled before it was raised".to_string());
+    }
+    if repo::settle(conn, id, &status, &settled_on, note.as_deref()).map_err(|e| e.to_string())?
+        == 0
+    {
+        return Err(format!(
+            "This due is already {}",
+            due.status.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication::MessageDraft;
+    use crate::service::communication::{compose_message, get_recipients};
+    use crate::service::students::{save_contact, save_core};
+    use crate::test_support::{create_class, memory_db, sample_student};
+
+    fn due(student_id: i64, reference: Option<&str>, amount: f64) -> StudentDue {
+        StudentDue {
+            id: None,
+            student_id,
+            source: None,
+            reference: reference.map(str::to_string),
+            description: "Late return of 'Wings of Fire'".to_string(),
+            amount,
+            raised_on: Some("2024-08-01".to_string()),
+            status: None,
+            settled_on: None,
+            settled_note: None,
+            student_name: None,
+            class_name: None,
+        }
+    }
+
+    fn settlement(status: &str, note: Option<&str>) -> DueSettlement {
+        DueSettlement {
+            status: status.to_string(),
+            settled_on: Some("2024-08-05".to_string()),
+            note: note.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn dues_are_raised_once_and_settled_once() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-3 A");
+        let student_id =
+            save_core(&conn, sample_student(&class_id.to_string(), "D1").core).unwrap();
+
+        let id = post_due(&conn, due(student_id, Some("loan:7"), 12.5), "library").unwrap();
+        let err = post_due(&conn, due(student_id, Some("loan:7"), 12.5), "library").unwrap_err();
+        assert!(err.contains("already raised"), "{}", err);
+        assert!(post_due(&conn, due(student_id, None, 0.0), "library").is_err());
+        add_manual_due(&conn, due(student_id, None, 100.0)).unwrap();
+        assert!(post_due(&conn, due(student_id + 1, None, 5.0), "manual")
+            .unwrap_err()
+            .contains("not found"));
+
+        let open = get_open_dues(&conn, Some(class_id)).unwrap();
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].class_name.as_deref(), Some("Class-3 A"));
+        assert_eq!(outstanding_totals(&conn).unwrap()[&student_id], 112.5);
+
+        let err = settle_due(&conn, id, settlement("waived", None)).unwrap_err();
+        assert!(err.contains("why"), "{}", err);
+        settle_due(&conn, id, settlement("paid", None)).unwrap();
+        let err = settle_due(&conn, id, settlement("paid", None)).unwrap_err();
+        assert_eq!(err, "This due is already paid");
+        assert_eq!(get_student_dues(&conn, student_id, false).unwrap().len(), 1);
+        assert_eq!(get_student_dues(&conn, student_id, true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn dues_are_not_raised_against_students_off_the_roll() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-3 A");
+        let student_id =
+            save_core(&conn, sample_student(&class_id.to_string(), "D1").core).unwrap();
+        conn.execute(
+            "UPDATE students SET status = 'left' WHERE id = ?1",
+            [student_id],
+        )
+        .unwrap();
+
+        let err = add_manual_due(&conn, due(student_id, None, 100.0)).unwrap_err();
+        assert!(err.contains("not on the rolls"), "{}", err);
+        assert!(get_student_dues(&conn, student_id, true)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn fee_reminders_fill_in_open_dues() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-3 A");
+        let mut owing = 0;
+        for gr_number in ["D1", "D2"] {
+            let student = sample_student(&class_id.to_string(), gr_number);
+            let id = save_core(&conn, student.core).unwrap();
+            save_contact(&conn, id, student.contact).unwrap();
+            owing = id;
+        }
+        post_due(&conn, due(owing, None, 1500.0), "manual").unwrap();
+
+        let message = compose_message(
+            &conn,
+            MessageDraft {
+                kind: None,
+                title: "Dues".to_string(),
+                subject: None,
+                body: Some("Rs. {due_amount} is due for {student_name}".to_string()),
+                template_id: None,
+                channel: "sms".to_string(),
+                audience: "class".to_string(),
+                target_ids: vec![class_id],
+                due_amounts: None,
+                expires_on: None,
+                created_by: None,
+            },
+        )
+        .unwrap();
+        assert_eq!((message.pending, message.skipped), (1, 1));
+        let pending = get_recipients(&conn, message.id, Some("pending".to_string())).unwrap();
+        assert_eq!(pending[0].body, "Rs. 1,500.00 is due for Student D2");
+    }
+}
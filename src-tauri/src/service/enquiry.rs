@@ -0,0 +1,119 @@
+use crate::enquiry::{Enquiry, FollowUp, Note};
+use crate::repository::enquiry as repo;
+use rusqlite::Connection;
+
+pub fn create_enquiry(conn: &Connection, enquiry: Enquiry) -> Result<i64, String> {
+    let status = enquiry.status.clone().unwrap_or_else(|| "new".to_string());
+    repo::insert(conn, &enquiry, &status).map_err(|e| e.to_string())
+}
+
+pub fn get_enquiry(conn: &Connection, id: i64) -> Result<Enquiry, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Enquiry not found".to_string())
+}
+
+pub fn get_all_enquiries(conn: &Connection) -> Result<Vec<Enquiry>, String> {
+    repo::find_all(conn).map_err(|e| e.to_string())
+}
+
+pub fn update_enquiry(conn: &Connection, id: i64, enquiry: Enquiry) -> Result<(), String> {
+    repo::update(conn, id, &enquiry).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn delete_enquiry(conn: &Connection, id: i64) -> Result<(), String> {
+    repo::delete(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn update_enquiry_status(conn: &Connection, id: i64, status: &str) -> Result<(), String> {
+    repo::update_status(conn, id, status).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn add_enquiry_follow_up(conn: &Connection, follow_up: FollowUp) -> Result<i64, String> {
+    repo::insert_follow_up(conn, &follow_up).map_err(|e| e.to_string())
+}
+
+pub fn get_enquiry_follow_ups(conn: &Connection, enquiry_id: i64) -> Result<Vec<FollowUp>, String> {
+    repo::find_follow_ups(conn, enquiry_id).map_err(|e| e.to_string())
+}
+
+pub fn add_enquiry_note(conn: &Connection, note: Note) -> Result<i64, String> {
+    repo::insert_note(conn, &note).map_err(|e| e.to_string())
+}
+
+pub fn get_enquiry_notes(conn: &Connection, enquiry_id: i64) -> Result<Vec<Note>, String> {
+    repo::find_notes(conn, enquiry_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{memory_db, sample_enquiry};
+
+    #[test]
+    fn create_defaults_status_to_new() {
+        let conn = memory_db();
+        let id = create_enquiry(&conn, sample_enquiry("Asha")).unwrap();
+        let stored = get_enquiry(&conn, id).unwrap();
+        assert_eq!(stored.student_name, "Asha");
+        assert_eq!(stored.status.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn update_and_status_change_are_persisted() {
+        let conn = memory_db();
+        let id = create_enquiry(&conn, sample_enquiry("Asha")).unwrap();
+
+        let mut changed = sample_enquiry("Asha Rao");
+        changed.status = Some("contacted".to_string());
+        update_enquiry(&conn, id, changed).unwrap();
+        assert_eq!(get_enquiry(&conn, id).unwrap().student_name, "Asha Rao");
+
+        update_enquiry_status(&conn, id, "admitted").unwrap();
+        assert_eq!(get_enquiry(&conn, id).unwrap().status.as_deref(), Some("admitted"));
+    }
+
+    #[test]
+    fn get_missing_enquiry_is_an_error() {
+        let conn = memory_db();
+        assert!(get_enquiry(&conn, 42).is_err());
+    }
+
+    #[test]
+    fn notes_and_follow_ups_are_scoped_to_enquiry_and_cascade() {
+        let conn = memory_db();
+        let first = create_enquiry(&conn, sample_enquiry("Asha")).unwrap();
+        let second = create_enquiry(&conn, sample_enquiry("Ravi")).unwrap();
+
+        add_enquiry_note(
+            &conn,
+            Note { id: None, enquiry_id: first, notes: "Called parent".to_string(), created_at: None },
+        )
+        .unwrap();
+        add_enquiry_follow_up(
+            &conn,
+            FollowUp {
+                id: None,
+                enquiry_id: first,
+                notes: "Campus visit".to_string(),
+                status: "scheduled".to_string(),
+                created_at: String::new(),
+                follow_up_date: Some("2025-06-01".to_string()),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(get_enquiry_notes(&conn, first).unwrap().len(), 1);
+        assert!(get_enquiry_notes(&conn, second).unwrap().is_empty());
+        let follow_ups = get_enquiry_follow_ups(&conn, first).unwrap();
+        assert_eq!(follow_ups[0].follow_up_date.as_deref(), Some("2025-06-01"));
+
+        delete_enquiry(&conn, first).unwrap();
+        assert_eq!(get_all_enquiries(&conn).unwrap().len(), 1);
+        assert!(get_enquiry_notes(&conn, first).unwrap().is_empty());
+        assert!(get_enquiry_follow_ups(&conn, first).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,499 @@
+use crate::guardian::{Family, Guardian, StudentGuardian};
+use crate::repository::guardian as repo;
+use crate::repository::students as student_repo;
+use rusqlite::Connection;
+
+const RELATION_TYPES: [&str; 6] = ["father", "mother", "guardian", "grandparent", "sibling", "other"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Trims the guardian's fields and checks the name and relation type.
+fn normalize(guardian: Guardian) -> Result<Guardian, String> {
+    let full_name = guardian.full_name.trim().to_string();
+    if full_name.is_empty() {
+        return Err("Guardian name cannot be empty".to_string());
+    }
+
+    let relation_type = guardian.relation_type.trim().to_lowercase();
+    if !RELATION_TYPES.contains(&relation_type.as_str()) {
+        return Err(format!(
+            "Invalid relation type '{}'. Expected one of: {}",
+            guardian.relation_type,
+            RELATION_TYPES.join(", ")
+        ));
+    }
+
+    Ok(Guardian {
+        full_name,
+        relation_type,
+        phone: non_blank(guardian.phone),
+        alternate_phone: non_blank(guardian.alternate_phone),
+        email: non_blank(guardian.email),
+        address: non_blank(guardian.address),
+        occupation: non_blank(guardian.occupation),
+        aadhaar_number: non_blank(guardian.aadhaar_number),
+        pan_number: non_blank(guardian.pan_number),
+        notes: non_blank(guardian.notes),
+        ..guardian
+    })
+}
+
+fn ensure_student_exists(conn: &Connection, student_id: i64) -> Result<(), String> {
+    let exists = student_repo::exists(conn, student_id).map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Student with id {} not found", student_id));
+    }
+    Ok(())
+}
+
+pub fn create_guardian(conn: &Connection, guardian: Guardian) -> Result<i64, String> {
+    let guardian = normalize(guardian)?;
+    repo::insert(conn, &guardian).map_err(|e| e.to_string())
+}
+
+pub fn get_guardian(conn: &Connection, id: i64) -> Result<Guardian, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Guardian with id {} not found", id))
+}
+
+pub fn search_guardians(conn: &Connection, query: &str) -> Result<Vec<Guardian>, String> {
+    repo::search(conn, query.trim()).map_err(|e| e.to_string())
+}
+
+/// Updates the guardian and keeps the inline parent columns of linked students in step.
+pub fn update_guardian(conn: &Connection, id: i64, guardian: Guardian) -> Result<(), String> {
+    let guardian = normalize(guardian)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let updated = repo::update(&tx, id, &guardian).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Guardian with id {} not found", id));
+    }
+    repo::sync_inline_parent_columns(&tx, id, &guardian).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Deletes the guardian; students who relied on it as primary contact get another one promoted.
+pub fn delete_guardian(conn: &Connection, id: i64) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let student_ids = repo::find_student_ids(&tx, id).map_err(|e| e.to_string())?;
+    repo::delete(&tx, id).map_err(|e| e.to_string())?;
+    for student_id in student_ids {
+        repo::ensure_primary(&tx, student_id).map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Links a guardian to a student. The first guardian linked always becomes the primary contact.
+pub fn link_guardian(
+    conn: &Connection,
+    student_id: i64,
+    guardian_id: i64,
+    is_primary_contact: bool,
+) -> Result<(), String> {
+    ensure_student_exists(conn, student_id)?;
+    get_guardian(conn, guardian_id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if is_primary_contact {
+        repo::clear_primary(&tx, student_id).map_err(|e| e.to_string())?;
+    }
+    repo::link(&tx, student_id, guardian_id, is_primary_contact).map_err(|e| e.to_string())?;
+    repo::ensure_primary(&tx, student_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn unlink_guardian(conn: &Connection, student_id: i64, guardian_id: i64) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let removed = repo::unlink(&tx, student_id, guardian_id).map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err("Guardian is not linked to this student".to_string());
+    }
+    repo::ensure_primary(&tx, student_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn set_primary_guardian(
+    conn: &Connection,
+    student_id: i64,
+    guardian_id: i64,
+) -> Result<(), String> {
+    let linked = repo::is_linked(conn, student_id, guardian_id).map_err(|e| e.to_string())?;
+    if !linked {
+        return Err("Guardian is not linked to this student".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::clear_primary(&tx, student_id).map_err(|e| e.to_string())?;
+    repo::link(&tx, student_id, guardian_id, true).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_student_guardians(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<StudentGuardian>, String> {
+    ensure_student_exists(conn, student_id)?;
+    repo::find_for_student(conn, student_id).map_err(|e| e.to_string())
+}
+
+/// Guardians of the student plus every other student sharing one of them.
+pub fn get_student_family(conn: &Connection, student_id: i64) -> Result<Family, String> {
+    ensure_student_exists(conn, student_id)?;
+    let guardians = repo::find_for_student(conn, student_id).map_err(|e| e.to_string())?;
+    let siblings = repo::find_siblings(conn, student_id).map_err(|e| e.to_string())?;
+    Ok(Family {
+        student_id,
+        guardians,
+        siblings,
+    })
+}
+
+/// Reuses a guardian already on record with the same relation, name and phone, otherwise creates one.
+/// Without a phone number there is nothing reliable to match on, so a new record is always created.
+fn find_or_create_parent(conn: &Connection, guardian: Guardian) -> rusqlite::Result<i64> {
+    if let Some(phone) = guardian.phone.as_deref() {
+        if let Some(id) =
+            repo::find_matching(conn, &guardian.relation_type, &guardian.full_name, Some(phone))?
+        {
+            return Ok(id);
+        }
+    }
+    repo::insert(conn, &guardian)
+}
+
+fn inline_parent(
+    relation_type: &str,
+    full_name: String,
+    occupation: Option<String>,
+    annual_income: Option<f64>,
+    parents: &repo::InlineParents,
+) -> Guardian {
+    Guardian {
+        id: None,
+        full_name,
+        relation_type: relation_type.to_string(),
+        phone: non_blank(parents.mobile_number.clone()),
+        alternate_phone: None,
+        email: None,
+        address: non_blank(parents.address.clone()),
+        occupation: non_blank(occupation),
+        annual_income,
+        aadhaar_number: None,
+        pan_number: None,
+        notes: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+/// Creates guardian records from the father/mother columns of a student that has none linked yet.
+/// Parents already on record for a sibling (same name and phone) are linked instead of duplicated.
+/// A student with guardians linked has them brought up to date instead; see [`sync_inline_parents`].
+pub fn link_inline_parents(conn: &Connection, student_id: i64) -> Result<(), String> {
+    let linked = repo::count_links(conn, student_id).map_err(|e| e.to_string())?;
+    let Some(parents) = repo::find_inline_parents(conn, student_id).map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    if linked > 0 {
+        sync_parents(conn, &parents)
+    } else {
+        link_parents(conn, &parents)
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Copies edits to the father/mother columns onto the student's linked father and mother, and
+/// from there onto their other children. A parent named on the form but not linked yet is
+/// added. Students without any guardian linked are left alone until their contact is saved.
+pub fn sync_inline_parents(conn: &Connection, student_id: i64) -> Result<(), String> {
+    let linked = repo::count_links(conn, student_id).map_err(|e| e.to_string())?;
+    if linked == 0 {
+        return Ok(());
+    }
+    let Some(parents) = repo::find_inline_parents(conn, student_id).map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    sync_parents(conn, &parents).map_err(|e| e.to_string())
+}
+
+fn sync_parents(conn: &Connection, parents: &repo::InlineParents) -> rusqlite::Result<()> {
+    let linked = repo::find_for_student(conn, parents.student_id)?;
+    let inline = [
+        ("father", &parents.father_name, &parents.father_occupation, parents.annual_income),
+        ("mother", &parents.mother_name, &parents.mother_occupation, None),
+    ];
+    for (relation_type, name, occupation, annual_income) in inline {
+        // A blank name on the form does not unlink or erase a guardian kept on record.
+        let Some(name) = non_blank(name.clone()) else {
+            continue;
+        };
+        let current = linked
+            .iter()
+            .find(|g| g.guardian.relation_type == relation_type)
+            .map(|g| &g.guardian);
+        match current {
+            Some(current) => {
+                let Some(guardian_id) = current.id else {
+                    continue;
+                };
+                let guardian = Guardian {
+                    full_name: name,
+                    occupation: non_blank(occupation.clone()),
+                    annual_income: annual_income.or(current.annual_income),
+                    phone: non_blank(parents.mobile_number.clone()).or(current.phone.clone()),
+                    address: non_blank(parents.address.clone()).or(current.address.clone()),
+                    ..current.clone()
+                };
+                repo::update(conn, guardian_id, &guardian)?;
+                repo::sync_inline_parent_columns(conn, guardian_id, &guardian)?;
+            }
+            None => {
+                let guardian =
+                    inline_parent(relation_type, name, occupation.clone(), annual_income, parents);
+                let guardian_id = find_or_create_parent(conn, guardian)?;
+                repo::link(conn, parents.student_id, guardian_id, false)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn link_parents(conn: &Connection, parents: &repo::InlineParents) -> rusqlite::Result<()> {
+    let mut candidates = Vec::new();
+    if let Some(name) = non_blank(parents.father_name.clone()) {
+        let occupation = parents.father_occupation.clone();
+        candidates.push(inline_parent("father", name, occupation, parents.annual_income, parents));
+    }
+    if let Some(name) = non_blank(parents.mother_name.clone()) {
+        let occupation = parents.mother_occupation.clone();
+        candidates.push(inline_parent("mother", name, occupation, None, parents));
+    }
+
+    for (index, mut guardian) in candidates.into_iter().enumerate() {
+        // The father (or the mother, when no father is recorded) becomes the primary contact
+        // and carries the free-text guardian contact notes.
+        let is_primary = index == 0;
+        if is_primary {
+            guardian.notes = non_blank(parents.guardian_contact_info.clone());
+        }
+        let guardian_id = find_or_create_parent(conn, guardian)?;
+        repo::link(conn, parents.student_id, guardian_id, is_primary)?;
+    }
+    Ok(())
+}
+
+/// One-time backfill of guardians from the inline parent columns of existing students.
+pub fn migrate_inline_guardians(conn: &Connection) -> Result<(), String> {
+    if repo::inline_parents_migrated(conn).map_err(|e| e.to_string())? {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let students = repo::find_unlinked_inline_parents(&tx).map_err(|e| e.to_string())?;
+    for parents in &students {
+        link_parents(&tx, parents).map_err(|e| e.to_string())?;
+    }
+    repo::mark_inline_parents_migrated(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::students::{bulk_insert, save_contact, save_core};
+    use crate::test_support::{create_class, memory_db, sample_guardian, sample_student};
+
+    #[test]
+    fn create_validates_name_and_relation() {
+        let conn = memory_db();
+        let mut guardian = sample_guardian("  ", "father");
+        assert!(create_guardian(&conn, guardian.clone()).is_err());
+
+        guardian.full_name = "Rajesh Sharma".to_string();
+        guardian.relation_type = "uncle".to_string();
+        assert!(create_guardian(&conn, guardian.clone()).unwrap_err().contains("uncle"));
+
+        guardian.relation_type = " Father ".to_string();
+        let id = create_guardian(&conn, guardian).unwrap();
+        assert_eq!(get_guardian(&conn, id).unwrap().relation_type, "father");
+    }
+
+    #[test]
+    fn only_one_primary_contact_per_student() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student_id = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        let father = create_guardian(&conn, sample_guardian("Rajesh", "father")).unwrap();
+        let mother = create_guardian(&conn, sample_guardian("Sunita", "mother")).unwrap();
+
+        // The first link is promoted even when not asked for.
+        link_guardian(&conn, student_id, father, false).unwrap();
+        link_guardian(&conn, student_id, mother, false).unwrap();
+        let guardians = get_student_guardians(&conn, student_id).unwrap();
+        assert_eq!(guardians[0].guardian.id, Some(father));
+        assert!(guardians[0].is_primary_contact);
+        assert!(!guardians[1].is_primary_contact);
+
+        set_primary_guardian(&conn, student_id, mother).unwrap();
+        let guardians = get_student_guardians(&conn, student_id).unwrap();
+        assert_eq!(guardians[0].guardian.id, Some(mother));
+        assert_eq!(guardians.iter().filter(|g| g.is_primary_contact).count(), 1);
+
+        unlink_guardian(&conn, student_id, mother).unwrap();
+        let guardians = get_student_guardians(&conn, student_id).unwrap();
+        assert_eq!(guardians.len(), 1);
+        assert!(guardians[0].is_primary_contact);
+
+        assert!(set_primary_guardian(&conn, student_id, mother).is_err());
+    }
+
+    #[test]
+    fn family_lists_siblings_sharing_a_guardian() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let elder = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        let mut younger_core = sample_student(&class_id, "GR-2").core;
+        younger_core.dob = Some("2018-01-05".to_string());
+        let younger = save_core(&conn, younger_core).unwrap();
+        let other = save_core(&conn, sample_student(&class_id, "GR-3").core).unwrap();
+
+        let mother = create_guardian(&conn, sample_guardian("Sunita", "mother")).unwrap();
+        link_guardian(&conn, elder, mother, true).unwrap();
+        link_guardian(&conn, younger, mother, true).unwrap();
+
+        let family = get_student_family(&conn, younger).unwrap();
+        assert_eq!(family.guardians.len(), 1);
+        assert_eq!(family.siblings.len(), 1);
+        assert_eq!(family.siblings[0].student_id, elder);
+        assert_eq!(family.siblings[0].class_name.as_deref(), Some("Class-1 A"));
+        assert!(get_student_family(&conn, other).unwrap().siblings.is_empty());
+        assert!(get_student_family(&conn, 999).is_err());
+    }
+
+    #[test]
+    fn update_syncs_inline_parent_columns() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student_id = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        let father = create_guardian(&conn, sample_guardian("Rajesh", "father")).unwrap();
+        link_guardian(&conn, student_id, father, true).unwrap();
+
+        let mut changed = sample_guardian("Rajesh Sharma", "father");
+        changed.occupation = Some("Engineer".to_string());
+        changed.annual_income = Some(900000.0);
+        update_guardian(&conn, father, changed).unwrap();
+
+        let student = student_repo::find_by_id(&conn, student_id).unwrap().unwrap();
+        assert_eq!(student.core.father_name, "Rajesh Sharma");
+        assert_eq!(student.core.father_occupation.as_deref(), Some("Engineer"));
+        assert_eq!(student.core.annual_income, Some(900000.0));
+    }
+
+    #[test]
+    fn deleting_primary_promotes_remaining_guardian() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student_id = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        let father = create_guardian(&conn, sample_guardian("Rajesh", "father")).unwrap();
+        let mother = create_guardian(&conn, sample_guardian("Sunita", "mother")).unwrap();
+        link_guardian(&conn, student_id, father, true).unwrap();
+        link_guardian(&conn, student_id, mother, false).unwrap();
+
+        delete_guardian(&conn, father).unwrap();
+        let guardians = get_student_guardians(&conn, student_id).unwrap();
+        assert_eq!(guardians.len(), 1);
+        assert_eq!(guardians[0].guardian.id, Some(mother));
+        assert!(guardians[0].is_primary_contact);
+    }
+
+    #[test]
+    fn inline_parents_are_shared_between_siblings() {
+        let mut conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let mut first = sample_student(&class_id, "GR-1");
+        first.core.father_occupation = Some("Farmer".to_string());
+        first.contact.guardian_contact_info = Some("Call after 6pm".to_string());
+        let ids = bulk_insert(&mut conn, vec![first, sample_student(&class_id, "GR-2")]).unwrap();
+
+        let family = get_student_family(&conn, ids[1]).unwrap();
+        assert_eq!(family.siblings.len(), 1);
+        assert_eq!(family.guardians.len(), 2);
+        let father = &family.guardians[0];
+        assert!(father.is_primary_contact);
+        assert_eq!(father.guardian.relation_type, "father");
+        assert_eq!(father.guardian.occupation.as_deref(), Some("Farmer"));
+        assert_eq!(father.guardian.notes.as_deref(), Some("Call after 6pm"));
+    }
+
+    #[test]
+    fn admission_wizard_links_parents_once_contact_is_saved() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student = sample_student(&class_id, "GR-1");
+        let id = save_core(&conn, student.core).unwrap();
+        assert!(get_student_guardians(&conn, id).unwrap().is_empty());
+
+        save_contact(&conn, id, student.contact).unwrap();
+        save_contact(&conn, id, sample_student(&class_id, "GR-1").contact).unwrap();
+        let guardians = get_student_guardians(&conn, id).unwrap();
+        assert_eq!(guardians.len(), 2);
+        assert_eq!(guardians[0].guardian.phone.as_deref(), Some("9876543210"));
+    }
+
+    #[test]
+    fn later_edits_to_inline_parents_reach_linked_guardians() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let mut ids = Vec::new();
+        for gr_number in ["GR-1", "GR-2"] {
+            let student = sample_student(&class_id, gr_number);
+            let id = save_core(&conn, student.core).unwrap();
+            save_contact(&conn, id, student.contact).unwrap();
+            ids.push(id);
+        }
+
+        let mut contact = sample_student(&class_id, "GR-1").contact;
+        contact.mobile_number = Some("9123456780".to_string());
+        save_contact(&conn, ids[0], contact).unwrap();
+        let mut core = student_repo::find_by_id(&conn, ids[0]).unwrap().unwrap().core;
+        core.father_occupation = Some("Teacher".to_string());
+        core.mother_name = "Sunita Sharma".to_string();
+        save_core(&conn, core).unwrap();
+
+        // The sibling shares the guardians, so the edits show there too.
+        let guardians = get_student_guardians(&conn, ids[1]).unwrap();
+        assert_eq!(guardians.len(), 2);
+        let father = &guardians[0].guardian;
+        assert_eq!(father.phone.as_deref(), Some("9123456780"));
+        assert_eq!(father.occupation.as_deref(), Some("Teacher"));
+        assert_eq!(guardians[1].guardian.full_name, "Sunita Sharma");
+        let sibling = student_repo::find_by_id(&conn, ids[1]).unwrap().unwrap();
+        assert_eq!(sibling.core.father_occupation.as_deref(), Some("Teacher"));
+        assert_eq!(sibling.core.mother_name, "Sunita Sharma");
+    }
+
+    #[test]
+    fn migration_backfills_existing_students_once() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student_id = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        conn.execute("DROP TABLE guardians_migrated", []).unwrap();
+
+        migrate_inline_guardians(&conn).unwrap();
+        assert_eq!(get_student_guardians(&conn, student_id).unwrap().len(), 2);
+
+        // Later runs leave students without guardians alone.
+        let late = save_core(&conn, sample_student(&class_id, "GR-2").core).unwrap();
+        migrate_inline_guardians(&conn).unwrap();
+        assert!(get_student_guardians(&conn, late).unwrap().is_empty());
+    }
+}
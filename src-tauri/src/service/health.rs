@@ -0,0 +1,870 @@
+use crate::date::{days_in_month, month_name, parse_time, Date};
+use crate::health::{
+    HealthCheckup, InfirmaryVisit, MedicalAlert, StudentVaccination, VaccinationReminder,
+    VaccineDose,
+};
+use crate::pdf::{wrap, Align, Document, Font, Page, A4_HEIGHT, A4_WIDTH};
+use crate::repository;
+use crate::repository::class as class_repo;
+use crate::repository::health as repo;
+use crate::repository::school as school_repo;
+use crate::repository::students as student_repo;
+use crate::school::School;
+use crate::service::students::ensure_on_roll;
+use rusqlite::Connection;
+
+pub const OUTCOMES: [&str; 4] = ["returned_to_class", "rested", "sent_home", "referred"];
+
+/// CDC 2000 BMI-for-age reference at each whole year from 2 to 18, as the 5th, 50th, 85th
+/// and 95th percentiles. Ages between birthdays are interpolated by month.
+const BOYS_BMI: [[f64; 4]; 17] = [
+    [14.7, 16.5, 18.2, 19.3],
+    [14.3, 16.0, 17.4, 18.3],
+    [14.0, 15.6, 16.9, 17.8],
+    [13.8, 15.4, 16.8, 18.0],
+    [13.7, 15.4, 17.0, 18.4],
+    [13.7, 15.5, 17.4, 19.1],
+    [13.8, 15.8, 17.9, 20.0],
+    [14.0, 16.1, 18.6, 21.0],
+    [14.2, 16.6, 19.4, 22.1],
+    [14.6, 17.2, 20.2, 23.2],
+    [15.0, 17.8, 21.0, 24.2],
+    [15.5, 18.5, 21.8, 25.1],
+    [16.0, 19.2, 22.6, 26.0],
+    [16.6, 19.9, 23.4, 26.8],
+    [17.1, 20.5, 24.2, 27.5],
+    [17.6, 21.1, 24.9, 28.2],
+    [18.2, 21.7, 25.6, 28.9],
+];
+const GIRLS_BMI: [[f64; 4]; 17] = [
+    [14.4, 16.4, 18.0, 19.1],
+    [14.0, 15.8, 17.2, 18.3],
+    [13.7, 15.4, 16.8, 18.0],
+    [13.5, 15.2, 16.8, 18.3],
+    [13.4, 15.2, 17.1, 18.8],
+    [13.4, 15.4, 17.6, 19.7],
+    [13.6, 15.8, 18.3, 20.7],
+    [13.8, 16.3, 19.1, 21.8],
+    [14.0, 16.9, 20.0, 22.9],
+    [14.4, 17.5, 20.9, 24.1],
+    [14.8, 18.1, 21.7, 25.2],
+    [15.3, 18.7, 22.6, 26.3],
+    [15.8, 19.4, 23.3, 27.2],
+    [16.3, 19.9, 24.0, 28.1],
+    [16.8, 20.4, 24.7, 28.9],
+    [17.2, 20.9, 25.2, 29.6],
+    [17.5, 21.3, 25.7, 30.3],
+];
+/// Ages the reference covers, in months: from the second birthday to the nineteenth.
+const REFERENCE_MONTHS: std::ops::Range<u32> = 24..228;
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, allowed: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<Date, String> {
+    match non_blank(value) {
+        Some(date) => Date::parse(&date),
+        None => today(conn),
+    }
+}
+
+fn time_or_now(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(time) => {
+            parse_time(&time)?;
+            Ok(time.chars().take(5).collect())
+        }
+        None => repository::now_time(conn).map_err(|e| e.to_string()),
+    }
+}
+
+/// Date of birth and gender of a student who must exist.
+fn student_profile(conn: &Connection, student_id: i64) -> Result<(Option<Date>, String), String> {
+    let student = student_repo::find_by_id(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+    let dob = non_blank(student.core.dob)
+        .map(|dob| Date::parse(&dob))
+        .transpose()?;
+    Ok((dob, student.core.gender))
+}
+
+/// Refuses dates after today or before the student was born.
+fn ensure_plausible(
+    conn: &Connection,
+    date: Date,
+    dob: Option<Date>,
+    what: &str,
+) -> Result<(), String> {
+    if date > today(conn)? {
+        return Err(format!("The {} cannot be in the future", what));
+    }
+    if dob.is_some_and(|dob| date < dob) {
+        return Err(format!(
+            "The {} is before the student's date of birth",
+            what
+        ));
+    }
+    Ok(())
+}
+
+fn in_range(
+    value: Option<f64>,
+    range: std::ops::RangeInclusive<f64>,
+    what: &str,
+) -> Result<(), String> {
+    match value {
+        Some(value) if !range.contains(&value) => Err(format!(
+            "{} of {} is outside {}-{}",
+            what,
+            value,
+            range.start(),
+            range.end()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reference percentiles for a child of `gender` aged `months`, when the reference covers it.
+fn reference_for(gender: &str, months: u32) -> Option<[f64; 4]> {
+    let table = match gender.trim().to_lowercase().chars().next() {
+        Some('m') => &BOYS_BMI,
+        Some('f') => &GIRLS_BMI,
+        _ => return None,
+    };
+    if !REFERENCE_MONTHS.contains(&months) {
+        return None;
+    }
+    let index = (months / 12 - 2) as usize;
+    let lower = table[index];
+    let upper = table[(index + 1).min(table.len() - 1)];
+    let fraction = (months % 12) as f64 / 12.0;
+    let mut reference = [0.0; 4];
+    for (i, value) in reference.iter_mut().enumerate() {
+        *value = lower[i] + (upper[i] - lower[i]) * fraction;
+    }
+    Some(reference)
+}
+
+/// Fills in the age, BMI and where the BMI falls against the reference percentiles.
+fn assess(mut checkup: HealthCheckup, dob: Option<Date>, gender: &str) -> HealthCheckup {
+    let date = checkup
+        .checkup_date
+        .as_deref()
+        .and_then(|d| Date::parse(d).ok());
+    checkup.age_months = match (dob, date) {
+        (Some(dob), Some(date)) => u32::try_from(dob.months_until(date)).ok(),
+        _ => None,
+    };
+    checkup.bmi = match (checkup.weight_kg, checkup.height_cm) {
+        (Some(weight), Some(height)) if height > 0.0 => {
+            let metres = height / 100.0;
+            Some((weight / (metres * metres) * 10.0).round() / 10.0)
+        }
+        _ => None,
+    };
+    let reference = checkup
+        .age_months
+        .and_then(|months| reference_for(gender, months));
+    if let (Some(bmi), Some([p5, p50, p85, p95])) = (checkup.bmi, reference) {
+        let (percentile, category) = if bmi < p5 {
+            ("below 5th", "underweight")
+        } else if bmi < p50 {
+            ("5th-50th", "healthy")
+        } else if bmi < p85 {
+            ("50th-85th", "healthy")
+        } else if bmi < p95 {
+            ("85th-95th", "overweight")
+        } else {
+            ("95th and above", "obese")
+        };
+        checkup.bmi_percentile = Some(percentile.to_string());
+        checkup.bmi_category = Some(category.to_string());
+    }
+    checkup
+}
+
+pub fn save_checkup(conn: &Connection, checkup: HealthCheckup) -> Result<i64, String> {
+    let (dob, _) = student_profile(conn, checkup.student_id)?;
+    let date = date_or_today(conn, checkup.checkup_date)?;
+    ensure_plausible(conn, date, dob, "checkup date")?;
+    in_range(checkup.weight_kg, 1.0..=200.0, "Weight (kg)")?;
+    in_range(checkup.height_cm, 30.0..=250.0, "Height (cm)")?;
+    let checkup = HealthCheckup {
+        checkup_date: Some(date.to_string()),
+        hb_range: non_blank(checkup.hb_range),
+        vision: non_blank(checkup.vision),
+        dental: non_blank(checkup.dental),
+        remarks: non_blank(checkup.remarks),
+        checked_by: non_blank(checkup.checked_by),
+        ..checkup
+    };
+    if checkup.weight_kg.is_none()
+        && checkup.height_cm.is_none()
+        && checkup.hb_range.is_none()
+        && checkup.vision.is_none()
+        && checkup.dental.is_none()
+        && checkup.remarks.is_none()
+    {
+        return Err("Record at least one finding".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = match checkup.id {
+        Some(id) => {
+            let existing = repo::find_checkup(&tx, id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Checkup with id {} not found", id))?;
+            if existing.student_id != checkup.student_id {
+                return Err("A checkup cannot be moved to another student".to_string());
+            }
+            repo::update_checkup(&tx, id, &checkup).map_err(|e| e.to_string())?;
+            id
+        }
+        None => repo::insert_checkup(&tx, &checkup).map_err(|e| e.to_string())?,
+    };
+    repo::sync_latest_measurements(&tx, checkup.student_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub fn delete_checkup(conn: &Connection, id: i64) -> Result<(), String> {
+    let checkup = repo::find_checkup(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Checkup with id {} not found", id))?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::delete_checkup(&tx, id).map_err(|e| e.to_string())?;
+    repo::sync_latest_measurements(&tx, checkup.student_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_growth_history(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<HealthCheckup>, String> {
+    let (dob, gender) = student_profile(conn, student_id)?;
+    let checkups = repo::find_checkups(conn, student_id).map_err(|e| e.to_string())?;
+    Ok(checkups
+        .into_iter()
+        .map(|checkup| assess(checkup, dob, &gender))
+        .collect())
+}
+
+pub fn get_vaccine_schedule(conn: &Connection) -> Result<Vec<VaccineDose>, String> {
+    repo::find_schedule(conn, false).map_err(|e| e.to_string())
+}
+
+pub fn save_schedule_dose(conn: &Connection, dose: VaccineDose) -> Result<i64, String> {
+    let dose = VaccineDose {
+        vaccine: dose.vaccine.trim().to_string(),
+        ..dose
+    };
+    if dose.vaccine.is_empty() {
+        return Err("Name the vaccine".to_string());
+    }
+    if dose.dose_number == 0 {
+        return Err("Dose numbers start at 1".to_string());
+    }
+    if dose.due_age_months >= REFERENCE_MONTHS.end {
+        return Err("Only doses due before the age of 19 can be scheduled".to_string());
+    }
+    let clash = repo::find_schedule_dose_id(conn, &dose.vaccine, dose.dose_number)
+        .map_err(|e| e.to_string())?;
+    if clash.is_some() && clash != dose.id {
+        return Err(format!(
+            "{} dose {} is already on the schedule",
+            dose.vaccine, dose.dose_number
+        ));
+    }
+    match dose.id {
+        Some(id) => {
+            if repo::update_schedule_dose(conn, id, &dose).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Scheduled dose with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_schedule_dose(conn, &dose).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn record_vaccination(
+    conn: &Connection,
+    vaccination: StudentVaccination,
+) -> Result<i64, String> {
+    let (dob, _) = student_profile(conn, vaccination.student_id)?;
+    let given_on = date_or_today(conn, vaccination.given_on)?;
+    ensure_plausible(conn, given_on, dob, "vaccination date")?;
+    let vaccination = StudentVaccination {
+        id: None,
+        vaccine: vaccination.vaccine.trim().to_string(),
+        given_on: Some(given_on.to_string()),
+        batch_number: non_blank(vaccination.batch_number),
+        given_at: non_blank(vaccination.given_at),
+        remarks: non_blank(vaccination.remarks),
+        due_date: None,
+        status: None,
+        ..vaccination
+    };
+    if vaccination.vaccine.is_empty() {
+        return Err("Name the vaccine".to_string());
+    }
+    if vaccination.dose_number == 0 {
+        return Err("Dose numbers start at 1".to_string());
+    }
+    if repo::vaccination_recorded(
+        conn,
+        vaccination.student_id,
+        &vaccination.vaccine,
+        vaccination.dose_number,
+    )
+    .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "{} dose {} is already recorded for this student",
+            vaccination.vaccine, vaccination.dose_number
+        ));
+    }
+    repo::insert_vaccination(conn, &vaccination).map_err(|e| e.to_string())
+}
+
+pub fn delete_vaccination(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::delete_vaccination(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Vaccination with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Reminder window used on the vaccination card for doses that are not yet overdue.
+const DUE_SOON_DAYS: i64 = 30;
+
+pub fn get_student_vaccinations(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<StudentVaccination>, String> {
+    let (dob, _) = student_profile(conn, student_id)?;
+    let today = today(conn)?;
+    let mut given = repo::find_vaccinations(conn, student_id).map_err(|e| e.to_string())?;
+    let mut card = Vec::new();
+    for dose in repo::find_schedule(conn, true).map_err(|e| e.to_string())? {
+        let due = dob.map(|dob| dob.add_months(dose.due_age_months));
+        let position = given.iter().position(|v| {
+            v.vaccine.eq_ignore_ascii_case(&dose.vaccine) && v.dose_number == dose.dose_number
+        });
+        let entry = match position {
+            Some(position) => StudentVaccination {
+                status: Some("given".to_string()),
+                ..given.remove(position)
+            },
+            None => {
+                let status = match due.map(|due| today.days_until(due)) {
+                    Some(days) if days < 0 => "overdue",
+                    Some(days) if days <= DUE_SOON_DAYS => "due",
+                    _ => "upcoming",
+                };
+                StudentVaccination {
+                    id: None,
+                    student_id,
+                    vaccine: dose.vaccine,
+                    dose_number: dose.dose_number,
+                    given_on: None,
+                    batch_number: None,
+                    given_at: None,
+                    remarks: None,
+                    due_date: None,
+                    status: Some(status.to_string()),
+                }
+            }
+        };
+        card.push(StudentVaccination {
+            due_date: due.map(|due| due.to_string()),
+            ..entry
+        });
+    }
+    card.extend(given.into_iter().map(|v| StudentVaccination {
+        status: Some("given".to_string()),
+        ..v
+    }));
+    Ok(card)
+}
+
+/// Scheduled doses that students on the roll are overdue for, or that fall due within
+/// `within_days` of `as_of`, most overdue first.
+pub fn get_reminders(
+    conn: &Connection,
+    as_of: Option<String>,
+    within_days: Option<u32>,
+    class_id: Option<i64>,
+) -> Result<Vec<VaccinationReminder>, String> {
+    let as_of = date_or_today(conn, as_of)?;
+    let within_days = within_days.unwrap_or(DUE_SOON_DAYS as u32) as i64;
+    let mut reminders = Vec::new();
+    for pending in repo::find_pending_doses(conn, class_id).map_err(|e| e.to_string())? {
+        // A malformed date of birth on an old record should not hide everyone else's doses.
+        let Ok(dob) = Date::parse(&pending.dob) else {
+            continue;
+        };
+        let due = dob.add_months(pending.due_age_months);
+        let days_overdue = due.days_until(as_of);
+        if days_overdue < -within_days {
+            continue;
+        }
+        reminders.push(VaccinationReminder {
+            student_id: pending.student_id,
+            student_name: pending.student_name,
+            class_name: pending.class_name,
+            mobile_number: pending.mobile_number,
+            vaccine: pending.vaccine,
+            dose_number: pending.dose_number,
+            due_date: due.to_string(),
+            days_overdue,
+        });
+    }
+    reminders.sort_by(|a, b| {
+        a.due_date
+            .cmp(&b.due_date)
+            .then_with(|| a.student_name.cmp(&b.student_name))
+    });
+    Ok(reminders)
+}
+
+pub fn record_infirmary_visit(conn: &Connection, visit: InfirmaryVisit) -> Result<i64, String> {
+    ensure_on_roll(conn, visit.student_id)?;
+    let visit_date = date_or_today(conn, visit.visit_date)?;
+    if visit_date > today(conn)? {
+        return Err("A visit cannot be dated in the future".to_string());
+    }
+    in_range(visit.temperature_c, 30.0..=45.0, "Temperature (°C)")?;
+    let visit = InfirmaryVisit {
+        visit_date: Some(visit_date.to_string()),
+        visit_time: Some(time_or_now(conn, visit.visit_time)?),
+        complaint: visit.complaint.trim().to_string(),
+        treatment: non_blank(visit.treatment),
+        outcome: Some(match non_blank(visit.outcome) {
+            Some(outcome) => one_of(&outcome, &OUTCOMES, "outcome")?,
+            None => OUTCOMES[0].to_string(),
+        }),
+        attended_by: non_blank(visit.attended_by),
+        ..visit
+    };
+    if visit.complaint.is_empty() {
+        return Err("Record what the student came in with".to_string());
+    }
+    repo::insert_visit(conn, &visit).map_err(|e| e.to_string())
+}
+
+pub fn get_infirmary_visits(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<InfirmaryVisit>, String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    repo::find_visits(conn, student_id, &from, &to).map_err(|e| e.to_string())
+}
+
+pub fn get_medical_alerts(
+    conn: &Connection,
+    class_id: Option<i64>,
+) -> Result<Vec<MedicalAlert>, String> {
+    repo::find_medical_alerts(conn, class_id).map_err(|e| e.to_string())
+}
+
+fn file_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+const REGISTER_MARGIN: f32 = 30.0;
+const REGISTER_ROLL_WIDTH: f32 = 30.0;
+const REGISTER_NAME_WIDTH: f32 = 150.0;
+const REGISTER_TOTAL_WIDTH: f32 = 36.0;
+const WEEKDAY_LETTERS: [&str; 7] = ["M", "T", "W", "T", "F", "S", "S"];
+
+/// Width of one day's column when the month has `days` days.
+fn day_width(page: &Page, days: usize) -> f32 {
+    (page.width()
+        - 2.0 * REGISTER_MARGIN
+        - REGISTER_ROLL_WIDTH
+        - REGISTER_NAME_WIDTH
+        - REGISTER_TOTAL_WIDTH)
+        / days as f32
+}
+
+/// x of the left edge of every column: roll, name, each day, total, and the right edge.
+fn register_columns(page: &Page, days: usize) -> Vec<f32> {
+    let mut edges = vec![REGISTER_MARGIN, REGISTER_MARGIN + REGISTER_ROLL_WIDTH];
+    let first_day = REGISTER_MARGIN + REGISTER_ROLL_WIDTH + REGISTER_NAME_WIDTH;
+    edges.extend((0..=days).map(|day| first_day + day as f32 * day_width(page, days)));
+    edges.push(page.width() - REGISTER_MARGIN);
+    edges
+}
+
+fn draw_register_header(
+    page: &mut Page,
+    school: Option<&School>,
+    title: &str,
+    dates: &[Date],
+) -> f32 {
+    let center = page.width() / 2.0;
+    let mut y = page.height() - 40.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            14.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 18.0;
+    }
+    page.text_aligned(center, y, 11.0, Font::Bold, Align::Center, title);
+    y -= 22.0;
+    let edges = register_columns(page, dates.len());
+    page.text(edges[0] + 2.0, y, 8.0, Font::Bold, "Roll");
+    page.text(edges[1] + 2.0, y, 8.0, Font::Bold, "Name");
+    for (index, date) in dates.iter().enumerate() {
+        let middle = (edges[index + 2] + edges[index + 3]) / 2.0;
+        let day = date.day.to_string();
+        page.text_aligned(middle, y + 4.0, 7.0, Font::Bold, Align::Center, &day);
+        let weekday = WEEKDAY_LETTERS[date.weekday() as usize];
+        page.text_aligned(middle, y - 4.0, 6.0, Font::Regular, Align::Center, weekday);
+    }
+    let last = edges.len() - 2;
+    page.text(edges[last] + 2.0, y, 8.0, Font::Bold, "Total");
+    y -= 8.0;
+    page.line(edges[0], y, edges[edges.len() - 1], y, 0.8);
+    y - 11.0
+}
+
+/// File name and PDF of a blank monthly attendance register for a class: one row per
+/// student on the roll, in roll order, and a box per day. Students with allergies or
+/// chronic conditions carry them under their row, so whoever takes the register sees them.
+pub fn render_attendance_register_pdf(
+    conn: &Connection,
+    class_id: i64,
+    year: i32,
+    month: u32,
+) -> Result<(String, Vec<u8>), String> {
+    let first = Date::new(year, month, 1)?;
+    let class = class_repo::find_by_id(conn, class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Class with id {} not found", class_id))?;
+    let students = repo::find_register_students(conn, class_id).map_err(|e| e.to_string())?;
+    if students.is_empty() {
+        return Err(format!("{} has no students on the roll", class.class_name));
+    }
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let dates: Vec<Date> = (0..days_in_month(year, month))
+        .map(|offset| first.add_days(offset as i64))
+        .collect();
+    let title = format!(
+        "Attendance Register - {} - {} {}",
+        class.class_name,
+        month_name(month),
+        year
+    );
+
+    let mut doc = Document::new(&title);
+    let mut page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+    let mut y = draw_register_header(page, school.as_ref(), &title, &dates);
+    for student in &students {
+        if y < 40.0 {
+            page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+            y = draw_register_header(page, school.as_ref(), &title, &dates);
+        }
+        let edges = register_columns(page, dates.len());
+        let (top, bottom) = (y + 11.0, y - 5.0);
+        let roll = student.roll_number.clone().unwrap_or_default();
+        page.text(edges[0] + 2.0, y, 8.5, Font::Regular, &roll);
+        page.text(edges[1] + 2.0, y, 8.5, Font::Regular, &student.full_name);
+        for x in &edges {
+            page.line(*x, top, *x, bottom, 0.4);
+        }
+        page.line(edges[0], bottom, edges[edges.len() - 1], bottom, 0.4);
+        y -= 16.0;
+        if let Some(alert) = &student.medical_alert {
+            let indent = edges[1] + 2.0;
+            let note = format!("Medical alert: {}", alert);
+            let width = page.width() - indent - REGISTER_MARGIN;
+            for line in wrap(&note, 7.5, Font::Bold, width) {
+                if y < 40.0 {
+                    page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+                    y = draw_register_header(page, school.as_ref(), &title, &dates);
+                }
+                page.text(indent, y + 3.0, 7.5, Font::Bold, &line);
+                y -= 10.0;
+            }
+        }
+    }
+    let file_name = format!(
+        "Attendance-Register-{}-{}.pdf",
+        file_part(&class.class_name),
+        &first.to_string()[..7]
+    );
+    Ok((file_name, doc.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::students::{save_core, save_health};
+    use crate::service::transport::{allocate_student, get_route_students, save_route};
+    use crate::students::StudentHealth;
+    use crate::test_support::{create_class, memory_db, sample_student};
+    use crate::transport::{RouteStop, TransportAllocation, TransportRoute};
+
+    fn add_student(conn: &Connection, class_id: i64, gr_number: &str) -> i64 {
+        save_core(conn, sample_student(&class_id.to_string(), gr_number).core).unwrap()
+    }
+
+    fn checkup(student_id: i64, date: &str, weight_kg: f64, height_cm: f64) -> HealthCheckup {
+        HealthCheckup {
+            id: None,
+            student_id,
+            checkup_date: Some(date.to_string()),
+            weight_kg: Some(weight_kg),
+            height_cm: Some(height_cm),
+            hb_range: None,
+            vision: None,
+            dental: None,
+            remarks: None,
+            checked_by: Some("Dr. Kulkarni".to_string()),
+            age_months: None,
+            bmi: None,
+            bmi_percentile: None,
+            bmi_category: None,
+        }
+    }
+
+    #[test]
+    fn checkups_build_a_growth_history_against_the_reference() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "4");
+        // Born 2015-04-12, female.
+        let student = add_student(&conn, class_id, "GR-1");
+
+        save_checkup(&conn, checkup(student, "2024-10-12", 40.0, 135.0)).unwrap();
+        save_checkup(&conn, checkup(student, "2023-10-12", 26.0, 130.0)).unwrap();
+        let history = get_growth_history(&conn, student).unwrap();
+        let summary: Vec<_> = history
+            .iter()
+            .map(|c| {
+                (
+                    c.checkup_date.clone().unwrap(),
+                    c.age_months.unwrap(),
+                    c.bmi.unwrap(),
+                    c.bmi_category.clone().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("2023-10-12".to_string(), 102, 15.4, "healthy".to_string()),
+                (
+                    "2024-10-12".to_string(),
+                    114,
+                    21.9,
+                    "overweight".to_string()
+                ),
+            ]
+        );
+        assert_eq!(history[1].bmi_percentile.as_deref(), Some("85th-95th"));
+
+        // The latest checkup, not the last one entered, is kept on the student record.
+        let record = student_repo::find_by_id(&conn, student).unwrap().unwrap();
+        assert_eq!(record.health.weight_kg, Some(40.0));
+        assert!(
+            save_checkup(&conn, checkup(student, "2015-01-01", 3.0, 50.0))
+                .unwrap_err()
+                .contains("date of birth")
+        );
+    }
+
+    #[test]
+    fn the_vaccination_card_and_reminders_follow_the_schedule() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "4");
+        let student = add_student(&conn, class_id, "GR-1");
+        let vaccination = |vaccine: &str, dose_number: u32| StudentVaccination {
+            id: None,
+            student_id: student,
+            vaccine: vaccine.to_string(),
+            dose_number,
+            given_on: Some("2020-05-01".to_string()),
+            batch_number: None,
+            given_at: Some("City Hospital".to_string()),
+            remarks: None,
+            due_date: None,
+            status: None,
+        };
+        record_vaccination(&conn, vaccination("dtap booster", 2)).unwrap();
+        record_vaccination(&conn, vaccination("Hepatitis A", 2)).unwrap();
+        assert!(record_vaccination(&conn, vaccination("DTaP Booster", 2))
+            .unwrap_err()
+            .contains("already recorded"));
+
+        let card = get_student_vaccinations(&conn, student).unwrap();
+        let dtap = card.iter().find(|v| v.vaccine == "dtap booster").unwrap();
+        assert_eq!(dtap.status.as_deref(), Some("given"));
+        assert_eq!(dtap.due_date.as_deref(), Some("2020-04-12"));
+        let hpv = card.iter().find(|v| v.vaccine == "HPV").unwrap();
+        assert_eq!(hpv.due_date.as_deref(), Some("2024-04-12"));
+        assert_eq!(card.last().unwrap().vaccine, "Hepatitis A");
+
+        let reminders = get_reminders(&conn, Some("2025-03-20".to_string()), None, None).unwrap();
+        let due: Vec<_> = reminders
+            .iter()
+            .map(|r| (r.vaccine.as_str(), r.dose_number, r.days_overdue))
+            .collect();
+        assert_eq!(
+            due,
+            vec![
+                ("MMR", 3, 1803),
+                ("Typhoid conjugate", 2, 1803),
+                ("HPV", 1, 342),
+                ("HPV", 2, 159),
+                ("Tdap", 1, -23),
+            ]
+        );
+    }
+
+    #[test]
+    fn medical_conditions_show_up_on_registers_route_lists_and_the_infirmary_log() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "4");
+        let student = add_student(&conn, class_id, "GR-1");
+        add_student(&conn, class_id, "GR-2");
+        let health = StudentHealth {
+            blood_group: Some("B+".to_string()),
+            status: None,
+            admission_date: None,
+            weight_kg: None,
+            height_cm: None,
+            hb_range: None,
+            medical_conditions: Some(" Peanut allergy; carries an EpiPen ".to_string()),
+            emergency_contact_person: None,
+            emergency_contact: None,
+        };
+        save_health(&conn, student, health).unwrap();
+
+        let alerts = get_medical_alerts(&conn, Some(class_id)).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].medical_conditions,
+            "Peanut allergy; carries an EpiPen"
+        );
+
+        let route = save_route(
+            &conn,
+            TransportRoute {
+                id: None,
+                name: "North".to_string(),
+                vehicle_id: None,
+                stops: vec![RouteStop {
+                    id: None,
+                    name: "Market".to_string(),
+                    pickup_time: "07:30".to_string(),
+                    drop_time: None,
+                    distance_km: 4.0,
+                    students: 0,
+                }],
+                registration_number: None,
+                capacity: None,
+                students: 0,
+            },
+        )
+        .unwrap();
+        let stop_id = route.stops[0].id.unwrap();
+        allocate_student(
+            &conn,
+            TransportAllocation {
+                student_id: student,
+                stop_id,
+                allocated_on: None,
+                student_name: None,
+                class_name: None,
+                roll_number: None,
+                mobile_number: None,
+                route_id: None,
+                route_name: None,
+                stop_name: None,
+                pickup_time: None,
+                distance_km: None,
+                fare: None,
+                capacity_warning: None,
+                medical_alert: None,
+            },
+        )
+        .unwrap();
+        let riders = get_route_students(&conn, route.id.unwrap()).unwrap();
+        assert_eq!(
+            riders[0].medical_alert.as_deref(),
+            Some("Peanut allergy; carries an EpiPen")
+        );
+
+        let visit = InfirmaryVisit {
+            id: None,
+            student_id: student,
+            visit_date: None,
+            visit_time: Some("11:15".to_string()),
+            complaint: "Rash after lunch".to_string(),
+            temperature_c: Some(37.2),
+            treatment: Some("Antihistamine".to_string()),
+            outcome: Some("Sent_Home".to_string()),
+            guardian_informed: true,
+            attended_by: None,
+            student_name: None,
+            class_name: None,
+            medical_alert: None,
+        };
+        record_infirmary_visit(&conn, visit).unwrap();
+        let log = get_infirmary_visits(&conn, Some(student), None, None).unwrap();
+        assert_eq!(log[0].outcome.as_deref(), Some("sent_home"));
+        assert!(log[0].medical_alert.is_some());
+
+        let (file_name, pdf) = render_attendance_register_pdf(&conn, class_id, 2025, 2).unwrap();
+        assert_eq!(file_name, "Attendance-Register-4-2025-02.pdf");
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(Attendance Register - 4 - February 2025) Tj"));
+        assert!(text.contains("(Student GR-2) Tj"));
+        assert!(text.contains("(Medical alert: Peanut allergy; carries an EpiPen) Tj"));
+        assert!(text.contains("(28) Tj") && !text.contains("(29) Tj"));
+        assert!(render_attendance_register_pdf(&conn, class_id, 2025, 13).is_err());
+    }
+}
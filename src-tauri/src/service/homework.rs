@@ -0,0 +1,620 @@
+use crate::date::Date;
+use crate::homework::{
+    DigestItem, Homework, HomeworkAttachment, HomeworkDigest, HomeworkSubmission, MissingWork,
+    SubmissionEntry,
+};
+use crate::repository;
+use crate::repository::class as class_repo;
+use crate::repository::homework as repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::subject as subject_repo;
+use crate::service::academic_year::ensure_class_open;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+pub const SUBMISSION_STATUSES: [&str; 3] = ["submitted", "late", "missing"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn find_homework(conn: &Connection, id: i64) -> Result<Homework, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Homework with id {} not found", id))
+}
+
+/// Homework of the class due within `from..=to` (default: all of it), with attachments.
+pub fn get_class_homework(
+    conn: &Connection,
+    class_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Homework>, String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    let mut homework =
+        repo::find_for_class(conn, class_id, &from, &to).map_err(|e| e.to_string())?;
+    for item in &mut homework {
+        item.attachments =
+            repo::find_attachments(conn, item.id.unwrap_or_default()).map_err(|e| e.to_string())?;
+    }
+    Ok(homework)
+}
+
+pub fn save_homework(conn: &Connection, homework: Homework) -> Result<i64, String> {
+    let mut homework = Homework {
+        title: homework.title.trim().to_string(),
+        description: non_blank(homework.description),
+        ..homework
+    };
+    if homework.title.is_empty() {
+        return Err("Homework title is required".to_string());
+    }
+    if let Some(id) = homework.id {
+        // Moving homework between classes touches both classes' records.
+        ensure_class_open(conn, find_homework(conn, id)?.class_id)?;
+    }
+    ensure_class_open(conn, homework.class_id)?;
+
+    let taught = subject_repo::find_class_subjects(conn, homework.class_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|cs| cs.subject_id == homework.subject_id);
+    let Some(taught) = taught else {
+        return Err("The subject is not taught in this class".to_string());
+    };
+    homework.teacher_id = homework.teacher_id.or(taught.teacher_id);
+    if let Some(teacher_id) = homework.teacher_id {
+        let teacher = staff_repo::find_by_id(conn, teacher_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Teacher not found".to_string())?;
+        if !is_active(teacher.status.as_deref()) {
+            return Err(format!("{} is not an active staff member", teacher.name));
+        }
+    }
+
+    let assigned_on = match non_blank(homework.assigned_on.take()) {
+        Some(date) => Date::parse(&date)?,
+        None => today(conn)?,
+    };
+    let due_date = Date::parse(homework.due_date.trim())?;
+    if due_date < assigned_on {
+        return Err("The due date cannot be before the day the homework is set".to_string());
+    }
+    homework.assigned_on = Some(assigned_on.to_string());
+    homework.due_date = due_date.to_string();
+
+    match homework.id {
+        Some(id) => {
+            repo::update(conn, id, &homework).map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+        None => repo::insert(conn, &homework).map_err(|e| e.to_string()),
+    }
+}
+
+/// Deletes the homework and returns the stored attachment files to remove.
+pub fn delete_homework(conn: &Connection, id: i64) -> Result<Vec<String>, String> {
+    ensure_class_open(conn, find_homework(conn, id)?.class_id)?;
+    let files = repo::find_attachments(conn, id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|a| a.file_name)
+        .collect();
+    repo::delete(conn, id).map_err(|e| e.to_string())?;
+    Ok(files)
+}
+
+/// Keeps letters, digits, dots, dashes and underscores so the stored name is a plain file
+/// name whatever was uploaded.
+fn safe_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or("").trim();
+    let safe: String = base
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let safe = safe.trim_start_matches('.');
+    if safe.is_empty() {
+        "attachment".to_string()
+    } else {
+        safe.to_string()
+    }
+}
+
+/// Records an attachment and picks the name its file is stored under.
+pub fn add_attachment(
+    conn: &Connection,
+    homework_id: i64,
+    original_name: &str,
+) -> Result<HomeworkAttachment, String> {
+    ensure_class_open(conn, find_homework(conn, homework_id)?.class_id)?;
+    let original_name = original_name.trim();
+    if original_name.is_empty() {
+        return Err("The attachment needs a file name".to_string());
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = repo::insert_attachment(&tx, homework_id, original_name).map_err(|e| e.to_string())?;
+    let file_name = format!("{}_{}_{}", homework_id, id, safe_file_name(original_name));
+    repo::set_attachment_file(&tx, id, &file_name).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_attachment(conn, id)
+}
+
+pub fn get_attachment(conn: &Connection, id: i64) -> Result<HomeworkAttachment, String> {
+    repo::find_attachment(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Attachment with id {} not found", id))
+}
+
+/// Removes the attachment record and returns the stored file to delete.
+pub fn delete_attachment(conn: &Connection, id: i64) -> Result<String, String> {
+    let attachment = get_attachment(conn, id)?;
+    ensure_class_open(conn, find_homework(conn, attachment.homework_id)?.class_id)?;
+    repo::delete_attachment(conn, id).map_err(|e| e.to_string())?;
+    Ok(attachment.file_name)
+}
+
+/// Fills in pending or missing for students with nothing recorded.
+fn with_derived_status(
+    mut submissions: Vec<HomeworkSubmission>,
+    due_date: Date,
+    today: Date,
+) -> Vec<HomeworkSubmission> {
+    for submission in &mut submissions {
+        if submission.status.is_empty() {
+            submission.status = if today > due_date {
+                "missing"
+            } else {
+                "pending"
+            }
+            .to_string();
+        }
+    }
+    submissions
+}
+
+pub fn get_submissions(
+    conn: &Connection,
+    homework_id: i64,
+) -> Result<Vec<HomeworkSubmission>, String> {
+    let homework = find_homework(conn, homework_id)?;
+    let submissions =
+        repo::find_submissions(conn, homework_id, homework.class_id).map_err(|e| e.to_string())?;
+    Ok(with_derived_status(
+        submissions,
+        Date::parse(&homework.due_date)?,
+        today(conn)?,
+    ))
+}
+
+/// Status to store for an entry: an explicit late or missing stands, anything handed in
+/// after the due date is late, and work handed in without a date counts as handed in today.
+fn resolve_entry(
+    entry: &SubmissionEntry,
+    due_date: Date,
+    today: Date,
+) -> Result<Option<(String, Option<String>)>, String> {
+    let status = non_blank(entry.status.clone()).map(|s| s.to_lowercase());
+    let submitted_on = match non_blank(entry.submitted_on.clone()) {
+        Some(date) => Some(Date::parse(&date)?),
+        None => None,
+    };
+    match status.as_deref() {
+        Some("pending") => Ok(None),
+        Some("missing") => Ok(Some(("missing".to_string(), None))),
+        None | Some("submitted") | Some("late") => {
+            let Some(on) = submitted_on.or(status.is_some().then_some(today)) else {
+                return Err(format!(
+                    "Record whether student {} submitted the homework",
+                    entry.student_id
+                ));
+            };
+            let status = if status.as_deref() == Some("late") || on > due_date {
+                "late"
+            } else {
+                "submitted"
+            };
+            Ok(Some((status.to_string(), Some(on.to_string()))))
+        }
+        Some(other) => Err(format!(
+            "Invalid submission status '{}'. Expected one of: {}, pending",
+            other,
+            SUBMISSION_STATUSES.join(", ")
+        )),
+    }
+}
+
+/// Saves the teacher's entries for the listed students and returns the updated class list.
+pub fn record_submissions(
+    conn: &Connection,
+    homework_id: i64,
+    entries: Vec<SubmissionEntry>,
+) -> Result<Vec<HomeworkSubmission>, String> {
+    let homework = find_homework(conn, homework_id)?;
+    ensure_class_open(conn, homework.class_id)?;
+    let due_date = Date::parse(&homework.due_date)?;
+    let today = today(conn)?;
+    let roster =
+        repo::find_submissions(conn, homework_id, homework.class_id).map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for entry in &entries {
+        if !roster.iter().any(|s| s.student_id == entry.student_id) {
+            return Err(format!(
+                "Student {} is not in {}",
+                entry.student_id,
+                homework.class_name.as_deref().unwrap_or("the class")
+            ));
+        }
+        match resolve_entry(entry, due_date, today)? {
+            Some((status, submitted_on)) => repo::upsert_submission(
+                &tx,
+                homework_id,
+                entry.student_id,
+                &status,
+                submitted_on.as_deref(),
+                non_blank(entry.grade.clone()).as_deref(),
+                non_blank(entry.remarks.clone()).as_deref(),
+            )
+            .map_err(|e| e.to_string())?,
+            None => {
+                repo::delete_submission(&tx, homework_id, entry.student_id)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_submissions(conn, homework_id)
+}
+
+/// Homework of the class due in the Monday-to-Sunday week containing `week_of`, with how
+/// many students handed each in, and who has missed what.
+pub fn get_weekly_digest(
+    conn: &Connection,
+    class_id: i64,
+    week_of: Option<String>,
+) -> Result<HomeworkDigest, String> {
+    let class = class_repo::find_by_id(conn, class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())?;
+    let today = today(conn)?;
+    let day = match non_blank(week_of) {
+        Some(date) => Date::parse(&date)?,
+        None => today,
+    };
+    let week_start = day.add_days(-(day.weekday() as i64));
+    let week_end = week_start.add_days(6);
+
+    let homework = repo::find_for_class(
+        conn,
+        class_id,
+        &week_start.to_string(),
+        &week_end.to_string(),
+    )
+    .map_err(|e| e.to_string())?;
+    let mut items = Vec::new();
+    let mut missing: BTreeMap<i64, MissingWork> = BTreeMap::new();
+    for homework in homework.into_iter().rev() {
+        let id = homework.id.unwrap_or_default();
+        let due_date = Date::parse(&homework.due_date)?;
+        let submissions = with_derived_status(
+            repo::find_submissions(conn, id, class_id).map_err(|e| e.to_string())?,
+            due_date,
+            today,
+        );
+        let count = |status: &str| submissions.iter().filter(|s| s.status == status).count() as u32;
+        items.push(DigestItem {
+            homework_id: id,
+            subject_name: homework.subject_name.clone().unwrap_or_default(),
+            title: homework.title.clone(),
+            assigned_on: homework.assigned_on.clone().unwrap_or_default(),
+            due_date: homework.due_date.clone(),
+            submitted: count("submitted"),
+            late: count("late"),
+            missing: count("missing"),
+            pending: count("pending"),
+        });
+        for submission in submissions.into_iter().filter(|s| s.status == "missing") {
+            missing
+                .entry(submission.student_id)
+                .or_insert_with(|| MissingWork {
+                    student_id: submission.student_id,
+                    student_name: submission.student_name,
+                    titles: Vec::new(),
+                })
+                .titles
+                .push(homework.title.clone());
+        }
+    }
+    let mut students_missing: Vec<MissingWork> = missing.into_values().collect();
+    students_missing.sort_by(|a, b| {
+        b.titles
+            .len()
+            .cmp(&a.titles.len())
+            .then_with(|| a.student_name.cmp(&b.student_name))
+    });
+
+    Ok(HomeworkDigest {
+        class_id,
+        class_name: class.class_name,
+        week_start: week_start.to_string(),
+        week_end: week_end.to_string(),
+        items,
+        students_missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::students::save_core;
+    use crate::service::subject::{
+        assign_class_subject, create_subject, delete_subject, get_class_subjects,
+        remove_class_subject,
+    };
+    use crate::subject::{ClassSubject, Subject};
+    use crate::test_support::{create_class, memory_db, sample_student};
+
+    /// A class taking Mathematics, with two students; returns (class, subject, students).
+    fn class_with_maths(conn: &Connection) -> (i64, i64, Vec<i64>) {
+        let class_id = create_class(conn, "Class-7 A");
+        let subject_id = create_subject(
+            conn,
+            Subject {
+                id: None,
+                code: "MATH".to_string(),
+                name: "Mathematics".to_string(),
+                subject_type: "theory".to_string(),
+                status: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        assign_class_subject(
+            conn,
+            ClassSubject {
+                id: None,
+                class_id,
+                subject_id,
+                periods_per_week: 6,
+                teacher_id: None,
+                academic_year_id: None,
+                class_name: None,
+                subject_code: None,
+                subject_name: None,
+                subject_type: None,
+                teacher_name: None,
+            },
+        )
+        .unwrap();
+        let students = ["H1", "H2"]
+            .iter()
+            .map(|gr| save_core(conn, sample_student(&class_id.to_string(), gr).core).unwrap())
+            .collect();
+        (class_id, subject_id, students)
+    }
+
+    fn homework(
+        class_id: i64,
+        subject_id: i64,
+        title: &str,
+        assigned: &str,
+        due: &str,
+    ) -> Homework {
+        Homework {
+            id: None,
+            class_id,
+            subject_id,
+            teacher_id: None,
+            title: title.to_string(),
+            description: None,
+            assigned_on: Some(assigned.to_string()),
+            due_date: due.to_string(),
+            class_name: None,
+            subject_name: None,
+            teacher_name: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    fn entry(student_id: i64, status: Option<&str>, submitted_on: Option<&str>) -> SubmissionEntry {
+        SubmissionEntry {
+            student_id,
+            status: status.map(str::to_string),
+            submitted_on: submitted_on.map(str::to_string),
+            grade: None,
+            remarks: None,
+        }
+    }
+
+    #[test]
+    fn homework_must_be_for_a_subject_of_the_class() {
+        let conn = memory_db();
+        let (class_id, subject_id, _) = class_with_maths(&conn);
+        let other_class = create_class(&conn, "Class-8 A");
+
+        let err = save_homework(
+            &conn,
+            homework(
+                other_class,
+                subject_id,
+                "Fractions",
+                "2024-07-01",
+                "2024-07-03",
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(err, "The subject is not taught in this class");
+        let err = save_homework(
+            &conn,
+            homework(
+                class_id,
+                subject_id,
+                "Fractions",
+                "2024-07-03",
+                "2024-07-01",
+            ),
+        )
+        .unwrap_err();
+        assert!(err.contains("due date cannot be before"), "{}", err);
+
+        let id = save_homework(
+            &conn,
+            homework(
+                class_id,
+                subject_id,
+                "Fractions",
+                "2024-07-01",
+                "2024-07-03",
+            ),
+        )
+        .unwrap();
+        let attachment =
+            add_attachment(&conn, id, "C:\\Users\\T\\Worksheet 1 (final).pdf").unwrap();
+        assert_eq!(
+            attachment.file_name,
+            format!("{}_{}_Worksheet_1__final_.pdf", id, attachment.id)
+        );
+        let listed = get_class_homework(&conn, class_id, None, None).unwrap();
+        assert_eq!(listed[0].attachments.len(), 1);
+        assert_eq!(listed[0].subject_name.as_deref(), Some("Mathematics"));
+
+        let mapping = get_class_subjects(&conn, class_id).unwrap()[0].id.unwrap();
+        remove_class_subject(&conn, mapping).unwrap();
+        let err = delete_subject(&conn, subject_id).unwrap_err();
+        assert!(err.contains("homework"), "{}", err);
+        assert_eq!(
+            delete_homework(&conn, id).unwrap(),
+            vec![attachment.file_name]
+        );
+    }
+
+    #[test]
+    fn submissions_follow_the_due_date() {
+        let conn = memory_db();
+        let (class_id, subject_id, students) = class_with_maths(&conn);
+        let outsider = save_core(
+            &conn,
+            sample_student(&create_class(&conn, "Class-8 A").to_string(), "X1").core,
+        )
+        .unwrap();
+        let id = save_homework(
+            &conn,
+            homework(
+                class_id,
+                subject_id,
+                "Fractions",
+                "2024-07-01",
+                "2024-07-03",
+            ),
+        )
+        .unwrap();
+
+        let roster = get_submissions(&conn, id).unwrap();
+        assert_eq!(roster.len(), 2);
+        assert!(roster.iter().all(|s| s.status == "missing"));
+
+        let err = record_submissions(&conn, id, vec![entry(outsider, Some("submitted"), None)])
+            .unwrap_err();
+        assert!(err.contains("is not in Class-7 A"), "{}", err);
+        let err = record_submissions(&conn, id, vec![entry(students[0], None, None)]).unwrap_err();
+        assert!(err.contains("Record whether"), "{}", err);
+
+        let mut graded = entry(students[0], None, Some("2024-07-03"));
+        graded.grade = Some(" A+ ".to_string());
+        let roster = record_submissions(
+            &conn,
+            id,
+            vec![
+                graded,
+                entry(students[1], Some("submitted"), Some("2024-07-05")),
+            ],
+        )
+        .unwrap();
+        let statuses: Vec<(&str, Option<&str>)> = roster
+            .iter()
+            .map(|s| (s.status.as_str(), s.grade.as_deref()))
+            .collect();
+        assert_eq!(statuses, vec![("submitted", Some("A+")), ("late", None)]);
+
+        let roster =
+            record_submissions(&conn, id, vec![entry(students[1], Some("pending"), None)]).unwrap();
+        assert_eq!(roster[1].status, "missing");
+        assert_eq!(roster[1].submitted_on, None);
+    }
+
+    #[test]
+    fn weekly_digest_counts_work_due_that_week() {
+        let conn = memory_db();
+        let (class_id, subject_id, students) = class_with_maths(&conn);
+        let first = save_homework(
+            &conn,
+            homework(
+                class_id,
+                subject_id,
+                "Fractions",
+                "2024-07-01",
+                "2024-07-02",
+            ),
+        )
+        .unwrap();
+        let second = save_homework(
+            &conn,
+            homework(class_id, subject_id, "Decimals", "2024-07-03", "2024-07-07"),
+        )
+        .unwrap();
+        save_homework(
+            &conn,
+            homework(class_id, subject_id, "Ratios", "2024-07-08", "2024-07-08"),
+        )
+        .unwrap();
+        record_submissions(
+            &conn,
+            first,
+            vec![entry(students[0], None, Some("2024-07-02"))],
+        )
+        .unwrap();
+        record_submissions(
+            &conn,
+            second,
+            vec![entry(students[0], Some("missing"), None)],
+        )
+        .unwrap();
+
+        let digest = get_weekly_digest(&conn, class_id, Some("2024-07-04".to_string())).unwrap();
+        assert_eq!(
+            (digest.week_start.as_str(), digest.week_end.as_str()),
+            ("2024-07-01", "2024-07-07")
+        );
+        let titles: Vec<&str> = digest.items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["Fractions", "Decimals"]);
+        assert_eq!((digest.items[0].submitted, digest.items[0].missing), (1, 1));
+        assert_eq!(digest.items[1].missing, 2);
+        assert_eq!(digest.students_missing[0].student_id, students[1]);
+        assert_eq!(
+            digest.students_missing[0].titles,
+            vec!["Fractions", "Decimals"]
+        );
+        assert_eq!(digest.students_missing[1].titles, vec!["Decimals"]);
+    }
+}
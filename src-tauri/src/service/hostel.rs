@@ -0,0 +1,719 @@
+use crate::date::{parse_time, Date};
+use crate::dues::StudentDue;
+use crate::hostel::{
+    BedAllotment, BoardingFeeRun, Hostel, HostelMovement, HostelOccupancy, HostelRoom,
+    HostelVisitor,
+};
+use crate::repository;
+use crate::repository::dues as dues_repo;
+use crate::repository::hostel as repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::students as student_repo;
+use crate::service::dues::post_due;
+use crate::service::lifecycle::is_on_roll;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+
+pub const HOSTEL_TYPES: [&str; 3] = ["boys", "girls", "mixed"];
+pub const MOVEMENT_TYPES: [&str; 2] = ["outing", "leave"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, allowed: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(date) => Ok(Date::parse(&date)?.to_string()),
+        None => repository::today(conn).map_err(|e| e.to_string()),
+    }
+}
+
+fn time_or_now(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(time) => {
+            parse_time(&time)?;
+            Ok(time.chars().take(5).collect())
+        }
+        None => repository::now_time(conn).map_err(|e| e.to_string()),
+    }
+}
+
+/// Normalises `YYYY-MM-DD HH:MM`.
+fn parse_timestamp(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let (date, time) = value
+        .split_once([' ', 'T'])
+        .ok_or_else(|| format!("'{}' should be a date and time (YYYY-MM-DD HH:MM)", value))?;
+    parse_time(time)?;
+    Ok(format!(
+        "{} {}",
+        Date::parse(date)?,
+        time.trim().chars().take(5).collect::<String>()
+    ))
+}
+
+fn timestamp_or_now(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(value) => parse_timestamp(&value),
+        None => Ok(format!(
+            "{} {}",
+            repository::today(conn).map_err(|e| e.to_string())?,
+            repository::now_time(conn).map_err(|e| e.to_string())?
+        )),
+    }
+}
+
+/// The student's current allotment; only boarders go out or have visitors.
+fn current_boarder(conn: &Connection, student_id: i64) -> Result<BedAllotment, String> {
+    repo::find_current_allotment(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "The student is not a boarder".to_string())
+}
+
+pub fn get_hostels(conn: &Connection) -> Result<Vec<Hostel>, String> {
+    repo::find_hostels(conn).map_err(|e| e.to_string())
+}
+
+pub fn save_hostel(conn: &Connection, hostel: Hostel) -> Result<i64, String> {
+    let hostel = Hostel {
+        name: hostel.name.trim().to_string(),
+        ..hostel
+    };
+    let hostel_type = match non_blank(hostel.hostel_type.clone()) {
+        Some(hostel_type) => one_of(&hostel_type, &HOSTEL_TYPES, "hostel type")?,
+        None => "mixed".to_string(),
+    };
+    if hostel.name.is_empty() {
+        return Err("Hostel name is required".to_string());
+    }
+    if !hostel.boarding_fee.is_finite() || hostel.boarding_fee < 0.0 {
+        return Err("The boarding fee cannot be negative".to_string());
+    }
+    if repo::hostel_name_taken(conn, &hostel.name, hostel.id).map_err(|e| e.to_string())? {
+        return Err(format!("A hostel named {} already exists", hostel.name));
+    }
+    if let Some(warden_id) = hostel.warden_id {
+        let warden = staff_repo::find_by_id(conn, warden_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Staff member with id {} not found", warden_id))?;
+        if !is_active(warden.status.as_deref()) {
+            return Err(format!("{} is no longer active", warden.name));
+        }
+    }
+    match hostel.id {
+        Some(id) => {
+            if repo::update_hostel(conn, id, &hostel, &hostel_type).map_err(|e| e.to_string())? == 0
+            {
+                return Err(format!("Hostel with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_hostel(conn, &hostel, &hostel_type).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_hostel(conn: &Connection, id: i64) -> Result<(), String> {
+    let boarders = repo::count_boarders(conn, Some(id), None).map_err(|e| e.to_string())?;
+    if boarders > 0 {
+        return Err(format!(
+            "{} boarder(s) still live in this hostel; vacate their beds first",
+            boarders
+        ));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if repo::delete_hostel(&tx, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Hostel with id {} not found", id));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_rooms(conn: &Connection, hostel_id: i64) -> Result<Vec<HostelRoom>, String> {
+    let mut rooms = repo::find_rooms(conn, hostel_id).map_err(|e| e.to_string())?;
+    for room in &mut rooms {
+        room.beds =
+            repo::find_beds(conn, room.id.unwrap_or_default()).map_err(|e| e.to_string())?;
+    }
+    Ok(rooms)
+}
+
+/// Saves the room and its beds. Beds left out are removed, except occupied ones.
+pub fn save_room(conn: &Connection, room: HostelRoom) -> Result<HostelRoom, String> {
+    let mut room = HostelRoom {
+        room_number: room.room_number.trim().to_string(),
+        ..room
+    };
+    if room.room_number.is_empty() {
+        return Err("Room number is required".to_string());
+    }
+    if repo::find_hostel(conn, room.hostel_id)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!("Hostel with id {} not found", room.hostel_id));
+    }
+    if repo::room_number_taken(conn, room.hostel_id, &room.room_number, room.id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "Room {} already exists in this hostel",
+            room.room_number
+        ));
+    }
+    for bed in &mut room.beds {
+        bed.label = bed.label.trim().to_string();
+        if bed.label.is_empty() {
+            return Err("Every bed needs a label".to_string());
+        }
+    }
+    for (index, bed) in room.beds.iter().enumerate() {
+        if room.beds[..index]
+            .iter()
+            .any(|other| other.label.eq_ignore_ascii_case(&bed.label))
+        {
+            return Err(format!("Bed {} is listed twice", bed.label));
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let room_id = match room.id {
+        Some(id) => {
+            if repo::find_room_hostel(&tx, id).map_err(|e| e.to_string())? != Some(room.hostel_id) {
+                return Err(format!("Room with id {} not found in this hostel", id));
+            }
+            repo::update_room(&tx, id, &room).map_err(|e| e.to_string())?;
+            for existing in repo::find_beds(&tx, id).map_err(|e| e.to_string())? {
+                if room.beds.iter().any(|bed| bed.id == existing.id) {
+                    continue;
+                }
+                if let Some(name) = existing.student_name {
+                    return Err(format!(
+                        "Bed {} is allotted to {}; vacate it before removing the bed",
+                        existing.label, name
+                    ));
+                }
+                repo::delete_bed(&tx, existing.id.unwrap_or_default())
+                    .map_err(|e| e.to_string())?;
+            }
+            id
+        }
+        None => repo::insert_room(&tx, &room).map_err(|e| e.to_string())?,
+    };
+    // Labels are unique per room, so renamed beds are saved after the others make way.
+    for bed in &room.beds {
+        if let Some(bed_id) = bed.id {
+            repo::update_bed(&tx, bed_id, &format!("~{}", bed_id)).map_err(|e| e.to_string())?;
+        }
+    }
+    for bed in &room.beds {
+        match bed.id {
+            Some(bed_id) => repo::update_bed(&tx, bed_id, &bed.label).map(|_| ()),
+            None => repo::insert_bed(&tx, room_id, &bed.label).map(|_| ()),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_rooms(conn, room.hostel_id)?
+        .into_iter()
+        .find(|saved| saved.id == Some(room_id))
+        .ok_or_else(|| "Room was not saved".to_string())
+}
+
+pub fn delete_room(conn: &Connection, id: i64) -> Result<(), String> {
+    let boarders = repo::count_boarders(conn, None, Some(id)).map_err(|e| e.to_string())?;
+    if boarders > 0 {
+        return Err(format!(
+            "{} boarder(s) still live in this room; vacate their beds first",
+            boarders
+        ));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if repo::delete_room(&tx, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Room with id {} not found", id));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Gives the student a bed. A bed holds one boarder and a boarder one bed; moving rooms
+/// means vacating first. Boys' and girls' hostels only take students of that gender.
+pub fn allot_bed(conn: &Connection, allotment: BedAllotment) -> Result<BedAllotment, String> {
+    let student = student_repo::find_by_id(conn, allotment.student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", allotment.student_id))?;
+    let status = student_repo::find_status(conn, allotment.student_id)
+        .map_err(|e| e.to_string())?
+        .flatten();
+    if !is_on_roll(status.as_deref()) {
+        return Err("Only students on the roll can board".to_string());
+    }
+    let (_, hostel_type, occupant) = repo::find_bed_details(conn, allotment.bed_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Bed with id {} not found", allotment.bed_id))?;
+    if let Some(occupant) = occupant {
+        return Err(format!("The bed is already allotted to {}", occupant));
+    }
+    if let Some(current) =
+        repo::find_current_allotment(conn, allotment.student_id).map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "{} already has bed {} in room {} of {}; vacate it first",
+            student.core.full_name,
+            current.bed_label.unwrap_or_default(),
+            current.room_number.unwrap_or_default(),
+            current.hostel_name.unwrap_or_default()
+        ));
+    }
+    let gender = student.core.gender.trim().to_lowercase();
+    let mismatch = match hostel_type.as_str() {
+        "boys" => !gender.starts_with('m'),
+        "girls" => !gender.starts_with('f'),
+        _ => false,
+    };
+    if mismatch {
+        return Err(format!("This is a {} hostel", hostel_type));
+    }
+
+    let allotted_on = date_or_today(conn, allotment.allotted_on)?;
+    repo::insert_allotment(conn, allotment.bed_id, allotment.student_id, &allotted_on)
+        .map_err(|e| e.to_string())?;
+    current_boarder(conn, allotment.student_id)
+}
+
+pub fn vacate_bed(
+    conn: &Connection,
+    student_id: i64,
+    vacated_on: Option<String>,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let current = current_boarder(conn, student_id)?;
+    let vacated_on = date_or_today(conn, vacated_on)?;
+    if Some(vacated_on.as_str()) < current.allotted_on.as_deref() {
+        return Err("A bed cannot be vacated before it was allotted".to_string());
+    }
+    repo::vacate(
+        conn,
+        current.id.unwrap_or_default(),
+        &vacated_on,
+        non_blank(reason).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_boarders(
+    conn: &Connection,
+    hostel_id: Option<i64>,
+) -> Result<Vec<BedAllotment>, String> {
+    repo::find_boarders(conn, hostel_id).map_err(|e| e.to_string())
+}
+
+pub fn get_student_allotments(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<BedAllotment>, String> {
+    repo::find_student_allotments(conn, student_id).map_err(|e| e.to_string())
+}
+
+/// Refuses while the student holds a hostel bed; the bed has to be vacated before the
+/// student leaves.
+pub fn ensure_not_boarding(conn: &Connection, student_id: i64) -> Result<(), String> {
+    if let Some(current) =
+        repo::find_current_allotment(conn, student_id).map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "The student still holds a bed in {}; vacate it first",
+            current.hostel_name.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+pub fn get_occupancy(conn: &Connection) -> Result<Vec<HostelOccupancy>, String> {
+    repo::find_occupancy(conn).map_err(|e| e.to_string())
+}
+
+pub fn check_out(conn: &Connection, movement: HostelMovement) -> Result<i64, String> {
+    let movement = HostelMovement {
+        movement_type: one_of(&movement.movement_type, &MOVEMENT_TYPES, "movement type")?,
+        out_at: Some(timestamp_or_now(conn, movement.out_at)?),
+        expected_back: non_blank(movement.expected_back)
+            .map(|value| parse_timestamp(&value))
+            .transpose()?,
+        purpose: non_blank(movement.purpose),
+        escorted_by: non_blank(movement.escorted_by),
+        approved_by: non_blank(movement.approved_by),
+        ..movement
+    };
+    current_boarder(conn, movement.student_id)?;
+    if movement.movement_type == "leave" && movement.approved_by.is_none() {
+        return Err("Record who approved the leave".to_string());
+    }
+    if movement.expected_back.is_some() && movement.expected_back <= movement.out_at {
+        return Err("The boarder must be due back after going out".to_string());
+    }
+    if repo::is_out(conn, movement.student_id).map_err(|e| e.to_string())? {
+        return Err("The boarder is already signed out".to_string());
+    }
+    repo::insert_movement(conn, &movement).map_err(|e| e.to_string())
+}
+
+pub fn check_in(conn: &Connection, id: i64, returned_at: Option<String>) -> Result<(), String> {
+    let movement = repo::find_movement(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Movement with id {} not found", id))?;
+    if movement.returned_at.is_some() {
+        return Err("The boarder is already back".to_string());
+    }
+    let returned_at = timestamp_or_now(conn, returned_at)?;
+    if Some(returned_at.as_str()) < movement.out_at.as_deref() {
+        return Err("A boarder cannot return before going out".to_string());
+    }
+    repo::record_return(conn, id, &returned_at).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_movements(
+    conn: &Connection,
+    from: Option<String>,
+    to: Option<String>,
+    movement_type: Option<String>,
+    out_only: bool,
+) -> Result<Vec<HostelMovement>, String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    let movement_type = non_blank(movement_type)
+        .map(|value| one_of(&value, &MOVEMENT_TYPES, "movement type"))
+        .transpose()?;
+    repo::find_movements(conn, &from, &to, movement_type.as_deref(), out_only)
+        .map_err(|e| e.to_string())
+}
+
+pub fn record_visitor(conn: &Connection, visitor: HostelVisitor) -> Result<i64, String> {
+    let visitor = HostelVisitor {
+        visitor_name: visitor.visitor_name.trim().to_string(),
+        relation: non_blank(visitor.relation),
+        phone: non_blank(visitor.phone),
+        visited_on: Some(date_or_today(conn, visitor.visited_on)?),
+        in_time: Some(time_or_now(conn, visitor.in_time)?),
+        out_time: non_blank(visitor.out_time)
+            .map(|time| time_or_now(conn, Some(time)))
+            .transpose()?,
+        purpose: non_blank(visitor.purpose),
+        ..visitor
+    };
+    if visitor.visitor_name.is_empty() {
+        return Err("Visitor name is required".to_string());
+    }
+    if visitor.out_time.is_some() && visitor.out_time < visitor.in_time {
+        return Err("A visitor cannot leave before arriving".to_string());
+    }
+    current_boarder(conn, visitor.student_id)?;
+    repo::insert_visitor(conn, &visitor).map_err(|e| e.to_string())
+}
+
+pub fn record_visitor_exit(
+    conn: &Connection,
+    id: i64,
+    out_time: Option<String>,
+) -> Result<(), String> {
+    let visitor = repo::find_visitor(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Visitor entry with id {} not found", id))?;
+    let out_time = time_or_now(conn, out_time)?;
+    if Some(&out_time) < visitor.in_time.as_ref() {
+        return Err("A visitor cannot leave before arriving".to_string());
+    }
+    repo::set_visitor_out_time(conn, id, &out_time).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_visitors(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<HostelVisitor>, String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    repo::find_visitors(conn, student_id, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Raises the hostel's boarding fee for `period` against each current boarder. Boarders
+/// already charged for the period are left alone, so a run can be repeated.
+pub fn raise_fees(
+    conn: &Connection,
+    period: &str,
+    raised_on: Option<String>,
+) -> Result<BoardingFeeRun, String> {
+    let period = period.trim();
+    if period.is_empty() {
+        return Err("Say which period the fees are for".to_string());
+    }
+    let fees: Vec<(i64, f64)> = get_hostels(conn)?
+        .into_iter()
+        .map(|hostel| (hostel.id.unwrap_or_default(), hostel.boarding_fee))
+        .collect();
+    let mut run = BoardingFeeRun {
+        raised: 0,
+        total: 0.0,
+        already_raised: 0,
+    };
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for boarder in repo::find_boarders(&tx, None).map_err(|e| e.to_string())? {
+        let fee = fees
+            .iter()
+            .find(|(hostel_id, _)| Some(*hostel_id) == boarder.hostel_id)
+            .map_or(0.0, |(_, fee)| *fee);
+        if fee <= 0.0 {
+            continue;
+        }
+        let reference = format!("{}:{}", period, boarder.student_id);
+        if dues_repo::reference_taken(&tx, "hostel", &reference).map_err(|e| e.to_string())? {
+            run.already_raised += 1;
+            continue;
+        }
+        let due = StudentDue {
+            id: None,
+            student_id: boarder.student_id,
+            source: None,
+            reference: Some(reference),
+            description: format!(
+                "Boarding fee {} ({})",
+                period,
+                boarder.hostel_name.unwrap_or_default()
+            ),
+            amount: fee,
+            raised_on: raised_on.clone(),
+            status: None,
+            settled_on: None,
+            settled_note: None,
+            student_name: None,
+            class_name: None,
+        };
+        post_due(&tx, due, "hostel")?;
+        run.raised += 1;
+        run.total += fee;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hostel::HostelBed;
+    use crate::lifecycle::StatusChange;
+    use crate::service::lifecycle::change_status;
+    use crate::service::students::save_core;
+    use crate::test_support::{create_class, memory_db, sample_student};
+
+    fn hostel(name: &str, hostel_type: &str) -> Hostel {
+        Hostel {
+            id: None,
+            name: name.to_string(),
+            hostel_type: Some(hostel_type.to_string()),
+            warden_id: None,
+            boarding_fee: 15000.0,
+            warden_name: None,
+        }
+    }
+
+    fn room(hostel_id: i64, room_number: &str, beds: &[&str]) -> HostelRoom {
+        HostelRoom {
+            id: None,
+            hostel_id,
+            floor: 1,
+            room_number: room_number.to_string(),
+            beds: beds
+                .iter()
+                .map(|label| HostelBed {
+                    id: None,
+                    label: label.to_string(),
+                    student_id: None,
+                    student_name: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn allotment(bed_id: i64, student_id: i64) -> BedAllotment {
+        BedAllotment {
+            id: None,
+            bed_id,
+            student_id,
+            allotted_on: Some("2024-06-10".to_string()),
+            vacated_on: None,
+            vacate_reason: None,
+            student_name: None,
+            class_name: None,
+            hostel_id: None,
+            hostel_name: None,
+            floor: None,
+            room_number: None,
+            bed_label: None,
+        }
+    }
+
+    fn student(conn: &Connection, gr_number: &str, gender: &str) -> i64 {
+        let class_id = create_class(conn, "Class-8 A");
+        let mut student = sample_student(&class_id.to_string(), gr_number);
+        student.core.gender = gender.to_string();
+        save_core(conn, student.core).unwrap()
+    }
+
+    #[test]
+    fn a_bed_is_never_allotted_twice() {
+        let conn = memory_db();
+        let girls = save_hostel(&conn, hostel("Ganga House", "girls")).unwrap();
+        let saved = save_room(&conn, room(girls, "101", &["A", "B"])).unwrap();
+        let (bed_a, bed_b) = (saved.beds[0].id.unwrap(), saved.beds[1].id.unwrap());
+        let asha = student(&conn, "H1", "Female");
+        let meena = student(&conn, "H2", "Female");
+        let ravi = student(&conn, "H3", "Male");
+
+        allot_bed(&conn, allotment(bed_a, asha)).unwrap();
+        let err = allot_bed(&conn, allotment(bed_a, meena)).unwrap_err();
+        assert_eq!(err, "The bed is already allotted to Student H1");
+        let err = allot_bed(&conn, allotment(bed_b, asha)).unwrap_err();
+        assert!(err.contains("vacate it first"), "{}", err);
+        assert_eq!(
+            allot_bed(&conn, allotment(bed_b, ravi)).unwrap_err(),
+            "This is a girls hostel"
+        );
+
+        let occupancy = get_occupancy(&conn).unwrap();
+        assert_eq!(
+            (
+                occupancy[0].beds,
+                occupancy[0].occupied,
+                occupancy[0].vacant
+            ),
+            (2, 1, 1)
+        );
+        let mut edited = get_rooms(&conn, girls).unwrap().remove(0);
+        edited.beds.remove(0);
+        assert!(save_room(&conn, edited).unwrap_err().contains("vacate it"));
+
+        vacate_bed(&conn, asha, Some("2024-07-01".to_string()), None).unwrap();
+        let moved = allot_bed(&conn, allotment(bed_a, meena)).unwrap();
+        assert_eq!(moved.room_number.as_deref(), Some("101"));
+        assert_eq!(get_student_allotments(&conn, asha).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn movements_visitors_and_fees_are_kept_per_boarder() {
+        let conn = memory_db();
+        let mixed = save_hostel(&conn, hostel("Tapti House", "mixed")).unwrap();
+        let saved = save_room(&conn, room(mixed, "G1", &["A", "B"])).unwrap();
+        let boarder = student(&conn, "H4", "Male");
+        let day_scholar = student(&conn, "H5", "Female");
+        allot_bed(&conn, allotment(saved.beds[0].id.unwrap(), boarder)).unwrap();
+
+        let movement =
+            |student_id: i64, movement_type: &str, approved: Option<&str>| HostelMovement {
+                id: None,
+                student_id,
+                movement_type: movement_type.to_string(),
+                out_at: Some("2024-07-05 16:00".to_string()),
+                expected_back: Some("2024-07-07 18:00".to_string()),
+                returned_at: None,
+                purpose: Some("Home visit".to_string()),
+                escorted_by: Some("Rajesh (father)".to_string()),
+                approved_by: approved.map(str::to_string),
+                student_name: None,
+            };
+        assert!(check_out(&conn, movement(day_scholar, "outing", None))
+            .unwrap_err()
+            .contains("not a boarder"));
+        assert!(check_out(&conn, movement(boarder, "leave", None))
+            .unwrap_err()
+            .contains("approved"));
+        let id = check_out(&conn, movement(boarder, "leave", Some("Warden"))).unwrap();
+        assert!(check_out(&conn, movement(boarder, "outing", None)).is_err());
+        assert_eq!(get_occupancy(&conn).unwrap()[0].out, 1);
+        assert!(check_in(&conn, id, Some("2024-07-04 10:00".to_string())).is_err());
+        check_in(&conn, id, Some("2024-07-07 17:30".to_string())).unwrap();
+        let leave = get_movements(&conn, None, None, Some("leave".to_string()), false).unwrap();
+        assert_eq!(leave[0].returned_at.as_deref(), Some("2024-07-07 17:30"));
+
+        record_visitor(
+            &conn,
+            HostelVisitor {
+                id: None,
+                student_id: boarder,
+                visitor_name: "Sunita".to_string(),
+                relation: Some("Mother".to_string()),
+                phone: None,
+                visited_on: Some("2024-07-14".to_string()),
+                in_time: Some("10:00".to_string()),
+                out_time: None,
+                purpose: None,
+                student_name: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            get_visitors(&conn, Some(boarder), None, None)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let run = raise_fees(&conn, "2024-25 Term 1", None).unwrap();
+        assert_eq!((run.raised, run.total), (1, 15000.0));
+        assert_eq!(
+            raise_fees(&conn, "2024-25 Term 1", None)
+                .unwrap()
+                .already_raised,
+            1
+        );
+
+        let leaving = StatusChange {
+            status: "left".to_string(),
+            effective_date: None,
+            reason: Some("Moved city".to_string()),
+            passing_year: None,
+        };
+        change_status(
+            &conn,
+            boarder,
+            StatusChange {
+                status: "active".to_string(),
+                effective_date: None,
+                reason: None,
+                passing_year: None,
+            },
+        )
+        .unwrap();
+        let err = change_status(&conn, boarder, leaving).unwrap_err();
+        assert!(err.contains("holds a bed"), "{}", err);
+    }
+}
@@ -0,0 +1,758 @@
+use crate::date::Date;
+use crate::inventory::{
+    AuditReport, DepreciationRow, InventoryItem, StockMovement, StockVerification,
+};
+use crate::pdf::{Align, Document, Font, Page};
+use crate::repository;
+use crate::repository::inventory as repo;
+use crate::repository::school as school_repo;
+use crate::repository::staff as staff_repo;
+use crate::school::School;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+
+pub const CATEGORIES: [&str; 6] = ["furniture", "lab", "it", "sports", "stationery", "other"];
+pub const ITEM_KINDS: [&str; 2] = ["asset", "consumable"];
+pub const CONDITIONS: [&str; 7] = ["new", "good", "fair", "poor", "damaged", "lost", "disposed"];
+pub const MOVEMENT_TYPES: [&str; 3] = ["receipt", "issue", "adjustment"];
+pub const DEPRECIATION_METHODS: [&str; 2] = ["straight_line", "written_down"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, allowed: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn optional_date(value: Option<String>) -> Result<Option<String>, String> {
+    non_blank(value)
+        .map(|date| Date::parse(&date).map(|d| d.to_string()))
+        .transpose()
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn file_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// First calendar year of the April-to-March financial year the date falls in.
+fn financial_year(date: Date) -> i32 {
+    if date.month >= 4 {
+        date.year
+    } else {
+        date.year - 1
+    }
+}
+
+fn find_item(conn: &Connection, id: i64) -> Result<InventoryItem, String> {
+    repo::find_item(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item with id {} not found", id))
+}
+
+fn find_verification(conn: &Connection, id: i64) -> Result<StockVerification, String> {
+    repo::find_verification(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Stock verification with id {} not found", id))
+}
+
+pub fn get_items(
+    conn: &Connection,
+    category: Option<String>,
+    item_kind: Option<String>,
+) -> Result<Vec<InventoryItem>, String> {
+    let category = non_blank(category)
+        .map(|value| one_of(&value, &CATEGORIES, "category"))
+        .transpose()?;
+    let item_kind = non_blank(item_kind)
+        .map(|value| one_of(&value, &ITEM_KINDS, "item kind"))
+        .transpose()?;
+    repo::find_items(conn, category.as_deref(), item_kind.as_deref()).map_err(|e| e.to_string())
+}
+
+pub fn save_item(conn: &Connection, item: InventoryItem) -> Result<i64, String> {
+    let item = InventoryItem {
+        code: item.code.trim().to_string(),
+        name: item.name.trim().to_string(),
+        category: one_of(&item.category, &CATEGORIES, "category")?,
+        item_kind: one_of(&item.item_kind, &ITEM_KINDS, "item kind")?,
+        unit: non_blank(item.unit),
+        purchase_date: optional_date(item.purchase_date)?,
+        vendor: non_blank(item.vendor),
+        invoice_number: non_blank(item.invoice_number),
+        location: non_blank(item.location),
+        condition: Some(match non_blank(item.condition) {
+            Some(condition) => one_of(&condition, &CONDITIONS, "condition")?,
+            None => "good".to_string(),
+        }),
+        depreciation_method: non_blank(item.depreciation_method)
+            .map(|method| one_of(&method, &DEPRECIATION_METHODS, "depreciation method"))
+            .transpose()?,
+        ..item
+    };
+    if item.code.is_empty() || item.name.is_empty() {
+        return Err("Item code and name are required".to_string());
+    }
+    if !item.purchase_cost.is_finite() || item.purchase_cost < 0.0 {
+        return Err("The purchase cost cannot be negative".to_string());
+    }
+    if item.item_kind == "asset" && item.reorder_level.is_some() {
+        return Err("Reorder levels apply to consumables only".to_string());
+    }
+    let item = match item.depreciation_method.as_deref() {
+        Some(_) if item.item_kind == "consumable" => {
+            return Err("Consumables are not depreciated".to_string());
+        }
+        Some(method) => {
+            if item.useful_life_years.unwrap_or(0) == 0 {
+                return Err("Set the useful life in years to depreciate the item".to_string());
+            }
+            let salvage = item.salvage_value.unwrap_or(0.0);
+            if !(0.0..=item.purchase_cost).contains(&salvage) {
+                return Err("The salvage value must be between 0 and the cost".to_string());
+            }
+            let rate = match method {
+                "written_down" => match item.depreciation_rate {
+                    Some(rate) if rate > 0.0 && rate < 100.0 => Some(rate),
+                    _ => {
+                        return Err(
+                            "Written-down depreciation needs a rate between 0 and 100 percent"
+                                .to_string(),
+                        )
+                    }
+                },
+                _ => None,
+            };
+            InventoryItem {
+                depreciation_rate: rate,
+                ..item
+            }
+        }
+        None => InventoryItem {
+            useful_life_years: None,
+            depreciation_rate: None,
+            salvage_value: None,
+            ..item
+        },
+    };
+    if repo::code_taken(conn, &item.code, item.id).map_err(|e| e.to_string())? {
+        return Err(format!("Item code {} is already in use", item.code));
+    }
+    let existing = item.id.map(|id| find_item(conn, id)).transpose()?;
+    if let Some(staff_id) = item.assigned_to {
+        let staff = staff_repo::find_by_id(conn, staff_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Staff member with id {} not found", staff_id))?;
+        let reassigned = existing
+            .as_ref()
+            .map_or(true, |existing| existing.assigned_to != Some(staff_id));
+        if reassigned && !is_active(staff.status.as_deref()) {
+            return Err(format!("{} is no longer active", staff.name));
+        }
+    }
+
+    match item.id {
+        Some(id) => {
+            repo::update_item(conn, id, &item).map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+        None => {
+            if item.quantity < 0 {
+                return Err("The opening quantity cannot be negative".to_string());
+            }
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            let id = repo::insert_item(&tx, &item).map_err(|e| e.to_string())?;
+            if item.quantity > 0 {
+                let moved_on = match &item.purchase_date {
+                    Some(date) => date.clone(),
+                    None => today(&tx)?.to_string(),
+                };
+                let opening = StockMovement {
+                    id: None,
+                    item_id: id,
+                    movement_type: "receipt".to_string(),
+                    quantity: item.quantity,
+                    moved_on: Some(moved_on),
+                    reference: Some(
+                        item.invoice_number
+                            .clone()
+                            .unwrap_or_else(|| "Opening stock".to_string()),
+                    ),
+                    issued_to: None,
+                    remarks: None,
+                    item_name: None,
+                };
+                repo::insert_movement(&tx, &opening).map_err(|e| e.to_string())?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+    }
+}
+
+/// Removes an item entered by mistake. Items that went through a stock verification are
+/// part of the audit trail and are marked disposed instead.
+pub fn delete_item(conn: &Connection, id: i64) -> Result<(), String> {
+    let item = find_item(conn, id)?;
+    if repo::was_verified(conn, id).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "{} is part of a stock verification; mark it disposed instead",
+            item.name
+        ));
+    }
+    repo::delete_item(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn record_movement(conn: &Connection, movement: StockMovement) -> Result<i64, String> {
+    let movement = StockMovement {
+        movement_type: one_of(&movement.movement_type, &MOVEMENT_TYPES, "movement type")?,
+        moved_on: Some(match optional_date(movement.moved_on)? {
+            Some(date) => date,
+            None => today(conn)?.to_string(),
+        }),
+        reference: non_blank(movement.reference),
+        issued_to: non_blank(movement.issued_to),
+        remarks: non_blank(movement.remarks),
+        ..movement
+    };
+    let item = find_item(conn, movement.item_id)?;
+    match movement.movement_type.as_str() {
+        "adjustment" if movement.quantity == 0 => {
+            return Err("An adjustment must change the quantity".to_string());
+        }
+        "adjustment" => {}
+        _ if movement.quantity <= 0 => {
+            return Err("The quantity must be more than zero".to_string());
+        }
+        _ => {}
+    }
+    if movement.movement_type == "issue" && item.item_kind != "consumable" {
+        return Err(
+            "Assets are not issued; change their location or assigned staff instead".to_string(),
+        );
+    }
+    let change = match movement.movement_type.as_str() {
+        "issue" => -movement.quantity,
+        _ => movement.quantity,
+    };
+    if item.quantity + change < 0 {
+        return Err(format!(
+            "Only {} {} of {} in stock",
+            item.quantity,
+            item.unit.as_deref().unwrap_or("unit(s)"),
+            item.name
+        ));
+    }
+    repo::insert_movement(conn, &movement).map_err(|e| e.to_string())
+}
+
+pub fn get_movements(conn: &Connection, item_id: i64) -> Result<Vec<StockMovement>, String> {
+    repo::find_movements(conn, item_id).map_err(|e| e.to_string())
+}
+
+pub fn get_reorder_list(conn: &Connection) -> Result<Vec<InventoryItem>, String> {
+    Ok(repo::find_items(conn, None, Some("consumable"))
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|item| item.below_reorder)
+        .collect())
+}
+
+/// Year-by-year values over the item's useful life. The year of purchase bears a full
+/// year's charge, and the value never falls below the salvage value.
+fn depreciation_schedule(item: &InventoryItem) -> Result<Vec<DepreciationRow>, String> {
+    let method = item
+        .depreciation_method
+        .as_deref()
+        .ok_or_else(|| format!("No depreciation is set for {}", item.name))?;
+    let purchased = item
+        .purchase_date
+        .as_deref()
+        .ok_or_else(|| format!("Set the purchase date of {} to depreciate it", item.name))?;
+    let first_year = financial_year(Date::parse(purchased)?);
+    let life = item.useful_life_years.unwrap_or(1).max(1);
+    let salvage = item.salvage_value.unwrap_or(0.0);
+    let mut value = item.purchase_cost;
+    let mut rows = Vec::new();
+    for year in 0..life {
+        let charge = match method {
+            "written_down" => value * item.depreciation_rate.unwrap_or(0.0) / 100.0,
+            _ if year + 1 == life => value - salvage,
+            _ => (item.purchase_cost - salvage) / f64::from(life),
+        };
+        let charge = round2(charge.min(value - salvage).max(0.0));
+        let start = first_year + year as i32;
+        rows.push(DepreciationRow {
+            financial_year: format!("{}-{:02}", start, (start + 1) % 100),
+            opening_value: round2(value),
+            depreciation: charge,
+            closing_value: round2(value - charge),
+        });
+        value = round2(value - charge);
+    }
+    Ok(rows)
+}
+
+pub fn get_depreciation_schedule(
+    conn: &Connection,
+    item_id: i64,
+) -> Result<Vec<DepreciationRow>, String> {
+    depreciation_schedule(&find_item(conn, item_id)?)
+}
+
+/// Value at the start of the financial year `as_of` falls in. Items without depreciation
+/// are carried at cost.
+fn book_value(item: &InventoryItem, as_of: Date) -> Result<f64, String> {
+    let purchased = match (&item.depreciation_method, &item.purchase_date) {
+        (Some(_), Some(purchased)) => financial_year(Date::parse(purchased)?),
+        _ => return Ok(item.purchase_cost),
+    };
+    let year = financial_year(as_of);
+    if year < purchased {
+        return Ok(item.purchase_cost);
+    }
+    let schedule = depreciation_schedule(item)?;
+    Ok(match schedule.get((year - purchased) as usize) {
+        Some(row) => row.opening_value,
+        None => schedule
+            .last()
+            .map_or(item.purchase_cost, |row| row.closing_value),
+    })
+}
+
+/// Opens the year's verification. Every item not lost or disposed of is listed with the
+/// units on hand and its book value as they stand today.
+pub fn start_verification(
+    conn: &Connection,
+    label: &str,
+    verified_by: Option<String>,
+) -> Result<StockVerification, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err("Name the verification, for instance 2024-25".to_string());
+    }
+    if let Some(open) = repo::open_verification_label(conn).map_err(|e| e.to_string())? {
+        return Err(format!("Verification {} is still open", open));
+    }
+    if repo::label_taken(conn, label).map_err(|e| e.to_string())? {
+        return Err(format!("A verification named {} already exists", label));
+    }
+    let items = repo::find_items_in_use(conn).map_err(|e| e.to_string())?;
+    if items.is_empty() {
+        return Err("There are no items to verify".to_string());
+    }
+    let started_on = today(conn)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = repo::insert_verification(
+        &tx,
+        label,
+        &started_on.to_string(),
+        non_blank(verified_by).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    for item in &items {
+        let value = book_value(item, started_on)?;
+        repo::insert_line(&tx, id, item.id.unwrap_or_default(), item.quantity, value)
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    find_verification(conn, id)
+}
+
+pub fn get_verifications(conn: &Connection) -> Result<Vec<StockVerification>, String> {
+    repo::find_verifications(conn).map_err(|e| e.to_string())
+}
+
+pub fn record_count(
+    conn: &Connection,
+    verification_id: i64,
+    item_id: i64,
+    found_quantity: i64,
+    condition: Option<String>,
+    remarks: Option<String>,
+) -> Result<(), String> {
+    let verification = find_verification(conn, verification_id)?;
+    if verification.status != "open" {
+        return Err(format!("Verification {} is closed", verification.label));
+    }
+    if found_quantity < 0 {
+        return Err("The quantity found cannot be negative".to_string());
+    }
+    let condition = non_blank(condition)
+        .map(|value| one_of(&value, &CONDITIONS, "condition"))
+        .transpose()?;
+    let updated = repo::update_count(
+        conn,
+        verification_id,
+        item_id,
+        found_quantity,
+        condition.as_deref(),
+        non_blank(remarks).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!(
+            "Item with id {} is not part of verification {}",
+            item_id, verification.label
+        ));
+    }
+    Ok(())
+}
+
+/// Closes the verification once every item is counted. Differences are posted as
+/// adjustments so stock matches what was found, and conditions noted are carried over.
+pub fn close_verification(conn: &Connection, id: i64) -> Result<StockVerification, String> {
+    let verification = find_verification(conn, id)?;
+    if verification.status != "open" {
+        return Err(format!(
+            "Verification {} is already closed",
+            verification.label
+        ));
+    }
+    if verification.counted < verification.items {
+        return Err(format!(
+            "{} item(s) are not counted yet",
+            verification.items - verification.counted
+        ));
+    }
+    let completed_on = today(conn)?.to_string();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for line in repo::find_lines(&tx, id).map_err(|e| e.to_string())? {
+        let difference = line.found_quantity.unwrap_or_default() - line.expected_quantity;
+        if difference != 0 {
+            let adjustment = StockMovement {
+                id: None,
+                item_id: line.item_id,
+                movement_type: "adjustment".to_string(),
+                quantity: difference,
+                moved_on: Some(completed_on.clone()),
+                reference: Some(format!("Verification {}", verification.label)),
+                issued_to: None,
+                remarks: line.remarks.clone(),
+                item_name: None,
+            };
+            repo::insert_movement(&tx, &adjustment).map_err(|e| e.to_string())?;
+        }
+        if let Some(condition) = &line.condition {
+            repo::set_condition(&tx, line.item_id, condition).map_err(|e| e.to_string())?;
+        }
+    }
+    repo::complete_verification(&tx, id, &completed_on).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    find_verification(conn, id)
+}
+
+/// Every line of the verification with the book value of units not found.
+pub fn get_audit_report(conn: &Connection, verification_id: i64) -> Result<AuditReport, String> {
+    let verification = find_verification(conn, verification_id)?;
+    let mut lines = repo::find_lines(conn, verification_id).map_err(|e| e.to_string())?;
+    for line in &mut lines {
+        let missing =
+            line.expected_quantity - line.found_quantity.unwrap_or(line.expected_quantity);
+        if missing > 0 && line.expected_quantity > 0 {
+            line.shortfall_value =
+                round2(line.book_value * missing as f64 / line.expected_quantity as f64);
+        }
+    }
+    Ok(AuditReport {
+        book_value: round2(lines.iter().map(|line| line.book_value).sum()),
+        shortfall_value: round2(lines.iter().map(|line| line.shortfall_value).sum()),
+        verification,
+        lines,
+    })
+}
+
+const AUDIT_COLUMNS: [(&str, f32); 8] = [
+    ("Code", 55.0),
+    ("Item", 120.0),
+    ("Location", 75.0),
+    ("Expected", 45.0),
+    ("Found", 40.0),
+    ("Condition", 55.0),
+    ("Book value", 60.0),
+    ("Shortfall", 55.0),
+];
+
+fn draw_audit_header(page: &mut Page, school: Option<&School>, report: &AuditReport) -> f32 {
+    let center = page.width() / 2.0;
+    let margin = 40.0;
+    let mut y = page.height() - 50.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            15.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 20.0;
+    }
+    let verification = &report.verification;
+    let title = format!("Stock Verification {}", verification.label);
+    page.text_aligned(center, y, 12.0, Font::Bold, Align::Center, &title);
+    y -= 18.0;
+    let details = format!(
+        "Started: {}    Completed: {}    Verified by: {}",
+        verification.started_on,
+        verification.completed_on.as_deref().unwrap_or("open"),
+        verification.verified_by.as_deref().unwrap_or("-")
+    );
+    page.text(margin, y, 9.0, Font::Regular, &details);
+    y -= 13.0;
+    let summary = format!(
+        "Items: {}    Discrepancies: {}    Book value: {:.2}    Shortfall: {:.2}",
+        verification.items, verification.discrepancies, report.book_value, report.shortfall_value
+    );
+    page.text(margin, y, 9.0, Font::Regular, &summary);
+    y -= 16.0;
+    let mut x = margin;
+    for (label, width) in AUDIT_COLUMNS {
+        page.text(x + 2.0, y, 9.0, Font::Bold, label);
+        x += width;
+    }
+    y -= 5.0;
+    page.line(margin, y, page.width() - margin, y, 0.8);
+    y - 13.0
+}
+
+/// File name and PDF of the audit report, discrepancies marked with an asterisk.
+pub fn render_audit_pdf(
+    conn: &Connection,
+    verification_id: i64,
+) -> Result<(String, Vec<u8>), String> {
+    let report = get_audit_report(conn, verification_id)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let mut doc = Document::new("Stock Verification");
+    let mut page = doc.add_page();
+    let mut y = draw_audit_header(page, school.as_ref(), &report);
+    for line in &report.lines {
+        if y < 50.0 {
+            page = doc.add_page();
+            y = draw_audit_header(page, school.as_ref(), &report);
+        }
+        let found = match line.found_quantity {
+            Some(found) if found != line.expected_quantity => format!("{} *", found),
+            Some(found) => found.to_string(),
+            None => "-".to_string(),
+        };
+        let cells = [
+            line.code.clone(),
+            line.name.clone(),
+            line.location.clone().unwrap_or_default(),
+            line.expected_quantity.to_string(),
+            found,
+            line.condition.clone().unwrap_or_default(),
+            format!("{:.2}", line.book_value),
+            format!("{:.2}", line.shortfall_value),
+        ];
+        let mut x = 40.0;
+        for ((_, width), cell) in AUDIT_COLUMNS.iter().zip(cells.iter()) {
+            page.text(x + 2.0, y, 8.5, Font::Regular, cell);
+            x += width;
+        }
+        y -= 14.0;
+    }
+    let file_name = format!(
+        "Stock-Verification-{}.pdf",
+        file_part(&report.verification.label)
+    );
+    Ok((file_name, doc.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::memory_db;
+
+    fn item(code: &str, item_kind: &str, quantity: i64) -> InventoryItem {
+        InventoryItem {
+            id: None,
+            code: code.to_string(),
+            name: format!("Item {}", code),
+            category: if item_kind == "asset" {
+                "furniture"
+            } else {
+                "stationery"
+            }
+            .to_string(),
+            item_kind: item_kind.to_string(),
+            unit: Some("pieces".to_string()),
+            purchase_date: Some("2023-08-01".to_string()),
+            vendor: Some("Sharma Traders".to_string()),
+            invoice_number: None,
+            purchase_cost: 0.0,
+            location: Some("Store room".to_string()),
+            assigned_to: None,
+            condition: None,
+            reorder_level: None,
+            depreciation_method: None,
+            useful_life_years: None,
+            depreciation_rate: None,
+            salvage_value: None,
+            quantity,
+            assigned_name: None,
+            below_reorder: false,
+        }
+    }
+
+    fn movement(item_id: i64, movement_type: &str, quantity: i64) -> StockMovement {
+        StockMovement {
+            id: None,
+            item_id,
+            movement_type: movement_type.to_string(),
+            quantity,
+            moved_on: Some("2024-07-01".to_string()),
+            reference: None,
+            issued_to: Some("Class-5 A".to_string()),
+            remarks: None,
+            item_name: None,
+        }
+    }
+
+    #[test]
+    fn consumables_are_issued_from_stock_and_flagged_for_reorder() {
+        let conn = memory_db();
+        let chalk = save_item(
+            &conn,
+            InventoryItem {
+                reorder_level: Some(70),
+                ..item("CH-01", "consumable", 100)
+            },
+        )
+        .unwrap();
+        let bench = save_item(&conn, item("BN-01", "asset", 40)).unwrap();
+
+        record_movement(&conn, movement(chalk, "issue", 30)).unwrap();
+        assert_eq!(
+            record_movement(&conn, movement(chalk, "issue", 80)).unwrap_err(),
+            "Only 70 pieces of Item CH-01 in stock"
+        );
+        let reorder = get_reorder_list(&conn).unwrap();
+        assert_eq!(reorder.len(), 1);
+        assert_eq!(reorder[0].quantity, 70);
+        record_movement(&conn, movement(chalk, "receipt", 50)).unwrap();
+        assert!(get_reorder_list(&conn).unwrap().is_empty());
+        assert_eq!(get_movements(&conn, chalk).unwrap().len(), 3);
+
+        assert!(record_movement(&conn, movement(bench, "issue", 1))
+            .unwrap_err()
+            .contains("not issued"));
+        record_movement(&conn, movement(bench, "adjustment", -2)).unwrap();
+        assert_eq!(find_item(&conn, bench).unwrap().quantity, 38);
+        assert!(save_item(
+            &conn,
+            InventoryItem {
+                reorder_level: Some(5),
+                ..item("BN-02", "asset", 1)
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn depreciation_runs_by_financial_year_down_to_salvage() {
+        let straight = InventoryItem {
+            purchase_cost: 50000.0,
+            salvage_value: Some(5000.0),
+            depreciation_method: Some("straight_line".to_string()),
+            useful_life_years: Some(5),
+            ..item("PR-01", "asset", 1)
+        };
+        let rows = depreciation_schedule(&straight).unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].financial_year, "2023-24");
+        assert_eq!(rows[0].depreciation, 9000.0);
+        assert_eq!(rows[4].financial_year, "2027-28");
+        assert_eq!(rows[4].closing_value, 5000.0);
+        let as_of = Date::parse("2025-02-10").unwrap();
+        assert_eq!(book_value(&straight, as_of).unwrap(), 41000.0);
+
+        let written_down = InventoryItem {
+            purchase_cost: 100000.0,
+            depreciation_method: Some("written_down".to_string()),
+            depreciation_rate: Some(15.0),
+            useful_life_years: Some(3),
+            ..item("LP-01", "asset", 1)
+        };
+        let charges: Vec<f64> = depreciation_schedule(&written_down)
+            .unwrap()
+            .iter()
+            .map(|row| row.depreciation)
+            .collect();
+        assert_eq!(charges, vec![15000.0, 12750.0, 10837.5]);
+    }
+
+    #[test]
+    fn verification_adjusts_stock_and_reports_shortfall() {
+        let conn = memory_db();
+        let chairs = save_item(
+            &conn,
+            InventoryItem {
+                purchase_cost: 20000.0,
+                ..item("CR-01", "asset", 40)
+            },
+        )
+        .unwrap();
+        let paper = save_item(&conn, item("PP-01", "consumable", 10)).unwrap();
+        save_item(
+            &conn,
+            InventoryItem {
+                condition: Some("disposed".to_string()),
+                ..item("OLD-01", "asset", 1)
+            },
+        )
+        .unwrap();
+
+        let verification = start_verification(&conn, "2024-25", Some("Accounts".into())).unwrap();
+        assert_eq!(verification.items, 2);
+        let id = verification.id;
+        assert!(start_verification(&conn, "2025-26", None).is_err());
+        record_count(&conn, id, chairs, 36, Some("fair".to_string()), None).unwrap();
+        assert_eq!(
+            close_verification(&conn, id).unwrap_err(),
+            "1 item(s) are not counted yet"
+        );
+        record_count(&conn, id, paper, 10, None, None).unwrap();
+        let closed = close_verification(&conn, id).unwrap();
+        assert_eq!(
+            (closed.status.as_str(), closed.discrepancies),
+            ("closed", 1)
+        );
+
+        let chair = find_item(&conn, chairs).unwrap();
+        assert_eq!(chair.quantity, 36);
+        assert_eq!(chair.condition.as_deref(), Some("fair"));
+        let report = get_audit_report(&conn, id).unwrap();
+        assert_eq!(report.shortfall_value, 2000.0);
+        assert!(record_count(&conn, id, paper, 9, None, None).is_err());
+        assert!(delete_item(&conn, chairs).unwrap_err().contains("disposed"));
+        let (file_name, pdf) = render_audit_pdf(&conn, id).unwrap();
+        assert_eq!(file_name, "Stock-Verification-2024-25.pdf");
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,538 @@
+use crate::date::Date;
+use crate::leave::{LeaveApplication, LeaveBalance, LeaveQuota, LeaveType};
+use crate::repository::leave as repo;
+use crate::repository::staff as staff_repo;
+use crate::service::calendar::{self, DaysOff};
+use crate::service::staff::is_active;
+use crate::staff::Staff;
+use rusqlite::Connection;
+
+pub const APPLICATION_STATUSES: [&str; 4] = ["pending", "approved", "rejected", "cancelled"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn find_type(conn: &Connection, leave_type: &str) -> Result<LeaveType, String> {
+    repo::find_type(conn, leave_type)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown leave type '{}'", leave_type))
+}
+
+fn find_staff(conn: &Connection, staff_id: i64) -> Result<Staff, String> {
+    staff_repo::find_by_id(conn, staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())
+}
+
+fn find_application(conn: &Connection, id: i64) -> Result<LeaveApplication, String> {
+    repo::find_application(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Leave application with id {} not found", id))
+}
+
+/// How much of `date` a full day of this type of leave takes: the part of the day that would
+/// have been worked, or all of it when the type counts weekly offs and holidays.
+pub fn leave_share(leave_type: &LeaveType, date: Date, days_off: &DaysOff) -> f64 {
+    if leave_type.include_weekly_offs {
+        1.0
+    } else {
+        days_off.working_share(date)
+    }
+}
+
+/// Whether staff on this type of leave are counted as on leave for `date`.
+pub fn counts_day(leave_type: &LeaveType, date: Date, days_off: &DaysOff) -> bool {
+    leave_share(leave_type, date, days_off) > 0.0
+}
+
+/// Leave days between two dates, both inclusive. The weekly off and full-day holidays are not
+/// counted, and a half-day holiday counts as half, unless the leave type says otherwise.
+fn count_days(
+    leave_type: &LeaveType,
+    start: Date,
+    end: Date,
+    half_day: bool,
+    days_off: &DaysOff,
+) -> Result<f64, String> {
+    if end < start {
+        return Err("Leave cannot end before it starts".to_string());
+    }
+    if start.year != end.year {
+        return Err(
+            "Leave crossing into a new year must be applied for separately for each year"
+                .to_string(),
+        );
+    }
+    if half_day && start != end {
+        return Err("A half day of leave must start and end on the same date".to_string());
+    }
+    let days: f64 = (0..=start.days_until(end))
+        .map(|offset| leave_share(leave_type, start.add_days(offset), days_off))
+        .sum();
+    if days == 0.0 {
+        return Err("The leave period only covers weekly offs and holidays".to_string());
+    }
+    Ok(if half_day { days.min(0.5) } else { days })
+}
+
+fn balance_for(
+    conn: &Connection,
+    staff: &Staff,
+    leave_type: &LeaveType,
+    year: i32,
+) -> Result<LeaveBalance, String> {
+    let staff_id = staff.id.unwrap_or_default();
+    let opening = repo::find_opening(conn, staff_id, &leave_type.leave_type, year)
+        .map_err(|e| e.to_string())?;
+    let quota = repo::find_quota(conn, &staff.employment_type, &leave_type.leave_type)
+        .map_err(|e| e.to_string())?
+        .unwrap_or(0.0);
+    let used = repo::sum_days(conn, staff_id, &leave_type.leave_type, year, "approved")
+        .map_err(|e| e.to_string())?;
+    let pending = repo::sum_days(conn, staff_id, &leave_type.leave_type, year, "pending")
+        .map_err(|e| e.to_string())?;
+    Ok(LeaveBalance {
+        leave_type: leave_type.leave_type.clone(),
+        name: leave_type.name.clone(),
+        paid: leave_type.paid,
+        opening,
+        quota,
+        used,
+        pending,
+        available: leave_type.paid.then_some(opening + quota - used),
+    })
+}
+
+pub fn get_leave_types(conn: &Connection) -> Result<Vec<LeaveType>, String> {
+    repo::find_types(conn).map_err(|e| e.to_string())
+}
+
+pub fn update_leave_type(conn: &Connection, leave_type: LeaveType) -> Result<(), String> {
+    if leave_type.short_code.trim().is_empty() || leave_type.name.trim().is_empty() {
+        return Err("Leave type code and name are required".to_string());
+    }
+    if leave_type.max_carry_forward.is_some_and(|max| max < 0.0) {
+        return Err("Carry-forward limit cannot be negative".to_string());
+    }
+    let updated = repo::update_type(conn, &leave_type).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Unknown leave type '{}'", leave_type.leave_type));
+    }
+    Ok(())
+}
+
+pub fn get_leave_quotas(conn: &Connection) -> Result<Vec<LeaveQuota>, String> {
+    repo::find_quotas(conn).map_err(|e| e.to_string())
+}
+
+pub fn set_leave_quota(conn: &Connection, mut quota: LeaveQuota) -> Result<(), String> {
+    quota.employment_type = quota.employment_type.trim().to_string();
+    if quota.employment_type.is_empty() {
+        return Err("Employment type is required".to_string());
+    }
+    if quota.days_per_year < 0.0 {
+        return Err("Leave quota cannot be negative".to_string());
+    }
+    find_type(conn, &quota.leave_type)?;
+    repo::upsert_quota(conn, &quota).map_err(|e| e.to_string())
+}
+
+pub fn apply_leave(conn: &Connection, mut application: LeaveApplication) -> Result<i64, String> {
+    let staff = find_staff(conn, application.staff_id)?;
+    if !is_active(staff.status.as_deref()) {
+        return Err(format!("{} is not an active staff member", staff.name));
+    }
+    let leave_type = find_type(conn, &application.leave_type)?;
+    let start = Date::parse(&application.start_date)?;
+    let end = Date::parse(&application.end_date)?;
+    let days_off = calendar::days_off(conn, start, end)?;
+    let days = count_days(&leave_type, start, end, application.half_day, &days_off)?;
+    application.start_date = start.to_string();
+    application.end_date = end.to_string();
+    application.reason = non_blank(application.reason);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if repo::has_overlap(
+        &tx,
+        staff.id.unwrap_or_default(),
+        &application.start_date,
+        &application.end_date,
+    )
+    .map_err(|e| e.to_string())?
+    {
+        return Err("Leave has already been applied for some of these dates".to_string());
+    }
+    let balance = balance_for(&tx, &staff, &leave_type, start.year)?;
+    if let Some(available) = balance.available {
+        if days > available - balance.pending {
+            return Err(format!(
+                "Only {} day(s) of {} available ({} pending approval)",
+                available - balance.pending,
+                leave_type.name,
+                balance.pending
+            ));
+        }
+    }
+    let id = repo::insert_application(&tx, &application, days).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub fn approve_leave(
+    conn: &Connection,
+    id: i64,
+    decided_by: Option<String>,
+    note: Option<String>,
+) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let application = find_application(&tx, id)?;
+    if application.status.as_deref() != Some("pending") {
+        return Err("Only pending applications can be approved".to_string());
+    }
+    let staff = find_staff(&tx, application.staff_id)?;
+    let leave_type = find_type(&tx, &application.leave_type)?;
+    let year = Date::parse(&application.start_date)?.year;
+    let days = application.days.unwrap_or_default();
+    if let Some(available) = balance_for(&tx, &staff, &leave_type, year)?.available {
+        if days > available {
+            return Err(format!(
+                "Only {} day(s) of {} left to approve",
+                available, leave_type.name
+            ));
+        }
+    }
+    repo::set_application_status(
+        &tx,
+        id,
+        "approved",
+        non_blank(decided_by).as_deref(),
+        non_blank(note).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn reject_leave(
+    conn: &Connection,
+    id: i64,
+    decided_by: Option<String>,
+    note: Option<String>,
+) -> Result<(), String> {
+    let application = find_application(conn, id)?;
+    if application.status.as_deref() != Some("pending") {
+        return Err("Only pending applications can be rejected".to_string());
+    }
+    repo::set_application_status(
+        conn,
+        id,
+        "rejected",
+        non_blank(decided_by).as_deref(),
+        non_blank(note).as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Withdraws a pending or approved application; approved days go back to the balance.
+pub fn cancel_leave(conn: &Connection, id: i64) -> Result<(), String> {
+    let application = find_application(conn, id)?;
+    if !matches!(application.status.as_deref(), Some("pending" | "approved")) {
+        return Err("Only pending or approved applications can be cancelled".to_string());
+    }
+    repo::set_application_status(
+        conn,
+        id,
+        "cancelled",
+        application.decided_by.as_deref(),
+        application.decision_note.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_leave_applications(
+    conn: &Connection,
+    staff_id: Option<i64>,
+    status: Option<&str>,
+) -> Result<Vec<LeaveApplication>, String> {
+    let status = status
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty());
+    if let Some(status) = &status {
+        if !APPLICATION_STATUSES.contains(&status.as_str()) {
+            return Err(format!(
+                "Invalid leave status '{}'. Expected one of: {}",
+                status,
+                APPLICATION_STATUSES.join(", ")
+            ));
+        }
+    }
+    repo::find_applications(conn, staff_id, status.as_deref()).map_err(|e| e.to_string())
+}
+
+pub fn get_leave_balances(
+    conn: &Connection,
+    staff_id: i64,
+    year: i32,
+) -> Result<Vec<LeaveBalance>, String> {
+    let staff = find_staff(conn, staff_id)?;
+    get_leave_types(conn)?
+        .iter()
+        .map(|leave_type| balance_for(conn, &staff, leave_type, year))
+        .collect()
+}
+
+/// Sets next year's opening balance for every active staff member and carry-forward leave
+/// type to what is left of `from_year`, capped by the type's limit. Running it again
+/// recomputes the same figures. Returns the number of balances written.
+pub fn carry_forward_leave(conn: &Connection, from_year: i32) -> Result<usize, String> {
+    let carried_types: Vec<LeaveType> = get_leave_types(conn)?
+        .into_iter()
+        .filter(|t| t.carry_forward && t.paid)
+        .collect();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let staff = staff_repo::find_all(&tx).map_err(|e| e.to_string())?;
+    let mut written = 0;
+    for member in staff.iter().filter(|s| is_active(s.status.as_deref())) {
+        for leave_type in &carried_types {
+            let left = balance_for(&tx, member, leave_type, from_year)?
+                .available
+                .unwrap_or(0.0)
+                .max(0.0);
+            let carried = match leave_type.max_carry_forward {
+                Some(max) => left.min(max),
+                None => left,
+            };
+            repo::upsert_opening(
+                &tx,
+                member.id.unwrap_or_default(),
+                &leave_type.leave_type,
+                from_year + 1,
+                carried,
+            )
+            .map_err(|e| e.to_string())?;
+            written += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::staff::create_staff;
+    use crate::test_support::{memory_db, sample_staff};
+
+    fn full_timer(conn: &Connection, name: &str) -> i64 {
+        let mut staff = sample_staff(name);
+        staff.employment_type = "full-time".to_string();
+        create_staff(conn, staff).unwrap()
+    }
+
+    fn application(staff_id: i64, leave_type: &str, start: &str, end: &str) -> LeaveApplication {
+        LeaveApplication {
+            id: None,
+            staff_id,
+            leave_type: leave_type.to_string(),
+            start_date: start.to_string(),
+            end_date: end.to_string(),
+            half_day: false,
+            days: None,
+            reason: Some("Family function".to_string()),
+            status: None,
+            decided_by: None,
+            decided_on: None,
+            decision_note: None,
+            staff_name: None,
+            created_at: None,
+        }
+    }
+
+    fn balance(conn: &Connection, staff_id: i64, leave_type: &str, year: i32) -> LeaveBalance {
+        get_leave_balances(conn, staff_id, year)
+            .unwrap()
+            .into_iter()
+            .find(|b| b.leave_type == leave_type)
+            .unwrap()
+    }
+
+    #[test]
+    fn applications_skip_sundays_and_need_approval() {
+        let conn = memory_db();
+        let id = full_timer(&conn, "Meera");
+
+        // Friday to Monday: the Sunday in between is a weekly off.
+        let leave =
+            apply_leave(&conn, application(id, "casual", "2025-05-30", "2025-06-02")).unwrap();
+        let stored = &get_leave_applications(&conn, Some(id), Some("pending")).unwrap()[0];
+        assert_eq!(stored.days, Some(3.0));
+        assert_eq!(balance(&conn, id, "casual", 2025).pending, 3.0);
+
+        assert!(
+            apply_leave(&conn, application(id, "sick", "2025-06-02", "2025-06-02"))
+                .unwrap_err()
+                .contains("already been applied")
+        );
+
+        approve_leave(&conn, leave, Some("Principal".to_string()), None).unwrap();
+        assert!(approve_leave(&conn, leave, None, None).is_err());
+        let casual = balance(&conn, id, "casual", 2025);
+        assert_eq!((casual.used, casual.available), (3.0, Some(9.0)));
+
+        cancel_leave(&conn, leave).unwrap();
+        assert_eq!(balance(&conn, id, "casual", 2025).available, Some(12.0));
+    }
+
+    #[test]
+    fn quotas_limit_paid_leave_but_not_unpaid_leave() {
+        let conn = memory_db();
+        let id = full_timer(&conn, "Meera");
+
+        let mut half = application(id, "casual", "2025-01-06", "2025-01-06");
+        half.half_day = true;
+        apply_leave(&conn, half).unwrap();
+        assert!(
+            apply_leave(&conn, application(id, "casual", "2025-02-03", "2025-02-15"))
+                .unwrap_err()
+                .contains("11.5")
+        );
+        let rejected =
+            apply_leave(&conn, application(id, "casual", "2025-02-03", "2025-02-14")).unwrap();
+        reject_leave(&conn, rejected, None, Some("Exams".to_string())).unwrap();
+
+        apply_leave(&conn, application(id, "unpaid", "2025-03-03", "2025-03-31")).unwrap();
+        assert_eq!(balance(&conn, id, "unpaid", 2025).available, None);
+
+        let mut part_timer = sample_staff("Arjun");
+        part_timer.employment_type = "Part-time".to_string();
+        let part_timer = create_staff(&conn, part_timer).unwrap();
+        assert!(apply_leave(
+            &conn,
+            application(part_timer, "earned", "2025-01-06", "2025-01-06")
+        )
+        .is_err());
+
+        set_leave_quota(
+            &conn,
+            LeaveQuota {
+                employment_type: "Part-time".to_string(),
+                leave_type: "earned".to_string(),
+                days_per_year: 5.0,
+            },
+        )
+        .unwrap();
+        apply_leave(
+            &conn,
+            application(part_timer, "earned", "2025-01-06", "2025-01-06"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn holidays_and_the_weekly_off_are_not_leave_days() {
+        use crate::calendar::{CalendarEntry, CalendarSettings};
+        use crate::service::academic_year::upsert_academic_year;
+
+        let conn = memory_db();
+        let year_id = upsert_academic_year(
+            &conn,
+            "2025 - 2026",
+            false,
+            Some("2025-06-01".to_string()),
+            Some("2026-04-30".to_string()),
+        )
+        .unwrap();
+        for (date, day_part) in [("2025-06-03", "full"), ("2025-06-04", "half")] {
+            calendar::save_entry(
+                &conn,
+                CalendarEntry {
+                    id: None,
+                    academic_year_id: year_id,
+                    kind: "holiday".to_string(),
+                    title: "Local festival".to_string(),
+                    start_date: date.to_string(),
+                    end_date: None,
+                    day_part: Some(day_part.to_string()),
+                    description: None,
+                },
+            )
+            .unwrap();
+        }
+
+        // Monday to Saturday: the full holiday is skipped and the half holiday counts half.
+        let meera = full_timer(&conn, "Meera");
+        apply_leave(
+            &conn,
+            application(meera, "casual", "2025-06-02", "2025-06-07"),
+        )
+        .unwrap();
+        assert_eq!(balance(&conn, meera, "casual", 2025).pending, 4.5);
+        let err = apply_leave(
+            &conn,
+            application(meera, "sick", "2025-06-03", "2025-06-03"),
+        )
+        .unwrap_err();
+        assert!(err.contains("weekly offs and holidays"), "{}", err);
+
+        let weekly_off = |day: &str| CalendarSettings {
+            weekly_off: day.to_string(),
+        };
+        assert!(calendar::save_settings(&conn, weekly_off("funday")).is_err());
+        calendar::save_settings(&conn, weekly_off(" Saturday ")).unwrap();
+        assert_eq!(
+            calendar::get_settings(&conn).unwrap().weekly_off,
+            "saturday"
+        );
+        let arjun = full_timer(&conn, "Arjun");
+        apply_leave(
+            &conn,
+            application(arjun, "casual", "2025-06-02", "2025-06-07"),
+        )
+        .unwrap();
+        assert_eq!(balance(&conn, arjun, "casual", 2025).pending, 3.5);
+    }
+
+    #[test]
+    fn maternity_leave_counts_calendar_days() {
+        let conn = memory_db();
+        let id = full_timer(&conn, "Meera");
+        let leave = apply_leave(
+            &conn,
+            application(id, "maternity", "2025-06-01", "2025-06-30"),
+        )
+        .unwrap();
+        approve_leave(&conn, leave, None, None).unwrap();
+        assert_eq!(balance(&conn, id, "maternity", 2025).used, 30.0);
+        assert!(
+            apply_leave(&conn, application(id, "casual", "2025-12-29", "2026-01-02"))
+                .unwrap_err()
+                .contains("new year")
+        );
+    }
+
+    #[test]
+    fn unused_balances_carry_forward_within_limits() {
+        let conn = memory_db();
+        let id = full_timer(&conn, "Meera");
+        let leave =
+            apply_leave(&conn, application(id, "sick", "2025-03-03", "2025-03-04")).unwrap();
+        approve_leave(&conn, leave, None, None).unwrap();
+
+        let mut earned = find_type(&conn, "earned").unwrap();
+        earned.max_carry_forward = Some(10.0);
+        update_leave_type(&conn, earned).unwrap();
+
+        // Sick and earned leave carry forward; casual and maternity leave do not.
+        assert_eq!(carry_forward_leave(&conn, 2025).unwrap(), 2);
+        assert_eq!(carry_forward_leave(&conn, 2025).unwrap(), 2);
+
+        let sick = balance(&conn, id, "sick", 2026);
+        assert_eq!((sick.opening, sick.available), (8.0, Some(18.0)));
+        assert_eq!(balance(&conn, id, "earned", 2026).opening, 10.0);
+        assert_eq!(balance(&conn, id, "casual", 2026).opening, 0.0);
+    }
+}
@@ -0,0 +1,628 @@
+use crate::date::Date;
+use crate::dues::StudentDue;
+use crate::library::{BookCopy, BorrowCount, LibraryBook, LibraryLoan, LibrarySettings};
+use crate::repository;
+use crate::repository::library as repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::students as student_repo;
+use crate::service::dues::post_due;
+use crate::service::lifecycle::is_on_roll;
+use crate::service::staff::is_active;
+use rusqlite::Connection;
+
+pub const MEMBER_TYPES: [&str; 2] = ["student", "staff"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, allowed: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<Date, String> {
+    match non_blank(value) {
+        Some(date) => Date::parse(&date),
+        None => today(conn),
+    }
+}
+
+/// Strips hyphens and spaces and checks the ISBN-10 or ISBN-13 check digit.
+pub fn normalize_isbn(isbn: &str) -> Result<String, String> {
+    let isbn: String = isbn
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let digits: Vec<u32> = isbn
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'X' if i == 9 && isbn.len() == 10 => Some(10),
+            _ => c.to_digit(10),
+        })
+        .collect::<Option<_>>()
+        .ok_or_else(|| format!("ISBN {} may only contain digits", isbn))?;
+    let valid = match digits.len() {
+        10 => {
+            digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (10 - i as u32) * d)
+                .sum::<u32>()
+                % 11
+                == 0
+        }
+        13 => {
+            digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| if i % 2 == 0 { *d } else { 3 * d })
+                .sum::<u32>()
+                % 10
+                == 0
+        }
+        _ => return Err(format!("ISBN {} must have 10 or 13 digits", isbn)),
+    };
+    if !valid {
+        return Err(format!("ISBN {} has a wrong check digit", isbn));
+    }
+    Ok(isbn)
+}
+
+pub fn get_settings(conn: &Connection) -> Result<LibrarySettings, String> {
+    repo::find_settings(conn).map_err(|e| e.to_string())
+}
+
+pub fn save_settings(conn: &Connection, settings: LibrarySettings) -> Result<(), String> {
+    if settings.student_loan_days == 0 || settings.staff_loan_days == 0 {
+        return Err("A loan must last at least one day".to_string());
+    }
+    if settings.student_max_books == 0 || settings.staff_max_books == 0 {
+        return Err("Members must be allowed at least one book".to_string());
+    }
+    if !settings.fine_per_day.is_finite() || settings.fine_per_day < 0.0 {
+        return Err("The fine per day cannot be negative".to_string());
+    }
+    repo::update_settings(conn, &settings).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_books(conn: &Connection, query: Option<String>) -> Result<Vec<LibraryBook>, String> {
+    repo::find_books(conn, non_blank(query).as_deref()).map_err(|e| e.to_string())
+}
+
+pub fn save_book(conn: &Connection, book: LibraryBook) -> Result<i64, String> {
+    let book = LibraryBook {
+        isbn: non_blank(book.isbn)
+            .map(|isbn| normalize_isbn(&isbn))
+            .transpose()?,
+        title: book.title.trim().to_string(),
+        author: book.author.trim().to_string(),
+        publisher: non_blank(book.publisher),
+        category: non_blank(book.category),
+        shelf_location: non_blank(book.shelf_location),
+        ..book
+    };
+    if book.title.is_empty() {
+        return Err("Book title is required".to_string());
+    }
+    if book.author.is_empty() {
+        return Err("Author is required".to_string());
+    }
+    match book.id {
+        Some(id) => {
+            if repo::update_book(conn, id, &book).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Book with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_book(conn, &book).map_err(|e| e.to_string()),
+    }
+}
+
+/// Removes a title and its copies. Titles that have been lent out keep their history;
+/// withdraw their copies instead.
+pub fn delete_book(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::count_loans_of_book(conn, id).map_err(|e| e.to_string())? > 0 {
+        return Err("This book has been lent out before; withdraw its copies instead".to_string());
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if repo::delete_book(&tx, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Book with id {} not found", id));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_copies(conn: &Connection, book_id: i64) -> Result<Vec<BookCopy>, String> {
+    repo::find_copies(conn, book_id).map_err(|e| e.to_string())
+}
+
+pub fn add_copy(conn: &Connection, copy: BookCopy) -> Result<i64, String> {
+    let copy = BookCopy {
+        accession_number: copy.accession_number.trim().to_string(),
+        barcode: non_blank(copy.barcode),
+        ..copy
+    };
+    if repo::find_book(conn, copy.book_id)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!("Book with id {} not found", copy.book_id));
+    }
+    if copy.accession_number.is_empty() {
+        return Err("Accession number is required".to_string());
+    }
+    // Either code is scanned at the desk, so neither may be mistaken for another copy.
+    for code in std::iter::once(&copy.accession_number).chain(copy.barcode.as_ref()) {
+        if repo::code_taken(conn, code).map_err(|e| e.to_string())? {
+            return Err(format!("{} is already used by another copy", code));
+        }
+    }
+    repo::insert_copy(conn, &copy).map_err(|e| e.to_string())
+}
+
+pub fn withdraw_copy(conn: &Connection, id: i64) -> Result<(), String> {
+    let copy = repo::find_copy(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Copy with id {} not found", id))?;
+    match copy.status.as_deref() {
+        Some("issued") => Err(format!(
+            "Copy {} is out on loan; return it before withdrawing it",
+            copy.accession_number
+        )),
+        Some("withdrawn") => Err(format!(
+            "Copy {} is already withdrawn",
+            copy.accession_number
+        )),
+        _ => {
+            repo::set_copy_status(conn, id, "withdrawn").map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+fn find_copy_by_code(conn: &Connection, code: &str) -> Result<BookCopy, String> {
+    let code = code.trim();
+    repo::find_copy_by_code(conn, code)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No copy has barcode or accession number {}", code))
+}
+
+/// Checks the member may borrow and returns their loan period and book limit.
+fn member_terms(
+    conn: &Connection,
+    settings: &LibrarySettings,
+    member_type: &str,
+    member_id: i64,
+) -> Result<(u32, u32), String> {
+    if member_type == "student" {
+        let status = student_repo::find_status(conn, member_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Student with id {} not found", member_id))?;
+        if !is_on_roll(status.as_deref()) {
+            return Err("Only students on the roll can borrow books".to_string());
+        }
+        Ok((settings.student_loan_days, settings.student_max_books))
+    } else {
+        let staff = staff_repo::find_by_id(conn, member_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Staff member with id {} not found", member_id))?;
+        if !is_active(staff.status.as_deref()) {
+            return Err(format!("{} is no longer active", staff.name));
+        }
+        Ok((settings.staff_loan_days, settings.staff_max_books))
+    }
+}
+
+fn find_loan(conn: &Connection, id: i64, as_of: &str) -> Result<LibraryLoan, String> {
+    repo::find_loan(conn, id, as_of)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Loan with id {} not found", id))
+}
+
+/// Lends a copy, found by barcode or accession number. The member must be under their
+/// book limit and have nothing overdue.
+pub fn issue_book(
+    conn: &Connection,
+    code: &str,
+    member_type: &str,
+    member_id: i64,
+    issued_on: Option<String>,
+) -> Result<LibraryLoan, String> {
+    let member_type = one_of(member_type, &MEMBER_TYPES, "member type")?;
+    let settings = get_settings(conn)?;
+    let issued_on = date_or_today(conn, issued_on)?;
+    let copy = find_copy_by_code(conn, code)?;
+    match copy.status.as_deref() {
+        Some("issued") => {
+            return Err(format!(
+                "Copy {} is already out on loan",
+                copy.accession_number
+            ))
+        }
+        Some("withdrawn") => {
+            return Err(format!("Copy {} has been withdrawn", copy.accession_number))
+        }
+        _ => {}
+    }
+
+    let (loan_days, max_books) = member_terms(conn, &settings, &member_type, member_id)?;
+    let overdue = repo::count_overdue_loans(conn, &member_type, member_id, &issued_on.to_string())
+        .map_err(|e| e.to_string())?;
+    if overdue > 0 {
+        return Err(format!(
+            "{} overdue book(s) must be returned first",
+            overdue
+        ));
+    }
+    let open = repo::count_open_loans(conn, &member_type, member_id).map_err(|e| e.to_string())?;
+    if open >= max_books {
+        return Err(format!(
+            "Already borrowed the limit of {} book(s)",
+            max_books
+        ));
+    }
+
+    let copy_id = copy.id.unwrap_or_default();
+    let due_date = issued_on.add_days(i64::from(loan_days));
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = repo::insert_loan(
+        &tx,
+        copy_id,
+        &member_type,
+        member_id,
+        &issued_on.to_string(),
+        &due_date.to_string(),
+    )
+    .map_err(|e| e.to_string())?;
+    repo::set_copy_status(&tx, copy_id, "issued").map_err(|e| e.to_string())?;
+    let loan = find_loan(&tx, id, &issued_on.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(loan)
+}
+
+/// Extends an open loan by a full loan period from today, up to the renewal limit.
+/// Overdue books must be returned (and fined) rather than renewed.
+pub fn renew_book(conn: &Connection, code: &str) -> Result<LibraryLoan, String> {
+    let settings = get_settings(conn)?;
+    let today = today(conn)?;
+    let copy = find_copy_by_code(conn, code)?;
+    let loan = repo::find_open_loan(conn, copy.id.unwrap_or_default(), &today.to_string())
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Copy {} is not out on loan", copy.accession_number))?;
+    if loan.days_overdue > 0 {
+        return Err("This book is overdue; return it instead of renewing it".to_string());
+    }
+    if loan.renewals >= settings.max_renewals {
+        return Err(format!(
+            "This loan has already been renewed {} time(s)",
+            loan.renewals
+        ));
+    }
+    let loan_days = if loan.member_type == "student" {
+        settings.student_loan_days
+    } else {
+        settings.staff_loan_days
+    };
+    let due_date = today.add_days(i64::from(loan_days));
+    repo::renew_loan(conn, loan.id, &due_date.to_string()).map_err(|e| e.to_string())?;
+    find_loan(conn, loan.id, &today.to_string())
+}
+
+/// Takes a copy back. Days past the due date are fined; a student's fine is raised as a
+/// due when the settings say so.
+pub fn return_book(
+    conn: &Connection,
+    code: &str,
+    returned_on: Option<String>,
+) -> Result<LibraryLoan, String> {
+    let settings = get_settings(conn)?;
+    let returned_on = date_or_today(conn, returned_on)?.to_string();
+    let copy = find_copy_by_code(conn, code)?;
+    let loan = repo::find_open_loan(conn, copy.id.unwrap_or_default(), &returned_on)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Copy {} is not out on loan", copy.accession_number))?;
+    if returned_on < loan.issued_on {
+        return Err("A book cannot be returned before it was issued".to_string());
+    }
+
+    let fine = (loan.days_overdue as f64 * settings.fine_per_day * 100.0).round() / 100.0;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let fine_due_id = if fine > 0.0 && loan.member_type == "student" && settings.post_fines_to_dues
+    {
+        let due = StudentDue {
+            id: None,
+            student_id: loan.member_id,
+            source: None,
+            reference: Some(format!("loan:{}", loan.id)),
+            description: format!(
+                "Library fine: '{}' returned {} day(s) late",
+                loan.title, loan.days_overdue
+            ),
+            amount: fine,
+            raised_on: Some(returned_on.clone()),
+            status: None,
+            settled_on: None,
+            settled_note: None,
+            student_name: None,
+            class_name: None,
+        };
+        Some(post_due(&tx, due, "library")?)
+    } else {
+        None
+    };
+    repo::close_loan(&tx, loan.id, &returned_on, fine, fine_due_id).map_err(|e| e.to_string())?;
+    repo::set_copy_status(&tx, loan.copy_id, "available").map_err(|e| e.to_string())?;
+    let loan = find_loan(&tx, loan.id, &returned_on)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(loan)
+}
+
+pub fn get_member_loans(
+    conn: &Connection,
+    member_type: &str,
+    member_id: i64,
+    include_returned: bool,
+) -> Result<Vec<LibraryLoan>, String> {
+    let member_type = one_of(member_type, &MEMBER_TYPES, "member type")?;
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    repo::find_member_loans(conn, &member_type, member_id, include_returned, &today)
+        .map_err(|e| e.to_string())
+}
+
+/// Open loans past their due date as of `as_of` (default: today), with the fine accrued.
+pub fn get_overdue(conn: &Connection, as_of: Option<String>) -> Result<Vec<LibraryLoan>, String> {
+    let settings = get_settings(conn)?;
+    let as_of = date_or_today(conn, as_of)?.to_string();
+    let mut loans = repo::find_overdue(conn, &as_of).map_err(|e| e.to_string())?;
+    for loan in &mut loans {
+        loan.fine_amount =
+            (loan.days_overdue as f64 * settings.fine_per_day * 100.0).round() / 100.0;
+    }
+    Ok(loans)
+}
+
+pub fn get_most_borrowed(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    limit: Option<u32>,
+) -> Result<Vec<BorrowCount>, String> {
+    let from = Date::parse(from.trim())?;
+    let to = Date::parse(to.trim())?;
+    if to < from {
+        return Err("The end date is before the start date".to_string());
+    }
+    repo::find_most_borrowed(
+        conn,
+        &from.to_string(),
+        &to.to_string(),
+        limit.unwrap_or(10).max(1),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Refuses when the student still has library books out; students cannot leave until
+/// they are returned.
+pub fn ensure_no_books_out(conn: &Connection, student_id: i64) -> Result<(), String> {
+    let open = repo::count_open_loans(conn, "student", student_id).map_err(|e| e.to_string())?;
+    if open > 0 {
+        return Err(format!(
+            "The student has {} library book(s) outstanding",
+            open
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::StatusChange;
+    use crate::service::dues::get_student_dues;
+    use crate::service::lifecycle::change_status;
+    use crate::service::staff::create_staff;
+    use crate::service::students::save_core;
+    use crate::test_support::{create_class, memory_db, sample_staff, sample_student};
+
+    fn book(title: &str, isbn: Option<&str>) -> LibraryBook {
+        LibraryBook {
+            id: None,
+            isbn: isbn.map(str::to_string),
+            title: title.to_string(),
+            author: "A. P. J. Abdul Kalam".to_string(),
+            publisher: None,
+            category: Some("Biography".to_string()),
+            shelf_location: Some("B-2".to_string()),
+            copies: 0,
+            available: 0,
+        }
+    }
+
+    fn copy(book_id: i64, accession_number: &str, barcode: Option<&str>) -> BookCopy {
+        BookCopy {
+            id: None,
+            book_id,
+            accession_number: accession_number.to_string(),
+            barcode: barcode.map(str::to_string),
+            status: None,
+            title: None,
+        }
+    }
+
+    fn student(conn: &Connection, gr_number: &str) -> i64 {
+        let class_id = create_class(conn, "Class-6 A");
+        save_core(conn, sample_student(&class_id.to_string(), gr_number).core).unwrap()
+    }
+
+    #[test]
+    fn isbns_are_checked_and_copies_need_unique_codes() {
+        assert_eq!(normalize_isbn("81-7371-146-1").unwrap(), "8173711461");
+        assert_eq!(
+            normalize_isbn("978-81-7371-146-6").unwrap(),
+            "9788173711466"
+        );
+        assert_eq!(normalize_isbn("0-8044-2957-x").unwrap(), "080442957X");
+        assert!(normalize_isbn("978-81-7371-146-7")
+            .unwrap_err()
+            .contains("check digit"));
+        assert!(normalize_isbn("12345").unwrap_err().contains("10 or 13"));
+
+        let conn = memory_db();
+        let id = save_book(&conn, book("Wings of Fire", Some("978-81-7371-146-6"))).unwrap();
+        assert!(save_book(&conn, book("Ignited Minds", Some("9788173711467"))).is_err());
+        add_copy(&conn, copy(id, "ACC-1", Some("890001"))).unwrap();
+        add_copy(&conn, copy(id, "ACC-2", None)).unwrap();
+        let err = add_copy(&conn, copy(id, "acc-1", None)).unwrap_err();
+        assert!(err.contains("already used"), "{}", err);
+        assert!(add_copy(&conn, copy(id, "ACC-3", Some("ACC-2"))).is_err());
+
+        let found = get_books(&conn, Some("890001".to_string())).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].copies, found[0].available), (2, 2));
+        assert_eq!(found[0].isbn.as_deref(), Some("9788173711466"));
+    }
+
+    #[test]
+    fn late_returns_are_fined_to_student_dues() {
+        let conn = memory_db();
+        let book_id = save_book(&conn, book("Wings of Fire", None)).unwrap();
+        for n in 1..=3 {
+            add_copy(&conn, copy(book_id, &format!("ACC-{}", n), None)).unwrap();
+        }
+        let student_id = student(&conn, "L1");
+
+        let issue = |code: &str, on: &str| {
+            issue_book(&conn, code, "student", student_id, Some(on.to_string()))
+        };
+        let loan = issue("ACC-1", "2024-07-01").unwrap();
+        assert_eq!(loan.due_date, "2024-07-15");
+        assert!(issue("ACC-1", "2024-07-01")
+            .unwrap_err()
+            .contains("already out"));
+        issue("ACC-2", "2024-07-01").unwrap();
+        let err = issue("ACC-3", "2024-07-02").unwrap_err();
+        assert!(err.contains("limit of 2"), "{}", err);
+        let err = issue("ACC-3", "2024-07-20").unwrap_err();
+        assert!(err.contains("overdue"), "{}", err);
+
+        let returned = return_book(&conn, "ACC-1", Some("2024-07-20".to_string())).unwrap();
+        assert_eq!((returned.days_overdue, returned.fine_amount), (5, 5.0));
+        let dues = get_student_dues(&conn, student_id, false).unwrap();
+        assert_eq!(dues.len(), 1);
+        assert_eq!(returned.fine_due_id, dues[0].id);
+        assert_eq!(dues[0].source.as_deref(), Some("library"));
+
+        let on_time = return_book(&conn, "ACC-2", Some("2024-07-10".to_string())).unwrap();
+        assert_eq!((on_time.fine_amount, on_time.fine_due_id), (0.0, None));
+        assert!(return_book(&conn, "ACC-2", None)
+            .unwrap_err()
+            .contains("not out"));
+    }
+
+    #[test]
+    fn renewals_are_limited_and_leavers_must_return_books() {
+        let conn = memory_db();
+        let book_id = save_book(&conn, book("Wings of Fire", None)).unwrap();
+        add_copy(&conn, copy(book_id, "ACC-1", Some("890001"))).unwrap();
+        let staff_id = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let student_id = student(&conn, "L2");
+
+        issue_book(&conn, "890001", "staff", staff_id, None).unwrap();
+        let today = today(&conn).unwrap();
+        for _ in 0..2 {
+            let renewed = renew_book(&conn, "ACC-1").unwrap();
+            assert_eq!(renewed.due_date, today.add_days(30).to_string());
+        }
+        assert!(renew_book(&conn, "ACC-1")
+            .unwrap_err()
+            .contains("renewed 2"));
+        return_book(&conn, "ACC-1", None).unwrap();
+
+        issue_book(&conn, "ACC-1", "student", student_id, None).unwrap();
+        let leave = |status: &str| StatusChange {
+            status: status.to_string(),
+            effective_date: None,
+            reason: Some("Family relocated".to_string()),
+            passing_year: None,
+        };
+        change_status(&conn, student_id, leave("active")).unwrap();
+        let err = change_status(&conn, student_id, leave("left")).unwrap_err();
+        assert!(err.contains("1 library book(s)"), "{}", err);
+        return_book(&conn, "ACC-1", None).unwrap();
+        change_status(&conn, student_id, leave("left")).unwrap();
+        let err = issue_book(&conn, "ACC-1", "student", student_id, None).unwrap_err();
+        assert!(err.contains("on the roll"), "{}", err);
+    }
+
+    #[test]
+    fn reports_list_overdue_loans_and_popular_titles() {
+        let conn = memory_db();
+        let popular = save_book(&conn, book("Wings of Fire", None)).unwrap();
+        let other = save_book(&conn, book("Ignited Minds", None)).unwrap();
+        add_copy(&conn, copy(popular, "ACC-1", None)).unwrap();
+        add_copy(&conn, copy(other, "ACC-2", None)).unwrap();
+        let student_id = student(&conn, "L3");
+
+        issue_book(
+            &conn,
+            "ACC-1",
+            "student",
+            student_id,
+            Some("2024-06-01".to_string()),
+        )
+        .unwrap();
+        return_book(&conn, "ACC-1", Some("2024-06-10".to_string())).unwrap();
+        issue_book(
+            &conn,
+            "ACC-1",
+            "student",
+            student_id,
+            Some("2024-06-10".to_string()),
+        )
+        .unwrap();
+        issue_book(
+            &conn,
+            "ACC-2",
+            "student",
+            student_id,
+            Some("2024-06-12".to_string()),
+        )
+        .unwrap();
+
+        let overdue = get_overdue(&conn, Some("2024-06-30".to_string())).unwrap();
+        assert_eq!(overdue.len(), 2);
+        assert_eq!(overdue[0].title, "Wings of Fire");
+        assert_eq!((overdue[0].days_overdue, overdue[0].fine_amount), (6, 6.0));
+        assert_eq!(overdue[0].member_name, "Student L3");
+
+        let ranked = get_most_borrowed(&conn, "2024-06-01", "2024-06-30", None).unwrap();
+        assert_eq!(
+            ranked
+                .iter()
+                .map(|b| (b.title.as_str(), b.loans))
+                .collect::<Vec<_>>(),
+            vec![("Wings of Fire", 2), ("Ignited Minds", 1)]
+        );
+        assert!(delete_book(&conn, popular)
+            .unwrap_err()
+            .contains("lent out"));
+    }
+}
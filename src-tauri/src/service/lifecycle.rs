@@ -0,0 +1,430 @@
+use crate::date::Date;
+use crate::lifecycle::{AlumniRecord, StatusChange, StatusHistoryEntry};
+use crate::repository;
+use crate::repository::class as class_repo;
+use crate::repository::lifecycle as repo;
+use crate::repository::students as student_repo;
+use rusqlite::Connection;
+
+pub const STUDENT_STATUSES: [&str; 7] = [
+    "applicant",
+    "active",
+    "suspended",
+    "transferred",
+    "left",
+    "graduated",
+    "alumni",
+];
+
+/// Statuses a student can move to from `from`. Students who left or transferred can be readmitted.
+fn allowed_next(from: &str) -> &'static [&'static str] {
+    match from {
+        "applicant" => &["active", "left"],
+        "active" => &["suspended", "transferred", "left", "graduated"],
+        "suspended" => &["active", "transferred", "left"],
+        "transferred" | "left" => &["active"],
+        "graduated" => &["alumni"],
+        _ => &[],
+    }
+}
+
+fn requires_reason(status: &str) -> bool {
+    matches!(status, "suspended" | "transferred" | "left")
+}
+
+/// Students counted on the rolls: billed for fees and marked in attendance.
+/// A student still being entered through the admission form has no status yet.
+pub fn is_on_roll(status: Option<&str>) -> bool {
+    matches!(status, None | Some("active") | Some("suspended"))
+}
+
+pub fn normalize_status(status: &str) -> Result<String, String> {
+    let status = status.trim().to_lowercase();
+    if STUDENT_STATUSES.contains(&status.as_str()) {
+        Ok(status)
+    } else {
+        Err(format!(
+            "Invalid student status '{}'. Expected one of: {}",
+            status,
+            STUDENT_STATUSES.join(", ")
+        ))
+    }
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Moves the student to a new status and records the transition. Runs in the caller's
+/// transaction, if any.
+fn apply_change(conn: &Connection, student_id: i64, change: StatusChange) -> Result<(), String> {
+    let to = normalize_status(&change.status)?;
+    let effective_date = match non_blank(change.effective_date) {
+        Some(date) => Date::parse(&date)?,
+        None => Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?,
+    };
+    let from = student_repo::find_status(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+
+    let reason = non_blank(change.reason);
+    match from.as_deref() {
+        // First status: any is accepted, so older records can be imported as they are.
+        None => {}
+        Some(current) if current == to => {
+            return Err(format!("Student is already {}", to));
+        }
+        Some(current) => {
+            if !allowed_next(current).contains(&to.as_str()) {
+                return Err(format!("Cannot change status from {} to {}", current, to));
+            }
+            if requires_reason(&to) && reason.is_none() {
+                return Err(format!("A reason is required to mark a student as {}", to));
+            }
+            // Admissions and readmissions take a seat in the class.
+            if is_on_roll(Some(&to)) && !is_on_roll(Some(current)) {
+                let class_id = student_repo::find_class_id(conn, student_id)
+                    .map_err(|e| e.to_string())?;
+                if let Some(class_id) = class_id.and_then(|id| id.parse().ok()) {
+                    crate::service::class::ensure_seat(conn, class_id)?;
+                }
+            }
+            if is_on_roll(Some(current)) && !is_on_roll(Some(&to)) {
+                crate::service::library::ensure_no_books_out(conn, student_id)?;
+                crate::service::hostel::ensure_not_boarding(conn, student_id)?;
+            }
+        }
+    }
+
+    student_repo::set_status(conn, student_id, &to).map_err(|e| e.to_string())?;
+    if is_on_roll(Some(&to)) {
+        class_repo::remove_from_waitlist(conn, student_id).map_err(|e| e.to_string())?;
+    }
+    repo::insert_history(
+        conn,
+        student_id,
+        from.as_deref(),
+        &to,
+        &effective_date.to_string(),
+        reason.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    if matches!(to.as_str(), "left" | "transferred") {
+        let reason = reason.as_deref().unwrap_or_default();
+        student_repo::set_leaving_details(conn, student_id, &effective_date.to_string(), reason)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if to == "graduated" || (to == "alumni" && from.is_none()) {
+        let student = student_repo::find_by_id(conn, student_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+        let passing_year =
+            non_blank(change.passing_year).unwrap_or_else(|| effective_date.year.to_string());
+        let alumni = AlumniRecord {
+            id: None,
+            student_id: Some(student_id),
+            full_name: student.core.full_name,
+            gr_number: student.core.gr_number,
+            final_class: student.core.class_name,
+            passing_year,
+            email: student.contact.email,
+            phone: student.contact.mobile_number,
+            address: student.contact.address,
+            current_occupation: None,
+            notes: None,
+            updated_at: None,
+        };
+        repo::upsert_alumni(conn, &alumni).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+pub fn change_status(
+    conn: &Connection,
+    student_id: i64,
+    change: StatusChange,
+) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    apply_change(&tx, student_id, change)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Applies the status chosen on the admission form or in an import. The first status is
+/// dated from the admission date; later changes go through the usual transition rules.
+/// Waitlisted applicants stay applicants until admitted from the waitlist.
+pub fn set_form_status(
+    conn: &Connection,
+    student_id: i64,
+    status: Option<String>,
+    admission_date: Option<String>,
+) -> Result<(), String> {
+    if class_repo::find_waitlisted_class(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .is_some()
+    {
+        return Ok(());
+    }
+    let current = student_repo::find_status(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+    let requested = non_blank(status).map(|s| s.to_lowercase());
+
+    let change = match (current, requested) {
+        (None, requested) => StatusChange {
+            status: requested.unwrap_or_else(|| "active".to_string()),
+            effective_date: admission_date,
+            reason: None,
+            passing_year: None,
+        },
+        (Some(current), Some(requested)) if current != requested => StatusChange {
+            status: requested,
+            effective_date: None,
+            reason: None,
+            passing_year: None,
+        },
+        _ => return Ok(()),
+    };
+    apply_change(conn, student_id, change)
+}
+
+/// Takes a student off the roll when a transfer or leaving certificate is issued.
+/// Students who already left, transferred or graduated keep their status; only the leaving
+/// details are recorded.
+pub fn record_leaving(
+    conn: &Connection,
+    student_id: i64,
+    leaving_date: &str,
+    leaving_reason: &str,
+) -> Result<(), String> {
+    let status = student_repo::find_status(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+
+    match status.as_deref() {
+        Some("left" | "transferred" | "graduated" | "alumni") => {
+            student_repo::set_leaving_details(conn, student_id, leaving_date, leaving_reason)
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        _ => apply_change(
+            conn,
+            student_id,
+            StatusChange {
+                status: "left".to_string(),
+                effective_date: Some(leaving_date.to_string()),
+                reason: Some(leaving_reason.to_string()),
+                passing_year: None,
+            },
+        ),
+    }
+}
+
+pub fn get_status_history(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Vec<StatusHistoryEntry>, String> {
+    repo::find_history(conn, student_id).map_err(|e| e.to_string())
+}
+
+pub fn get_alumni(
+    conn: &Connection,
+    passing_year: Option<&str>,
+    query: Option<&str>,
+) -> Result<Vec<AlumniRecord>, String> {
+    let passing_year = passing_year.map(str::trim).filter(|y| !y.is_empty());
+    let query = query.map(str::trim).filter(|q| !q.is_empty());
+    repo::find_alumni(conn, passing_year, query).map_err(|e| e.to_string())
+}
+
+pub fn update_alumni(conn: &Connection, id: i64, alumni: AlumniRecord) -> Result<(), String> {
+    if alumni.passing_year.trim().is_empty() {
+        return Err("Passing year is required".to_string());
+    }
+    let updated = repo::update_alumni(conn, id, &alumni).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Alumni record with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Brings statuses saved before the lifecycle existed into line and dates them.
+pub fn migrate_legacy_statuses(conn: &Connection) -> Result<(), String> {
+    repo::normalize_legacy_statuses(conn, &STUDENT_STATUSES).map_err(|e| e.to_string())?;
+    repo::record_missing_initial_statuses(conn).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::students::{bulk_insert, get_students, save_core, save_health};
+    use crate::test_support::{create_class, memory_db, sample_student};
+
+    fn change(status: &str, reason: Option<&str>) -> StatusChange {
+        StatusChange {
+            status: status.to_string(),
+            effective_date: Some("2025-04-01".to_string()),
+            reason: reason.map(str::to_string),
+            passing_year: None,
+        }
+    }
+
+    fn admit(conn: &Connection, gr_number: &str) -> i64 {
+        let class_id = create_class(conn, &format!("Class-10 {}", gr_number)).to_string();
+        let student = sample_student(&class_id, gr_number);
+        let id = save_core(conn, student.core).unwrap();
+        save_health(conn, id, student.health).unwrap();
+        id
+    }
+
+    fn status_of(conn: &Connection, id: i64) -> Option<String> {
+        student_repo::find_status(conn, id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn transitions_are_enforced_and_dated() {
+        let conn = memory_db();
+        let id = admit(&conn, "GR-1");
+
+        assert!(change_status(&conn, id, change("alumni", None)).is_err());
+        assert!(change_status(&conn, id, change("suspended", None))
+            .unwrap_err()
+            .contains("reason"));
+        let mut misdated = change("suspended", Some("Fee default"));
+        misdated.effective_date = Some("01/04/2025".to_string());
+        assert!(change_status(&conn, id, misdated)
+            .unwrap_err()
+            .contains("Invalid date"));
+        change_status(&conn, id, change("suspended", Some("Fee default"))).unwrap();
+        let mut readmitted = change("active", None);
+        readmitted.effective_date = Some(" 2025-04-15T09:30:00 ".to_string());
+        change_status(&conn, id, readmitted).unwrap();
+        assert!(change_status(&conn, id, change("active", None)).is_err());
+        assert!(change_status(&conn, id, change("expelled", Some("x"))).is_err());
+
+        let history = get_status_history(&conn, id).unwrap();
+        let steps: Vec<_> = history
+            .iter()
+            .map(|h| (h.from_status.as_deref(), h.to_status.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (None, "active"),
+                (Some("active"), "suspended"),
+                (Some("suspended"), "active"),
+            ]
+        );
+        assert_eq!(history[0].effective_date, "2024-06-10");
+        assert_eq!(history[1].reason.as_deref(), Some("Fee default"));
+        assert_eq!(history[2].effective_date, "2025-04-15");
+    }
+
+    #[test]
+    fn listing_defaults_to_active_students() {
+        let conn = memory_db();
+        let active = admit(&conn, "GR-1");
+        let leaver = admit(&conn, "GR-2");
+        change_status(&conn, leaver, change("left", Some("Moved city"))).unwrap();
+
+        let listed = get_students(&conn, None, None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].core.id, Some(active));
+
+        let left = get_students(&conn, None, Some("left".to_string())).unwrap();
+        assert_eq!(left[0].core.id, Some(leaver));
+        assert_eq!(
+            get_students(&conn, None, Some("all".to_string()))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(get_students(&conn, None, Some("gone".to_string())).is_err());
+    }
+
+    #[test]
+    fn form_status_changes_follow_the_rules() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student = sample_student(&class_id, "GR-1");
+        let id = save_core(&conn, student.core).unwrap();
+        assert_eq!(status_of(&conn, id), None);
+
+        let mut health = sample_student(&class_id, "GR-1").health;
+        health.status = Some("Applicant".to_string());
+        save_health(&conn, id, health).unwrap();
+        assert_eq!(status_of(&conn, id).as_deref(), Some("applicant"));
+
+        let mut health = sample_student(&class_id, "GR-1").health;
+        health.status = Some("graduated".to_string());
+        assert!(save_health(&conn, id, health).is_err());
+
+        save_health(&conn, id, sample_student(&class_id, "GR-1").health).unwrap();
+        assert_eq!(status_of(&conn, id).as_deref(), Some("active"));
+        assert_eq!(get_status_history(&conn, id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn graduates_enter_the_alumni_directory() {
+        let mut conn = memory_db();
+        let class_id = create_class(&conn, "Class-12 Science").to_string();
+        let mut student = sample_student(&class_id, "GR-1");
+        student.contact.email = Some("asha@example.test".to_string());
+        let id = bulk_insert(&mut conn, vec![student]).unwrap()[0];
+
+        let mut graduate = change("graduated", None);
+        graduate.passing_year = Some("2025".to_string());
+        change_status(&conn, id, graduate).unwrap();
+        change_status(&conn, id, change("alumni", None)).unwrap();
+
+        let alumni = get_alumni(&conn, Some("2025"), Some("GR-1")).unwrap();
+        assert_eq!(alumni.len(), 1);
+        assert_eq!(alumni[0].final_class.as_deref(), Some("Class-12 Science"));
+        assert_eq!(alumni[0].email.as_deref(), Some("asha@example.test"));
+        assert!(get_alumni(&conn, Some("2024"), None).unwrap().is_empty());
+
+        let mut record = get_alumni(&conn, None, None).unwrap().remove(0);
+        let alumni_id = record.id.unwrap();
+        record.current_occupation = Some("Engineering student".to_string());
+        update_alumni(&conn, alumni_id, record).unwrap();
+        let stored = &get_alumni(&conn, None, None).unwrap()[0];
+        assert_eq!(
+            stored.current_occupation.as_deref(),
+            Some("Engineering student")
+        );
+    }
+
+    #[test]
+    fn legacy_statuses_are_normalized_once() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        for (gr, status) in [("GR-1", "Active"), ("GR-2", "inactive"), ("GR-3", "")] {
+            let mut student = sample_student(&class_id, gr);
+            student.health.status = Some(status.to_string());
+            student_repo::insert_full(&conn, &student).unwrap();
+        }
+
+        migrate_legacy_statuses(&conn).unwrap();
+        migrate_legacy_statuses(&conn).unwrap();
+
+        let students = get_students(&conn, None, Some("all".to_string())).unwrap();
+        let statuses: Vec<_> = students
+            .iter()
+            .map(|s| s.health.status.as_deref())
+            .collect();
+        assert_eq!(statuses, vec![Some("active"), Some("left"), Some("active")]);
+        for student in &students {
+            assert_eq!(
+                get_status_history(&conn, student.core.id.unwrap())
+                    .unwrap()
+                    .len(),
+                1
+            );
+        }
+    }
+}
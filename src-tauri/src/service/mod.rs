@@ -0,0 +1,29 @@
+// Business rules for each entity. Tauri commands are thin wrappers around these.
+pub mod academic_year;
+pub mod calendar;
+pub mod certificate;
+pub mod class;
+pub mod communication;
+pub mod dashboard;
+pub mod discipline;
+pub mod dues;
+pub mod enquiry;
+pub mod guardian;
+pub mod health;
+pub mod homework;
+pub mod hostel;
+pub mod inventory;
+pub mod leave;
+pub mod library;
+pub mod lifecycle;
+pub mod payroll;
+pub mod report;
+pub mod school;
+pub mod staff;
+pub mod staff_attendance;
+pub mod statutory;
+pub mod students;
+pub mod subject;
+pub mod timetable;
+pub mod transport;
+pub mod visitor;
@@ -0,0 +1,861 @@
+use crate::date::{days_in_month, month_name, Date};
+use crate::payroll::{
+    BankTransferExport, PayrollRun, PayrollRunOutcome, Payslip, SalaryStructure, StaffPayDetails,
+    StatutoryRow, StatutorySummary,
+};
+use crate::pdf::{Align, Document, Font, Page};
+use crate::repository::payroll as repo;
+use crate::repository::school as school_repo;
+use crate::repository::staff as staff_repo;
+use crate::school::School;
+use crate::service::staff_attendance::get_muster_roll;
+use rusqlite::Connection;
+
+/// Employee and employer provident fund contribution, each a share of basic + DA.
+const PF_RATE: f64 = 0.12;
+/// PF is calculated on basic + DA up to this monthly ceiling.
+const PF_WAGE_CEILING: f64 = 15_000.0;
+const ESI_EMPLOYEE_RATE: f64 = 0.0075;
+const ESI_EMPLOYER_RATE: f64 = 0.0325;
+/// Staff whose monthly gross is above this are outside ESI.
+const ESI_WAGE_LIMIT: f64 = 21_000.0;
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn find_run(conn: &Connection, id: i64) -> Result<PayrollRun, String> {
+    repo::find_run(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Payroll run with id {} not found", id))
+}
+
+fn period(run: &PayrollRun) -> String {
+    format!("{} {}", month_name(run.month), run.year)
+}
+
+pub fn save_salary_structure(
+    conn: &Connection,
+    mut structure: SalaryStructure,
+) -> Result<i64, String> {
+    staff_repo::find_by_id(conn, structure.staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    structure.effective_from = Date::parse(&structure.effective_from)?.to_string();
+    let amounts = [
+        structure.basic,
+        structure.da,
+        structure.hra,
+        structure.other_allowances,
+        structure.professional_tax,
+        structure.other_deductions,
+    ];
+    if amounts.iter().any(|a| !a.is_finite() || *a < 0.0) {
+        return Err("Salary amounts cannot be negative".to_string());
+    }
+    if structure.basic <= 0.0 {
+        return Err("Basic pay is required".to_string());
+    }
+    structure.remarks = non_blank(structure.remarks);
+    repo::upsert_structure(conn, &structure).map_err(|e| e.to_string())
+}
+
+pub fn get_salary_structures(
+    conn: &Connection,
+    staff_id: i64,
+) -> Result<Vec<SalaryStructure>, String> {
+    repo::find_structures(conn, staff_id).map_err(|e| e.to_string())
+}
+
+pub fn delete_salary_structure(conn: &Connection, id: i64) -> Result<(), String> {
+    let deleted = repo::delete_structure(conn, id).map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Salary structure with id {} not found", id));
+    }
+    Ok(())
+}
+
+pub fn get_pay_details(
+    conn: &Connection,
+    staff_id: i64,
+) -> Result<Option<StaffPayDetails>, String> {
+    repo::find_pay_details(conn, staff_id).map_err(|e| e.to_string())
+}
+
+pub fn save_pay_details(conn: &Connection, mut details: StaffPayDetails) -> Result<(), String> {
+    staff_repo::find_by_id(conn, details.staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    details.bank_name = non_blank(details.bank_name);
+    details.account_number = non_blank(details.account_number).map(|a| a.replace(' ', ""));
+    details.ifsc_code = non_blank(details.ifsc_code).map(|c| c.to_uppercase());
+    details.pan_number = non_blank(details.pan_number).map(|p| p.to_uppercase());
+    details.uan_number = non_blank(details.uan_number);
+    details.esi_number = non_blank(details.esi_number);
+    if let Some(ifsc) = &details.ifsc_code {
+        // Four letters for the bank, a zero, then six characters for the branch.
+        let valid = ifsc.len() == 11
+            && ifsc[..4].chars().all(|c| c.is_ascii_alphabetic())
+            && &ifsc[4..5] == "0"
+            && ifsc[5..].chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(format!("Invalid IFSC code '{}'", ifsc));
+        }
+    }
+    repo::upsert_pay_details(conn, &details).map_err(|e| e.to_string())
+}
+
+/// Pay for `paid_days` of a month under `structure`. Earnings are pro-rated and rounded
+/// to the rupee; ESI is rounded up, as the ESI rules require.
+fn calculate(structure: &SalaryStructure, days_in_month: f64, paid_days: f64) -> Payslip {
+    let share = if days_in_month > 0.0 {
+        (paid_days / days_in_month).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let prorate = |amount: f64| (amount * share).round();
+    let basic = prorate(structure.basic);
+    let da = prorate(structure.da);
+    let hra = prorate(structure.hra);
+    let other_allowances = prorate(structure.other_allowances);
+    let gross = basic + da + hra + other_allowances;
+
+    let pf_wages = if structure.pf_applicable {
+        (basic + da).min(PF_WAGE_CEILING)
+    } else {
+        0.0
+    };
+    let full_gross = structure.basic + structure.da + structure.hra + structure.other_allowances;
+    let esi_wages = if structure.esi_applicable && full_gross <= ESI_WAGE_LIMIT {
+        gross
+    } else {
+        0.0
+    };
+    let employee_pf = (pf_wages * PF_RATE).round();
+    let employee_esi = (esi_wages * ESI_EMPLOYEE_RATE).ceil();
+    let professional_tax = if gross > 0.0 {
+        structure.professional_tax
+    } else {
+        0.0
+    };
+    let other_deductions = structure.other_deductions.min(gross);
+    let total_deductions = employee_pf + employee_esi + professional_tax + other_deductions;
+
+    Payslip {
+        staff_id: structure.staff_id,
+        days_in_month,
+        paid_days,
+        unpaid_days: days_in_month - paid_days,
+        basic,
+        da,
+        hra,
+        other_allowances,
+        gross,
+        pf_wages,
+        employee_pf,
+        employer_pf: employee_pf,
+        esi_wages,
+        employee_esi,
+        employer_esi: (esi_wages * ESI_EMPLOYER_RATE).ceil(),
+        professional_tax,
+        other_deductions,
+        total_deductions,
+        net_pay: (gross - total_deductions).max(0.0),
+        ..Payslip::default()
+    }
+}
+
+/// Calculates the month's payslips from the salary structure in force at month end and the
+/// muster roll: absent days and leave without pay are unpaid, and so are days before joining.
+/// Days without any attendance mark are paid. A draft run is recalculated from scratch.
+pub fn run_payroll(conn: &Connection, year: i32, month: u32) -> Result<PayrollRunOutcome, String> {
+    let start = Date::new(year, month, 1)?;
+    let days = days_in_month(year, month);
+    let month_end = start.add_days(days as i64 - 1);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let run_id = match repo::find_run_id(&tx, year, month).map_err(|e| e.to_string())? {
+        Some(id) => {
+            if find_run(&tx, id)?.status != "draft" {
+                return Err(format!(
+                    "Payroll for {} {} is finalized and cannot be recalculated",
+                    month_name(month),
+                    year
+                ));
+            }
+            repo::delete_payslips(&tx, id).map_err(|e| e.to_string())?;
+            id
+        }
+        None => repo::insert_run(&tx, year, month).map_err(|e| e.to_string())?,
+    };
+
+    let roll = get_muster_roll(&tx, year, month)?;
+    let mut skipped_staff = Vec::new();
+    for row in roll.rows {
+        let structure = repo::find_structure_on(&tx, row.staff_id, &month_end.to_string())
+            .map_err(|e| e.to_string())?;
+        let (Some(structure), Some(staff)) = (
+            structure,
+            staff_repo::find_by_id(&tx, row.staff_id).map_err(|e| e.to_string())?,
+        ) else {
+            skipped_staff.push(row.name);
+            continue;
+        };
+        let before_joining = match Date::parse(&staff.joining_date) {
+            Ok(joined) if joined > start => start.days_until(joined) as f64,
+            _ => 0.0,
+        };
+        let paid_days = (days as f64 - before_joining - row.absent - row.unpaid_leave).max(0.0);
+        let details = repo::find_pay_details(&tx, row.staff_id).map_err(|e| e.to_string())?;
+
+        let mut payslip = calculate(&structure, days as f64, paid_days);
+        payslip.run_id = run_id;
+        payslip.staff_name = staff.name;
+        payslip.designation = staff.designation;
+        payslip.department = staff.department;
+        if let Some(details) = details {
+            payslip.bank_name = details.bank_name;
+            payslip.account_number = details.account_number;
+            payslip.ifsc_code = details.ifsc_code;
+            payslip.pan_number = details.pan_number;
+            payslip.uan_number = details.uan_number;
+            payslip.esi_number = details.esi_number;
+        }
+        repo::insert_payslip(&tx, &payslip).map_err(|e| e.to_string())?;
+    }
+
+    let run = find_run(&tx, run_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(PayrollRunOutcome { run, skipped_staff })
+}
+
+pub fn finalize_run(conn: &Connection, id: i64) -> Result<(), String> {
+    let run = find_run(conn, id)?;
+    if run.staff_count == 0 {
+        return Err("The payroll run has no payslips".to_string());
+    }
+    if repo::finalize_run(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Payroll for {} is already finalized", period(&run)));
+    }
+    Ok(())
+}
+
+pub fn get_runs(conn: &Connection) -> Result<Vec<PayrollRun>, String> {
+    repo::find_runs(conn).map_err(|e| e.to_string())
+}
+
+pub fn get_payslips(conn: &Connection, run_id: i64) -> Result<Vec<Payslip>, String> {
+    find_run(conn, run_id)?;
+    repo::find_payslips(conn, run_id).map_err(|e| e.to_string())
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Bank upload file for a finalized run: one credit per staff member with a positive net pay.
+/// Returns the file name, its contents and a summary whose `path` the caller fills in.
+pub fn bank_transfer_csv(
+    conn: &Connection,
+    run_id: i64,
+) -> Result<(String, String, BankTransferExport), String> {
+    let run = find_run(conn, run_id)?;
+    if run.status != "finalized" {
+        return Err("Finalize the payroll run before exporting the bank transfer file".to_string());
+    }
+    let narration = format!("Salary {}", period(&run));
+    let mut csv =
+        String::from("Beneficiary Name,Account Number,IFSC Code,Bank Name,Amount,Narration\r\n");
+    let mut export = BankTransferExport {
+        path: String::new(),
+        count: 0,
+        total: 0.0,
+        missing_bank_details: Vec::new(),
+    };
+    for payslip in get_payslips(conn, run_id)? {
+        if payslip.net_pay <= 0.0 {
+            continue;
+        }
+        let (Some(account), Some(ifsc)) = (&payslip.account_number, &payslip.ifsc_code) else {
+            export.missing_bank_details.push(payslip.staff_name);
+            continue;
+        };
+        let fields = [
+            csv_field(&payslip.staff_name),
+            csv_field(account),
+            csv_field(ifsc),
+            csv_field(payslip.bank_name.as_deref().unwrap_or_default()),
+            format!("{:.2}", payslip.net_pay),
+            csv_field(&narration),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+        export.count += 1;
+        export.total += payslip.net_pay;
+    }
+    let file_name = format!("Bank-Transfer-{}-{:02}.csv", run.year, run.month);
+    Ok((file_name, csv, export))
+}
+
+pub fn get_statutory_summary(conn: &Connection, run_id: i64) -> Result<StatutorySummary, String> {
+    let run = find_run(conn, run_id)?;
+    let rows: Vec<StatutoryRow> = get_payslips(conn, run_id)?
+        .into_iter()
+        .map(|p| StatutoryRow {
+            staff_id: p.staff_id,
+            staff_name: p.staff_name,
+            uan_number: p.uan_number,
+            esi_number: p.esi_number,
+            pf_wages: p.pf_wages,
+            employee_pf: p.employee_pf,
+            employer_pf: p.employer_pf,
+            esi_wages: p.esi_wages,
+            employee_esi: p.employee_esi,
+            employer_esi: p.employer_esi,
+            professional_tax: p.professional_tax,
+        })
+        .collect();
+    Ok(StatutorySummary {
+        run_id,
+        year: run.year,
+        month: run.month,
+        total_pf: rows.iter().map(|r| r.employee_pf + r.employer_pf).sum(),
+        total_esi: rows.iter().map(|r| r.employee_esi + r.employer_esi).sum(),
+        total_professional_tax: rows.iter().map(|r| r.professional_tax).sum(),
+        rows,
+    })
+}
+
+/// `1234567.5` as `12,34,567.50`, grouped the Indian way.
+pub fn money(amount: f64) -> String {
+    let formatted = format!("{:.2}", amount.abs());
+    let (whole, paise) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+    let mut grouped = String::new();
+    let digits: Vec<char> = whole.chars().collect();
+    for (i, digit) in digits.iter().enumerate() {
+        let remaining = digits.len() - i;
+        if i > 0 && (remaining == 3 || (remaining > 3 && remaining % 2 == 1)) {
+            grouped.push(',');
+        }
+        grouped.push(*digit);
+    }
+    let sign = if amount < 0.0 { "-" } else { "" };
+    format!("{}{}.{}", sign, grouped, paise)
+}
+
+const ONES: [&str; 20] = [
+    "",
+    "One",
+    "Two",
+    "Three",
+    "Four",
+    "Five",
+    "Six",
+    "Seven",
+    "Eight",
+    "Nine",
+    "Ten",
+    "Eleven",
+    "Twelve",
+    "Thirteen",
+    "Fourteen",
+    "Fifteen",
+    "Sixteen",
+    "Seventeen",
+    "Eighteen",
+    "Nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "Twenty", "Thirty", "Forty", "Fifty", "Sixty", "Seventy", "Eighty", "Ninety",
+];
+
+fn below_hundred(n: u64) -> String {
+    if n < 20 {
+        ONES[n as usize].to_string()
+    } else if n % 10 == 0 {
+        TENS[(n / 10) as usize].to_string()
+    } else {
+        format!("{} {}", TENS[(n / 10) as usize], ONES[(n % 10) as usize])
+    }
+}
+
+/// Whole number in words using crore, lakh and thousand.
+fn number_in_words(n: u64) -> String {
+    if n == 0 {
+        return "Zero".to_string();
+    }
+    let mut parts = Vec::new();
+    if n >= 10_000_000 {
+        parts.push(format!("{} Crore", number_in_words(n / 10_000_000)));
+    }
+    let n = n % 10_000_000;
+    for (unit, name) in [(100_000, "Lakh"), (1_000, "Thousand"), (100, "Hundred")] {
+        let count = (n % (unit * if unit == 100 { 10 } else { 100 })) / unit;
+        if count > 0 {
+            parts.push(format!("{} {}", below_hundred(count), name));
+        }
+    }
+    if n % 100 > 0 {
+        parts.push(below_hundred(n % 100));
+    }
+    parts.join(" ")
+}
+
+fn rupees_in_words(amount: f64) -> String {
+    format!(
+        "Rupees {} Only",
+        number_in_words(amount.round().max(0.0) as u64)
+    )
+}
+
+fn draw_payslip(page: &mut Page, payslip: &Payslip, run: &PayrollRun, school: Option<&School>) {
+    let (width, height) = (page.width(), page.height());
+    let center = width / 2.0;
+    let margin = 50.0;
+    let right = width - margin;
+
+    let mut y = height - 60.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            18.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 16.0;
+        let address = format!(
+            "{}, {}, {} - {}",
+            school.address, school.city, school.state, school.pincode
+        );
+        page.text_aligned(center, y, 9.5, Font::Regular, Align::Center, &address);
+        y -= 13.0;
+        let contact = format!(
+            "Phone: {} | Email: {}",
+            school.contact_number, school.school_email
+        );
+        page.text_aligned(center, y, 9.0, Font::Regular, Align::Center, &contact);
+        y -= 12.0;
+    }
+    page.line(margin, y, right, y, 1.0);
+    y -= 24.0;
+    let title = format!("Payslip for {}", period(run));
+    page.text_aligned(center, y, 13.0, Font::Bold, Align::Center, &title);
+
+    // Staff details in two columns
+    let dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let left_details = [
+        ("Name", payslip.staff_name.clone()),
+        ("Designation", payslip.designation.clone()),
+        ("Department", payslip.department.clone()),
+        ("Staff ID", payslip.staff_id.to_string()),
+    ];
+    let right_details = [
+        ("PAN", dash(&payslip.pan_number)),
+        ("UAN", dash(&payslip.uan_number)),
+        ("ESI No.", dash(&payslip.esi_number)),
+        ("Bank A/c", dash(&payslip.account_number)),
+    ];
+    y -= 30.0;
+    for ((left_label, left_value), (right_label, right_value)) in
+        left_details.iter().zip(right_details.iter())
+    {
+        page.text(margin, y, 10.0, Font::Bold, left_label);
+        page.text(margin + 80.0, y, 10.0, Font::Regular, left_value);
+        page.text(center + 20.0, y, 10.0, Font::Bold, right_label);
+        page.text(center + 90.0, y, 10.0, Font::Regular, right_value);
+        y -= 15.0;
+    }
+    y -= 5.0;
+    let days = format!(
+        "Days in month: {}    Paid days: {}    Loss of pay days: {}",
+        payslip.days_in_month, payslip.paid_days, payslip.unpaid_days
+    );
+    page.text(margin, y, 10.0, Font::Regular, &days);
+
+    // Earnings and deductions side by side
+    let earnings = [
+        ("Basic", payslip.basic),
+        ("Dearness Allowance", payslip.da),
+        ("House Rent Allowance", payslip.hra),
+        ("Other Allowances", payslip.other_allowances),
+    ];
+    let deductions = [
+        ("Provident Fund", payslip.employee_pf),
+        ("ESI", payslip.employee_esi),
+        ("Professional Tax", payslip.professional_tax),
+        ("Other Deductions", payslip.other_deductions),
+    ];
+    let row_height = 18.0;
+    let table_top = y - 20.0;
+    let rows = earnings.len() + 2;
+    let table_height = row_height * rows as f32;
+    page.rect(
+        margin,
+        table_top - table_height,
+        right - margin,
+        table_height,
+        0.8,
+    );
+    page.line(center, table_top, center, table_top - table_height, 0.8);
+    let header_y = table_top - row_height;
+    page.line(margin, header_y, right, header_y, 0.8);
+    page.text(margin + 6.0, header_y + 5.0, 10.0, Font::Bold, "Earnings");
+    page.text_aligned(
+        center - 6.0,
+        header_y + 5.0,
+        10.0,
+        Font::Bold,
+        Align::Right,
+        "Amount (Rs.)",
+    );
+    page.text(center + 6.0, header_y + 5.0, 10.0, Font::Bold, "Deductions");
+    page.text_aligned(
+        right - 6.0,
+        header_y + 5.0,
+        10.0,
+        Font::Bold,
+        Align::Right,
+        "Amount (Rs.)",
+    );
+
+    let mut row_y = header_y;
+    for ((earning, earned), (deduction, deducted)) in earnings.iter().zip(deductions.iter()) {
+        row_y -= row_height;
+        page.text(margin + 6.0, row_y + 5.0, 10.0, Font::Regular, earning);
+        page.text_aligned(
+            center - 6.0,
+            row_y + 5.0,
+            10.0,
+            Font::Regular,
+            Align::Right,
+            &money(*earned),
+        );
+        page.text(center + 6.0, row_y + 5.0, 10.0, Font::Regular, deduction);
+        page.text_aligned(
+            right - 6.0,
+            row_y + 5.0,
+            10.0,
+            Font::Regular,
+            Align::Right,
+            &money(*deducted),
+        );
+    }
+    page.line(margin, row_y, right, row_y, 0.8);
+    row_y -= row_height;
+    page.text(
+        margin + 6.0,
+        row_y + 5.0,
+        10.0,
+        Font::Bold,
+        "Gross Earnings",
+    );
+    page.text_aligned(
+        center - 6.0,
+        row_y + 5.0,
+        10.0,
+        Font::Bold,
+        Align::Right,
+        &money(payslip.gross),
+    );
+    page.text(
+        center + 6.0,
+        row_y + 5.0,
+        10.0,
+        Font::Bold,
+        "Total Deductions",
+    );
+    page.text_aligned(
+        right - 6.0,
+        row_y + 5.0,
+        10.0,
+        Font::Bold,
+        Align::Right,
+        &money(payslip.total_deductions),
+    );
+
+    y = row_y - 28.0;
+    let net = format!("Net Pay: Rs. {}", money(payslip.net_pay));
+    page.text(margin, y, 12.0, Font::Bold, &net);
+    y -= 16.0;
+    page.text(
+        margin,
+        y,
+        10.0,
+        Font::Regular,
+        &rupees_in_words(payslip.net_pay),
+    );
+
+    page.text_aligned(
+        center,
+        60.0,
+        8.5,
+        Font::Regular,
+        Align::Center,
+        "This is a computer-generated payslip and does not require a signature.",
+    );
+}
+
+fn payslips_pdf(payslips: &[Payslip], run: &PayrollRun, school: Option<&School>) -> Vec<u8> {
+    let mut doc = Document::new(&format!("Payslips {}", period(run)));
+    for payslip in payslips {
+        draw_payslip(doc.add_page(), payslip, run, school);
+    }
+    doc.to_bytes()
+}
+
+/// File name and PDF for one payslip.
+pub fn render_payslip_pdf(conn: &Connection, payslip_id: i64) -> Result<(String, Vec<u8>), String> {
+    let payslip = repo::find_payslip(conn, payslip_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Payslip with id {} not found", payslip_id))?;
+    let run = find_run(conn, payslip.run_id)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let file_name = format!(
+        "Payslip-{}-{:02}-{}.pdf",
+        run.year, run.month, payslip.staff_id
+    );
+    let pdf = payslips_pdf(std::slice::from_ref(&payslip), &run, school.as_ref());
+    Ok((file_name, pdf))
+}
+
+/// File name and a PDF with every payslip of the run.
+pub fn render_run_payslips_pdf(
+    conn: &Connection,
+    run_id: i64,
+) -> Result<(String, Vec<u8>), String> {
+    let run = find_run(conn, run_id)?;
+    let payslips = get_payslips(conn, run_id)?;
+    if payslips.is_empty() {
+        return Err(format!("No payslips for {}", period(&run)));
+    }
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let file_name = format!("Payslips-{}-{:02}.pdf", run.year, run.month);
+    Ok((file_name, payslips_pdf(&payslips, &run, school.as_ref())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leave::LeaveApplication;
+    use crate::service::leave::{apply_leave, approve_leave};
+    use crate::service::school::upsert_school_details;
+    use crate::service::staff::create_staff;
+    use crate::test_support::{memory_db, sample_school, sample_staff};
+
+    fn structure(
+        staff_id: i64,
+        effective_from: &str,
+        basic: f64,
+        da: f64,
+        hra: f64,
+    ) -> SalaryStructure {
+        SalaryStructure {
+            id: None,
+            staff_id,
+            effective_from: effective_from.to_string(),
+            basic,
+            da,
+            hra,
+            other_allowances: 0.0,
+            pf_applicable: true,
+            esi_applicable: true,
+            professional_tax: 200.0,
+            other_deductions: 0.0,
+            remarks: None,
+            created_at: None,
+        }
+    }
+
+    fn full_timer(conn: &Connection, name: &str) -> i64 {
+        let mut staff = sample_staff(name);
+        staff.employment_type = "Full-time".to_string();
+        create_staff(conn, staff).unwrap()
+    }
+
+    fn unpaid_leave(conn: &Connection, staff_id: i64, start: &str, end: &str) {
+        let id = apply_leave(
+            conn,
+            LeaveApplication {
+                id: None,
+                staff_id,
+                leave_type: "unpaid".to_string(),
+                start_date: start.to_string(),
+                end_date: end.to_string(),
+                half_day: false,
+                days: None,
+                reason: None,
+                status: None,
+                decided_by: None,
+                decided_on: None,
+                decision_note: None,
+                staff_name: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        approve_leave(conn, id, None, None).unwrap();
+    }
+
+    fn pay_details(staff_id: i64, account: Option<&str>) -> StaffPayDetails {
+        StaffPayDetails {
+            staff_id,
+            bank_name: Some("State Bank of India".to_string()),
+            account_number: account.map(str::to_string),
+            ifsc_code: Some("sbin0001234".to_string()),
+            pan_number: None,
+            uan_number: Some("100200300400".to_string()),
+            esi_number: None,
+        }
+    }
+
+    #[test]
+    fn statutory_deductions_follow_ceilings_and_limits() {
+        let senior = calculate(
+            &structure(1, "2025-04-01", 30_000.0, 6_000.0, 8_000.0),
+            30.0,
+            30.0,
+        );
+        assert_eq!(senior.gross, 44_000.0);
+        assert_eq!((senior.pf_wages, senior.employee_pf), (15_000.0, 1_800.0));
+        assert_eq!((senior.esi_wages, senior.employee_esi), (0.0, 0.0));
+        assert_eq!(senior.net_pay, 44_000.0 - 1_800.0 - 200.0);
+
+        let junior = calculate(
+            &structure(2, "2025-04-01", 10_000.0, 2_000.0, 1_000.0),
+            30.0,
+            30.0,
+        );
+        assert_eq!(junior.employee_pf, 1_440.0);
+        assert_eq!((junior.employee_esi, junior.employer_esi), (98.0, 423.0));
+    }
+
+    #[test]
+    fn monthly_run_prorates_unpaid_leave_and_uses_the_revision_in_force() {
+        let conn = memory_db();
+        let meera = full_timer(&conn, "Meera");
+        let arjun = full_timer(&conn, "Arjun");
+        full_timer(&conn, "Kavya");
+        save_salary_structure(
+            &conn,
+            structure(meera, "2025-04-01", 20_000.0, 5_000.0, 8_000.0),
+        )
+        .unwrap();
+        save_salary_structure(
+            &conn,
+            structure(meera, "2025-07-01", 22_000.0, 5_000.0, 8_000.0),
+        )
+        .unwrap();
+        save_salary_structure(
+            &conn,
+            structure(arjun, "2025-01-01", 10_000.0, 2_000.0, 1_000.0),
+        )
+        .unwrap();
+        assert_eq!(get_salary_structures(&conn, meera).unwrap().len(), 2);
+        unpaid_leave(&conn, meera, "2025-06-09", "2025-06-10");
+
+        let outcome = run_payroll(&conn, 2025, 6).unwrap();
+        assert_eq!(outcome.skipped_staff, vec!["Kavya".to_string()]);
+        assert_eq!(outcome.run.staff_count, 2);
+
+        let payslips = get_payslips(&conn, outcome.run.id).unwrap();
+        let meera_slip = payslips.iter().find(|p| p.staff_id == meera).unwrap();
+        assert_eq!((meera_slip.paid_days, meera_slip.unpaid_days), (28.0, 2.0));
+        assert_eq!(meera_slip.basic, 18_667.0);
+        assert_eq!(meera_slip.gross, 18_667.0 + 4_667.0 + 7_467.0);
+        assert_eq!(meera_slip.net_pay, meera_slip.gross - 1_800.0 - 200.0);
+
+        // Recalculating a draft replaces its payslips; a finalized run is locked.
+        let rerun = run_payroll(&conn, 2025, 6).unwrap();
+        assert_eq!(rerun.run.id, outcome.run.id);
+        assert_eq!(rerun.run.staff_count, 2);
+        finalize_run(&conn, outcome.run.id).unwrap();
+        assert!(run_payroll(&conn, 2025, 6)
+            .unwrap_err()
+            .contains("finalized"));
+        assert!(finalize_run(&conn, outcome.run.id).is_err());
+    }
+
+    #[test]
+    fn bank_file_and_statutory_summary_cover_the_run() {
+        let conn = memory_db();
+        let meera = full_timer(&conn, "Meera, Iyer");
+        let arjun = full_timer(&conn, "Arjun");
+        save_salary_structure(
+            &conn,
+            structure(meera, "2025-01-01", 20_000.0, 5_000.0, 8_000.0),
+        )
+        .unwrap();
+        save_salary_structure(
+            &conn,
+            structure(arjun, "2025-01-01", 10_000.0, 2_000.0, 1_000.0),
+        )
+        .unwrap();
+        save_pay_details(&conn, pay_details(meera, Some("3210 0012 3456"))).unwrap();
+        save_pay_details(&conn, pay_details(arjun, None)).unwrap();
+        let mut bad = pay_details(arjun, Some("1"));
+        bad.ifsc_code = Some("SBIN1234".to_string());
+        assert!(save_pay_details(&conn, bad).is_err());
+
+        let run = run_payroll(&conn, 2025, 6).unwrap().run;
+        assert!(bank_transfer_csv(&conn, run.id).is_err());
+        finalize_run(&conn, run.id).unwrap();
+
+        let (file_name, csv, export) = bank_transfer_csv(&conn, run.id).unwrap();
+        assert_eq!(file_name, "Bank-Transfer-2025-06.csv");
+        assert_eq!(export.count, 1);
+        assert_eq!(export.missing_bank_details, vec!["Arjun".to_string()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[1],
+            "\"Meera, Iyer\",321000123456,SBIN0001234,State Bank of India,31000.00,Salary June 2025"
+        );
+
+        let summary = get_statutory_summary(&conn, run.id).unwrap();
+        assert_eq!(summary.rows.len(), 2);
+        assert_eq!(summary.total_pf, 2.0 * (1_800.0 + 1_440.0));
+        assert_eq!(summary.total_esi, 98.0 + 423.0);
+        assert_eq!(summary.total_professional_tax, 400.0);
+    }
+
+    #[test]
+    fn payslip_pdf_carries_school_branding() {
+        let conn = memory_db();
+        upsert_school_details(&conn, &sample_school()).unwrap();
+        let meera = full_timer(&conn, "Meera");
+        save_salary_structure(
+            &conn,
+            structure(meera, "2025-01-01", 20_000.0, 5_000.0, 8_000.0),
+        )
+        .unwrap();
+        let run = run_payroll(&conn, 2025, 6).unwrap().run;
+        let payslip = &get_payslips(&conn, run.id).unwrap()[0];
+
+        let (file_name, pdf) = render_payslip_pdf(&conn, payslip.id).unwrap();
+        assert_eq!(file_name, format!("Payslip-2025-06-{}.pdf", meera));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("Green Valley School"));
+        assert!(text.contains("Payslip for June 2025"));
+        assert!(text.contains("Rupees Thirty One Thousand Only"));
+        assert!(render_run_payslips_pdf(&conn, run.id).is_ok());
+    }
+
+    #[test]
+    fn amounts_are_written_the_indian_way() {
+        assert_eq!(money(1_234_567.5), "12,34,567.50");
+        assert_eq!(money(950.0), "950.00");
+        assert_eq!(
+            number_in_words(12_345_678),
+            "One Crore Twenty Three Lakh Forty Five Thousand Six Hundred Seventy Eight"
+        );
+        assert_eq!(number_in_words(100_010), "One Lakh Ten");
+    }
+}
@@ -0,0 +1,1230 @@
+use crate::date::Date;
+use crate::pdf::{text_width, Align, Document, Font, Page, A4_HEIGHT, A4_WIDTH};
+use crate::report::{
+    ReportColumn, ReportDefinition, ReportFilter, ReportResult, ReportSchemaEntity,
+    ReportSchemaField, SavedReport,
+};
+use crate::repository;
+use crate::repository::report::{self as repo, StoredReport};
+use crate::repository::school as school_repo;
+use crate::school::School;
+use crate::xlsx::{Cell, Workbook};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde_json::Value;
+
+/// Most rows a report returns; a definition may ask for fewer.
+pub const MAX_ROWS: u32 = 10_000;
+pub const FORMATS: [&str; 3] = ["csv", "xlsx", "pdf"];
+pub const AGGREGATES: [&str; 5] = ["count", "sum", "avg", "min", "max"];
+
+const TEXT_OPERATORS: [&str; 8] = [
+    "eq",
+    "ne",
+    "in",
+    "not_in",
+    "contains",
+    "starts_with",
+    "is_blank",
+    "is_not_blank",
+];
+const RANGE_OPERATORS: [&str; 11] = [
+    "eq",
+    "ne",
+    "lt",
+    "lte",
+    "gt",
+    "gte",
+    "between",
+    "in",
+    "not_in",
+    "is_blank",
+    "is_not_blank",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Number,
+    Date,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Text => "text",
+            Kind::Number => "number",
+            Kind::Date => "date",
+        }
+    }
+
+    fn operators(self) -> &'static [&'static str] {
+        match self {
+            Kind::Text => &TEXT_OPERATORS,
+            Kind::Number | Kind::Date => &RANGE_OPERATORS,
+        }
+    }
+}
+
+/// A column a report may show, filter or group on. `sql` is only ever taken from this table,
+/// never from the definition, so a definition cannot inject SQL.
+struct Field {
+    key: &'static str,
+    label: &'static str,
+    sql: &'static str,
+    kind: Kind,
+    /// Used instead of `sql` when sorting, so classes come out in grade order.
+    sort: &'static [&'static str],
+}
+
+const fn field(key: &'static str, label: &'static str, sql: &'static str, kind: Kind) -> Field {
+    Field {
+        key,
+        label,
+        sql,
+        kind,
+        sort: &[],
+    }
+}
+
+/// Sorted like the class list: by grade, then section, with classes outside the grade scheme
+/// last.
+const CLASS_NAME: Field = Field {
+    sort: &[
+        "c.grade_order IS NULL",
+        "c.grade_order",
+        "c.grade",
+        "c.section",
+        "c.class_name",
+    ],
+    ..field("class_name", "Class", "c.class_name", Kind::Text)
+};
+
+struct Entity {
+    key: &'static str,
+    label: &'static str,
+    from: &'static str,
+    fields: &'static [Field],
+}
+
+const STUDENT_FIELDS: [Field; 34] = [
+    field("gr_number", "GR number", "s.gr_number", Kind::Text),
+    field("roll_number", "Roll number", "s.roll_number", Kind::Text),
+    field("full_name", "Name", "s.full_name", Kind::Text),
+    field(
+        "gender",
+        "Gender",
+        "UPPER(SUBSTR(TRIM(s.gender), 1, 1)) || LOWER(SUBSTR(TRIM(s.gender), 2))",
+        Kind::Text,
+    ),
+    field("dob", "Date of birth", "s.dob", Kind::Date),
+    CLASS_NAME,
+    // Pre-primary classes count as grade 0 or below.
+    field("grade", "Grade", "c.grade_order", Kind::Number),
+    field("section", "Section", "s.section", Kind::Text),
+    field(
+        "academic_year",
+        "Academic year",
+        "s.academic_year",
+        Kind::Text,
+    ),
+    field(
+        "admission_date",
+        "Admission date",
+        "s.admission_date",
+        Kind::Date,
+    ),
+    field(
+        "status",
+        "Status",
+        "COALESCE(NULLIF(LOWER(TRIM(s.status)), ''), 'active')",
+        Kind::Text,
+    ),
+    field(
+        "blood_group",
+        "Blood group",
+        "TRIM(s.blood_group)",
+        Kind::Text,
+    ),
+    field("nationality", "Nationality", "s.nationality", Kind::Text),
+    field("father_name", "Father's name", "s.father_name", Kind::Text),
+    field("mother_name", "Mother's name", "s.mother_name", Kind::Text),
+    field(
+        "annual_income",
+        "Annual income",
+        "s.annual_income",
+        Kind::Number,
+    ),
+    field("mobile_number", "Mobile", "s.mobile_number", Kind::Text),
+    field("email", "Email", "s.email", Kind::Text),
+    field("city", "City", "s.city", Kind::Text),
+    field("state", "State", "s.state", Kind::Text),
+    field("postal_code", "Postal code", "s.postal_code", Kind::Text),
+    field("weight_kg", "Weight (kg)", "s.weight_kg", Kind::Number),
+    field("height_cm", "Height (cm)", "s.height_cm", Kind::Number),
+    field(
+        "medical_conditions",
+        "Medical conditions",
+        "s.medical_conditions",
+        Kind::Text,
+    ),
+    field(
+        "birth_certificate",
+        "Birth certificate",
+        "s.birth_certificate",
+        Kind::Text,
+    ),
+    field(
+        "transfer_certificate",
+        "Transfer certificate",
+        "s.transfer_certificate",
+        Kind::Text,
+    ),
+    field(
+        "address_proof",
+        "Address proof",
+        "s.address_proof",
+        Kind::Text,
+    ),
+    field("id_proof", "ID proof", "s.id_proof", Kind::Text),
+    field(
+        "passport_photo",
+        "Passport photo",
+        "s.passport_photo",
+        Kind::Text,
+    ),
+    field(
+        "medical_certificate",
+        "Medical certificate",
+        "s.medical_certificate",
+        Kind::Text,
+    ),
+    field(
+        "vaccination_certificate",
+        "Vaccination certificate",
+        "s.vaccination_certificate",
+        Kind::Text,
+    ),
+    field("leaving_date", "Leaving date", "s.leaving_date", Kind::Date),
+    field(
+        "leaving_reason",
+        "Leaving reason",
+        "s.leaving_reason",
+        Kind::Text,
+    ),
+    field("created_on", "Added on", "date(s.created_at)", Kind::Date),
+];
+
+const STAFF_FIELDS: [Field; 11] = [
+    field("name", "Name", "st.name", Kind::Text),
+    field(
+        "gender",
+        "Gender",
+        "UPPER(SUBSTR(TRIM(st.gender), 1, 1)) || LOWER(SUBSTR(TRIM(st.gender), 2))",
+        Kind::Text,
+    ),
+    field("dob", "Date of birth", "st.dob", Kind::Date),
+    field("phone", "Phone", "st.phone", Kind::Text),
+    field("email", "Email", "st.email", Kind::Text),
+    field(
+        "qualification",
+        "Qualification",
+        "st.qualification",
+        Kind::Text,
+    ),
+    field("designation", "Designation", "st.designation", Kind::Text),
+    field(
+        "department",
+        "Department",
+        "TRIM(st.department)",
+        Kind::Text,
+    ),
+    field(
+        "joining_date",
+        "Joining date",
+        "st.joining_date",
+        Kind::Date,
+    ),
+    field(
+        "employment_type",
+        "Employment type",
+        "st.employment_type",
+        Kind::Text,
+    ),
+    field(
+        "status",
+        "Status",
+        "COALESCE(NULLIF(LOWER(TRIM(st.status)), ''), 'active')",
+        Kind::Text,
+    ),
+];
+
+const ENQUIRY_FIELDS: [Field; 7] = [
+    field("student_name", "Student", "e.student_name", Kind::Text),
+    field("parent_name", "Parent", "e.parent_name", Kind::Text),
+    field("phone", "Phone", "e.phone", Kind::Text),
+    field("email", "Email", "e.email", Kind::Text),
+    field("source", "Source", "e.source", Kind::Text),
+    field(
+        "status",
+        "Status",
+        "COALESCE(NULLIF(LOWER(TRIM(e.status)), ''), 'new')",
+        Kind::Text,
+    ),
+    field(
+        "enquiry_date",
+        "Enquiry date",
+        "date(e.created_at)",
+        Kind::Date,
+    ),
+];
+
+const DUE_FIELDS: [Field; 10] = [
+    field("student_name", "Student", "s.full_name", Kind::Text),
+    field("gr_number", "GR number", "s.gr_number", Kind::Text),
+    CLASS_NAME,
+    field("source", "Source", "d.source", Kind::Text),
+    field("reference", "Reference", "d.reference", Kind::Text),
+    field("description", "Description", "d.description", Kind::Text),
+    field("amount", "Amount", "d.amount", Kind::Number),
+    field("raised_on", "Raised on", "d.raised_on", Kind::Date),
+    field("status", "Status", "d.status", Kind::Text),
+    field("settled_on", "Settled on", "d.settled_on", Kind::Date),
+];
+
+const ENTITIES: [Entity; 4] = [
+    Entity {
+        key: "students",
+        label: "Students",
+        from: "students s LEFT JOIN classes c ON c.id = s.class_id",
+        fields: &STUDENT_FIELDS,
+    },
+    Entity {
+        key: "staff",
+        label: "Staff",
+        from: "staff st",
+        fields: &STAFF_FIELDS,
+    },
+    Entity {
+        key: "enquiries",
+        label: "Enquiries",
+        from: "enquiries e",
+        fields: &ENQUIRY_FIELDS,
+    },
+    Entity {
+        key: "dues",
+        label: "Student dues",
+        from: "student_dues d
+            JOIN students s ON s.id = d.student_id
+            LEFT JOIN classes c ON c.id = s.class_id",
+        fields: &DUE_FIELDS,
+    },
+];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, options: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if options.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            options.join(", ")
+        ))
+    }
+}
+
+fn file_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn get_schema() -> Vec<ReportSchemaEntity> {
+    ENTITIES
+        .iter()
+        .map(|entity| ReportSchemaEntity {
+            key: entity.key.to_string(),
+            label: entity.label.to_string(),
+            fields: entity
+                .fields
+                .iter()
+                .map(|field| ReportSchemaField {
+                    key: field.key.to_string(),
+                    label: field.label.to_string(),
+                    kind: field.kind.name().to_string(),
+                    operators: field
+                        .kind
+                        .operators()
+                        .iter()
+                        .map(|op| op.to_string())
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn find_entity(key: &str) -> Result<&'static Entity, String> {
+    let key = key.trim().to_lowercase();
+    ENTITIES
+        .iter()
+        .find(|entity| entity.key == key)
+        .ok_or_else(|| {
+            format!(
+                "Unknown report entity '{}'. Expected one of: {}",
+                key,
+                ENTITIES.map(|entity| entity.key).join(", ")
+            )
+        })
+}
+
+fn find_field(entity: &'static Entity, key: &str) -> Result<&'static Field, String> {
+    let key = key.trim();
+    entity
+        .fields
+        .iter()
+        .find(|field| field.key == key)
+        .ok_or_else(|| format!("Unknown {} field '{}'", entity.label.to_lowercase(), key))
+}
+
+/// A filter value as a bound parameter of the field's kind.
+fn bind(field: &Field, value: &Value) -> Result<SqlValue, String> {
+    let invalid = || format!("{} needs a {} value", field.label, field.kind.name());
+    match (field.kind, value) {
+        (Kind::Text, Value::String(text)) => Ok(SqlValue::Text(text.trim().to_string())),
+        (Kind::Text, Value::Number(number)) => Ok(SqlValue::Text(number.to_string())),
+        (Kind::Number, Value::Number(number)) => Ok(match number.as_i64() {
+            Some(whole) => SqlValue::Integer(whole),
+            None => SqlValue::Real(number.as_f64().ok_or_else(invalid)?),
+        }),
+        (Kind::Number, Value::String(text)) => text
+            .trim()
+            .parse()
+            .map(SqlValue::Real)
+            .map_err(|_| invalid()),
+        (Kind::Date, Value::String(text)) => Ok(SqlValue::Text(Date::parse(text)?.to_string())),
+        _ => Err(invalid()),
+    }
+}
+
+/// Escapes `%`, `_` and the escape character itself for a `LIKE ... ESCAPE '\'` pattern.
+fn like_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn compile_filter(
+    entity: &'static Entity,
+    filter: &ReportFilter,
+    values: &mut Vec<SqlValue>,
+) -> Result<String, String> {
+    let field = find_field(entity, &filter.field)?;
+    let op = filter.op.trim().to_lowercase();
+    if !field.kind.operators().contains(&op.as_str()) {
+        return Err(format!(
+            "Operator '{}' cannot be used on {}. Expected one of: {}",
+            op,
+            field.label,
+            field.kind.operators().join(", ")
+        ));
+    }
+    let expr = field.sql;
+    // Text matches ignore case, so "o+" finds "O+".
+    let collate = if field.kind == Kind::Text {
+        " COLLATE NOCASE"
+    } else {
+        ""
+    };
+    let list = || match &filter.value {
+        Value::Array(items) if !items.is_empty() => Ok(items.as_slice()),
+        _ => Err(format!(
+            "'{}' on {} needs a list of values",
+            op, field.label
+        )),
+    };
+    let sql = match op.as_str() {
+        "is_blank" => format!("({0} IS NULL OR TRIM({0}) = '')", expr),
+        "is_not_blank" => format!("TRIM({}) <> ''", expr),
+        "in" | "not_in" => {
+            let items = list()?;
+            for item in items {
+                values.push(bind(field, item)?);
+            }
+            let placeholders = vec!["?"; items.len()].join(", ");
+            if op == "in" {
+                format!("{}{} IN ({})", expr, collate, placeholders)
+            } else {
+                format!(
+                    "({0} IS NULL OR {0}{1} NOT IN ({2}))",
+                    expr, collate, placeholders
+                )
+            }
+        }
+        "between" => {
+            let items = list()?;
+            if items.len() != 2 {
+                return Err(format!(
+                    "'between' on {} needs a lower and an upper value",
+                    field.label
+                ));
+            }
+            values.push(bind(field, &items[0])?);
+            values.push(bind(field, &items[1])?);
+            format!("{} BETWEEN ? AND ?", expr)
+        }
+        "contains" | "starts_with" => {
+            let text = match bind(field, &filter.value)? {
+                SqlValue::Text(text) => like_escape(&text),
+                _ => unreachable!("text fields bind as text"),
+            };
+            let pattern = if op == "contains" {
+                format!("%{}%", text)
+            } else {
+                format!("{}%", text)
+            };
+            values.push(SqlValue::Text(pattern));
+            format!("{} LIKE ? ESCAPE '\\'", expr)
+        }
+        _ => {
+            values.push(bind(field, &filter.value)?);
+            match op.as_str() {
+                "eq" => format!("{} = ?{}", expr, collate),
+                "ne" => format!("({0} IS NULL OR {0} <> ?{1})", expr, collate),
+                "lt" => format!("{} < ?", expr),
+                "lte" => format!("{} <= ?", expr),
+                "gt" => format!("{} > ?", expr),
+                _ => format!("{} >= ?", expr),
+            }
+        }
+    };
+    Ok(sql)
+}
+
+/// A definition turned into one SELECT over whitelisted expressions, with every filter value
+/// bound as a parameter.
+#[derive(Debug)]
+struct CompiledReport {
+    sql: String,
+    values: Vec<SqlValue>,
+    columns: Vec<ReportColumn>,
+}
+
+fn resolve_fields(
+    entity: &'static Entity,
+    keys: &[String],
+    what: &str,
+) -> Result<Vec<&'static Field>, String> {
+    let mut fields: Vec<&'static Field> = Vec::new();
+    for key in keys {
+        let field = find_field(entity, key)?;
+        if fields.iter().any(|f| f.key == field.key) {
+            return Err(format!("{} is listed twice in the {}", field.label, what));
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+fn compile(definition: &ReportDefinition) -> Result<CompiledReport, String> {
+    let entity = find_entity(&definition.entity)?;
+    let group_by = resolve_fields(entity, &definition.group_by, "grouping")?;
+    let grouped = !group_by.is_empty() || !definition.aggregates.is_empty();
+    let columns = if definition.columns.is_empty() && grouped {
+        group_by.clone()
+    } else {
+        resolve_fields(entity, &definition.columns, "columns")?
+    };
+    if columns.is_empty() && definition.aggregates.is_empty() {
+        return Err("Choose at least one column for the report".to_string());
+    }
+    if grouped {
+        if let Some(field) = columns
+            .iter()
+            .find(|field| !group_by.iter().any(|g| g.key == field.key))
+        {
+            return Err(format!(
+                "{} must also be grouped on when the report has groups or totals",
+                field.label
+            ));
+        }
+    }
+
+    let mut select = Vec::new();
+    let mut output = Vec::new();
+    // Output column key and the expressions it sorts by.
+    let mut sort_keys: Vec<(String, Vec<String>)> = Vec::new();
+    for field in &columns {
+        select.push(field.sql.to_string());
+        output.push(ReportColumn {
+            key: field.key.to_string(),
+            label: field.label.to_string(),
+            kind: field.kind.name().to_string(),
+        });
+        let sort = if field.sort.is_empty() {
+            vec![field.sql.to_string()]
+        } else {
+            field.sort.iter().map(|part| part.to_string()).collect()
+        };
+        sort_keys.push((field.key.to_string(), sort));
+    }
+    for aggregate in &definition.aggregates {
+        let function = one_of(&aggregate.function, &AGGREGATES, "aggregate")?;
+        let field = match aggregate.field.as_deref().map(str::trim) {
+            Some(key) if !key.is_empty() => Some(find_field(entity, key)?),
+            _ => None,
+        };
+        let (expr, key, label, kind) = match (function.as_str(), field) {
+            ("count", None) => (
+                "COUNT(*)".to_string(),
+                "count".to_string(),
+                "Count".to_string(),
+                Kind::Number,
+            ),
+            (_, None) => return Err(format!("'{}' needs a field to total", function)),
+            ("sum" | "avg", Some(field)) if field.kind != Kind::Number => {
+                return Err(format!(
+                    "'{}' needs a number field, not {}",
+                    function, field.label
+                ))
+            }
+            (_, Some(field)) => {
+                let (name, kind) = match function.as_str() {
+                    "count" => ("Count", Kind::Number),
+                    "sum" => ("Sum", Kind::Number),
+                    "avg" => ("Average", Kind::Number),
+                    "min" => ("Minimum", field.kind),
+                    _ => ("Maximum", field.kind),
+                };
+                (
+                    format!("{}({})", function.to_uppercase(), field.sql),
+                    format!("{}_{}", function, field.key),
+                    format!("{} of {}", name, field.label),
+                    kind,
+                )
+            }
+        };
+        if output.iter().any(|column| column.key == key) {
+            return Err(format!("{} is listed twice in the report", label));
+        }
+        select.push(expr);
+        sort_keys.push((key.clone(), vec![(output.len() + 1).to_string()]));
+        output.push(ReportColumn {
+            key,
+            label: non_blank(aggregate.label.clone()).unwrap_or(label),
+            kind: kind.name().to_string(),
+        });
+    }
+
+    let mut values = Vec::new();
+    let conditions = definition
+        .filters
+        .iter()
+        .map(|filter| compile_filter(entity, filter, &mut values))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let order: Vec<String> = if definition.order_by.is_empty() {
+        sort_keys.into_iter().flat_map(|(_, sort)| sort).collect()
+    } else {
+        definition
+            .order_by
+            .iter()
+            .map(|order| {
+                let key = order.key.trim();
+                let (_, sort) = sort_keys
+                    .iter()
+                    .find(|(column, _)| column == key)
+                    .ok_or_else(|| {
+                        format!("Cannot sort by '{}': it is not a report column", key)
+                    })?;
+                let direction = if order.descending { " DESC" } else { "" };
+                Ok(sort
+                    .iter()
+                    .map(|part| format!("{}{}", part, direction))
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .concat()
+    };
+
+    let mut sql = format!("SELECT {} FROM {}", select.join(", "), entity.from);
+    if !conditions.is_empty() {
+        sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    if !group_by.is_empty() {
+        let groups: Vec<&str> = group_by.iter().map(|field| field.sql).collect();
+        sql.push_str(&format!(" GROUP BY {}", groups.join(", ")));
+    }
+    if !order.is_empty() {
+        sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+    }
+    let limit = definition.limit.unwrap_or(MAX_ROWS).clamp(1, MAX_ROWS);
+    sql.push_str(&format!(" LIMIT {}", limit));
+
+    Ok(CompiledReport {
+        sql,
+        values,
+        columns: output,
+    })
+}
+
+pub fn run_report(
+    conn: &Connection,
+    definition: &ReportDefinition,
+) -> Result<ReportResult, String> {
+    let compiled = compile(definition)?;
+    let rows = repo::run_query(
+        conn,
+        &compiled.sql,
+        &compiled.values,
+        compiled.columns.len(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(ReportResult {
+        columns: compiled.columns,
+        rows,
+    })
+}
+
+fn to_saved(stored: StoredReport) -> Result<SavedReport, String> {
+    let definition = serde_json::from_str(&stored.definition)
+        .map_err(|e| format!("Saved report '{}' cannot be read: {}", stored.name, e))?;
+    Ok(SavedReport {
+        id: Some(stored.id),
+        name: stored.name,
+        description: stored.description,
+        definition,
+        created_at: stored.created_at,
+        updated_at: stored.updated_at,
+    })
+}
+
+pub fn get_saved_reports(conn: &Connection) -> Result<Vec<SavedReport>, String> {
+    repo::find_reports(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(to_saved)
+        .collect()
+}
+
+fn find_report(conn: &Connection, id: i64) -> Result<SavedReport, String> {
+    let stored = repo::find_report(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Saved report with id {} not found", id))?;
+    to_saved(stored)
+}
+
+/// Only definitions that compile are saved, so a saved report always runs.
+pub fn save_report(conn: &Connection, report: SavedReport) -> Result<i64, String> {
+    let report = SavedReport {
+        name: report.name.trim().to_string(),
+        description: non_blank(report.description),
+        ..report
+    };
+    if report.name.is_empty() {
+        return Err("Report name is required".to_string());
+    }
+    compile(&report.definition)?;
+    if repo::report_name_taken(conn, &report.name, report.id).map_err(|e| e.to_string())? {
+        return Err(format!("A report named '{}' already exists", report.name));
+    }
+    let definition = serde_json::to_string(&report.definition).map_err(|e| e.to_string())?;
+    match report.id {
+        Some(id) => {
+            if repo::update_report(conn, id, &report, &definition).map_err(|e| e.to_string())? == 0
+            {
+                return Err(format!("Saved report with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_report(conn, &report, &definition).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_report(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::delete_report(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Saved report with id {} not found", id));
+    }
+    Ok(())
+}
+
+pub fn run_saved_report(conn: &Connection, id: i64) -> Result<ReportResult, String> {
+    run_report(conn, &find_report(conn, id)?.definition)
+}
+
+/// A value as printed in CSV and PDF: averages to two decimals, blanks for missing values.
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Number(number) if number.is_f64() => number
+            .as_f64()
+            .map(|n| ((n * 100.0).round() / 100.0).to_string())
+            .unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+fn render_csv(result: &ReportResult) -> Vec<u8> {
+    let mut csv = String::new();
+    let header: Vec<String> = result
+        .columns
+        .iter()
+        .map(|column| csv_field(&column.label))
+        .collect();
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+    for row in &result.rows {
+        let cells: Vec<String> = row.iter().map(|value| csv_field(&display(value))).collect();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv.into_bytes()
+}
+
+fn render_xlsx(result: &ReportResult, title: &str) -> Vec<u8> {
+    let header = result
+        .columns
+        .iter()
+        .map(|column| column.label.clone())
+        .collect();
+    let mut workbook = Workbook::new(title, header);
+    for row in &result.rows {
+        workbook.add_row(
+            row.iter()
+                .map(|value| match value {
+                    Value::Number(number) => number.as_f64().map_or(Cell::Empty, Cell::Number),
+                    Value::Null => Cell::Empty,
+                    other => Cell::Text(display(other)),
+                })
+                .collect(),
+        );
+    }
+    workbook.to_bytes()
+}
+
+fn fit(text: &str, size: f32, font: Font, width: f32) -> String {
+    if text_width(text, size, font) <= width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}..", chars.iter().collect::<String>().trim_end());
+        if text_width(&candidate, size, font) <= width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+const PDF_MARGIN: f32 = 36.0;
+const PDF_TEXT_SIZE: f32 = 8.5;
+
+fn draw_pdf_header(
+    page: &mut Page,
+    school: Option<&School>,
+    title: &str,
+    summary: &str,
+    columns: &[(&ReportColumn, f32)],
+) -> f32 {
+    let center = page.width() / 2.0;
+    let mut y = page.height() - 44.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            14.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 18.0;
+    }
+    page.text_aligned(center, y, 12.0, Font::Bold, Align::Center, title);
+    y -= 15.0;
+    page.text_aligned(center, y, 8.5, Font::Regular, Align::Center, summary);
+    y -= 20.0;
+    let mut x = PDF_MARGIN;
+    for (column, width) in columns {
+        let label = fit(&column.label, PDF_TEXT_SIZE, Font::Bold, width - 4.0);
+        if column.kind == "number" {
+            page.text_aligned(
+                x + width - 2.0,
+                y,
+                PDF_TEXT_SIZE,
+                Font::Bold,
+                Align::Right,
+                &label,
+            );
+        } else {
+            page.text(x + 2.0, y, PDF_TEXT_SIZE, Font::Bold, &label);
+        }
+        x += width;
+    }
+    y -= 5.0;
+    page.line(PDF_MARGIN, y, page.width() - PDF_MARGIN, y, 0.8);
+    y - 12.0
+}
+
+/// A landscape table, each column as wide as its longest value up to a cap, and all of them
+/// narrowed together when the table would not fit the page.
+fn render_pdf(conn: &Connection, result: &ReportResult, title: &str) -> Result<Vec<u8>, String> {
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(display).collect())
+        .collect();
+    let available = A4_HEIGHT - 2.0 * PDF_MARGIN;
+    let natural: Vec<f32> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let widest = cells
+                .iter()
+                .map(|row| text_width(&row[index], PDF_TEXT_SIZE, Font::Regular))
+                .fold(
+                    text_width(&column.label, PDF_TEXT_SIZE, Font::Bold),
+                    f32::max,
+                );
+            widest.min(180.0) + 8.0
+        })
+        .collect();
+    let scale = (available / natural.iter().sum::<f32>()).min(1.0);
+    let columns: Vec<(&ReportColumn, f32)> = result
+        .columns
+        .iter()
+        .zip(natural.iter().map(|width| width * scale))
+        .collect();
+    let summary = format!("{} rows    Generated on {}", cells.len(), today);
+
+    let mut doc = Document::new(title);
+    let mut page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+    let mut y = draw_pdf_header(page, school.as_ref(), title, &summary, &columns);
+    for row in &cells {
+        if y < 40.0 {
+            page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+            y = draw_pdf_header(page, school.as_ref(), title, &summary, &columns);
+        }
+        let mut x = PDF_MARGIN;
+        for ((column, width), cell) in columns.iter().zip(row) {
+            let text = fit(cell, PDF_TEXT_SIZE, Font::Regular, width - 4.0);
+            if column.kind == "number" {
+                page.text_aligned(
+                    x + width - 2.0,
+                    y,
+                    PDF_TEXT_SIZE,
+                    Font::Regular,
+                    Align::Right,
+                    &text,
+                );
+            } else {
+                page.text(x + 2.0, y, PDF_TEXT_SIZE, Font::Regular, &text);
+            }
+            x += width;
+        }
+        y -= 13.0;
+    }
+    Ok(doc.to_bytes())
+}
+
+/// File name and contents of a report in the requested format.
+pub fn render_report(
+    conn: &Connection,
+    definition: &ReportDefinition,
+    title: &str,
+    format: &str,
+) -> Result<(String, Vec<u8>), String> {
+    let format = one_of(format, &FORMATS, "export format")?;
+    let title = match title.trim() {
+        "" => "Report",
+        title => title,
+    };
+    let result = run_report(conn, definition)?;
+    let bytes = match format.as_str() {
+        "csv" => render_csv(&result),
+        "xlsx" => render_xlsx(&result, title),
+        _ => render_pdf(conn, &result, title)?,
+    };
+    Ok((format!("{}.{}", file_part(title), format), bytes))
+}
+
+pub fn render_saved_report(
+    conn: &Connection,
+    id: i64,
+    format: &str,
+) -> Result<(String, Vec<u8>), String> {
+    let report = find_report(conn, id)?;
+    render_report(conn, &report.definition, &report.name, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{ReportAggregate, ReportOrder};
+    use crate::service::class::create_class;
+    use crate::service::students::{save_core, save_docs, save_health};
+    use crate::test_support::{memory_db, sample_student, DEFAULT_ACADEMIC_YEAR_ID};
+    use serde_json::json;
+
+    fn add_student(
+        conn: &Connection,
+        class_id: i64,
+        gr_number: &str,
+        gender: &str,
+        blood_group: &str,
+        vaccination_certificate: Option<&str>,
+    ) {
+        let mut student = sample_student(&class_id.to_string(), gr_number);
+        student.core.gender = gender.to_string();
+        student.health.blood_group = Some(blood_group.to_string());
+        student.docs.vaccination_certificate = vaccination_certificate.map(str::to_string);
+        let id = save_core(conn, student.core).unwrap();
+        save_health(conn, id, student.health).unwrap();
+        save_docs(conn, id, student.docs).unwrap();
+    }
+
+    fn filter(field: &str, op: &str, value: Value) -> ReportFilter {
+        ReportFilter {
+            field: field.to_string(),
+            op: op.to_string(),
+            value,
+        }
+    }
+
+    fn definition(entity: &str, columns: &[&str], filters: Vec<ReportFilter>) -> ReportDefinition {
+        ReportDefinition {
+            entity: entity.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            filters,
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+        }
+    }
+
+    fn school(conn: &Connection) {
+        let class = |name: &str| create_class(conn, name, DEFAULT_ACADEMIC_YEAR_ID, None).unwrap();
+        let class_10 = class("Class-10");
+        let class_6 = class("Class-6 A");
+        let class_8 = class("Class-8");
+        add_student(conn, class_6, "G1", "Female", "O+", None);
+        add_student(conn, class_8, "G2", "female", "o+", Some(""));
+        add_student(
+            conn,
+            class_6,
+            "G3",
+            "Female",
+            "O+",
+            Some("docs/vaccination.pdf"),
+        );
+        add_student(conn, class_8, "G4", "Female", "B+", None);
+        add_student(conn, class_10, "G5", "Female", "O+", None);
+        add_student(conn, class_6, "B1", "Male", "O+", None);
+    }
+
+    #[test]
+    fn finds_girls_in_classes_six_to_eight_without_a_vaccination_certificate() {
+        let conn = memory_db();
+        school(&conn);
+        let report = ReportDefinition {
+            order_by: vec![ReportOrder {
+                key: "full_name".to_string(),
+                descending: false,
+            }],
+            ..definition(
+                "students",
+                &["full_name", "class_name", "gender", "blood_group"],
+                vec![
+                    filter("gender", "eq", json!("female")),
+                    filter("grade", "between", json!([6, 8])),
+                    filter("blood_group", "eq", json!("O+")),
+                    filter("vaccination_certificate", "is_blank", Value::Null),
+                ],
+            )
+        };
+
+        let compiled = compile(&report).unwrap();
+        assert!(!compiled.sql.contains("O+"));
+        assert_eq!(compiled.values.len(), 4);
+
+        let result = run_report(&conn, &report).unwrap();
+        let labels: Vec<&str> = result.columns.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, vec!["Name", "Class", "Gender", "Blood group"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![
+                    json!("Student G1"),
+                    json!("Class-6 A"),
+                    json!("Female"),
+                    json!("O+")
+                ],
+                vec![
+                    json!("Student G2"),
+                    json!("Class-8"),
+                    json!("Female"),
+                    json!("o+")
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn only_whitelisted_fields_and_operators_compile() {
+        let conn = memory_db();
+        school(&conn);
+        let fails = |report: ReportDefinition| compile(&report).unwrap_err();
+
+        assert!(fails(definition("users", &["name"], vec![])).contains("Unknown report entity"));
+        assert!(fails(definition(
+            "students",
+            &["full_name FROM students; DROP TABLE students; --"],
+            vec![]
+        ))
+        .contains("Unknown students field"));
+        assert!(fails(definition("students", &[], vec![])).contains("at least one column"));
+        assert!(fails(definition(
+            "students",
+            &["full_name"],
+            vec![filter("dob", "contains", json!("2015"))]
+        ))
+        .contains("cannot be used on Date of birth"));
+        assert!(fails(definition(
+            "students",
+            &["full_name"],
+            vec![filter("admission_date", "gt", json!("last week"))]
+        ))
+        .contains("Invalid date"));
+        assert!(fails(definition(
+            "students",
+            &["full_name"],
+            vec![filter("grade", "between", json!([6]))]
+        ))
+        .contains("lower and an upper"));
+        assert!(fails(ReportDefinition {
+            aggregates: vec![ReportAggregate {
+                function: "sum".to_string(),
+                field: Some("full_name".to_string()),
+                label: None,
+            }],
+            ..definition("students", &[], vec![])
+        })
+        .contains("needs a number field"));
+        assert!(fails(ReportDefinition {
+            group_by: vec!["class_name".to_string()],
+            ..definition("students", &["class_name", "full_name"], vec![])
+        })
+        .contains("Name must also be grouped"));
+
+        // Values are bound, never spliced into the SQL.
+        let sneaky = definition(
+            "students",
+            &["full_name"],
+            vec![filter("full_name", "eq", json!("x' OR '1'='1"))],
+        );
+        assert!(run_report(&conn, &sneaky).unwrap().rows.is_empty());
+        let literal = definition(
+            "students",
+            &["full_name"],
+            vec![filter("full_name", "contains", json!("_"))],
+        );
+        assert!(run_report(&conn, &literal).unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn grouped_reports_are_saved_rerun_and_exported() {
+        let conn = memory_db();
+        school(&conn);
+        let by_class = ReportDefinition {
+            group_by: vec!["class_name".to_string()],
+            aggregates: vec![ReportAggregate {
+                function: "count".to_string(),
+                field: None,
+                label: Some("Students".to_string()),
+            }],
+            ..definition(
+                "students",
+                &[],
+                vec![filter("gender", "in", json!(["FEMALE"]))],
+            )
+        };
+        let saved = SavedReport {
+            id: None,
+            name: " Girls by class ".to_string(),
+            description: Some("  ".to_string()),
+            definition: by_class.clone(),
+            created_at: None,
+            updated_at: None,
+        };
+        let id = save_report(&conn, saved).unwrap();
+        let duplicate = SavedReport {
+            id: None,
+            name: "GIRLS BY CLASS".to_string(),
+            description: None,
+            definition: by_class,
+            created_at: None,
+            updated_at: None,
+        };
+        assert!(save_report(&conn, duplicate)
+            .unwrap_err()
+            .contains("already exists"));
+
+        let listed = get_saved_reports(&conn).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Girls by class");
+        assert_eq!(listed[0].description, None);
+
+        // Classes come out in grade order, not alphabetically.
+        let result = run_saved_report(&conn, id).unwrap();
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![json!("Class-6 A"), json!(2)],
+                vec![json!("Class-8"), json!(2)],
+                vec![json!("Class-10"), json!(1)],
+            ]
+        );
+
+        let (file_name, csv) = render_saved_report(&conn, id, "CSV").unwrap();
+        assert_eq!(file_name, "Girls-by-class.csv");
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Class,Students\nClass-6 A,2\nClass-8,2\nClass-10,1\n"
+        );
+        let (file_name, xlsx) = render_saved_report(&conn, id, "xlsx").unwrap();
+        assert_eq!(file_name, "Girls-by-class.xlsx");
+        assert_eq!(&xlsx[..2], b"PK");
+        let (_, pdf) = render_saved_report(&conn, id, "pdf").unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(render_saved_report(&conn, id, "doc")
+            .unwrap_err()
+            .contains("Invalid export format"));
+
+        delete_report(&conn, id).unwrap();
+        assert!(run_saved_report(&conn, id)
+            .unwrap_err()
+            .contains("not found"));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::repository::school as repo;
+use crate::school::School;
+use rusqlite::Connection;
+
+/// Result of saving the school profile.
+#[derive(Debug)]
+pub struct SchoolUpsert {
+    pub id: i64,
+    /// File name of a logo that was replaced and should be removed from disk.
+    pub replaced_image: Option<String>,
+}
+
+pub fn get_school_details(conn: &Connection) -> Result<Option<School>, String> {
+    repo::find_first(conn).map_err(|e| e.to_string())
+}
+
+/// Only one school profile is kept: the first row is updated, otherwise a new one is inserted.
+pub fn upsert_school_details(conn: &Connection, school: &School) -> Result<SchoolUpsert, String> {
+    match repo::find_first(conn).map_err(|e| e.to_string())? {
+        Some(existing) => {
+            let id = existing.id.unwrap_or(1);
+            let replaced_image = match (existing.school_image, school.school_image.as_ref()) {
+                (Some(old_image), Some(new_image)) if &old_image != new_image => {
+                    old_image.split('/').next_back().map(|name| name.to_string())
+                }
+                _ => None,
+            };
+
+            repo::update(conn, id, school).map_err(|e| e.to_string())?;
+            Ok(SchoolUpsert { id, replaced_image })
+        }
+        None => {
+            let id = repo::insert(conn, school).map_err(|e| e.to_string())?;
+            Ok(SchoolUpsert { id, replaced_image: None })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{memory_db, sample_school};
+
+    #[test]
+    fn first_save_inserts_profile() {
+        let conn = memory_db();
+        assert!(get_school_details(&conn).unwrap().is_none());
+
+        let saved = upsert_school_details(&conn, &sample_school()).unwrap();
+        assert!(saved.replaced_image.is_none());
+        let school = get_school_details(&conn).unwrap().unwrap();
+        assert_eq!(school.id, Some(saved.id));
+        assert_eq!(school.school_name, "Green Valley School");
+    }
+
+    #[test]
+    fn second_save_updates_the_same_row() {
+        let conn = memory_db();
+        let first = upsert_school_details(&conn, &sample_school()).unwrap();
+
+        let mut changed = sample_school();
+        changed.principal_name = "Dr. Mehta".to_string();
+        let second = upsert_school_details(&conn, &changed).unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert_eq!(get_school_details(&conn).unwrap().unwrap().principal_name, "Dr. Mehta");
+    }
+
+    #[test]
+    fn changing_logo_reports_the_old_file() {
+        let conn = memory_db();
+        let mut school = sample_school();
+        school.school_image = Some("images/logo_old.png".to_string());
+        upsert_school_details(&conn, &school).unwrap();
+
+        let saved = upsert_school_details(&conn, &school).unwrap();
+        assert!(saved.replaced_image.is_none());
+
+        school.school_image = Some("images/logo_new.png".to_string());
+        let saved = upsert_school_details(&conn, &school).unwrap();
+        assert_eq!(saved.replaced_image.as_deref(), Some("logo_old.png"));
+    }
+}
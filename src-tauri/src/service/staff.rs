@@ -0,0 +1,76 @@
+use crate::repository::staff as repo;
+use crate::staff::Staff;
+use rusqlite::Connection;
+
+fn status_or_active(staff: &Staff) -> String {
+    staff.status.clone().unwrap_or_else(|| "active".to_string())
+}
+
+/// Staff currently employed: marked in attendance and granted leave.
+pub fn is_active(status: Option<&str>) -> bool {
+    status.map_or(true, |s| s.trim().eq_ignore_ascii_case("active"))
+}
+
+pub fn create_staff(conn: &Connection, staff: Staff) -> Result<i64, String> {
+    repo::insert(conn, &staff, &status_or_active(&staff)).map_err(|e| e.to_string())
+}
+
+pub fn get_staff(conn: &Connection, id: i64) -> Result<Staff, String> {
+    repo::find_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())
+}
+
+pub fn get_all_staffs(conn: &Connection) -> Result<Vec<Staff>, String> {
+    repo::find_all(conn).map_err(|e| e.to_string())
+}
+
+pub fn update_staff(conn: &Connection, id: i64, staff: Staff) -> Result<(), String> {
+    repo::update(conn, id, &staff, &status_or_active(&staff)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn delete_staff(conn: &Connection, id: i64) -> Result<(), String> {
+    repo::delete(conn, id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{memory_db, sample_staff};
+
+    #[test]
+    fn create_defaults_status_to_active() {
+        let conn = memory_db();
+        let id = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let stored = get_staff(&conn, id).unwrap();
+        assert_eq!(stored.name, "Meera");
+        assert_eq!(stored.status.as_deref(), Some("active"));
+    }
+
+    #[test]
+    fn update_overwrites_fields() {
+        let conn = memory_db();
+        let id = create_staff(&conn, sample_staff("Meera")).unwrap();
+
+        let mut changed = sample_staff("Meera Iyer");
+        changed.status = Some("inactive".to_string());
+        update_staff(&conn, id, changed).unwrap();
+
+        let stored = get_staff(&conn, id).unwrap();
+        assert_eq!(stored.name, "Meera Iyer");
+        assert_eq!(stored.status.as_deref(), Some("inactive"));
+    }
+
+    #[test]
+    fn delete_removes_staff() {
+        let conn = memory_db();
+        let id = create_staff(&conn, sample_staff("Meera")).unwrap();
+        create_staff(&conn, sample_staff("Arjun")).unwrap();
+
+        delete_staff(&conn, id).unwrap();
+        assert!(get_staff(&conn, id).is_err());
+        assert_eq!(get_all_staffs(&conn).unwrap().len(), 1);
+    }
+}
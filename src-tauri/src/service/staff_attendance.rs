@@ -0,0 +1,471 @@
+use crate::date::{days_in_month, parse_time, Date};
+use crate::leave::{LeaveApplication, LeaveType};
+use crate::repository;
+use crate::repository::leave as leave_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::staff_attendance as repo;
+use crate::service::calendar::{self, DaysOff};
+use crate::service::leave::{counts_day, leave_share};
+use crate::service::staff::is_active;
+use crate::staff::Staff;
+use crate::staff_attendance::{MusterRoll, MusterRollRow, StaffAttendance, StaffDayRecord};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub const ATTENDANCE_STATUSES: [&str; 3] = ["present", "half_day", "absent"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn find_active_staff(conn: &Connection, staff_id: i64) -> Result<Staff, String> {
+    let staff = staff_repo::find_by_id(conn, staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    if !is_active(staff.status.as_deref()) {
+        return Err(format!("{} is not an active staff member", staff.name));
+    }
+    Ok(staff)
+}
+
+/// The given time, or the current time, checked to be `HH:MM`.
+fn time_or_now(conn: &Connection, time: Option<String>) -> Result<(String, u32), String> {
+    let time = match non_blank(time) {
+        Some(time) => time,
+        None => repository::now_time(conn).map_err(|e| e.to_string())?,
+    };
+    let minutes = parse_time(&time)?;
+    Ok((time.chars().take(5).collect(), minutes))
+}
+
+pub fn check_in(
+    conn: &Connection,
+    staff_id: i64,
+    time: Option<String>,
+) -> Result<StaffAttendance, String> {
+    find_active_staff(conn, staff_id)?;
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    let (time, _) = time_or_now(conn, time)?;
+
+    let mut record = match repo::find(conn, staff_id, &today).map_err(|e| e.to_string())? {
+        Some(record) => {
+            if let Some(check_in) = &record.check_in {
+                return Err(format!("Already checked in at {}", check_in));
+            }
+            record
+        }
+        None => StaffAttendance {
+            id: None,
+            staff_id,
+            date: today.clone(),
+            check_in: None,
+            check_out: None,
+            status: "present".to_string(),
+            remarks: None,
+        },
+    };
+    record.check_in = Some(time);
+    if record.status == "absent" {
+        record.status = "present".to_string();
+    }
+    repo::upsert(conn, &record).map_err(|e| e.to_string())?;
+    repo::find(conn, staff_id, &today)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Attendance record was not saved".to_string())
+}
+
+pub fn check_out(
+    conn: &Connection,
+    staff_id: i64,
+    time: Option<String>,
+) -> Result<StaffAttendance, String> {
+    find_active_staff(conn, staff_id)?;
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    let (time, minutes) = time_or_now(conn, time)?;
+
+    let mut record = repo::find(conn, staff_id, &today)
+        .map_err(|e| e.to_string())?
+        .filter(|r| r.check_in.is_some())
+        .ok_or_else(|| "Not checked in today".to_string())?;
+    if let Some(check_out) = &record.check_out {
+        return Err(format!("Already checked out at {}", check_out));
+    }
+    if minutes < parse_time(record.check_in.as_deref().unwrap_or_default())? {
+        return Err("Check-out cannot be earlier than check-in".to_string());
+    }
+    record.check_out = Some(time);
+    repo::upsert(conn, &record).map_err(|e| e.to_string())?;
+    Ok(record)
+}
+
+pub fn mark_attendance(conn: &Connection, mut attendance: StaffAttendance) -> Result<(), String> {
+    staff_repo::find_by_id(conn, attendance.staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    attendance.date = Date::parse(&attendance.date)?.to_string();
+    attendance.status = attendance.status.trim().to_lowercase();
+    if !ATTENDANCE_STATUSES.contains(&attendance.status.as_str()) {
+        return Err(format!(
+            "Invalid attendance status '{}'. Expected one of: {}",
+            attendance.status,
+            ATTENDANCE_STATUSES.join(", ")
+        ));
+    }
+    attendance.remarks = non_blank(attendance.remarks);
+    if attendance.status == "absent" {
+        attendance.check_in = None;
+        attendance.check_out = None;
+    }
+    attendance.check_in = non_blank(attendance.check_in);
+    attendance.check_out = non_blank(attendance.check_out);
+    match (&attendance.check_in, &attendance.check_out) {
+        (None, Some(_)) => return Err("Check-out needs a check-in time".to_string()),
+        (Some(check_in), Some(check_out)) if parse_time(check_out)? < parse_time(check_in)? => {
+            return Err("Check-out cannot be earlier than check-in".to_string())
+        }
+        (Some(check_in), _) => {
+            parse_time(check_in)?;
+        }
+        _ => {}
+    }
+    repo::upsert(conn, &attendance).map_err(|e| e.to_string())
+}
+
+/// Approved leave that counts `date` as a leave day for the staff member.
+fn leave_on<'a>(
+    leaves: &'a [LeaveApplication],
+    types: &'a HashMap<String, LeaveType>,
+    staff_id: i64,
+    date: Date,
+    days_off: &DaysOff,
+) -> Option<(&'a LeaveApplication, &'a LeaveType)> {
+    let day = date.to_string();
+    leaves
+        .iter()
+        .filter(|l| l.staff_id == staff_id && l.start_date <= day && l.end_date >= day)
+        .find_map(|l| types.get(&l.leave_type).map(|t| (l, t)))
+        .filter(|(_, t)| counts_day(t, date, days_off))
+}
+
+fn leave_types(conn: &Connection) -> Result<HashMap<String, LeaveType>, String> {
+    Ok(leave_repo::find_types(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|t| (t.leave_type.clone(), t))
+        .collect())
+}
+
+fn joined_by(staff: &Staff, date: Date) -> bool {
+    Date::parse(&staff.joining_date).map_or(true, |joined| joined <= date)
+}
+
+pub fn get_register(
+    conn: &Connection,
+    date: Option<String>,
+) -> Result<Vec<StaffDayRecord>, String> {
+    let date = match non_blank(date) {
+        Some(date) => Date::parse(&date)?,
+        None => Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?,
+    };
+    let day = date.to_string();
+    let types = leave_types(conn)?;
+    let days_off = calendar::days_off(conn, date, date)?;
+    let leaves = leave_repo::find_approved_between(conn, &day, &day).map_err(|e| e.to_string())?;
+    let mut attendance: HashMap<i64, StaffAttendance> = repo::find_between(conn, &day, &day)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|a| (a.staff_id, a))
+        .collect();
+
+    let mut staff = staff_repo::find_all(conn).map_err(|e| e.to_string())?;
+    staff.retain(|s| is_active(s.status.as_deref()) && joined_by(s, date));
+    staff.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(staff
+        .into_iter()
+        .map(|s| {
+            let staff_id = s.id.unwrap_or_default();
+            StaffDayRecord {
+                staff_id,
+                on_leave: leave_on(&leaves, &types, staff_id, date, &days_off)
+                    .map(|(_, t)| t.short_code.clone()),
+                attendance: attendance.remove(&staff_id),
+                name: s.name,
+                designation: s.designation,
+                department: s.department,
+            }
+        })
+        .collect())
+}
+
+/// Monthly muster roll. Approved leave takes precedence over attendance marks; days before
+/// joining and days still to come are left blank and not counted. Full-day holidays from the
+/// academic calendar are marked like weekly offs.
+pub fn get_muster_roll(conn: &Connection, year: i32, month: u32) -> Result<MusterRoll, String> {
+    let start = Date::new(year, month, 1)?;
+    let end = start.add_days(days_in_month(year, month) as i64 - 1);
+    let today = Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)?;
+    let (first, last) = (start.to_string(), end.to_string());
+
+    let types = leave_types(conn)?;
+    let days_off = calendar::days_off(conn, start, end)?;
+    let leaves =
+        leave_repo::find_approved_between(conn, &first, &last).map_err(|e| e.to_string())?;
+    let attendance: HashMap<(i64, String), StaffAttendance> =
+        repo::find_between(conn, &first, &last)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|a| ((a.staff_id, a.date.clone()), a))
+            .collect();
+
+    // Staff who have since left still appear for the months they have records in.
+    let mut staff = staff_repo::find_all(conn).map_err(|e| e.to_string())?;
+    staff.retain(|s| {
+        let id = s.id.unwrap_or_default();
+        joined_by(s, end)
+            && (is_active(s.status.as_deref())
+                || attendance.keys().any(|(staff_id, _)| *staff_id == id)
+                || leaves.iter().any(|l| l.staff_id == id))
+    });
+    staff.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let dates: Vec<Date> = (0..=start.days_until(end))
+        .map(|d| start.add_days(d))
+        .collect();
+    let rows = staff
+        .into_iter()
+        .map(|s| {
+            let staff_id = s.id.unwrap_or_default();
+            let mut row = MusterRollRow {
+                staff_id,
+                name: s.name.clone(),
+                designation: s.designation.clone(),
+                department: s.department.clone(),
+                employment_type: s.employment_type.clone(),
+                marks: Vec::with_capacity(dates.len()),
+                present: 0.0,
+                absent: 0.0,
+                paid_leave: 0.0,
+                unpaid_leave: 0.0,
+                weekly_offs: 0,
+                holidays: 0,
+                unmarked: 0,
+            };
+            for &date in &dates {
+                let record = attendance.get(&(staff_id, date.to_string()));
+                let mark = if date > today || !joined_by(&s, date) {
+                    String::new()
+                } else if let Some((leave, leave_type)) =
+                    leave_on(&leaves, &types, staff_id, date, &days_off)
+                {
+                    let share = if leave.half_day {
+                        0.5
+                    } else {
+                        leave_share(leave_type, date, &days_off)
+                    };
+                    if leave_type.paid {
+                        row.paid_leave += share;
+                    } else {
+                        row.unpaid_leave += share;
+                    }
+                    if leave.half_day {
+                        // The other half of the day is worked unless marked absent.
+                        if record.is_some_and(|r| r.status == "absent") {
+                            row.absent += 0.5;
+                        } else {
+                            row.present += 0.5;
+                        }
+                        format!("{}/2", leave_type.short_code)
+                    } else {
+                        leave_type.short_code.clone()
+                    }
+                } else if let Some(record) = record {
+                    match record.status.as_str() {
+                        "present" => {
+                            row.present += 1.0;
+                            "P".to_string()
+                        }
+                        "half_day" => {
+                            row.present += 0.5;
+                            row.absent += 0.5;
+                            "HD".to_string()
+                        }
+                        _ => {
+                            row.absent += 1.0;
+                            "A".to_string()
+                        }
+                    }
+                } else if days_off.is_weekly_off(date) {
+                    row.weekly_offs += 1;
+                    "WO".to_string()
+                } else if days_off.holidays.get(&date) == Some(&false) {
+                    row.holidays += 1;
+                    "H".to_string()
+                } else {
+                    row.unmarked += 1;
+                    String::new()
+                };
+                row.marks.push(mark);
+            }
+            row
+        })
+        .collect();
+
+    Ok(MusterRoll {
+        year,
+        month,
+        dates: dates.iter().map(Date::to_string).collect(),
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarEntry;
+    use crate::leave::LeaveApplication;
+    use crate::service::academic_year::upsert_academic_year;
+    use crate::service::leave::{apply_leave, approve_leave};
+    use crate::service::staff::create_staff;
+    use crate::test_support::{memory_db, sample_staff};
+
+    fn mark(
+        conn: &Connection,
+        staff_id: i64,
+        date: &str,
+        status: &str,
+        times: Option<(&str, &str)>,
+    ) {
+        mark_attendance(
+            conn,
+            StaffAttendance {
+                id: None,
+                staff_id,
+                date: date.to_string(),
+                check_in: times.map(|t| t.0.to_string()),
+                check_out: times.map(|t| t.1.to_string()),
+                status: status.to_string(),
+                remarks: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn approved_leave(
+        conn: &Connection,
+        staff_id: i64,
+        leave_type: &str,
+        start: &str,
+        end: &str,
+        half_day: bool,
+    ) {
+        let id = apply_leave(
+            conn,
+            LeaveApplication {
+                id: None,
+                staff_id,
+                leave_type: leave_type.to_string(),
+                start_date: start.to_string(),
+                end_date: end.to_string(),
+                half_day,
+                days: None,
+                reason: None,
+                status: None,
+                decided_by: None,
+                decided_on: None,
+                decision_note: None,
+                staff_name: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        approve_leave(conn, id, None, None).unwrap();
+    }
+
+    #[test]
+    fn check_in_and_out_are_recorded_once_in_order() {
+        let conn = memory_db();
+        let id = create_staff(&conn, sample_staff("Meera")).unwrap();
+
+        assert!(check_out(&conn, id, Some("17:00".to_string())).is_err());
+        let record = check_in(&conn, id, Some("08:55".to_string())).unwrap();
+        assert_eq!(
+            (record.check_in.as_deref(), record.status.as_str()),
+            (Some("08:55"), "present")
+        );
+        assert!(check_in(&conn, id, None).unwrap_err().contains("08:55"));
+        assert!(check_out(&conn, id, Some("08:00".to_string())).is_err());
+        assert!(check_out(&conn, id, Some("5pm".to_string())).is_err());
+
+        let record = check_out(&conn, id, Some("16:30".to_string())).unwrap();
+        assert_eq!(record.check_out.as_deref(), Some("16:30"));
+        let register = get_register(&conn, None).unwrap();
+        assert_eq!(
+            register[0]
+                .attendance
+                .as_ref()
+                .unwrap()
+                .check_out
+                .as_deref(),
+            Some("16:30")
+        );
+    }
+
+    #[test]
+    fn muster_roll_combines_attendance_leave_weekly_offs_and_holidays() {
+        let conn = memory_db();
+        let mut staff = sample_staff("Meera");
+        staff.employment_type = "Full-time".to_string();
+        let id = create_staff(&conn, staff).unwrap();
+        let mut inactive = sample_staff("Arjun");
+        inactive.status = Some("inactive".to_string());
+        create_staff(&conn, inactive).unwrap();
+
+        // June 2025 starts on a Sunday.
+        mark(&conn, id, "2025-06-02", "present", Some(("09:00", "16:00")));
+        mark(&conn, id, "2025-06-03", "half_day", None);
+        mark(&conn, id, "2025-06-04", "absent", Some(("09:00", "16:00")));
+        approved_leave(&conn, id, "casual", "2025-06-05", "2025-06-06", false);
+        approved_leave(&conn, id, "sick", "2025-06-07", "2025-06-07", true);
+        approved_leave(&conn, id, "unpaid", "2025-06-09", "2025-06-09", false);
+        mark(&conn, id, "2025-06-09", "present", None);
+        let year_id = upsert_academic_year(
+            &conn,
+            "2025 - 2026",
+            false,
+            Some("2025-06-01".to_string()),
+            Some("2026-04-30".to_string()),
+        )
+        .unwrap();
+        calendar::save_entry(
+            &conn,
+            CalendarEntry {
+                id: None,
+                academic_year_id: year_id,
+                kind: "holiday".to_string(),
+                title: "Founders' Day".to_string(),
+                start_date: "2025-06-10".to_string(),
+                end_date: None,
+                day_part: None,
+                description: None,
+            },
+        )
+        .unwrap();
+
+        let roll = get_muster_roll(&conn, 2025, 6).unwrap();
+        assert_eq!(roll.dates.len(), 30);
+        assert_eq!(roll.rows.len(), 1);
+        let row = &roll.rows[0];
+        assert_eq!(
+            &row.marks[..10],
+            &["WO", "P", "HD", "A", "CL", "CL", "SL/2", "WO", "LWP", "H"]
+        );
+        assert_eq!((row.present, row.absent), (2.0, 1.5));
+        assert_eq!((row.paid_leave, row.unpaid_leave), (2.5, 1.0));
+        assert_eq!((row.weekly_offs, row.holidays), (5, 1));
+        assert_eq!(row.unmarked, 30 - 5 - 8);
+
+        assert!(get_muster_roll(&conn, 2025, 13).is_err());
+    }
+}
@@ -0,0 +1,947 @@
+use crate::repository;
+use crate::repository::school as school_repo;
+use crate::repository::statutory as repo;
+use crate::service::class::{grade_order, split_class_name};
+use crate::statutory::{
+    BoardCandidate, EnrolmentRow, StatutoryDetails, TeacherProfile, ValidationIssue,
+    ValidationReport,
+};
+use crate::xlsx::{Cell, Workbook};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+
+/// UDISE+ social categories.
+pub const SOCIAL_CATEGORIES: [&str; 4] = ["general", "sc", "st", "obc"];
+/// `none` and the disability types of the RPwD Act 2016 that UDISE+ records.
+pub const DISABILITIES: [&str; 22] = [
+    "none",
+    "blindness",
+    "low_vision",
+    "hearing_impairment",
+    "speech_and_language",
+    "locomotor",
+    "mental_illness",
+    "specific_learning_disability",
+    "cerebral_palsy",
+    "autism_spectrum_disorder",
+    "multiple_disabilities",
+    "leprosy_cured",
+    "dwarfism",
+    "intellectual_disability",
+    "muscular_dystrophy",
+    "chronic_neurological_condition",
+    "multiple_sclerosis",
+    "thalassemia",
+    "hemophilia",
+    "sickle_cell_disease",
+    "acid_attack_victim",
+    "parkinsons_disease",
+];
+pub const EXPORTS: [&str; 3] = ["udise_enrolment", "udise_teachers", "board_candidates"];
+pub const FORMATS: [&str; 2] = ["csv", "xlsx"];
+
+const MISSING_CATEGORY: &str = "Caste category";
+const MISSING_AADHAAR: &str = "Aadhaar";
+const MISSING_DISABILITY: &str = "Disability status";
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn one_of(value: &str, options: &[&str], what: &str) -> Result<String, String> {
+    let value = value.trim().to_lowercase();
+    if options.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} '{}'. Expected one of: {}",
+            what,
+            value,
+            options.join(", ")
+        ))
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Verhoeff check digit tables; the last digit of an Aadhaar number is its Verhoeff checksum.
+const VERHOEFF_MULTIPLY: [[u8; 10]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+    [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+    [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+    [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+    [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+    [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+    [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+    [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+    [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+const VERHOEFF_PERMUTE: [[u8; 10]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+    [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+    [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+    [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
+    [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+    [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+    [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+];
+
+/// True when the trailing digit of `digits` is its Verhoeff checksum.
+fn verhoeff_valid(digits: &[u8]) -> bool {
+    let check = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .fold(0u8, |check, (position, digit)| {
+            VERHOEFF_MULTIPLY[check as usize]
+                [VERHOEFF_PERMUTE[position % 8][*digit as usize] as usize]
+        });
+    check == 0
+}
+
+/// The 12 digits of an Aadhaar number written with or without spaces or hyphens.
+fn aadhaar(value: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid Aadhaar number '{}'", value.trim());
+    let digits: String = value.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("{}, expected 12 digits", invalid()));
+    }
+    let numbers: Vec<u8> = digits.bytes().map(|b| b - b'0').collect();
+    // UIDAI never issues numbers starting with 0 or 1.
+    if numbers[0] < 2 || !verhoeff_valid(&numbers) {
+        return Err(invalid());
+    }
+    Ok(digits)
+}
+
+fn clean_details(details: StatutoryDetails) -> Result<StatutoryDetails, String> {
+    Ok(StatutoryDetails {
+        social_category: non_blank(details.social_category)
+            .map(|v| one_of(&v, &SOCIAL_CATEGORIES, "social category"))
+            .transpose()?,
+        aadhaar_number: non_blank(details.aadhaar_number)
+            .map(|v| aadhaar(&v))
+            .transpose()?,
+        disability: non_blank(details.disability)
+            .map(|v| one_of(&v.replace([' ', '-'], "_"), &DISABILITIES, "disability"))
+            .transpose()?,
+    })
+}
+
+pub fn get_student_details(conn: &Connection, student_id: i64) -> Result<StatutoryDetails, String> {
+    repo::find_student_details(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))
+}
+
+/// Aadhaar numbers are unique among students.
+pub fn save_student_details(
+    conn: &Connection,
+    student_id: i64,
+    details: StatutoryDetails,
+) -> Result<(), String> {
+    let details = clean_details(details)?;
+    if let Some(number) = &details.aadhaar_number {
+        if let Some(name) =
+            repo::student_with_aadhaar(conn, number, student_id).map_err(|e| e.to_string())?
+        {
+            return Err(format!(
+                "Aadhaar number {} is already recorded for {}",
+                number, name
+            ));
+        }
+    }
+    if repo::update_student_details(conn, student_id, &details).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Student with id {} not found", student_id));
+    }
+    Ok(())
+}
+
+pub fn get_staff_details(conn: &Connection, staff_id: i64) -> Result<StatutoryDetails, String> {
+    repo::find_staff_details(conn, staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Staff with id {} not found", staff_id))
+}
+
+/// Aadhaar numbers are unique among staff.
+pub fn save_staff_details(
+    conn: &Connection,
+    staff_id: i64,
+    details: StatutoryDetails,
+) -> Result<(), String> {
+    let details = clean_details(details)?;
+    if let Some(number) = &details.aadhaar_number {
+        if let Some(name) =
+            repo::staff_with_aadhaar(conn, number, staff_id).map_err(|e| e.to_string())?
+        {
+            return Err(format!(
+                "Aadhaar number {} is already recorded for {}",
+                number, name
+            ));
+        }
+    }
+    if repo::update_staff_details(conn, staff_id, &details).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Staff with id {} not found", staff_id));
+    }
+    Ok(())
+}
+
+fn grade_of(class_name: Option<&str>) -> String {
+    class_name
+        .map(|name| split_class_name(name).0)
+        .unwrap_or_else(|| "Unassigned".to_string())
+}
+
+/// Position in `SOCIAL_CATEGORIES`, with unrecorded categories last.
+fn category_rank(category: Option<&str>) -> usize {
+    category
+        .and_then(|c| SOCIAL_CATEGORIES.iter().position(|known| *known == c))
+        .unwrap_or(SOCIAL_CATEGORIES.len())
+}
+
+fn category_label(category: Option<&str>) -> &'static str {
+    match category {
+        Some("general") => "General",
+        Some("sc") => "SC",
+        Some("st") => "ST",
+        Some("obc") => "OBC",
+        _ => "Not recorded",
+    }
+}
+
+/// Stored keys as words for the exported files, e.g. "Low vision".
+fn humanize(key: Option<&str>) -> String {
+    let words = key.unwrap_or_default().replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn missing_details(details: &StatutoryDetails) -> Vec<String> {
+    let mut missing = Vec::new();
+    if details.social_category.is_none() {
+        missing.push(MISSING_CATEGORY.to_string());
+    }
+    if details.aadhaar_number.is_none() {
+        missing.push(MISSING_AADHAAR.to_string());
+    }
+    if details.disability.is_none() {
+        missing.push(MISSING_DISABILITY.to_string());
+    }
+    missing
+}
+
+/// Students on the roll by grade and social category, split by gender as UDISE+ asks.
+pub fn get_enrolment(conn: &Connection) -> Result<Vec<EnrolmentRow>, String> {
+    let students = repo::find_students_on_roll(conn).map_err(|e| e.to_string())?;
+    let mut rows: BTreeMap<(i64, String, usize), EnrolmentRow> = BTreeMap::new();
+    for student in &students {
+        let grade = grade_of(student.class_name.as_deref());
+        let category = student.details.social_category.as_deref();
+        let rank = category_rank(category);
+        let row = rows
+            .entry((grade_order(&grade), grade.clone(), rank))
+            .or_insert_with(|| EnrolmentRow {
+                grade,
+                social_category: SOCIAL_CATEGORIES
+                    .get(rank)
+                    .unwrap_or(&"not_recorded")
+                    .to_string(),
+                boys: 0,
+                girls: 0,
+                transgender: 0,
+                total: 0,
+                with_disability: 0,
+            });
+        match student.gender.trim().to_lowercase().as_str() {
+            "male" | "m" | "boy" => row.boys += 1,
+            "female" | "f" | "girl" => row.girls += 1,
+            _ => row.transgender += 1,
+        }
+        row.total += 1;
+        if student
+            .details
+            .disability
+            .as_deref()
+            .is_some_and(|d| d != "none")
+        {
+            row.with_disability += 1;
+        }
+    }
+    Ok(rows.into_values().collect())
+}
+
+/// Teaching staff with the subjects and grades they take this year.
+pub fn get_teacher_profiles(conn: &Connection) -> Result<Vec<TeacherProfile>, String> {
+    let teachers = repo::find_teachers(conn).map_err(|e| e.to_string())?;
+    let mut teaching: HashMap<i64, (Vec<String>, Vec<String>)> = HashMap::new();
+    for (staff_id, subject, class_name) in repo::find_teaching(conn).map_err(|e| e.to_string())? {
+        let (subjects, grades) = teaching.entry(staff_id).or_default();
+        if !subjects.contains(&subject) {
+            subjects.push(subject);
+        }
+        let grade = grade_of(Some(&class_name));
+        if !grades.contains(&grade) {
+            grades.push(grade);
+        }
+    }
+    Ok(teachers
+        .into_iter()
+        .map(|teacher| {
+            let (subjects, mut classes) = teaching.remove(&teacher.id).unwrap_or_default();
+            classes.sort_by_key(|grade| grade_order(grade));
+            TeacherProfile {
+                staff_id: teacher.id,
+                name: teacher.name,
+                gender: teacher.gender,
+                dob: teacher.dob,
+                social_category: teacher.details.social_category,
+                aadhaar_number: teacher.details.aadhaar_number,
+                disability: teacher.details.disability,
+                qualification: teacher.qualification,
+                designation: teacher.designation,
+                employment_type: teacher.employment_type,
+                joining_date: teacher.joining_date,
+                subjects,
+                classes,
+            }
+        })
+        .collect())
+}
+
+/// Students on the roll in the chosen classes, with the subjects mapped to their class.
+pub fn get_board_candidates(
+    conn: &Connection,
+    class_ids: &[i64],
+) -> Result<Vec<BoardCandidate>, String> {
+    if class_ids.is_empty() {
+        return Err("Choose the classes to register for the board examination".to_string());
+    }
+    let mut subjects: HashMap<i64, Vec<String>> = HashMap::new();
+    for (class_id, subject) in repo::find_class_subjects(conn).map_err(|e| e.to_string())? {
+        subjects.entry(class_id).or_default().push(subject);
+    }
+    Ok(repo::find_students_on_roll(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|student| {
+            let class_id = student.class_id.filter(|id| class_ids.contains(id))?;
+            Some(BoardCandidate {
+                student_id: student.id,
+                gr_number: student.gr_number,
+                roll_number: student.roll_number,
+                full_name: student.full_name,
+                father_name: student.father_name,
+                mother_name: student.mother_name,
+                dob: student.dob,
+                gender: student.gender,
+                social_category: student.details.social_category,
+                aadhaar_number: student.details.aadhaar_number,
+                disability: student.details.disability,
+                class_name: student.class_name.unwrap_or_default(),
+                subjects: subjects.get(&class_id).cloned().unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+fn issue(
+    record_type: &str,
+    record_id: Option<i64>,
+    name: &str,
+    class_name: Option<String>,
+    missing: Vec<String>,
+) -> Option<ValidationIssue> {
+    (!missing.is_empty()).then(|| ValidationIssue {
+        record_type: record_type.to_string(),
+        record_id,
+        name: name.to_string(),
+        class_name,
+        missing,
+    })
+}
+
+/// Every record `export` would include that lacks a field the portal or board requires,
+/// starting with the school's own UDISE code or affiliation number.
+pub fn validate(
+    conn: &Connection,
+    export: &str,
+    class_ids: &[i64],
+) -> Result<ValidationReport, String> {
+    let export = one_of(export, &EXPORTS, "export")?;
+    let mut issues = Vec::new();
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let school_missing = match &school {
+        None => vec!["School details".to_string()],
+        Some(school) if export == "board_candidates" => {
+            non_blank(school.affiliation_number.clone())
+                .map_or_else(|| vec!["Affiliation number".to_string()], |_| Vec::new())
+        }
+        Some(school) => non_blank(school.udise_code.clone())
+            .map_or_else(|| vec!["UDISE code".to_string()], |_| Vec::new()),
+    };
+    issues.extend(issue(
+        "school",
+        None,
+        "School profile",
+        None,
+        school_missing,
+    ));
+    let mut checked = 1;
+
+    match export.as_str() {
+        "udise_enrolment" => {
+            for student in repo::find_students_on_roll(conn).map_err(|e| e.to_string())? {
+                checked += 1;
+                let mut missing = missing_details(&student.details);
+                if student.class_name.is_none() {
+                    missing.push("Class".to_string());
+                }
+                issues.extend(issue(
+                    "student",
+                    Some(student.id),
+                    &student.full_name,
+                    student.class_name,
+                    missing,
+                ));
+            }
+        }
+        "udise_teachers" => {
+            for teacher in get_teacher_profiles(conn)? {
+                checked += 1;
+                let mut missing = missing_details(&StatutoryDetails {
+                    social_category: teacher.social_category,
+                    aadhaar_number: teacher.aadhaar_number,
+                    disability: teacher.disability,
+                });
+                if teacher.dob.trim().is_empty() {
+                    missing.push("Date of birth".to_string());
+                }
+                if teacher.qualification.trim().is_empty() {
+                    missing.push("Qualification".to_string());
+                }
+                issues.extend(issue(
+                    "teacher",
+                    Some(teacher.staff_id),
+                    &teacher.name,
+                    None,
+                    missing,
+                ));
+            }
+        }
+        _ => {
+            for candidate in get_board_candidates(conn, class_ids)? {
+                checked += 1;
+                let mut missing = missing_details(&StatutoryDetails {
+                    social_category: candidate.social_category,
+                    aadhaar_number: candidate.aadhaar_number,
+                    disability: candidate.disability,
+                });
+                if non_blank(candidate.dob).is_none() {
+                    missing.push("Date of birth".to_string());
+                }
+                if candidate.father_name.trim().is_empty() {
+                    missing.push("Father's name".to_string());
+                }
+                if candidate.mother_name.trim().is_empty() {
+                    missing.push("Mother's name".to_string());
+                }
+                if candidate.subjects.is_empty() {
+                    missing.push("Subjects".to_string());
+                }
+                issues.extend(issue(
+                    "student",
+                    Some(candidate.student_id),
+                    &candidate.full_name,
+                    Some(candidate.class_name),
+                    missing,
+                ));
+            }
+        }
+    }
+
+    Ok(ValidationReport {
+        export,
+        checked,
+        issues,
+    })
+}
+
+fn render(title: &str, header: &[&str], rows: Vec<Vec<Cell>>, format: &str) -> Vec<u8> {
+    if format == "xlsx" {
+        let mut workbook = Workbook::new(title, header.iter().map(|h| h.to_string()).collect());
+        for row in rows {
+            workbook.add_row(row);
+        }
+        return workbook.to_bytes();
+    }
+    let mut csv = header
+        .iter()
+        .map(|h| csv_field(h))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| match cell {
+                Cell::Text(text) => csv_field(text),
+                Cell::Number(number) => number.to_string(),
+                Cell::Empty => String::new(),
+            })
+            .collect();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv.into_bytes()
+}
+
+fn text(value: impl Into<String>) -> Cell {
+    let value = value.into();
+    if value.is_empty() {
+        Cell::Empty
+    } else {
+        Cell::Text(value)
+    }
+}
+
+fn file_name(title: &str, conn: &Connection, format: &str) -> Result<String, String> {
+    let today = repository::today(conn).map_err(|e| e.to_string())?;
+    Ok(format!("{}-{}.{}", title.replace(' ', "-"), today, format))
+}
+
+/// File name and contents of the validation report for `export`.
+pub fn render_validation(
+    conn: &Connection,
+    export: &str,
+    class_ids: &[i64],
+    format: &str,
+) -> Result<(String, Vec<u8>), String> {
+    let format = one_of(format, &FORMATS, "export format")?;
+    let report = validate(conn, export, class_ids)?;
+    let rows = report
+        .issues
+        .into_iter()
+        .map(|issue| {
+            vec![
+                text(humanize(Some(&issue.record_type))),
+                issue
+                    .record_id
+                    .map_or(Cell::Empty, |id| Cell::Number(id as f64)),
+                text(issue.name),
+                text(issue.class_name.unwrap_or_default()),
+                text(issue.missing.join("; ")),
+            ]
+        })
+        .collect();
+    let title = format!("{} Validation", humanize(Some(&report.export)));
+    let bytes = render(
+        &title,
+        &["Record", "ID", "Name", "Class", "Missing"],
+        rows,
+        &format,
+    );
+    Ok((file_name(&title, conn, &format)?, bytes))
+}
+
+/// File name and contents of a return in the portal's column layout.
+pub fn render_export(
+    conn: &Connection,
+    export: &str,
+    class_ids: &[i64],
+    format: &str,
+    allow_incomplete: bool,
+) -> Result<(String, Vec<u8>), String> {
+    let format = one_of(format, &FORMATS, "export format")?;
+    let report = validate(conn, export, class_ids)?;
+    if !report.issues.is_empty() && !allow_incomplete {
+        return Err(format!(
+            "{} record(s) are missing required fields; see the validation report",
+            report.issues.len()
+        ));
+    }
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let udise_code = school
+        .as_ref()
+        .and_then(|s| s.udise_code.clone())
+        .unwrap_or_default();
+
+    let (title, header, rows): (&str, &[&str], Vec<Vec<Cell>>) = match report.export.as_str() {
+        "udise_enrolment" => (
+            "UDISE Enrolment",
+            &[
+                "UDISE code",
+                "Grade",
+                "Social category",
+                "Boys",
+                "Girls",
+                "Transgender",
+                "Total",
+                "CWSN",
+            ],
+            get_enrolment(conn)?
+                .into_iter()
+                .map(|row| {
+                    let category = category_label(Some(&row.social_category));
+                    vec![
+                        text(udise_code.as_str()),
+                        text(row.grade),
+                        text(category),
+                        Cell::Number(row.boys as f64),
+                        Cell::Number(row.girls as f64),
+                        Cell::Number(row.transgender as f64),
+                        Cell::Number(row.total as f64),
+                        Cell::Number(row.with_disability as f64),
+                    ]
+                })
+                .collect(),
+        ),
+        "udise_teachers" => (
+            "UDISE Teachers",
+            &[
+                "UDISE code",
+                "Name",
+                "Gender",
+                "Date of birth",
+                "Social category",
+                "Aadhaar",
+                "Disability",
+                "Academic qualification",
+                "Designation",
+                "Nature of appointment",
+                "Date of joining",
+                "Subjects taught",
+                "Classes taught",
+            ],
+            get_teacher_profiles(conn)?
+                .into_iter()
+                .map(|teacher| {
+                    vec![
+                        text(udise_code.as_str()),
+                        text(teacher.name),
+                        text(teacher.gender),
+                        text(teacher.dob),
+                        text(category_label(teacher.social_category.as_deref())),
+                        text(teacher.aadhaar_number.unwrap_or_default()),
+                        text(humanize(teacher.disability.as_deref())),
+                        text(teacher.qualification),
+                        text(teacher.designation),
+                        text(teacher.employment_type),
+                        text(teacher.joining_date),
+                        text(teacher.subjects.join("; ")),
+                        text(teacher.classes.join("; ")),
+                    ]
+                })
+                .collect(),
+        ),
+        _ => {
+            let affiliation = school
+                .as_ref()
+                .and_then(|s| s.affiliation_number.clone())
+                .unwrap_or_default();
+            (
+                "Board Candidates",
+                &[
+                    "Affiliation number",
+                    "Class",
+                    "Roll number",
+                    "GR number",
+                    "Candidate name",
+                    "Father's name",
+                    "Mother's name",
+                    "Date of birth",
+                    "Gender",
+                    "Category",
+                    "Aadhaar",
+                    "Disability",
+                    "Subjects",
+                ],
+                get_board_candidates(conn, class_ids)?
+                    .into_iter()
+                    .map(|candidate| {
+                        vec![
+                            text(affiliation.as_str()),
+                            text(candidate.class_name),
+                            text(candidate.roll_number.unwrap_or_default()),
+                            text(candidate.gr_number),
+                            text(candidate.full_name),
+                            text(candidate.father_name),
+                            text(candidate.mother_name),
+                            text(candidate.dob.unwrap_or_default()),
+                            text(candidate.gender),
+                            text(category_label(candidate.social_category.as_deref())),
+                            text(candidate.aadhaar_number.unwrap_or_default()),
+                            text(humanize(candidate.disability.as_deref())),
+                            text(candidate.subjects.join("; ")),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+    };
+    Ok((
+        file_name(title, conn, &format)?,
+        render(title, header, rows, &format),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::school::upsert_school_details;
+    use crate::service::staff::create_staff;
+    use crate::service::students::save_core;
+    use crate::service::subject::{assign_class_subject, create_subject};
+    use crate::staff::Staff;
+    use crate::subject::{ClassSubject, Subject};
+    use crate::test_support::{
+        create_class, memory_db, sample_school, sample_staff, sample_student,
+    };
+
+    fn details(category: &str, aadhaar: &str, disability: &str) -> StatutoryDetails {
+        StatutoryDetails {
+            social_category: Some(category.to_string()),
+            aadhaar_number: Some(aadhaar.to_string()),
+            disability: Some(disability.to_string()),
+        }
+    }
+
+    fn add_student(conn: &Connection, class_id: i64, gr_number: &str, gender: &str) -> i64 {
+        let mut student = sample_student(&class_id.to_string(), gr_number);
+        student.core.gender = gender.to_string();
+        save_core(conn, student.core).unwrap()
+    }
+
+    fn teach(conn: &Connection, class_id: i64, code: &str, name: &str, teacher_id: Option<i64>) {
+        let subject_id = create_subject(
+            conn,
+            Subject {
+                id: None,
+                code: code.to_string(),
+                name: name.to_string(),
+                subject_type: "theory".to_string(),
+                status: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        assign_class_subject(
+            conn,
+            ClassSubject {
+                id: None,
+                class_id,
+                subject_id,
+                periods_per_week: 6,
+                teacher_id,
+                academic_year_id: None,
+                class_name: None,
+                subject_code: None,
+                subject_name: None,
+                subject_type: None,
+                teacher_name: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn details_are_checked_before_saving() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-6 A");
+        let first = add_student(&conn, class_id, "S1", "Female");
+        let second = add_student(&conn, class_id, "S2", "Male");
+
+        assert!(verhoeff_valid(&[2, 3, 6, 3]));
+        save_student_details(
+            &conn,
+            first,
+            details(" OBC ", "2345 6789 0124", "Low vision"),
+        )
+        .unwrap();
+        let saved = get_student_details(&conn, first).unwrap();
+        assert_eq!(saved.social_category.as_deref(), Some("obc"));
+        assert_eq!(saved.aadhaar_number.as_deref(), Some("234567890124"));
+        assert_eq!(saved.disability.as_deref(), Some("low_vision"));
+
+        let fails = |details: StatutoryDetails| save_student_details(&conn, second, details);
+        assert!(fails(details("sc", "2345-6789-0125", "none"))
+            .unwrap_err()
+            .contains("Invalid Aadhaar"));
+        assert!(fails(details("sc", "1234 5678 9012", "none"))
+            .unwrap_err()
+            .contains("Invalid Aadhaar"));
+        assert!(fails(details("sc", "234567890124", "none"))
+            .unwrap_err()
+            .contains("already recorded for Student S1"));
+        assert!(fails(details("minority", "345678901238", "none"))
+            .unwrap_err()
+            .contains("Invalid social category"));
+        save_student_details(&conn, second, StatutoryDetails::default()).unwrap();
+
+        // Staff are numbered separately from students.
+        let staff_id = create_staff(&conn, sample_staff("Meera Iyer")).unwrap();
+        save_staff_details(&conn, staff_id, details("general", "234567890124", "none")).unwrap();
+        assert_eq!(
+            get_staff_details(&conn, staff_id)
+                .unwrap()
+                .social_category
+                .as_deref(),
+            Some("general")
+        );
+        assert!(save_staff_details(&conn, 999, StatutoryDetails::default())
+            .unwrap_err()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn enrolment_is_counted_and_gaps_are_reported() {
+        let conn = memory_db();
+        let six_a = create_class(&conn, "Class-6 A");
+        let six_b = create_class(&conn, "Class-6 B");
+        let nursery = create_class(&conn, "Nursery A");
+        let girl = add_student(&conn, six_a, "S1", "Female");
+        let boy = add_student(&conn, six_b, "S2", "male");
+        let other = add_student(&conn, six_b, "S3", "Female");
+        let little = add_student(&conn, nursery, "S4", "Male");
+        save_student_details(&conn, girl, details("sc", "234567890124", "none")).unwrap();
+        save_student_details(&conn, boy, details("sc", "345678901238", "locomotor")).unwrap();
+        save_student_details(&conn, little, details("general", "456789012341", "none")).unwrap();
+
+        let rows: Vec<_> = get_enrolment(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|r| {
+                (
+                    r.grade,
+                    r.social_category,
+                    r.boys,
+                    r.girls,
+                    r.total,
+                    r.with_disability,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("Nursery".to_string(), "general".to_string(), 1, 0, 1, 0),
+                ("Class-6".to_string(), "sc".to_string(), 1, 1, 2, 1),
+                (
+                    "Class-6".to_string(),
+                    "not_recorded".to_string(),
+                    0,
+                    1,
+                    1,
+                    0
+                ),
+            ]
+        );
+
+        let report = validate(&conn, "udise_enrolment", &[]).unwrap();
+        assert_eq!(report.checked, 5);
+        let gaps: Vec<_> = report
+            .issues
+            .iter()
+            .map(|i| (i.record_type.as_str(), i.record_id, i.missing.join(", ")))
+            .collect();
+        assert_eq!(
+            gaps,
+            vec![
+                ("school", None, "School details".to_string()),
+                (
+                    "student",
+                    Some(other),
+                    "Caste category, Aadhaar, Disability status".to_string()
+                ),
+            ]
+        );
+        assert!(render_export(&conn, "udise_enrolment", &[], "csv", false)
+            .unwrap_err()
+            .contains("2 record(s)"));
+
+        upsert_school_details(&conn, &sample_school()).unwrap();
+        let (file_name, csv) = render_export(&conn, "udise_enrolment", &[], "csv", true).unwrap();
+        assert!(file_name.starts_with("UDISE-Enrolment-"));
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "UDISE code,Grade,Social category,Boys,Girls,Transgender,Total,CWSN"
+        );
+        assert_eq!(lines[2], "27251234567,Class-6,SC,1,1,0,2,1");
+        assert_eq!(lines[3], "27251234567,Class-6,Not recorded,0,1,0,1,0");
+    }
+
+    #[test]
+    fn teacher_profiles_and_board_candidates() {
+        let conn = memory_db();
+        upsert_school_details(&conn, &sample_school()).unwrap();
+        let ten = create_class(&conn, "Class-10 A");
+        let nine = create_class(&conn, "Class-9 A");
+        let teacher = create_staff(
+            &conn,
+            Staff {
+                designation: "PGT".to_string(),
+                ..sample_staff("Meera Iyer")
+            },
+        )
+        .unwrap();
+        create_staff(
+            &conn,
+            Staff {
+                designation: "Accountant".to_string(),
+                ..sample_staff("Vijay Rao")
+            },
+        )
+        .unwrap();
+        teach(&conn, ten, "MATH", "Mathematics", Some(teacher));
+        teach(&conn, nine, "SCI", "Science", Some(teacher));
+        teach(&conn, ten, "ENG", "English", None);
+
+        let profiles = get_teacher_profiles(&conn).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].subjects, vec!["Mathematics", "Science"]);
+        assert_eq!(profiles[0].classes, vec!["Class-9", "Class-10"]);
+
+        let candidate = add_student(&conn, ten, "S1", "Female");
+        add_student(&conn, nine, "S2", "Female");
+        assert!(get_board_candidates(&conn, &[]).is_err());
+        let candidates = get_board_candidates(&conn, &[ten]).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].subjects, vec!["English", "Mathematics"]);
+
+        let report = validate(&conn, "board_candidates", &[ten]).unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.issues.len(), 1);
+        let (file_name, xlsx) =
+            render_validation(&conn, "board_candidates", &[ten], "xlsx").unwrap();
+        assert!(file_name.starts_with("Board-candidates-Validation-"));
+        assert_eq!(&xlsx[..2], b"PK");
+
+        save_student_details(&conn, candidate, details("st", "234567890124", "none")).unwrap();
+        let (_, csv) = render_export(&conn, "board_candidates", &[ten], "csv", false).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "1130456,Class-10 A,1,S1,Student S1,Rajesh,Sunita,2015-04-12,Female,ST,234567890124,None,English; Mathematics"
+        );
+
+        let teachers = validate(&conn, "udise_teachers", &[]).unwrap();
+        assert_eq!(
+            teachers.issues[0].missing,
+            vec![MISSING_CATEGORY, MISSING_AADHAAR, MISSING_DISABILITY]
+        );
+    }
+}
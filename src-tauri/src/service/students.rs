@@ -0,0 +1,267 @@
+use crate::class::Class;
+use crate::repository::students as repo;
+use crate::service::academic_year::ensure_class_open;
+use crate::service::class as class_service;
+use crate::service::guardian::{link_inline_parents, sync_inline_parents};
+use crate::service::lifecycle;
+use crate::students::{Student, StudentContact, StudentCore, StudentDocs, StudentHealth};
+use rusqlite::Connection;
+
+/// The student's class with its numeric id.
+fn find_class(conn: &Connection, class_id: &str) -> Result<(i64, Class), String> {
+    let exists = repo::class_exists(conn, class_id)
+        .map_err(|e| format!("Class validation failed: {}", e))?;
+    let id = class_id.trim().parse::<i64>().ok().filter(|_| exists);
+    match id {
+        Some(id) => Ok((id, class_service::get_class(conn, id)?)),
+        None => Err(format!("Class with id {} does not exist", class_id)),
+    }
+}
+
+/// Fails for students who are off the rolls (left, transferred, graduated, ...). Anything that
+/// bills or marks a student (fee invoices, attendance) must check this first.
+pub fn ensure_on_roll(conn: &Connection, id: i64) -> Result<(), String> {
+    let status = repo::find_status(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", id))?;
+    if !lifecycle::is_on_roll(status.as_deref()) {
+        return Err(format!(
+            "Student with id {} is not on the rolls (status: {})",
+            id,
+            status.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the single student when `id` is given. Otherwise lists students with `status`,
+/// which defaults to active; pass "all" for every student.
+pub fn get_students(
+    conn: &Connection,
+    id: Option<i64>,
+    status: Option<String>,
+) -> Result<Vec<Student>, String> {
+    match id {
+        Some(student_id) => {
+            let student = repo::find_by_id(conn, student_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+            Ok(vec![student])
+        }
+        None => {
+            let status = match status.as_deref().map(str::trim) {
+                None | Some("") => Some("active".to_string()),
+                Some(s) if s.eq_ignore_ascii_case("all") => None,
+                Some(s) => Some(lifecycle::normalize_status(s)?),
+            };
+            repo::find_all(conn, status.as_deref()).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Step one of the admission form: creates the student, or updates it when `core.id` is set.
+/// The section always follows the class. A new student for a full class is rejected, or
+/// waitlisted as an applicant when the class keeps a waitlist.
+pub fn save_core(conn: &Connection, mut core: StudentCore) -> Result<i64, String> {
+    let (class_id, class) = find_class(conn, &core.class_id)?;
+    if class.section.is_some() {
+        core.section = class.section;
+    }
+
+    match core.id {
+        Some(id) => {
+            let current = repo::find_by_id(conn, id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Student with id {} not found", id))?;
+            let moving = current.core.class_id.trim() != core.class_id.trim();
+            if moving {
+                // Moving between classes changes both years' rolls.
+                if let Ok(previous) = current.core.class_id.trim().parse() {
+                    ensure_class_open(conn, previous)?;
+                }
+                ensure_class_open(conn, class_id)?;
+                if lifecycle::is_on_roll(current.health.status.as_deref()) {
+                    class_service::ensure_seat(conn, class_id)?;
+                }
+            }
+            repo::update_core(conn, id, &core).map_err(|e| e.to_string())?;
+            sync_inline_parents(conn, id)?;
+            Ok(id)
+        }
+        None => {
+            ensure_class_open(conn, class_id)?;
+            let waitlisted = class_service::admission_waitlisted(conn, class_id)?;
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            let id = repo::insert_core(&tx, &core).map_err(|e| e.to_string())?;
+            if waitlisted {
+                class_service::waitlist_student(&tx, class_id, id)?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+    }
+}
+
+/// Step two of the admission form. Once the family phone is known, the parents from step one
+/// are linked as guardians, reusing a sibling's records when they match. Later saves keep the
+/// linked guardians in step with the form.
+pub fn save_contact(conn: &Connection, id: i64, contact: StudentContact) -> Result<(), String> {
+    repo::update_contact(conn, id, &contact).map_err(|e| e.to_string())?;
+    link_inline_parents(conn, id)
+}
+
+/// Step three of the admission form. The status field is applied through the lifecycle rules.
+pub fn save_health(conn: &Connection, id: i64, health: StudentHealth) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::update_health(&tx, id, &health).map_err(|e| e.to_string())?;
+    lifecycle::set_form_status(&tx, id, health.status, health.admission_date)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn save_docs(conn: &Connection, id: i64, docs: StudentDocs) -> Result<(), String> {
+    repo::update_docs(conn, id, &docs).map_err(|e| format!("DB update failed: {}", e))?;
+    Ok(())
+}
+
+/// Inserts all students or none: any invalid class, full class or duplicate GR number rolls
+/// back the batch.
+pub fn bulk_insert(conn: &mut Connection, students: Vec<Student>) -> Result<Vec<i64>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut student_ids = Vec::with_capacity(students.len());
+
+    for mut student in students {
+        let (class_id, class) = find_class(&tx, &student.core.class_id)?;
+        ensure_class_open(&tx, class_id)?;
+        if class.section.is_some() {
+            student.core.section = class.section;
+        }
+
+        let gr_exists = repo::gr_number_exists(&tx, &student.core.gr_number)
+            .map_err(|e| format!("GR number check failed: {}", e))?;
+        if gr_exists {
+            return Err(format!(
+                "Student with GR number {} already exists",
+                student.core.gr_number
+            ));
+        }
+
+        // The status is applied separately so the lifecycle history starts with it.
+        let status = student.health.status.take();
+        let status_on_roll = match status.as_deref().map(str::trim) {
+            None | Some("") => true,
+            Some(s) => lifecycle::is_on_roll(Some(s.to_lowercase().as_str())),
+        };
+        if status_on_roll {
+            class_service::ensure_seat(&tx, class_id)?;
+        }
+        let student_id = repo::insert_full(&tx, &student).map_err(|e| e.to_string())?;
+        lifecycle::set_form_status(&tx, student_id, status, student.health.admission_date)?;
+        link_inline_parents(&tx, student_id)?;
+        student_ids.push(student_id);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(student_ids)
+}
+
+/// Deletes the student and returns the document file names that should be removed from disk.
+pub fn delete_student(conn: &Connection, id: i64) -> Result<Option<StudentDocs>, String> {
+    let docs = repo::find_docs(conn, id).map_err(|e| e.to_string())?;
+    repo::delete(conn, id).map_err(|e| e.to_string())?;
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_class, memory_db, sample_student};
+
+    #[test]
+    fn save_core_requires_existing_class() {
+        let conn = memory_db();
+        let err = save_core(&conn, sample_student("999", "GR-1").core).unwrap_err();
+        assert!(err.contains("does not exist"));
+    }
+
+    #[test]
+    fn save_core_creates_then_updates() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+
+        let id = save_core(&conn, sample_student(&class_id, "GR-1").core).unwrap();
+        let mut core = sample_student(&class_id, "GR-1").core;
+        core.id = Some(id);
+        core.full_name = "Kavya Sharma".to_string();
+        assert_eq!(save_core(&conn, core).unwrap(), id);
+
+        let students = get_students(&conn, Some(id), None).unwrap();
+        assert_eq!(students[0].core.full_name, "Kavya Sharma");
+        assert_eq!(students[0].core.class_name.as_deref(), Some("Class-1 A"));
+    }
+
+    #[test]
+    fn wizard_steps_fill_remaining_sections() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-1 A").to_string();
+        let student = sample_student(&class_id, "GR-1");
+        let id = save_core(&conn, student.core).unwrap();
+
+        let mut contact = student.contact;
+        contact.city = Some("Pune".to_string());
+        save_contact(&conn, id, contact).unwrap();
+
+        let mut health = student.health;
+        health.blood_group = Some("O+".to_string());
+        health.weight_kg = Some(31.5);
+        save_health(&conn, id, health).unwrap();
+
+        let mut docs = student.docs;
+        docs.birth_certificate = Some(format!("{}_birth_certificate.pdf", id));
+        save_docs(&conn, id, docs).unwrap();
+
+        let stored = &get_students(&conn, Some(id), None).unwrap()[0];
+        assert_eq!(stored.contact.city.as_deref(), Some("Pune"));
+        assert_eq!(stored.health.blood_group.as_deref(), Some("O+"));
+        assert_eq!(stored.health.weight_kg, Some(31.5));
+        assert!(stored.docs.birth_certificate.is_some());
+    }
+
+    #[test]
+    fn bulk_insert_is_all_or_nothing() {
+        let mut conn = memory_db();
+        let class_id = create_class(&conn, "Class-2 A").to_string();
+
+        let ids = bulk_insert(
+            &mut conn,
+            vec![sample_student(&class_id, "GR-1"), sample_student(&class_id, "GR-2")],
+        )
+        .unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let err = bulk_insert(
+            &mut conn,
+            vec![sample_student(&class_id, "GR-3"), sample_student(&class_id, "GR-1")],
+        )
+        .unwrap_err();
+        assert!(err.contains("GR-1"));
+
+        let err = bulk_insert(&mut conn, vec![sample_student("999", "GR-4")]).unwrap_err();
+        assert!(err.contains("999"));
+
+        assert_eq!(get_students(&conn, None, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn delete_returns_documents_for_cleanup() {
+        let mut conn = memory_db();
+        let class_id = create_class(&conn, "Class-3 A").to_string();
+        let mut student = sample_student(&class_id, "GR-1");
+        student.docs.id_proof = Some("1_id_proof.pdf".to_string());
+        let id = bulk_insert(&mut conn, vec![student]).unwrap()[0];
+
+        let docs = delete_student(&conn, id).unwrap().unwrap();
+        assert_eq!(docs.id_proof.as_deref(), Some("1_id_proof.pdf"));
+        assert!(get_students(&conn, Some(id), None).is_err());
+        assert!(delete_student(&conn, id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,439 @@
+use crate::repository::academic_year as year_repo;
+use crate::repository::class as class_repo;
+use crate::repository::homework as homework_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::subject as repo;
+use crate::service::academic_year::ensure_open;
+use crate::service::staff::is_active;
+use crate::staff::Staff;
+use crate::subject::{ClassSubject, ClassTeacher, Subject, TeacherWorkload, WorkloadItem};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+pub const SUBJECT_TYPES: [&str; 3] = ["theory", "practical", "elective"];
+
+/// Weekly periods a teacher can take unless a limit is set for them.
+pub const DEFAULT_MAX_PERIODS_PER_WEEK: u32 = 30;
+
+fn normalize(subject: &mut Subject) -> Result<(), String> {
+    subject.code = subject.code.trim().to_uppercase();
+    subject.name = subject.name.trim().to_string();
+    subject.subject_type = subject.subject_type.trim().to_lowercase();
+    if subject.code.is_empty() || subject.name.is_empty() {
+        return Err("Subject code and name are required".to_string());
+    }
+    if !SUBJECT_TYPES.contains(&subject.subject_type.as_str()) {
+        return Err(format!(
+            "Invalid subject type '{}'. Expected one of: {}",
+            subject.subject_type,
+            SUBJECT_TYPES.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn status_or_active(subject: &Subject) -> String {
+    subject
+        .status
+        .clone()
+        .unwrap_or_else(|| "active".to_string())
+}
+
+fn find_teacher(conn: &Connection, staff_id: i64) -> Result<Staff, String> {
+    let staff = staff_repo::find_by_id(conn, staff_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    if !is_active(staff.status.as_deref()) {
+        return Err(format!("{} is not an active staff member", staff.name));
+    }
+    Ok(staff)
+}
+
+/// The given academic year, or the current one.
+pub fn resolve_academic_year(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+) -> Result<i64, String> {
+    match academic_year_id {
+        Some(id) => Ok(id),
+        None => year_repo::find_current(conn)
+            .map_err(|e| e.to_string())?
+            .and_then(|year| year.id)
+            .ok_or_else(|| "No current academic year is set".to_string()),
+    }
+}
+
+pub fn create_subject(conn: &Connection, mut subject: Subject) -> Result<i64, String> {
+    normalize(&mut subject)?;
+    if repo::code_taken(conn, &subject.code, None).map_err(|e| e.to_string())? {
+        return Err(format!("Subject code '{}' is already in use", subject.code));
+    }
+    repo::insert(conn, &subject, &status_or_active(&subject)).map_err(|e| e.to_string())
+}
+
+pub fn get_subjects(conn: &Connection, include_inactive: bool) -> Result<Vec<Subject>, String> {
+    repo::find_all(conn, include_inactive).map_err(|e| e.to_string())
+}
+
+pub fn update_subject(conn: &Connection, id: i64, mut subject: Subject) -> Result<(), String> {
+    normalize(&mut subject)?;
+    if repo::code_taken(conn, &subject.code, Some(id)).map_err(|e| e.to_string())? {
+        return Err(format!("Subject code '{}' is already in use", subject.code));
+    }
+    let updated =
+        repo::update(conn, id, &subject, &status_or_active(&subject)).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Subject not found".to_string());
+    }
+    Ok(())
+}
+
+/// Subjects still taught in a class or with homework on record cannot be deleted; mark them
+/// inactive instead.
+pub fn delete_subject(conn: &Connection, id: i64) -> Result<(), String> {
+    let mapped = repo::count_class_mappings(conn, id).map_err(|e| e.to_string())?;
+    if mapped > 0 {
+        return Err(format!(
+            "Subject is mapped to {} class(es); remove it from them or mark it inactive",
+            mapped
+        ));
+    }
+    let homework = homework_repo::count_for_subject(conn, id).map_err(|e| e.to_string())?;
+    if homework > 0 {
+        return Err(format!(
+            "Subject has {} homework posted against it; mark it inactive instead",
+            homework
+        ));
+    }
+    if repo::delete(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err("Subject not found".to_string());
+    }
+    Ok(())
+}
+
+pub fn assign_class_subject(conn: &Connection, assignment: ClassSubject) -> Result<i64, String> {
+    let class = class_repo::find_by_id(conn, assignment.class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())?;
+    ensure_open(conn, class.academic_years)?;
+    let subject = repo::find_by_id(conn, assignment.subject_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Subject not found".to_string())?;
+    if subject.status.as_deref() != Some("active") {
+        return Err(format!("Subject {} is inactive", subject.name));
+    }
+    if assignment.periods_per_week > 60 {
+        return Err("Periods per week cannot exceed 60".to_string());
+    }
+    if let Some(teacher_id) = assignment.teacher_id {
+        find_teacher(conn, teacher_id)?;
+    }
+    repo::upsert_class_subject(conn, &assignment, class.academic_years).map_err(|e| e.to_string())
+}
+
+pub fn remove_class_subject(conn: &Connection, id: i64) -> Result<(), String> {
+    let assignment = repo::find_class_subject(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class subject not found".to_string())?;
+    ensure_open(conn, assignment.academic_year_id.unwrap_or_default())?;
+    if repo::delete_class_subject(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err("Class subject not found".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_class_subjects(conn: &Connection, class_id: i64) -> Result<Vec<ClassSubject>, String> {
+    repo::find_class_subjects(conn, class_id).map_err(|e| e.to_string())
+}
+
+pub fn set_subject_teacher(
+    conn: &Connection,
+    class_subject_id: i64,
+    teacher_id: Option<i64>,
+) -> Result<Option<TeacherWorkload>, String> {
+    let assignment = repo::find_class_subject(conn, class_subject_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class subject not found".to_string())?;
+    ensure_open(conn, assignment.academic_year_id.unwrap_or_default())?;
+    if let Some(teacher_id) = teacher_id {
+        find_teacher(conn, teacher_id)?;
+    }
+    repo::set_subject_teacher(conn, class_subject_id, teacher_id).map_err(|e| e.to_string())?;
+
+    let Some(teacher_id) = teacher_id else {
+        return Ok(None);
+    };
+    let academic_year_id = assignment.academic_year_id.unwrap_or_default();
+    Ok(get_teacher_workload(conn, Some(academic_year_id))?
+        .into_iter()
+        .find(|w| w.staff_id == teacher_id))
+}
+
+/// Assigns (or with `None`, removes) the class teacher. A teacher can be class teacher of
+/// only one class in an academic year.
+pub fn set_class_teacher(
+    conn: &Connection,
+    class_id: i64,
+    staff_id: Option<i64>,
+) -> Result<(), String> {
+    let class = class_repo::find_by_id(conn, class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())?;
+    ensure_open(conn, class.academic_years)?;
+    let Some(staff_id) = staff_id else {
+        repo::clear_class_teacher(conn, class_id).map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+    let teacher = find_teacher(conn, staff_id)?;
+    let existing =
+        repo::find_class_teachers(conn, class.academic_years).map_err(|e| e.to_string())?;
+    if let Some(other) = existing
+        .iter()
+        .find(|t| t.staff_id == staff_id && t.class_id != class_id)
+    {
+        return Err(format!(
+            "{} is already class teacher of {}",
+            teacher.name, other.class_name
+        ));
+    }
+    repo::set_class_teacher(conn, class_id, staff_id).map_err(|e| e.to_string())
+}
+
+pub fn get_class_teachers(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<ClassTeacher>, String> {
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    repo::find_class_teachers(conn, academic_year_id).map_err(|e| e.to_string())
+}
+
+pub fn set_teacher_max_periods(
+    conn: &Connection,
+    staff_id: i64,
+    max_periods_per_week: Option<u32>,
+) -> Result<(), String> {
+    find_teacher(conn, staff_id)?;
+    if max_periods_per_week == Some(0) {
+        return Err("The weekly period limit must be at least 1".to_string());
+    }
+    repo::set_max_periods(conn, staff_id, max_periods_per_week).map_err(|e| e.to_string())
+}
+
+/// Weekly period limit for a teacher.
+pub fn max_periods_for(conn: &Connection, staff_id: i64) -> Result<u32, String> {
+    Ok(repo::find_max_periods(conn, staff_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or(DEFAULT_MAX_PERIODS_PER_WEEK))
+}
+
+/// Workload of every teacher who teaches a subject or is a class teacher in the year,
+/// most loaded first.
+pub fn get_teacher_workload(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TeacherWorkload>, String> {
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    let mut assignments: BTreeMap<i64, Vec<WorkloadItem>> = BTreeMap::new();
+    for taught in repo::find_taught_in_year(conn, academic_year_id).map_err(|e| e.to_string())? {
+        assignments
+            .entry(taught.teacher_id.unwrap_or_default())
+            .or_default()
+            .push(WorkloadItem {
+                class_subject_id: taught.id.unwrap_or_default(),
+                class_name: taught.class_name.unwrap_or_default(),
+                subject_name: taught.subject_name.unwrap_or_default(),
+                periods_per_week: taught.periods_per_week,
+            });
+    }
+    let class_teachers =
+        repo::find_class_teachers(conn, academic_year_id).map_err(|e| e.to_string())?;
+    for teacher in &class_teachers {
+        assignments.entry(teacher.staff_id).or_default();
+    }
+
+    let mut workload = Vec::with_capacity(assignments.len());
+    for (staff_id, items) in assignments {
+        let Some(staff) = staff_repo::find_by_id(conn, staff_id).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        let total_periods = items.iter().map(|i| i.periods_per_week).sum();
+        let max_periods = max_periods_for(conn, staff_id)?;
+        workload.push(TeacherWorkload {
+            staff_id,
+            name: staff.name,
+            department: staff.department,
+            total_periods,
+            max_periods,
+            over_assigned: total_periods > max_periods,
+            class_teacher_of: class_teachers
+                .iter()
+                .find(|t| t.staff_id == staff_id)
+                .map(|t| t.class_name.clone()),
+            assignments: items,
+        });
+    }
+    workload.sort_by(|a, b| {
+        b.total_periods
+            .cmp(&a.total_periods)
+            .then(a.name.cmp(&b.name))
+    });
+    Ok(workload)
+}
+
+pub fn copy_class_subjects(
+    conn: &Connection,
+    from_academic_year_id: i64,
+    to_academic_year_id: i64,
+) -> Result<usize, String> {
+    if from_academic_year_id == to_academic_year_id {
+        return Err("Choose two different academic years".to_string());
+    }
+    for id in [from_academic_year_id, to_academic_year_id] {
+        if !year_repo::exists(conn, id).map_err(|e| e.to_string())? {
+            return Err("Academic year does not exist".to_string());
+        }
+    }
+    ensure_open(conn, to_academic_year_id)?;
+    repo::copy_mappings(conn, from_academic_year_id, to_academic_year_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::academic_year::upsert_academic_year;
+    use crate::service::staff::create_staff;
+    use crate::test_support::{create_class, memory_db, sample_staff};
+
+    fn subject(code: &str, name: &str, subject_type: &str) -> Subject {
+        Subject {
+            id: None,
+            code: code.to_string(),
+            name: name.to_string(),
+            subject_type: subject_type.to_string(),
+            status: None,
+            created_at: None,
+        }
+    }
+
+    fn map(
+        conn: &Connection,
+        class_id: i64,
+        subject_id: i64,
+        periods: u32,
+        teacher_id: Option<i64>,
+    ) -> i64 {
+        assign_class_subject(
+            conn,
+            ClassSubject {
+                id: None,
+                class_id,
+                subject_id,
+                periods_per_week: periods,
+                teacher_id,
+                academic_year_id: None,
+                class_name: None,
+                subject_code: None,
+                subject_name: None,
+                subject_type: None,
+                teacher_name: None,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn catalogue_validates_codes_and_types() {
+        let conn = memory_db();
+        let maths = create_subject(&conn, subject(" math ", "Mathematics", "Theory")).unwrap();
+        assert!(create_subject(&conn, subject("MATH", "Maths again", "theory")).is_err());
+        assert!(create_subject(&conn, subject("ART", "Art", "hobby")).is_err());
+
+        let stored = &get_subjects(&conn, false).unwrap()[0];
+        assert_eq!(
+            (stored.code.as_str(), stored.subject_type.as_str()),
+            ("MATH", "theory")
+        );
+
+        let class_id = create_class(&conn, "Class-5 A");
+        let mapping = map(&conn, class_id, maths, 6, None);
+        assert!(delete_subject(&conn, maths).unwrap_err().contains("mapped"));
+        remove_class_subject(&conn, mapping).unwrap();
+        delete_subject(&conn, maths).unwrap();
+        assert!(get_subjects(&conn, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn workload_flags_over_assigned_teachers() {
+        let conn = memory_db();
+        let teacher = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let maths = create_subject(&conn, subject("MATH", "Mathematics", "theory")).unwrap();
+        let science = create_subject(&conn, subject("SCI", "Science", "theory")).unwrap();
+        let six_a = create_class(&conn, "Class-6 A");
+        let six_b = create_class(&conn, "Class-6 B");
+
+        map(&conn, six_a, maths, 8, Some(teacher));
+        map(&conn, six_b, maths, 8, Some(teacher));
+        let science_b = map(&conn, six_b, science, 7, None);
+        set_class_teacher(&conn, six_a, Some(teacher)).unwrap();
+
+        let load = set_subject_teacher(&conn, science_b, Some(teacher))
+            .unwrap()
+            .unwrap();
+        assert_eq!((load.total_periods, load.over_assigned), (23, false));
+        assert_eq!(load.class_teacher_of.as_deref(), Some("Class-6 A"));
+
+        set_teacher_max_periods(&conn, teacher, Some(20)).unwrap();
+        let workload = get_teacher_workload(&conn, None).unwrap();
+        assert_eq!(workload.len(), 1);
+        assert!(workload[0].over_assigned);
+        assert_eq!(workload[0].assignments.len(), 3);
+
+        assert!(set_subject_teacher(&conn, science_b, None)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            get_teacher_workload(&conn, None).unwrap()[0].total_periods,
+            16
+        );
+    }
+
+    #[test]
+    fn a_teacher_is_class_teacher_of_one_class_per_year() {
+        let conn = memory_db();
+        let meera = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let arjun = create_staff(&conn, sample_staff("Arjun")).unwrap();
+        let six_a = create_class(&conn, "Class-6 A");
+        let six_b = create_class(&conn, "Class-6 B");
+
+        set_class_teacher(&conn, six_a, Some(meera)).unwrap();
+        assert!(set_class_teacher(&conn, six_b, Some(meera))
+            .unwrap_err()
+            .contains("Class-6 A"));
+        set_class_teacher(&conn, six_a, Some(arjun)).unwrap();
+        set_class_teacher(&conn, six_b, Some(meera)).unwrap();
+        set_class_teacher(&conn, six_a, None).unwrap();
+
+        let teachers = get_class_teachers(&conn, None).unwrap();
+        assert_eq!(teachers.len(), 1);
+        assert_eq!((teachers[0].class_id, teachers[0].staff_id), (six_b, meera));
+    }
+
+    #[test]
+    fn mappings_copy_to_the_next_year_by_class_name() {
+        let conn = memory_db();
+        let teacher = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let maths = create_subject(&conn, subject("MATH", "Mathematics", "theory")).unwrap();
+        let current = create_class(&conn, "Class-6 A");
+        map(&conn, current, maths, 8, Some(teacher));
+
+        let next_year = upsert_academic_year(&conn, "2025 - 2026", false, None, None).unwrap();
+        let next_class = class_repo::insert(&conn, "Class-6 A", next_year, "active").unwrap();
+        assert_eq!(copy_class_subjects(&conn, 1, next_year).unwrap(), 1);
+        assert_eq!(copy_class_subjects(&conn, 1, next_year).unwrap(), 0);
+
+        let copied = get_class_subjects(&conn, next_class).unwrap();
+        assert_eq!(copied[0].periods_per_week, 8);
+        assert_eq!(copied[0].teacher_id, None);
+        assert_eq!(copied[0].academic_year_id, Some(next_year));
+    }
+}
@@ -0,0 +1,1379 @@
+use crate::class::Class;
+use crate::date::{parse_time, Date};
+use crate::pdf::{text_width, Align, Document, Font, Page, A4_HEIGHT, A4_WIDTH};
+use crate::repository::academic_year as year_repo;
+use crate::repository::class as class_repo;
+use crate::repository::leave as leave_repo;
+use crate::repository::school as school_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::staff_attendance as attendance_repo;
+use crate::repository::subject as subject_repo;
+use crate::repository::timetable as repo;
+use crate::school::School;
+use crate::service::academic_year::{ensure_class_open, ensure_open};
+use crate::service::staff::is_active;
+use crate::service::subject::resolve_academic_year;
+use crate::subject::ClassSubject;
+use crate::timetable::{
+    BellSlot, GenerationOutcome, Room, SubstituteCandidate, SubstitutionNeed, TimetableClash,
+    TimetableEntry, TimetableSettings, UnplacedPeriods,
+};
+use rusqlite::Connection;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub const SLOT_KINDS: [&str; 2] = ["period", "break"];
+
+pub const DAY_NAMES: [&str; 6] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+fn day_name(day: u32) -> &'static str {
+    DAY_NAMES[(day.clamp(1, 6) - 1) as usize]
+}
+
+pub fn get_settings(conn: &Connection) -> Result<TimetableSettings, String> {
+    repo::find_settings(conn).map_err(|e| e.to_string())
+}
+
+pub fn save_settings(conn: &Connection, settings: TimetableSettings) -> Result<(), String> {
+    if !(1..=6).contains(&settings.working_days) {
+        return Err(
+            "Working days must be between 1 (Monday only) and 6 (Monday to Saturday)".to_string(),
+        );
+    }
+    if settings.max_periods_per_day == 0 {
+        return Err("Teachers must be allowed at least one period a day".to_string());
+    }
+    if let Some(day) = repo::max_day_used(conn).map_err(|e| e.to_string())? {
+        if day > settings.working_days {
+            return Err(format!(
+                "The timetable has periods on {}; clear them before dropping the day",
+                day_name(day)
+            ));
+        }
+    }
+    repo::save_settings(conn, &settings).map_err(|e| e.to_string())
+}
+
+/// The bell schedule with periods numbered in order.
+pub fn get_bell_schedule(conn: &Connection) -> Result<Vec<BellSlot>, String> {
+    let mut slots = repo::find_bell_schedule(conn).map_err(|e| e.to_string())?;
+    let mut period_no = 0;
+    for slot in &mut slots {
+        slot.period_no = if slot.kind == "period" {
+            period_no += 1;
+            Some(period_no)
+        } else {
+            None
+        };
+    }
+    Ok(slots)
+}
+
+fn period_count(slots: &[BellSlot]) -> u32 {
+    slots.iter().filter(|s| s.period_no.is_some()).count() as u32
+}
+
+pub fn save_bell_schedule(
+    conn: &Connection,
+    mut slots: Vec<BellSlot>,
+) -> Result<Vec<BellSlot>, String> {
+    let mut previous_end = None;
+    let mut periods = 0;
+    for slot in &mut slots {
+        slot.kind = slot.kind.trim().to_lowercase();
+        if !SLOT_KINDS.contains(&slot.kind.as_str()) {
+            return Err(format!(
+                "Invalid slot kind '{}'. Expected period or break",
+                slot.kind
+            ));
+        }
+        if slot.kind == "period" {
+            periods += 1;
+        }
+        slot.label = match slot.label.trim() {
+            "" if slot.kind == "period" => format!("Period {}", periods),
+            "" => "Break".to_string(),
+            label => label.to_string(),
+        };
+        let start = parse_time(&slot.start_time)?;
+        let end = parse_time(&slot.end_time)?;
+        if end <= start {
+            return Err(format!("{} must end after it starts", slot.label));
+        }
+        if previous_end.is_some_and(|previous| start < previous) {
+            return Err(format!(
+                "{} starts before the previous slot ends",
+                slot.label
+            ));
+        }
+        previous_end = Some(end);
+        slot.start_time = slot.start_time.trim().chars().take(5).collect();
+        slot.end_time = slot.end_time.trim().chars().take(5).collect();
+    }
+    if periods == 0 {
+        return Err("The bell schedule needs at least one period".to_string());
+    }
+    if let Some(used) = repo::max_period_used(conn).map_err(|e| e.to_string())? {
+        if used > periods {
+            return Err(format!(
+                "Period {} is used in the timetable; clear it before shortening the day",
+                used
+            ));
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::replace_bell_schedule(&tx, &slots).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_bell_schedule(conn)
+}
+
+fn normalize_room(room: &mut Room) -> Result<(), String> {
+    room.name = room.name.trim().to_string();
+    if room.name.is_empty() {
+        return Err("Room name is required".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_rooms(conn: &Connection, include_inactive: bool) -> Result<Vec<Room>, String> {
+    repo::find_rooms(conn, include_inactive).map_err(|e| e.to_string())
+}
+
+pub fn create_room(conn: &Connection, mut room: Room) -> Result<i64, String> {
+    normalize_room(&mut room)?;
+    if repo::room_name_taken(conn, &room.name, None).map_err(|e| e.to_string())? {
+        return Err(format!("Room '{}' already exists", room.name));
+    }
+    let status = room.status.clone().unwrap_or_else(|| "active".to_string());
+    repo::insert_room(conn, &room, &status).map_err(|e| e.to_string())
+}
+
+pub fn update_room(conn: &Connection, id: i64, mut room: Room) -> Result<(), String> {
+    normalize_room(&mut room)?;
+    if repo::room_name_taken(conn, &room.name, Some(id)).map_err(|e| e.to_string())? {
+        return Err(format!("Room '{}' already exists", room.name));
+    }
+    let status = room.status.clone().unwrap_or_else(|| "active".to_string());
+    if repo::update_room(conn, id, &room, &status).map_err(|e| e.to_string())? == 0 {
+        return Err("Room not found".to_string());
+    }
+    Ok(())
+}
+
+/// Periods held in the room keep their subject and lose the room.
+pub fn delete_room(conn: &Connection, id: i64) -> Result<(), String> {
+    if repo::delete_room(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err("Room not found".to_string());
+    }
+    Ok(())
+}
+
+fn find_class(conn: &Connection, class_id: i64) -> Result<Class, String> {
+    class_repo::find_by_id(conn, class_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class not found".to_string())
+}
+
+pub fn set_entry(conn: &Connection, entry: TimetableEntry) -> Result<i64, String> {
+    let class = find_class(conn, entry.class_id)?;
+    ensure_open(conn, class.academic_years)?;
+    let settings = get_settings(conn)?;
+    let periods = period_count(&get_bell_schedule(conn)?);
+    if !(1..=settings.working_days).contains(&entry.day) {
+        return Err(format!(
+            "Day must be between 1 and {}",
+            settings.working_days
+        ));
+    }
+    if !(1..=periods).contains(&entry.period_no) {
+        return Err(format!(
+            "Period must be between 1 and {} of the bell schedule",
+            periods
+        ));
+    }
+    let assignment = subject_repo::find_class_subject(conn, entry.class_subject_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Class subject not found".to_string())?;
+    let subject_name = assignment.subject_name.clone().unwrap_or_default();
+    if assignment.class_id != entry.class_id {
+        return Err(format!(
+            "{} is not taught in {}",
+            subject_name, class.class_name
+        ));
+    }
+    if let Some(room_id) = entry.room_id {
+        let room = repo::find_room(conn, room_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Room not found".to_string())?;
+        if room.status.as_deref() != Some("active") {
+            return Err(format!("Room {} is not in use", room.name));
+        }
+    }
+
+    // Every other period of the year; the one being replaced does not count.
+    let others: Vec<TimetableEntry> = repo::find_year_entries(conn, class.academic_years)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| {
+            (e.class_id, e.day, e.period_no) != (entry.class_id, entry.day, entry.period_no)
+        })
+        .collect();
+    let same_slot = |e: &&TimetableEntry| e.day == entry.day && e.period_no == entry.period_no;
+
+    let placed = others
+        .iter()
+        .filter(|e| Some(e.class_subject_id) == assignment.id)
+        .count() as u32;
+    if placed >= assignment.periods_per_week {
+        return Err(format!(
+            "{} already has all {} periods of the week in {}",
+            subject_name, assignment.periods_per_week, class.class_name
+        ));
+    }
+    if let Some(teacher_id) = assignment.teacher_id {
+        let teacher = assignment.teacher_name.clone().unwrap_or_default();
+        let teaching = |e: &&TimetableEntry| e.teacher_id == Some(teacher_id);
+        if let Some(clash) = others.iter().filter(same_slot).find(teaching) {
+            return Err(format!(
+                "{} already teaches {} in period {} on {}",
+                teacher,
+                clash.class_name.as_deref().unwrap_or_default(),
+                entry.period_no,
+                day_name(entry.day)
+            ));
+        }
+        let load = others
+            .iter()
+            .filter(teaching)
+            .filter(|e| e.day == entry.day)
+            .count() as u32;
+        if load >= settings.max_periods_per_day {
+            return Err(format!(
+                "{} already has {} periods on {}, the most allowed in a day",
+                teacher,
+                load,
+                day_name(entry.day)
+            ));
+        }
+    }
+    if let Some(room_id) = entry.room_id {
+        if let Some(clash) = others
+            .iter()
+            .filter(same_slot)
+            .find(|e| e.room_id == Some(room_id))
+        {
+            return Err(format!(
+                "{} is taken by {} in period {} on {}",
+                clash.room_name.as_deref().unwrap_or_default(),
+                clash.class_name.as_deref().unwrap_or_default(),
+                entry.period_no,
+                day_name(entry.day)
+            ));
+        }
+    }
+    repo::upsert_entry(conn, &entry, class.academic_years).map_err(|e| e.to_string())
+}
+
+pub fn clear_entry(
+    conn: &Connection,
+    class_id: i64,
+    day: u32,
+    period_no: u32,
+) -> Result<(), String> {
+    ensure_class_open(conn, class_id)?;
+    if repo::delete_entry(conn, class_id, day, period_no).map_err(|e| e.to_string())? == 0 {
+        return Err("No subject is set for that period".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_class_timetable(
+    conn: &Connection,
+    class_id: i64,
+) -> Result<Vec<TimetableEntry>, String> {
+    repo::find_class_entries(conn, class_id).map_err(|e| e.to_string())
+}
+
+pub fn get_teacher_timetable(
+    conn: &Connection,
+    staff_id: i64,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TimetableEntry>, String> {
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    repo::find_teacher_entries(conn, staff_id, academic_year_id).map_err(|e| e.to_string())
+}
+
+fn clash(
+    kind: &str,
+    day: u32,
+    period_no: Option<u32>,
+    name: &str,
+    entries: &[&TimetableEntry],
+) -> TimetableClash {
+    let mut classes: Vec<String> = entries
+        .iter()
+        .filter_map(|e| e.class_name.clone())
+        .collect();
+    classes.sort();
+    classes.dedup();
+    TimetableClash {
+        kind: kind.to_string(),
+        day,
+        period_no,
+        name: name.to_string(),
+        classes,
+    }
+}
+
+pub fn find_clashes(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TimetableClash>, String> {
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    let settings = get_settings(conn)?;
+    let entries = repo::find_year_entries(conn, academic_year_id).map_err(|e| e.to_string())?;
+
+    let mut by_teacher_slot: BTreeMap<(u32, u32, i64), Vec<&TimetableEntry>> = BTreeMap::new();
+    let mut by_room_slot: BTreeMap<(u32, u32, i64), Vec<&TimetableEntry>> = BTreeMap::new();
+    let mut by_teacher_day: BTreeMap<(u32, i64), Vec<&TimetableEntry>> = BTreeMap::new();
+    for entry in &entries {
+        if let Some(teacher_id) = entry.teacher_id {
+            by_teacher_slot
+                .entry((entry.day, entry.period_no, teacher_id))
+                .or_default()
+                .push(entry);
+            by_teacher_day
+                .entry((entry.day, teacher_id))
+                .or_default()
+                .push(entry);
+        }
+        if let Some(room_id) = entry.room_id {
+            by_room_slot
+                .entry((entry.day, entry.period_no, room_id))
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let mut clashes = Vec::new();
+    for ((day, period_no, _), group) in by_teacher_slot.iter().filter(|(_, g)| g.len() > 1) {
+        let name = group[0].teacher_name.as_deref().unwrap_or_default();
+        clashes.push(clash("teacher", *day, Some(*period_no), name, group));
+    }
+    for ((day, period_no, _), group) in by_room_slot.iter().filter(|(_, g)| g.len() > 1) {
+        let name = group[0].room_name.as_deref().unwrap_or_default();
+        clashes.push(clash("room", *day, Some(*period_no), name, group));
+    }
+    for ((day, _), group) in by_teacher_day
+        .iter()
+        .filter(|(_, g)| g.len() as u32 > settings.max_periods_per_day)
+    {
+        let name = group[0].teacher_name.as_deref().unwrap_or_default();
+        clashes.push(clash("daily_limit", *day, None, name, group));
+    }
+    clashes.sort_by_key(|c| (c.day, c.period_no));
+    Ok(clashes)
+}
+
+/// Occupancy of the week while the generator places periods.
+#[derive(Default)]
+struct Grid {
+    class_busy: HashSet<(i64, u32, u32)>,
+    teacher_busy: HashSet<(i64, u32, u32)>,
+    teacher_day_load: HashMap<(i64, u32), u32>,
+    subject_day_count: HashMap<(i64, u32), u32>,
+    placed: HashMap<i64, u32>,
+}
+
+impl Grid {
+    fn occupy(
+        &mut self,
+        class_id: i64,
+        class_subject_id: i64,
+        teacher_id: Option<i64>,
+        day: u32,
+        period_no: u32,
+    ) {
+        self.class_busy.insert((class_id, day, period_no));
+        if let Some(teacher_id) = teacher_id {
+            self.teacher_busy.insert((teacher_id, day, period_no));
+            *self.teacher_day_load.entry((teacher_id, day)).or_default() += 1;
+        }
+        *self
+            .subject_day_count
+            .entry((class_subject_id, day))
+            .or_default() += 1;
+        *self.placed.entry(class_subject_id).or_default() += 1;
+    }
+
+    /// The free slot that spreads the subject most evenly over the week, preferring days the
+    /// teacher is lightly loaded and earlier periods.
+    fn best_slot(
+        &self,
+        subject: &ClassSubject,
+        settings: &TimetableSettings,
+        periods: u32,
+    ) -> Option<(u32, u32)> {
+        let class_subject_id = subject.id.unwrap_or_default();
+        let teacher_load = |day| {
+            subject
+                .teacher_id
+                .and_then(|t| self.teacher_day_load.get(&(t, day)).copied())
+                .unwrap_or(0)
+        };
+        (1..=settings.working_days)
+            .flat_map(|day| (1..=periods).map(move |period_no| (day, period_no)))
+            .filter(|&(day, period_no)| {
+                !self
+                    .class_busy
+                    .contains(&(subject.class_id, day, period_no))
+            })
+            .filter(|&(day, period_no)| match subject.teacher_id {
+                Some(teacher_id) => {
+                    !self.teacher_busy.contains(&(teacher_id, day, period_no))
+                        && teacher_load(day) < settings.max_periods_per_day
+                }
+                None => true,
+            })
+            .min_by_key(|&(day, period_no)| {
+                (
+                    self.subject_day_count
+                        .get(&(class_subject_id, day))
+                        .copied()
+                        .unwrap_or(0),
+                    teacher_load(day),
+                    period_no,
+                    day,
+                )
+            })
+    }
+}
+
+/// The class given, or every active class of the academic year.
+fn target_classes(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+    class_id: Option<i64>,
+) -> Result<(i64, Vec<Class>), String> {
+    if let Some(class_id) = class_id {
+        let class = find_class(conn, class_id)?;
+        return Ok((class.academic_years, vec![class]));
+    }
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    let classes: Vec<Class> = class_repo::find_all(conn, true)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|c| c.academic_years == academic_year_id)
+        .collect();
+    if classes.is_empty() {
+        return Err("There are no active classes in the academic year".to_string());
+    }
+    Ok((academic_year_id, classes))
+}
+
+pub fn generate(
+    conn: &Connection,
+    academic_year_id: Option<i64>,
+    class_id: Option<i64>,
+    keep_existing: bool,
+) -> Result<GenerationOutcome, String> {
+    let settings = get_settings(conn)?;
+    let periods = period_count(&get_bell_schedule(conn)?);
+    if periods == 0 {
+        return Err("Set up the bell schedule before generating a timetable".to_string());
+    }
+    let (academic_year_id, classes) = target_classes(conn, academic_year_id, class_id)?;
+    ensure_open(conn, academic_year_id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if !keep_existing {
+        for class in &classes {
+            repo::delete_class_entries(&tx, class.id.unwrap_or_default())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let mut grid = Grid::default();
+    for entry in repo::find_year_entries(&tx, academic_year_id).map_err(|e| e.to_string())? {
+        grid.occupy(
+            entry.class_id,
+            entry.class_subject_id,
+            entry.teacher_id,
+            entry.day,
+            entry.period_no,
+        );
+    }
+
+    let mut weekly_load: HashMap<i64, u32> = HashMap::new();
+    for taught in
+        subject_repo::find_taught_in_year(&tx, academic_year_id).map_err(|e| e.to_string())?
+    {
+        *weekly_load
+            .entry(taught.teacher_id.unwrap_or_default())
+            .or_default() += taught.periods_per_week;
+    }
+    let mut demands = Vec::new();
+    for class in &classes {
+        for subject in subject_repo::find_class_subjects(&tx, class.id.unwrap_or_default())
+            .map_err(|e| e.to_string())?
+        {
+            let placed = grid
+                .placed
+                .get(&subject.id.unwrap_or_default())
+                .copied()
+                .unwrap_or(0);
+            let remaining = subject.periods_per_week.saturating_sub(placed);
+            if remaining > 0 {
+                demands.push((subject, remaining));
+            }
+        }
+    }
+    // Busiest teachers and longest subjects first, while the week is still open.
+    demands.sort_by_key(|(subject, remaining)| {
+        (
+            Reverse(
+                subject
+                    .teacher_id
+                    .and_then(|t| weekly_load.get(&t).copied())
+                    .unwrap_or(0),
+            ),
+            Reverse(*remaining),
+            subject.class_id,
+            subject.subject_id,
+        )
+    });
+
+    let mut outcome = GenerationOutcome {
+        placed: 0,
+        unplaced: Vec::new(),
+    };
+    for (subject, remaining) in demands {
+        let mut missing = remaining;
+        while missing > 0 {
+            let Some((day, period_no)) = grid.best_slot(&subject, &settings, periods) else {
+                break;
+            };
+            let entry = TimetableEntry {
+                id: None,
+                class_id: subject.class_id,
+                day,
+                period_no,
+                class_subject_id: subject.id.unwrap_or_default(),
+                room_id: None,
+                academic_year_id: None,
+                class_name: None,
+                subject_id: None,
+                subject_code: None,
+                subject_name: None,
+                teacher_id: None,
+                teacher_name: None,
+                room_name: None,
+            };
+            repo::upsert_entry(&tx, &entry, academic_year_id).map_err(|e| e.to_string())?;
+            grid.occupy(
+                subject.class_id,
+                entry.class_subject_id,
+                subject.teacher_id,
+                day,
+                period_no,
+            );
+            outcome.placed += 1;
+            missing -= 1;
+        }
+        if missing > 0 {
+            outcome.unplaced.push(UnplacedPeriods {
+                class_name: subject.class_name.unwrap_or_default(),
+                subject_name: subject.subject_name.unwrap_or_default(),
+                teacher_name: subject.teacher_name,
+                missing,
+            });
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(outcome)
+}
+
+fn year_label(conn: &Connection, academic_year_id: i64) -> Result<String, String> {
+    Ok(year_repo::find_all(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|y| y.id == Some(academic_year_id))
+        .map(|y| y.academic_year)
+        .unwrap_or_default())
+}
+
+fn file_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Shortens `text` with a trailing ".." until it fits in `width`.
+fn fit(text: &str, size: f32, font: Font, width: f32) -> String {
+    if text_width(text, size, font) <= width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}..", chars.iter().collect::<String>().trim_end());
+        if text_width(&candidate, size, font) <= width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+/// One timetable on a landscape page: days down the side, the bell schedule across the top.
+/// `cell` gives the lines printed under the subject of each period.
+#[allow(clippy::too_many_arguments)]
+fn draw_timetable(
+    page: &mut Page,
+    school: Option<&School>,
+    title: &str,
+    year: &str,
+    slots: &[BellSlot],
+    working_days: u32,
+    entries: &[TimetableEntry],
+    cell: &dyn Fn(&TimetableEntry) -> Vec<String>,
+) {
+    let (width, height) = (page.width(), page.height());
+    let center = width / 2.0;
+    let margin = 36.0;
+
+    let mut y = height - 40.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            16.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 14.0;
+        let address = format!("{}, {}, {}", school.address, school.city, school.state);
+        page.text_aligned(center, y, 9.0, Font::Regular, Align::Center, &address);
+        y -= 10.0;
+    }
+    page.line(margin, y, width - margin, y, 1.0);
+    y -= 20.0;
+    page.text_aligned(center, y, 13.0, Font::Bold, Align::Center, title);
+    y -= 14.0;
+    page.text_aligned(
+        center,
+        y,
+        9.5,
+        Font::Regular,
+        Align::Center,
+        &format!("Academic Year {}", year),
+    );
+    y -= 14.0;
+
+    // Breaks get a narrow column of their own.
+    let day_column = 68.0;
+    let weight = |slot: &BellSlot| if slot.period_no.is_some() { 1.0 } else { 0.45 };
+    let total_weight: f32 = slots.iter().map(weight).sum();
+    let unit = (width - 2.0 * margin - day_column) / total_weight;
+    let header_height = 30.0;
+    let row_height = ((y - margin - header_height) / working_days as f32).min(62.0);
+
+    page.rect(margin, y - header_height, day_column, header_height, 0.8);
+    page.text(margin + 5.0, y - 18.0, 8.5, Font::Bold, "Day");
+    let mut x = margin + day_column;
+    let mut columns = Vec::with_capacity(slots.len());
+    for slot in slots {
+        let column_width = unit * weight(slot);
+        page.rect(x, y - header_height, column_width, header_height, 0.8);
+        let inner = column_width - 6.0;
+        page.text(
+            x + 3.0,
+            y - 12.0,
+            8.0,
+            Font::Bold,
+            &fit(&slot.label, 8.0, Font::Bold, inner),
+        );
+        let time = format!("{}-{}", slot.start_time, slot.end_time);
+        page.text(
+            x + 3.0,
+            y - 23.0,
+            6.5,
+            Font::Regular,
+            &fit(&time, 6.5, Font::Regular, inner),
+        );
+        columns.push((x, column_width, slot.period_no));
+        x += column_width;
+    }
+    y -= header_height;
+
+    for day in 1..=working_days {
+        page.rect(margin, y - row_height, day_column, row_height, 0.8);
+        page.text(margin + 5.0, y - 14.0, 8.5, Font::Bold, day_name(day));
+        for &(x, column_width, period_no) in &columns {
+            page.rect(x, y - row_height, column_width, row_height, 0.5);
+            let Some(period_no) = period_no else {
+                continue;
+            };
+            let Some(entry) = entries
+                .iter()
+                .find(|e| e.day == day && e.period_no == period_no)
+            else {
+                continue;
+            };
+            let inner = column_width - 6.0;
+            let subject = entry.subject_name.as_deref().unwrap_or_default();
+            page.text(
+                x + 3.0,
+                y - 12.0,
+                8.0,
+                Font::Bold,
+                &fit(subject, 8.0, Font::Bold, inner),
+            );
+            let mut line_y = y - 23.0;
+            for line in cell(entry) {
+                if line_y < y - row_height + 4.0 {
+                    break;
+                }
+                page.text(
+                    x + 3.0,
+                    line_y,
+                    7.0,
+                    Font::Regular,
+                    &fit(&line, 7.0, Font::Regular, inner),
+                );
+                line_y -= 9.5;
+            }
+        }
+        y -= row_height;
+    }
+}
+
+fn room_line(entry: &TimetableEntry) -> Option<String> {
+    entry
+        .room_name
+        .as_ref()
+        .map(|room| format!("Room {}", room))
+}
+
+/// File name and PDF with the timetable of one class, or of every class that has one.
+pub fn render_class_timetable_pdf(
+    conn: &Connection,
+    class_id: Option<i64>,
+    academic_year_id: Option<i64>,
+) -> Result<(String, Vec<u8>), String> {
+    let slots = get_bell_schedule(conn)?;
+    if period_count(&slots) == 0 {
+        return Err("Set up the bell schedule first".to_string());
+    }
+    let settings = get_settings(conn)?;
+    let (academic_year_id, classes) = target_classes(conn, academic_year_id, class_id)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let year = year_label(conn, academic_year_id)?;
+
+    let cell = |entry: &TimetableEntry| {
+        let mut lines: Vec<String> = entry.teacher_name.iter().cloned().collect();
+        lines.extend(room_line(entry));
+        lines
+    };
+    let mut doc = Document::new(&format!("Timetable {}", year));
+    let mut pages = 0;
+    for class in &classes {
+        let entries = repo::find_class_entries(conn, class.id.unwrap_or_default())
+            .map_err(|e| e.to_string())?;
+        if entries.is_empty() && class_id.is_none() {
+            continue;
+        }
+        let title = format!("Class Timetable - {}", class.class_name);
+        let page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+        draw_timetable(
+            page,
+            school.as_ref(),
+            &title,
+            &year,
+            &slots,
+            settings.working_days,
+            &entries,
+            &cell,
+        );
+        pages += 1;
+    }
+    if pages == 0 {
+        return Err("No class has a timetable yet".to_string());
+    }
+    let file_name = match class_id {
+        Some(_) => format!(
+            "Timetable-{}-{}.pdf",
+            file_part(&classes[0].class_name),
+            file_part(&year)
+        ),
+        None => format!("Timetable-Classes-{}.pdf", file_part(&year)),
+    };
+    Ok((file_name, doc.to_bytes()))
+}
+
+/// File name and PDF with the timetable of one teacher, or of every teacher with periods.
+pub fn render_teacher_timetable_pdf(
+    conn: &Connection,
+    staff_id: Option<i64>,
+    academic_year_id: Option<i64>,
+) -> Result<(String, Vec<u8>), String> {
+    let slots = get_bell_schedule(conn)?;
+    if period_count(&slots) == 0 {
+        return Err("Set up the bell schedule first".to_string());
+    }
+    let settings = get_settings(conn)?;
+    let academic_year_id = resolve_academic_year(conn, academic_year_id)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let year = year_label(conn, academic_year_id)?;
+
+    let mut by_teacher: BTreeMap<(String, i64), Vec<TimetableEntry>> = BTreeMap::new();
+    for entry in repo::find_year_entries(conn, academic_year_id).map_err(|e| e.to_string())? {
+        let Some(teacher_id) = entry.teacher_id else {
+            continue;
+        };
+        if staff_id.is_some_and(|id| id != teacher_id) {
+            continue;
+        }
+        let name = entry.teacher_name.clone().unwrap_or_default();
+        by_teacher
+            .entry((name, teacher_id))
+            .or_default()
+            .push(entry);
+    }
+    if by_teacher.is_empty() {
+        return Err(match staff_id {
+            Some(_) => "The teacher has no periods in the timetable".to_string(),
+            None => "No teacher has periods in the timetable yet".to_string(),
+        });
+    }
+
+    let cell = |entry: &TimetableEntry| {
+        let mut lines: Vec<String> = entry.class_name.iter().cloned().collect();
+        lines.extend(room_line(entry));
+        lines
+    };
+    let mut doc = Document::new(&format!("Teacher Timetable {}", year));
+    for ((name, _), entries) in &by_teacher {
+        let title = format!("Teacher Timetable - {}", name);
+        let page = doc.add_page_sized(A4_HEIGHT, A4_WIDTH);
+        draw_timetable(
+            page,
+            school.as_ref(),
+            &title,
+            &year,
+            &slots,
+            settings.working_days,
+            entries,
+            &cell,
+        );
+    }
+    let file_name = match staff_id {
+        Some(_) => {
+            let ((name, _), _) = by_teacher.iter().next().expect("checked non-empty");
+            format!("Timetable-{}-{}.pdf", file_part(name), file_part(&year))
+        }
+        None => format!("Timetable-Teachers-{}.pdf", file_part(&year)),
+    };
+    Ok((file_name, doc.to_bytes()))
+}
+
+/// Staff marked absent or on approved leave on `date`.
+fn absent_staff(conn: &Connection, date: &str) -> Result<HashSet<i64>, String> {
+    let mut absent: HashSet<i64> = attendance_repo::find_between(conn, date, date)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|a| a.status == "absent")
+        .map(|a| a.staff_id)
+        .collect();
+    absent.extend(
+        leave_repo::find_approved_between(conn, date, date)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|leave| leave.staff_id),
+    );
+    Ok(absent)
+}
+
+pub fn substitution_suggestions(
+    conn: &Connection,
+    date: &str,
+) -> Result<Vec<SubstitutionNeed>, String> {
+    let date = Date::parse(date)?;
+    let day = date.weekday() + 1;
+    let date = date.to_string();
+    let settings = get_settings(conn)?;
+    if day > settings.working_days {
+        return Ok(Vec::new());
+    }
+    let absent = absent_staff(conn, &date)?;
+    if absent.is_empty() {
+        return Ok(Vec::new());
+    }
+    let academic_year_id = resolve_academic_year(conn, None)?;
+    let entries: Vec<TimetableEntry> = repo::find_year_entries(conn, academic_year_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| e.day == day)
+        .collect();
+    let substitutions: HashMap<i64, (i64, String)> = repo::find_substitutions(conn, &date)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(entry_id, staff_id, name)| (entry_id, (staff_id, name)))
+        .collect();
+
+    let mut busy: HashSet<(i64, u32)> = HashSet::new();
+    let mut load: HashMap<i64, u32> = HashMap::new();
+    for entry in &entries {
+        let covering = substitutions
+            .get(&entry.id.unwrap_or_default())
+            .map(|(id, _)| *id);
+        for teacher_id in entry.teacher_id.into_iter().chain(covering) {
+            busy.insert((teacher_id, entry.period_no));
+            *load.entry(teacher_id).or_default() += 1;
+        }
+    }
+    let teaches: HashSet<(i64, i64)> = subject_repo::find_taught_in_year(conn, academic_year_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|t| Some((t.teacher_id?, t.subject_id)))
+        .collect();
+    let available: Vec<(i64, String)> = staff_repo::find_all(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|s| is_active(s.status.as_deref()))
+        .filter_map(|s| Some((s.id?, s.name)))
+        .filter(|(id, _)| !absent.contains(id))
+        .collect();
+
+    let mut needs = Vec::new();
+    for entry in entries {
+        let Some(absent_teacher_id) = entry.teacher_id.filter(|id| absent.contains(id)) else {
+            continue;
+        };
+        let subject_id = entry.subject_id.unwrap_or_default();
+        let mut suggestions: Vec<SubstituteCandidate> = available
+            .iter()
+            .filter(|(id, _)| !busy.contains(&(*id, entry.period_no)))
+            .map(|(id, name)| SubstituteCandidate {
+                staff_id: *id,
+                name: name.clone(),
+                periods_that_day: load.get(id).copied().unwrap_or(0),
+                teaches_subject: teaches.contains(&(*id, subject_id)),
+            })
+            .filter(|c| c.periods_that_day < settings.max_periods_per_day)
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.teaches_subject
+                .cmp(&a.teaches_subject)
+                .then(a.periods_that_day.cmp(&b.periods_that_day))
+                .then(a.name.cmp(&b.name))
+        });
+        let substitute = substitutions.get(&entry.id.unwrap_or_default()).cloned();
+        needs.push(SubstitutionNeed {
+            absent_teacher_id,
+            absent_teacher_name: entry.teacher_name.clone().unwrap_or_default(),
+            substitute_id: substitute.as_ref().map(|(id, _)| *id),
+            substitute_name: substitute.map(|(_, name)| name),
+            suggestions,
+            entry,
+        });
+    }
+    Ok(needs)
+}
+
+pub fn assign_substitute(
+    conn: &Connection,
+    date: &str,
+    entry_id: i64,
+    substitute_id: Option<i64>,
+) -> Result<(), String> {
+    let date = Date::parse(date)?;
+    let day = date.weekday() + 1;
+    let date = date.to_string();
+    let entry = repo::find_entry(conn, entry_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Timetable period not found".to_string())?;
+    let Some(substitute_id) = substitute_id else {
+        repo::delete_substitution(conn, &date, entry_id).map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+    if entry.day != day {
+        return Err(format!(
+            "That period is on {}, not on {}",
+            day_name(entry.day),
+            date
+        ));
+    }
+    if entry.teacher_id == Some(substitute_id) {
+        return Err("Choose a teacher other than the one who is away".to_string());
+    }
+    let substitute = staff_repo::find_by_id(conn, substitute_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Staff member not found".to_string())?;
+    if !is_active(substitute.status.as_deref()) {
+        return Err(format!("{} is not an active staff member", substitute.name));
+    }
+
+    let slot: Vec<TimetableEntry> =
+        repo::find_year_entries(conn, entry.academic_year_id.unwrap_or_default())
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|e| e.day == day && e.period_no == entry.period_no)
+            .collect();
+    if let Some(own) = slot.iter().find(|e| e.teacher_id == Some(substitute_id)) {
+        return Err(format!(
+            "{} teaches {} in that period",
+            substitute.name,
+            own.class_name.as_deref().unwrap_or_default()
+        ));
+    }
+    for (covered, staff_id, _) in
+        repo::find_substitutions(conn, &date).map_err(|e| e.to_string())?
+    {
+        if staff_id != substitute_id || covered == entry_id {
+            continue;
+        }
+        if let Some(other) = slot.iter().find(|e| e.id == Some(covered)) {
+            return Err(format!(
+                "{} is already covering {} in that period",
+                substitute.name,
+                other.class_name.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+    repo::upsert_substitution(conn, &date, entry_id, substitute_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::staff::create_staff;
+    use crate::service::staff_attendance::mark_attendance;
+    use crate::service::subject::{assign_class_subject, create_subject, set_subject_teacher};
+    use crate::staff_attendance::StaffAttendance;
+    use crate::subject::Subject;
+    use crate::test_support::{create_class, memory_db, sample_staff};
+
+    fn slot(label: &str, kind: &str, start: &str, end: &str) -> BellSlot {
+        BellSlot {
+            id: None,
+            label: label.to_string(),
+            kind: kind.to_string(),
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            period_no: None,
+        }
+    }
+
+    /// A day of `periods` forty-minute periods from 08:00 with a break after the third.
+    fn bell_day(conn: &Connection, periods: u32) {
+        let mut slots = Vec::new();
+        let mut start = 8 * 60;
+        for n in 1..=periods {
+            let time = |m: u32| format!("{:02}:{:02}", m / 60, m % 60);
+            slots.push(slot("", "period", &time(start), &time(start + 40)));
+            start += 40;
+            if n == 3 {
+                slots.push(slot("Recess", "break", &time(start), &time(start + 20)));
+                start += 20;
+            }
+        }
+        save_bell_schedule(conn, slots).unwrap();
+    }
+
+    fn subject_id(conn: &Connection, code: &str) -> i64 {
+        create_subject(
+            conn,
+            Subject {
+                id: None,
+                code: code.to_string(),
+                name: code.to_string(),
+                subject_type: "theory".to_string(),
+                status: None,
+                created_at: None,
+            },
+        )
+        .unwrap()
+    }
+
+    fn teach(
+        conn: &Connection,
+        class_id: i64,
+        subject_id: i64,
+        periods: u32,
+        teacher_id: Option<i64>,
+    ) -> i64 {
+        assign_class_subject(
+            conn,
+            ClassSubject {
+                id: None,
+                class_id,
+                subject_id,
+                periods_per_week: periods,
+                teacher_id,
+                academic_year_id: None,
+                class_name: None,
+                subject_code: None,
+                subject_name: None,
+                subject_type: None,
+                teacher_name: None,
+            },
+        )
+        .unwrap()
+    }
+
+    fn period(
+        class_id: i64,
+        day: u32,
+        period_no: u32,
+        class_subject_id: i64,
+        room_id: Option<i64>,
+    ) -> TimetableEntry {
+        TimetableEntry {
+            id: None,
+            class_id,
+            day,
+            period_no,
+            class_subject_id,
+            room_id,
+            academic_year_id: None,
+            class_name: None,
+            subject_id: None,
+            subject_code: None,
+            subject_name: None,
+            teacher_id: None,
+            teacher_name: None,
+            room_name: None,
+        }
+    }
+
+    #[test]
+    fn bell_schedule_numbers_periods_and_rejects_overlaps() {
+        let conn = memory_db();
+        bell_day(&conn, 4);
+        let slots = get_bell_schedule(&conn).unwrap();
+        assert_eq!(slots.len(), 5);
+        assert_eq!(slots[3].label, "Recess");
+        assert_eq!(slots[3].period_no, None);
+        assert_eq!(
+            (slots[4].label.as_str(), slots[4].period_no),
+            ("Period 4", Some(4))
+        );
+
+        let overlapping = vec![
+            slot("P1", "period", "08:00", "08:40"),
+            slot("P2", "period", "08:30", "09:10"),
+        ];
+        assert!(save_bell_schedule(&conn, overlapping)
+            .unwrap_err()
+            .contains("P2"));
+
+        let class_id = create_class(&conn, "Class-6 A");
+        let maths = teach(&conn, class_id, subject_id(&conn, "MATH"), 6, None);
+        set_entry(&conn, period(class_id, 1, 4, maths, None)).unwrap();
+        let err =
+            save_bell_schedule(&conn, vec![slot("P1", "period", "08:00", "08:40")]).unwrap_err();
+        assert!(err.contains("Period 4"));
+        assert!(save_settings(
+            &conn,
+            TimetableSettings {
+                working_days: 0,
+                max_periods_per_day: 6
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn manual_periods_reject_teacher_and_room_clashes_and_the_daily_limit() {
+        let conn = memory_db();
+        bell_day(&conn, 6);
+        save_settings(
+            &conn,
+            TimetableSettings {
+                working_days: 6,
+                max_periods_per_day: 2,
+            },
+        )
+        .unwrap();
+        let meera = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let arjun = create_staff(&conn, sample_staff("Arjun")).unwrap();
+        let lab = create_room(
+            &conn,
+            Room {
+                id: None,
+                name: "Science Lab".to_string(),
+                capacity: Some(30),
+                status: None,
+            },
+        )
+        .unwrap();
+        let six_a = create_class(&conn, "Class-6 A");
+        let six_b = create_class(&conn, "Class-6 B");
+        let maths = subject_id(&conn, "MATH");
+        let science = subject_id(&conn, "SCI");
+        let maths_a = teach(&conn, six_a, maths, 6, Some(meera));
+        let maths_b = teach(&conn, six_b, maths, 6, Some(meera));
+        let science_b = teach(&conn, six_b, science, 1, Some(arjun));
+
+        set_entry(&conn, period(six_a, 1, 1, maths_a, Some(lab))).unwrap();
+        let err = set_entry(&conn, period(six_b, 1, 1, maths_b, None)).unwrap_err();
+        assert!(err.contains("Meera already teaches Class-6 A"), "{}", err);
+        let err = set_entry(&conn, period(six_b, 1, 1, science_b, Some(lab))).unwrap_err();
+        assert!(err.contains("Science Lab is taken by Class-6 A"), "{}", err);
+        set_entry(&conn, period(six_b, 1, 1, science_b, None)).unwrap();
+        assert!(set_entry(&conn, period(six_b, 2, 1, science_b, None))
+            .unwrap_err()
+            .contains("all 1 periods"));
+
+        set_entry(&conn, period(six_b, 1, 2, maths_b, None)).unwrap();
+        let err = set_entry(&conn, period(six_a, 1, 3, maths_a, None)).unwrap_err();
+        assert!(err.contains("most allowed in a day"), "{}", err);
+
+        // Handing 6-B science to Meera double-books her in period 1 on Monday.
+        assert!(find_clashes(&conn, None).unwrap().is_empty());
+        set_subject_teacher(&conn, science_b, Some(meera)).unwrap();
+        let clashes = find_clashes(&conn, None).unwrap();
+        assert_eq!(clashes.len(), 2);
+        assert_eq!(
+            (clashes[0].kind.as_str(), clashes[0].period_no),
+            ("daily_limit", None)
+        );
+        assert_eq!(clashes[1].kind, "teacher");
+        assert_eq!(clashes[1].classes, vec!["Class-6 A", "Class-6 B"]);
+    }
+
+    #[test]
+    fn generator_fills_the_week_without_clashes() {
+        let conn = memory_db();
+        bell_day(&conn, 6);
+        save_settings(
+            &conn,
+            TimetableSettings {
+                working_days: 5,
+                max_periods_per_day: 5,
+            },
+        )
+        .unwrap();
+        let meera = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let arjun = create_staff(&conn, sample_staff("Arjun")).unwrap();
+        let maths = subject_id(&conn, "MATH");
+        let english = subject_id(&conn, "ENG");
+        let art = subject_id(&conn, "ART");
+        let mut classes = Vec::new();
+        for name in ["Class-6 A", "Class-6 B"] {
+            let class_id = create_class(&conn, name);
+            teach(&conn, class_id, maths, 8, Some(meera));
+            teach(&conn, class_id, english, 7, Some(arjun));
+            teach(&conn, class_id, art, 5, None);
+            classes.push(class_id);
+        }
+        // 6-C needs more periods than its 30-slot week holds.
+        let overfull = create_class(&conn, "Class-6 C");
+        teach(&conn, overfull, art, 20, None);
+        teach(&conn, overfull, english, 12, None);
+
+        let outcome = generate(&conn, None, None, false).unwrap();
+        assert_eq!(outcome.placed, 20 + 20 + 30);
+        assert_eq!(outcome.unplaced.len(), 1);
+        assert_eq!(outcome.unplaced[0].class_name, "Class-6 C");
+        assert_eq!(outcome.unplaced[0].missing, 2);
+        assert!(find_clashes(&conn, None).unwrap().is_empty());
+
+        let timetable = get_class_timetable(&conn, classes[0]).unwrap();
+        assert_eq!(timetable.len(), 20);
+        for day in 1..=5 {
+            let maths_that_day = timetable
+                .iter()
+                .filter(|e| e.day == day && e.subject_code.as_deref() == Some("MATH"))
+                .count();
+            assert!((1..=2).contains(&maths_that_day));
+        }
+
+        // Regenerating one class keeps the others and still avoids them.
+        let outcome = generate(&conn, None, Some(classes[1]), false).unwrap();
+        assert_eq!((outcome.placed, outcome.unplaced.len()), (20, 0));
+        assert_eq!(get_teacher_timetable(&conn, meera, None).unwrap().len(), 16);
+        assert!(find_clashes(&conn, None).unwrap().is_empty());
+
+        let (file_name, pdf) = render_class_timetable_pdf(&conn, Some(classes[0]), None).unwrap();
+        assert_eq!(file_name, "Timetable-Class-6-A-2024-2025.pdf");
+        assert!(pdf.starts_with(b"%PDF"));
+        let (file_name, _) = render_teacher_timetable_pdf(&conn, None, None).unwrap();
+        assert_eq!(file_name, "Timetable-Teachers-2024-2025.pdf");
+    }
+
+    #[test]
+    fn substitutes_are_suggested_for_absent_teachers() {
+        let conn = memory_db();
+        bell_day(&conn, 4);
+        let meera = create_staff(&conn, sample_staff("Meera")).unwrap();
+        let arjun = create_staff(&conn, sample_staff("Arjun")).unwrap();
+        let kavya = create_staff(&conn, sample_staff("Kavya")).unwrap();
+        let maths = subject_id(&conn, "MATH");
+        let english = subject_id(&conn, "ENG");
+        let six_a = create_class(&conn, "Class-6 A");
+        let six_b = create_class(&conn, "Class-6 B");
+        let maths_a = teach(&conn, six_a, maths, 6, Some(meera));
+        let maths_b = teach(&conn, six_b, maths, 6, Some(kavya));
+        let english_b = teach(&conn, six_b, english, 6, Some(arjun));
+
+        // 2024-07-01 is a Monday.
+        set_entry(&conn, period(six_a, 1, 1, maths_a, None)).unwrap();
+        set_entry(&conn, period(six_a, 1, 2, maths_a, None)).unwrap();
+        set_entry(&conn, period(six_b, 1, 1, english_b, None)).unwrap();
+        set_entry(&conn, period(six_b, 1, 3, maths_b, None)).unwrap();
+        mark_attendance(
+            &conn,
+            StaffAttendance {
+                id: None,
+                staff_id: meera,
+                date: "2024-07-01".to_string(),
+                check_in: None,
+                check_out: None,
+                status: "absent".to_string(),
+                remarks: None,
+            },
+        )
+        .unwrap();
+
+        let needs = substitution_suggestions(&conn, "2024-07-01").unwrap();
+        assert_eq!(needs.len(), 2);
+        // Arjun teaches 6-B in period 1, so only Kavya is free.
+        let first: Vec<&str> = needs[0]
+            .suggestions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(first, vec!["Kavya"]);
+        // Kavya also teaches maths, so she is suggested ahead of Arjun.
+        let second: Vec<(&str, bool)> = needs[1]
+            .suggestions
+            .iter()
+            .map(|s| (s.name.as_str(), s.teaches_subject))
+            .collect();
+        assert_eq!(second, vec![("Kavya", true), ("Arjun", false)]);
+
+        let entry_id = needs[0].entry.id.unwrap();
+        assert!(assign_substitute(&conn, "2024-07-01", entry_id, Some(arjun)).is_err());
+        assign_substitute(&conn, "2024-07-01", entry_id, Some(kavya)).unwrap();
+        let needs = substitution_suggestions(&conn, "2024-07-01").unwrap();
+        assert_eq!(needs[0].substitute_name.as_deref(), Some("Kavya"));
+        assert_eq!(needs[1].suggestions[0].periods_that_day, 2);
+        assert!(substitution_suggestions(&conn, "2024-07-02")
+            .unwrap()
+            .is_empty());
+    }
+}
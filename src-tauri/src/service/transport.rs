@@ -0,0 +1,772 @@
+use crate::date::{parse_time, Date};
+use crate::dues::StudentDue;
+use crate::pdf::{wrap, Align, Document, Font, Page};
+use crate::repository;
+use crate::repository::dues as dues_repo;
+use crate::repository::school as school_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::students as student_repo;
+use crate::repository::transport as repo;
+use crate::school::School;
+use crate::service::dues::post_due;
+use crate::service::lifecycle::is_on_roll;
+use crate::service::staff::is_active;
+use crate::transport::{
+    FareSlab, TransportAllocation, TransportFeeRun, TransportRoute, Vehicle, VehicleAlert,
+};
+use rusqlite::Connection;
+
+pub const VEHICLE_STATUSES: [&str; 2] = ["active", "retired"];
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn optional_date(value: Option<String>) -> Result<Option<String>, String> {
+    non_blank(value)
+        .map(|date| Date::parse(&date).map(|d| d.to_string()))
+        .transpose()
+}
+
+fn today(conn: &Connection) -> Result<Date, String> {
+    Date::parse(&repository::today(conn).map_err(|e| e.to_string())?)
+}
+
+fn time_of_day(value: &str) -> Result<String, String> {
+    parse_time(value)?;
+    Ok(value.trim().chars().take(5).collect())
+}
+
+pub fn get_vehicles(conn: &Connection) -> Result<Vec<Vehicle>, String> {
+    repo::find_vehicles(conn).map_err(|e| e.to_string())
+}
+
+pub fn save_vehicle(conn: &Connection, vehicle: Vehicle) -> Result<i64, String> {
+    let vehicle = Vehicle {
+        registration_number: vehicle.registration_number.trim().to_uppercase(),
+        vehicle_type: non_blank(vehicle.vehicle_type),
+        fitness_expiry: optional_date(vehicle.fitness_expiry)?,
+        insurance_expiry: optional_date(vehicle.insurance_expiry)?,
+        ..vehicle
+    };
+    let status = non_blank(vehicle.status.clone())
+        .unwrap_or_else(|| "active".to_string())
+        .to_lowercase();
+    if !VEHICLE_STATUSES.contains(&status.as_str()) {
+        return Err(format!(
+            "Invalid vehicle status '{}'. Expected one of: {}",
+            status,
+            VEHICLE_STATUSES.join(", ")
+        ));
+    }
+    if vehicle.registration_number.is_empty() {
+        return Err("Registration number is required".to_string());
+    }
+    if vehicle.capacity == 0 {
+        return Err("Capacity must be at least one seat".to_string());
+    }
+    if repo::registration_taken(conn, &vehicle.registration_number, vehicle.id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "A vehicle with registration {} already exists",
+            vehicle.registration_number
+        ));
+    }
+    if let Some(driver_id) = vehicle.driver_id {
+        let driver = staff_repo::find_by_id(conn, driver_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Staff member with id {} not found", driver_id))?;
+        if !is_active(driver.status.as_deref()) {
+            return Err(format!("{} is no longer active", driver.name));
+        }
+    }
+
+    match vehicle.id {
+        Some(id) => {
+            if status == "retired" {
+                if let Some(route) =
+                    repo::find_route_of_vehicle(conn, id).map_err(|e| e.to_string())?
+                {
+                    return Err(format!(
+                        "The vehicle runs {}; assign another vehicle to it first",
+                        route
+                    ));
+                }
+            }
+            if repo::update_vehicle(conn, id, &vehicle, &status).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Vehicle with id {} not found", id));
+            }
+            Ok(id)
+        }
+        None => repo::insert_vehicle(conn, &vehicle, &status).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn delete_vehicle(conn: &Connection, id: i64) -> Result<(), String> {
+    if let Some(route) = repo::find_route_of_vehicle(conn, id).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "The vehicle runs {}; assign another vehicle to it first",
+            route
+        ));
+    }
+    if repo::delete_vehicle(conn, id).map_err(|e| e.to_string())? == 0 {
+        return Err(format!("Vehicle with id {} not found", id));
+    }
+    Ok(())
+}
+
+/// Fitness and insurance of active vehicles expiring by `as_of` (default: today) plus
+/// `within_days` (default 30), soonest first.
+pub fn get_vehicle_alerts(
+    conn: &Connection,
+    as_of: Option<String>,
+    within_days: Option<u32>,
+) -> Result<Vec<VehicleAlert>, String> {
+    let as_of = match non_blank(as_of) {
+        Some(date) => Date::parse(&date)?,
+        None => today(conn)?,
+    };
+    let limit = as_of
+        .add_days(i64::from(within_days.unwrap_or(30)))
+        .to_string();
+    let as_of = as_of.to_string();
+    let mut alerts = Vec::new();
+    for vehicle in get_vehicles(conn)? {
+        if vehicle.status.as_deref() != Some("active") {
+            continue;
+        }
+        let documents = [
+            ("fitness", &vehicle.fitness_expiry),
+            ("insurance", &vehicle.insurance_expiry),
+        ];
+        for (document, expiry) in documents {
+            let Some(expiry) = expiry.as_ref().filter(|expiry| **expiry <= limit) else {
+                continue;
+            };
+            alerts.push(VehicleAlert {
+                vehicle_id: vehicle.id.unwrap_or_default(),
+                registration_number: vehicle.registration_number.clone(),
+                document: document.to_string(),
+                expires_on: expiry.clone(),
+                expired: *expiry < as_of,
+            });
+        }
+    }
+    alerts.sort_by(|a, b| a.expires_on.cmp(&b.expires_on));
+    Ok(alerts)
+}
+
+pub fn get_routes(conn: &Connection) -> Result<Vec<TransportRoute>, String> {
+    let mut routes = repo::find_routes(conn).map_err(|e| e.to_string())?;
+    for route in &mut routes {
+        route.stops =
+            repo::find_stops(conn, route.id.unwrap_or_default()).map_err(|e| e.to_string())?;
+    }
+    Ok(routes)
+}
+
+fn get_route(conn: &Connection, id: i64) -> Result<TransportRoute, String> {
+    get_routes(conn)?
+        .into_iter()
+        .find(|route| route.id == Some(id))
+        .ok_or_else(|| format!("Route with id {} not found", id))
+}
+
+/// Saves the route and its stops in the order given. Stops left out are removed, which
+/// is refused while students on the roll are assigned to them.
+pub fn save_route(conn: &Connection, route: TransportRoute) -> Result<TransportRoute, String> {
+    let mut route = TransportRoute {
+        name: route.name.trim().to_string(),
+        ..route
+    };
+    if route.name.is_empty() {
+        return Err("Route name is required".to_string());
+    }
+    if repo::route_name_taken(conn, &route.name, route.id).map_err(|e| e.to_string())? {
+        return Err(format!("A route named {} already exists", route.name));
+    }
+    if let Some(vehicle_id) = route.vehicle_id {
+        let vehicle = repo::find_vehicle(conn, vehicle_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Vehicle with id {} not found", vehicle_id))?;
+        if vehicle.status.as_deref() != Some("active") {
+            return Err(format!("{} is retired", vehicle.registration_number));
+        }
+    }
+    let mut previous_pickup = None;
+    for stop in &mut route.stops {
+        stop.name = stop.name.trim().to_string();
+        if stop.name.is_empty() {
+            return Err("Every stop needs a name".to_string());
+        }
+        stop.pickup_time = time_of_day(&stop.pickup_time)?;
+        stop.drop_time = non_blank(stop.drop_time.take())
+            .map(|time| time_of_day(&time))
+            .transpose()?;
+        if !stop.distance_km.is_finite() || stop.distance_km < 0.0 {
+            return Err(format!("{} needs a distance from school", stop.name));
+        }
+        if previous_pickup.is_some_and(|previous| stop.pickup_time < previous) {
+            return Err(format!(
+                "{} is picked up before the stop ahead of it",
+                stop.name
+            ));
+        }
+        previous_pickup = Some(stop.pickup_time.clone());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let route_id = match route.id {
+        Some(id) => {
+            if repo::update_route(&tx, id, &route).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("Route with id {} not found", id));
+            }
+            for existing in repo::find_stops(&tx, id).map_err(|e| e.to_string())? {
+                if route.stops.iter().any(|stop| stop.id == existing.id) {
+                    continue;
+                }
+                if existing.students > 0 {
+                    return Err(format!(
+                        "{} student(s) board at {}; move them before removing the stop",
+                        existing.students, existing.name
+                    ));
+                }
+                repo::delete_stop(&tx, existing.id.unwrap_or_default())
+                    .map_err(|e| e.to_string())?;
+            }
+            id
+        }
+        None => repo::insert_route(&tx, &route).map_err(|e| e.to_string())?,
+    };
+    for (order, stop) in route.stops.iter().enumerate() {
+        match stop.id {
+            Some(stop_id) => {
+                if repo::find_stop_route(&tx, stop_id).map_err(|e| e.to_string())? != Some(route_id)
+                {
+                    return Err(format!("{} is not a stop of this route", stop.name));
+                }
+                repo::update_stop(&tx, stop_id, order, stop).map_err(|e| e.to_string())?;
+            }
+            None => {
+                repo::insert_stop(&tx, route_id, order, stop).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_route(conn, route_id)
+}
+
+pub fn delete_route(conn: &Connection, id: i64) -> Result<(), String> {
+    let route = get_route(conn, id)?;
+    if route.students > 0 {
+        return Err(format!(
+            "{} student(s) use {}; move them to another route first",
+            route.students, route.name
+        ));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::delete_route(&tx, id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_fare_slabs(conn: &Connection) -> Result<Vec<FareSlab>, String> {
+    repo::find_fare_slabs(conn).map_err(|e| e.to_string())
+}
+
+/// Replaces the slabs. Slabs may leave gaps but must not overlap.
+pub fn save_fare_slabs(
+    conn: &Connection,
+    mut slabs: Vec<FareSlab>,
+) -> Result<Vec<FareSlab>, String> {
+    slabs.sort_by(|a, b| a.from_km.total_cmp(&b.from_km));
+    for slab in &slabs {
+        if !slab.from_km.is_finite() || !slab.to_km.is_finite() || slab.from_km < 0.0 {
+            return Err("Slab distances must be zero or more".to_string());
+        }
+        if slab.to_km <= slab.from_km {
+            return Err(format!(
+                "The slab from {} km must end after it starts",
+                slab.from_km
+            ));
+        }
+        if !slab.amount.is_finite() || slab.amount < 0.0 {
+            return Err("A fare cannot be negative".to_string());
+        }
+    }
+    if let Some(pair) = slabs
+        .windows(2)
+        .find(|pair| pair[1].from_km < pair[0].to_km)
+    {
+        return Err(format!(
+            "The slabs from {} km and {} km overlap",
+            pair[0].from_km, pair[1].from_km
+        ));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    repo::replace_fare_slabs(&tx, &slabs).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_fare_slabs(conn)
+}
+
+/// Assigns the student to the stop. Going past the vehicle's capacity is allowed, but the
+/// returned allocation carries a warning.
+pub fn allocate_student(
+    conn: &Connection,
+    allocation: TransportAllocation,
+) -> Result<TransportAllocation, String> {
+    let student_id = allocation.student_id;
+    let status = student_repo::find_status(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", student_id))?;
+    if !is_on_roll(status.as_deref()) {
+        return Err("Only students on the roll can be given transport".to_string());
+    }
+    let route_id = repo::find_stop_route(conn, allocation.stop_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Stop with id {} not found", allocation.stop_id))?;
+    let allocated_on = match non_blank(allocation.allocated_on) {
+        Some(date) => Date::parse(&date)?.to_string(),
+        None => today(conn)?.to_string(),
+    };
+    repo::upsert_allocation(conn, student_id, allocation.stop_id, &allocated_on)
+        .map_err(|e| e.to_string())?;
+
+    let route = get_route(conn, route_id)?;
+    let mut saved = repo::find_allocation(conn, student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Allocation was not saved".to_string())?;
+    if let Some(capacity) = route.capacity.filter(|capacity| route.students > *capacity) {
+        saved.capacity_warning = Some(format!(
+            "{} now has {} students for {} seats in {}",
+            route.name,
+            route.students,
+            capacity,
+            route.registration_number.unwrap_or_default()
+        ));
+    }
+    Ok(saved)
+}
+
+pub fn remove_allocation(conn: &Connection, student_id: i64) -> Result<(), String> {
+    if repo::delete_allocation(conn, student_id).map_err(|e| e.to_string())? == 0 {
+        return Err("The student has no transport".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_student_allocation(
+    conn: &Connection,
+    student_id: i64,
+) -> Result<Option<TransportAllocation>, String> {
+    repo::find_allocation(conn, student_id).map_err(|e| e.to_string())
+}
+
+pub fn get_route_students(
+    conn: &Connection,
+    route_id: i64,
+) -> Result<Vec<TransportAllocation>, String> {
+    repo::find_allocations(conn, Some(route_id)).map_err(|e| e.to_string())
+}
+
+/// Raises every allocated student's fare for `period` as a transport due. Students already
+/// charged for the period are left alone, so a run can be repeated after adding students.
+pub fn raise_fees(
+    conn: &Connection,
+    period: &str,
+    raised_on: Option<String>,
+) -> Result<TransportFeeRun, String> {
+    let period = period.trim();
+    if period.is_empty() {
+        return Err("Say which period the fees are for".to_string());
+    }
+    let mut run = TransportFeeRun {
+        raised: 0,
+        total: 0.0,
+        already_raised: 0,
+        without_fare: Vec::new(),
+    };
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for allocation in repo::find_allocations(&tx, None).map_err(|e| e.to_string())? {
+        let name = allocation.student_name.clone().unwrap_or_default();
+        let Some(fare) = allocation.fare.filter(|fare| *fare > 0.0) else {
+            run.without_fare.push(name);
+            continue;
+        };
+        let reference = format!("{}:{}", period, allocation.student_id);
+        if dues_repo::reference_taken(&tx, "transport", &reference).map_err(|e| e.to_string())? {
+            run.already_raised += 1;
+            continue;
+        }
+        let due = StudentDue {
+            id: None,
+            student_id: allocation.student_id,
+            source: None,
+            reference: Some(reference),
+            description: format!(
+                "Transport fee {} ({}, {})",
+                period,
+                allocation.route_name.unwrap_or_default(),
+                allocation.stop_name.unwrap_or_default()
+            ),
+            amount: fare,
+            raised_on: raised_on.clone(),
+            status: None,
+            settled_on: None,
+            settled_note: None,
+            student_name: None,
+            class_name: None,
+        };
+        post_due(&tx, due, "transport")?;
+        run.raised += 1;
+        run.total += fare;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(run)
+}
+
+fn file_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+const LIST_COLUMNS: [(&str, f32); 7] = [
+    ("#", 22.0),
+    ("Stop", 95.0),
+    ("Pickup", 40.0),
+    ("Student", 130.0),
+    ("Class", 70.0),
+    ("Roll", 35.0),
+    ("Phone", 73.0),
+];
+
+fn draw_list_header(
+    page: &mut Page,
+    school: Option<&School>,
+    route: &TransportRoute,
+    driver: &str,
+) -> f32 {
+    let center = page.width() / 2.0;
+    let margin = 40.0;
+    let mut y = page.height() - 50.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            15.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 20.0;
+    }
+    let title = format!("Route List - {}", route.name);
+    page.text_aligned(center, y, 12.0, Font::Bold, Align::Center, &title);
+    y -= 18.0;
+    let vehicle = match (&route.registration_number, route.capacity) {
+        (Some(registration), Some(capacity)) => format!("{} ({} seats)", registration, capacity),
+        _ => "Not assigned".to_string(),
+    };
+    let details = format!(
+        "Vehicle: {}    Driver: {}    Students: {}",
+        vehicle, driver, route.students
+    );
+    page.text(margin, y, 9.0, Font::Regular, &details);
+    y -= 16.0;
+    let mut x = margin;
+    for (label, width) in LIST_COLUMNS {
+        page.text(x + 2.0, y, 9.0, Font::Bold, label);
+        x += width;
+    }
+    y -= 5.0;
+    page.line(margin, y, page.width() - margin, y, 0.8);
+    y - 13.0
+}
+
+/// File name and PDF listing the students of one route, or of every route with students,
+/// grouped by stop in pickup order.
+pub fn render_route_list_pdf(
+    conn: &Connection,
+    route_id: Option<i64>,
+) -> Result<(String, Vec<u8>), String> {
+    let routes = match route_id {
+        Some(id) => vec![get_route(conn, id)?],
+        None => get_routes(conn)?
+            .into_iter()
+            .filter(|route| route.students > 0)
+            .collect(),
+    };
+    if routes.is_empty() {
+        return Err("No route has students yet".to_string());
+    }
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let mut doc = Document::new("Route Lists");
+    for route in &routes {
+        let driver = match route.vehicle_id {
+            Some(vehicle_id) => repo::find_vehicle(conn, vehicle_id)
+                .map_err(|e| e.to_string())?
+                .and_then(|vehicle| {
+                    let name = vehicle.driver_name?;
+                    Some(match vehicle.driver_phone {
+                        Some(phone) => format!("{} ({})", name, phone),
+                        None => name,
+                    })
+                })
+                .unwrap_or_else(|| "-".to_string()),
+            None => "-".to_string(),
+        };
+        let students = get_route_students(conn, route.id.unwrap_or_default())?;
+        let mut page = doc.add_page();
+        let mut y = draw_list_header(page, school.as_ref(), route, &driver);
+        for (index, student) in students.iter().enumerate() {
+            if y < 50.0 {
+                page = doc.add_page();
+                y = draw_list_header(page, school.as_ref(), route, &driver);
+            }
+            let cells = [
+                (index + 1).to_string(),
+                student.stop_name.clone().unwrap_or_default(),
+                student.pickup_time.clone().unwrap_or_default(),
+                student.student_name.clone().unwrap_or_default(),
+                student.class_name.clone().unwrap_or_default(),
+                student.roll_number.clone().unwrap_or_default(),
+                student.mobile_number.clone().unwrap_or_default(),
+            ];
+            let mut x = 40.0;
+            for ((_, width), cell) in LIST_COLUMNS.iter().zip(cells.iter()) {
+                page.text(x + 2.0, y, 8.5, Font::Regular, cell);
+                x += width;
+            }
+            y -= 14.0;
+            if let Some(alert) = &student.medical_alert {
+                let indent = 40.0 + LIST_COLUMNS[0].1 + 2.0;
+                let note = format!("Medical alert: {}", alert);
+                for line in wrap(&note, 7.5, Font::Bold, page.width() - indent - 40.0) {
+                    if y < 50.0 {
+                        page = doc.add_page();
+                        y = draw_list_header(page, school.as_ref(), route, &driver);
+                    }
+                    page.text(indent, y + 3.0, 7.5, Font::Bold, &line);
+                    y -= 10.0;
+                }
+            }
+        }
+    }
+    let file_name = match route_id {
+        Some(_) => format!("Route-List-{}.pdf", file_part(&routes[0].name)),
+        None => "Route-Lists.pdf".to_string(),
+    };
+    Ok((file_name, doc.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::dues::get_open_dues;
+    use crate::service::staff::create_staff;
+    use crate::service::students::save_core;
+    use crate::test_support::{create_class, memory_db, sample_staff, sample_student};
+    use crate::transport::RouteStop;
+
+    fn vehicle(registration_number: &str, capacity: u32, driver_id: Option<i64>) -> Vehicle {
+        Vehicle {
+            id: None,
+            registration_number: registration_number.to_string(),
+            capacity,
+            vehicle_type: Some("van".to_string()),
+            fitness_expiry: None,
+            insurance_expiry: None,
+            driver_id,
+            status: None,
+            driver_name: None,
+            driver_phone: None,
+        }
+    }
+
+    fn stop(name: &str, pickup_time: &str, distance_km: f64) -> RouteStop {
+        RouteStop {
+            id: None,
+            name: name.to_string(),
+            pickup_time: pickup_time.to_string(),
+            drop_time: None,
+            distance_km,
+            students: 0,
+        }
+    }
+
+    fn route(name: &str, vehicle_id: Option<i64>, stops: Vec<RouteStop>) -> TransportRoute {
+        TransportRoute {
+            id: None,
+            name: name.to_string(),
+            vehicle_id,
+            stops,
+            registration_number: None,
+            capacity: None,
+            students: 0,
+        }
+    }
+
+    fn allocation(student_id: i64, stop_id: i64) -> TransportAllocation {
+        TransportAllocation {
+            student_id,
+            stop_id,
+            allocated_on: Some("2024-06-01".to_string()),
+            student_name: None,
+            class_name: None,
+            roll_number: None,
+            mobile_number: None,
+            route_id: None,
+            route_name: None,
+            stop_name: None,
+            pickup_time: None,
+            distance_km: None,
+            fare: None,
+            capacity_warning: None,
+            medical_alert: None,
+        }
+    }
+
+    fn slab(from_km: f64, to_km: f64, amount: f64) -> FareSlab {
+        FareSlab {
+            from_km,
+            to_km,
+            amount,
+        }
+    }
+
+    #[test]
+    fn routes_keep_stop_order_and_protect_used_stops() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-4 A");
+        let student_id =
+            save_core(&conn, sample_student(&class_id.to_string(), "T1").core).unwrap();
+        let err = save_route(
+            &conn,
+            route(
+                "North",
+                None,
+                vec![stop("Market", "07:30", 4.0), stop("Temple", "07:10", 6.0)],
+            ),
+        )
+        .unwrap_err();
+        assert!(err.contains("before the stop ahead"), "{}", err);
+
+        let mut saved = save_route(
+            &conn,
+            route(
+                "North",
+                None,
+                vec![stop("Temple", "07:10", 6.0), stop("Market", "07:30", 4.0)],
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            saved
+                .stops
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Temple", "Market"]
+        );
+        assert!(save_route(&conn, route("north", None, Vec::new())).is_err());
+
+        let market = saved.stops[1].id.unwrap();
+        allocate_student(&conn, allocation(student_id, market)).unwrap();
+        saved.stops.pop();
+        let err = save_route(&conn, saved).unwrap_err();
+        assert!(err.contains("1 student(s) board at Market"), "{}", err);
+        assert!(delete_route(&conn, get_routes(&conn).unwrap()[0].id.unwrap()).is_err());
+    }
+
+    #[test]
+    fn allocating_past_capacity_warns_and_fares_follow_distance() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-4 A");
+        let driver = create_staff(&conn, sample_staff("Ramesh")).unwrap();
+        let van = save_vehicle(&conn, vehicle("mh12 ab 1234", 2, Some(driver))).unwrap();
+        assert!(save_vehicle(&conn, vehicle("MH12 AB 1234", 30, None)).is_err());
+        let saved = save_route(
+            &conn,
+            route(
+                "South",
+                Some(van),
+                vec![stop("Lake", "07:00", 7.5), stop("Park", "07:20", 2.0)],
+            ),
+        )
+        .unwrap();
+        let (lake, park) = (saved.stops[0].id.unwrap(), saved.stops[1].id.unwrap());
+
+        assert!(
+            save_fare_slabs(&conn, vec![slab(0.0, 5.0, 600.0), slab(4.0, 10.0, 900.0)])
+                .unwrap_err()
+                .contains("overlap")
+        );
+        save_fare_slabs(&conn, vec![slab(5.0, 10.0, 900.0), slab(0.0, 5.0, 600.0)]).unwrap();
+
+        let mut warnings = Vec::new();
+        for (gr_number, stop_id) in [("T1", lake), ("T2", park), ("T3", park)] {
+            let student_id =
+                save_core(&conn, sample_student(&class_id.to_string(), gr_number).core).unwrap();
+            let saved = allocate_student(&conn, allocation(student_id, stop_id)).unwrap();
+            warnings.push(saved.capacity_warning);
+        }
+        assert_eq!(warnings[..2], [None, None]);
+        assert_eq!(
+            warnings[2].as_deref(),
+            Some("South now has 3 students for 2 seats in MH12 AB 1234")
+        );
+
+        let students = get_route_students(&conn, saved.id.unwrap()).unwrap();
+        assert_eq!(
+            students
+                .iter()
+                .map(|s| (s.stop_name.as_deref().unwrap(), s.fare))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Lake", Some(900.0)),
+                ("Park", Some(600.0)),
+                ("Park", Some(600.0))
+            ]
+        );
+
+        let run = raise_fees(&conn, "2024-07", Some("2024-07-01".to_string())).unwrap();
+        assert_eq!((run.raised, run.total), (3, 2100.0));
+        let again = raise_fees(&conn, "2024-07", None).unwrap();
+        assert_eq!((again.raised, again.already_raised), (0, 3));
+        assert_eq!(get_open_dues(&conn, Some(class_id)).unwrap().len(), 3);
+
+        let (file_name, pdf) = render_route_list_pdf(&conn, None).unwrap();
+        assert_eq!(file_name, "Route-Lists.pdf");
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn vehicle_documents_are_flagged_before_they_lapse() {
+        let conn = memory_db();
+        let mut bus = vehicle("KA01 F 2345", 40, None);
+        bus.fitness_expiry = Some("2024-06-20".to_string());
+        bus.insurance_expiry = Some("2024-05-30".to_string());
+        let bus = save_vehicle(&conn, bus).unwrap();
+        let mut van = vehicle("KA01 F 9999", 12, None);
+        van.insurance_expiry = Some("2024-12-31".to_string());
+        save_vehicle(&conn, van).unwrap();
+
+        let alerts = get_vehicle_alerts(&conn, Some("2024-06-01".to_string()), None).unwrap();
+        assert_eq!(
+            alerts
+                .iter()
+                .map(|a| (a.document.as_str(), a.expired))
+                .collect::<Vec<_>>(),
+            vec![("insurance", true), ("fitness", false)]
+        );
+        assert!(alerts.iter().all(|a| a.vehicle_id == bus));
+    }
+}
@@ -0,0 +1,472 @@
+use crate::date::{parse_time, Date};
+use crate::pdf::{Align, Document, Font, Page};
+use crate::repository;
+use crate::repository::guardian as guardian_repo;
+use crate::repository::school as school_repo;
+use crate::repository::staff as staff_repo;
+use crate::repository::students as student_repo;
+use crate::repository::visitor as repo;
+use crate::school::School;
+use crate::service::lifecycle::is_on_roll;
+use crate::visitor::{DailyGateRegister, GatePass, Visitor};
+use rusqlite::Connection;
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn date_or_today(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(date) => Ok(Date::parse(&date)?.to_string()),
+        None => repository::today(conn).map_err(|e| e.to_string()),
+    }
+}
+
+fn time_or_now(conn: &Connection, value: Option<String>) -> Result<String, String> {
+    match non_blank(value) {
+        Some(time) => {
+            parse_time(&time)?;
+            Ok(time.chars().take(5).collect())
+        }
+        None => repository::now_time(conn).map_err(|e| e.to_string()),
+    }
+}
+
+fn date_range(from: Option<String>, to: Option<String>) -> Result<(String, String), String> {
+    let from = match non_blank(from) {
+        Some(from) => Date::parse(&from)?.to_string(),
+        None => "0000-01-01".to_string(),
+    };
+    let to = match non_blank(to) {
+        Some(to) => Date::parse(&to)?.to_string(),
+        None => "9999-12-31".to_string(),
+    };
+    Ok((from, to))
+}
+
+pub fn record_visitor(conn: &Connection, visitor: Visitor) -> Result<i64, String> {
+    let mut visitor = Visitor {
+        visitor_name: visitor.visitor_name.trim().to_string(),
+        phone: non_blank(visitor.phone),
+        purpose: visitor.purpose.trim().to_string(),
+        person_to_meet: visitor.person_to_meet.trim().to_string(),
+        visited_on: Some(date_or_today(conn, visitor.visited_on)?),
+        in_time: Some(time_or_now(conn, visitor.in_time)?),
+        out_time: non_blank(visitor.out_time)
+            .map(|time| time_or_now(conn, Some(time)))
+            .transpose()?,
+        photo: non_blank(visitor.photo),
+        remarks: non_blank(visitor.remarks),
+        ..visitor
+    };
+    if visitor.visitor_name.is_empty() || visitor.purpose.is_empty() {
+        return Err("Visitor name and purpose are required".to_string());
+    }
+    if let Some(staff_id) = visitor.staff_id {
+        let staff = staff_repo::find_by_id(conn, staff_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Staff member with id {} not found", staff_id))?;
+        if visitor.person_to_meet.is_empty() {
+            visitor.person_to_meet = staff.name;
+        }
+    }
+    if visitor.person_to_meet.is_empty() {
+        return Err("Say whom the visitor came to meet".to_string());
+    }
+    if visitor.out_time.is_some() && visitor.out_time < visitor.in_time {
+        return Err("A visitor cannot leave before arriving".to_string());
+    }
+    repo::insert_visitor(conn, &visitor).map_err(|e| e.to_string())
+}
+
+pub fn record_exit(conn: &Connection, id: i64, out_time: Option<String>) -> Result<(), String> {
+    let visitor = repo::find_visitor(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Visitor entry with id {} not found", id))?;
+    if let Some(out_time) = visitor.out_time {
+        return Err(format!(
+            "{} already left at {}",
+            visitor.visitor_name, out_time
+        ));
+    }
+    let out_time = time_or_now(conn, out_time)?;
+    if Some(&out_time) < visitor.in_time.as_ref() {
+        return Err("A visitor cannot leave before arriving".to_string());
+    }
+    repo::set_out_time(conn, id, &out_time).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn search_visitors(
+    conn: &Connection,
+    query: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Visitor>, String> {
+    let (from, to) = date_range(from, to)?;
+    let term = non_blank(query).unwrap_or_default();
+    repo::search_visitors(conn, &term, &from, &to).map_err(|e| e.to_string())
+}
+
+/// Lets a student on the roll leave early. The pass must name a guardian on the student's
+/// record, and a student gets at most one pass a day.
+pub fn issue_gate_pass(conn: &Connection, pass: GatePass) -> Result<GatePass, String> {
+    let pass = GatePass {
+        issued_on: Some(date_or_today(conn, pass.issued_on)?),
+        out_time: Some(time_or_now(conn, pass.out_time)?),
+        reason: pass.reason.trim().to_string(),
+        collected_by: non_blank(pass.collected_by),
+        approved_by: non_blank(pass.approved_by),
+        ..pass
+    };
+    if pass.reason.is_empty() {
+        return Err("Give the reason for leaving early".to_string());
+    }
+    let status = student_repo::find_status(conn, pass.student_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Student with id {} not found", pass.student_id))?;
+    if !is_on_roll(status.as_deref()) {
+        return Err("Gate passes are only issued to students on the roll".to_string());
+    }
+    let guardian = guardian_repo::find_by_id(conn, pass.guardian_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Guardian with id {} not found", pass.guardian_id))?;
+    if !guardian_repo::is_linked(conn, pass.student_id, pass.guardian_id)
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "{} is not a guardian on this student's record",
+            guardian.full_name
+        ));
+    }
+    let issued_on = pass.issued_on.clone().unwrap_or_default();
+    if let Some(out_time) =
+        repo::find_pass_time(conn, pass.student_id, &issued_on).map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "A gate pass was already issued to the student on {} at {}",
+            issued_on, out_time
+        ));
+    }
+    let pass = GatePass {
+        collected_by: Some(pass.collected_by.clone().unwrap_or(guardian.full_name)),
+        ..pass
+    };
+    let id = repo::insert_gate_pass(conn, &pass).map_err(|e| e.to_string())?;
+    repo::find_gate_pass(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Gate pass was not saved".to_string())
+}
+
+pub fn get_gate_passes(
+    conn: &Connection,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<GatePass>, String> {
+    let (from, to) = date_range(from, to)?;
+    repo::find_gate_passes(conn, student_id, &from, &to).map_err(|e| e.to_string())
+}
+
+pub fn get_daily_register(
+    conn: &Connection,
+    date: Option<String>,
+) -> Result<DailyGateRegister, String> {
+    let date = date_or_today(conn, date)?;
+    let visitors = repo::find_visitors_on(conn, &date).map_err(|e| e.to_string())?;
+    let mut gate_passes =
+        repo::find_gate_passes(conn, None, &date, &date).map_err(|e| e.to_string())?;
+    gate_passes.reverse();
+    Ok(DailyGateRegister {
+        still_inside: visitors
+            .iter()
+            .filter(|visitor| visitor.out_time.is_none())
+            .count() as u32,
+        date,
+        visitors,
+        gate_passes,
+    })
+}
+
+const VISITOR_COLUMNS: [(&str, f32); 7] = [
+    ("In", 35.0),
+    ("Out", 35.0),
+    ("Visitor", 105.0),
+    ("Phone", 70.0),
+    ("Purpose", 105.0),
+    ("To meet", 95.0),
+    ("Photo", 70.0),
+];
+
+const PASS_COLUMNS: [(&str, f32); 6] = [
+    ("Out", 35.0),
+    ("Student", 105.0),
+    ("Class", 60.0),
+    ("Authorised by", 115.0),
+    ("Collected by", 90.0),
+    ("Reason", 110.0),
+];
+
+fn draw_header(page: &mut Page, school: Option<&School>, register: &DailyGateRegister) -> f32 {
+    let center = page.width() / 2.0;
+    let mut y = page.height() - 50.0;
+    if let Some(school) = school {
+        page.text_aligned(
+            center,
+            y,
+            15.0,
+            Font::Bold,
+            Align::Center,
+            &school.school_name,
+        );
+        y -= 20.0;
+    }
+    let title = format!("Gate Register - {}", register.date);
+    page.text_aligned(center, y, 12.0, Font::Bold, Align::Center, &title);
+    y -= 18.0;
+    let summary = format!(
+        "Visitors: {}    Still inside: {}    Gate passes: {}",
+        register.visitors.len(),
+        register.still_inside,
+        register.gate_passes.len()
+    );
+    page.text(40.0, y, 9.0, Font::Regular, &summary);
+    y - 24.0
+}
+
+/// Draws a section title and its column labels, returning the y of the first row.
+fn draw_section(page: &mut Page, y: f32, title: &str, columns: &[(&str, f32)]) -> f32 {
+    let margin = 40.0;
+    page.text(margin, y, 11.0, Font::Bold, title);
+    let y = y - 16.0;
+    let mut x = margin;
+    for (label, width) in columns {
+        page.text(x + 2.0, y, 9.0, Font::Bold, label);
+        x += width;
+    }
+    page.line(margin, y - 5.0, page.width() - margin, y - 5.0, 0.8);
+    y - 18.0
+}
+
+/// Title, columns and rows of one part of the register.
+type Section<'a> = (&'a str, &'a [(&'a str, f32)], &'a [Vec<String>]);
+
+/// File name and PDF of one day's visitors followed by its gate passes.
+pub fn render_daily_register_pdf(
+    conn: &Connection,
+    date: Option<String>,
+) -> Result<(String, Vec<u8>), String> {
+    let register = get_daily_register(conn, date)?;
+    let school = school_repo::find_first(conn).map_err(|e| e.to_string())?;
+    let visitor_rows: Vec<Vec<String>> = register
+        .visitors
+        .iter()
+        .map(|visitor| {
+            vec![
+                visitor.in_time.clone().unwrap_or_default(),
+                visitor.out_time.clone().unwrap_or_else(|| "-".to_string()),
+                visitor.visitor_name.clone(),
+                visitor.phone.clone().unwrap_or_default(),
+                visitor.purpose.clone(),
+                visitor.person_to_meet.clone(),
+                if visitor.photo.is_some() {
+                    "On file"
+                } else {
+                    "-"
+                }
+                .to_string(),
+            ]
+        })
+        .collect();
+    let pass_rows: Vec<Vec<String>> = register
+        .gate_passes
+        .iter()
+        .map(|pass| {
+            vec![
+                pass.out_time.clone().unwrap_or_default(),
+                pass.student_name.clone().unwrap_or_default(),
+                pass.class_name.clone().unwrap_or_default(),
+                format!(
+                    "{} ({})",
+                    pass.guardian_name.clone().unwrap_or_default(),
+                    pass.guardian_relation.clone().unwrap_or_default()
+                ),
+                pass.collected_by.clone().unwrap_or_default(),
+                pass.reason.clone(),
+            ]
+        })
+        .collect();
+
+    let mut doc = Document::new("Gate Register");
+    let mut page = doc.add_page();
+    let mut y = draw_header(page, school.as_ref(), &register);
+    let sections: [Section; 2] = [
+        ("Visitors", &VISITOR_COLUMNS, &visitor_rows),
+        ("Early-leave gate passes", &PASS_COLUMNS, &pass_rows),
+    ];
+    for (title, columns, rows) in sections {
+        if y < 100.0 {
+            page = doc.add_page();
+            y = draw_header(page, school.as_ref(), &register);
+        }
+        y = draw_section(page, y, title, columns);
+        if rows.is_empty() {
+            page.text(42.0, y, 8.5, Font::Regular, "None");
+            y -= 14.0;
+        }
+        for row in rows {
+            if y < 50.0 {
+                page = doc.add_page();
+                y = draw_header(page, school.as_ref(), &register);
+                y = draw_section(page, y, &format!("{} (continued)", title), columns);
+            }
+            let mut x = 40.0;
+            for ((_, width), cell) in columns.iter().zip(row.iter()) {
+                page.text(x + 2.0, y, 8.5, Font::Regular, cell);
+                x += width;
+            }
+            y -= 14.0;
+        }
+        y -= 16.0;
+    }
+    Ok((
+        format!("Gate-Register-{}.pdf", register.date),
+        doc.to_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guardian::Guardian;
+    use crate::lifecycle::StatusChange;
+    use crate::service::guardian::{create_guardian, link_guardian};
+    use crate::service::lifecycle::change_status;
+    use crate::service::staff::create_staff;
+    use crate::service::students::save_core;
+    use crate::test_support::{
+        create_class, memory_db, sample_guardian, sample_staff, sample_student,
+    };
+
+    fn visitor(name: &str, person_to_meet: &str, in_time: &str) -> Visitor {
+        Visitor {
+            id: None,
+            visitor_name: name.to_string(),
+            phone: Some("9822011111".to_string()),
+            purpose: "Admission enquiry".to_string(),
+            person_to_meet: person_to_meet.to_string(),
+            staff_id: None,
+            visited_on: Some("2024-08-12".to_string()),
+            in_time: Some(in_time.to_string()),
+            out_time: None,
+            photo: None,
+            remarks: None,
+        }
+    }
+
+    fn pass(student_id: i64, guardian_id: i64) -> GatePass {
+        GatePass {
+            id: None,
+            student_id,
+            guardian_id,
+            issued_on: Some("2024-08-12".to_string()),
+            out_time: Some("11:30".to_string()),
+            reason: "Doctor's appointment".to_string(),
+            collected_by: None,
+            approved_by: Some("Class teacher".to_string()),
+            student_name: None,
+            class_name: None,
+            guardian_name: None,
+            guardian_relation: None,
+            guardian_phone: None,
+        }
+    }
+
+    #[test]
+    fn visitors_are_logged_in_and_out_and_searchable() {
+        let conn = memory_db();
+        let principal = create_staff(&conn, sample_staff("Meera Joshi")).unwrap();
+        let first = record_visitor(
+            &conn,
+            Visitor {
+                staff_id: Some(principal),
+                photo: Some("visitor_1.jpg".to_string()),
+                ..visitor("Anil Kapoor", "", "09:40")
+            },
+        )
+        .unwrap();
+        record_visitor(&conn, visitor("Courier", "Office", "10:15")).unwrap();
+        assert!(record_visitor(&conn, visitor("Nobody", "", "10:20")).is_err());
+
+        assert!(record_exit(&conn, first, Some("09:00".to_string())).is_err());
+        record_exit(&conn, first, Some("10:05".to_string())).unwrap();
+        assert!(record_exit(&conn, first, None)
+            .unwrap_err()
+            .contains("already left"));
+
+        let found = search_visitors(&conn, Some("meera".to_string()), None, None).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].photo.as_deref(), Some("visitor_1.jpg"));
+        assert!(
+            search_visitors(&conn, None, Some("2024-08-13".to_string()), None)
+                .unwrap()
+                .is_empty()
+        );
+
+        let register = get_daily_register(&conn, Some("2024-08-12".to_string())).unwrap();
+        assert_eq!((register.visitors.len(), register.still_inside), (2, 1));
+        let (file_name, pdf) =
+            render_daily_register_pdf(&conn, Some("2024-08-12".to_string())).unwrap();
+        assert_eq!(file_name, "Gate-Register-2024-08-12.pdf");
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn gate_passes_need_a_guardian_on_record() {
+        let conn = memory_db();
+        let class_id = create_class(&conn, "Class-3 B");
+        let student = save_core(&conn, sample_student(&class_id.to_string(), "G1").core).unwrap();
+        let mother = create_guardian(&conn, sample_guardian("Kavita Rao", "Mother")).unwrap();
+        let stranger = create_guardian(
+            &conn,
+            Guardian {
+                phone: Some("9811100000".to_string()),
+                ..sample_guardian("Someone Else", "Other")
+            },
+        )
+        .unwrap();
+        link_guardian(&conn, student, mother, true).unwrap();
+
+        assert_eq!(
+            issue_gate_pass(&conn, pass(student, stranger)).unwrap_err(),
+            "Someone Else is not a guardian on this student's record"
+        );
+        let issued = issue_gate_pass(&conn, pass(student, mother)).unwrap();
+        assert_eq!(issued.collected_by.as_deref(), Some("Kavita Rao"));
+        assert_eq!(issued.guardian_relation.as_deref(), Some("mother"));
+        assert!(issue_gate_pass(&conn, pass(student, mother))
+            .unwrap_err()
+            .contains("already issued"));
+        let register = get_daily_register(&conn, Some("2024-08-12".to_string())).unwrap();
+        assert_eq!(register.gate_passes.len(), 1);
+
+        for status in ["active", "left"] {
+            let change = StatusChange {
+                status: status.to_string(),
+                effective_date: None,
+                reason: Some("Transfer".to_string()),
+                passing_year: None,
+            };
+            change_status(&conn, student, change).unwrap();
+        }
+        let next_day = GatePass {
+            issued_on: Some("2024-08-13".to_string()),
+            ..pass(student, mother)
+        };
+        assert!(issue_gate_pass(&conn, next_day)
+            .unwrap_err()
+            .contains("on the roll"));
+    }
+}
@@ -1,5 +1,7 @@
 // Staff related structs and implementations
+use crate::migration::add_column_if_missing;
 use crate::DbState;
+use crate::service::staff as service;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use tauri::State;
@@ -49,148 +51,42 @@ pub fn init_staff_table(conn: &Connection) -> rusqlite::Result<()> {
         )",
         [],
     )?;
+    // Statutory details for UDISE+ teacher profiles; see the statutory module
+    add_column_if_missing(conn, "staff", "social_category", "TEXT")?;
+    add_column_if_missing(conn, "staff", "aadhaar_number", "TEXT")?;
+    add_column_if_missing(conn, "staff", "disability", "TEXT")?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn create_staff(state: State<'_, DbState>, staff: Staff) -> Result<i64, String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute(
-        "INSERT INTO staff (
-            name, gender, dob, phone, alt_phone, email, qualification,
-            designation, department, joining_date, employment_type, photo_url, status
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-        (
-            &staff.name,
-            &staff.gender,
-            &staff.dob,
-            &staff.phone,
-            &staff.alt_phone,
-            &staff.email,
-            &staff.qualification,
-            &staff.designation,
-            &staff.department,
-            &staff.joining_date,
-            &staff.employment_type,
-            &staff.photo_url,
-            &staff.status.unwrap_or_else(|| "active".to_string()),
-        ),
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(conn.last_insert_rowid())
+    state
+        .write(move |conn| service::create_staff(conn, staff))
+        .await
 }
 
 #[tauri::command]
 pub async fn get_staff(state: State<'_, DbState>, id: i64) -> Result<Staff, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, gender, dob, phone, alt_phone, email, qualification,
-         designation, department, joining_date, employment_type, photo_url, status, created_at
-         FROM staff WHERE id = ?1",
-        )
-        .map_err(|e| e.to_string())?;
-
-    let staff = stmt
-        .query_row([id], |row| {
-            Ok(Staff {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                gender: row.get(2)?,
-                dob: row.get(3)?,
-                phone: row.get(4)?,
-                alt_phone: row.get(5)?,
-                email: row.get(6)?,
-                qualification: row.get(7)?,
-                designation: row.get(8)?,
-                department: row.get(9)?,
-                joining_date: row.get(10)?,
-                employment_type: row.get(11)?,
-                photo_url: row.get(12)?,
-                status: row.get(13)?,
-                created_at: row.get(14)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    Ok(staff)
+    state
+        .read(move |conn| service::get_staff(conn, id))
+        .await
 }
 
 #[tauri::command]
 pub async fn get_all_staffs(state: State<'_, DbState>) -> Result<Vec<Staff>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, gender, dob, phone, alt_phone, email, qualification,
-         designation, department, joining_date, employment_type, photo_url, status, created_at
-         FROM staff ORDER BY created_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
-
-    let staffs = stmt
-        .query_map([], |row| {
-            Ok(Staff {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                gender: row.get(2)?,
-                dob: row.get(3)?,
-                phone: row.get(4)?,
-                alt_phone: row.get(5)?,
-                email: row.get(6)?,
-                qualification: row.get(7)?,
-                designation: row.get(8)?,
-                department: row.get(9)?,
-                joining_date: row.get(10)?,
-                employment_type: row.get(11)?,
-                photo_url: row.get(12)?,
-                status: row.get(13)?,
-                created_at: row.get(14)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
-
-    Ok(staffs)
+    state.read(service::get_all_staffs).await
 }
 
 #[tauri::command]
 pub async fn update_staff(state: State<'_, DbState>, id: i64, staff: Staff) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute(
-        "UPDATE staff SET 
-            name = ?1, gender = ?2, dob = ?3, phone = ?4, alt_phone = ?5, 
-            email = ?6, qualification = ?7, designation = ?8, department = ?9, 
-            joining_date = ?10, employment_type = ?11, photo_url = ?12, status = ?13
-         WHERE id = ?14",
-        (
-            &staff.name,
-            &staff.gender,
-            &staff.dob,
-            &staff.phone,
-            &staff.alt_phone,
-            &staff.email,
-            &staff.qualification,
-            &staff.designation,
-            &staff.department,
-            &staff.joining_date,
-            &staff.employment_type,
-            &staff.photo_url,
-            &staff.status.unwrap_or_else(|| "active".to_string()),
-            id,
-        ),
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+    state
+        .write(move |conn| service::update_staff(conn, id, staff))
+        .await
 }
 
 #[tauri::command]
 pub async fn delete_staff(state: State<'_, DbState>, id: i64) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute("DELETE FROM staff WHERE id = ?1", [id])
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    state
+        .write(move |conn| service::delete_staff(conn, id))
+        .await
 }
@@ -0,0 +1,141 @@
+// Daily staff attendance with check-in/check-out times and the monthly muster roll
+use crate::service::staff_attendance as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StaffAttendance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub staff_id: i64,
+    pub date: String,
+    /// `HH:MM`
+    pub check_in: Option<String>,
+    /// `HH:MM`
+    pub check_out: Option<String>,
+    /// present, half_day or absent.
+    pub status: String,
+    pub remarks: Option<String>,
+}
+
+/// One line of the daily register: every active staff member, marked or not.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StaffDayRecord {
+    pub staff_id: i64,
+    pub name: String,
+    pub designation: String,
+    pub department: String,
+    pub attendance: Option<StaffAttendance>,
+    /// Short code of approved leave covering the day, e.g. `CL`.
+    pub on_leave: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MusterRollRow {
+    pub staff_id: i64,
+    pub name: String,
+    pub designation: String,
+    pub department: String,
+    pub employment_type: String,
+    /// One mark per day of the month: P, A, HD, a leave code such as CL (CL/2 for half a
+    /// day), WO for a weekly off, H for a holiday, or blank when nothing is recorded.
+    pub marks: Vec<String>,
+    pub present: f64,
+    pub absent: f64,
+    pub paid_leave: f64,
+    pub unpaid_leave: f64,
+    pub weekly_offs: u32,
+    /// Full-day holidays on the academic calendar.
+    pub holidays: u32,
+    /// Past working days with neither attendance nor leave recorded.
+    pub unmarked: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MusterRoll {
+    pub year: i32,
+    pub month: u32,
+    pub dates: Vec<String>,
+    pub rows: Vec<MusterRollRow>,
+}
+
+pub fn init_staff_attendance_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS staff_attendance (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            staff_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            check_in TEXT,
+            check_out TEXT,
+            status TEXT NOT NULL DEFAULT 'present',
+            remarks TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (staff_id, date),
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_staff_attendance_date ON staff_attendance(date)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Records arrival today; `time` defaults to now.
+#[tauri::command]
+pub async fn staff_check_in(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    time: Option<String>,
+) -> Result<StaffAttendance, String> {
+    state
+        .write(move |conn| service::check_in(conn, staff_id, time))
+        .await
+}
+
+/// Records departure today; `time` defaults to now.
+#[tauri::command]
+pub async fn staff_check_out(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    time: Option<String>,
+) -> Result<StaffAttendance, String> {
+    state
+        .write(move |conn| service::check_out(conn, staff_id, time))
+        .await
+}
+
+/// Marks or corrects attendance for any day.
+#[tauri::command]
+pub async fn mark_staff_attendance(
+    state: State<'_, DbState>,
+    attendance: StaffAttendance,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::mark_attendance(conn, attendance))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_staff_attendance_register(
+    state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<Vec<StaffDayRecord>, String> {
+    state
+        .read(move |conn| service::get_register(conn, date))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_staff_muster_roll(
+    state: State<'_, DbState>,
+    year: i32,
+    month: u32,
+) -> Result<MusterRoll, String> {
+    state
+        .read(move |conn| service::get_muster_roll(conn, year, month))
+        .await
+}
@@ -0,0 +1,223 @@
+// Statutory returns: UDISE+ enrolment and teacher profiles, and board registration lists
+use crate::service::statutory as service;
+use crate::DbState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_statutory_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Statutory Returns");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create returns dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Fields statutory returns need that the admission and staff forms do not collect.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatutoryDetails {
+    /// general, sc, st or obc.
+    pub social_category: Option<String>,
+    /// 12 digits, stored without spaces.
+    pub aadhaar_number: Option<String>,
+    /// `none`, or one of the disability types UDISE+ records.
+    pub disability: Option<String>,
+}
+
+/// Students on the roll in one grade and social category.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrolmentRow {
+    /// Grade without the section, e.g. "Class-6".
+    pub grade: String,
+    /// general, sc, st, obc or not_recorded.
+    pub social_category: String,
+    pub boys: u32,
+    pub girls: u32,
+    pub transgender: u32,
+    pub total: u32,
+    /// Children with special needs: a disability other than `none`.
+    pub with_disability: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TeacherProfile {
+    pub staff_id: i64,
+    pub name: String,
+    pub gender: String,
+    pub dob: String,
+    pub social_category: Option<String>,
+    pub aadhaar_number: Option<String>,
+    pub disability: Option<String>,
+    pub qualification: String,
+    pub designation: String,
+    /// Nature of appointment, e.g. permanent or contract.
+    pub employment_type: String,
+    pub joining_date: String,
+    pub subjects: Vec<String>,
+    /// Grades taught, in grade order.
+    pub classes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardCandidate {
+    pub student_id: i64,
+    pub gr_number: String,
+    pub roll_number: Option<String>,
+    pub full_name: String,
+    pub father_name: String,
+    pub mother_name: String,
+    pub dob: Option<String>,
+    pub gender: String,
+    pub social_category: Option<String>,
+    pub aadhaar_number: Option<String>,
+    pub disability: Option<String>,
+    pub class_name: String,
+    /// Subjects mapped to the candidate's class.
+    pub subjects: Vec<String>,
+}
+
+/// A record an export would send with required fields missing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// school, student or teacher.
+    pub record_type: String,
+    pub record_id: Option<i64>,
+    pub name: String,
+    pub class_name: Option<String>,
+    /// Labels of the missing fields, e.g. "Aadhaar".
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub export: String,
+    /// Records the export would include, the school profile among them.
+    pub checked: u32,
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[tauri::command]
+pub async fn get_student_statutory_details(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<StatutoryDetails, String> {
+    state
+        .read(move |conn| service::get_student_details(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn save_student_statutory_details(
+    state: State<'_, DbState>,
+    student_id: i64,
+    details: StatutoryDetails,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_student_details(conn, student_id, details))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_staff_statutory_details(
+    state: State<'_, DbState>,
+    staff_id: i64,
+) -> Result<StatutoryDetails, String> {
+    state
+        .read(move |conn| service::get_staff_details(conn, staff_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn save_staff_statutory_details(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    details: StatutoryDetails,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_staff_details(conn, staff_id, details))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_udise_enrolment(state: State<'_, DbState>) -> Result<Vec<EnrolmentRow>, String> {
+    state.read(service::get_enrolment).await
+}
+
+#[tauri::command]
+pub async fn get_udise_teacher_profiles(
+    state: State<'_, DbState>,
+) -> Result<Vec<TeacherProfile>, String> {
+    state.read(service::get_teacher_profiles).await
+}
+
+#[tauri::command]
+pub async fn get_board_candidates(
+    state: State<'_, DbState>,
+    class_ids: Vec<i64>,
+) -> Result<Vec<BoardCandidate>, String> {
+    state
+        .read(move |conn| service::get_board_candidates(conn, &class_ids))
+        .await
+}
+
+/// Required fields missing for `export` (udise_enrolment, udise_teachers or
+/// board_candidates); `class_ids` picks the board classes.
+#[tauri::command]
+pub async fn validate_statutory_export(
+    state: State<'_, DbState>,
+    export: String,
+    class_ids: Option<Vec<i64>>,
+) -> Result<ValidationReport, String> {
+    state
+        .read(move |conn| service::validate(conn, &export, &class_ids.unwrap_or_default()))
+        .await
+}
+
+/// Writes the validation report as csv or xlsx and returns its path.
+#[tauri::command]
+pub async fn export_statutory_validation(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    export: String,
+    class_ids: Option<Vec<i64>>,
+    format: String,
+) -> Result<String, String> {
+    let (file_name, bytes) = state
+        .read(move |conn| {
+            service::render_validation(conn, &export, &class_ids.unwrap_or_default(), &format)
+        })
+        .await?;
+    let path = ensure_statutory_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Writes a return as csv or xlsx and returns its path. Refused while the validation report
+/// lists anything, unless `allow_incomplete` is set.
+#[tauri::command]
+pub async fn export_statutory_return(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    export: String,
+    class_ids: Option<Vec<i64>>,
+    format: String,
+    allow_incomplete: Option<bool>,
+) -> Result<String, String> {
+    let (file_name, bytes) = state
+        .read(move |conn| {
+            service::render_export(
+                conn,
+                &export,
+                &class_ids.unwrap_or_default(),
+                &format,
+                allow_incomplete.unwrap_or(false),
+            )
+        })
+        .await?;
+    let path = ensure_statutory_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,264 @@
+// Subjects catalogue, class-subject mapping and teacher assignments
+use crate::service::subject as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Subject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Short unique code, e.g. `MATH` or `PHY-PR`.
+    pub code: String,
+    pub name: String,
+    /// theory, practical or elective.
+    pub subject_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// A subject taught in a class, with its weekly periods and subject teacher.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClassSubject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub class_id: i64,
+    pub subject_id: i64,
+    pub periods_per_week: u32,
+    /// Staff id of the subject teacher.
+    pub teacher_id: Option<i64>,
+    /// Taken from the class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub academic_year_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teacher_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClassTeacher {
+    pub class_id: i64,
+    pub class_name: String,
+    pub staff_id: i64,
+    pub staff_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkloadItem {
+    pub class_subject_id: i64,
+    pub class_name: String,
+    pub subject_name: String,
+    pub periods_per_week: u32,
+}
+
+/// Teaching load of one teacher in an academic year.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TeacherWorkload {
+    pub staff_id: i64,
+    pub name: String,
+    pub department: String,
+    pub total_periods: u32,
+    pub max_periods: u32,
+    /// True when `total_periods` is above `max_periods`.
+    pub over_assigned: bool,
+    pub class_teacher_of: Option<String>,
+    pub assignments: Vec<WorkloadItem>,
+}
+
+pub fn init_subject_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS subjects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            code TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            name TEXT NOT NULL,
+            subject_type TEXT NOT NULL DEFAULT 'theory',
+            status TEXT NOT NULL DEFAULT 'active',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS class_subjects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            class_id INTEGER NOT NULL,
+            subject_id INTEGER NOT NULL,
+            academic_year_id INTEGER NOT NULL,
+            periods_per_week INTEGER NOT NULL DEFAULT 0,
+            teacher_id INTEGER,
+            UNIQUE (class_id, subject_id),
+            FOREIGN KEY (class_id) REFERENCES classes(id) ON DELETE CASCADE,
+            FOREIGN KEY (subject_id) REFERENCES subjects(id),
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id),
+            FOREIGN KEY (teacher_id) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_class_subjects_teacher
+         ON class_subjects(teacher_id, academic_year_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS class_teachers (
+            class_id INTEGER PRIMARY KEY,
+            staff_id INTEGER NOT NULL,
+            assigned_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (class_id) REFERENCES classes(id) ON DELETE CASCADE,
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // Per-teacher override of the default weekly period limit
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS teacher_period_limits (
+            staff_id INTEGER PRIMARY KEY,
+            max_periods_per_week INTEGER NOT NULL,
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_subject(state: State<'_, DbState>, subject: Subject) -> Result<i64, String> {
+    state
+        .write(move |conn| service::create_subject(conn, subject))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_subjects(
+    state: State<'_, DbState>,
+    include_inactive: Option<bool>,
+) -> Result<Vec<Subject>, String> {
+    state
+        .read(move |conn| service::get_subjects(conn, include_inactive.unwrap_or(false)))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_subject(
+    state: State<'_, DbState>,
+    id: i64,
+    subject: Subject,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_subject(conn, id, subject))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_subject(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_subject(conn, id))
+        .await
+}
+
+/// Adds the subject to the class, or updates its periods and teacher if already mapped.
+#[tauri::command]
+pub async fn assign_class_subject(
+    state: State<'_, DbState>,
+    assignment: ClassSubject,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::assign_class_subject(conn, assignment))
+        .await
+}
+
+#[tauri::command]
+pub async fn remove_class_subject(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::remove_class_subject(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_class_subjects(
+    state: State<'_, DbState>,
+    class_id: i64,
+) -> Result<Vec<ClassSubject>, String> {
+    state
+        .read(move |conn| service::get_class_subjects(conn, class_id))
+        .await
+}
+
+/// Sets or clears the subject teacher and returns the teacher's resulting workload, so an
+/// over-assignment can be shown straight away.
+#[tauri::command]
+pub async fn set_subject_teacher(
+    state: State<'_, DbState>,
+    class_subject_id: i64,
+    teacher_id: Option<i64>,
+) -> Result<Option<TeacherWorkload>, String> {
+    state
+        .write(move |conn| service::set_subject_teacher(conn, class_subject_id, teacher_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn set_class_teacher(
+    state: State<'_, DbState>,
+    class_id: i64,
+    staff_id: Option<i64>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::set_class_teacher(conn, class_id, staff_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_class_teachers(
+    state: State<'_, DbState>,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<ClassTeacher>, String> {
+    state
+        .read(move |conn| service::get_class_teachers(conn, academic_year_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn set_teacher_max_periods(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    max_periods_per_week: Option<u32>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::set_teacher_max_periods(conn, staff_id, max_periods_per_week))
+        .await
+}
+
+/// Periods per week for every teacher with assignments; defaults to the current academic year.
+#[tauri::command]
+pub async fn get_teacher_workload(
+    state: State<'_, DbState>,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TeacherWorkload>, String> {
+    state
+        .read(move |conn| service::get_teacher_workload(conn, academic_year_id))
+        .await
+}
+
+/// Copies subject mappings (without teachers) to the classes of the same name in another year.
+#[tauri::command]
+pub async fn copy_class_subjects(
+    state: State<'_, DbState>,
+    from_academic_year_id: i64,
+    to_academic_year_id: i64,
+) -> Result<usize, String> {
+    state
+        .write(move |conn| {
+            service::copy_class_subjects(conn, from_academic_year_id, to_academic_year_id)
+        })
+        .await
+}
@@ -0,0 +1,160 @@
+// In-memory database harness and fixtures shared by the service tests.
+use crate::enquiry::Enquiry;
+use crate::guardian::Guardian;
+use crate::migration::run_migrations;
+use crate::school::School;
+use crate::staff::Staff;
+use crate::students::{Student, StudentContact, StudentCore, StudentDocs, StudentHealth};
+use rusqlite::{params, Connection};
+
+/// Id of the "2024 - 2025" year the migrations seed as active.
+pub const DEFAULT_ACADEMIC_YEAR_ID: i64 = 1;
+
+/// Fresh in-memory database with every migration applied.
+pub fn memory_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("open in-memory database");
+    run_migrations(&conn).expect("run migrations");
+    conn
+}
+
+/// Inserts an active class in the default academic year and returns its id.
+pub fn create_class(conn: &Connection, class_name: &str) -> i64 {
+    conn.execute(
+        "INSERT INTO classes (class_name, academic_years, status) VALUES (?1, ?2, 'active')",
+        params![class_name, DEFAULT_ACADEMIC_YEAR_ID],
+    )
+    .expect("insert class");
+    conn.last_insert_rowid()
+}
+
+pub fn sample_student(class_id: &str, gr_number: &str) -> Student {
+    Student {
+        core: StudentCore {
+            id: None,
+            gr_number: gr_number.to_string(),
+            roll_number: Some("1".to_string()),
+            full_name: format!("Student {}", gr_number),
+            dob: Some("2015-04-12".to_string()),
+            gender: "Female".to_string(),
+            mother_name: "Sunita".to_string(),
+            father_name: "Rajesh".to_string(),
+            father_occupation: None,
+            mother_occupation: None,
+            annual_income: None,
+            nationality: Some("Indian".to_string()),
+            profile_image: None,
+            class_id: class_id.to_string(),
+            section: None,
+            academic_year: Some("2024 - 2025".to_string()),
+            class_name: None,
+        },
+        contact: StudentContact {
+            email: None,
+            mobile_number: Some("9876543210".to_string()),
+            alternate_contact_number: None,
+            address: None,
+            city: None,
+            state: None,
+            country: None,
+            postal_code: None,
+            guardian_contact_info: None,
+        },
+        health: StudentHealth {
+            blood_group: None,
+            status: Some("active".to_string()),
+            admission_date: Some("2024-06-10".to_string()),
+            weight_kg: None,
+            height_cm: None,
+            hb_range: None,
+            medical_conditions: None,
+            emergency_contact_person: None,
+            emergency_contact: None,
+        },
+        docs: StudentDocs {
+            birth_certificate: None,
+            transfer_certificate: None,
+            previous_academic_records: None,
+            address_proof: None,
+            id_proof: None,
+            passport_photo: None,
+            medical_certificate: None,
+            vaccination_certificate: None,
+            other_documents: None,
+        },
+    }
+}
+
+pub fn sample_enquiry(student_name: &str) -> Enquiry {
+    Enquiry {
+        id: None,
+        student_name: student_name.to_string(),
+        parent_name: "Parent".to_string(),
+        phone: "9876543210".to_string(),
+        email: None,
+        source: "walk-in".to_string(),
+        status: None,
+        created_at: None,
+    }
+}
+
+pub fn sample_guardian(full_name: &str, relation_type: &str) -> Guardian {
+    Guardian {
+        id: None,
+        full_name: full_name.to_string(),
+        relation_type: relation_type.to_string(),
+        phone: Some("9876543210".to_string()),
+        alternate_phone: None,
+        email: None,
+        address: None,
+        occupation: None,
+        annual_income: None,
+        aadhaar_number: None,
+        pan_number: None,
+        notes: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+pub fn sample_staff(name: &str) -> Staff {
+    Staff {
+        id: None,
+        name: name.to_string(),
+        gender: "Female".to_string(),
+        dob: "1988-01-20".to_string(),
+        phone: "9876500000".to_string(),
+        alt_phone: None,
+        email: format!("{}@school.test", name.to_lowercase().replace(' ', ".")),
+        qualification: "B.Ed".to_string(),
+        designation: "Teacher".to_string(),
+        department: "Science".to_string(),
+        joining_date: "2020-06-01".to_string(),
+        employment_type: "permanent".to_string(),
+        photo_url: None,
+        status: None,
+        created_at: None,
+    }
+}
+
+pub fn sample_school() -> School {
+    School {
+        id: None,
+        school_name: "Green Valley School".to_string(),
+        school_board: "CBSE".to_string(),
+        school_medium: "English".to_string(),
+        principal_name: "Dr. Rao".to_string(),
+        contact_number: "02012345678".to_string(),
+        alternate_contact_number: None,
+        school_email: "office@greenvalley.test".to_string(),
+        address: "12 Hill Road".to_string(),
+        city: "Pune".to_string(),
+        state: "Maharashtra".to_string(),
+        pincode: "411001".to_string(),
+        website: None,
+        school_image: None,
+        udise_code: Some("27251234567".to_string()),
+        affiliation_number: Some("1130456".to_string()),
+        created_at: None,
+        updated_at: None,
+    }
+}
@@ -0,0 +1,407 @@
+// Weekly timetables: bell schedule, rooms, class periods, generation and substitutions
+use crate::service::timetable as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_timetable_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Timetable");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create timetable dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimetableSettings {
+    /// Teaching days from Monday, e.g. 6 for Monday to Saturday.
+    pub working_days: u32,
+    /// Most periods a teacher may take in a day.
+    pub max_periods_per_day: u32,
+}
+
+/// One row of the bell schedule. Rows are kept in the order they were saved.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BellSlot {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub label: String,
+    /// period or break.
+    pub kind: String,
+    /// `HH:MM`
+    pub start_time: String,
+    /// `HH:MM`
+    pub end_time: String,
+    /// 1-based number among the periods of the day; `None` for breaks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_no: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Room {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    pub capacity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// A class subject placed in a day and period. The teacher is the subject teacher of the
+/// class subject.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimetableEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub class_id: i64,
+    /// 1 for Monday through 6 for Saturday.
+    pub day: u32,
+    pub period_no: u32,
+    pub class_subject_id: i64,
+    pub room_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub academic_year_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teacher_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teacher_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimetableClash {
+    /// teacher, room or daily_limit.
+    pub kind: String,
+    pub day: u32,
+    /// `None` for a daily limit breach, which spans the day.
+    pub period_no: Option<u32>,
+    /// Teacher or room name.
+    pub name: String,
+    pub classes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnplacedPeriods {
+    pub class_name: String,
+    pub subject_name: String,
+    pub teacher_name: Option<String>,
+    pub missing: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerationOutcome {
+    pub placed: u32,
+    /// Periods the generator could not fit without a clash or breaking a limit.
+    pub unplaced: Vec<UnplacedPeriods>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubstituteCandidate {
+    pub staff_id: i64,
+    pub name: String,
+    /// Periods already taken that day, substitutions included.
+    pub periods_that_day: u32,
+    /// Teaches the same subject in some class.
+    pub teaches_subject: bool,
+}
+
+/// A period left uncovered by an absent teacher on a date, with free teachers to cover it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubstitutionNeed {
+    pub entry: TimetableEntry,
+    pub absent_teacher_id: i64,
+    pub absent_teacher_name: String,
+    pub substitute_id: Option<i64>,
+    pub substitute_name: Option<String>,
+    pub suggestions: Vec<SubstituteCandidate>,
+}
+
+pub fn init_timetable_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timetable_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            working_days INTEGER NOT NULL DEFAULT 6,
+            max_periods_per_day INTEGER NOT NULL DEFAULT 6
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO timetable_settings (id) VALUES (1)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bell_schedule (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            position INTEGER NOT NULL UNIQUE,
+            label TEXT NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'period',
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS rooms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            capacity INTEGER,
+            status TEXT NOT NULL DEFAULT 'active'
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timetable_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            academic_year_id INTEGER NOT NULL,
+            class_id INTEGER NOT NULL,
+            day INTEGER NOT NULL,
+            period_no INTEGER NOT NULL,
+            class_subject_id INTEGER NOT NULL,
+            room_id INTEGER,
+            UNIQUE (class_id, day, period_no),
+            FOREIGN KEY (academic_year_id) REFERENCES academic_years(id),
+            FOREIGN KEY (class_id) REFERENCES classes(id) ON DELETE CASCADE,
+            FOREIGN KEY (class_subject_id) REFERENCES class_subjects(id) ON DELETE CASCADE,
+            FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_timetable_entries_slot
+         ON timetable_entries(academic_year_id, day, period_no)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timetable_substitutions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            entry_id INTEGER NOT NULL,
+            substitute_id INTEGER NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (date, entry_id),
+            FOREIGN KEY (entry_id) REFERENCES timetable_entries(id) ON DELETE CASCADE,
+            FOREIGN KEY (substitute_id) REFERENCES staff(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_timetable_settings(
+    state: State<'_, DbState>,
+) -> Result<TimetableSettings, String> {
+    state.read(service::get_settings).await
+}
+
+#[tauri::command]
+pub async fn save_timetable_settings(
+    state: State<'_, DbState>,
+    settings: TimetableSettings,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::save_settings(conn, settings))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_bell_schedule(state: State<'_, DbState>) -> Result<Vec<BellSlot>, String> {
+    state.read(service::get_bell_schedule).await
+}
+
+/// Replaces the whole bell schedule with `slots`, in the given order.
+#[tauri::command]
+pub async fn save_bell_schedule(
+    state: State<'_, DbState>,
+    slots: Vec<BellSlot>,
+) -> Result<Vec<BellSlot>, String> {
+    state
+        .write(move |conn| service::save_bell_schedule(conn, slots))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_rooms(
+    state: State<'_, DbState>,
+    include_inactive: Option<bool>,
+) -> Result<Vec<Room>, String> {
+    state
+        .read(move |conn| service::get_rooms(conn, include_inactive.unwrap_or(false)))
+        .await
+}
+
+#[tauri::command]
+pub async fn create_room(state: State<'_, DbState>, room: Room) -> Result<i64, String> {
+    state
+        .write(move |conn| service::create_room(conn, room))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_room(state: State<'_, DbState>, id: i64, room: Room) -> Result<(), String> {
+    state
+        .write(move |conn| service::update_room(conn, id, room))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_room(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_room(conn, id))
+        .await
+}
+
+/// Places a subject in a class period, replacing whatever was there. Rejects teacher and
+/// room clashes and periods beyond the teacher's daily limit.
+#[tauri::command]
+pub async fn set_timetable_entry(
+    state: State<'_, DbState>,
+    entry: TimetableEntry,
+) -> Result<i64, String> {
+    state
+        .write(move |conn| service::set_entry(conn, entry))
+        .await
+}
+
+#[tauri::command]
+pub async fn clear_timetable_entry(
+    state: State<'_, DbState>,
+    class_id: i64,
+    day: u32,
+    period_no: u32,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::clear_entry(conn, class_id, day, period_no))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_class_timetable(
+    state: State<'_, DbState>,
+    class_id: i64,
+) -> Result<Vec<TimetableEntry>, String> {
+    state
+        .read(move |conn| service::get_class_timetable(conn, class_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_teacher_timetable(
+    state: State<'_, DbState>,
+    staff_id: i64,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TimetableEntry>, String> {
+    state
+        .read(move |conn| service::get_teacher_timetable(conn, staff_id, academic_year_id))
+        .await
+}
+
+/// Teacher and room double bookings and daily limit breaches, e.g. after a subject teacher
+/// was changed; defaults to the current academic year.
+#[tauri::command]
+pub async fn get_timetable_clashes(
+    state: State<'_, DbState>,
+    academic_year_id: Option<i64>,
+) -> Result<Vec<TimetableClash>, String> {
+    state
+        .read(move |conn| service::find_clashes(conn, academic_year_id))
+        .await
+}
+
+/// Fills the timetable of one class, or every class of the year, from the periods per week
+/// of its subjects. With `keep_existing` the periods already placed stay and only the
+/// shortfall is filled.
+#[tauri::command]
+pub async fn generate_timetable(
+    state: State<'_, DbState>,
+    academic_year_id: Option<i64>,
+    class_id: Option<i64>,
+    keep_existing: Option<bool>,
+) -> Result<GenerationOutcome, String> {
+    state
+        .write(move |conn| {
+            service::generate(
+                conn,
+                academic_year_id,
+                class_id,
+                keep_existing.unwrap_or(false),
+            )
+        })
+        .await
+}
+
+/// Writes the timetable of one class, or of every class a page each, and returns its path.
+#[tauri::command]
+pub async fn export_class_timetable_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    class_id: Option<i64>,
+    academic_year_id: Option<i64>,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_class_timetable_pdf(conn, class_id, academic_year_id))
+        .await?;
+    write_timetable_file(&app_handle, &file_name, &pdf)
+}
+
+/// Writes the timetable of one teacher, or of every teacher a page each, and returns its path.
+#[tauri::command]
+pub async fn export_teacher_timetable_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    staff_id: Option<i64>,
+    academic_year_id: Option<i64>,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_teacher_timetable_pdf(conn, staff_id, academic_year_id))
+        .await?;
+    write_timetable_file(&app_handle, &file_name, &pdf)
+}
+
+/// Periods of teachers absent or on leave on `date`, with free teachers to cover each.
+#[tauri::command]
+pub async fn get_substitution_suggestions(
+    state: State<'_, DbState>,
+    date: String,
+) -> Result<Vec<SubstitutionNeed>, String> {
+    state
+        .read(move |conn| service::substitution_suggestions(conn, &date))
+        .await
+}
+
+/// Records (or with `None`, removes) the teacher covering a period on `date`.
+#[tauri::command]
+pub async fn assign_substitute(
+    state: State<'_, DbState>,
+    date: String,
+    entry_id: i64,
+    substitute_id: Option<i64>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::assign_substitute(conn, &date, entry_id, substitute_id))
+        .await
+}
+
+fn write_timetable_file(
+    app_handle: &AppHandle,
+    file_name: &str,
+    contents: &[u8],
+) -> Result<String, String> {
+    let path = ensure_timetable_dir(app_handle)?.join(file_name);
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
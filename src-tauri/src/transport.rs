@@ -0,0 +1,342 @@
+// Transport: vehicles, routes with ordered stops, distance-based fares and student allocation
+use crate::service::transport as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_transport_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Transport");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create transport dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Vehicle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub registration_number: String,
+    /// Seats available to students.
+    pub capacity: u32,
+    /// bus or van, for instance.
+    pub vehicle_type: Option<String>,
+    pub fitness_expiry: Option<String>,
+    pub insurance_expiry: Option<String>,
+    /// Staff id of the driver.
+    pub driver_id: Option<i64>,
+    /// active or retired; defaults to active.
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver_phone: Option<String>,
+}
+
+/// A stop on a route. Stops are kept in the order they were saved.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RouteStop {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    /// HH:MM, morning pickup.
+    pub pickup_time: String,
+    /// HH:MM, afternoon drop, when the bus runs back the same way.
+    pub drop_time: Option<String>,
+    /// Distance from school, which decides the fare.
+    pub distance_km: f64,
+    /// Students currently assigned to the stop.
+    #[serde(default)]
+    pub students: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransportRoute {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub name: String,
+    pub vehicle_id: Option<i64>,
+    #[serde(default)]
+    pub stops: Vec<RouteStop>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<u32>,
+    /// Students on the roll assigned to any stop of the route.
+    #[serde(default)]
+    pub students: u32,
+}
+
+/// Fare for stops from `from_km` up to, but not including, `to_km`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FareSlab {
+    pub from_km: f64,
+    pub to_km: f64,
+    /// Charged each time transport fees are raised.
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransportAllocation {
+    pub student_id: i64,
+    pub stop_id: i64,
+    /// Defaults to today.
+    pub allocated_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roll_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mobile_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pickup_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+    /// Fare of the slab the stop falls in; `None` when no slab covers it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fare: Option<f64>,
+    /// Set when the allocation takes the route past its vehicle's capacity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity_warning: Option<String>,
+    /// Allergies and chronic conditions from the student's health record.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medical_alert: Option<String>,
+}
+
+/// Outcome of raising a period's transport fees as student dues.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransportFeeRun {
+    pub raised: u32,
+    pub total: f64,
+    /// Students already charged for the period.
+    pub already_raised: u32,
+    /// Students whose stop no fare slab covers.
+    pub without_fare: Vec<String>,
+}
+
+/// A fitness certificate or insurance policy expired or expiring soon.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VehicleAlert {
+    pub vehicle_id: i64,
+    pub registration_number: String,
+    /// fitness or insurance.
+    pub document: String,
+    pub expires_on: String,
+    pub expired: bool,
+}
+
+pub fn init_transport_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transport_vehicles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            registration_number TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            capacity INTEGER NOT NULL,
+            vehicle_type TEXT,
+            fitness_expiry TEXT,
+            insurance_expiry TEXT,
+            driver_id INTEGER,
+            status TEXT NOT NULL DEFAULT 'active',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (driver_id) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transport_routes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            vehicle_id INTEGER,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (vehicle_id) REFERENCES transport_vehicles(id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transport_stops (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            route_id INTEGER NOT NULL,
+            stop_order INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            pickup_time TEXT NOT NULL,
+            drop_time TEXT,
+            distance_km REAL NOT NULL,
+            FOREIGN KEY (route_id) REFERENCES transport_routes(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transport_fare_slabs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            from_km REAL NOT NULL,
+            to_km REAL NOT NULL,
+            amount REAL NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS student_transport (
+            student_id INTEGER PRIMARY KEY,
+            stop_id INTEGER NOT NULL,
+            allocated_on TEXT NOT NULL,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            FOREIGN KEY (stop_id) REFERENCES transport_stops(id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_vehicles(state: State<'_, DbState>) -> Result<Vec<Vehicle>, String> {
+    state.read(service::get_vehicles).await
+}
+
+/// Adds a vehicle, or updates it when `vehicle.id` is set.
+#[tauri::command]
+pub async fn save_vehicle(state: State<'_, DbState>, vehicle: Vehicle) -> Result<i64, String> {
+    state
+        .write(move |conn| service::save_vehicle(conn, vehicle))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_vehicle(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_vehicle(conn, id))
+        .await
+}
+
+/// Fitness certificates and insurance expired or expiring within `within_days` (default 30).
+#[tauri::command]
+pub async fn get_vehicle_alerts(
+    state: State<'_, DbState>,
+    within_days: Option<u32>,
+) -> Result<Vec<VehicleAlert>, String> {
+    state
+        .read(move |conn| service::get_vehicle_alerts(conn, None, within_days))
+        .await
+}
+
+/// Routes with their stops and the number of students on each.
+#[tauri::command]
+pub async fn get_transport_routes(
+    state: State<'_, DbState>,
+) -> Result<Vec<TransportRoute>, String> {
+    state.read(service::get_routes).await
+}
+
+/// Saves a route and its stops in the order given.
+#[tauri::command]
+pub async fn save_transport_route(
+    state: State<'_, DbState>,
+    route: TransportRoute,
+) -> Result<TransportRoute, String> {
+    state
+        .write(move |conn| service::save_route(conn, route))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_transport_route(state: State<'_, DbState>, id: i64) -> Result<(), String> {
+    state
+        .write(move |conn| service::delete_route(conn, id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_fare_slabs(state: State<'_, DbState>) -> Result<Vec<FareSlab>, String> {
+    state.read(service::get_fare_slabs).await
+}
+
+/// Replaces the fare slabs.
+#[tauri::command]
+pub async fn save_fare_slabs(
+    state: State<'_, DbState>,
+    slabs: Vec<FareSlab>,
+) -> Result<Vec<FareSlab>, String> {
+    state
+        .write(move |conn| service::save_fare_slabs(conn, slabs))
+        .await
+}
+
+/// Assigns a student to a stop, replacing any earlier stop.
+#[tauri::command]
+pub async fn allocate_student_transport(
+    state: State<'_, DbState>,
+    allocation: TransportAllocation,
+) -> Result<TransportAllocation, String> {
+    state
+        .write(move |conn| service::allocate_student(conn, allocation))
+        .await
+}
+
+#[tauri::command]
+pub async fn remove_student_transport(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::remove_allocation(conn, student_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_student_transport(
+    state: State<'_, DbState>,
+    student_id: i64,
+) -> Result<Option<TransportAllocation>, String> {
+    state
+        .read(move |conn| service::get_student_allocation(conn, student_id))
+        .await
+}
+
+/// Students on the roll using the route, by stop order then name.
+#[tauri::command]
+pub async fn get_route_students(
+    state: State<'_, DbState>,
+    route_id: i64,
+) -> Result<Vec<TransportAllocation>, String> {
+    state
+        .read(move |conn| service::get_route_students(conn, route_id))
+        .await
+}
+
+/// Writes the student list of one route, or of every route a page each, and returns its path.
+#[tauri::command]
+pub async fn export_route_list_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    route_id: Option<i64>,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_route_list_pdf(conn, route_id))
+        .await?;
+    let path = ensure_transport_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Raises each allocated student's fare for `period` (e.g. "2024-07") as a due.
+#[tauri::command]
+pub async fn raise_transport_fees(
+    state: State<'_, DbState>,
+    period: String,
+    raised_on: Option<String>,
+) -> Result<TransportFeeRun, String> {
+    state
+        .write(move |conn| service::raise_fees(conn, &period, raised_on))
+        .await
+}
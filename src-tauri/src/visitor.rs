@@ -0,0 +1,198 @@
+// Gate: visitor log and student early-leave gate passes
+use crate::service::visitor as service;
+use crate::DbState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+fn ensure_gate_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?
+        .join("Gate Register");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create gate register dir: {}", e))?;
+    Ok(dir)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Visitor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub visitor_name: String,
+    pub phone: Option<String>,
+    pub purpose: String,
+    /// Whom the visitor came to see.
+    pub person_to_meet: String,
+    /// Staff id, when the person to meet is on the staff.
+    pub staff_id: Option<i64>,
+    /// Defaults to today.
+    pub visited_on: Option<String>,
+    /// HH:MM; defaults to now.
+    pub in_time: Option<String>,
+    pub out_time: Option<String>,
+    /// File name returned by `save_image`.
+    pub photo: Option<String>,
+    pub remarks: Option<String>,
+}
+
+/// A student let out before the end of the day, on a guardian's authority.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatePass {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub student_id: i64,
+    /// A guardian linked to the student, who authorised the pass.
+    pub guardian_id: i64,
+    /// Defaults to today.
+    pub issued_on: Option<String>,
+    /// HH:MM; defaults to now.
+    pub out_time: Option<String>,
+    pub reason: String,
+    /// Who took the student out; defaults to the guardian.
+    pub collected_by: Option<String>,
+    /// Staff member who let the student go.
+    pub approved_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub student_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardian_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardian_relation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardian_phone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyGateRegister {
+    pub date: String,
+    pub visitors: Vec<Visitor>,
+    pub gate_passes: Vec<GatePass>,
+    /// Visitors with no out time yet.
+    pub still_inside: u32,
+}
+
+pub fn init_visitor_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS visitors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            visitor_name TEXT NOT NULL,
+            phone TEXT,
+            purpose TEXT NOT NULL,
+            person_to_meet TEXT NOT NULL,
+            staff_id INTEGER,
+            visited_on TEXT NOT NULL,
+            in_time TEXT NOT NULL,
+            out_time TEXT,
+            photo TEXT,
+            remarks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (staff_id) REFERENCES staff(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_visitors_visited_on ON visitors(visited_on)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS gate_passes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id INTEGER NOT NULL,
+            guardian_id INTEGER NOT NULL,
+            issued_on TEXT NOT NULL,
+            out_time TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            collected_by TEXT NOT NULL,
+            approved_by TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (student_id) REFERENCES students(id) ON DELETE CASCADE,
+            FOREIGN KEY (guardian_id) REFERENCES guardians(id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn record_visitor(state: State<'_, DbState>, visitor: Visitor) -> Result<i64, String> {
+    state
+        .write(move |conn| service::record_visitor(conn, visitor))
+        .await
+}
+
+#[tauri::command]
+pub async fn record_visitor_exit(
+    state: State<'_, DbState>,
+    id: i64,
+    out_time: Option<String>,
+) -> Result<(), String> {
+    state
+        .write(move |conn| service::record_exit(conn, id, out_time))
+        .await
+}
+
+/// Past visits whose visitor, phone, purpose or person to meet matches `query`.
+#[tauri::command]
+pub async fn search_visitors(
+    state: State<'_, DbState>,
+    query: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<Visitor>, String> {
+    state
+        .read(move |conn| service::search_visitors(conn, query, from, to))
+        .await
+}
+
+#[tauri::command]
+pub async fn issue_gate_pass(
+    state: State<'_, DbState>,
+    pass: GatePass,
+) -> Result<GatePass, String> {
+    state
+        .write(move |conn| service::issue_gate_pass(conn, pass))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_gate_passes(
+    state: State<'_, DbState>,
+    student_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<GatePass>, String> {
+    state
+        .read(move |conn| service::get_gate_passes(conn, student_id, from, to))
+        .await
+}
+
+/// Visitors and gate passes of one day, today by default.
+#[tauri::command]
+pub async fn get_daily_gate_register(
+    state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<DailyGateRegister, String> {
+    state
+        .read(move |conn| service::get_daily_register(conn, date))
+        .await
+}
+
+/// Writes the day's gate register and returns its path.
+#[tauri::command]
+pub async fn export_daily_gate_register_pdf(
+    state: State<'_, DbState>,
+    app_handle: AppHandle,
+    date: Option<String>,
+) -> Result<String, String> {
+    let (file_name, pdf) = state
+        .read(move |conn| service::render_daily_register_pdf(conn, date))
+        .await?;
+    let path = ensure_gate_dir(&app_handle)?.join(&file_name);
+    fs::write(&path, pdf).map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
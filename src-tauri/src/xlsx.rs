@@ -0,0 +1,240 @@
+// Minimal XLSX writer for tabular exports: a single sheet of text and numbers with a bold
+// header row. Parts are stored uncompressed in the zip container, so no compression
+// library is needed.
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+pub struct Workbook {
+    sheet_name: String,
+    header: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+/// CRC-32 (IEEE) as used by zip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Escapes XML text and drops control characters XML 1.0 does not allow.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Spreadsheet column letters for a zero-based index: A, B, ... Z, AA, AB, ...
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Style 0 is the default font, style 1 the bold one used for the header row.
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs></styleSheet>"#;
+
+impl Workbook {
+    /// `sheet_name` is cut to the 31 characters Excel allows, without the characters it
+    /// refuses in sheet names.
+    pub fn new(sheet_name: &str, header: Vec<String>) -> Self {
+        let sheet_name: String = sheet_name
+            .chars()
+            .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+            .take(31)
+            .collect();
+        Workbook {
+            sheet_name: if sheet_name.trim().is_empty() {
+                "Sheet1".to_string()
+            } else {
+                sheet_name
+            },
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    fn workbook_xml(&self) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            escape(&self.sheet_name)
+        )
+    }
+
+    fn sheet_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        let header = self.header.iter().map(|h| Cell::Text(h.clone())).collect();
+        for (index, (cells, style)) in std::iter::once((&header, 1))
+            .chain(self.rows.iter().map(|row| (row, 0)))
+            .enumerate()
+        {
+            let row = index + 1;
+            let _ = write!(xml, r#"<row r="{}">"#, row);
+            for (column, cell) in cells.iter().enumerate() {
+                let reference = format!("{}{}", column_name(column), row);
+                let _ = match cell {
+                    Cell::Text(text) => write!(
+                        xml,
+                        r#"<c r="{}" s="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                        reference,
+                        style,
+                        escape(text)
+                    ),
+                    Cell::Number(number) if number.is_finite() => write!(
+                        xml,
+                        r#"<c r="{}" s="{}"><v>{}</v></c>"#,
+                        reference, style, number
+                    ),
+                    _ => Ok(()),
+                };
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData></worksheet>");
+        xml
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let parts = [
+            ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+            ("_rels/.rels", ROOT_RELS.to_string()),
+            ("xl/workbook.xml", self.workbook_xml()),
+            ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.to_string()),
+            ("xl/styles.xml", STYLES.to_string()),
+            ("xl/worksheets/sheet1.xml", self.sheet_xml()),
+        ];
+
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in &parts {
+            let data = content.as_bytes();
+            let crc = crc32(data);
+            let offset = out.len() as u32;
+            // Fields shared by the local and central headers: version 2.0, no flags,
+            // stored, dated 1980-01-01 00:00.
+            let header = |signature: u32, central: bool| {
+                let mut h = Vec::new();
+                h.extend_from_slice(&signature.to_le_bytes());
+                if central {
+                    h.extend_from_slice(&20u16.to_le_bytes());
+                }
+                h.extend_from_slice(&20u16.to_le_bytes());
+                h.extend_from_slice(&0u16.to_le_bytes());
+                h.extend_from_slice(&0u16.to_le_bytes());
+                h.extend_from_slice(&0u16.to_le_bytes());
+                h.extend_from_slice(&0x21u16.to_le_bytes());
+                h.extend_from_slice(&crc.to_le_bytes());
+                h.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                h.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                h.extend_from_slice(&0u16.to_le_bytes());
+                h
+            };
+            out.extend(header(0x0403_4b50, false));
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            directory.extend(header(0x0201_4b50, true));
+            // Comment length, disk number, internal and external attributes, offset.
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&0u32.to_le_bytes());
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let directory_offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&(parts.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(parts.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_and_column_names() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+
+    #[test]
+    fn writes_a_zip_with_every_part() {
+        let mut workbook = Workbook::new("Girls: 6/8", vec!["Name".into(), "Age".into()]);
+        workbook.add_row(vec![Cell::Text("A & B <C>".into()), Cell::Number(12.0)]);
+        workbook.add_row(vec![Cell::Empty, Cell::Number(f64::NAN)]);
+        let bytes = workbook.to_bytes();
+
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        let end = &bytes[bytes.len() - 22..];
+        assert_eq!(&end[..4], &0x0605_4b50u32.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 6);
+
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains(r#"<sheet name="Girls 68""#));
+        assert!(text.contains("A &amp; B &lt;C&gt;"));
+        assert!(text.contains(r#"<c r="B2" s="0"><v>12</v></c>"#));
+        assert!(text.contains(r#"<row r="3"></row>"#));
+    }
+}
@@ -187,7 +187,7 @@ const REQUIRED_FIELDS: Record<number, (keyof Student)[]> = {
   3: []
 };
 
-const STATUS_OPTIONS = ['active', 'inactive', 'alumni'];
+const STATUS_OPTIONS = ['applicant', 'active', 'suspended', 'transferred', 'left', 'graduated', 'alumni'];
 const GENDER_OPTIONS = ['male', 'female', 'other'];
 const BLOOD_GROUPS = ['A+', 'A-', 'B+', 'B-', 'AB+', 'AB-', 'O+', 'O-', 'Unknown'];
 